@@ -0,0 +1,42 @@
+#!/usr/bin/env bash
+# Smoke test for program arguments (`--`) and stdin passthrough
+# Usage: ./scripts/args-smoke.sh
+set -euo pipefail
+
+ROOT_DIR="$(cd "$(dirname "$0")/.." && pwd)"
+BIN="$ROOT_DIR/franz"
+OUT_DIR="$(mktemp -d)"
+trap 'rm -rf "$OUT_DIR"' EXIT
+
+cd "$ROOT_DIR"
+
+SOURCE=test/arguments/arguments-test.franz
+
+check() {
+  local label="$1"
+  local output="$2"
+  for expected in "count: 2" "argument 0: one" "argument 1: two words" "stdin: piped line" "Arguments test PASSED"; do
+    if ! grep -qF "$expected" <<< "$output"; then
+      echo "$label: missing '$expected'" >&2
+      echo "$output" >&2
+      exit 1
+    fi
+  done
+}
+
+echo "--- AOT" >&2
+check "aot" "$(echo "piped line" | "$BIN" "$SOURCE" -- one "two words" 2>/dev/null)"
+
+echo "--- JIT" >&2
+check "jit" "$(echo "piped line" | "$BIN" run "$SOURCE" -- one "two words" 2>/dev/null)"
+
+echo "--- Built executable" >&2
+"$BIN" build "$SOURCE" -o "$OUT_DIR/args" >/dev/null 2>&1
+check "build" "$(echo "piped line" | "$OUT_DIR/args" one "two words" 2>/dev/null)"
+
+# Arguments that look like franz flags are passed through untouched after `--`
+output=$(echo "" | "$BIN" "$SOURCE" -- -d --emit=ir 2>/dev/null)
+grep -qF "argument 0: -d" <<< "$output"
+grep -qF "argument 1: --emit=ir" <<< "$output"
+
+echo "All argument smoke tests passed." >&2
@@ -0,0 +1,64 @@
+#!/usr/bin/env bash
+# Smoke test for per-invocation build directories and the runtime cache
+# Usage: ./scripts/build-dir-smoke.sh
+# Runs several programs concurrently against an empty cache: each must print its
+# own output, the runtime must be built once, and no work directories may be left.
+set -euo pipefail
+
+ROOT_DIR="$(cd "$(dirname "$0")/.." && pwd)"
+BIN="$ROOT_DIR/franz"
+SCRATCH="$(mktemp -d)"
+trap 'rm -rf "$SCRATCH"' EXIT
+
+export FRANZ_CACHE_DIR="$SCRATCH/cache"
+export TMPDIR="$SCRATCH/tmp"
+mkdir -p "$TMPDIR"
+
+cd "$ROOT_DIR"
+
+pids=()
+for i in 1 2 3 4 5 6; do
+  if [ $((i % 2)) -eq 0 ]; then
+    file=test/build/build-test.franz
+  else
+    file=test/optimization/opt-test.franz
+  fi
+  "$BIN" "$file" > "$SCRATCH/out$i.txt" 2>/dev/null &
+  pids+=($!)
+done
+for pid in "${pids[@]}"; do wait "$pid"; done
+
+for i in 1 2 3 4 5 6; do
+  if [ $((i % 2)) -eq 0 ]; then
+    expected="Build test PASSED"
+    unexpected="Optimization test PASSED"
+  else
+    expected="Optimization test PASSED"
+    unexpected="Build test PASSED"
+  fi
+  grep -q "$expected" "$SCRATCH/out$i.txt" || { echo "run $i: missing '$expected'" >&2; exit 1; }
+  if grep -q "$unexpected" "$SCRATCH/out$i.txt"; then
+    echo "run $i executed another program's binary" >&2
+    exit 1
+  fi
+done
+
+if [ -n "$(ls -A "$TMPDIR")" ]; then
+  echo "work directories were not cleaned up: $(ls "$TMPDIR")" >&2
+  exit 1
+fi
+
+runtime_dirs=$(ls -d "$FRANZ_CACHE_DIR"/runtime-* | wc -l)
+[ "$runtime_dirs" -eq 1 ] || { echo "expected one runtime directory, found $runtime_dirs" >&2; exit 1; }
+
+# A second run reuses the cached runtime without rebuilding it
+touch "$SCRATCH/marker"
+sleep 1
+output=$("$BIN" test/build/build-test.franz 2>/dev/null)
+echo "$output" | grep -q "Build test PASSED"
+if [ -n "$(find "$FRANZ_CACHE_DIR" -name '*.a' -newer "$SCRATCH/marker")" ]; then
+  echo "runtime library was rebuilt although its sources did not change" >&2
+  exit 1
+fi
+
+echo "All build directory smoke tests passed." >&2
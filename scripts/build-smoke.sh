@@ -0,0 +1,28 @@
+#!/usr/bin/env bash
+# Smoke test for ahead-of-time builds (franz build)
+# Usage: ./scripts/build-smoke.sh
+set -euo pipefail
+
+ROOT_DIR="$(cd "$(dirname "$0")/.." && pwd)"
+BIN="$ROOT_DIR/franz"
+OUT_DIR="$(mktemp -d)"
+trap 'rm -rf "$OUT_DIR"' EXIT
+
+cd "$ROOT_DIR"
+
+# Build must write the executable and must not run it
+build_output=$("$BIN" build test/build/build-test.franz -o "$OUT_DIR/build-test")
+if echo "$build_output" | grep -q "Build test PASSED"; then
+  echo "franz build executed the program" >&2
+  exit 1
+fi
+
+if [ ! -x "$OUT_DIR/build-test" ]; then
+  echo "franz build did not produce $OUT_DIR/build-test" >&2
+  exit 1
+fi
+
+# The executable must run on its own, from any working directory
+(cd "$OUT_DIR" && ./build-test) | grep -q "Build test PASSED"
+
+echo "All build smoke tests passed." >&2
@@ -0,0 +1,184 @@
+#!/usr/bin/env bash
+# Smoke test for the bytecode compiler and VM (--bytecode, --emit=franzc)
+# Usage: ./scripts/bytecode-smoke.sh
+# Covers output parity with the interpreter, closures, deep recursion,
+# modules, runtime errors, .franzc round trips without a toolchain,
+# malformed .franzc files, rejected options and the repository's tests on
+# all backends.
+set -euo pipefail
+
+ROOT_DIR="$(cd "$(dirname "$0")/.." && pwd)"
+BIN="$ROOT_DIR/franz"
+
+WORK_DIR=$(mktemp -d)
+trap 'rm -rf "$WORK_DIR"' EXIT
+export FRANZ_CACHE_DIR="$WORK_DIR/cache"
+
+expect() {
+  local output="$1" expected="$2"
+  if ! grep -qxF -- "$expected" <<< "$output"; then
+    echo "Expected line '$expected' in output:" >&2
+    echo "$output" >&2
+    exit 1
+  fi
+}
+
+cd "$WORK_DIR"
+cat > app.franz <<'EOF'
+counter = {->
+  base = 10
+  add_base = {step -> <- (add base step)}
+  <- (add_base 5)
+}
+(println "counter " (counter))
+
+mut total = 0
+(loop 5 {i ->
+  (if (is i 3) {(continue)} {})
+  total = (add total i)
+})
+(println "total " total)
+
+found = (loop 10 {i ->
+  (when (greater_than (multiply i i) 20) {(break i)})
+})
+(println "found " found)
+
+mut n = 0
+(while (less_than n 3) {n = (add n 1)})
+(println "n " n)
+(println (and 1 0) (or 0 1) [1, "two", 3.5])
+
+nest = {a -> <- {b -> <- {c -> <- (add a (add b c))}}}
+(println "nest " (((nest 1) 2) 3))
+
+grade = {score ->
+  <- (cond
+    ((greater_than score 89) "A")
+    ((greater_than score 69) "B")
+    (else "C"))
+}
+(println (grade 95) (grade 75) (grade 10))
+(println (map [1, 2, 3] {v -> <- (multiply v 2)}))
+(println (reduce [1, 2, 3] {acc v -> <- (add acc v)} 0))
+EOF
+
+echo "--- Same output as the interpreter" >&2
+interpreted=$("$BIN" --interpret app.franz)
+output=$("$BIN" --bytecode app.franz)
+if [ "$interpreted" != "$output" ]; then
+  echo "VM output differs from interpreter output:" >&2
+  diff <(echo "$interpreted") <(echo "$output") >&2 || true
+  exit 1
+fi
+expect "$output" "counter 15"
+expect "$output" "total 7"
+expect "$output" "found 5"
+expect "$output" "n 3"
+expect "$output" "01[1, two, 3.500000]"
+expect "$output" "nest 6"
+expect "$output" "ABC"
+expect "$output" "[2, 4, 6]"
+
+echo "--- Deep recursion" >&2
+printf 'down = {n -> <- (if (is n 0) {<- 0} {<- (down (subtract n 1))})}\n(println (down 50000))\n' > deep.franz
+expect "$("$BIN" --bytecode deep.franz)" "0"
+
+echo "--- Modules" >&2
+printf 'double = {x -> <- (multiply x 2)}\nquad = {x -> <- (double (double x))}\n' > lib.franz
+printf 'lib = (use_as "lib.franz")\n(println (lib.double 21))\n(println (lib.quad 2))\n' > main.franz
+output=$("$BIN" --bytecode main.franz)
+expect "$output" "42"
+expect "$output" "8"
+
+echo "--- Runtime errors" >&2
+printf '(println "before")\n(println missing)\n' > undefined.franz
+status=0
+output=$("$BIN" --bytecode undefined.franz) || status=$?
+expect "$output" "before"
+expect "$output" "Runtime Error @ Line 2: Undefined variable 'missing'. [F0201]"
+if [ "$status" -ne 1 ]; then
+  echo "Expected exit status 1 after a runtime error, got $status" >&2
+  exit 1
+fi
+printf '(break)\n' > outside.franz
+output=$("$BIN" --bytecode outside.franz || true)
+expect "$output" "Runtime Error @ Line 1: 'break' used outside of a loop. [F0205]"
+printf 'forever = {n -> <- (add 1 (forever n))}\n(forever 1)\n' > overflow.franz
+output=$("$BIN" --bytecode overflow.franz || true)
+expect "$output" "Runtime Error @ Line 1: Call stack overflow in 'forever' (too many nested calls). [F0400]"
+printf 'f = {x -> <- x}\n(f 1 2)\n' > arity.franz
+status=0
+output=$("$BIN" --bytecode arity.franz) || status=$?
+expect "$output" "Runtime Error @ Line 2: Supplied more arguments than required to function."
+[ "$status" -eq 1 ] || { echo "Expected exit status 1 after an arity error, got $status" >&2; exit 1; }
+
+echo "--- Precompiled programs" >&2
+"$BIN" --emit=franzc app.franz
+"$BIN" --emit=franzc app.franz -o copy.franzc
+cmp app.franzc copy.franzc
+# Neither llc nor a C compiler can be found, and nothing is cached
+rm -rf "$FRANZ_CACHE_DIR"
+precompiled=$(PATH=/nonexistent "$BIN" app.franzc)
+if [ "$precompiled" != "$output" ] && [ "$precompiled" != "$interpreted" ]; then
+  echo "Precompiled output differs:" >&2
+  diff <(echo "$interpreted") <(echo "$precompiled") >&2 || true
+  exit 1
+fi
+if [ -e "$FRANZ_CACHE_DIR" ] && [ -n "$(ls -A "$FRANZ_CACHE_DIR")" ]; then
+  echo "Bytecode programs should not write the build cache" >&2
+  exit 1
+fi
+printf '(println "args " (get arguments 0))\n' > args.franz
+"$BIN" --emit=franzc args.franz
+expect "$("$BIN" args.franzc hello)" "args hello"
+output=$("$BIN" -d app.franzc)
+expect "$output" "== <toplevel>: 0 params, 0 slots, 0 captures, stack 7 =="
+
+echo "--- Malformed .franzc files" >&2
+head -c 40 app.franzc > truncated.franzc
+output=$("$BIN" truncated.franzc 2>&1 || true)
+expect "$output" "Error: 'truncated.franzc' is not a valid .franzc file."
+cp app.franzc version.franzc
+printf '\x09' | dd of=version.franzc bs=1 seek=8 conv=notrunc 2> /dev/null
+output=$("$BIN" version.franzc 2>&1 || true)
+expect "$output" "Error: 'version.franzc' was compiled for .franzc version 9; this franz reads version 2. Recompile it with --emit=franzc."
+# Flip bytes all over the file: every load is rejected or runs (a changed
+# jump may loop forever), but never crashes
+size=$(wc -c < app.franzc)
+for offset in $(seq 12 7 "$size"); do
+  cp app.franzc corrupt.franzc
+  printf '\xff' | dd of=corrupt.franzc bs=1 seek="$offset" conv=notrunc 2> /dev/null
+  status=0
+  timeout 5 "$BIN" corrupt.franzc > /dev/null 2>&1 || status=$?
+  if [ "$status" -gt 1 ] && [ "$status" -ne 124 ]; then
+    echo "Corrupt .franzc (byte $offset) crashed with exit code $status" >&2
+    exit 1
+  fi
+done
+
+echo "--- Rejected options" >&2
+output=$("$BIN" --bytecode --jit app.franz 2>&1 || true)
+expect "$output" "Error: '--bytecode' cannot be combined with 'franz build', 'franz run', 'franz repl', '--emit' or '--jit'."
+output=$("$BIN" --bytecode --interpret app.franz 2>&1 || true)
+expect "$output" "Error: '--bytecode' cannot be combined with '--interpret'."
+output=$("$BIN" --bytecode --profile app.franz 2>&1 || true)
+expect "$output" "Error: Options '-g', '--coverage', '--trace' and '--profile' are not supported by '--bytecode'."
+output=$("$BIN" --emit=franzc -g app.franz 2>&1 || true)
+expect "$output" "Error: Options '-g', '--coverage', '--trace', '--profile', '--target' and '--sysroot' are not supported by '--emit=franzc'."
+output=$("$BIN" --emit=ir app.franzc 2>&1 || true)
+expect "$output" "Error: 'app.franzc' is a precompiled .franzc program; it can only be run (franz app.franzc)."
+
+echo "--- Repository tests on all backends" >&2
+cd "$ROOT_DIR"
+output=$("$BIN" test --backend=all test/ 2>/dev/null)
+expect "$output" "PASS test/loop/loop-simple.franz [bytecode]"
+expect "$output" "PASS test/llvm-control-flow/break-test.franz [bytecode]"
+expect "$output" "PASS test/llvm-filter/simple-filter-test.franz [bytecode]"
+expect "$output" "PASS test/llvm-type-guards/type-guards-comprehensive-test.franz [bytecode]"
+if grep -q "^FAIL" <<< "$output"; then
+  echo "$output" >&2
+  exit 1
+fi
+
+echo "All bytecode smoke tests passed."
@@ -0,0 +1,118 @@
+#!/usr/bin/env bash
+# Smoke test for the compilation cache
+# Usage: ./scripts/cache-smoke.sh
+# A repeated run must reuse the cached executable (no code generation), and
+# changing an imported module, the flags, the runtime sources or the source
+# must recompile. Programs with compile errors are never cached, and
+# programs/ is trimmed to FRANZ_CACHE_MAX_SIZE.
+set -euo pipefail
+
+ROOT_DIR="$(cd "$(dirname "$0")/.." && pwd)"
+BIN="$ROOT_DIR/franz"
+WORK="$(mktemp -d)"
+trap 'rm -rf "$WORK"' EXIT
+
+export FRANZ_CACHE_DIR="$WORK/cache"
+cd "$WORK"
+
+mkdir lib
+echo 'offset = {x -> <- (add x 1)}' > lib/inner.franz
+cat > lib/math.franz <<'FRANZ'
+(use "lib/inner.franz")
+scale = {x -> <- (multiply x 10)}
+FRANZ
+cat > app.franz <<'FRANZ'
+(use "lib/math.franz")
+scaled = (scale 4)
+(println "scaled: " scaled)
+shifted = (offset 4)
+(println "shifted: " shifted)
+FRANZ
+
+entries() { ls "$FRANZ_CACHE_DIR/programs" 2>/dev/null | wc -l | tr -d ' '; }
+
+expect() {
+  local label="$1" expected="$2" output="$3"
+  if ! grep -qF "$expected" <<< "$output"; then
+    echo "$label: expected '$expected'" >&2
+    echo "$output" >&2
+    exit 1
+  fi
+}
+
+echo "--- Cold run" >&2
+output=$("$BIN" app.franz 2>/dev/null)
+expect "cold" "shifted: 5" "$output"
+expect "cold" "LLVM IR generation complete" "$output"
+[ "$(entries)" -eq 1 ] || { echo "expected one cache entry" >&2; exit 1; }
+
+echo "--- Cached run" >&2
+output=$("$BIN" app.franz 2>/dev/null)
+expect "cached" "shifted: 5" "$output"
+if grep -q "LLVM IR generation complete" <<< "$output"; then
+  echo "cached run went through code generation" >&2
+  exit 1
+fi
+[ "$(entries)" -eq 1 ] || { echo "cached run added an entry" >&2; exit 1; }
+
+echo "--- Transitive import changed" >&2
+echo 'offset = {x -> <- (add x 2)}' > lib/inner.franz
+output=$("$BIN" app.franz 2>/dev/null)
+expect "import" "shifted: 6" "$output"
+[ "$(entries)" -eq 2 ] || { echo "import change did not produce a new entry" >&2; exit 1; }
+
+echo "--- Flags changed" >&2
+"$BIN" -O2 app.franz >/dev/null 2>&1
+[ "$(entries)" -eq 3 ] || { echo "-O2 reused the -O0 entry" >&2; exit 1; }
+
+echo "--- Runtime source changed" >&2
+mkdir home
+cp -R "$ROOT_DIR/Makefile.runtime" "$ROOT_DIR/src" home/
+FRANZ_HOME="$WORK/home" "$BIN" app.franz >/dev/null 2>&1
+[ "$(entries)" -eq 4 ] || { echo "runtime directory change did not produce a new entry" >&2; exit 1; }
+# The sources are hashed once per franz executable: an edit is picked up
+# when franz is rebuilt, which drops the remembered hash like this
+echo '/* edited */' >> home/src/stdlib.c
+output=$(FRANZ_HOME="$WORK/home" "$BIN" app.franz 2>/dev/null)
+if grep -q "LLVM IR generation complete" <<< "$output"; then
+  echo "runtime sources were hashed again by the same franz" >&2
+  exit 1
+fi
+rm -r "$FRANZ_CACHE_DIR/sources"
+output=$(FRANZ_HOME="$WORK/home" "$BIN" app.franz 2>/dev/null)
+expect "runtime" "LLVM IR generation complete" "$output"
+[ "$(entries)" -eq 5 ] || { echo "runtime source change reused a stale entry" >&2; exit 1; }
+
+echo "--- --no-cache" >&2
+echo '(println "uncached")' > other.franz
+output=$("$BIN" --no-cache other.franz 2>/dev/null)
+expect "no-cache" "uncached" "$output"
+[ "$(entries)" -eq 5 ] || { echo "--no-cache wrote a cache entry" >&2; exit 1; }
+
+echo "--- Program with compile errors" >&2
+echo '(println (no_such_function 1))' > broken.franz
+for run in first second; do
+  output=$("$BIN" broken.franz 2>&1 || true)
+  expect "broken ($run run)" "Unknown function 'no_such_function'" "$output"
+done
+output=$("$BIN" --message-format=json broken.franz 2>&1 || true)
+expect "broken (json)" '"code":"F0202"' "$output"
+[ "$(entries)" -eq 5 ] || { echo "a program with compile errors was cached" >&2; exit 1; }
+
+echo "--- Size limit" >&2
+echo '(println "newest")' > newest.franz
+output=$(FRANZ_CACHE_MAX_SIZE=0 "$BIN" newest.franz 2>/dev/null)
+expect "limit" "newest" "$output"
+[ "$(entries)" -eq 1 ] || { echo "FRANZ_CACHE_MAX_SIZE=0 kept $(entries) entries" >&2; exit 1; }
+output=$("$BIN" newest.franz 2>/dev/null)
+if grep -q "LLVM IR generation complete" <<< "$output"; then
+  echo "the program just published was evicted" >&2
+  exit 1
+fi
+
+if ls "$FRANZ_CACHE_DIR/programs" | grep -q "\.tmp\."; then
+  echo "temporary cache files were left behind" >&2
+  exit 1
+fi
+
+echo "All cache smoke tests passed." >&2
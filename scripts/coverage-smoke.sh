@@ -0,0 +1,123 @@
+#!/usr/bin/env bash
+# Smoke test for line and branch coverage (--coverage, franz coverage report)
+# Usage: ./scripts/coverage-smoke.sh
+# Covers native, JIT and built programs, counts adding up across runs,
+# runtime errors, edited programs, the listing and the lcov report.
+set -euo pipefail
+
+ROOT_DIR="$(cd "$(dirname "$0")/.." && pwd)"
+BIN="$ROOT_DIR/franz"
+
+WORK_DIR=$(mktemp -d)
+trap 'rm -rf "$WORK_DIR"' EXIT
+export FRANZ_CACHE_DIR="$WORK_DIR/cache"
+unset FRANZ_COVERAGE_FILE
+
+expect() {
+  local output="$1" expected="$2"
+  if ! grep -qxF -- "$expected" <<< "$output"; then
+    echo "Expected line '$expected' in output:" >&2
+    echo "$output" >&2
+    exit 1
+  fi
+}
+
+cd "$WORK_DIR"
+cat > app.franz <<'EOF'
+// app.franz
+double = {n ->
+  <- (multiply n 2)
+}
+unused = {n ->
+  <- (add n 1)
+}
+
+(println (double 21))
+(loop 3 {i ->
+  (if (is i 1)
+    {(println "one")}
+    {(println "not one")})
+})
+(when (is 1 2) {
+  (println "never")
+})
+EOF
+SOURCE="$WORK_DIR/app.franz"
+
+echo "--- Native run" >&2
+output=$("$BIN" --coverage app.franz)
+expect "$output" "42"
+report=$("$BIN" coverage report)
+expect "$report" "File '$SOURCE'"
+expect "$report" "Lines executed: 81.82% of 11"
+expect "$report" "Branches taken: 75.00% of 4"
+expect "$report" "        -:    1:// app.franz"
+expect "$report" "        1:    3:  <- (multiply n 2)"
+expect "$report" "    #####:    6:  <- (add n 1)"
+expect "$report" "        3:   11:  (if (is i 1)"
+expect "$report" "branch  0 taken 1"
+expect "$report" "branch  1 taken 2"
+expect "$report" "        2:   13:    {(println \"not one\")})"
+expect "$report" "branch  0 taken 0"
+expect "$report" "    #####:   16:  (println \"never\")"
+
+echo "--- Counts add up across native, JIT and built runs" >&2
+"$BIN" run --coverage app.franz > /dev/null
+"$BIN" build --coverage app.franz -o app > /dev/null
+./app > /dev/null
+report=$("$BIN" coverage report --lcov)
+expect "$report" "SF:$SOURCE"
+expect "$report" "DA:11,9"
+expect "$report" "DA:6,0"
+expect "$report" "BRDA:11,0,1,6"
+expect "$report" "BRDA:15,1,0,0"
+expect "$report" "LF:11"
+expect "$report" "LH:9"
+expect "$report" "BRF:4"
+expect "$report" "BRH:3"
+expect "$report" "end_of_record"
+
+echo "--- Runtime errors still write counts" >&2
+printf '(println "before")\n(write_file "missing-dir/out.txt" "x")\n(println "after")\n' > error.franz
+export FRANZ_COVERAGE_FILE="$WORK_DIR/error.coverage"
+status=0
+"$BIN" --coverage error.franz > /dev/null || status=$?
+[ "$status" -eq 1 ] || { echo "expected exit status 1 after a runtime error, got $status" >&2; exit 1; }
+"$BIN" coverage report -o error.txt
+expect "$(cat error.txt)" "        1:    2:(write_file \"missing-dir/out.txt\" \"x\")"
+expect "$(cat error.txt)" "    #####:    3:(println \"after\")"
+
+echo "--- An edited program starts over" >&2
+printf '(println "first")\n' > edit.franz
+export FRANZ_COVERAGE_FILE="$WORK_DIR/edit.coverage"
+"$BIN" --coverage edit.franz > /dev/null
+"$BIN" --coverage edit.franz > /dev/null
+expect "$("$BIN" coverage report)" "        2:    1:(println \"first\")"
+printf '(println "first")\n(println "second")\n' > edit.franz
+"$BIN" --coverage edit.franz > /dev/null
+report=$("$BIN" coverage report)
+expect "$report" "        1:    1:(println \"first\")"
+expect "$report" "        1:    2:(println \"second\")"
+unset FRANZ_COVERAGE_FILE
+
+echo "--- Programs without --coverage write nothing" >&2
+rm -f franz.coverage
+"$BIN" app.franz > /dev/null
+if [ -e franz.coverage ]; then
+  echo "Uninstrumented program wrote coverage data" >&2
+  exit 1
+fi
+
+echo "--- Errors" >&2
+output=$("$BIN" coverage report 2>&1 || true)
+expect "$output" "Error: Could not read coverage data 'franz.coverage' (run a program with --coverage first)."
+output=$("$BIN" coverage report app.franz 2>&1 || true)
+expect "$output" "Error: 'app.franz' is not a coverage data file."
+output=$("$BIN" coverage report --html 2>&1 || true)
+expect "$output" "Error: Unknown option '--html' for 'franz coverage report' (expected --lcov or -o FILE)."
+output=$("$BIN" coverage show 2>&1 || true)
+expect "$output" "Error: Unknown coverage command 'show'. Use 'franz coverage report [--lcov] [-o FILE] [data-file]'."
+output=$("$BIN" repl --coverage 2>&1 < /dev/null || true)
+expect "$output" "Error: '--coverage' is not supported by 'franz repl'."
+
+echo "All coverage smoke tests passed."
@@ -0,0 +1,52 @@
+#!/usr/bin/env bash
+# Smoke test for cross-compilation (--target, --sysroot)
+# Usage: ./scripts/cross-smoke.sh
+set -euo pipefail
+
+ROOT_DIR="$(cd "$(dirname "$0")/.." && pwd)"
+BIN="$ROOT_DIR/franz"
+OUT_DIR="$(mktemp -d)"
+trap 'rm -rf "$OUT_DIR"' EXIT
+export FRANZ_CACHE_DIR="$OUT_DIR/cache"
+
+SOURCE="$ROOT_DIR/test/build/build-test.franz"
+TARGET="aarch64-linux-gnu"
+
+# ELF e_machine (little-endian) of an object file or executable
+elf_machine() {
+  od -An -tx1 -j18 -N2 "$1" | tr -d ' \n'
+}
+
+echo "--- Emitting IR for $TARGET" >&2
+"$BIN" --emit=ir --target="$TARGET" "$SOURCE" -o "$OUT_DIR/out.ll" >/dev/null
+grep -q 'target triple = "aarch64-unknown-linux-gnu"' "$OUT_DIR/out.ll"
+
+echo "--- Emitting an object file for $TARGET" >&2
+"$BIN" --emit=obj --target="$TARGET" "$SOURCE" -o "$OUT_DIR/out.o" >/dev/null
+[ "$(elf_machine "$OUT_DIR/out.o")" = "b700" ]  # EM_AARCH64
+
+echo "--- Rejecting cross targets when running" >&2
+if "$BIN" --target="$TARGET" "$SOURCE" >/dev/null 2>&1; then
+  echo "--target ran the program" >&2
+  exit 1
+fi
+if "$BIN" run --sysroot=/ "$SOURCE" >/dev/null 2>&1; then
+  echo "--sysroot ran the program" >&2
+  exit 1
+fi
+
+echo "--- Rejecting unknown targets" >&2
+if "$BIN" --emit=obj --target=no-such-target "$SOURCE" -o "$OUT_DIR/bad.o" >/dev/null 2>&1; then
+  echo "--target accepted an unknown triple" >&2
+  exit 1
+fi
+
+if command -v "$TARGET-gcc" >/dev/null 2>&1; then
+  echo "--- Building an executable for $TARGET" >&2
+  "$BIN" build --target="$TARGET" "$SOURCE" -o "$OUT_DIR/app" >/dev/null
+  [ "$(elf_machine "$OUT_DIR/app")" = "b700" ]
+else
+  echo "--- Skipping link test ($TARGET-gcc not installed)" >&2
+fi
+
+echo "All cross-compilation smoke tests passed." >&2
@@ -0,0 +1,58 @@
+#!/usr/bin/env bash
+# Smoke test for DWARF debug info (-g)
+# Usage: ./scripts/debuginfo-smoke.sh
+# Checks the debug metadata in emitted IR and, when LLVM's binutils are
+# installed, the DWARF sections of a built executable.
+set -euo pipefail
+
+ROOT_DIR="$(cd "$(dirname "$0")/.." && pwd)"
+BIN="$ROOT_DIR/franz"
+
+TMP_DIR="$(mktemp -d)"
+trap 'rm -rf "$TMP_DIR"' EXIT
+export FRANZ_CACHE_DIR="$TMP_DIR/cache"
+
+cd "$ROOT_DIR"
+
+cat > "$TMP_DIR/debug.franz" <<'FRANZ'
+square = {n ->
+  <- (multiply n n)
+}
+
+(print (square 7) "\n")
+FRANZ
+
+echo "--- Emitted IR carries debug metadata" >&2
+"$BIN" -g --emit=ir "$TMP_DIR/debug.franz" -o "$TMP_DIR/debug.ll" >/dev/null
+ir=$(cat "$TMP_DIR/debug.ll")
+grep -q 'DICompileUnit(language: DW_LANG_C' <<< "$ir"
+grep -q "DIFile(filename: \"debug.franz\", directory: \"$TMP_DIR\")" <<< "$ir"
+grep -q 'DISubprogram(name: "square", linkageName: "_franz_lambda_0"' <<< "$ir"
+grep -q 'DISubprogram(name: "main"' <<< "$ir"
+grep -q 'DILocation(line: 2' <<< "$ir"
+
+echo "--- Without -g there is no debug metadata" >&2
+"$BIN" --emit=ir "$TMP_DIR/debug.franz" -o "$TMP_DIR/plain.ll" >/dev/null
+if grep -q 'DISubprogram' "$TMP_DIR/plain.ll"; then
+  echo "Unexpected debug metadata without -g" >&2
+  exit 1
+fi
+
+echo "--- Debug info with optimizations and the JIT" >&2
+"$BIN" -g -O2 --emit=ir "$TMP_DIR/debug.franz" -o "$TMP_DIR/opt.ll" >/dev/null
+grep -q 'isOptimized: true' "$TMP_DIR/opt.ll"
+output=$("$BIN" run -g "$TMP_DIR/debug.franz" 2>/dev/null)
+grep -qx "49" <<< "$output"
+
+if command -v llvm-dwarfdump >/dev/null 2>&1 && "$BIN" build -g "$TMP_DIR/debug.franz" -o "$TMP_DIR/debug" >/dev/null 2>&1; then
+  echo "--- Executable has DWARF for Franz functions" >&2
+  "$TMP_DIR/debug" | grep -qx "49"
+  dwarf=$(llvm-dwarfdump --debug-info "$TMP_DIR/debug")
+  grep -q 'DW_AT_name	("square")' <<< "$dwarf"
+  lines=$(llvm-dwarfdump --debug-line "$TMP_DIR/debug")
+  grep -q 'debug.franz' <<< "$lines"
+else
+  echo "--- Skipping DWARF check (no llvm-dwarfdump or C compiler)" >&2
+fi
+
+echo "All debug info smoke tests passed." >&2
@@ -0,0 +1,70 @@
+#!/usr/bin/env bash
+# Smoke test for source-span diagnostics (caret snippets under errors)
+# Usage: ./scripts/diagnostics-smoke.sh
+# Compiles programs with mistakes and checks the quoted line and underline.
+set -euo pipefail
+
+ROOT_DIR="$(cd "$(dirname "$0")/.." && pwd)"
+BIN="$ROOT_DIR/franz"
+
+WORK_DIR=$(mktemp -d)
+trap 'rm -rf "$WORK_DIR"' EXIT
+export FRANZ_CACHE_DIR="$WORK_DIR/cache"
+
+cd "$WORK_DIR"
+
+expect() {
+  local output="$1" expected="$2"
+  if ! grep -qxF -- "$expected" <<< "$output"; then
+    echo "Expected line '$expected' in diagnostic output:" >&2
+    echo "$output" >&2
+    exit 1
+  fi
+}
+
+echo "--- Unknown function underlines the callee" >&2
+printf 'x = 1\n  (println (frob x 2))\n' > unknown.franz
+output=$("$BIN" unknown.franz 2>&1 || true)
+expect "$output" "ERROR: Unknown function 'frob' at line 2 [F0202]"
+expect "$output" " --> unknown.franz:2:13"
+expect "$output" "2 |   (println (frob x 2))"
+expect "$output" "  |             ^^^^"
+
+echo "--- Arity errors underline the whole call" >&2
+printf '(println (add 1))\n' > arity.franz
+output=$("$BIN" arity.franz 2>&1 || true)
+expect "$output" " --> arity.franz:1:10"
+expect "$output" "  |          ^^^^^^^"
+
+echo "--- Syntax errors point at the token" >&2
+printf 'x = 1\ny = (add x 2) ]\n' > syntax.franz
+output=$("$BIN" syntax.franz 2>&1 || true)
+expect "$output" "Syntax Error @ Line 2: Unexpected rbracket token. [F0101]"
+expect "$output" " --> syntax.franz:2:15"
+expect "$output" "  |               ^"
+
+echo "--- Unterminated strings underline the string" >&2
+printf 'name = "Ada\n' > string.franz
+output=$("$BIN" string.franz 2>&1 || true)
+expect "$output" "  |        ^^^^"
+
+echo "--- Tabs are kept so carets line up" >&2
+printf '\t(println missing)\n' > tabs.franz
+output=$("$BIN" tabs.franz 2>&1 || true)
+expect "$output" "$(printf '  | \t         ^^^^^^^')"
+
+echo "--- Errors inside modules quote the module" >&2
+printf 'helper = {x -> <- (frobnicate x)}\n' > mod.franz
+printf '(use "mod.franz")\n' > main.franz
+output=$("$BIN" main.franz 2>&1 || true)
+expect "$output" " --> mod.franz:1:20"
+expect "$output" "1 | helper = {x -> <- (frobnicate x)}"
+
+echo "--- Runtime errors quote the line (JIT)" >&2
+printf 'x = 1\n(write_file "no/such/dir.txt" "a")\n' > runtime.franz
+output=$("$BIN" run runtime.franz 2>&1 || true)
+expect "$output" 'Runtime Error @ Line 2: Cannot write file "no/such/dir.txt". [F0404]'
+expect "$output" " --> runtime.franz:2"
+expect "$output" '  | ^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^'
+
+echo "All diagnostics smoke tests passed." >&2
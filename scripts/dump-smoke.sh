@@ -0,0 +1,86 @@
+#!/usr/bin/env bash
+# Snapshot test for the JSON token and AST dumps (--dump-tokens=json, --dump-ast=json)
+# Usage: ./scripts/dump-smoke.sh
+#        UPDATE=1 ./scripts/dump-smoke.sh   (rewrite the snapshots after a parser change)
+# Every test/parser/<name>.franz is dumped and compared with <name>.tokens.json
+# and <name>.ast.json next to it.
+set -euo pipefail
+
+ROOT_DIR="$(cd "$(dirname "$0")/.." && pwd)"
+BIN="$ROOT_DIR/franz"
+PARSER_DIR="$ROOT_DIR/test/parser"
+
+WORK_DIR=$(mktemp -d)
+trap 'rm -rf "$WORK_DIR"' EXIT
+export FRANZ_CACHE_DIR="$WORK_DIR/cache"
+
+expect() {
+  local output="$1" expected="$2"
+  if ! grep -qxF -- "$expected" <<< "$output"; then
+    echo "Expected line '$expected' in output:" >&2
+    echo "$output" >&2
+    exit 1
+  fi
+}
+
+# Compare a dump with its snapshot (or rewrite the snapshot with UPDATE=1)
+check_snapshot() {
+  local actual="$1" snapshot="$2"
+  if [ "${UPDATE:-0}" = "1" ]; then
+    cp "$actual" "$snapshot"
+    return
+  fi
+  if ! python3 -c 'import json, sys; json.load(open(sys.argv[1]))' "$actual"; then
+    echo "Dump is not valid JSON: $actual" >&2
+    exit 1
+  fi
+  if ! diff -u "$snapshot" "$actual" >&2; then
+    echo "Snapshot mismatch: $snapshot (rerun with UPDATE=1 if the change is intended)" >&2
+    exit 1
+  fi
+}
+
+echo "--- Snapshots" >&2
+cd "$PARSER_DIR"
+for source in *.franz; do
+  name="${source%.franz}"
+  echo "--- $name" >&2
+  "$BIN" --dump-tokens=json "$source" > "$WORK_DIR/$name.tokens.json"
+  check_snapshot "$WORK_DIR/$name.tokens.json" "$name.tokens.json"
+  "$BIN" --dump-ast=json "$source" > "$WORK_DIR/$name.ast.json"
+  check_snapshot "$WORK_DIR/$name.ast.json" "$name.ast.json"
+done
+
+cd "$WORK_DIR"
+
+echo "--- Both dumps, and the program is not run" >&2
+printf '(println "ran")\n' > run.franz
+output=$("$BIN" --dump-tokens=json --dump-ast=json run.franz)
+if grep -qx "ran" <<< "$output"; then
+  echo "Program ran during a dump:" >&2
+  echo "$output" >&2
+  exit 1
+fi
+python3 -c 'import json, sys
+text = sys.stdin.read()
+decoder = json.JSONDecoder()
+tokens, end = decoder.raw_decode(text)
+ast, _ = decoder.raw_decode(text[end:].lstrip())
+assert tokens[0]["type"] == "start" and ast["opcode"] == "statement"' <<< "$output"
+
+echo "--- Syntax errors" >&2
+printf 'x = (add 1\n' > broken.franz
+if "$BIN" --dump-ast=json broken.franz > ast.out 2>&1; then
+  echo "Expected --dump-ast=json to fail on a syntax error" >&2
+  exit 1
+fi
+expect "$(cat ast.out)" "Syntax Error @ Line 1: Application not closed. [F0105]"
+# Tokens are still dumped when the program does not parse
+"$BIN" --dump-tokens=json broken.franz > tokens.out
+expect "$(cat tokens.out)" '  {"type":"applyopen","value":null,"line":1,"column":5,"endLine":1,"endColumn":6,"offset":4,"endOffset":5},'
+
+echo "--- Invalid formats" >&2
+output=$("$BIN" --dump-ast=yaml run.franz 2>&1 || true)
+expect "$output" "Error: Invalid dump format 'yaml'. Use 'json'."
+
+echo "All dump smoke tests passed."
@@ -0,0 +1,42 @@
+#!/usr/bin/env bash
+# Smoke test for compiler output selection (--emit=ir|bc|asm|obj)
+# Usage: ./scripts/emit-smoke.sh
+set -euo pipefail
+
+ROOT_DIR="$(cd "$(dirname "$0")/.." && pwd)"
+BIN="$ROOT_DIR/franz"
+OUT_DIR="$(mktemp -d)"
+trap 'rm -rf "$OUT_DIR"' EXIT
+
+SOURCE="$ROOT_DIR/test/build/build-test.franz"
+
+emit() {
+  local kind="$1"
+  local output="$2"
+  echo "--- Emitting: $kind" >&2
+  if "$BIN" --emit="$kind" "$SOURCE" -o "$output" 2>/dev/null | grep -q "Build test PASSED"; then
+    echo "--emit=$kind ran the program" >&2
+    exit 1
+  fi
+  if [ ! -s "$output" ]; then
+    echo "--emit=$kind did not write $output" >&2
+    exit 1
+  fi
+}
+
+emit ir "$OUT_DIR/out.ll"
+grep -q "define i32 @main" "$OUT_DIR/out.ll"
+grep -q "target triple" "$OUT_DIR/out.ll"
+
+emit bc "$OUT_DIR/out.bc"
+[ "$(head -c 2 "$OUT_DIR/out.bc")" = "BC" ]
+
+emit asm "$OUT_DIR/out.s"
+grep -q "main" "$OUT_DIR/out.s"
+
+emit obj "$OUT_DIR/out.o"
+
+# Default output name is derived from the source file
+(cd "$OUT_DIR" && "$BIN" --emit=ir "$SOURCE" >/dev/null 2>&1 && [ -s build-test.ll ])
+
+echo "All emit smoke tests passed." >&2
@@ -0,0 +1,77 @@
+#!/usr/bin/env bash
+# Smoke test for stable error codes and franz --explain
+# Usage: ./scripts/error-codes-smoke.sh
+# Checks the catalog listing, explanations, unknown codes, and that the
+# erroneous example of each compiler error code really produces that code.
+set -euo pipefail
+
+ROOT_DIR="$(cd "$(dirname "$0")/.." && pwd)"
+BIN="$ROOT_DIR/franz"
+
+WORK_DIR=$(mktemp -d)
+trap 'rm -rf "$WORK_DIR"' EXIT
+export FRANZ_CACHE_DIR="$WORK_DIR/cache"
+
+cd "$WORK_DIR"
+
+expect() {
+  local output="$1" expected="$2"
+  if ! grep -qxF -- "$expected" <<< "$output"; then
+    echo "Expected line '$expected' in output:" >&2
+    echo "$output" >&2
+    exit 1
+  fi
+}
+
+echo "--- Listing the catalog" >&2
+output=$("$BIN" --explain)
+expect "$output" "F0101  Unexpected token"
+expect "$output" "F0202  Unknown function"
+expect "$output" "F0404  File error"
+if grep -vqE '^F0[1-4][0-9]{2}  ' <<< "$output"; then
+  echo "Unexpected line in catalog listing:" >&2
+  echo "$output" >&2
+  exit 1
+fi
+
+echo "--- Explaining a code" >&2
+output=$("$BIN" --explain F0202)
+expect "$output" "F0202: Unknown function"
+expect "$output" "Erroneous code example:"
+expect "$output" "    (println (sum 1 2))"
+expect "$output" "Fixed:"
+expect "$output" "    (println (add 1 2))"
+output=$("$BIN" --explain=f0105)
+expect "$output" "F0105: Unclosed bracket"
+
+echo "--- Unknown codes" >&2
+if "$BIN" --explain F9999 2>/dev/null; then
+  echo "Expected --explain F9999 to fail" >&2
+  exit 1
+fi
+output=$("$BIN" --explain F9999 2>&1 || true)
+expect "$output" "Error: 'F9999' is not a Franz error code. Run 'franz --explain' to list them."
+
+echo "--- Examples produce their code" >&2
+for code in F0101 F0102 F0103 F0104 F0105 F0106 F0201 F0202 F0203 F0204 F0205 F0206 F0207 F0208 F0209 F0402 F0404 F0405; do
+  # The erroneous example is the indented block between the two headings
+  "$BIN" --explain "$code" \
+    | awk '/^Erroneous code example:/ { on = 1; next } /^Fixed:/ { on = 0 } on && /^    / { print substr($0, 5) }' \
+    > "$code.franz"
+  output=$("$BIN" --no-cache "$code.franz" 2>&1 || true)
+  if ! grep -qF -- "[$code]" <<< "$output"; then
+    echo "Example for $code did not report [$code]:" >&2
+    cat "$code.franz" >&2
+    echo "$output" >&2
+    exit 1
+  fi
+done
+
+echo "--- Codes in human and JSON output" >&2
+printf 'x = 1\n  (println (frob x 2))\n' > unknown.franz
+output=$("$BIN" --no-cache unknown.franz 2>&1 || true)
+expect "$output" "ERROR: Unknown function 'frob' at line 2 [F0202]"
+output=$("$BIN" --no-cache --message-format=json unknown.franz 2>&1 >/dev/null || true)
+grep -qF '"code":"F0202"' <<< "$output" || { echo "Expected code F0202 in JSON: $output" >&2; exit 1; }
+
+echo "All error code smoke tests passed."
@@ -0,0 +1,100 @@
+#!/usr/bin/env bash
+# Smoke test for the source formatter (`franz fmt`)
+# Usage: ./scripts/fmt-smoke.sh
+# Checks the canonical layout, comment preservation, --check, and that
+# formatting every example twice changes nothing the second time.
+set -euo pipefail
+
+ROOT_DIR="$(cd "$(dirname "$0")/.." && pwd)"
+BIN="$ROOT_DIR/franz"
+
+WORK_DIR=$(mktemp -d)
+trap 'rm -rf "$WORK_DIR"' EXIT
+export FRANZ_CACHE_DIR="$WORK_DIR/cache"
+
+cd "$WORK_DIR"
+
+expect() {
+  local output="$1" expected="$2"
+  if ! grep -qxF -- "$expected" <<< "$output"; then
+    echo "Expected line '$expected' in output:" >&2
+    echo "$output" >&2
+    exit 1
+  fi
+}
+
+echo "--- Layout is normalized" >&2
+printf 'square={x->\n<- (multiply x x)}\nitems =  [1,2,   3]\n\n\n\n(println (square 4)   items)\n' > messy.franz
+output=$("$BIN" fmt < messy.franz)
+expect "$output" "square = {x -> <- (multiply x x)}"
+expect "$output" "items = [1, 2, 3]"
+expect "$output" "(println (square 4) items)"
+if [ "$(grep -c '^$' <<< "$output")" -ne 1 ]; then
+  echo "Expected runs of blank lines to collapse to one:" >&2
+  echo "$output" >&2
+  exit 1
+fi
+
+echo "--- Long calls break and hug their last function" >&2
+printf 'total = (add 1 2)\n(loop 3 {i -> (println "a fairly long line that keeps going" i) (println "and a second statement" i)})\n' > long.franz
+output=$("$BIN" fmt < long.franz)
+expect "$output" "(loop 3 {i ->"
+expect "$output" '  (println "a fairly long line that keeps going" i)'
+expect "$output" "})"
+
+echo "--- Comments are kept" >&2
+printf '// header\nx = 1 // trailing\nf = {a ->\n  // inside\n  <- a\n}\n' > comments.franz
+output=$("$BIN" fmt < comments.franz)
+expect "$output" "// header"
+expect "$output" "x = 1  // trailing"
+expect "$output" "  // inside"
+
+echo "--- Files are rewritten in place and --check passes afterwards" >&2
+before=$(cat messy.franz)
+status=0
+output=$("$BIN" fmt --check messy.franz) || status=$?
+expect "$output" "Would reformat messy.franz"
+if [ "$status" -ne 1 ]; then
+  echo "Expected --check to exit 1 on an unformatted file, got $status" >&2
+  exit 1
+fi
+if [ "$before" != "$(cat messy.franz)" ]; then
+  echo "--check must not modify files" >&2
+  exit 1
+fi
+"$BIN" fmt messy.franz
+"$BIN" fmt --check messy.franz
+expected_run=$("$BIN" long.franz 2>&1)
+"$BIN" fmt long.franz
+if [ "$expected_run" != "$("$BIN" long.franz 2>&1)" ]; then
+  echo "Formatting changed the output of long.franz" >&2
+  exit 1
+fi
+
+echo "--- Syntax errors leave the file alone" >&2
+printf 'x = (add 1\n' > broken.franz
+status=0
+output=$("$BIN" fmt broken.franz 2>&1) || status=$?
+expect "$output" "Syntax Error @ Line 1: Application not closed. [F0105]"
+expect "$output" "Error: cannot format broken.franz: 1 syntax error"
+if [ "$status" -ne 1 ] || [ "$(cat broken.franz)" != "x = (add 1" ]; then
+  echo "A file with syntax errors must be reported and left unchanged" >&2
+  exit 1
+fi
+
+echo "--- Formatting the examples is idempotent" >&2
+cp -r "$ROOT_DIR/examples" examples
+"$BIN" fmt examples > /dev/null 2>&1 || true
+cp -r examples first-pass
+"$BIN" fmt examples > /dev/null 2>&1 || true
+if ! diff -r first-pass examples > /dev/null; then
+  echo "Formatting formatted examples changed them:" >&2
+  diff -r first-pass examples >&2 || true
+  exit 1
+fi
+if "$BIN" fmt examples 2>&1 | grep -q "could not format"; then
+  echo "The formatter failed to verify an example" >&2
+  exit 1
+fi
+
+echo "All fmt smoke tests passed." >&2
@@ -0,0 +1,140 @@
+#!/usr/bin/env bash
+# Smoke test for the tree-walking interpreter (--interpret)
+# Usage: ./scripts/interpret-smoke.sh
+# Covers output parity with compiled programs, control flow, closures,
+# modules, runtime errors, rejected options, running without a toolchain
+# and the repository's tests on both backends.
+set -euo pipefail
+
+ROOT_DIR="$(cd "$(dirname "$0")/.." && pwd)"
+BIN="$ROOT_DIR/franz"
+
+WORK_DIR=$(mktemp -d)
+trap 'rm -rf "$WORK_DIR"' EXIT
+export FRANZ_CACHE_DIR="$WORK_DIR/cache"
+
+expect() {
+  local output="$1" expected="$2"
+  if ! grep -qxF -- "$expected" <<< "$output"; then
+    echo "Expected line '$expected' in output:" >&2
+    echo "$output" >&2
+    exit 1
+  fi
+}
+
+cd "$WORK_DIR"
+cat > app.franz <<'EOF'
+counter = {->
+  base = 10
+  add_base = {step -> <- (add base step)}
+  <- (add_base 5)
+}
+(println "counter " (counter))
+
+mut total = 0
+(loop 5 {i ->
+  (if (is i 3) {(continue)} {})
+  total = (add total i)
+})
+(println "total " total)
+
+found = (loop 10 {i ->
+  (when (greater_than (multiply i i) 20) {(break i)})
+})
+(println "found " found)
+
+mut n = 0
+(while (less_than n 3) {n = (add n 1)})
+(println "n " n)
+(println (and 1 0) (or 0 1) [1, "two", 3.5])
+EOF
+
+echo "--- Same output as the compiled program" >&2
+"$BIN" build app.franz -o app > /dev/null 2>&1
+compiled=$(./app 2>/dev/null)
+interpreted=$("$BIN" --interpret app.franz)
+if [ "$compiled" != "$interpreted" ]; then
+  echo "Interpreter output differs from compiled output:" >&2
+  diff <(echo "$compiled") <(echo "$interpreted") >&2 || true
+  exit 1
+fi
+expect "$interpreted" "counter 15"
+expect "$interpreted" "total 7"
+expect "$interpreted" "found 5"
+expect "$interpreted" "n 3"
+expect "$interpreted" "01[1, two, 3.500000]"
+
+echo "--- cond and callbacks" >&2
+cat > callbacks.franz <<'EOF'
+grade = {score ->
+  <- (cond
+    ((greater_than score 89) "A")
+    ((greater_than score 69) "B")
+    (else "C"))
+}
+(println (grade 95) (grade 75) (grade 10))
+(println (map [1, 2, 3] {v -> <- (multiply v 2)}))
+(println (reduce [1, 2, 3] {acc v -> <- (add acc v)} 0))
+EOF
+output=$("$BIN" --interpret callbacks.franz)
+expect "$output" "ABC"
+expect "$output" "[2, 4, 6]"
+expect "$output" "6"
+
+echo "--- Modules" >&2
+printf 'double = {x -> <- (multiply x 2)}\n' > lib.franz
+printf 'lib = (use_as "lib.franz")\n(println (lib.double 21))\n' > main.franz
+expect "$("$BIN" --interpret main.franz)" "42"
+
+echo "--- Runtime errors" >&2
+printf '(println "before")\n(println missing)\n' > undefined.franz
+status=0
+output=$("$BIN" --interpret undefined.franz) || status=$?
+expect "$output" "before"
+expect "$output" "Runtime Error @ Line 2: Undefined variable 'missing'. [F0201]"
+if [ "$status" -ne 1 ]; then
+  echo "Expected exit status 1 after a runtime error, got $status" >&2
+  exit 1
+fi
+printf '(println (get [1] 5))\n' > range.franz
+status=0
+"$BIN" --interpret range.franz > /dev/null || status=$?
+if [ "$status" -ne 1 ]; then
+  echo "Expected exit status 1 after a built-in function's runtime error, got $status" >&2
+  exit 1
+fi
+printf '(break)\n' > outside.franz
+output=$("$BIN" --interpret outside.franz || true)
+if ! grep -q "Runtime Error @ Line 1" <<< "$output"; then
+  echo "Expected a runtime error for break outside a loop:" >&2
+  echo "$output" >&2
+  exit 1
+fi
+
+echo "--- Rejected options" >&2
+output=$("$BIN" --interpret --jit app.franz 2>&1 || true)
+expect "$output" "Error: '--interpret' cannot be combined with 'franz build', 'franz run', 'franz repl', '--emit' or '--jit'."
+output=$("$BIN" --interpret --profile app.franz 2>&1 || true)
+expect "$output" "Error: Options '-g', '--coverage', '--trace' and '--profile' are not supported by '--interpret'."
+
+echo "--- No toolchain needed" >&2
+# Neither llc nor a C compiler can be found, and nothing is cached
+rm -rf "$FRANZ_CACHE_DIR"
+output=$(PATH=/nonexistent "$BIN" --interpret app.franz)
+expect "$output" "counter 15"
+if [ -e "$FRANZ_CACHE_DIR" ] && [ -n "$(ls -A "$FRANZ_CACHE_DIR")" ]; then
+  echo "Interpreted programs should not write the build cache" >&2
+  exit 1
+fi
+
+echo "--- Repository tests on both backends" >&2
+cd "$ROOT_DIR"
+output=$("$BIN" test --backend=both test/ 2>/dev/null)
+expect "$output" "PASS test/loop/loop-simple.franz [interpret]"
+expect "$output" "PASS test/llvm-control-flow/break-test.franz [interpret]"
+if grep -q "^FAIL" <<< "$output"; then
+  echo "$output" >&2
+  exit 1
+fi
+
+echo "All interpret smoke tests passed."
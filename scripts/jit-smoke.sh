@@ -0,0 +1,26 @@
+#!/usr/bin/env bash
+# Smoke test for JIT execution (franz run)
+# Usage: ./scripts/jit-smoke.sh
+# Verifies that programs produce the same output under the JIT and the AOT path.
+set -euo pipefail
+
+ROOT_DIR="$(cd "$(dirname "$0")/.." && pwd)"
+BIN="$ROOT_DIR/franz"
+
+cd "$ROOT_DIR"
+
+for file in test/jit/jit-test.franz test/build/build-test.franz; do
+  echo "--- Running: $file" >&2
+  jit_output=$("$BIN" run "$file" 2>/dev/null | grep -v "LLVM IR generation complete")
+  aot_output=$("$BIN" "$file" 2>/dev/null | grep -v "LLVM IR generation complete")
+
+  if [ "$jit_output" != "$aot_output" ]; then
+    echo "JIT output differs from AOT output for $file" >&2
+    diff <(echo "$aot_output") <(echo "$jit_output") >&2 || true
+    exit 1
+  fi
+done
+
+"$BIN" run test/jit/jit-test.franz 2>/dev/null | grep -q "JIT test PASSED"
+
+echo "All JIT smoke tests passed." >&2
@@ -0,0 +1,129 @@
+#!/usr/bin/env bash
+# Smoke test for the static analyzer (`franz lint`)
+# Usage: ./scripts/lint-smoke.sh
+# Triggers every rule, checks --enable/--disable and the file directive, and
+# lints every example to make sure the linter finishes on all of them.
+set -euo pipefail
+
+ROOT_DIR="$(cd "$(dirname "$0")/.." && pwd)"
+BIN="$ROOT_DIR/franz"
+
+WORK_DIR=$(mktemp -d)
+trap 'rm -rf "$WORK_DIR"' EXIT
+export FRANZ_CACHE_DIR="$WORK_DIR/cache"
+
+cd "$WORK_DIR"
+
+expect() {
+  local output="$1" expected="$2"
+  if ! grep -qxF -- "$expected" <<< "$output"; then
+    echo "Expected line '$expected' in output:" >&2
+    echo "$output" >&2
+    exit 1
+  fi
+}
+
+reject() {
+  local output="$1" pattern="$2"
+  if grep -qF -- "$pattern" <<< "$output"; then
+    echo "Did not expect '$pattern' in output:" >&2
+    echo "$output" >&2
+    exit 1
+  fi
+}
+
+cat > module.franz <<'FRANZ'
+double = {x -> <- (multiply x 2)}
+FRANZ
+
+cat > mistakes.franz <<'FRANZ'
+(use "module.franz" {
+  (println "the module is not used here")
+})
+count = 1
+count = 2
+map = {x -> <- x}
+f = {a b ->
+  unused = 5
+  <- (if (is a 1) {<- 2})
+  (println "never")
+}
+(println (f 1 2) count (map 3))
+FRANZ
+
+echo "--- Every rule reports" >&2
+status=0
+output=$("$BIN" lint mistakes.franz 2>&1) || status=$?
+expect "$output" "Warning @ Line 1: Nothing from 'module.franz' is used in the callback. [unused-import]"
+expect "$output" "Warning @ Line 5: 'count' is reassigned but was not declared with 'mut'. [immutable-assign]"
+expect "$output" "Warning @ Line 6: 'map' shadows the stdlib function of the same name. [shadowed-stdlib]"
+expect "$output" "Warning @ Line 7: Parameter 'b' is never used (prefix it with '_' if that is intended). [unused-param]"
+expect "$output" "Warning @ Line 8: 'unused' is assigned but never used. [unused-binding]"
+expect "$output" "Warning @ Line 9: 'if' has no else branch but its value is used (it is void when no condition holds). [if-without-else]"
+expect "$output" "Warning @ Line 10: Unreachable statement after '<-'. [unreachable-code]"
+expect "$output" "7 warnings in 1 file"
+if [ "$status" -ne 1 ]; then
+  echo "Expected exit status 1 when there are warnings, got $status" >&2
+  exit 1
+fi
+
+echo "--- Clean code passes" >&2
+cat > clean.franz <<'FRANZ'
+mut total = 0
+(use "module.franz" {
+  total = (double 21)
+})
+pick = {_key value -> <- value}
+(println (if (greater_than total 0) {<- total} {<- 0}) (pick "k" 1))
+helper = {n -> <- (later n)}
+later = {n -> <- (add n 1)}
+FRANZ
+output=$("$BIN" lint clean.franz 2>&1)
+expect "$output" "0 warnings in 1 file"
+
+echo "--- Rules can be disabled and enabled" >&2
+output=$("$BIN" lint --disable=all --enable=unused-param mistakes.franz 2>&1) || true
+expect "$output" "1 warning in 1 file"
+expect "$output" "Warning @ Line 7: Parameter 'b' is never used (prefix it with '_' if that is intended). [unused-param]"
+output=$("$BIN" lint --disable=unreachable-code,shadowed-stdlib mistakes.franz 2>&1) || true
+expect "$output" "5 warnings in 1 file"
+reject "$output" "[unreachable-code]"
+status=0
+output=$("$BIN" lint --disable=no-such-rule mistakes.franz 2>&1) || status=$?
+expect "$output" "Error: Unknown lint rule 'no-such-rule' (see 'franz lint --list-rules')."
+if [ "$status" -ne 1 ]; then
+  echo "Expected an unknown rule to fail" >&2
+  exit 1
+fi
+output=$("$BIN" lint --list-rules)
+expect "$output" "unused-binding"
+expect "$output" "unreachable-code"
+
+echo "--- The file directive disables rules" >&2
+{ echo "// franz-lint: disable=immutable-assign, unused-import"; cat mistakes.franz; } > directive.franz
+output=$("$BIN" lint directive.franz 2>&1) || true
+expect "$output" "5 warnings in 1 file"
+reject "$output" "[immutable-assign]"
+
+echo "--- Stdin and syntax errors" >&2
+output=$(printf 'f = {x y -> <- x}\n(f 1 2)\n' | "$BIN" lint 2>&1) || true
+expect "$output" " --> <stdin>:1:8"
+printf 'x = (add 1\n' > broken.franz
+status=0
+output=$("$BIN" lint broken.franz 2>&1) || status=$?
+expect "$output" "Syntax Error @ Line 1: Application not closed. [F0105]"
+expect "$output" "Error: cannot lint broken.franz: 1 syntax error"
+if [ "$status" -ne 1 ]; then
+  echo "Expected a file with syntax errors to fail" >&2
+  exit 1
+fi
+
+echo "--- Every example can be linted" >&2
+status=0
+(cd "$ROOT_DIR" && "$BIN" lint examples > /dev/null 2>&1) || status=$?
+if [ "$status" -gt 1 ]; then
+  echo "franz lint crashed on examples/ (exit status $status)" >&2
+  exit 1
+fi
+
+echo "All lint smoke tests passed." >&2
@@ -0,0 +1,109 @@
+#!/usr/bin/env bash
+# Smoke test for the language server (franz lsp)
+# Usage: ./scripts/lsp-smoke.sh
+# Drives a scripted JSON-RPC session over stdio and checks the responses.
+set -euo pipefail
+
+ROOT_DIR="$(cd "$(dirname "$0")/.." && pwd)"
+BIN="$ROOT_DIR/franz"
+
+WORK_DIR=$(mktemp -d)
+trap 'rm -rf "$WORK_DIR"' EXIT
+export FRANZ_CACHE_DIR="$WORK_DIR/cache"
+
+cd "$WORK_DIR"
+
+# Frame each argument as one LSP message (Content-Length counts bytes)
+frame() {
+  local message
+  for message in "$@"; do
+    printf 'Content-Length: %d\r\n\r\n%s' "$(LC_ALL=C; echo "${#message}")" "$message"
+  done
+}
+
+# Quote a file as a JSON string
+json_text() {
+  sed -e 's/\\/\\\\/g' -e 's/"/\\"/g' "$1" | awk '{ printf "%s\\n", $0 }'
+}
+
+expect() {
+  local output="$1" expected="$2"
+  if ! grep -qF -- "$expected" <<< "$output"; then
+    echo "Expected '$expected' in output:" >&2
+    echo "$output" >&2
+    exit 1
+  fi
+}
+
+INIT='{"jsonrpc":"2.0","id":1,"method":"initialize","params":{"capabilities":{}}}'
+SHUTDOWN='{"jsonrpc":"2.0","id":99,"method":"shutdown"}'
+EXIT='{"jsonrpc":"2.0","method":"exit"}'
+
+printf 'square = {x -> <- (multiply x x)}\n' > geometry.franz
+printf '(use_as "geometry.franz" "geo")\ndouble = {n ->\n  twice = (add n n)\n  <- twice\n}\nr = (double 4)\n(println r (geo_square 3) missing)\n' > main.franz
+URI="file://$WORK_DIR/main.franz"
+OPEN="{\"jsonrpc\":\"2.0\",\"method\":\"textDocument/didOpen\",\"params\":{\"textDocument\":{\"uri\":\"$URI\",\"languageId\":\"franz\",\"version\":1,\"text\":\"$(json_text main.franz)\"}}}"
+
+request() {
+  local id="$1" method="$2" line="$3" character="$4"
+  echo "{\"jsonrpc\":\"2.0\",\"id\":$id,\"method\":\"$method\",\"params\":{\"textDocument\":{\"uri\":\"$URI\"},\"position\":{\"line\":$line,\"character\":$character}}}"
+}
+
+echo "--- initialize advertises the capabilities" >&2
+status=0
+output=$(frame "$INIT" "$SHUTDOWN" "$EXIT" | "$BIN" lsp) || status=$?
+expect "$output" '"hoverProvider":true'
+expect "$output" '"definitionProvider":true'
+expect "$output" '"documentSymbolProvider":true'
+expect "$output" '"id":99,"result":null'
+if [ "$status" -ne 0 ]; then
+  echo "Expected exit status 0 after shutdown, got $status" >&2
+  exit 1
+fi
+
+echo "--- Diagnostics, hover, definition, completion and symbols" >&2
+output=$(frame "$INIT" "$OPEN" \
+  "$(request 2 textDocument/hover 1 2)" \
+  "$(request 3 textDocument/hover 2 15)" \
+  "$(request 4 textDocument/definition 6 13)" \
+  "$(request 5 textDocument/definition 3 6)" \
+  "$(request 6 textDocument/completion 6 4)" \
+  "{\"jsonrpc\":\"2.0\",\"id\":7,\"method\":\"textDocument/documentSymbol\",\"params\":{\"textDocument\":{\"uri\":\"$URI\"}}}" \
+  "$SHUTDOWN" "$EXIT" | "$BIN" lsp)
+expect "$output" '"message":"Undefined variable '"'"'missing'"'"'"'
+expect "$output" '"range":{"start":{"line":6,"character":26},"end":{"line":6,"character":33}},"severity":1'
+expect "$output" 'double : ((or integer float) -> (or integer float))'
+expect "$output" 'n : (or integer float)'
+expect "$output" "\"id\":4,\"result\":{\"uri\":\"file://$WORK_DIR/geometry.franz\",\"range\":{\"start\":{\"line\":0,\"character\":0},\"end\":{\"line\":0,\"character\":6}}}"
+expect "$output" "\"id\":5,\"result\":{\"uri\":\"$URI\",\"range\":{\"start\":{\"line\":2,\"character\":2},\"end\":{\"line\":2,\"character\":7}}}"
+expect "$output" '{"label":"println","kind":3,"detail":"(any -> void)"}'
+expect "$output" '{"label":"print","kind":3'
+expect "$output" '{"name":"double","kind":12'
+expect "$output" '"children":[{"name":"twice","kind":13'
+
+echo "--- Every syntax error is published, and cleared on close" >&2
+BROKEN="{\"jsonrpc\":\"2.0\",\"method\":\"textDocument/didChange\",\"params\":{\"textDocument\":{\"uri\":\"$URI\",\"version\":2},\"contentChanges\":[{\"text\":\"x = (add 1\\ny = ]\\n\"}]}}"
+CLOSE="{\"jsonrpc\":\"2.0\",\"method\":\"textDocument/didClose\",\"params\":{\"textDocument\":{\"uri\":\"$URI\"}}}"
+output=$(frame "$INIT" "$OPEN" "$BROKEN" "$CLOSE" "$SHUTDOWN" "$EXIT" | "$BIN" lsp)
+expect "$output" '"message":"Application not closed."'
+expect "$output" '"message":"Unexpected rbracket token."'
+expect "$output" '"diagnostics":[]'
+
+echo "--- Positions count UTF-16 code units" >&2
+printf 's = "h\xc3\xa9llo"\n' > text.franz
+URI="file://$WORK_DIR/text.franz"
+OPEN="{\"jsonrpc\":\"2.0\",\"method\":\"textDocument/didOpen\",\"params\":{\"textDocument\":{\"uri\":\"$URI\",\"text\":\"$(json_text text.franz)\"}}}"
+output=$(frame "$INIT" "$OPEN" "$(request 2 textDocument/hover 0 6)" "$SHUTDOWN" "$EXIT" | "$BIN" lsp)
+expect "$output" '"value":"```franz\nstring\n```"},"range":{"start":{"line":0,"character":4},"end":{"line":0,"character":11}}'
+
+echo "--- Protocol errors" >&2
+status=0
+output=$(frame "$INIT" '{"jsonrpc":"2.0","id":2,"method":"franz/unknown"}' 'not json' "$EXIT" | "$BIN" lsp) || status=$?
+expect "$output" '"id":2,"error":{"code":-32601'
+expect "$output" '"id":null,"error":{"code":-32700'
+if [ "$status" -ne 1 ]; then
+  echo "Expected exit status 1 without shutdown, got $status" >&2
+  exit 1
+fi
+
+echo "All lsp smoke tests passed." >&2
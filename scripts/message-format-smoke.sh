@@ -0,0 +1,88 @@
+#!/usr/bin/env bash
+# Smoke test for machine-readable diagnostics (--message-format=json)
+# Usage: ./scripts/message-format-smoke.sh
+# Checks the JSON records for syntax, compile, runtime and lint diagnostics and
+# that every stderr line parses as JSON.
+set -euo pipefail
+
+ROOT_DIR="$(cd "$(dirname "$0")/.." && pwd)"
+BIN="$ROOT_DIR/franz"
+
+WORK_DIR=$(mktemp -d)
+trap 'rm -rf "$WORK_DIR"' EXIT
+export FRANZ_CACHE_DIR="$WORK_DIR/cache"
+
+cd "$WORK_DIR"
+
+expect() {
+  local output="$1" expected="$2"
+  if ! grep -qxF -- "$expected" <<< "$output"; then
+    echo "Expected line '$expected' in output:" >&2
+    echo "$output" >&2
+    exit 1
+  fi
+}
+
+# Every line must be a JSON object
+expect_json_lines() {
+  local output="$1"
+  if ! python3 -c 'import json, sys
+for line in sys.stdin:
+    assert isinstance(json.loads(line), dict)' <<< "$output"; then
+    echo "Expected only JSON lines in output:" >&2
+    echo "$output" >&2
+    exit 1
+  fi
+}
+
+echo "--- Syntax errors" >&2
+printf 'x = 1\ny = (add x 2) ]\n' > syntax.franz
+output=$("$BIN" --message-format=json syntax.franz 2>&1 >/dev/null || true)
+expect_json_lines "$output"
+expect "$output" '{"file":"syntax.franz","line":2,"column":15,"endLine":2,"endColumn":16,"severity":"error","code":"F0101","kind":"Syntax Error","message":"Unexpected rbracket token.","related":[]}'
+
+echo "--- Compile errors" >&2
+printf 'x = 1\n  (println (frob x 2))\n' > unknown.franz
+output=$("$BIN" --message-format=json unknown.franz 2>&1 >/dev/null || true)
+expect_json_lines "$output"
+expect "$output" '{"file":"unknown.franz","line":2,"column":13,"endLine":2,"endColumn":17,"severity":"error","code":"F0202","kind":"Compile Error","message":"Unknown function '"'frob'"'","related":[]}'
+
+echo "--- Code generator errors keep their hints out of JSON" >&2
+printf 'x = 1\nx = 2\n(println x)\n' > immutable.franz
+output=$("$BIN" --message-format=json immutable.franz 2>&1 >/dev/null || true)
+expect_json_lines "$output"
+expect "$output" '{"file":"immutable.franz","line":2,"column":1,"endLine":2,"endColumn":6,"severity":"error","code":"F0206","kind":"Compile Error","message":"Cannot reassign immutable variable '"'x'"'","related":[]}'
+
+echo "--- Runtime errors in native programs and the JIT" >&2
+printf '(write_file "missing-dir/out.txt" "x")\n' > runtime.franz
+output=$("$BIN" --message-format=json runtime.franz 2>&1 >/dev/null || true)
+expect_json_lines "$output"
+expect "$output" '{"file":null,"line":1,"column":null,"endLine":null,"endColumn":null,"severity":"error","code":"F0404","kind":"Runtime Error","message":"Cannot write file \"missing-dir/out.txt\".","related":[]}'
+output=$("$BIN" run --message-format=json runtime.franz 2>&1 >/dev/null || true)
+expect "$output" '{"file":"runtime.franz","line":1,"column":null,"endLine":null,"endColumn":null,"severity":"error","code":"F0404","kind":"Runtime Error","message":"Cannot write file \"missing-dir/out.txt\".","related":[]}'
+
+printf '(println (add (head ["a", 1]) 1))\n' > unbox.franz
+output=$("$BIN" --message-format=json unbox.franz 2>&1 >/dev/null || true)
+expect_json_lines "$output"
+expect "$output" '{"file":null,"line":null,"column":null,"endLine":null,"endColumn":null,"severity":"error","code":"F0402","kind":"Runtime Error","message":"Cannot unbox string to int","related":[]}'
+
+echo "--- Lint warnings carry related spans (environment variable)" >&2
+printf 'x = 1\nx = 2\n(println x)\n' > twice.franz
+output=$(FRANZ_MESSAGE_FORMAT=json "$BIN" lint twice.franz 2>&1 || true)
+expect "$output" '{"file":"twice.franz","line":2,"column":1,"endLine":2,"endColumn":6,"severity":"warning","code":null,"kind":"Warning","message":"'"'x'"' is reassigned but was not declared with '"'mut'"'. [immutable-assign]","related":[{"file":"twice.franz","line":1,"column":1,"endLine":1,"endColumn":2,"message":"'"'x'"' was first assigned here"}]}'
+
+echo "--- Human output is unchanged and shows notes" >&2
+output=$("$BIN" lint twice.franz 2>&1 || true)
+expect "$output" "note: 'x' was first assigned here"
+output=$("$BIN" --no-cache unknown.franz 2>&1 || true)
+expect "$output" "ERROR: Unknown function 'frob' at line 2 [F0202]"
+output=$("$BIN" --no-cache immutable.franz 2>&1 >/dev/null || true)
+expect "$output" "Hint: Declare the variable with 'mut' to make it mutable: mut x = ..."
+
+echo "--- Unknown formats are rejected" >&2
+if "$BIN" --message-format=xml syntax.franz 2>/dev/null; then
+  echo "Expected --message-format=xml to fail" >&2
+  exit 1
+fi
+
+echo "All message-format smoke tests passed." >&2
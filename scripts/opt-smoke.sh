@@ -0,0 +1,39 @@
+#!/usr/bin/env bash
+# Smoke test for optimization levels (-O0..-O3)
+# Usage: ./scripts/opt-smoke.sh
+# Verifies that every level produces the same program output and that -O2
+# actually runs the pass pipeline (mem2reg removes the stack slots).
+set -euo pipefail
+
+ROOT_DIR="$(cd "$(dirname "$0")/.." && pwd)"
+BIN="$ROOT_DIR/franz"
+OUT_DIR="$(mktemp -d)"
+trap 'rm -rf "$OUT_DIR"' EXIT
+
+cd "$ROOT_DIR"
+
+SOURCE=test/optimization/opt-test.franz
+expected=$("$BIN" -O0 "$SOURCE" 2>/dev/null | grep -v "LLVM IR generation complete")
+echo "$expected" | grep -q "Optimization test PASSED"
+
+for level in 1 2 3; do
+  echo "--- Running: -O$level" >&2
+  for mode in "" run; do
+    output=$("$BIN" $mode -O$level "$SOURCE" 2>/dev/null | grep -v "LLVM IR generation complete")
+    if [ "$output" != "$expected" ]; then
+      echo "-O$level ${mode:-aot} output differs from -O0" >&2
+      diff <(echo "$expected") <(echo "$output") >&2 || true
+      exit 1
+    fi
+  done
+done
+
+"$BIN" --emit=ir -O0 "$SOURCE" -o "$OUT_DIR/o0.ll" >/dev/null 2>&1
+"$BIN" --emit=ir -O2 "$SOURCE" -o "$OUT_DIR/o2.ll" >/dev/null 2>&1
+grep -q "alloca" "$OUT_DIR/o0.ll"
+if grep -q "alloca" "$OUT_DIR/o2.ll"; then
+  echo "-O2 IR still contains allocas; pass pipeline did not run" >&2
+  exit 1
+fi
+
+echo "All optimization smoke tests passed." >&2
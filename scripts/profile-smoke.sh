@@ -0,0 +1,98 @@
+#!/usr/bin/env bash
+# Smoke test for call profiling (--profile)
+# Usage: ./scripts/profile-smoke.sh
+# Covers native, JIT, built and REPL programs, call counts of functions,
+# closures and runtime calls, the folded-stack file and runtime errors.
+set -euo pipefail
+
+ROOT_DIR="$(cd "$(dirname "$0")/.." && pwd)"
+BIN="$ROOT_DIR/franz"
+
+WORK_DIR=$(mktemp -d)
+trap 'rm -rf "$WORK_DIR"' EXIT
+export FRANZ_CACHE_DIR="$WORK_DIR/cache"
+unset FRANZ_PROFILE_FILE
+
+expect() {
+  local output="$1" expected="$2"
+  if ! grep -qxE -- "$expected" <<< "$output"; then
+    echo "Expected a line matching '$expected' in output:" >&2
+    echo "$output" >&2
+    exit 1
+  fi
+}
+
+# Table row: calls, inclusive ms, exclusive ms, share, function
+row() {
+  echo " +$1 +[0-9]+\.[0-9]{3} +[0-9]+\.[0-9]{3} +[0-9]+\.[0-9]%  $2"
+}
+
+cd "$WORK_DIR"
+cat > app.franz <<'EOF'
+double = {n ->
+  <- (multiply n 2)
+}
+quad = {n ->
+  <- (double (double n))
+}
+(quad 5)
+(quad 6)
+(map [1, 2, 3] {v -> <- (add v 1)})
+d = (dict_merge (dict "a" 1) (dict "b" 2))
+(println "done")
+EOF
+
+echo "--- Native run" >&2
+report=$("$BIN" --profile app.franz 2>&1 >/dev/null)
+expect "$report" "Profile \(wall time, [0-9]+\.[0-9]{3} ms total, sorted by exclusive time\)"
+expect "$report" " +calls +inclusive ms +exclusive ms +excl %  function"
+expect "$report" "$(row 1 '<toplevel>')"
+expect "$report" "$(row 2 quad)"
+expect "$report" "$(row 4 double)"
+expect "$report" "$(row 3 'lambda@9')"
+expect "$report" "$(row 1 franz_llvm_map)"
+expect "$report" "$(row 1 franz_dict_merge)"
+expect "$report" "Folded stacks written to 'franz.folded'."
+if grep -q "franz_box_" <<< "$report"; then
+  echo "Boxing helpers should not be timed" >&2
+  exit 1
+fi
+
+echo "--- Folded stacks" >&2
+folded=$(cat franz.folded)
+expect "$folded" "<toplevel> [0-9]+"
+expect "$folded" "<toplevel>;quad;double [0-9]+"
+expect "$folded" "<toplevel>;franz_llvm_map;lambda@9 [0-9]+"
+expect "$folded" "<toplevel>;franz_dict_merge [0-9]+"
+if grep -vqE '^[^ ;]+(;[^ ;]+)* [0-9]+$' franz.folded; then
+  echo "Malformed folded-stack line:" >&2
+  cat franz.folded >&2
+  exit 1
+fi
+
+echo "--- JIT, built and REPL programs" >&2
+report=$("$BIN" run --profile app.franz 2>&1 >/dev/null)
+expect "$report" "$(row 4 double)"
+"$BIN" build --profile app.franz -o app > /dev/null 2>&1
+report=$(FRANZ_PROFILE_FILE="$WORK_DIR/app.folded" ./app 2>&1 >/dev/null)
+expect "$report" "$(row 2 quad)"
+expect "$report" "Folded stacks written to '$WORK_DIR/app.folded'."
+expect "$(cat app.folded)" "<toplevel>;quad;double [0-9]+"
+report=$(printf 'triple = {n -> <- (multiply n 3)}\n(triple 1)\n(triple 2)\n' | "$BIN" repl --profile 2>&1)
+expect "$report" "$(row 2 triple)"
+
+echo "--- Runtime errors still report" >&2
+printf 'f = {n ->\n  <- (write_file "missing-dir/out.txt" "x")\n}\n(f 1)\n(println "after")\n' > error.franz
+report=$("$BIN" --profile error.franz 2>&1 >/dev/null || true)
+expect "$report" "$(row 1 f)"
+expect "$(cat franz.folded)" "<toplevel>;f [0-9]+"
+
+echo "--- Programs without --profile report nothing" >&2
+rm -f franz.folded
+report=$("$BIN" app.franz 2>&1 >/dev/null)
+if grep -q "Profile" <<< "$report" || [ -e franz.folded ]; then
+  echo "Unprofiled program wrote a profile" >&2
+  exit 1
+fi
+
+echo "All profile smoke tests passed."
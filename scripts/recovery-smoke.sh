@@ -0,0 +1,81 @@
+#!/usr/bin/env bash
+# Smoke test for parser error recovery (all syntax errors reported in one run)
+# Usage: ./scripts/recovery-smoke.sh
+# Compiles programs with several mistakes and checks every one is reported.
+set -euo pipefail
+
+ROOT_DIR="$(cd "$(dirname "$0")/.." && pwd)"
+BIN="$ROOT_DIR/franz"
+
+WORK_DIR=$(mktemp -d)
+trap 'rm -rf "$WORK_DIR"' EXIT
+export FRANZ_CACHE_DIR="$WORK_DIR/cache"
+
+cd "$WORK_DIR"
+
+expect() {
+  local output="$1" expected="$2"
+  if ! grep -qxF -- "$expected" <<< "$output"; then
+    echo "Expected line '$expected' in output:" >&2
+    echo "$output" >&2
+    exit 1
+  fi
+}
+
+echo "--- Every syntax error is reported, in source order" >&2
+printf 'x = (add 1 2\ny = [1, 2\n(println "done"})\n' > broken.franz
+status=0
+output=$("$BIN" broken.franz 2>&1) || status=$?
+expect "$output" "Syntax Error @ Line 1: Application not closed. [F0105]"
+expect "$output" "Syntax Error @ Line 2: Expected ']' at end of list. [F0105]"
+expect "$output" "Syntax Error @ Line 3: Unexpected funcclose token. [F0101]"
+expect "$output" "ERROR: aborting due to 3 syntax errors"
+if [ "$status" -ne 1 ]; then
+  echo "Expected exit status 1, got $status" >&2
+  exit 1
+fi
+if grep -q "^done$" <<< "$output"; then
+  echo "A program with syntax errors must not run" >&2
+  exit 1
+fi
+
+echo "--- Statements after an unclosed bracket still parse" >&2
+printf 'f = {x ->\n  (println (add x 1]\n  <- x\n}\ny = )\n' > unclosed.franz
+output=$("$BIN" unclosed.franz 2>&1 || true)
+expect "$output" "Syntax Error @ Line 2: Unexpected rbracket token. [F0101]"
+expect "$output" "Syntax Error @ Line 5: Unexpected applyclose token. [F0101]"
+if grep -q "Unexpected return token" <<< "$output"; then
+  echo "Recovery should resume at the statement after the unclosed bracket:" >&2
+  echo "$output" >&2
+  exit 1
+fi
+
+echo "--- An unclosed bracket is reported before the statement it runs into" >&2
+printf 'x = (add 1 2\ny = 3)\n(println y)\n' > runon.franz
+output=$("$BIN" runon.franz 2>&1 || true)
+expect "$output" "Syntax Error @ Line 1: Application not closed. [F0105]"
+expect "$output" "Syntax Error @ Line 2: Unexpected applyclose token. [F0101]"
+if grep -q "Unexpected assignment token" <<< "$output"; then
+  echo "The next statement must not be reported as part of the unclosed application:" >&2
+  echo "$output" >&2
+  exit 1
+fi
+
+echo "--- Lexer errors are collected too" >&2
+printf 'a = "open\nb = 0xZZ\n(println a b)\n' > lexer.franz
+output=$("$BIN" lexer.franz 2>&1 || true)
+expect "$output" "ERROR: aborting due to 2 syntax errors"
+
+echo "--- Errors in modules abort the import" >&2
+printf 'helper = (add 1\nother = ]\n' > mod.franz
+printf '(use "mod.franz")\n' > main.franz
+output=$("$BIN" main.franz 2>&1 || true)
+expect "$output" " --> mod.franz:1:10"
+expect "$output" " --> mod.franz:2:9"
+
+echo "--- The REPL reports all errors and keeps the session" >&2
+output=$(printf 'x = 5\ny = (add x ] 1\n(println x)\n' | "$BIN" repl 2>&1 || true)
+expect "$output" "Syntax Error @ Line 1: Unexpected rbracket token. [F0101]"
+expect "$output" "5"
+
+echo "All recovery smoke tests passed." >&2
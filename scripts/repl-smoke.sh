@@ -0,0 +1,58 @@
+#!/usr/bin/env bash
+# Smoke test for the interactive REPL (franz repl)
+# Usage: ./scripts/repl-smoke.sh
+# Pipes entries into the REPL and checks the printed results.
+set -euo pipefail
+
+ROOT_DIR="$(cd "$(dirname "$0")/.." && pwd)"
+BIN="$ROOT_DIR/franz"
+
+cd "$ROOT_DIR"
+
+expect() {
+  local output="$1" expected="$2"
+  if ! grep -qxF -- "$expected" <<< "$output"; then
+    echo "Expected line '$expected' in REPL output:" >&2
+    echo "$output" >&2
+    exit 1
+  fi
+}
+
+echo "--- Globals and result printing" >&2
+output=$(printf 'x = 5\n(add x 1)\ns = "hi"\ns\nf = 2.5\n(multiply f 2.0)\n(multiply 100000 100000)\n' | "$BIN" repl 2>/dev/null)
+expect "$output" "6"
+expect "$output" "hi"
+expect "$output" "5.000000"
+expect "$output" "10000000000"
+
+echo "--- Multi-line functions" >&2
+output=$(printf 'square = {n ->\n  <- (multiply n n)\n}\n(square 7)\nhalf = {a -> <- (divide a 2.0)}\n(half 3.0)\n' | "$BIN" repl 2>/dev/null)
+expect "$output" "49"
+expect "$output" "1.500000"
+
+echo "--- Mutable globals" >&2
+output=$(printf 'mut count = 1\ncount = (add count 1)\ncount\n' | "$BIN" repl 2>/dev/null)
+expect "$output" "2"
+
+echo "--- Void entries print nothing" >&2
+output=$(printf '(println "hello")\n' | "$BIN" repl 2>/dev/null)
+if [ "$output" != "hello" ]; then
+  echo "Expected only 'hello', got: $output" >&2
+  exit 1
+fi
+
+echo "--- :type" >&2
+output=$(printf 'x = 4\n:type x\n' | "$BIN" repl 2>/dev/null)
+expect "$output" "x : integer"
+
+echo "--- Errors do not end the session" >&2
+output=$(printf 'x = 3\n(add x "\n(undefined_fn 1)\nx\n' | "$BIN" repl 2>/dev/null)
+expect "$output" "3"
+
+echo "--- Invalid usage" >&2
+if "$BIN" repl examples/hello-world.franz </dev/null >/dev/null 2>&1; then
+  echo "Expected 'franz repl <file>' to fail" >&2
+  exit 1
+fi
+
+echo "All REPL smoke tests passed." >&2
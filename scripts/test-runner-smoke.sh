@@ -0,0 +1,93 @@
+#!/usr/bin/env bash
+# Smoke test for the built-in test runner (franz test)
+# Usage: ./scripts/test-runner-smoke.sh
+# Covers inline and .expected expectations, exit codes, compile errors,
+# timeouts, skipped files, the failure diff, backends and the repository's
+# own tests.
+set -euo pipefail
+
+ROOT_DIR="$(cd "$(dirname "$0")/.." && pwd)"
+BIN="$ROOT_DIR/franz"
+
+WORK_DIR=$(mktemp -d)
+trap 'rm -rf "$WORK_DIR"' EXIT
+export FRANZ_CACHE_DIR="$WORK_DIR/cache"
+
+expect() {
+  local output="$1" expected="$2"
+  if ! grep -qxF -- "$expected" <<< "$output"; then
+    echo "Expected line '$expected' in output:" >&2
+    echo "$output" >&2
+    exit 1
+  fi
+}
+
+mkdir "$WORK_DIR/suite"
+cd "$WORK_DIR/suite"
+
+printf '(println (add 1 2))  // expect: 3\n(println "hi")  // expect: hi\n' > inline.franz
+printf '(println 4)\n(println 5)\n' > sibling.franz
+printf '4\n5\n' > sibling.expected
+printf '(println "before")\n(write_file "missing-dir/out.txt" "x")\n// expect-exit: 1\n' > runtime.franz
+printf 'before\nRuntime Error @ Line 2: Cannot write file "missing-dir/out.txt". [F0404]\n' > runtime.expected
+# A program that does not compile is judged by the compiler's messages
+printf '(println "a"\n// expect-exit: 1\n' > compile.franz
+cat > compile.expected <<'END'
+Syntax Error @ Line 1: Application not closed. [F0105]
+ --> ./compile.franz:1:1
+  |
+1 | (println "a"
+  | ^
+ERROR: aborting due to 1 syntax error
+END
+printf '(println "not a test")\n' > plain.franz
+
+echo "--- Passing suite" >&2
+output=$("$BIN" test --jobs=2 .)
+expect "$output" "PASS ./compile.franz"
+expect "$output" "PASS ./inline.franz"
+expect "$output" "PASS ./runtime.franz"
+expect "$output" "PASS ./sibling.franz"
+expect "$output" "4 passed, 0 failed, 1 skipped (no expectations)"
+
+echo "--- Failures are diffed" >&2
+mkdir "$WORK_DIR/failing"
+cd "$WORK_DIR/failing"
+printf '(println (add 1 1))  // expect: 3\n(println "same")  // expect: same\n' > wrong.franz
+printf '(println "ok")  // expect: ok\n// expect-exit: 2\n' > code.franz
+printf '(loop 100000000000 {i -> (add i 1)})\n// expect: never\n' > slow.franz
+if output=$("$BIN" test --timeout=2 -j 3 .); then
+  echo "Expected franz test to fail" >&2
+  echo "$output" >&2
+  exit 1
+fi
+expect "$output" "FAIL ./wrong.franz"
+expect "$output" "--- ./wrong.franz"
+expect "$output" "  stdout (-expected +actual):"
+expect "$output" "    -3"
+expect "$output" "    +2"
+expect "$output" "     same"
+expect "$output" "  exit code: expected 2, got 0"
+expect "$output" "  timed out after 2s"
+expect "$output" "0 passed, 3 failed, 0 skipped (no expectations)"
+
+echo "--- Invalid options" >&2
+output=$("$BIN" test --jobs=0 . 2>&1 || true)
+expect "$output" "Error: Invalid job count '0'."
+output=$("$BIN" test --verbose . 2>&1 || true)
+expect "$output" "Error: Unknown option '--verbose' for 'franz test' (expected --jobs=N, --timeout=SECONDS or --backend=NAME)."
+output=$("$BIN" test --backend=jvm . 2>&1 || true)
+expect "$output" "Error: Invalid backend 'jvm' (expected llvm, interpret, bytecode, both or all)."
+
+echo "--- Repository tests" >&2
+cd "$ROOT_DIR"
+output=$("$BIN" test test/)
+expect "$output" "PASS test/jit/jit-test.franz"
+expect "$output" "PASS test/loop/loop-simple.franz"
+output=$("$BIN" test --backend=both test/)
+expect "$output" "PASS test/loop/loop-simple.franz [llvm]"
+expect "$output" "PASS test/loop/loop-simple.franz [interpret]"
+output=$("$BIN" test --backend=all test/loop/)
+expect "$output" "PASS test/loop/loop-simple.franz [bytecode]"
+
+echo "All test runner smoke tests passed."
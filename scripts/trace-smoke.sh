@@ -0,0 +1,98 @@
+#!/usr/bin/env bash
+# Smoke test for execution tracing (--trace, --trace=GLOB)
+# Usage: ./scripts/trace-smoke.sh
+# Covers native, JIT, built and REPL programs, nesting depth, argument and
+# result rendering, anonymous closures, name patterns and the cache.
+set -euo pipefail
+
+ROOT_DIR="$(cd "$(dirname "$0")/.." && pwd)"
+BIN="$ROOT_DIR/franz"
+
+WORK_DIR=$(mktemp -d)
+trap 'rm -rf "$WORK_DIR"' EXIT
+export FRANZ_CACHE_DIR="$WORK_DIR/cache"
+
+expect() {
+  local output="$1" expected="$2"
+  if ! grep -qxF -- "$expected" <<< "$output"; then
+    echo "Expected line '$expected' in output:" >&2
+    echo "$output" >&2
+    exit 1
+  fi
+}
+
+reject() {
+  local output="$1" unexpected="$2"
+  if grep -qF -- "$unexpected" <<< "$output"; then
+    echo "Unexpected '$unexpected' in output:" >&2
+    echo "$output" >&2
+    exit 1
+  fi
+}
+
+cd "$WORK_DIR"
+cat > app.franz <<'EOF'
+double = {n ->
+  <- (multiply n 2)
+}
+quad = {n ->
+  <- (double (double n))
+}
+half = {x -> <- (divide x 2.0)}
+greet = {name -> <- (join "hi " name)}
+
+(quad 5)
+(println (greet "ada"))
+(half 5.0)
+(map [1, 2] {v -> <- (add v 1)})
+EOF
+
+echo "--- Native run" >&2
+trace=$("$BIN" --trace app.franz 2>&1 >/dev/null)
+expect "$trace" "[trace 1] -> quad(n=5)"
+expect "$trace" "[trace 2]   -> double(n=5)"
+expect "$trace" "[trace 2]   <- double = 10"
+expect "$trace" "[trace 2]   <- double = 20"
+expect "$trace" "[trace 1] <- quad = 20"
+expect "$trace" "[trace 1] -> greet(name=ada)"
+expect "$trace" "[trace 1] <- greet = hi ada"
+expect "$trace" "[trace 1] -> half(x=5.000000)"
+expect "$trace" "[trace 1] <- half = 2.500000"
+expect "$(grep -c 'lambda@13(v=' <<< "$trace")" "2"
+
+echo "--- Program output stays on stdout" >&2
+output=$("$BIN" --trace app.franz 2>/dev/null)
+expect "$output" "hi ada"
+reject "$output" "[trace"
+
+echo "--- JIT, built and REPL programs" >&2
+trace=$("$BIN" run --trace app.franz 2>&1 >/dev/null)
+expect "$trace" "[trace 2]   -> double(n=10)"
+"$BIN" build --trace app.franz -o app > /dev/null
+trace=$(./app 2>&1 >/dev/null)
+expect "$trace" "[trace 1] <- quad = 20"
+trace=$(printf 'triple = {n -> <- (multiply n 3)}\n(println (triple 4))\n' | "$BIN" repl --trace 2>&1)
+expect "$trace" "[trace 1] -> triple(n=4)"
+expect "$trace" "[trace 1] <- triple = 12"
+
+echo "--- Name patterns" >&2
+trace=$("$BIN" --trace='d*' app.franz 2>&1 >/dev/null)
+expect "$trace" "[trace 1] -> double(n=5)"
+reject "$trace" "quad"
+reject "$trace" "greet"
+trace=$("$BIN" --trace='lambda@*' app.franz 2>&1 >/dev/null)
+expect "$(grep -c '^\[trace 1\] -> lambda@13(v=' <<< "$trace")" "2"
+reject "$trace" "double"
+
+echo "--- Cached builds keep the pattern apart" >&2
+trace=$("$BIN" --trace=greet app.franz 2>&1 >/dev/null)
+expect "$trace" "[trace 1] -> greet(name=ada)"
+reject "$trace" "double"
+trace=$("$BIN" app.franz 2>&1 >/dev/null)
+reject "$trace" "[trace"
+
+echo "--- Errors" >&2
+output=$("$BIN" --trace= app.franz 2>&1 || true)
+expect "$output" "Error: Option '--trace=' requires a function name pattern."
+
+echo "All trace smoke tests passed."
@@ -7,6 +7,7 @@
 #include "types.h"
 #include "typeinfer.h"
 #include "typecheck.h"
+#include "diagnostics/diagnostic.h"
 #include "assert_types.h"
 
 int franz_assert_types(const char *code, long fileLength, int verbose) {
@@ -16,6 +17,13 @@ int franz_assert_types(const char *code, long fileLength, int verbose) {
   //  Array-based parsing
   AstNode *ast = parseProgram(tokens);
 
+  if (Diagnostic_count() > 0) {
+    Diagnostic_flush(stdout);
+    if (ast) AstNode_free(ast);
+    TokenArray_free(tokens);
+    return 0;
+  }
+
   // Free tokens after parsing
   TokenArray_free(tokens);
 
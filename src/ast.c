@@ -135,6 +135,14 @@ AstNode* AstNode_new(char* val, enum Opcodes opcode, int lineNumber) {
 
   res->lineNumber = lineNumber;
 
+  //  Span defaults to the whole line until the parser narrows it
+  res->offset = 0;
+  res->column = 0;
+  res->endOffset = 0;
+  res->endLine = lineNumber;
+  res->endColumn = 0;
+  res->sourceId = -1;
+
   //  Initialize free variables
   res->freeVars = NULL;
   res->freeVarsCount = 0;
@@ -181,5 +189,13 @@ AstNode* AstNode_copy(AstNode *p_head, int depth) {
   p_res->var_offset = p_head->var_offset;
   p_res->var_depth = p_head->var_depth;
 
+  //  Copy source span
+  p_res->offset = p_head->offset;
+  p_res->column = p_head->column;
+  p_res->endOffset = p_head->endOffset;
+  p_res->endLine = p_head->endLine;
+  p_res->endColumn = p_head->endColumn;
+  p_res->sourceId = p_head->sourceId;
+
   return p_res;
 }
@@ -31,6 +31,15 @@ typedef struct AstNode {
   enum Opcodes opcode;
   char *val;
   int lineNumber;
+
+  //  Source span for diagnostics (set by the parser from the node's first and last token)
+  int offset;            // Byte offset of the first token
+  int column;            // 1-based column, 0 when only the line is known
+  int endOffset;         // Byte offset one past the last token
+  int endLine;
+  int endColumn;         // Column one past the last token
+  int sourceId;          // Diagnostic source the node was parsed from
+
   char **freeVars;       //  Array of free variable names (for OP_FUNCTION)
   int freeVarsCount;     //  Number of free variables
 
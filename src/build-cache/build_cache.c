@@ -0,0 +1,425 @@
+#include "build_cache.h"
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <stdint.h>
+#include <limits.h>
+#include <errno.h>
+#include <fcntl.h>
+#include <ftw.h>
+#include <dirent.h>
+#include <utime.h>
+#include <unistd.h>
+#include <sys/file.h>
+#include <sys/stat.h>
+#include "../toolchain/toolchain.h"
+
+#ifndef PATH_MAX
+#define PATH_MAX 4096
+#endif
+
+// Resolved once per process
+static char g_cache_root[PATH_MAX];
+static int g_cache_root_resolved = 0;
+
+//  mkdir -p with mode 0700 (cache contents are per-user)
+static int makeDirs(const char *path) {
+  char partial[PATH_MAX];
+  snprintf(partial, sizeof(partial), "%s", path);
+
+  for (char *p = partial + 1; *p; p++) {
+    if (*p != '/') continue;
+    *p = '\0';
+    if (mkdir(partial, 0700) != 0 && errno != EEXIST) return -1;
+    *p = '/';
+  }
+  if (mkdir(partial, 0700) != 0 && errno != EEXIST) return -1;
+  return 0;
+}
+
+const char *BuildCache_root(void) {
+  if (g_cache_root_resolved) return g_cache_root[0] ? g_cache_root : NULL;
+  g_cache_root_resolved = 1;
+
+  const char *override = getenv("FRANZ_CACHE_DIR");
+  const char *xdg = getenv("XDG_CACHE_HOME");
+  const char *home = getenv("HOME");
+
+  if (override != NULL && override[0] != '\0') {
+    snprintf(g_cache_root, sizeof(g_cache_root), "%s", override);
+  } else if (xdg != NULL && xdg[0] == '/') {
+    snprintf(g_cache_root, sizeof(g_cache_root), "%s/franz", xdg);
+  } else if (home != NULL && home[0] != '\0') {
+    snprintf(g_cache_root, sizeof(g_cache_root), "%s/.cache/franz", home);
+  } else {
+    g_cache_root[0] = '\0';
+    return NULL;
+  }
+
+  if (makeDirs(g_cache_root) != 0) {
+    g_cache_root[0] = '\0';
+    return NULL;
+  }
+  return g_cache_root;
+}
+
+int BuildCache_createWorkDir(char *out, size_t outSize) {
+  const char *tmp = getenv("TMPDIR");
+  if (tmp == NULL || tmp[0] == '\0') tmp = "/tmp";
+
+  snprintf(out, outSize, "%s/franz-build-XXXXXX", tmp);
+  if (mkdtemp(out) == NULL) {
+    fprintf(stderr, "ERROR: Failed to create build directory in %s: %s\n", tmp, strerror(errno));
+    return -1;
+  }
+  return 0;
+}
+
+static int removeEntry(const char *path, const struct stat *sb, int typeflag, struct FTW *ftwbuf) {
+  (void)sb;
+  (void)ftwbuf;
+  return typeflag == FTW_DP ? rmdir(path) : unlink(path);
+}
+
+void BuildCache_removeDir(const char *path) {
+  nftw(path, removeEntry, 16, FTW_DEPTH | FTW_PHYS);
+}
+
+//  FNV-1a: cache directories and programs are keyed by hashes of their inputs
+#define FNV_OFFSET_BASIS 14695981039346656037ULL
+
+static uint64_t hashBytes(uint64_t hash, const void *data, size_t length) {
+  const unsigned char *bytes = (const unsigned char *)data;
+  for (size_t i = 0; i < length; i++) {
+    hash ^= bytes[i];
+    hash *= 1099511628211ULL;
+  }
+  return hash;
+}
+
+static uint64_t hashString(uint64_t hash, const char *text) {
+  // include the terminator so ("ab", "c") and ("a", "bc") hash differently
+  return hashBytes(hash, text, strlen(text) + 1);
+}
+
+int BuildCache_runtimeDir(const char *cc, const char *ar, const char *root, const char *workDir,
+                          char *out, size_t outSize, int debug) {
+  const char *cacheRoot = BuildCache_root();
+
+  if (cacheRoot == NULL) {
+    // No cache available: build a private copy for this compilation
+    snprintf(out, outSize, "%s/runtime", workDir);
+  } else {
+    char resolvedRoot[PATH_MAX];
+    if (realpath(root, resolvedRoot) == NULL) {
+      snprintf(resolvedRoot, sizeof(resolvedRoot), "%s", root);
+    }
+
+    uint64_t key = FNV_OFFSET_BASIS;
+    key = hashString(key, cc);
+    key = hashString(key, ar);
+    key = hashString(key, resolvedRoot);
+    snprintf(out, outSize, "%s/runtime-%016llx", cacheRoot, (unsigned long long)key);
+  }
+
+  if (makeDirs(out) != 0) {
+    fprintf(stderr, "ERROR: Failed to create runtime directory %s: %s\n", out, strerror(errno));
+    return -1;
+  }
+
+  //  Serialize builds of the same runtime directory across franz processes
+  char lockPath[PATH_MAX];
+  snprintf(lockPath, sizeof(lockPath), "%s/.lock", out);
+  int lockFd = open(lockPath, O_RDWR | O_CREAT, 0600);
+  if (lockFd >= 0) flock(lockFd, LOCK_EX);
+
+  char makeCmd[PATH_MAX * 4];
+  snprintf(makeCmd, sizeof(makeCmd),
+           "make -s -C '%s' -f Makefile.runtime CC='%s' AR='%s' OUT='%s'%s",
+           root, cc, ar, out, debug ? "" : " >/dev/null 2>&1");
+
+  if (debug) {
+    printf("[DEBUG] Runtime directory: %s\n", out);
+    printf("[DEBUG] Runtime build command: %s\n", makeCmd);
+    fflush(stdout);
+  }
+
+  int result = system(makeCmd);
+
+  if (lockFd >= 0) {
+    flock(lockFd, LOCK_UN);
+    close(lockFd);
+  }
+
+  if (result != 0) {
+    fprintf(stderr, "ERROR: Failed to build the Franz runtime in %s\n", out);
+    fprintf(stderr, "Hint: Run \"make -C '%s' -f Makefile.runtime CC='%s' AR='%s' OUT='%s'\" to see the compiler errors.\n",
+            root, cc, ar, out);
+    return -1;
+  }
+  return 0;
+}
+
+//  Imported modules seen while hashing a program (guards against import cycles)
+typedef struct {
+  char **paths;
+  int count;
+} ImportSet;
+
+static int ImportSet_add(ImportSet *set, const char *path) {
+  for (int i = 0; i < set->count; i++) {
+    if (strcmp(set->paths[i], path) == 0) return 0;
+  }
+  set->paths = realloc(set->paths, sizeof(char *) * (set->count + 1));
+  set->paths[set->count++] = strdup(path);
+  return 1;
+}
+
+//  Hash every module a program may import, transitively. Any string literal
+// naming a .franz file counts (use, use_as, use_with, ...): an unrelated match
+// only costs a spurious cache miss. Paths resolve against the working directory,
+// as in the compiler; a missing module is hashed as missing.
+static uint64_t hashImports(uint64_t hash, const char *code, size_t length, ImportSet *seen) {
+  const char *end = code + length;
+  const char *suffix = ".franz\"";
+  size_t suffixLength = strlen(suffix);
+
+  for (const char *p = code; p + suffixLength <= end; p++) {
+    if (memcmp(p, suffix, suffixLength) != 0) continue;
+
+    const char *start = p;
+    while (start > code && start[-1] != '"' && start[-1] != '\n') start--;
+    if (start == code || start[-1] != '"') continue;
+
+    size_t pathLength = (size_t)(p - start) + suffixLength - 1;
+    if (pathLength >= PATH_MAX) continue;
+    char path[PATH_MAX];
+    memcpy(path, start, pathLength);
+    path[pathLength] = '\0';
+
+    if (!ImportSet_add(seen, path)) continue;
+    hash = hashString(hash, path);
+
+    FILE *file = fopen(path, "rb");
+    if (file == NULL) {
+      hash = hashString(hash, "<missing>");
+      continue;
+    }
+
+    char *contents = NULL;
+    size_t contentsLength = 0;
+    char buffer[8192];
+    size_t n;
+    while ((n = fread(buffer, 1, sizeof(buffer), file)) > 0) {
+      contents = realloc(contents, contentsLength + n);
+      memcpy(contents + contentsLength, buffer, n);
+      contentsLength += n;
+    }
+    fclose(file);
+
+    hash = hashBytes(hash, contents, contentsLength);
+    hash = hashImports(hash, contents, contentsLength, seen);
+    free(contents);
+  }
+  return hash;
+}
+
+static uint64_t hashFile(uint64_t hash, const char *path) {
+  FILE *file = fopen(path, "rb");
+  if (file == NULL) return hashString(hash, "<missing>");
+
+  char buffer[8192];
+  size_t n;
+  while ((n = fread(buffer, 1, sizeof(buffer), file)) > 0) {
+    hash = hashBytes(hash, buffer, n);
+  }
+  fclose(file);
+  return hash;
+}
+
+//  Runtime source tree being hashed (nftw callbacks take no user data)
+static uint64_t g_runtime_hash;
+static size_t g_runtime_prefix;
+
+static int hashRuntimeEntry(const char *path, const struct stat *sb, int typeflag, struct FTW *ftwbuf) {
+  (void)sb;
+  (void)ftwbuf;
+  size_t length = strlen(path);
+  if (typeflag != FTW_F || length < 2 || path[length - 2] != '.' ||
+      (path[length - 1] != 'c' && path[length - 1] != 'h')) {
+    return 0;
+  }
+
+  // Per-file hashes are summed so the directory walk order does not matter
+  uint64_t fileHash = hashString(FNV_OFFSET_BASIS, path + g_runtime_prefix);
+  g_runtime_hash += hashFile(fileHash, path);
+  return 0;
+}
+
+//  Hash the runtime sources and headers under root/src, plus Makefile.runtime.
+// The runtime library is rebuilt from these when they change, so they must
+// invalidate programs linked against the old library.
+static uint64_t hashRuntimeSources(uint64_t hash, const char *root) {
+  char path[PATH_MAX];
+  snprintf(path, sizeof(path), "%s/Makefile.runtime", root);
+  hash = hashFile(hash, path);
+
+  snprintf(path, sizeof(path), "%s/src", root);
+  g_runtime_hash = 0;
+  g_runtime_prefix = strlen(root);
+  nftw(path, hashRuntimeEntry, 16, FTW_PHYS);
+  return hashBytes(hash, &g_runtime_hash, sizeof(g_runtime_hash));
+}
+
+//  Identity of the franz executable: path, size and modification time.
+// A rebuilt franz (new compiler) changes it.
+static uint64_t hashExecutable(uint64_t hash) {
+  char exePath[PATH_MAX];
+  struct stat exeStat;
+  if (Toolchain_executablePath(exePath, sizeof(exePath)) && stat(exePath, &exeStat) == 0) {
+    long long identity[2] = { (long long)exeStat.st_size, (long long)exeStat.st_mtime };
+    hash = hashString(hash, exePath);
+    hash = hashBytes(hash, identity, sizeof(identity));
+  }
+  return hash;
+}
+
+//  Hash of the runtime sources, computed once per franz executable and runtime
+// directory and remembered in <cache>/sources/, so cache hits do not
+// read the whole source tree
+static uint64_t runtimeSourcesHash(const char *root) {
+  const char *cacheRoot = BuildCache_root();
+  if (cacheRoot == NULL) return hashRuntimeSources(FNV_OFFSET_BASIS, root);
+
+  char resolvedRoot[PATH_MAX];
+  if (realpath(root, resolvedRoot) == NULL) {
+    snprintf(resolvedRoot, sizeof(resolvedRoot), "%s", root);
+  }
+  uint64_t memoKey = hashString(hashExecutable(FNV_OFFSET_BASIS), resolvedRoot);
+
+  char memoDir[PATH_MAX];
+  char memoPath[PATH_MAX + 32];
+  snprintf(memoDir, sizeof(memoDir), "%s/sources", cacheRoot);
+  snprintf(memoPath, sizeof(memoPath), "%s/%016llx", memoDir, (unsigned long long)memoKey);
+
+  unsigned long long memo;
+  FILE *file = fopen(memoPath, "r");
+  if (file != NULL) {
+    int found = fscanf(file, "%16llx", &memo) == 1;
+    fclose(file);
+    if (found) return (uint64_t)memo;
+  }
+
+  uint64_t hash = hashRuntimeSources(FNV_OFFSET_BASIS, root);
+
+  //  Written under a temporary name and renamed, as cached programs are
+  char memoTemp[PATH_MAX + 64];
+  snprintf(memoTemp, sizeof(memoTemp), "%s.tmp.%d", memoPath, (int)getpid());
+  if (makeDirs(memoDir) == 0 && (file = fopen(memoTemp, "w")) != NULL) {
+    int written = fprintf(file, "%016llx\n", (unsigned long long)hash) > 0;
+    if (fclose(file) != 0 || !written || rename(memoTemp, memoPath) != 0) unlink(memoTemp);
+  }
+  return hash;
+}
+
+void BuildCache_programKey(const char *code, long length, const char *config, const char *runtimeRoot,
+                           char *out, size_t outSize) {
+  uint64_t hash = FNV_OFFSET_BASIS;
+  hash = hashString(hash, config);
+
+  uint64_t runtimeHash = runtimeSourcesHash(runtimeRoot);
+  hash = hashBytes(hash, &runtimeHash, sizeof(runtimeHash));
+
+  //  A rebuilt franz (new compiler) invalidates every cached program
+  hash = hashExecutable(hash);
+
+  hash = hashBytes(hash, code, (size_t)length);
+
+  ImportSet seen = { NULL, 0 };
+  hash = hashImports(hash, code, (size_t)length, &seen);
+  for (int i = 0; i < seen.count; i++) free(seen.paths[i]);
+  free(seen.paths);
+
+  snprintf(out, outSize, "%016llx", (unsigned long long)hash);
+}
+
+int BuildCache_programPath(const char *key, char *out, size_t outSize) {
+  const char *cacheRoot = BuildCache_root();
+  if (cacheRoot == NULL) return -1;
+
+  char programsDir[PATH_MAX];
+  snprintf(programsDir, sizeof(programsDir), "%s/programs", cacheRoot);
+  if (makeDirs(programsDir) != 0) return -1;
+
+  snprintf(out, outSize, "%s/%s", programsDir, key);
+  return 0;
+}
+
+void BuildCache_markUsed(const char *path) {
+  utime(path, NULL);
+}
+
+//  Size limit of programs/ in megabytes, unless FRANZ_CACHE_MAX_SIZE sets another
+#define PROGRAMS_MAX_SIZE_MB 512
+
+typedef struct {
+  char *path;
+  off_t size;
+  time_t mtime;
+} ProgramEntry;
+
+static int compareLastUse(const void *a, const void *b) {
+  time_t left = ((const ProgramEntry *)a)->mtime;
+  time_t right = ((const ProgramEntry *)b)->mtime;
+  return (left > right) - (left < right);
+}
+
+void BuildCache_trimPrograms(const char *keep) {
+  const char *cacheRoot = BuildCache_root();
+  if (cacheRoot == NULL) return;
+
+  long long maxSize = (long long)PROGRAMS_MAX_SIZE_MB * 1024 * 1024;
+  const char *limit = getenv("FRANZ_CACHE_MAX_SIZE");
+  if (limit != NULL && limit[0] != '\0') {
+    char *end;
+    long long megabytes = strtoll(limit, &end, 10);
+    if (*end == '\0' && megabytes >= 0) maxSize = megabytes * 1024 * 1024;
+  }
+
+  char programsDir[PATH_MAX];
+  snprintf(programsDir, sizeof(programsDir), "%s/programs", cacheRoot);
+  DIR *dir = opendir(programsDir);
+  if (dir == NULL) return;
+
+  ProgramEntry *entries = NULL;
+  int count = 0;
+  long long total = 0;
+  struct dirent *dirent;
+  while ((dirent = readdir(dir)) != NULL) {
+    //  Skip . and .. and entries other franz processes are still writing
+    if (dirent->d_name[0] == '.' || strstr(dirent->d_name, ".tmp.") != NULL) continue;
+
+    char path[PATH_MAX + 256];
+    snprintf(path, sizeof(path), "%s/%s", programsDir, dirent->d_name);
+    struct stat st;
+    if (stat(path, &st) != 0 || !S_ISREG(st.st_mode)) continue;
+
+    entries = realloc(entries, sizeof(ProgramEntry) * (count + 1));
+    entries[count].path = strdup(path);
+    entries[count].size = st.st_size;
+    entries[count].mtime = st.st_mtime;
+    count++;
+    total += st.st_size;
+  }
+  closedir(dir);
+
+  //  Least recently used first (cache hits refresh the modification time)
+  qsort(entries, count, sizeof(ProgramEntry), compareLastUse);
+  for (int i = 0; i < count && total > maxSize; i++) {
+    if (keep != NULL && strcmp(entries[i].path, keep) == 0) continue;
+    if (unlink(entries[i].path) == 0) total -= entries[i].size;
+  }
+
+  for (int i = 0; i < count; i++) free(entries[i].path);
+  free(entries);
+}
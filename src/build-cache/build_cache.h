@@ -0,0 +1,116 @@
+#ifndef BUILD_CACHE_H
+#define BUILD_CACHE_H
+
+#include <stddef.h>
+
+/**
+ * Build Directories for Franz
+ *
+ * Every compilation writes its intermediate files (object file, scratch
+ * executable) into a private work directory, so concurrent franz processes
+ * never read or execute each other's output.
+ *
+ * The compiled runtime (libfranz_runtime.a and the objects linked next to it)
+ * is shared through a persistent cache directory:
+ *   $FRANZ_CACHE_DIR, else $XDG_CACHE_HOME/franz, else $HOME/.cache/franz
+ * One subdirectory per C compiler (including its target and sysroot flags),
+ * archiver and runtime source tree. Makefile.runtime
+ * tracks source and header changes, and a lock file serializes concurrent
+ * builds of the same subdirectory.
+ *
+ * Compiled programs are cached in the same directory under programs/<key>,
+ * so running an unchanged program skips lexing, parsing and code generation.
+ * The least recently used programs are evicted past a size limit.
+ */
+
+/**
+ * Get the persistent cache directory, creating it if needed
+ *
+ * @return Directory path, or NULL if no cache directory is available
+ */
+const char *BuildCache_root(void);
+
+/**
+ * Create a private work directory for one compilation
+ *
+ * Created under $TMPDIR (default /tmp) with mode 0700.
+ *
+ * @param out - Buffer receiving the directory path
+ * @param outSize - Size of out
+ * @return 0 on success, -1 on failure (error already printed)
+ */
+int BuildCache_createWorkDir(char *out, size_t outSize);
+
+/**
+ * Remove a directory and everything in it
+ *
+ * @param path - Directory to remove
+ */
+void BuildCache_removeDir(const char *path);
+
+/**
+ * Build the runtime library if its sources changed, and get its directory
+ *
+ * The directory contains libfranz_runtime.a plus number_parse.o,
+ * terminal_runtime.o and llvm_repeat.o. Falls back to building inside
+ * workDir when no cache directory is available.
+ *
+ * @param cc - C compiler used to build the runtime
+ * @param ar - Archiver used to create libfranz_runtime.a
+ * @param root - Directory containing the runtime sources (see Toolchain_runtimeRoot)
+ * @param workDir - Work directory of the current compilation
+ * @param out - Buffer receiving the runtime directory
+ * @param outSize - Size of out
+ * @param debug - Print cache paths and build commands
+ * @return 0 on success, -1 on failure (error already printed)
+ */
+int BuildCache_runtimeDir(const char *cc, const char *ar, const char *root, const char *workDir,
+                          char *out, size_t outSize, int debug);
+
+/**
+ * Compute the compilation cache key of a program
+ *
+ * Hashes the source, every module it imports (transitively), the franz
+ * executable (compiler version), the runtime sources the program is linked
+ * against and config, which must describe all flags and settings that affect
+ * the generated executable.
+ *
+ * @param code - Program source
+ * @param length - Length of code
+ * @param config - Compiler flags and toolchain, e.g. "O2 tco=1 cc=gcc"
+ * @param runtimeRoot - Directory containing the runtime sources (see Toolchain_runtimeRoot)
+ * @param out - Buffer receiving the key (16 hex digits)
+ * @param outSize - Size of out
+ */
+void BuildCache_programKey(const char *code, long length, const char *config, const char *runtimeRoot,
+                           char *out, size_t outSize);
+
+/**
+ * Get the cache path of the executable compiled for a program key
+ *
+ * The file exists only if the program was compiled before.
+ *
+ * @param key - Key from BuildCache_programKey
+ * @param out - Buffer receiving the path
+ * @param outSize - Size of out
+ * @return 0 on success, -1 if no cache directory is available
+ */
+int BuildCache_programPath(const char *key, char *out, size_t outSize);
+
+/**
+ * Record that a cached program was run, so it is evicted last
+ *
+ * @param path - Path from BuildCache_programPath
+ */
+void BuildCache_markUsed(const char *path);
+
+/**
+ * Evict least recently used programs until programs/ fits its size limit
+ *
+ * The limit is 512 MB, or $FRANZ_CACHE_MAX_SIZE megabytes.
+ *
+ * @param keep - Program that is never evicted (the one just published), or NULL
+ */
+void BuildCache_trimPrograms(const char *keep);
+
+#endif
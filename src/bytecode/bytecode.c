@@ -0,0 +1,325 @@
+#include "bytecode.h"
+#include "../diagnostics/error_codes.h"
+#include <stdlib.h>
+#include <string.h>
+
+//  Name, number of 16-bit operands and whether a 32-bit jump target follows
+typedef struct {
+  const char *name;
+  int operands;
+  int jump;
+} OpInfo;
+
+static const OpInfo OPS[BC_OP_COUNT] = {
+  [BC_CONST] = {"CONST", 1, 0},
+  [BC_VOID] = {"VOID", 0, 0},
+  [BC_POP] = {"POP", 0, 0},
+  [BC_PICK] = {"PICK", 1, 0},
+  [BC_GET_SLOT] = {"GET_SLOT", 1, 0},
+  [BC_SET_SLOT] = {"SET_SLOT", 1, 0},
+  [BC_GET_CAPTURE] = {"GET_CAPTURE", 1, 0},
+  [BC_GET_GLOBAL] = {"GET_GLOBAL", 1, 0},
+  [BC_SET_GLOBAL] = {"SET_GLOBAL", 1, 0},
+  [BC_GET_NAMESPACE] = {"GET_NAMESPACE", 1, 0},
+  [BC_MEMBER] = {"MEMBER", 1, 0},
+  [BC_LIST] = {"LIST", 1, 0},
+  [BC_CLOSURE] = {"CLOSURE", 1, 0},
+  [BC_CALL] = {"CALL", 1, 0},
+  [BC_RETURN] = {"RETURN", 0, 0},
+  [BC_JUMP] = {"JUMP", 0, 1},
+  [BC_JUMP_IF_FALSE] = {"JUMP_IF_FALSE", 0, 1},
+  [BC_JUMP_IF_TRUE] = {"JUMP_IF_TRUE", 0, 1},
+  [BC_JUMP_IF_VOID] = {"JUMP_IF_VOID", 0, 1},
+  [BC_UNWIND] = {"UNWIND", 1, 0},
+  [BC_DROP_TO] = {"DROP_TO", 1, 0},
+  [BC_LOOP_INIT] = {"LOOP_INIT", 0, 0},
+  [BC_LOOP_NEXT] = {"LOOP_NEXT", 0, 1},
+  [BC_LOOP_STEP] = {"LOOP_STEP", 0, 0},
+  [BC_ERROR] = {"ERROR", 2, 0},
+};
+
+const char *Bytecode_opName(BytecodeOp op) {
+  return op < BC_OP_COUNT ? OPS[op].name : "?";
+}
+
+int Bytecode_opSize(BytecodeOp op) {
+  if (op >= BC_OP_COUNT) return 0;
+  return 1 + OPS[op].operands * 2 + OPS[op].jump * 4;
+}
+
+int Bytecode_isJump(BytecodeOp op) {
+  return op < BC_OP_COUNT && OPS[op].jump;
+}
+
+BytecodeSpan Bytecode_spanAt(BytecodeProto *proto, int pc) {
+  BytecodeSpan none = {pc, 0, 0, 0, 0};
+
+  // Last span starting at or before pc
+  int low = 0, high = proto->spanCount - 1, found = -1;
+  while (low <= high) {
+    int mid = (low + high) / 2;
+    if (proto->spans[mid].pc <= pc) {
+      found = mid;
+      low = mid + 1;
+    } else {
+      high = mid - 1;
+    }
+  }
+  return found >= 0 ? proto->spans[found] : none;
+}
+
+// ============================================================================
+// Verification
+// ============================================================================
+
+static int isName(BytecodeProto *proto, int k) {
+  return k < proto->constantCount && proto->constants[k]->type == TYPE_STRING;
+}
+
+//  Stack height after the instruction at pc, or -1 if it is malformed.
+// *jump is set to its jump target (if it has one), *next to 0 if control never
+// falls through to the following instruction.
+static int stepHeight(BytecodeProto *proto, int pc, int height, int *jump, int *next) {
+  BytecodeOp op = proto->code[pc];
+  const uint8_t *operands = proto->code + pc + 1;
+  int a = OPS[op].operands > 0 ? Bytecode_readU16(operands) : 0;
+
+  *jump = OPS[op].jump ? Bytecode_readU32(operands + OPS[op].operands * 2) : -1;
+  *next = 1;
+
+  switch (op) {
+    case BC_CONST:
+      return a < proto->constantCount ? height + 1 : -1;
+    case BC_VOID:
+      return height + 1;
+    case BC_POP:
+      return height >= 1 ? height - 1 : -1;
+    case BC_PICK:
+      return height >= a + 1 ? height + 1 : -1;
+    case BC_GET_SLOT:
+      return a < proto->slotCount ? height + 1 : -1;
+    case BC_SET_SLOT:
+      return a < proto->slotCount && height >= 1 ? height - 1 : -1;
+    case BC_GET_CAPTURE:
+      return a < proto->captureCount ? height + 1 : -1;
+    case BC_GET_GLOBAL:
+    case BC_GET_NAMESPACE:
+      return isName(proto, a) ? height + 1 : -1;
+    case BC_SET_GLOBAL:
+      return isName(proto, a) && height >= 1 ? height - 1 : -1;
+    case BC_MEMBER:
+      if (!isName(proto, a) || strchr(*((char **) proto->constants[a]->p_val), '.') == NULL) return -1;
+      return height >= 1 ? height : -1;
+    case BC_LIST:
+      return height >= a ? height - a + 1 : -1;
+    case BC_CLOSURE:
+      return a < proto->protoCount ? height + 1 : -1;
+    case BC_CALL:
+      return height >= a + 1 ? height - a : -1;
+    case BC_RETURN:
+      *next = 0;
+      return height >= 1 ? height - 1 : -1;
+    case BC_JUMP:
+      *next = 0;
+      return height;
+    case BC_JUMP_IF_FALSE:
+    case BC_JUMP_IF_TRUE:
+      return height >= 1 ? height - 1 : -1;
+    case BC_JUMP_IF_VOID:
+      return height >= 1 ? height : -1;
+    case BC_UNWIND:
+      return height >= a + 1 ? a + 1 : -1;
+    case BC_DROP_TO:
+      return height >= a ? a : -1;
+    case BC_LOOP_INIT:
+      return height >= 1 ? height + 1 : -1;
+    case BC_LOOP_NEXT:
+    case BC_LOOP_STEP:
+      return height >= 2 ? height : -1;
+    case BC_ERROR:
+      *next = 0;
+      return a < ERROR_CODE_COUNT && isName(proto, Bytecode_readU16(operands + 2)) ? height : -1;
+    default:
+      return -1;
+  }
+}
+
+//  Record the height an instruction is reached with; -1 if it conflicts
+static int reach(int *heights, int *work, int *workCount, int codeLength, int pc, int height) {
+  if (pc < 0 || pc >= codeLength) return -1;
+  if (heights[pc] == -1) {
+    heights[pc] = height;
+    work[(*workCount)++] = pc;
+    return 0;
+  }
+  return heights[pc] == height ? 0 : -1;
+}
+
+static int verifyCode(BytecodeProto *proto) {
+  if (proto->codeLength <= 0) return -1;
+
+  int *heights = (int *) malloc(sizeof(int) * proto->codeLength);
+  int *work = (int *) malloc(sizeof(int) * proto->codeLength);
+  int workCount = 0;
+  for (int i = 0; i < proto->codeLength; i++) heights[i] = -1;
+
+  int res = reach(heights, work, &workCount, proto->codeLength, 0, 0);
+  proto->maxStack = 0;
+
+  while (res == 0 && workCount > 0) {
+    int pc = work[--workCount];
+    BytecodeOp op = proto->code[pc];
+    int size = Bytecode_opSize(op);
+    if (size == 0 || pc + size > proto->codeLength) {
+      res = -1;
+      break;
+    }
+
+    int jump, next;
+    int height = stepHeight(proto, pc, heights[pc], &jump, &next);
+    if (height < 0) {
+      res = -1;
+      break;
+    }
+    if (height > proto->maxStack) proto->maxStack = height;
+    if (heights[pc] + 1 > proto->maxStack) proto->maxStack = heights[pc] + 1;
+
+    if (OPS[op].jump && reach(heights, work, &workCount, proto->codeLength, jump, height) != 0) res = -1;
+    if (next && reach(heights, work, &workCount, proto->codeLength, pc + size, height) != 0) res = -1;
+  }
+
+  free(heights);
+  free(work);
+  return res;
+}
+
+static int verifyProto(BytecodeProto *proto, BytecodeProto *parent) {
+  if (proto->paramCount < 0 || proto->paramCount > proto->slotCount) return -1;
+
+  for (int i = 0; i < proto->constantCount; i++) {
+    enum Type type = proto->constants[i]->type;
+    if (type != TYPE_INT && type != TYPE_FLOAT && type != TYPE_STRING) return -1;
+  }
+
+  // Captures are taken from the frame the closure is created in
+  if (parent == NULL && proto->captureCount > 0) return -1;
+  for (int i = 0; i < proto->captureCount; i++) {
+    BytecodeCapture *capture = &proto->captures[i];
+    if (capture->kind == CAPTURE_SLOT && (capture->index < 0 || capture->index >= parent->slotCount)) return -1;
+    if (capture->kind == CAPTURE_OUTER && (capture->index < 0 || capture->index >= parent->captureCount)) return -1;
+    if (capture->kind != CAPTURE_SLOT && capture->kind != CAPTURE_OUTER && capture->kind != CAPTURE_GLOBAL) return -1;
+  }
+
+  if (verifyCode(proto) != 0) return -1;
+
+  for (int i = 0; i < proto->protoCount; i++) {
+    if (verifyProto(proto->protos[i], proto) != 0) return -1;
+  }
+  return 0;
+}
+
+int Bytecode_verify(BytecodeProto *proto) {
+  return verifyProto(proto, NULL);
+}
+
+// ============================================================================
+// Disassembly
+// ============================================================================
+
+static void printConstant(FILE *out, Generic *constant) {
+  switch (constant->type) {
+    case TYPE_INT: fprintf(out, "%lld", (long long) *((int64_t *) constant->p_val)); break;
+    case TYPE_FLOAT: fprintf(out, "%f", *((double *) constant->p_val)); break;
+    case TYPE_STRING: fprintf(out, "\"%s\"", *((char **) constant->p_val)); break;
+    default: fprintf(out, "?"); break;
+  }
+}
+
+static void disassemble(FILE *out, BytecodeProto *proto, const char *path) {
+  static const char *captureKinds[] = {"slot", "capture", "global"};
+
+  fprintf(out, "== %s: %d params, %d slots, %d captures, stack %d ==\n",
+          path, proto->paramCount, proto->slotCount, proto->captureCount, proto->maxStack);
+  for (int i = 0; i < proto->slotCount; i++) {
+    fprintf(out, "  slot %d: %s\n", i, proto->slotNames[i]);
+  }
+  for (int i = 0; i < proto->captureCount; i++) {
+    BytecodeCapture *capture = &proto->captures[i];
+    fprintf(out, "  capture %d: %s (%s", i, capture->name, captureKinds[capture->kind]);
+    if (capture->kind != CAPTURE_GLOBAL) fprintf(out, " %d", capture->index);
+    fprintf(out, ")\n");
+  }
+
+  int line = 0;  // no line before the first span
+  for (int pc = 0; pc < proto->codeLength; pc += Bytecode_opSize(proto->code[pc])) {
+    BytecodeOp op = proto->code[pc];
+    if (Bytecode_opSize(op) == 0) break;
+
+    int spanLine = Bytecode_spanAt(proto, pc).line;
+    if (spanLine != line) {
+      fprintf(out, "  %5d %4d  ", pc, spanLine);
+      line = spanLine;
+    } else {
+      fprintf(out, "  %5d    |  ", pc);
+    }
+    fprintf(out, "%s", OPS[op].name);
+
+    const uint8_t *operands = proto->code + pc + 1;
+    for (int i = 0; i < OPS[op].operands; i++) {
+      fprintf(out, " %d", Bytecode_readU16(operands + i * 2));
+    }
+    if (OPS[op].jump) fprintf(out, " -> %d", Bytecode_readU32(operands + OPS[op].operands * 2));
+
+    int a = OPS[op].operands > 0 ? Bytecode_readU16(operands) : 0;
+    switch (op) {
+      case BC_CONST:
+      case BC_GET_GLOBAL:
+      case BC_SET_GLOBAL:
+      case BC_GET_NAMESPACE:
+      case BC_MEMBER:
+        fprintf(out, "  ; ");
+        printConstant(out, proto->constants[a]);
+        break;
+      case BC_GET_SLOT:
+      case BC_SET_SLOT:
+        fprintf(out, "  ; %s", proto->slotNames[a]);
+        break;
+      case BC_GET_CAPTURE:
+        fprintf(out, "  ; %s", proto->captures[a].name);
+        break;
+      case BC_CLOSURE:
+        fprintf(out, "  ; %s", proto->protos[a]->name);
+        break;
+      default:
+        break;
+    }
+    fprintf(out, "\n");
+  }
+
+  for (int i = 0; i < proto->protoCount; i++) {
+    char nested[512];
+    snprintf(nested, sizeof(nested), "%s/%s", path, proto->protos[i]->name);
+    disassemble(out, proto->protos[i], nested);
+  }
+}
+
+void Bytecode_disassemble(FILE *out, BytecodeProto *proto) {
+  disassemble(out, proto, proto->name);
+}
+
+void Bytecode_free(BytecodeProto *proto) {
+  if (proto == NULL) return;
+
+  free(proto->name);
+  for (int i = 0; i < proto->slotCount; i++) free(proto->slotNames[i]);
+  free(proto->slotNames);
+  for (int i = 0; i < proto->captureCount; i++) free(proto->captures[i].name);
+  free(proto->captures);
+  for (int i = 0; i < proto->constantCount; i++) Generic_release(proto->constants[i]);
+  free(proto->constants);
+  free(proto->code);
+  free(proto->spans);
+  for (int i = 0; i < proto->protoCount; i++) Bytecode_free(proto->protos[i]);
+  free(proto->protos);
+  free(proto);
+}
@@ -0,0 +1,251 @@
+#ifndef BYTECODE_H
+#define BYTECODE_H
+
+#include <stdint.h>
+#include <stdio.h>
+#include "../ast.h"
+#include "../scope.h"
+#include "../generic.h"
+
+/**
+ * Bytecode backend (franz --bytecode, --emit=franzc)
+ *
+ * Programs are compiled from the AST into one prototype per function and
+ * run on a stack VM. Like the interpreter, the VM works on the runtime in
+ * stdlib.c (Generics, Scopes, applyFunc), so it needs neither LLVM nor a C
+ * toolchain; unlike it, names are resolved once, by compile_optimize:
+ *
+ * - Top-level names live in the global Scope (or the module's namespace)
+ *   and are looked up by name.
+ * - Parameters and variables of a function are frame slots, numbered by
+ *   compile_optimize's offsets.
+ * - Names of enclosing functions are captured when a closure is created
+ *   (its snapshot), through the enclosing closure's captures if they are
+ *   more than one function out. Top-level names are captured by name.
+ *
+ * Blocks passed to if, when, unless, cond, loop and while run inline in the
+ * enclosing frame; `<-`, break and continue compile to jumps. Control flow
+ * follows the interpreter (see interpret/interpret.h).
+ *
+ * Instructions are one opcode byte followed by its operands: 16-bit
+ * indices, or a 32-bit code offset for jumps, little-endian. Operand stack
+ * heights (UNWIND, DROP_TO) count from the first value above the slots.
+ */
+
+//  Instructions (operands in brackets)
+typedef enum {
+  BC_CONST,          // [k]: push constant k
+  BC_VOID,           // push void
+  BC_POP,            // drop the top value
+  BC_PICK,           // [n]: push the value n below the top again (0 = the top)
+  BC_GET_SLOT,       // [s]: push slot s (unset: global of the slot's name)
+  BC_SET_SLOT,       // [s]: pop into slot s
+  BC_GET_CAPTURE,    // [c]: push capture c (missing: global of its name)
+  BC_GET_GLOBAL,     // [k]: push the variable named by constant k
+  BC_SET_GLOBAL,     // [k]: pop into the variable named by constant k
+  BC_GET_NAMESPACE,  // [k]: like GET_GLOBAL, but void if it does not exist
+  BC_MEMBER,         // [k]: replace a namespace by its member; k names "ns.member"
+  BC_LIST,           // [n]: replace the top n values by a list of them
+  BC_CLOSURE,        // [p]: push a closure of nested prototype p
+  BC_CALL,           // [n]: call the function below the top n values with them
+  BC_RETURN,         // return the top value
+  BC_JUMP,           // [t]: continue at t
+  BC_JUMP_IF_FALSE,  // [t]: pop; jump if it is not truthy
+  BC_JUMP_IF_TRUE,   // [t]: pop; jump if it is truthy
+  BC_JUMP_IF_VOID,   // [t]: jump if the top value is void (kept)
+  BC_UNWIND,         // [h]: keep the top value, drop the values below it down to height h
+  BC_DROP_TO,        // [h]: drop values down to height h
+  BC_LOOP_INIT,      // check the loop count on top and push index 0
+  BC_LOOP_NEXT,      // [t]: jump if the index (top) reached the count (below it)
+  BC_LOOP_STEP,      // replace the index by index + 1
+  BC_ERROR,          // [code, k]: report runtime error code with message k and exit
+  BC_OP_COUNT
+} BytecodeOp;
+
+//  Where a closure's capture comes from when it is created
+typedef enum {
+  CAPTURE_SLOT,    // slot of the enclosing function's frame
+  CAPTURE_OUTER,   // capture of the enclosing closure
+  CAPTURE_GLOBAL   // top-level variable, by name
+} BytecodeCaptureKind;
+
+typedef struct BytecodeCapture {
+  BytecodeCaptureKind kind;
+  int index;     // slot or capture index (unused for CAPTURE_GLOBAL)
+  char *name;    // variable name, for lookups and errors
+} BytecodeCapture;
+
+//  Source span of the instructions from pc on (runtime errors, native calls)
+typedef struct BytecodeSpan {
+  int pc;
+  int line;
+  int column;
+  int endLine;
+  int endColumn;
+} BytecodeSpan;
+
+//  A compiled function, or the program or module itself (the top level)
+typedef struct BytecodeProto {
+  char *name;                   // "<toplevel>", the assigned name or lambda@line
+  int sourceId;                 // diagnostics source of the spans (-1 = primary)
+  int paramCount;
+  int slotCount;                // parameters first, then local variables
+  char **slotNames;
+  int captureCount;
+  BytecodeCapture *captures;
+  int constantCount;
+  Generic **constants;          // ints, floats and strings (values and names)
+  uint8_t *code;
+  int codeLength;
+  BytecodeSpan *spans;          // sorted by pc
+  int spanCount;
+  struct BytecodeProto **protos;  // nested functions (BC_CLOSURE)
+  int protoCount;
+  int maxStack;                 // operand stack height the code needs (set by Bytecode_verify)
+} BytecodeProto;
+
+//  A function value of the VM (Generic type TYPE_BYTECODE_CLOSURE)
+typedef struct BytecodeClosure {
+  int refCount;        // Generic_copy shares closures
+  BytecodeProto *proto;
+  int captureCount;
+  Generic **captures;  // values captured when created (NULL = not defined then)
+  Scope *scope;        // global scope or module namespace it was created in
+} BytecodeClosure;
+
+/* Format (bytecode.c) */
+
+//  Operands are little-endian
+static inline int Bytecode_readU16(const uint8_t *p) {
+  return p[0] | (p[1] << 8);
+}
+
+static inline int Bytecode_readU32(const uint8_t *p) {
+  return (int) ((uint32_t) p[0] | ((uint32_t) p[1] << 8) | ((uint32_t) p[2] << 16) | ((uint32_t) p[3] << 24));
+}
+
+/**
+ * Name of an instruction, e.g. "GET_SLOT"
+ */
+const char *Bytecode_opName(BytecodeOp op);
+
+/**
+ * Size of an instruction in bytes, including the opcode (0 for invalid opcodes)
+ */
+int Bytecode_opSize(BytecodeOp op);
+
+/**
+ * 1 if the instruction's operand is a 32-bit jump target
+ */
+int Bytecode_isJump(BytecodeOp op);
+
+/**
+ * Span of the instruction at pc
+ */
+BytecodeSpan Bytecode_spanAt(BytecodeProto *proto, int pc);
+
+/**
+ * Check that code, operands and jumps are well-formed and compute maxStack
+ *
+ * Every path to an instruction must reach it with the same stack height.
+ * Runs on compiled and loaded prototypes and on their nested ones, so the
+ * VM can trust the code it runs.
+ *
+ * @return 0 on success, -1 if the prototype is malformed
+ */
+int Bytecode_verify(BytecodeProto *proto);
+
+/**
+ * Print a prototype and its nested prototypes as readable instructions (-d)
+ */
+void Bytecode_disassemble(FILE *out, BytecodeProto *proto);
+
+/**
+ * Free a prototype with its constants and nested prototypes
+ */
+void Bytecode_free(BytecodeProto *proto);
+
+/* Compiler (bytecode_compile.c) */
+
+/**
+ * Compile a program or module
+ *
+ * Runs compile_optimize on the AST for the slot offsets of its names.
+ * Misused forms (break outside a loop, wrong argument counts) compile to
+ * BC_ERROR, so they fail when reached, as in the interpreter.
+ *
+ * @param program - Program root (OP_STATEMENT)
+ * @return Prototype of the top level
+ */
+BytecodeProto *Bytecode_compile(AstNode *program);
+
+/* .franzc files (bytecode_file.c) */
+
+/**
+ * 1 if data starts with the .franzc signature
+ */
+int Bytecode_isImage(const char *data, long length);
+
+/**
+ * Write a compiled program to a .franzc file
+ *
+ * @return 0 on success, -1 if the file could not be written
+ */
+int Bytecode_write(BytecodeProto *proto, const char *path);
+
+/**
+ * Load a program from the contents of a .franzc file
+ *
+ * Reports what is wrong with the file on stderr.
+ *
+ * @param data - File contents
+ * @param length - Length of data
+ * @param path - File name for error messages
+ * @return Verified prototype of the top level, NULL on error
+ */
+BytecodeProto *Bytecode_read(const char *data, long length, const char *path);
+
+/* VM (bytecode_vm.c) */
+
+/**
+ * Run a program in the global scope
+ *
+ * From then on the VM is active: applyFunc calls closures and use, use_as
+ * and use_with run modules through it.
+ *
+ * @param proto - Compiled program
+ * @param p_global - Global scope from newGlobal
+ * @return Exit code of the program
+ */
+int BytecodeVM_run(BytecodeProto *proto, Scope *p_global);
+
+/**
+ * 1 once BytecodeVM_run has started a program
+ */
+int BytecodeVM_active(void);
+
+/**
+ * Call a closure of the VM from the runtime (applyFunc)
+ *
+ * Follows applyFunc's rules: temporary arguments and functions are freed,
+ * and the result is a temporary.
+ */
+Generic *BytecodeVM_call(Generic *func, Scope *p_scope, Generic *args[], int length, int lineNumber);
+
+/**
+ * Compile and run a module in p_scope (use, use_as, use_with)
+ *
+ * @return Value of the module (a temporary)
+ */
+Generic *BytecodeVM_runModule(AstNode *module, Scope *p_scope);
+
+/**
+ * Share and release closures (Generic_copy, Generic_free)
+ *
+ * TYPE_BYTECODE_CLOSURE values of the interpreter and of compiled programs
+ * hold other structures, so both do nothing unless the VM is active.
+ */
+void BytecodeClosure_retain(void *closure);
+void BytecodeClosure_free(void *closure);
+
+#endif
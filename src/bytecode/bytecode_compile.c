@@ -0,0 +1,733 @@
+#include "bytecode.h"
+#include <stdlib.h>
+#include <string.h>
+#include "../optimization/compile.h"
+#include "../number-formats/number_parse.h"
+#include "../string.h"
+#include "../diagnostics/error_codes.h"
+
+//  Jumps whose target is not known yet
+typedef struct {
+  int *pcs;
+  int count;
+  int capacity;
+} JumpList;
+
+//  An inline block: `<-` in it jumps to its end with the block's value
+typedef struct Block {
+  int height;      // operand stack height where the block's value goes
+  JumpList ends;
+  struct Block *outer;
+} Block;
+
+//  A loop: break and `<- value` leave it with a value, continue starts the next iteration
+typedef struct Loop {
+  int height;      // where the loop's value goes
+  int bodyHeight;  // height in the body (`loop` keeps its count and index below it)
+  JumpList exits;
+  JumpList continues;
+  Block *block;    // innermost block around the loop
+  struct Loop *outer;
+} Loop;
+
+//  The function being compiled
+typedef struct FuncState {
+  BytecodeProto *proto;
+  struct FuncState *parent;
+  int level;        // functions around it; 0 = top level, whose names are globals
+  int height;       // operand stack height after the code emitted so far
+  int codeCapacity;
+  int constantCapacity;
+  int spanCapacity;
+  int protoCapacity;
+  Block *block;     // innermost inline block
+  Loop *loop;       // innermost loop
+} FuncState;
+
+static void compileNode(FuncState *fs, AstNode *node);
+static void compileEffect(FuncState *fs, AstNode *node);
+static void compileFunction(FuncState *fs, AstNode *node, const char *name);
+
+static void *grow(void *items, int *capacity, int needed, size_t size) {
+  if (needed <= *capacity) return items;
+  while (*capacity < needed) *capacity = *capacity > 0 ? *capacity * 2 : 16;
+  return realloc(items, size * *capacity);
+}
+
+static BytecodeProto *newProto(const char *name, int sourceId) {
+  BytecodeProto *proto = (BytecodeProto *) calloc(1, sizeof(BytecodeProto));
+  proto->name = strdup(name);
+  proto->sourceId = sourceId;
+  return proto;
+}
+
+// ============================================================================
+// Emitting code
+// ============================================================================
+
+static void emitByte(FuncState *fs, int byte) {
+  BytecodeProto *proto = fs->proto;
+  proto->code = grow(proto->code, &fs->codeCapacity, proto->codeLength + 1, sizeof(uint8_t));
+  proto->code[proto->codeLength++] = (uint8_t) byte;
+}
+
+static void emitU16(FuncState *fs, int value) {
+  if (value < 0 || value > 0xFFFF) {
+    fprintf(stderr, "ERROR: Function '%s' is too large to compile to bytecode.\n", fs->proto->name);
+    exit(1);
+  }
+  emitByte(fs, value & 0xFF);
+  emitByte(fs, (value >> 8) & 0xFF);
+}
+
+static void emitU32(FuncState *fs, int value) {
+  for (int i = 0; i < 4; i++) emitByte(fs, (value >> (i * 8)) & 0xFF);
+}
+
+//  An instruction without operands, changing the stack height by delta
+static void emit(FuncState *fs, BytecodeOp op, int delta) {
+  emitByte(fs, op);
+  fs->height += delta;
+}
+
+static void emitArg(FuncState *fs, BytecodeOp op, int operand, int delta) {
+  emitByte(fs, op);
+  emitU16(fs, operand);
+  fs->height += delta;
+}
+
+//  A jump to be patched; returns the offset of its target operand
+static int emitJump(FuncState *fs, BytecodeOp op, int delta) {
+  emitByte(fs, op);
+  int at = fs->proto->codeLength;
+  emitU32(fs, 0);
+  fs->height += delta;
+  return at;
+}
+
+static void patch(FuncState *fs, int at, int target) {
+  for (int i = 0; i < 4; i++) fs->proto->code[at + i] = (uint8_t) ((target >> (i * 8)) & 0xFF);
+}
+
+static void emitJumpTo(FuncState *fs, BytecodeOp op, int target, int delta) {
+  patch(fs, emitJump(fs, op, delta), target);
+}
+
+static void addJump(JumpList *list, int at) {
+  list->pcs = grow(list->pcs, &list->capacity, list->count + 1, sizeof(int));
+  list->pcs[list->count++] = at;
+}
+
+//  Point the jumps of a list here and free it
+static void patchAll(FuncState *fs, JumpList *list) {
+  for (int i = 0; i < list->count; i++) patch(fs, list->pcs[i], fs->proto->codeLength);
+  free(list->pcs);
+}
+
+//  The instructions from here on report errors at node
+static void mark(FuncState *fs, AstNode *node) {
+  BytecodeProto *proto = fs->proto;
+  BytecodeSpan span = {proto->codeLength, node->lineNumber, node->column, node->endLine, node->endColumn};
+
+  if (proto->spanCount > 0) {
+    BytecodeSpan *last = &proto->spans[proto->spanCount - 1];
+    if (last->line == span.line && last->column == span.column &&
+        last->endLine == span.endLine && last->endColumn == span.endColumn) return;
+    if (last->pc == span.pc) {
+      *last = span;
+      return;
+    }
+  }
+
+  proto->spans = grow(proto->spans, &fs->spanCapacity, proto->spanCount + 1, sizeof(BytecodeSpan));
+  proto->spans[proto->spanCount++] = span;
+}
+
+//  Report a runtime error if this point is reached (the node is misused)
+static void emitError(FuncState *fs, AstNode *node, ErrorCode code, const char *format, const char *name);
+
+// ============================================================================
+// Constants, slots and captures
+// ============================================================================
+
+static int addConstant(FuncState *fs, Generic *value) {
+  BytecodeProto *proto = fs->proto;
+  for (int i = 0; i < proto->constantCount; i++) {
+    Generic *constant = proto->constants[i];
+    if (constant->type != value->type) continue;
+    int same = value->type == TYPE_INT ? *((int64_t *) constant->p_val) == *((int64_t *) value->p_val)
+             : value->type == TYPE_FLOAT ? memcmp(constant->p_val, value->p_val, sizeof(double)) == 0
+             : strcmp(*((char **) constant->p_val), *((char **) value->p_val)) == 0;
+    if (same) {
+      Generic_release(value);
+      return i;
+    }
+  }
+
+  proto->constants = grow(proto->constants, &fs->constantCapacity, proto->constantCount + 1, sizeof(Generic *));
+  proto->constants[proto->constantCount] = value;
+  return proto->constantCount++;
+}
+
+static int nameConstant(FuncState *fs, const char *name) {
+  return addConstant(fs, Generic_fromString(name));
+}
+
+static void emitInt(FuncState *fs, int64_t value) {
+  emitArg(fs, BC_CONST, addConstant(fs, Generic_fromInt(value)), 1);
+}
+
+static void emitError(FuncState *fs, AstNode *node, ErrorCode code, const char *format, const char *name) {
+  char message[512];
+  snprintf(message, sizeof(message), format, name);
+
+  mark(fs, node);
+  emitByte(fs, BC_ERROR);
+  emitU16(fs, code);
+  emitU16(fs, nameConstant(fs, message));
+}
+
+//  Make sure slot offset exists; the first name stored in a slot names it
+static void useSlot(FuncState *fs, int offset, const char *name) {
+  BytecodeProto *proto = fs->proto;
+  if (offset >= proto->slotCount) {
+    proto->slotNames = realloc(proto->slotNames, sizeof(char *) * (offset + 1));
+    for (int i = proto->slotCount; i <= offset; i++) proto->slotNames[i] = NULL;
+    proto->slotCount = offset + 1;
+  }
+  if (proto->slotNames[offset] == NULL) proto->slotNames[offset] = strdup(name);
+}
+
+static int addCapture(FuncState *fs, BytecodeCaptureKind kind, int index, const char *name) {
+  BytecodeProto *proto = fs->proto;
+  for (int i = 0; i < proto->captureCount; i++) {
+    BytecodeCapture *capture = &proto->captures[i];
+    if (capture->kind == kind && strcmp(capture->name, name) == 0 &&
+        (kind == CAPTURE_GLOBAL || capture->index == index)) return i;
+  }
+
+  proto->captures = realloc(proto->captures, sizeof(BytecodeCapture) * (proto->captureCount + 1));
+  proto->captures[proto->captureCount].kind = kind;
+  proto->captures[proto->captureCount].index = index;
+  proto->captures[proto->captureCount].name = strdup(name);
+  return proto->captureCount++;
+}
+
+//  Capture of a name bound depth functions out (at the top level when that is
+// as far out as the function is nested); the functions in between capture it too
+static int resolveCapture(FuncState *fs, const char *name, int depth, int offset) {
+  FuncState *parent = fs->parent;
+  if (parent->level == 0) return addCapture(fs, CAPTURE_GLOBAL, 0, name);
+  if (depth == 1) {
+    useSlot(parent, offset, name);
+    return addCapture(fs, CAPTURE_SLOT, offset, name);
+  }
+  return addCapture(fs, CAPTURE_OUTER, resolveCapture(parent, name, depth - 1, offset), name);
+}
+
+//  Push the variable name, resolved by compile_optimize to offset and depth
+static void compileLoad(FuncState *fs, AstNode *node, const char *name, BytecodeOp globalOp) {
+  mark(fs, node);
+  if (fs->level > 0 && node->var_offset >= 0 && node->var_depth == 0) {
+    useSlot(fs, node->var_offset, name);
+    emitArg(fs, BC_GET_SLOT, node->var_offset, 1);
+  } else if (fs->level > 0 && node->var_offset >= 0 && node->var_depth > 0 && node->var_depth <= fs->level) {
+    emitArg(fs, BC_GET_CAPTURE, resolveCapture(fs, name, node->var_depth, node->var_offset), 1);
+  } else {
+    emitArg(fs, globalOp, nameConstant(fs, name), 1);
+  }
+}
+
+//  Pop the top value into the variable assigned to (or bound as a block parameter)
+static void compileStore(FuncState *fs, AstNode *target) {
+  if (fs->level > 0 && target->var_offset >= 0 && target->var_depth == 0) {
+    useSlot(fs, target->var_offset, target->val);
+    emitArg(fs, BC_SET_SLOT, target->var_offset, -1);
+  } else {
+    mark(fs, target);
+    emitArg(fs, BC_SET_GLOBAL, nameConstant(fs, target->val), -1);
+  }
+}
+
+// ============================================================================
+// Statements and blocks
+// ============================================================================
+
+static int countParams(AstNode *fn) {
+  int count = 0;
+  while (count < fn->childCount && fn->children[count]->opcode == OP_IDENTIFIER) count++;
+  return count;
+}
+
+//  Children of a node from first on; leaves the value of the last one
+static void compileSequence(FuncState *fs, AstNode *node, int first) {
+  if (first >= node->childCount) {
+    emit(fs, BC_VOID, 1);
+    return;
+  }
+  for (int i = first; i < node->childCount - 1; i++) {
+    compileEffect(fs, node->children[i]);
+  }
+  compileNode(fs, node->children[node->childCount - 1]);
+}
+
+//  Jump to the end of the innermost block with the value on top
+static void jumpToBlockEnd(FuncState *fs, Block *block) {
+  if (fs->height != block->height + 1) emitArg(fs, BC_UNWIND, block->height, 0);
+  addJump(&block->ends, emitJump(fs, BC_JUMP, 0));
+}
+
+//  A branch or loop body. A block literal runs inline with its parameters
+// bound to the argCount values on top of the stack; anything else is
+// evaluated, and called with them if there are any.
+static void compileBlock(FuncState *fs, AstNode *node, int argCount) {
+  int start = fs->height;
+
+  if (node->opcode != OP_FUNCTION) {
+    compileNode(fs, node);
+    if (argCount > 0) {
+      for (int i = 0; i < argCount; i++) emitArg(fs, BC_PICK, argCount, 1);
+      mark(fs, node);
+      emitArg(fs, BC_CALL, argCount, -argCount);
+    }
+    fs->height = start + 1;
+    return;
+  }
+
+  int paramCount = countParams(node);
+  for (int i = 0; i < paramCount && i < argCount; i++) {
+    emitArg(fs, BC_PICK, argCount - 1 - i, 1);
+    compileStore(fs, node->children[i]);
+  }
+
+  Block block = {start, {NULL, 0, 0}, fs->block};
+  fs->block = &block;
+  compileSequence(fs, node, paramCount);
+  fs->block = block.outer;
+  patchAll(fs, &block.ends);
+  fs->height = start + 1;
+}
+
+//  name = value; functions are named after the variable they are assigned to
+static void compileAssignment(FuncState *fs, AstNode *node) {
+  AstNode *value = node->children[1];
+  if (value->opcode == OP_FUNCTION) {
+    compileFunction(fs, value, node->children[0]->val);
+  } else {
+    compileNode(fs, value);
+  }
+  compileStore(fs, node->children[0]);
+}
+
+//  A statement whose value is not used
+static void compileEffect(FuncState *fs, AstNode *node) {
+  if (node->opcode == OP_ASSIGNMENT && node->childCount >= 2) {
+    compileAssignment(fs, node);
+    return;
+  }
+  compileNode(fs, node);
+  emit(fs, BC_POP, -1);
+}
+
+//  Drop the iteration's values and start the next one
+static void emitContinue(FuncState *fs) {
+  emitArg(fs, BC_DROP_TO, fs->loop->bodyHeight, 0);
+  addJump(&fs->loop->continues, emitJump(fs, BC_JUMP, 0));
+}
+
+//  `<- value`. Inside a loop, returning void or a literal 0 continues the loop.
+static void compileReturn(FuncState *fs, AstNode *node) {
+  int start = fs->height;
+  AstNode *valueNode = node->childCount > 0 ? node->children[0] : NULL;
+  if (valueNode != NULL) {
+    compileNode(fs, valueNode);
+  } else {
+    emit(fs, BC_VOID, 1);
+  }
+
+  if (fs->loop != NULL) {
+    //  Void or 0 ends the innermost block in the loop (the iteration if there is none)
+    Block *block = fs->block != fs->loop->block ? fs->block : NULL;
+    int continues = valueNode == NULL || (valueNode->opcode == OP_INT && parseInteger(valueNode->val) == 0);
+    if (continues) {
+      if (block != NULL) {
+        jumpToBlockEnd(fs, block);
+      } else {
+        emitContinue(fs);
+      }
+    } else if (block != NULL) {
+      if (fs->height != block->height + 1) emitArg(fs, BC_UNWIND, block->height, 0);
+      addJump(&block->ends, emitJump(fs, BC_JUMP_IF_VOID, 0));
+      emitArg(fs, BC_UNWIND, fs->loop->height, 0);
+      addJump(&fs->loop->exits, emitJump(fs, BC_JUMP, 0));
+    } else {
+      int isVoid = emitJump(fs, BC_JUMP_IF_VOID, 0);
+      emitArg(fs, BC_UNWIND, fs->loop->height, 0);
+      addJump(&fs->loop->exits, emitJump(fs, BC_JUMP, 0));
+      patch(fs, isVoid, fs->proto->codeLength);
+      fs->height = start + 1;
+      emitContinue(fs);
+    }
+  } else if (fs->block != NULL) {
+    jumpToBlockEnd(fs, fs->block);
+  } else {
+    emit(fs, BC_RETURN, 0);
+  }
+  fs->height = start + 1;
+}
+
+// ============================================================================
+// Control flow forms
+// ============================================================================
+
+//  Argument count check of a form; emits an error and returns 0 if it fails
+static int requireArgs(FuncState *fs, AstNode *node, const char *name, int min, int max) {
+  int count = node->childCount - 1;
+  if (count < min || (max >= 0 && count > max)) {
+    emitError(fs, node, ERROR_CODE_RUNTIME_ARITY, "Wrong number of arguments to %s.", name);
+    fs->height++;
+    return 0;
+  }
+  return 1;
+}
+
+//  (if cond then [cond then ...] [else])
+static void compileIf(FuncState *fs, AstNode *node) {
+  if (!requireArgs(fs, node, "if", 2, -1)) return;
+
+  int start = fs->height;
+  JumpList ends = {NULL, 0, 0};
+  int i = 1;
+  for (; i + 1 < node->childCount; i += 2) {
+    compileNode(fs, node->children[i]);
+    int next = emitJump(fs, BC_JUMP_IF_FALSE, -1);
+    compileBlock(fs, node->children[i + 1], 0);
+    addJump(&ends, emitJump(fs, BC_JUMP, 0));
+    patch(fs, next, fs->proto->codeLength);
+    fs->height = start;
+  }
+  if (i < node->childCount) {
+    compileBlock(fs, node->children[i], 0);
+  } else {
+    emit(fs, BC_VOID, 1);
+  }
+  patchAll(fs, &ends);
+}
+
+//  (when cond action) and (unless cond action)
+static void compileWhen(FuncState *fs, AstNode *node, BytecodeOp skip, const char *name) {
+  if (!requireArgs(fs, node, name, 2, 2)) return;
+
+  int start = fs->height;
+  compileNode(fs, node->children[1]);
+  int skipped = emitJump(fs, skip, -1);
+  compileBlock(fs, node->children[2], 0);
+  int end = emitJump(fs, BC_JUMP, 0);
+  patch(fs, skipped, fs->proto->codeLength);
+  fs->height = start;
+  emitInt(fs, 0);
+  patch(fs, end, fs->proto->codeLength);
+}
+
+//  (cond (test result) ... (else default))
+static void compileCond(FuncState *fs, AstNode *node) {
+  int start = fs->height;
+  JumpList ends = {NULL, 0, 0};
+  int done = 0;
+
+  for (int i = 1; i < node->childCount && !done; i++) {
+    AstNode *clause = node->children[i];
+    if (clause->opcode != OP_APPLICATION || clause->childCount != 2) {
+      emitError(fs, clause, ERROR_CODE_RUNTIME, "%s clauses must be (test result) or (else result).", "cond");
+      fs->height = start + 1;
+      done = 1;
+      break;
+    }
+
+    AstNode *test = clause->children[0];
+    if (test->opcode == OP_IDENTIFIER && strcmp(test->val, "else") == 0) {
+      compileBlock(fs, clause->children[1], 0);
+      done = 1;
+      break;
+    }
+
+    compileNode(fs, test);
+    int next = emitJump(fs, BC_JUMP_IF_FALSE, -1);
+    compileBlock(fs, clause->children[1], 0);
+    addJump(&ends, emitJump(fs, BC_JUMP, 0));
+    patch(fs, next, fs->proto->codeLength);
+    fs->height = start;
+  }
+
+  if (!done) emitInt(fs, 0);
+  patchAll(fs, &ends);
+  fs->height = start + 1;
+}
+
+//  Close a loop: continue jumps were patched, exits land after its value
+static void endLoop(FuncState *fs, Loop *loop) {
+  fs->loop = loop->outer;
+  patchAll(fs, &loop->exits);
+  fs->height = loop->height + 1;
+}
+
+//  (loop count {i -> body}): the count and index stay on the stack
+static void compileLoop(FuncState *fs, AstNode *node) {
+  if (!requireArgs(fs, node, "loop", 2, 2)) return;
+
+  int start = fs->height;
+  compileNode(fs, node->children[1]);
+  mark(fs, node);
+  emit(fs, BC_LOOP_INIT, 1);
+
+  int top = fs->proto->codeLength;
+  int done = emitJump(fs, BC_LOOP_NEXT, 0);
+
+  Loop loop = {start, fs->height, {NULL, 0, 0}, {NULL, 0, 0}, fs->block, fs->loop};
+  fs->loop = &loop;
+  compileBlock(fs, node->children[2], 1);
+  emit(fs, BC_POP, -1);
+
+  patchAll(fs, &loop.continues);
+  emit(fs, BC_LOOP_STEP, 0);
+  emitJumpTo(fs, BC_JUMP, top, 0);
+
+  patch(fs, done, fs->proto->codeLength);
+  emit(fs, BC_POP, -1);
+  emit(fs, BC_POP, -1);
+  emitInt(fs, 0);
+  endLoop(fs, &loop);
+}
+
+//  (while cond {body}): the condition is evaluated before every iteration
+static void compileWhile(FuncState *fs, AstNode *node) {
+  if (!requireArgs(fs, node, "while", 2, 2)) return;
+
+  int start = fs->height;
+  int top = fs->proto->codeLength;
+  compileNode(fs, node->children[1]);
+  int done = emitJump(fs, BC_JUMP_IF_FALSE, -1);
+
+  Loop loop = {start, start, {NULL, 0, 0}, {NULL, 0, 0}, fs->block, fs->loop};
+  fs->loop = &loop;
+  compileBlock(fs, node->children[2], 0);
+  emit(fs, BC_POP, -1);
+
+  for (int i = 0; i < loop.continues.count; i++) patch(fs, loop.continues.pcs[i], top);
+  free(loop.continues.pcs);
+  emitJumpTo(fs, BC_JUMP, top, 0);
+
+  patch(fs, done, fs->proto->codeLength);
+  emitInt(fs, 0);
+  endLoop(fs, &loop);
+}
+
+//  (break [value]) and (continue)
+static void compileLoopExit(FuncState *fs, AstNode *node, int isBreak, const char *name) {
+  int start = fs->height;
+  if (fs->loop == NULL) {
+    emitError(fs, node, ERROR_CODE_OUTSIDE_LOOP, "'%s' used outside of a loop.", name);
+    fs->height = start + 1;
+    return;
+  }
+  if (!requireArgs(fs, node, name, 0, isBreak ? 1 : 0)) return;
+
+  if (isBreak) {
+    if (node->childCount > 1) {
+      compileNode(fs, node->children[1]);
+    } else {
+      emitInt(fs, 0);
+    }
+    emitArg(fs, BC_UNWIND, fs->loop->height, 0);
+    addJump(&fs->loop->exits, emitJump(fs, BC_JUMP, 0));
+  } else {
+    emitContinue(fs);
+  }
+  fs->height = start + 1;
+}
+
+//  (and a b ...) and (or a b ...): stop at the first operand that decides the result
+static void compileLogical(FuncState *fs, AstNode *node, int stopOn, const char *name) {
+  if (!requireArgs(fs, node, name, 1, -1)) return;
+
+  int start = fs->height;
+  JumpList decided = {NULL, 0, 0};
+  for (int i = 1; i < node->childCount; i++) {
+    compileNode(fs, node->children[i]);
+    addJump(&decided, emitJump(fs, stopOn ? BC_JUMP_IF_TRUE : BC_JUMP_IF_FALSE, -1));
+  }
+  emitInt(fs, !stopOn);
+  int end = emitJump(fs, BC_JUMP, 0);
+
+  fs->height = start;
+  patchAll(fs, &decided);
+  emitInt(fs, stopOn);
+  patch(fs, end, fs->proto->codeLength);
+}
+
+// ============================================================================
+// Expressions
+// ============================================================================
+
+static void compileApplication(FuncState *fs, AstNode *node) {
+  if (node->childCount == 0) {
+    emit(fs, BC_VOID, 1);
+    return;
+  }
+  AstNode *head = node->children[0];
+
+  //  Forms that control evaluation of their arguments, as in the interpreter
+  if (head->opcode == OP_IDENTIFIER) {
+    const char *name = head->val;
+    if (strcmp(name, "if") == 0) return compileIf(fs, node);
+    if (strcmp(name, "when") == 0) return compileWhen(fs, node, BC_JUMP_IF_FALSE, "when");
+    if (strcmp(name, "unless") == 0) return compileWhen(fs, node, BC_JUMP_IF_TRUE, "unless");
+    if (strcmp(name, "cond") == 0) return compileCond(fs, node);
+    if (strcmp(name, "loop") == 0) return compileLoop(fs, node);
+    if (strcmp(name, "while") == 0) return compileWhile(fs, node);
+    if (strcmp(name, "break") == 0) return compileLoopExit(fs, node, 1, "break");
+    if (strcmp(name, "continue") == 0) return compileLoopExit(fs, node, 0, "continue");
+    if (strcmp(name, "and") == 0) return compileLogical(fs, node, 0, "and");
+    if (strcmp(name, "or") == 0) return compileLogical(fs, node, 1, "or");
+  }
+
+  int argCount = node->childCount - 1;
+  for (int i = 0; i < node->childCount; i++) {
+    compileNode(fs, node->children[i]);
+  }
+  mark(fs, node);
+  emitArg(fs, BC_CALL, argCount, -argCount);
+}
+
+//  ns.member: a member of a namespace created with use_as
+static void compileQualified(FuncState *fs, AstNode *node) {
+  char *dot = strchr(node->val, '.');
+  *dot = '\0';
+  compileLoad(fs, node, node->val, BC_GET_NAMESPACE);
+  *dot = '.';
+  emitArg(fs, BC_MEMBER, nameConstant(fs, node->val), 0);
+}
+
+static void compileList(FuncState *fs, AstNode *node) {
+  for (int i = 0; i < node->childCount; i++) {
+    compileNode(fs, node->children[i]);
+  }
+  emitArg(fs, BC_LIST, node->childCount, 1 - node->childCount);
+}
+
+//  A function literal: compiled into a nested prototype, created as a closure
+static void compileFunction(FuncState *fs, AstNode *node, const char *name) {
+  char lambdaName[32];
+  if (name == NULL) {
+    snprintf(lambdaName, sizeof(lambdaName), "lambda@%d", node->lineNumber);
+    name = lambdaName;
+  }
+
+  FuncState child = {0};
+  child.proto = newProto(name, fs->proto->sourceId);
+  child.parent = fs;
+  child.level = fs->level + 1;
+
+  int paramCount = countParams(node);
+  child.proto->paramCount = paramCount;
+  for (int i = 0; i < paramCount; i++) {
+    useSlot(&child, i, node->children[i]->val);
+  }
+
+  compileSequence(&child, node, paramCount);
+  emit(&child, BC_RETURN, -1);
+
+  BytecodeProto *proto = fs->proto;
+  proto->protos = grow(proto->protos, &fs->protoCapacity, proto->protoCount + 1, sizeof(BytecodeProto *));
+  proto->protos[proto->protoCount] = child.proto;
+  emitArg(fs, BC_CLOSURE, proto->protoCount++, 1);
+}
+
+static void compileNode(FuncState *fs, AstNode *node) {
+  switch (node->opcode) {
+    case OP_INT:
+      emitInt(fs, parseInteger(node->val));
+      return;
+
+    case OP_FLOAT:
+      emitArg(fs, BC_CONST, addConstant(fs, Generic_fromFloat(parseFloat(node->val))), 1);
+      return;
+
+    case OP_STRING: {
+      char *value = parseString(node->val);
+      emitArg(fs, BC_CONST, addConstant(fs, Generic_fromString(value)), 1);
+      free(value);
+      return;
+    }
+
+    case OP_IDENTIFIER:
+      compileLoad(fs, node, node->val, BC_GET_GLOBAL);
+      return;
+
+    case OP_QUALIFIED:
+      compileQualified(fs, node);
+      return;
+
+    case OP_ASSIGNMENT:
+      if (node->childCount >= 2) compileAssignment(fs, node);
+      emit(fs, BC_VOID, 1);
+      return;
+
+    case OP_RETURN:
+      compileReturn(fs, node);
+      return;
+
+    case OP_STATEMENT:
+      compileSequence(fs, node, 0);
+      return;
+
+    case OP_APPLICATION:
+      compileApplication(fs, node);
+      return;
+
+    case OP_FUNCTION:
+      compileFunction(fs, node, NULL);
+      return;
+
+    case OP_LIST:
+      compileList(fs, node);
+      return;
+
+    case OP_SIGNATURE:
+      //  Type signatures only matter to the type checker
+      emit(fs, BC_VOID, 1);
+      return;
+  }
+  emit(fs, BC_VOID, 1);
+}
+
+//  Slots nothing was stored in still need a name for the disassembly and .franzc files
+static void nameSlots(BytecodeProto *proto) {
+  for (int i = 0; i < proto->slotCount; i++) {
+    if (proto->slotNames[i] == NULL) proto->slotNames[i] = strdup("?");
+  }
+  for (int i = 0; i < proto->protoCount; i++) nameSlots(proto->protos[i]);
+}
+
+BytecodeProto *Bytecode_compile(AstNode *program) {
+  //  Offsets of parameters and variables; compile_optimize only runs when enabled
+  int optimizationEnabled = g_optimization_enabled;
+  g_optimization_enabled = 1;
+  CompileEnv *env = CompileEnv_new(NULL);
+  compile_optimize(program, env);
+  CompileEnv_free(env);
+  g_optimization_enabled = optimizationEnabled;
+
+  FuncState top = {0};
+  top.proto = newProto("<toplevel>", program->sourceId);
+  compileNode(&top, program);
+  emit(&top, BC_RETURN, -1);
+  nameSlots(top.proto);
+
+  if (Bytecode_verify(top.proto) != 0) {
+    fprintf(stderr, "ERROR: Internal error: generated bytecode failed verification.\n");
+    Bytecode_disassemble(stderr, top.proto);
+    exit(1);
+  }
+  return top.proto;
+}
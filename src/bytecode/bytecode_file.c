@@ -0,0 +1,280 @@
+#include "bytecode.h"
+#include <stdlib.h>
+#include <string.h>
+
+/**
+ * .franzc layout (all integers little-endian)
+ *
+ *   magic      8 bytes: 0x7f "FRANZC" 0x00
+ *   version    u16
+ *   reserved   u16
+ *   proto      the top level
+ *
+ *   proto:     name, paramCount u16, slotCount u16, slot names,
+ *              captureCount u16, captures (kind u8, index u16, name),
+ *              constantCount u16, constants (tag u8, then i32 int,
+ *              f64 float or string), codeLength u32, code,
+ *              spanCount u32, spans (pc, line, column, endLine,
+ *              endColumn: u32 each), protoCount u16, nested protos
+ *   string:    length u32, bytes (no terminator)
+ */
+
+#define FRANZC_MAGIC "\x7f" "FRANZC"
+#define FRANZC_MAGIC_SIZE 8
+#define FRANZC_VERSION 2
+
+enum { CONSTANT_INT, CONSTANT_FLOAT, CONSTANT_STRING };
+
+// ============================================================================
+// Writing
+// ============================================================================
+
+static void writeU8(FILE *out, int value) {
+  fputc(value & 0xff, out);
+}
+
+static void writeU16(FILE *out, int value) {
+  writeU8(out, value);
+  writeU8(out, value >> 8);
+}
+
+static void writeU32(FILE *out, uint32_t value) {
+  for (int i = 0; i < 4; i++) writeU8(out, (int) (value >> (8 * i)));
+}
+
+static void writeU64(FILE *out, uint64_t value) {
+  writeU32(out, (uint32_t) value);
+  writeU32(out, (uint32_t) (value >> 32));
+}
+
+static void writeString(FILE *out, const char *value) {
+  size_t length = strlen(value);
+  writeU32(out, (uint32_t) length);
+  fwrite(value, 1, length, out);
+}
+
+static void writeProto(FILE *out, BytecodeProto *proto) {
+  writeString(out, proto->name);
+  writeU16(out, proto->paramCount);
+  writeU16(out, proto->slotCount);
+  for (int i = 0; i < proto->slotCount; i++) writeString(out, proto->slotNames[i]);
+
+  writeU16(out, proto->captureCount);
+  for (int i = 0; i < proto->captureCount; i++) {
+    writeU8(out, proto->captures[i].kind);
+    writeU16(out, proto->captures[i].index);
+    writeString(out, proto->captures[i].name);
+  }
+
+  writeU16(out, proto->constantCount);
+  for (int i = 0; i < proto->constantCount; i++) {
+    Generic *constant = proto->constants[i];
+    if (constant->type == TYPE_INT) {
+      writeU8(out, CONSTANT_INT);
+      writeU64(out, (uint64_t) *((int64_t *) constant->p_val));
+    } else if (constant->type == TYPE_FLOAT) {
+      uint64_t bits;
+      memcpy(&bits, constant->p_val, sizeof(bits));
+      writeU8(out, CONSTANT_FLOAT);
+      writeU64(out, bits);
+    } else {
+      writeU8(out, CONSTANT_STRING);
+      writeString(out, *((char **) constant->p_val));
+    }
+  }
+
+  writeU32(out, (uint32_t) proto->codeLength);
+  fwrite(proto->code, 1, proto->codeLength, out);
+
+  writeU32(out, (uint32_t) proto->spanCount);
+  for (int i = 0; i < proto->spanCount; i++) {
+    BytecodeSpan *span = &proto->spans[i];
+    writeU32(out, (uint32_t) span->pc);
+    writeU32(out, (uint32_t) span->line);
+    writeU32(out, (uint32_t) span->column);
+    writeU32(out, (uint32_t) span->endLine);
+    writeU32(out, (uint32_t) span->endColumn);
+  }
+
+  writeU16(out, proto->protoCount);
+  for (int i = 0; i < proto->protoCount; i++) writeProto(out, proto->protos[i]);
+}
+
+int Bytecode_isImage(const char *data, long length) {
+  return length >= FRANZC_MAGIC_SIZE && memcmp(data, FRANZC_MAGIC, FRANZC_MAGIC_SIZE) == 0;
+}
+
+int Bytecode_write(BytecodeProto *proto, const char *path) {
+  FILE *out = fopen(path, "wb");
+  if (out == NULL) return -1;
+
+  fwrite(FRANZC_MAGIC, 1, FRANZC_MAGIC_SIZE, out);
+  writeU16(out, FRANZC_VERSION);
+  writeU16(out, 0);
+  writeProto(out, proto);
+
+  int failed = ferror(out);
+  if (fclose(out) != 0) failed = 1;
+  return failed ? -1 : 0;
+}
+
+// ============================================================================
+// Reading
+// ============================================================================
+
+//  Every read is bounds-checked; the first failure sets ok to 0
+typedef struct Reader {
+  const uint8_t *data;
+  long length;
+  long pos;
+  int ok;
+} Reader;
+
+static const uint8_t *take(Reader *reader, long size) {
+  if (!reader->ok || size < 0 || reader->length - reader->pos < size) {
+    reader->ok = 0;
+    return NULL;
+  }
+  const uint8_t *p = reader->data + reader->pos;
+  reader->pos += size;
+  return p;
+}
+
+static int readU8(Reader *reader) {
+  const uint8_t *p = take(reader, 1);
+  return p != NULL ? p[0] : 0;
+}
+
+static int readU16(Reader *reader) {
+  const uint8_t *p = take(reader, 2);
+  return p != NULL ? Bytecode_readU16(p) : 0;
+}
+
+static uint32_t readU32(Reader *reader) {
+  const uint8_t *p = take(reader, 4);
+  return p != NULL ? (uint32_t) Bytecode_readU32(p) : 0;
+}
+
+static uint64_t readU64(Reader *reader) {
+  uint64_t low = readU32(reader);
+  return low | (uint64_t) readU32(reader) << 32;
+}
+
+//  Lengths and counts are checked against the bytes left before allocating
+static int readCount(Reader *reader, int minSize) {
+  uint32_t count = readU32(reader);
+  if (count > (uint32_t) (reader->length - reader->pos) / (uint32_t) minSize) {
+    reader->ok = 0;
+    return 0;
+  }
+  return (int) count;
+}
+
+static char *readString(Reader *reader) {
+  int length = readCount(reader, 1);
+  const uint8_t *bytes = take(reader, length);
+  if (bytes == NULL || memchr(bytes, '\0', length) != NULL) {
+    reader->ok = 0;
+    return strdup("");
+  }
+  char *value = (char *) malloc(length + 1);
+  memcpy(value, bytes, length);
+  value[length] = '\0';
+  return value;
+}
+
+//  Reads a whole prototype, even after a failure, so it can always be freed
+static BytecodeProto *readProto(Reader *reader, int depth) {
+  BytecodeProto *proto = (BytecodeProto *) calloc(1, sizeof(BytecodeProto));
+  proto->sourceId = -1;
+  proto->name = readString(reader);
+  proto->paramCount = readU16(reader);
+
+  proto->slotCount = readU16(reader);
+  proto->slotNames = (char **) calloc(proto->slotCount + 1, sizeof(char *));
+  for (int i = 0; i < proto->slotCount; i++) proto->slotNames[i] = readString(reader);
+
+  proto->captureCount = readU16(reader);
+  proto->captures = (BytecodeCapture *) calloc(proto->captureCount + 1, sizeof(BytecodeCapture));
+  for (int i = 0; i < proto->captureCount; i++) {
+    int kind = readU8(reader);
+    if (kind > CAPTURE_GLOBAL) reader->ok = 0;
+    proto->captures[i].kind = (BytecodeCaptureKind) kind;
+    proto->captures[i].index = readU16(reader);
+    proto->captures[i].name = readString(reader);
+  }
+
+  proto->constantCount = readU16(reader);
+  proto->constants = (Generic **) calloc(proto->constantCount + 1, sizeof(Generic *));
+  for (int i = 0; i < proto->constantCount; i++) {
+    int tag = readU8(reader);
+    if (tag == CONSTANT_INT) {
+      proto->constants[i] = Generic_fromInt((int64_t) readU64(reader));
+    } else if (tag == CONSTANT_FLOAT) {
+      uint64_t bits = readU64(reader);
+      double value;
+      memcpy(&value, &bits, sizeof(value));
+      proto->constants[i] = Generic_fromFloat(value);
+    } else {
+      if (tag != CONSTANT_STRING) reader->ok = 0;
+      char *value = readString(reader);
+      proto->constants[i] = Generic_fromString(value);
+      free(value);
+    }
+  }
+
+  proto->codeLength = readCount(reader, 1);
+  proto->code = (uint8_t *) malloc(proto->codeLength + 1);
+  const uint8_t *code = take(reader, proto->codeLength);
+  if (code != NULL) memcpy(proto->code, code, proto->codeLength);
+  else proto->codeLength = 0;
+
+  proto->spanCount = readCount(reader, 20);
+  proto->spans = (BytecodeSpan *) calloc(proto->spanCount + 1, sizeof(BytecodeSpan));
+  for (int i = 0; i < proto->spanCount; i++) {
+    BytecodeSpan *span = &proto->spans[i];
+    span->pc = (int) readU32(reader);
+    span->line = (int) readU32(reader);
+    span->column = (int) readU32(reader);
+    span->endLine = (int) readU32(reader);
+    span->endColumn = (int) readU32(reader);
+    if (i > 0 && span->pc < proto->spans[i - 1].pc) reader->ok = 0;
+  }
+
+  //  Nesting is bounded by the file size, but keep malicious files off the C stack
+  int protoCount = readU16(reader);
+  if (depth > 1000) {
+    reader->ok = 0;
+    protoCount = 0;
+  }
+  proto->protos = (BytecodeProto **) calloc(protoCount + 1, sizeof(BytecodeProto *));
+  for (int i = 0; i < protoCount && reader->ok; i++) {
+    proto->protos[i] = readProto(reader, depth + 1);
+    proto->protoCount = i + 1;
+  }
+  return proto;
+}
+
+BytecodeProto *Bytecode_read(const char *data, long length, const char *path) {
+  if (!Bytecode_isImage(data, length)) {
+    fprintf(stderr, "Error: '%s' is not a valid .franzc file.\n", path);
+    return NULL;
+  }
+
+  Reader reader = {(const uint8_t *) data, length, FRANZC_MAGIC_SIZE, 1};
+  int version = readU16(&reader);
+  readU16(&reader);
+  if (reader.ok && version != FRANZC_VERSION) {
+    fprintf(stderr, "Error: '%s' was compiled for .franzc version %d; this franz reads version %d. Recompile it with --emit=franzc.\n",
+            path, version, FRANZC_VERSION);
+    return NULL;
+  }
+
+  BytecodeProto *proto = readProto(&reader, 0);
+  if (!reader.ok || reader.pos != length || proto->paramCount != 0 || Bytecode_verify(proto) != 0) {
+    fprintf(stderr, "Error: '%s' is not a valid .franzc file.\n", path);
+    Bytecode_free(proto);
+    return NULL;
+  }
+  return proto;
+}
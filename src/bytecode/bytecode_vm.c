@@ -0,0 +1,441 @@
+#include "bytecode.h"
+#include <stdlib.h>
+#include <string.h>
+#include "../stdlib.h"
+#include "../list.h"
+#include "../diagnostics/diagnostic.h"
+
+//  Values and frames live in fixed arrays: natives keep pointers to their
+// arguments on the stack while callbacks run on top of them
+#define STACK_SIZE (1 << 20)
+#define MAX_FRAMES (1 << 16)
+
+//  A running function, program or module
+typedef struct Frame {
+  BytecodeClosure *closure;  // NULL for a program or module
+  BytecodeProto *proto;
+  int pc;                    // next instruction while another frame runs
+  int base;                  // stack index of slot 0; the function is below it
+  Scope *scope;              // where globals are looked up and assigned (lexically)
+} Frame;
+
+static Generic **g_stack = NULL;
+static int g_top = 0;
+static Frame *g_frames = NULL;
+static int g_frameCount = 0;
+static int g_active = 0;
+
+//  Modules compiled at runtime; their closures may be called until the program ends
+static BytecodeProto **g_modules = NULL;
+static int g_moduleCount = 0;
+
+// ============================================================================
+// Values
+// ============================================================================
+
+static Generic *intValue(int64_t value) {
+  int64_t *p_val = (int64_t *) malloc(sizeof(int64_t));
+  *p_val = value;
+  return Generic_new(TYPE_INT, p_val, 0);
+}
+
+static void release(Generic *value) {
+  if (value == NULL) return;
+  value->refCount--;
+  if (value->refCount == 0) Generic_free(value);
+}
+
+//  The stack holds a reference to every value on it
+static void push(Generic *value) {
+  value->refCount++;
+  g_stack[g_top++] = value;
+}
+
+static void pop(void) {
+  release(g_stack[--g_top]);
+}
+
+static void dropTo(int top) {
+  while (g_top > top) pop();
+}
+
+//  Take the top value off the stack for the runtime: a temporary, or a copy
+// if something else still holds it (it may go away with the frame's scope)
+static Generic *takeResult(void) {
+  Generic *value = g_stack[--g_top];
+  value->refCount--;
+  return value->refCount == 0 ? value : Generic_copy(value);
+}
+
+static int truthy(Generic *value) {
+  switch (value->type) {
+    case TYPE_INT: return *((int64_t *) value->p_val) != 0;
+    case TYPE_FLOAT: return *((double *) value->p_val) != 0.0;
+    case TYPE_VOID: return 0;
+    default: return 1;
+  }
+}
+
+static char *constantString(BytecodeProto *proto, int k) {
+  return *((char **) proto->constants[k]->p_val);
+}
+
+// ============================================================================
+// Errors and lookups
+// ============================================================================
+
+static void runtimeError(Frame *frame, int pc, ErrorCode code, const char *format, const char *name) {
+  BytecodeSpan at = Bytecode_spanAt(frame->proto, pc);
+  DiagnosticSpan span = {frame->proto->sourceId, at.line, at.column, at.endLine, at.endColumn};
+  Diagnostic_report(stdout, "Runtime Error", code, span, format, name);
+  exit(1);
+}
+
+//  A variable that is not a slot or capture (or was not defined when it was captured)
+static Generic *lookupGlobal(Frame *frame, int pc, const char *name) {
+  Generic *value = Scope_get(frame->scope, (char *) name, -1);
+  if (value != NULL) return value;
+  if (strcmp(name, "void") == 0) return Generic_new(TYPE_VOID, NULL, 0);
+
+  runtimeError(frame, pc, ERROR_CODE_UNDEFINED_VARIABLE, "Undefined variable '%s'.", name);
+  return NULL;
+}
+
+//  Index and count come from LOOP_INIT in compiled code; guard hand-made .franzc files
+static void checkLoop(Frame *frame, int pc) {
+  if (g_stack[g_top - 1]->type != TYPE_INT || g_stack[g_top - 2]->type != TYPE_INT) {
+    fprintf(stderr, "Internal Error: Loop instruction without a loop in '%s' at %d.\n", frame->proto->name, pc);
+    exit(1);
+  }
+}
+
+static Generic *newClosure(Frame *frame, BytecodeProto *proto) {
+  BytecodeClosure *closure = (BytecodeClosure *) malloc(sizeof(BytecodeClosure));
+  closure->refCount = 1;
+  closure->proto = proto;
+  closure->captureCount = proto->captureCount;
+  closure->scope = frame->scope;
+  closure->captures = (Generic **) malloc(sizeof(Generic *) * (proto->captureCount > 0 ? proto->captureCount : 1));
+
+  //  Snapshot of the captured variables, as the interpreter's closures take it
+  for (int i = 0; i < proto->captureCount; i++) {
+    BytecodeCapture *capture = &proto->captures[i];
+    Generic *value;
+    if (capture->kind == CAPTURE_SLOT) {
+      value = g_stack[frame->base + capture->index];
+    } else if (capture->kind == CAPTURE_OUTER) {
+      value = frame->closure->captures[capture->index];
+    } else {
+      value = Scope_get(frame->scope, capture->name, -1);
+    }
+    if (value != NULL) value->refCount++;
+    closure->captures[i] = value;
+  }
+
+  return Generic_new(TYPE_BYTECODE_CLOSURE, closure, 0);
+}
+
+// ============================================================================
+// Calls
+// ============================================================================
+
+//  Start running proto in a new frame; its arguments are the top values
+static void pushFrame(BytecodeClosure *closure, BytecodeProto *proto, Scope *p_scope, int argCount, int lineNumber) {
+  if (g_frameCount == MAX_FRAMES || g_top + proto->slotCount + proto->maxStack + 1 > STACK_SIZE) {
+    Diagnostic_report(stdout, "Runtime Error", ERROR_CODE_RUNTIME, Diagnostic_spanOfLine(lineNumber),
+                      "Call stack overflow in '%s' (too many nested calls).", proto->name);
+    exit(1);
+  }
+
+  Frame *frame = &g_frames[g_frameCount++];
+  frame->closure = closure;
+  frame->proto = proto;
+  frame->pc = 0;
+  frame->base = g_top - argCount;
+  frame->scope = p_scope;
+  for (int i = argCount; i < proto->slotCount; i++) g_stack[g_top++] = NULL;
+}
+
+//  Call the function below the top argCount values. Closures of the VM get
+// a frame (returns 1); anything else goes through applyFunc and its result
+// replaces the function and arguments (returns 0).
+static int callValue(Scope *p_scope, int argCount, int lineNumber) {
+  Generic *func = g_stack[g_top - argCount - 1];
+
+  if (func->type == TYPE_BYTECODE_CLOSURE) {
+    BytecodeClosure *closure = (BytecodeClosure *) func->p_val;
+    BytecodeProto *proto = closure->proto;
+    if (argCount != proto->paramCount) {
+      printf("Runtime Error @ Line %i: Supplied %s arguments than required to function.\n",
+             lineNumber, argCount > proto->paramCount ? "more" : "less");
+      exit(1);
+    }
+
+    pushFrame(closure, proto, closure->scope, argCount, lineNumber);
+    return 1;
+  }
+
+  //  The stack keeps the function and arguments alive: applyFunc frees none of them
+  Generic *res = applyFunc(func, p_scope, &g_stack[g_top - argCount], argCount, lineNumber);
+  res->refCount++;
+  dropTo(g_top - argCount - 1);
+  g_stack[g_top++] = res;
+  return 0;
+}
+
+// ============================================================================
+// Dispatch
+// ============================================================================
+
+//  Run until the frame count drops back to entryFrames; the result is left on the stack
+static void execute(int entryFrames) {
+  Frame *frame = &g_frames[g_frameCount - 1];
+  BytecodeProto *proto = frame->proto;
+  uint8_t *code = proto->code;
+  int pc = frame->pc;
+
+  for (;;) {
+    int at = pc;
+    BytecodeOp op = code[pc++];
+    //  First operand: none, a jump target or a 16-bit index
+    int size = Bytecode_opSize(op);
+    int a = size == 1 ? 0 : Bytecode_isJump(op) ? Bytecode_readU32(code + pc) : Bytecode_readU16(code + pc);
+    pc += size - 1;
+
+    switch (op) {
+      case BC_CONST:
+        push(proto->constants[a]);
+        break;
+
+      case BC_VOID:
+        push(Generic_new(TYPE_VOID, NULL, 0));
+        break;
+
+      case BC_POP:
+        pop();
+        break;
+
+      case BC_PICK:
+        push(g_stack[g_top - 1 - a]);
+        break;
+
+      case BC_GET_SLOT: {
+        Generic *value = g_stack[frame->base + a];
+        push(value != NULL ? value : lookupGlobal(frame, at, proto->slotNames[a]));
+        break;
+      }
+
+      case BC_SET_SLOT: {
+        Generic *old = g_stack[frame->base + a];
+        g_stack[frame->base + a] = g_stack[--g_top];
+        release(old);
+        break;
+      }
+
+      case BC_GET_CAPTURE: {
+        Generic *value = frame->closure->captures[a];
+        push(value != NULL ? value : lookupGlobal(frame, at, proto->captures[a].name));
+        break;
+      }
+
+      case BC_GET_GLOBAL:
+        push(lookupGlobal(frame, at, constantString(proto, a)));
+        break;
+
+      case BC_SET_GLOBAL:
+        Scope_set(frame->scope, constantString(proto, a), g_stack[g_top - 1], Bytecode_spanAt(proto, at).line);
+        pop();
+        break;
+
+      case BC_GET_NAMESPACE: {
+        Generic *value = Scope_get(frame->scope, constantString(proto, a), -1);
+        push(value != NULL ? value : Generic_new(TYPE_VOID, NULL, 0));
+        break;
+      }
+
+      case BC_MEMBER: {
+        char *name = constantString(proto, a);
+        Generic *ns = g_stack[g_top - 1];
+        if (ns->type != TYPE_NAMESPACE) {
+          runtimeError(frame, at, ERROR_CODE_UNDEFINED_VARIABLE, "'%s' does not name a namespace.", name);
+        }
+        Generic *member = Scope_get((Scope *) ns->p_val, strchr(name, '.') + 1, -1);
+        if (member == NULL) {
+          runtimeError(frame, at, ERROR_CODE_UNDEFINED_VARIABLE, "Undefined variable '%s'.", name);
+        }
+        member->refCount++;
+        g_stack[g_top - 1] = member;
+        release(ns);
+        break;
+      }
+
+      case BC_LIST: {
+        //  List_new stores copies
+        List *list = List_new(&g_stack[g_top - a], a);
+        dropTo(g_top - a);
+        push(Generic_new(TYPE_LIST, list, 0));
+        break;
+      }
+
+      case BC_CLOSURE:
+        push(newClosure(frame, proto->protos[a]));
+        break;
+
+      case BC_CALL:
+        frame->pc = pc;
+        if (callValue(frame->scope, a, Bytecode_spanAt(proto, at).line)) {
+          frame = &g_frames[g_frameCount - 1];
+          proto = frame->proto;
+          code = proto->code;
+          pc = 0;
+        }
+        break;
+
+      case BC_RETURN: {
+        //  The result replaces the function, its arguments and its slots
+        Generic *res = g_stack[--g_top];
+        dropTo(frame->base - 1);
+        g_stack[g_top++] = res;
+
+        g_frameCount--;
+        if (g_frameCount == entryFrames) return;
+        frame = &g_frames[g_frameCount - 1];
+        proto = frame->proto;
+        code = proto->code;
+        pc = frame->pc;
+        break;
+      }
+
+      case BC_JUMP:
+        pc = a;
+        break;
+
+      case BC_JUMP_IF_FALSE:
+      case BC_JUMP_IF_TRUE: {
+        int passed = truthy(g_stack[g_top - 1]);
+        pop();
+        if (passed == (op == BC_JUMP_IF_TRUE)) pc = a;
+        break;
+      }
+
+      case BC_JUMP_IF_VOID:
+        if (g_stack[g_top - 1]->type == TYPE_VOID) pc = a;
+        break;
+
+      case BC_UNWIND: {
+        Generic *value = g_stack[--g_top];
+        dropTo(frame->base + proto->slotCount + a);
+        g_stack[g_top++] = value;
+        break;
+      }
+
+      case BC_DROP_TO:
+        dropTo(frame->base + proto->slotCount + a);
+        break;
+
+      case BC_LOOP_INIT: {
+        Generic *count = g_stack[g_top - 1];
+        if (count->type != TYPE_INT) {
+          runtimeError(frame, at, ERROR_CODE_RUNTIME_TYPE,
+                       "loop function requires integer type for argument #1, %s type supplied instead.",
+                       getTypeString(count->type));
+        }
+        push(intValue(0));
+        break;
+      }
+
+      case BC_LOOP_NEXT:
+        checkLoop(frame, at);
+        if (*((int64_t *) g_stack[g_top - 1]->p_val) >= *((int64_t *) g_stack[g_top - 2]->p_val)) pc = a;
+        break;
+
+      case BC_LOOP_STEP: {
+        checkLoop(frame, at);
+        //  A new index: the loop variable keeps the old one
+        Generic *index = g_stack[g_top - 1];
+        Generic *next = intValue(*((int64_t *) index->p_val) + 1);
+        next->refCount++;
+        g_stack[g_top - 1] = next;
+        release(index);
+        break;
+      }
+
+      case BC_ERROR:
+        runtimeError(frame, at, (ErrorCode) a, "%s", constantString(proto, Bytecode_readU16(code + at + 3)));
+        break;
+
+      default:
+        fprintf(stderr, "Internal Error: Invalid bytecode instruction %d.\n", op);
+        exit(1);
+    }
+  }
+}
+
+//  Run a program or module in p_scope; returns its value (a temporary)
+static Generic *runTopLevel(BytecodeProto *proto, Scope *p_scope) {
+  int entryFrames = g_frameCount;
+  g_stack[g_top++] = NULL;  // no function below the frame
+  pushFrame(NULL, proto, p_scope, 0, 0);
+  execute(entryFrames);
+
+  return takeResult();
+}
+
+int BytecodeVM_run(BytecodeProto *proto, Scope *p_global) {
+  if (g_stack == NULL) {
+    g_stack = (Generic **) malloc(sizeof(Generic *) * STACK_SIZE);
+    g_frames = (Frame *) malloc(sizeof(Frame) * MAX_FRAMES);
+  }
+  g_active = 1;
+
+  Generic_free(runTopLevel(proto, p_global));
+  fflush(stdout);
+
+  for (int i = 0; i < g_moduleCount; i++) Bytecode_free(g_modules[i]);
+  free(g_modules);
+  g_modules = NULL;
+  g_moduleCount = 0;
+  return 0;
+}
+
+int BytecodeVM_active(void) {
+  return g_active;
+}
+
+Generic *BytecodeVM_call(Generic *func, Scope *p_scope, Generic *args[], int length, int lineNumber) {
+  int entryFrames = g_frameCount;
+  push(func);
+  for (int i = 0; i < length; i++) push(args[i]);
+
+  //  Pushing took temporaries over, so they are freed when popped
+  if (callValue(p_scope, length, lineNumber)) {
+    //  A callback resolves globals in the scope it is called from, as the
+    // interpreter's lexical closures do (use_with calls it in the sandbox)
+    Scope *global = p_scope;
+    while (global->p_parent != NULL) global = global->p_parent;
+    g_frames[g_frameCount - 1].scope = global;
+    execute(entryFrames);
+  }
+  return takeResult();
+}
+
+Generic *BytecodeVM_runModule(AstNode *module, Scope *p_scope) {
+  BytecodeProto *proto = Bytecode_compile(module);
+  g_modules = realloc(g_modules, sizeof(BytecodeProto *) * (g_moduleCount + 1));
+  g_modules[g_moduleCount++] = proto;
+  return runTopLevel(proto, p_scope);
+}
+
+void BytecodeClosure_retain(void *closure) {
+  if (g_active && closure != NULL) ((BytecodeClosure *) closure)->refCount++;
+}
+
+void BytecodeClosure_free(void *p_closure) {
+  if (!g_active || p_closure == NULL) return;
+
+  BytecodeClosure *closure = (BytecodeClosure *) p_closure;
+  if (--closure->refCount > 0) return;
+  for (int i = 0; i < closure->captureCount; i++) release(closure->captures[i]);
+  free(closure->captures);
+  free(closure);
+}
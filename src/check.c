@@ -8,6 +8,7 @@
 #include "typeinfer.h"
 #include "typecheck.h"
 #include "ast.h"
+#include "diagnostics/diagnostic.h"
 
 #define FRANZ_CHECK_VERSION "v0.1.0"
 
@@ -96,6 +97,9 @@ int main(int argc, char *argv[]) {
     printf("Lexing...\n");
   }
 
+  //  Errors quote the offending source line
+  Diagnostic_setPrimarySource(Diagnostic_addSource(filename, code, filesize));
+
   //  Array-based lexing
   TokenArray *tokens = lex(code, filesize);
 
@@ -110,6 +114,13 @@ int main(int argc, char *argv[]) {
 
   AstNode *ast = parseProgram(tokens);
 
+  if (Diagnostic_count() > 0) {
+    Diagnostic_flush(stdout);
+    fflush(stdout);
+    if (ast) AstNode_free(ast);
+    ast = NULL;
+  }
+
   if (ast == NULL) {
     fprintf(stderr, "Error: Parsing failed\n");
     free(code);
@@ -27,6 +27,9 @@ Closure *Closure_new(AstNode *p_fn, Scope *p_scope) {
   int capacity = p_closure->p_fn->freeVarsCount > 0 ? p_closure->p_fn->freeVarsCount : 8;
   p_closure->p_snapshot = Dict_new(capacity);
 
+  Scope *p_global = p_scope;
+  while (p_global->p_parent != NULL) p_global = p_global->p_parent;
+
   // For each free variable, capture its current value from scope
   for (int i = 0; i < p_closure->p_fn->freeVarsCount; i++) {
     char *varName = p_closure->p_fn->freeVars[i];
@@ -34,7 +37,9 @@ Closure *Closure_new(AstNode *p_fn, Scope *p_scope) {
     // Look up variable in scope (may return NULL if not defined)
     Generic *val = Scope_get(p_scope, varName, -1);  // -1 = no error if missing
 
-    if (val != NULL) {
+    int isBuiltin = val != NULL && val->type == TYPE_NATIVEFUNCTION && Scope_get(p_global, varName, -1) == val;
+
+    if (val != NULL && !isBuiltin) {
       // Create Generic string key for Dict
       char **keyPtr = (char **) malloc(sizeof(char *));
       *keyPtr = (char *) malloc(strlen(varName) + 1);
@@ -48,8 +53,8 @@ Closure *Closure_new(AstNode *p_fn, Scope *p_scope) {
       // Free the original key (Dict_set_inplace made a copy)
       Generic_free(key);
     }
-    // If variable not found, it's probably a builtin (like "add", "print")
-    // We don't snapshot builtins - they're looked up at runtime from global scope
+    // If variable not found or is a builtin (like "add", "print"), it is not snapshotted:
+    // builtins are looked up at runtime from global scope, so a module can redefine them
   }
 
   return p_closure;
@@ -0,0 +1,255 @@
+#include "coverage.h"
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <fcntl.h>
+#include <unistd.h>
+#include <sys/file.h>
+
+#define COVERAGE_HEADER "franz-coverage 1"
+
+const char *Coverage_dataPath(void) {
+  const char *path = getenv("FRANZ_COVERAGE_FILE");
+  return path && path[0] ? path : "franz.coverage";
+}
+
+// ============================================================================
+// Data file
+// ============================================================================
+
+static void addEntry(CoverageSection *section, int line, int group, int branch, long long hits) {
+  if (section->count == section->capacity) {
+    section->capacity = section->capacity ? section->capacity * 2 : 32;
+    section->entries = realloc(section->entries, sizeof(CoverageEntry) * section->capacity);
+  }
+  CoverageEntry *entry = &section->entries[section->count++];
+  entry->line = line;
+  entry->group = group;
+  entry->branch = branch;
+  entry->hits = hits;
+}
+
+static CoverageSection *addSection(CoverageData *data, const char *source) {
+  if (data->count == data->capacity) {
+    data->capacity = data->capacity ? data->capacity * 2 : 4;
+    data->sections = realloc(data->sections, sizeof(CoverageSection) * data->capacity);
+  }
+  CoverageSection *section = &data->sections[data->count++];
+  memset(section, 0, sizeof(*section));
+  section->source = strdup(source);
+  return section;
+}
+
+static void freeSection(CoverageSection *section) {
+  free(section->source);
+  free(section->entries);
+}
+
+int CoverageData_parse(const char *text, CoverageData *data) {
+  memset(data, 0, sizeof(*data));
+
+  size_t headerLength = strlen(COVERAGE_HEADER);
+  if (strncmp(text, COVERAGE_HEADER, headerLength) != 0 || text[headerLength] != '\n') return -1;
+
+  CoverageSection *section = NULL;
+  const char *cursor = text + headerLength + 1;
+  while (*cursor) {
+    const char *end = strchr(cursor, '\n');
+    size_t length = end ? (size_t)(end - cursor) : strlen(cursor);
+    char *line = strndup(cursor, length);
+    cursor += length + (end ? 1 : 0);
+
+    int number, group, branch;
+    long long hits;
+    int ok = 1;
+    if (strncmp(line, "source ", 7) == 0 && section == NULL) {
+      section = addSection(data, line + 7);
+    } else if (section && sscanf(line, "line %d %lld", &number, &hits) == 2) {
+      addEntry(section, number, -1, 0, hits);
+    } else if (section && sscanf(line, "branch %d %d %d %lld", &number, &group, &branch, &hits) == 4) {
+      addEntry(section, number, group, branch, hits);
+    } else if (section && strcmp(line, "end") == 0) {
+      section = NULL;
+    } else if (line[0] != '\0') {
+      ok = 0;
+    }
+    free(line);
+
+    if (!ok) {
+      CoverageData_free(data);
+      return -1;
+    }
+  }
+
+  //  A section cut off by a crash while writing is dropped
+  if (section) {
+    freeSection(section);
+    data->count--;
+  }
+  return 0;
+}
+
+void CoverageData_free(CoverageData *data) {
+  for (int i = 0; i < data->count; i++) {
+    freeSection(&data->sections[i]);
+  }
+  free(data->sections);
+  memset(data, 0, sizeof(*data));
+}
+
+static void writeData(FILE *out, CoverageData *data) {
+  fprintf(out, "%s\n", COVERAGE_HEADER);
+  for (int i = 0; i < data->count; i++) {
+    CoverageSection *section = &data->sections[i];
+    fprintf(out, "source %s\n", section->source);
+    for (int j = 0; j < section->count; j++) {
+      CoverageEntry *entry = &section->entries[j];
+      if (entry->group < 0) {
+        fprintf(out, "line %d %lld\n", entry->line, entry->hits);
+      } else {
+        fprintf(out, "branch %d %d %d %lld\n", entry->line, entry->group, entry->branch, entry->hits);
+      }
+    }
+    fprintf(out, "end\n");
+  }
+}
+
+// ============================================================================
+// Runtime (called from instrumented programs)
+// ============================================================================
+
+static const char *g_source = NULL;
+static int64_t g_count = 0;
+static int64_t **g_counters = NULL;
+static const int32_t *g_records = NULL;
+static int g_registered = 0;
+
+void franz_coverage_register(const char *source, int64_t count, int64_t **counters, const int32_t *records) {
+  static int atexitInstalled = 0;
+  g_source = source;
+  g_count = count;
+  g_counters = counters;
+  g_records = records;
+  g_registered = 1;
+
+  //  Runtime errors and signals end the program through exit()
+  if (!atexitInstalled) {
+    atexit(franz_coverage_flush);
+    atexitInstalled = 1;
+  }
+}
+
+static int compareEntries(const void *a, const void *b) {
+  const CoverageEntry *x = a, *y = b;
+  if (x->line != y->line) return x->line - y->line;
+  if (x->group != y->group) return x->group - y->group;
+  return x->branch - y->branch;
+}
+
+//  This run's counters as a section: one entry per line (its most executed
+// counter, since a line may be counted in several functions) and per arm
+static void collectSection(CoverageSection *section) {
+  for (int64_t i = 0; i < g_count; i++) {
+    addEntry(section, g_records[i * 3], g_records[i * 3 + 1], g_records[i * 3 + 2], *g_counters[i]);
+  }
+  qsort(section->entries, section->count, sizeof(CoverageEntry), compareEntries);
+
+  int kept = 0;
+  for (int i = 0; i < section->count; i++) {
+    CoverageEntry *entry = &section->entries[i];
+    if (kept > 0 && entry->group < 0 && section->entries[kept - 1].group < 0 &&
+        section->entries[kept - 1].line == entry->line) {
+      CoverageEntry *previous = &section->entries[kept - 1];
+      if (entry->hits > previous->hits) previous->hits = entry->hits;
+      continue;
+    }
+    section->entries[kept++] = *entry;
+  }
+  section->count = kept;
+}
+
+static int sameCounters(CoverageSection *a, CoverageSection *b) {
+  if (a->count != b->count) return 0;
+  for (int i = 0; i < a->count; i++) {
+    if (compareEntries(&a->entries[i], &b->entries[i]) != 0) return 0;
+  }
+  return 1;
+}
+
+//  Add this run to the data read from the file (or replace a stale section)
+static void mergeRun(CoverageData *data, CoverageSection *run) {
+  for (int i = 0; i < data->count; i++) {
+    CoverageSection *section = &data->sections[i];
+    if (strcmp(section->source, run->source) != 0) continue;
+    if (sameCounters(section, run)) {
+      for (int j = 0; j < section->count; j++) {
+        section->entries[j].hits += run->entries[j].hits;
+      }
+      freeSection(run);
+    } else {
+      freeSection(section);
+      *section = *run;
+    }
+    return;
+  }
+
+  if (data->count == data->capacity) {
+    data->capacity = data->capacity ? data->capacity * 2 : 4;
+    data->sections = realloc(data->sections, sizeof(CoverageSection) * data->capacity);
+  }
+  data->sections[data->count++] = *run;
+}
+
+static char *readAll(int fd) {
+  size_t length = 0, capacity = 4096;
+  char *text = malloc(capacity);
+  ssize_t got;
+  while ((got = read(fd, text + length, capacity - length - 1)) > 0) {
+    length += got;
+    if (capacity - length < 2) {
+      capacity *= 2;
+      text = realloc(text, capacity);
+    }
+  }
+  text[length] = '\0';
+  return text;
+}
+
+void franz_coverage_flush(void) {
+  if (!g_registered) return;
+  g_registered = 0;
+  fflush(stdout);
+
+  const char *path = Coverage_dataPath();
+  int fd = open(path, O_RDWR | O_CREAT, 0644);
+  if (fd < 0) {
+    fprintf(stderr, "Warning: Could not write coverage data to '%s'.\n", path);
+    return;
+  }
+  flock(fd, LOCK_EX);
+
+  char *text = readAll(fd);
+  CoverageData data = { 0 };
+  if (text[0] != '\0' && CoverageData_parse(text, &data) != 0) {
+    fprintf(stderr, "Warning: Replacing '%s', which is not coverage data.\n", path);
+  }
+  free(text);
+
+  CoverageSection run = { 0 };
+  run.source = strdup(g_source);
+  collectSection(&run);
+  mergeRun(&data, &run);
+
+  char *output = NULL;
+  size_t outputLength = 0;
+  FILE *buffer = open_memstream(&output, &outputLength);
+  writeData(buffer, &data);
+  fclose(buffer);
+  CoverageData_free(&data);
+
+  if (ftruncate(fd, 0) != 0 || pwrite(fd, output, outputLength, 0) != (ssize_t)outputLength) {
+    fprintf(stderr, "Warning: Could not write coverage data to '%s'.\n", path);
+  }
+  free(output);
+  close(fd);
+}
@@ -0,0 +1,101 @@
+#ifndef COVERAGE_H
+#define COVERAGE_H
+
+#include <stdint.h>
+
+/**
+ * Coverage data for programs compiled with --coverage
+ *
+ * Instrumented programs (see src/llvm-coverage/llvm_coverage.h) register
+ * their counters on entry to main() and write them to the data file when
+ * main() returns or the program exits. The file is FRANZ_COVERAGE_FILE,
+ * or franz.coverage in the working directory, and holds one section per
+ * program:
+ *
+ *   franz-coverage 1
+ *   source /home/me/app.franz
+ *   line 3 12               line 3 ran 12 times
+ *   branch 5 0 0 4          line 5, branch group 0, arm 0 taken 4 times
+ *   branch 5 0 1 8
+ *   end
+ *
+ * Runs of the same program add up; a section whose lines changed (the
+ * program was edited) is replaced. Writers lock the file, so programs can
+ * run in parallel. `franz coverage report` prints the data as an
+ * annotated listing or as lcov tracefile records.
+ */
+
+//  One counted line (group -1) or one arm of a branch
+typedef struct CoverageEntry {
+  int line;
+  int group;      // branch group (one per if/when/unless), -1 for a line
+  int branch;     // arm index within the group (0 = then, 1 = else)
+  long long hits;
+} CoverageEntry;
+
+//  Counters of one program, sorted by line, group and arm
+typedef struct CoverageSection {
+  char *source;   // absolute path of the .franz file, or "<stdin>"
+  CoverageEntry *entries;
+  int count;
+  int capacity;
+} CoverageSection;
+
+typedef struct CoverageData {
+  CoverageSection *sections;
+  int count;
+  int capacity;
+} CoverageData;
+
+/**
+ * Path of the coverage data file (FRANZ_COVERAGE_FILE or franz.coverage)
+ *
+ * @return Static path
+ */
+const char *Coverage_dataPath(void);
+
+/**
+ * Parse the contents of a coverage data file
+ *
+ * @param text - File contents (NUL-terminated)
+ * @param data - Output (initialized by this function; free with CoverageData_free)
+ * @return 0 on success, -1 if the text is not coverage data
+ */
+int CoverageData_parse(const char *text, CoverageData *data);
+
+/**
+ * Free parsed coverage data
+ *
+ * @param data - Data filled by CoverageData_parse
+ */
+void CoverageData_free(CoverageData *data);
+
+/**
+ * Register the counters of the running program
+ *
+ * Called first thing in main() of an instrumented program.
+ *
+ * @param source - Absolute path of the program's source, or "<stdin>"
+ * @param count - Number of counters
+ * @param counters - Counter addresses
+ * @param records - (line, group, arm) of each counter; group -1 marks a line counter
+ */
+void franz_coverage_register(const char *source, int64_t count, int64_t **counters, const int32_t *records);
+
+/**
+ * Add the registered counters to the data file
+ *
+ * Called on return from main() and at exit; only the first call writes.
+ */
+void franz_coverage_flush(void);
+
+/**
+ * Run `franz coverage report [--lcov] [-o FILE] [data-file]`
+ *
+ * @param argc - Number of arguments after "coverage"
+ * @param argv - Arguments after "coverage"
+ * @return Exit code: 0 on success, 1 on error
+ */
+int Coverage_main(int argc, char *argv[]);
+
+#endif
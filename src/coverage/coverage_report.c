@@ -0,0 +1,200 @@
+#include "coverage.h"
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <stdbool.h>
+
+/**
+ * `franz coverage report`: the coverage data file as an annotated listing
+ * (gcov style) or as lcov tracefile records for genhtml and CI services.
+ */
+
+static char *readFile(const char *path) {
+  FILE *file = fopen(path, "rb");
+  if (file == NULL) return NULL;
+  fseek(file, 0, SEEK_END);
+  long length = ftell(file);
+  rewind(file);
+  char *text = malloc(length + 1);
+  size_t got = fread(text, 1, length, file);
+  text[got] = '\0';
+  fclose(file);
+  return text;
+}
+
+//  Execution count of a line, or -1 if the line has no counter
+static long long lineHits(CoverageSection *section, int line) {
+  for (int i = 0; i < section->count; i++) {
+    if (section->entries[i].group < 0 && section->entries[i].line == line) return section->entries[i].hits;
+  }
+  return -1;
+}
+
+static double percent(int part, int whole) {
+  return whole > 0 ? 100.0 * part / whole : 0.0;
+}
+
+static void reportLcov(FILE *out, CoverageSection *section) {
+  int linesFound = 0, linesHit = 0, branchesFound = 0, branchesHit = 0;
+
+  fprintf(out, "TN:\nSF:%s\n", section->source);
+  for (int i = 0; i < section->count; i++) {
+    CoverageEntry *entry = &section->entries[i];
+    if (entry->group >= 0) continue;
+    fprintf(out, "DA:%d,%lld\n", entry->line, entry->hits);
+    linesFound++;
+    if (entry->hits > 0) linesHit++;
+  }
+  for (int i = 0; i < section->count; i++) {
+    CoverageEntry *entry = &section->entries[i];
+    if (entry->group < 0) continue;
+    //  "-" marks an arm whose branch was never reached
+    if (lineHits(section, entry->line) == 0) {
+      fprintf(out, "BRDA:%d,%d,%d,-\n", entry->line, entry->group, entry->branch);
+    } else {
+      fprintf(out, "BRDA:%d,%d,%d,%lld\n", entry->line, entry->group, entry->branch, entry->hits);
+    }
+    branchesFound++;
+    if (entry->hits > 0) branchesHit++;
+  }
+  fprintf(out, "LF:%d\nLH:%d\nBRF:%d\nBRH:%d\nend_of_record\n",
+          linesFound, linesHit, branchesFound, branchesHit);
+}
+
+static void printBranches(FILE *out, CoverageSection *section, int line) {
+  long long hits = lineHits(section, line);
+  for (int i = 0; i < section->count; i++) {
+    CoverageEntry *entry = &section->entries[i];
+    if (entry->group < 0 || entry->line != line) continue;
+    if (hits == 0) {
+      fprintf(out, "branch %2d never executed\n", entry->branch);
+    } else {
+      fprintf(out, "branch %2d taken %lld\n", entry->branch, entry->hits);
+    }
+  }
+}
+
+static void reportText(FILE *out, CoverageSection *section) {
+  int linesFound = 0, linesHit = 0, branchesFound = 0, branchesHit = 0, lastLine = 0;
+  for (int i = 0; i < section->count; i++) {
+    CoverageEntry *entry = &section->entries[i];
+    if (entry->group < 0) {
+      linesFound++;
+      if (entry->hits > 0) linesHit++;
+    } else {
+      branchesFound++;
+      if (entry->hits > 0) branchesHit++;
+    }
+    if (entry->line > lastLine) lastLine = entry->line;
+  }
+
+  fprintf(out, "File '%s'\n", section->source);
+  fprintf(out, "Lines executed: %.2f%% of %d\n", percent(linesHit, linesFound), linesFound);
+  if (branchesFound > 0) {
+    fprintf(out, "Branches taken: %.2f%% of %d\n", percent(branchesHit, branchesFound), branchesFound);
+  }
+  fprintf(out, "\n");
+
+  //  Without the source, list the counted lines only
+  char *source = readFile(section->source);
+  if (source == NULL) {
+    fprintf(out, "(source not available)\n");
+  }
+
+  const char *cursor = source;
+  for (int line = 1; source ? *cursor != '\0' : line <= lastLine; line++) {
+    const char *text = "";
+    int length = 0;
+    if (source) {
+      const char *end = strchr(cursor, '\n');
+      length = end ? (int)(end - cursor) : (int)strlen(cursor);
+      text = cursor;
+      cursor += length + (end ? 1 : 0);
+      if (length > 0 && text[length - 1] == '\r') length--;
+    }
+
+    long long hits = lineHits(section, line);
+    if (hits < 0 && !source) continue;
+    if (hits < 0) {
+      fprintf(out, "%9s:%5d:%.*s\n", "-", line, length, text);
+    } else if (hits == 0) {
+      fprintf(out, "%9s:%5d:%.*s\n", "#####", line, length, text);
+    } else {
+      fprintf(out, "%9lld:%5d:%.*s\n", hits, line, length, text);
+    }
+    printBranches(out, section, line);
+  }
+  free(source);
+}
+
+static int report(int argc, char *argv[]) {
+  bool lcov = false;
+  const char *outputPath = NULL;
+  const char *dataPath = NULL;
+
+  for (int i = 0; i < argc; i++) {
+    if (strcmp(argv[i], "--lcov") == 0) {
+      lcov = true;
+    } else if (strcmp(argv[i], "-o") == 0) {
+      if (i + 1 >= argc) {
+        fprintf(stderr, "Error: Option '-o' requires an output path.\n");
+        return 1;
+      }
+      outputPath = argv[++i];
+    } else if (argv[i][0] == '-') {
+      fprintf(stderr, "Error: Unknown option '%s' for 'franz coverage report' (expected --lcov or -o FILE).\n", argv[i]);
+      return 1;
+    } else if (dataPath == NULL) {
+      dataPath = argv[i];
+    } else {
+      fprintf(stderr, "Error: Unexpected argument '%s'.\n", argv[i]);
+      return 1;
+    }
+  }
+  if (dataPath == NULL) dataPath = Coverage_dataPath();
+
+  char *text = readFile(dataPath);
+  if (text == NULL) {
+    fprintf(stderr, "Error: Could not read coverage data '%s' (run a program with --coverage first).\n", dataPath);
+    return 1;
+  }
+  CoverageData data;
+  int parsed = CoverageData_parse(text, &data);
+  free(text);
+  if (parsed != 0) {
+    fprintf(stderr, "Error: '%s' is not a coverage data file.\n", dataPath);
+    return 1;
+  }
+
+  FILE *out = stdout;
+  if (outputPath != NULL) {
+    out = fopen(outputPath, "w");
+    if (out == NULL) {
+      fprintf(stderr, "Error: Could not write '%s'.\n", outputPath);
+      CoverageData_free(&data);
+      return 1;
+    }
+  }
+
+  for (int i = 0; i < data.count; i++) {
+    if (lcov) {
+      reportLcov(out, &data.sections[i]);
+    } else {
+      if (i > 0) fprintf(out, "\n");
+      reportText(out, &data.sections[i]);
+    }
+  }
+
+  if (out != stdout) fclose(out);
+  CoverageData_free(&data);
+  return 0;
+}
+
+int Coverage_main(int argc, char *argv[]) {
+  if (argc < 1 || strcmp(argv[0], "report") != 0) {
+    fprintf(stderr, "Error: Unknown coverage command '%s'. Use 'franz coverage report [--lcov] [-o FILE] [data-file]'.\n",
+            argc > 0 ? argv[0] : "");
+    return 1;
+  }
+  return report(argc - 1, argv + 1);
+}
@@ -0,0 +1,403 @@
+#include "diagnostic.h"
+#include "../json/json.h"
+#include <stdlib.h>
+#include <string.h>
+#include <stdarg.h>
+
+//  Registered source text, copied so diagnostics outlive the caller's buffer
+typedef struct DiagnosticSource {
+  char *path;
+  char *text;
+  int length;
+  const char *origin;  // Buffer the text was registered from (matched by lex)
+} DiagnosticSource;
+
+static DiagnosticSource *g_sources = NULL;
+static int g_sourceCount = 0;
+static int g_sourceCapacity = 0;
+static int g_primarySource = -1;
+
+static Diagnostic *g_diagnostics = NULL;
+static int g_diagnosticCount = 0;
+static int g_diagnosticCapacity = 0;
+
+static int g_reportedCount = 0;
+
+static DiagnosticFormat g_format = DIAGNOSTIC_FORMAT_HUMAN;
+static bool g_formatSet = false;
+
+bool Diagnostic_parseFormat(const char *name, DiagnosticFormat *format) {
+  if (strcmp(name, "human") == 0) {
+    *format = DIAGNOSTIC_FORMAT_HUMAN;
+    return true;
+  }
+  if (strcmp(name, "json") == 0) {
+    *format = DIAGNOSTIC_FORMAT_JSON;
+    return true;
+  }
+  return false;
+}
+
+void Diagnostic_setFormat(DiagnosticFormat format) {
+  g_format = format;
+  g_formatSet = true;
+}
+
+DiagnosticFormat Diagnostic_getFormat(void) {
+  if (!g_formatSet) {
+    const char *env = getenv("FRANZ_MESSAGE_FORMAT");
+    if (env == NULL || !Diagnostic_parseFormat(env, &g_format)) {
+      g_format = DIAGNOSTIC_FORMAT_HUMAN;
+    }
+    g_formatSet = true;
+  }
+  return g_format;
+}
+
+int Diagnostic_addSource(const char *path, const char *code, int length) {
+  if (code == NULL || length < 0) return -1;
+
+  if (g_sourceCount >= g_sourceCapacity) {
+    int newCapacity = g_sourceCapacity == 0 ? 4 : g_sourceCapacity * 2;
+    DiagnosticSource *grown = realloc(g_sources, sizeof(DiagnosticSource) * newCapacity);
+    if (grown == NULL) return -1;
+    g_sources = grown;
+    g_sourceCapacity = newCapacity;
+  }
+
+  DiagnosticSource *src = &g_sources[g_sourceCount];
+  src->path = strdup(path != NULL ? path : "<input>");
+  src->text = malloc(length + 1);
+  memcpy(src->text, code, length);
+  src->text[length] = '\0';
+  src->length = length;
+  src->origin = code;
+
+  return g_sourceCount++;
+}
+
+int Diagnostic_findSource(const char *code, int length) {
+  //  Newest first: a freed buffer may be reused for a later registration
+  for (int i = g_sourceCount - 1; i >= 0; i--) {
+    DiagnosticSource *src = &g_sources[i];
+    if (src->origin == code && src->length == length && memcmp(src->text, code, length) == 0) {
+      return i;
+    }
+  }
+  return -1;
+}
+
+void Diagnostic_setPrimarySource(int sourceId) {
+  g_primarySource = sourceId;
+}
+
+DiagnosticSpan Diagnostic_spanOfToken(const Token *tok) {
+  DiagnosticSpan span = {
+    tok->sourceId, tok->lineNumber, tok->column, tok->endLine, tok->endColumn
+  };
+  return span;
+}
+
+DiagnosticSpan Diagnostic_spanOfNode(const AstNode *node) {
+  DiagnosticSpan span = {
+    node->sourceId, node->lineNumber, node->column, node->endLine, node->endColumn
+  };
+  return span;
+}
+
+DiagnosticSpan Diagnostic_spanOfLine(int line) {
+  DiagnosticSpan span = { -1, line, 0, line, 0 };
+  return span;
+}
+
+static const DiagnosticSource *lookupSource(int sourceId) {
+  if (sourceId < 0) sourceId = g_primarySource;
+  if (sourceId < 0 || sourceId >= g_sourceCount) return NULL;
+  return &g_sources[sourceId];
+}
+
+// Finds the text of a 1-based line; returns its length (without \r\n) or -1
+static int findLine(const DiagnosticSource *src, int line, const char **start) {
+  const char *p = src->text;
+  const char *end = src->text + src->length;
+
+  for (int current = 1; current < line; current++) {
+    p = memchr(p, '\n', end - p);
+    if (p == NULL) return -1;
+    p++;
+  }
+
+  const char *lineEnd = memchr(p, '\n', end - p);
+  if (lineEnd == NULL) lineEnd = end;
+  if (lineEnd > p && lineEnd[-1] == '\r') lineEnd--;
+
+  *start = p;
+  return (int) (lineEnd - p);
+}
+
+void Diagnostic_printSnippet(FILE *out, DiagnosticSpan span) {
+  const DiagnosticSource *src = lookupSource(span.sourceId);
+  if (src == NULL || span.line <= 0) return;
+
+  const char *text;
+  int length = findLine(src, span.line, &text);
+  if (length < 0) return;
+
+  int column = span.column;
+  int endColumn;
+
+  if (column <= 0) {
+    //  Line-only position: underline the line without its indentation
+    int first = 0, last = length;
+    while (first < length && (text[first] == ' ' || text[first] == '\t')) first++;
+    while (last > first && (text[last - 1] == ' ' || text[last - 1] == '\t')) last--;
+    column = first + 1;
+    endColumn = last > first ? last + 1 : column + 1;
+  } else {
+    if (column > length + 1) column = length + 1;
+    if (span.endLine > span.line) {
+      //  Multi-line nodes are underlined to the end of their first line
+      endColumn = length + 1;
+    } else {
+      endColumn = span.endColumn;
+    }
+    if (endColumn > length + 1) endColumn = length + 1;
+    if (endColumn <= column) endColumn = column + 1;
+  }
+
+  int width = snprintf(NULL, 0, "%d", span.line);
+
+  if (span.column > 0) {
+    fprintf(out, "%*s--> %s:%d:%d\n", width, "", src->path, span.line, column);
+  } else {
+    fprintf(out, "%*s--> %s:%d\n", width, "", src->path, span.line);
+  }
+  fprintf(out, "%*s |\n", width, "");
+  fprintf(out, "%d | %.*s\n", span.line, length, text);
+  fprintf(out, "%*s | ", width, "");
+
+  // Keep tabs so the carets line up with the quoted source
+  for (int i = 0; i < column - 1; i++) {
+    fputc(text[i] == '\t' ? '\t' : ' ', out);
+  }
+  for (int i = column; i < endColumn; i++) {
+    fputc('^', out);
+  }
+  fputc('\n', out);
+}
+
+//  "file", "line", "column", "endLine" and "endColumn" members of a span
+// (null when unknown; line-only spans have no columns)
+static void appendSpanJson(JsonBuffer *json, DiagnosticSpan span) {
+  const DiagnosticSource *src = lookupSource(span.sourceId);
+
+  JsonBuffer_append(json, "\"file\":");
+  JsonBuffer_appendString(json, src != NULL ? src->path : NULL);
+
+  if (span.line > 0) {
+    JsonBuffer_appendf(json, ",\"line\":%d", span.line);
+  } else {
+    JsonBuffer_append(json, ",\"line\":null");
+  }
+
+  if (span.line > 0 && span.column > 0) {
+    JsonBuffer_appendf(json, ",\"column\":%d,\"endLine\":%d,\"endColumn\":%d",
+                       span.column, span.endLine, span.endColumn);
+  } else {
+    JsonBuffer_append(json, ",\"column\":null,\"endLine\":null,\"endColumn\":null");
+  }
+}
+
+//  One JSON line per diagnostic on stderr
+static void reportJson(const char *label, ErrorCode code, DiagnosticSpan span, const char *message,
+                       const DiagnosticRelated *related, int relatedCount) {
+  JsonBuffer json;
+  JsonBuffer_init(&json);
+
+  JsonBuffer_append(&json, "{");
+  appendSpanJson(&json, span);
+  JsonBuffer_appendf(&json, ",\"severity\":\"%s\"", strcmp(label, "Warning") == 0 ? "warning" : "error");
+  JsonBuffer_append(&json, ",\"code\":");
+  JsonBuffer_appendString(&json, ErrorCode_name(code));
+  JsonBuffer_append(&json, ",\"kind\":");
+  JsonBuffer_appendString(&json, label);
+  JsonBuffer_append(&json, ",\"message\":");
+  JsonBuffer_appendString(&json, message);
+
+  JsonBuffer_append(&json, ",\"related\":[");
+  for (int i = 0; i < relatedCount; i++) {
+    JsonBuffer_append(&json, i > 0 ? ",{" : "{");
+    appendSpanJson(&json, related[i].span);
+    JsonBuffer_append(&json, ",\"message\":");
+    JsonBuffer_appendString(&json, related[i].message);
+    JsonBuffer_append(&json, "}");
+  }
+  JsonBuffer_append(&json, "]}");
+
+  fprintf(stderr, "%s\n", json.data);
+  fflush(stderr);
+  JsonBuffer_free(&json);
+}
+
+//  " [F0101]" after the message, or nothing for diagnostics without a code
+static void printCode(FILE *out, ErrorCode code) {
+  const char *name = ErrorCode_name(code);
+  if (name != NULL) fprintf(out, " [%s]", name);
+}
+
+static void report(FILE *out, const char *label, ErrorCode code, DiagnosticSpan span, const char *message,
+                   const DiagnosticRelated *related, int relatedCount) {
+  g_reportedCount++;
+
+  if (Diagnostic_getFormat() == DIAGNOSTIC_FORMAT_JSON) {
+    reportJson(label, code, span, message, related, relatedCount);
+    return;
+  }
+
+  if (span.line > 0) {
+    fprintf(out, "%s @ Line %d: %s", label, span.line, message);
+  } else {
+    fprintf(out, "%s: %s", label, message);
+  }
+  printCode(out, code);
+  fputc('\n', out);
+
+  Diagnostic_printSnippet(out, span);
+
+  for (int i = 0; i < relatedCount; i++) {
+    fprintf(out, "note: %s\n", related[i].message);
+    Diagnostic_printSnippet(out, related[i].span);
+  }
+}
+
+void Diagnostic_report(FILE *out, const char *label, ErrorCode code, DiagnosticSpan span, const char *format, ...) {
+  char message[4096];
+  va_list args;
+  va_start(args, format);
+  vsnprintf(message, sizeof(message), format, args);
+  va_end(args);
+
+  report(out, label, code, span, message, NULL, 0);
+}
+
+void Diagnostic_error(ErrorCode code, DiagnosticSpan span, const char *format, ...) {
+  char message[4096];
+  va_list args;
+  va_start(args, format);
+  vsnprintf(message, sizeof(message), format, args);
+  va_end(args);
+
+  g_reportedCount++;
+
+  if (Diagnostic_getFormat() == DIAGNOSTIC_FORMAT_JSON) {
+    reportJson("Compile Error", code, span, message, NULL, 0);
+    return;
+  }
+
+  if (span.line > 0) {
+    fprintf(stderr, "ERROR: %s at line %d", message, span.line);
+  } else {
+    fprintf(stderr, "ERROR: %s", message);
+  }
+  printCode(stderr, code);
+  fputc('\n', stderr);
+  Diagnostic_printSnippet(stderr, span);
+}
+
+void Diagnostic_add(const char *label, ErrorCode code, DiagnosticSpan span, const char *format, ...) {
+  if (g_diagnosticCount >= g_diagnosticCapacity) {
+    int newCapacity = g_diagnosticCapacity == 0 ? 8 : g_diagnosticCapacity * 2;
+    Diagnostic *grown = realloc(g_diagnostics, sizeof(Diagnostic) * newCapacity);
+    if (grown == NULL) return;
+    g_diagnostics = grown;
+    g_diagnosticCapacity = newCapacity;
+  }
+
+  char message[4096];
+  va_list args;
+  va_start(args, format);
+  vsnprintf(message, sizeof(message), format, args);
+  va_end(args);
+
+  Diagnostic *diagnostic = &g_diagnostics[g_diagnosticCount++];
+  diagnostic->label = label;
+  diagnostic->code = code;
+  diagnostic->message = strdup(message);
+  diagnostic->span = span;
+  diagnostic->related = NULL;
+  diagnostic->relatedCount = 0;
+}
+
+void Diagnostic_addRelated(DiagnosticSpan span, const char *format, ...) {
+  if (g_diagnosticCount == 0) return;
+  Diagnostic *diagnostic = &g_diagnostics[g_diagnosticCount - 1];
+
+  DiagnosticRelated *grown = realloc(diagnostic->related,
+                                     sizeof(DiagnosticRelated) * (diagnostic->relatedCount + 1));
+  if (grown == NULL) return;
+  diagnostic->related = grown;
+
+  char message[4096];
+  va_list args;
+  va_start(args, format);
+  vsnprintf(message, sizeof(message), format, args);
+  va_end(args);
+
+  DiagnosticRelated *related = &diagnostic->related[diagnostic->relatedCount++];
+  related->span = span;
+  related->message = strdup(message);
+}
+
+int Diagnostic_count(void) {
+  return g_diagnosticCount;
+}
+
+int Diagnostic_reportedCount(void) {
+  return g_reportedCount;
+}
+
+const Diagnostic *Diagnostic_get(int index) {
+  if (index < 0 || index >= g_diagnosticCount) return NULL;
+  return &g_diagnostics[index];
+}
+
+// Source order: by source, then line, then column
+static int compareDiagnostics(const void *a, const void *b) {
+  const DiagnosticSpan *x = &((const Diagnostic *) a)->span;
+  const DiagnosticSpan *y = &((const Diagnostic *) b)->span;
+  if (x->sourceId != y->sourceId) return x->sourceId - y->sourceId;
+  if (x->line != y->line) return x->line - y->line;
+  return x->column - y->column;
+}
+
+void Diagnostic_flush(FILE *out) {
+  // Insertion sort keeps diagnostics at the same position in the order they were found
+  for (int i = 1; i < g_diagnosticCount; i++) {
+    Diagnostic current = g_diagnostics[i];
+    int j = i - 1;
+    while (j >= 0 && compareDiagnostics(&g_diagnostics[j], &current) > 0) {
+      g_diagnostics[j + 1] = g_diagnostics[j];
+      j--;
+    }
+    g_diagnostics[j + 1] = current;
+  }
+
+  for (int i = 0; i < g_diagnosticCount; i++) {
+    Diagnostic *diagnostic = &g_diagnostics[i];
+    report(out, diagnostic->label, diagnostic->code, diagnostic->span, diagnostic->message,
+           diagnostic->related, diagnostic->relatedCount);
+  }
+
+  Diagnostic_clear();
+}
+
+void Diagnostic_clear(void) {
+  for (int i = 0; i < g_diagnosticCount; i++) {
+    free(g_diagnostics[i].message);
+    for (int j = 0; j < g_diagnostics[i].relatedCount; j++) {
+      free(g_diagnostics[i].related[j].message);
+    }
+    free(g_diagnostics[i].related);
+  }
+  g_diagnosticCount = 0;
+}
@@ -0,0 +1,204 @@
+#ifndef DIAGNOSTIC_H
+#define DIAGNOSTIC_H
+
+#include <stdio.h>
+#include <stdbool.h>
+#include "../tokens.h"
+#include "../ast.h"
+#include "error_codes.h"
+
+/*
+ * Franz Diagnostics - shared source snippet renderer
+ *
+ * Sources are registered once (the program, each imported module, each REPL
+ * entry) and tokens/AST nodes remember which source they came from. Any
+ * error site that has a token, node or line number can then print the
+ * offending source line with a caret underline, rustc style. Syntax errors
+ * are collected (Diagnostic_add) so one run reports all of them:
+ *
+ *   Syntax Error @ Line 3: Unexpected rbracket token.
+ *    --> prog.franz:3:9
+ *     |
+ *   3 | (add 1 2])
+ *     |         ^
+ *
+ * With --message-format=json (or FRANZ_MESSAGE_FORMAT=json) every reported
+ * diagnostic is written to stderr as one JSON object per line instead.
+ */
+
+//  How diagnostics are printed (--message-format)
+typedef enum DiagnosticFormat {
+  DIAGNOSTIC_FORMAT_HUMAN,  // Label, message and source snippet
+  DIAGNOSTIC_FORMAT_JSON    // One JSON object per line on stderr
+} DiagnosticFormat;
+
+//  Region of a registered source (lines and columns are 1-based, end is exclusive)
+//  column 0 means only the line is known - the whole line gets underlined
+typedef struct DiagnosticSpan {
+  int sourceId;   // Registered source (-1 = the primary source)
+  int line;
+  int column;
+  int endLine;
+  int endColumn;
+} DiagnosticSpan;
+
+//  Secondary location of a diagnostic, e.g. where a name was first defined
+typedef struct DiagnosticRelated {
+  DiagnosticSpan span;
+  char *message;
+} DiagnosticRelated;
+
+//  Collected diagnostic (the lexer and parser collect errors instead of exiting)
+typedef struct Diagnostic {
+  const char *label;     // Error kind, e.g. "Syntax Error" (static string)
+  ErrorCode code;        // ERROR_CODE_NONE for warnings without a code
+  char *message;
+  DiagnosticSpan span;
+  DiagnosticRelated *related;
+  int relatedCount;
+} Diagnostic;
+
+/**
+ * Parse a --message-format name ("human" or "json").
+ * @param name Format name
+ * @param format Set to the parsed format
+ * @return false if the name is unknown
+ */
+bool Diagnostic_parseFormat(const char *name, DiagnosticFormat *format);
+
+/**
+ * Select how diagnostics are printed from now on.
+ * @param format Output format
+ */
+void Diagnostic_setFormat(DiagnosticFormat format);
+
+/**
+ * Current output format. Until Diagnostic_setFormat() is called it comes from
+ * the FRANZ_MESSAGE_FORMAT environment variable, so native programs started by
+ * franz report runtime errors in the format franz was asked for.
+ * @return Output format
+ */
+DiagnosticFormat Diagnostic_getFormat(void);
+
+/**
+ * Register source text so diagnostics can quote it.
+ * The text is copied; the caller keeps ownership of code.
+ * @param path Name shown in the " --> path:line:col" header
+ * @param code Source text
+ * @param length Length of code in bytes
+ * @return Source id stored in tokens lexed from this text
+ */
+int Diagnostic_addSource(const char *path, const char *code, int length);
+
+/**
+ * Find the source registered for a buffer about to be lexed.
+ * @param code Buffer passed to lex()
+ * @param length Length of code in bytes
+ * @return Source id, or -1 if the buffer was never registered
+ */
+int Diagnostic_findSource(const char *code, int length);
+
+/**
+ * Mark the source that line-only diagnostics (runtime errors) refer to.
+ * @param sourceId Id returned by Diagnostic_addSource
+ */
+void Diagnostic_setPrimarySource(int sourceId);
+
+/**
+ * Span covering a single token.
+ * @param tok Token with lexer position information
+ * @return Span of the token
+ */
+DiagnosticSpan Diagnostic_spanOfToken(const Token *tok);
+
+/**
+ * Span covering an AST node (first to last token it was parsed from).
+ * Nodes built outside the parser fall back to their whole line.
+ * @param node AST node
+ * @return Span of the node
+ */
+DiagnosticSpan Diagnostic_spanOfNode(const AstNode *node);
+
+/**
+ * Span covering a whole line of the primary source.
+ * @param line 1-based line number
+ * @return Span of the line
+ */
+DiagnosticSpan Diagnostic_spanOfLine(int line);
+
+/**
+ * Print the " --> path:line:col" header, the source line and a caret underline.
+ * Prints nothing when the span's source was never registered.
+ * @param out Stream to print to
+ * @param span Region to underline
+ */
+void Diagnostic_printSnippet(FILE *out, DiagnosticSpan span);
+
+/**
+ * Print "<label> @ Line N: <message> [code]" followed by the source snippet.
+ * In JSON format the record goes to stderr whatever out is.
+ * @param out Stream to print to
+ * @param label Error kind, e.g. "Syntax Error" or "Type Error"
+ * @param code Stable error code (ERROR_CODE_NONE prints none)
+ * @param span Region to underline
+ * @param format printf-style message format
+ */
+void Diagnostic_report(FILE *out, const char *label, ErrorCode code, DiagnosticSpan span, const char *format, ...);
+
+/**
+ * Print a compiler error as "ERROR: <message> at line N [code]" followed by
+ * the source snippet, on stderr. Spans with no line (line 0) print neither.
+ * @param code Stable error code
+ * @param span Region to underline
+ * @param format printf-style message format
+ */
+void Diagnostic_error(ErrorCode code, DiagnosticSpan span, const char *format, ...);
+
+/**
+ * Collect a diagnostic to be printed later with Diagnostic_flush().
+ * @param label Error kind, e.g. "Syntax Error" (must outlive the diagnostic)
+ * @param code Stable error code (ERROR_CODE_NONE for none)
+ * @param span Region to underline
+ * @param format printf-style message format
+ */
+void Diagnostic_add(const char *label, ErrorCode code, DiagnosticSpan span, const char *format, ...);
+
+/**
+ * Attach a related location to the most recently collected diagnostic.
+ * Does nothing when no diagnostic has been collected.
+ * @param span Related region
+ * @param format printf-style message format
+ */
+void Diagnostic_addRelated(DiagnosticSpan span, const char *format, ...);
+
+/**
+ * Number of collected diagnostics.
+ * @return Count since the last flush or clear
+ */
+int Diagnostic_count(void);
+
+/**
+ * Number of diagnostics printed so far (reported, compiler errors and flushed).
+ * @return Count since startup
+ */
+int Diagnostic_reportedCount(void);
+
+/**
+ * Access a collected diagnostic.
+ * @param index 0 <= index < Diagnostic_count()
+ * @return Diagnostic, owned by the collector
+ */
+const Diagnostic *Diagnostic_get(int index);
+
+/**
+ * Print all collected diagnostics in source order, then clear them.
+ * @param out Stream to print to
+ */
+void Diagnostic_flush(FILE *out);
+
+/**
+ * Drop all collected diagnostics without printing them.
+ */
+void Diagnostic_clear(void);
+
+#endif
@@ -0,0 +1,315 @@
+#include "error_codes.h"
+#include <string.h>
+#include <strings.h>
+
+//  Catalog entry: the explanation is prose, the example triggers the error
+//  and the fix shows the corrected program
+typedef struct ErrorCodeInfo {
+  const char *name;
+  const char *title;
+  const char *explanation;
+  const char *example;
+  const char *fix;
+} ErrorCodeInfo;
+
+static const ErrorCodeInfo g_catalog[ERROR_CODE_COUNT] = {
+  [ERROR_CODE_NONE] = { NULL, NULL, NULL, NULL, NULL },
+
+  [ERROR_CODE_SYNTAX] = {
+    "F0100", "Syntax error",
+    "The source could not be read as a Franz program. This code is used for\n"
+    "syntax errors found at run time (for example while loading a module\n"
+    "from a running program); errors found by the compiler have a more\n"
+    "specific F01xx code.",
+    "// broken.franz\n(add 1 2\n// main.franz\n(use \"broken.franz\")",
+    "// broken.franz\n(add 1 2)",
+  },
+  [ERROR_CODE_UNEXPECTED_TOKEN] = {
+    "F0101", "Unexpected token",
+    "The parser found a token where it cannot start or continue an\n"
+    "expression, most often a closing bracket without a matching opening\n"
+    "bracket, or an operator such as `=` in the middle of a call.",
+    "x = (add 1 2))",
+    "x = (add 1 2)",
+  },
+  [ERROR_CODE_UNTERMINATED_STRING] = {
+    "F0102", "Unterminated string",
+    "A string literal must be closed with `\"` on the same line. Use the\n"
+    "escape sequence \\n for a line break inside a string.",
+    "greeting = \"Hello\n(println greeting)",
+    "greeting = \"Hello\"\n(println greeting)",
+  },
+  [ERROR_CODE_INVALID_NUMBER] = {
+    "F0103", "Invalid number literal",
+    "A number literal is malformed: a second decimal point, a 0x/0b/0o\n"
+    "prefix without digits, an exponent without digits, or a hexadecimal\n"
+    "float without its p exponent.",
+    "rate = 1.2.5\nmask = 0x",
+    "rate = 1.25\nmask = 0xFF",
+  },
+  [ERROR_CODE_UNEXPECTED_CHAR] = {
+    "F0104", "Unexpected character",
+    "The lexer found a character that is not part of any Franz token.\n"
+    "Identifiers may contain letters, digits, `_` and `-`; comments start\n"
+    "with //, and decimal numbers need a digit before the point.",
+    "ratio = .5",
+    "ratio = 0.5",
+  },
+  [ERROR_CODE_UNCLOSED_BRACKET] = {
+    "F0105", "Unclosed bracket",
+    "A call `(`, function `{` or list `[` is not closed before the end of\n"
+    "the statement. The error points at the opening bracket; the statements\n"
+    "after it are still checked.",
+    "(println (add 1 2)\nsquare = {x -> <- (multiply x x)",
+    "(println (add 1 2))\nsquare = {x -> <- (multiply x x)}",
+  },
+  [ERROR_CODE_INCOMPLETE_STATEMENT] = {
+    "F0106", "Incomplete statement",
+    "An assignment has no value, `<-` has nothing to return, `mut` is not\n"
+    "followed by a name, or a statement is empty.",
+    "(println \"start\")\ncount =",
+    "(println \"start\")\ncount = 5",
+  },
+
+  [ERROR_CODE_UNDEFINED_VARIABLE] = {
+    "F0201", "Undefined variable",
+    "A name is used that is not defined in any enclosing scope. Names must\n"
+    "be assigned before the statement that reads them, or be parameters of\n"
+    "an enclosing function.",
+    "(println total)\ntotal = 10",
+    "total = 10\n(println total)",
+  },
+  [ERROR_CODE_UNKNOWN_FUNCTION] = {
+    "F0202", "Unknown function",
+    "The called name is neither a built-in function nor a function defined\n"
+    "in the program or in an imported module. Check the spelling and that\n"
+    "the module defining it is imported with `use`.",
+    "(println (sum 1 2))",
+    "(println (add 1 2))",
+  },
+  [ERROR_CODE_BUILTIN_ARITY] = {
+    "F0203", "Wrong number of arguments to a built-in",
+    "A built-in function was called with fewer or more arguments than it\n"
+    "accepts. Arithmetic functions such as `add` take at least two.",
+    "(println (add 1))",
+    "(println (add 1 2))",
+  },
+  [ERROR_CODE_BUILTIN_ARGUMENT] = {
+    "F0204", "Invalid argument to a built-in",
+    "A built-in function was given an argument it can never accept, known\n"
+    "at compile time: for example `join` with an argument that is not a\n"
+    "string.",
+    "(println (join \"id-\" 42))",
+    "(println (join \"id-\" \"42\"))",
+  },
+  [ERROR_CODE_OUTSIDE_LOOP] = {
+    "F0205", "break or continue outside a loop",
+    "`break` and `continue` only work inside the function passed to `loop`\n"
+    "or `while`. To leave a function early, return with `<-`.",
+    "check = {x -> (if (greater_than x 10) {(break)} {})}",
+    "check = {x -> <- (if (greater_than x 10) {<- 0} {<- x})}",
+  },
+  [ERROR_CODE_IMMUTABLE_ASSIGN] = {
+    "F0206", "Reassigning an immutable variable",
+    "Variables are immutable unless they are declared with `mut`. Declare\n"
+    "the variable with `mut` to update it, or pick a new name for the new\n"
+    "value.",
+    "count = 0\ncount = (add count 1)",
+    "mut count = 0\ncount = (add count 1)",
+  },
+  [ERROR_CODE_UNSUPPORTED] = {
+    "F0207", "Unsupported expression",
+    "The expression is valid syntax but the native compiler cannot compile\n"
+    "it: calling the result of a call directly, an empty call `()`, a\n"
+    "function without parameters and body, or `input` outside any function.\n"
+    "Name the value first, or run the program with --interpret.",
+    "make = {n -> <- {x -> <- (add x n)}}\n(println ((make 1) 2))",
+    "add-one = {x -> <- (add x 1)}\n(println (add-one 2))",
+  },
+  [ERROR_CODE_DIVISION_BY_ZERO] = {
+    "F0208", "Division by zero",
+    "The divisor of `divide` is zero at compile time, so the division could\n"
+    "never succeed.",
+    "(println (divide 10 0))",
+    "(println (divide 10 2))",
+  },
+  [ERROR_CODE_CODEGEN_FAILED] = {
+    "F0209", "Code generation failed",
+    "The code generator could not produce code for an expression. Most often\n"
+    "an error inside the expression was reported just before this one; fix\n"
+    "that error first. If this is the only error, the compiler itself is at\n"
+    "fault: please report the program as a bug.",
+    "total = (sum 1 2)",
+    "total = (add 1 2)",
+  },
+
+  [ERROR_CODE_TYPE_MISMATCH] = {
+    "F0301", "Type mismatch",
+    "Two uses of the same value require types that cannot be the same, for\n"
+    "example a variable used both as a string and as a number.",
+    "x = (add 1 \"a\")",
+    "x = (add 1 2)",
+  },
+  [ERROR_CODE_ARGUMENT_TYPE] = {
+    "F0302", "Argument type mismatch",
+    "A function was called with an argument whose type does not match the\n"
+    "parameter. franz-check reports the expected and the actual type.",
+    "(println (multiply \"3\" 2))",
+    "(println (multiply (integer \"3\") 2))",
+  },
+  [ERROR_CODE_ARITY_MISMATCH] = {
+    "F0303", "Wrong number of arguments",
+    "A function was called with a different number of arguments than it\n"
+    "declares parameters, or two function types with different parameter\n"
+    "counts were used for the same value.",
+    "square = {x -> <- (multiply x x)}\n(println (square 2 3))",
+    "square = {x -> <- (multiply x x)}\n(println (square 2))",
+  },
+  [ERROR_CODE_NOT_A_FUNCTION] = {
+    "F0304", "Calling a non-function",
+    "The first element of a call `( ... )` is a value that is not a\n"
+    "function, such as a number, string or list.",
+    "limit = 10\n(println (limit 2))",
+    "limit = {x -> <- (multiply x 10)}\n(println (limit 2))",
+  },
+  [ERROR_CODE_INFINITE_TYPE] = {
+    "F0305", "Infinite type",
+    "Inference would need a type that contains itself, for example a\n"
+    "function that is passed to itself as an argument.",
+    "apply_self = {f -> <- (f f)}",
+    "apply_twice = {f x -> <- (f (f x))}",
+  },
+  [ERROR_CODE_MALFORMED_EXPRESSION] = {
+    "F0306", "Malformed expression",
+    "An expression has a shape the type checker cannot give a type to: an\n"
+    "empty call `()` or an assignment without a name and a value.",
+    "x = ()",
+    "x = (list)",
+  },
+
+  [ERROR_CODE_RUNTIME] = {
+    "F0400", "Runtime error",
+    "The program failed while running. This is the general code for runtime\n"
+    "failures that have no more specific F04xx code; the message says what\n"
+    "went wrong.",
+    "d = 0\n(println (divide 1 d))",
+    "d = 0\n(println (if (is d 0) {<- 0} {<- (divide 1 d)}))",
+  },
+  [ERROR_CODE_RUNTIME_ARITY] = {
+    "F0401", "Wrong number of arguments at run time",
+    "A function value was called with more or fewer arguments than it\n"
+    "accepts. Unlike F0203 this is only known once the call happens, for\n"
+    "example when a callback is passed to a higher-order function.",
+    "(println (map [1, 2] {a b c -> <- a}))",
+    "(println (map [1, 2] {x i -> <- x}))",
+  },
+  [ERROR_CODE_RUNTIME_TYPE] = {
+    "F0402", "Wrong argument type at run time",
+    "A built-in function received a value of a type it does not accept, or\n"
+    "compiled code took a value out of a list that has a different type than\n"
+    "the operation needs (\"Cannot unbox string to int\"). The message names\n"
+    "the type that was passed.",
+    "(println (add (head [\"a\", 1]) 1))",
+    "(println (add (head [2, 1]) 1))",
+  },
+  [ERROR_CODE_RUNTIME_RANGE] = {
+    "F0403", "Argument out of range",
+    "A built-in function received a number outside the values it accepts,\n"
+    "such as a base other than 2, 8, 10 or 16 for `format-int`, an index\n"
+    "past the end of a list or string, or an empty list for `head`.",
+    "(println (format-int 255 3))",
+    "(println (format-int 255 16))",
+  },
+  [ERROR_CODE_FILE] = {
+    "F0404", "File error",
+    "A file could not be read or written: it does not exist, a directory on\n"
+    "its path is missing, or the program lacks permission.",
+    "(write_file \"missing-dir/out.txt\" \"data\")",
+    "(write_file \"out.txt\" \"data\")",
+  },
+  [ERROR_CODE_IMPORT] = {
+    "F0405", "Import error",
+    "A module passed to `use`, `use_as` or `use_with` could not be loaded.\n"
+    "Module paths are relative to the working directory.",
+    "(use \"stdlib/strings.franz\" {(println (upper \"a\"))})",
+    "(use \"stdlib/string.franz\" {(println (upper \"a\"))})",
+  },
+  [ERROR_CODE_CIRCULAR_IMPORT] = {
+    "F0406", "Circular import",
+    "Modules import each other in a cycle, so none of them can finish\n"
+    "loading first. Move the shared definitions into a third module that\n"
+    "both import.",
+    "// a.franz\n(use \"b.franz\")\n// b.franz\n(use \"a.franz\")",
+    "// shared.franz holds the common code\n(use \"shared.franz\")",
+  },
+  [ERROR_CODE_OUT_OF_MEMORY] = {
+    "F0407", "Out of memory",
+    "The runtime could not allocate memory. Very large lists or strings, or\n"
+    "unbounded recursion that builds data, are the usual causes.",
+    "grow = {xs -> <- (grow (cons 0 xs))}\n(grow [])",
+    "grow = {xs n -> <- (if (is n 0) {<- xs} {<- (grow (cons 0 xs) (subtract n 1))})}\n(grow [] 1000)",
+  },
+  [ERROR_CODE_UNCAUGHT_ERROR] = {
+    "F0408", "Uncaught error",
+    "The program raised an error with `(error message)` and no enclosing\n"
+    "`try` or `catch` handled it.",
+    "(error \"config missing\")",
+    "(try {(error \"config missing\")} {e -> (println e)})",
+  },
+};
+
+const char *ErrorCode_name(ErrorCode code) {
+  if (code <= ERROR_CODE_NONE || code >= ERROR_CODE_COUNT) return NULL;
+  return g_catalog[code].name;
+}
+
+const char *ErrorCode_title(ErrorCode code) {
+  if (code <= ERROR_CODE_NONE || code >= ERROR_CODE_COUNT) return NULL;
+  return g_catalog[code].title;
+}
+
+ErrorCode ErrorCode_parse(const char *name) {
+  for (int code = ERROR_CODE_NONE + 1; code < ERROR_CODE_COUNT; code++) {
+    if (strcasecmp(g_catalog[code].name, name) == 0) return (ErrorCode) code;
+  }
+  return ERROR_CODE_NONE;
+}
+
+// Indents every line of text by four spaces
+static void printIndented(FILE *out, const char *text) {
+  fputs("    ", out);
+  for (const char *p = text; *p != '\0'; p++) {
+    fputc(*p, out);
+    if (*p == '\n') fputs("    ", out);
+  }
+  fputc('\n', out);
+}
+
+void ErrorCode_explain(FILE *out, ErrorCode code) {
+  const ErrorCodeInfo *info = &g_catalog[code];
+
+  fprintf(out, "%s: %s\n\n", info->name, info->title);
+  fprintf(out, "%s\n\n", info->explanation);
+  fprintf(out, "Erroneous code example:\n\n");
+  printIndented(out, info->example);
+  fprintf(out, "\nFixed:\n\n");
+  printIndented(out, info->fix);
+}
+
+int ErrorCode_explainMain(const char *name) {
+  if (name == NULL) {
+    for (int code = ERROR_CODE_NONE + 1; code < ERROR_CODE_COUNT; code++) {
+      printf("%s  %s\n", g_catalog[code].name, g_catalog[code].title);
+    }
+    return 0;
+  }
+
+  ErrorCode code = ErrorCode_parse(name);
+  if (code == ERROR_CODE_NONE) {
+    fprintf(stderr, "Error: '%s' is not a Franz error code. Run 'franz --explain' to list them.\n", name);
+    return 1;
+  }
+
+  ErrorCode_explain(stdout, code);
+  return 0;
+}
@@ -0,0 +1,102 @@
+#ifndef ERROR_CODES_H
+#define ERROR_CODES_H
+
+#include <stdio.h>
+
+/*
+ * Franz Error Codes - stable identifiers for every diagnostic
+ *
+ * Each compiler, type-checker and runtime error carries a code such as
+ * F0202 that never changes meaning, so it can be searched for and looked
+ * up with `franz --explain F0202`. Codes are grouped by phase:
+ *
+ *   F01xx  syntax errors (lexer and parser)
+ *   F02xx  compile errors (code generator)
+ *   F03xx  type errors (franz-check, --assert-types)
+ *   F04xx  runtime errors
+ *
+ * Codes are only ever added. A code that is no longer produced keeps its
+ * catalog entry so old logs can still be explained.
+ */
+
+typedef enum ErrorCode {
+  ERROR_CODE_NONE = 0,
+
+  // Syntax
+  ERROR_CODE_SYNTAX,                  // F0100
+  ERROR_CODE_UNEXPECTED_TOKEN,        // F0101
+  ERROR_CODE_UNTERMINATED_STRING,     // F0102
+  ERROR_CODE_INVALID_NUMBER,          // F0103
+  ERROR_CODE_UNEXPECTED_CHAR,         // F0104
+  ERROR_CODE_UNCLOSED_BRACKET,        // F0105
+  ERROR_CODE_INCOMPLETE_STATEMENT,    // F0106
+
+  // Compile
+  ERROR_CODE_UNDEFINED_VARIABLE,      // F0201
+  ERROR_CODE_UNKNOWN_FUNCTION,        // F0202
+  ERROR_CODE_BUILTIN_ARITY,           // F0203
+  ERROR_CODE_BUILTIN_ARGUMENT,        // F0204
+  ERROR_CODE_OUTSIDE_LOOP,            // F0205
+  ERROR_CODE_IMMUTABLE_ASSIGN,        // F0206
+  ERROR_CODE_UNSUPPORTED,             // F0207
+  ERROR_CODE_DIVISION_BY_ZERO,        // F0208
+  ERROR_CODE_CODEGEN_FAILED,          // F0209
+
+  // Type
+  ERROR_CODE_TYPE_MISMATCH,           // F0301
+  ERROR_CODE_ARGUMENT_TYPE,           // F0302
+  ERROR_CODE_ARITY_MISMATCH,          // F0303
+  ERROR_CODE_NOT_A_FUNCTION,          // F0304
+  ERROR_CODE_INFINITE_TYPE,           // F0305
+  ERROR_CODE_MALFORMED_EXPRESSION,    // F0306
+
+  // Runtime
+  ERROR_CODE_RUNTIME,                 // F0400
+  ERROR_CODE_RUNTIME_ARITY,           // F0401
+  ERROR_CODE_RUNTIME_TYPE,            // F0402
+  ERROR_CODE_RUNTIME_RANGE,           // F0403
+  ERROR_CODE_FILE,                    // F0404
+  ERROR_CODE_IMPORT,                  // F0405
+  ERROR_CODE_CIRCULAR_IMPORT,         // F0406
+  ERROR_CODE_OUT_OF_MEMORY,           // F0407
+  ERROR_CODE_UNCAUGHT_ERROR,          // F0408
+
+  ERROR_CODE_COUNT
+} ErrorCode;
+
+/**
+ * Stable identifier of a code ("F0101").
+ * @param code Error code
+ * @return Static string, or NULL for ERROR_CODE_NONE
+ */
+const char *ErrorCode_name(ErrorCode code);
+
+/**
+ * One-line title of a code ("Unexpected token").
+ * @param code Error code
+ * @return Static string, or NULL for ERROR_CODE_NONE
+ */
+const char *ErrorCode_title(ErrorCode code);
+
+/**
+ * Look up a code by its identifier (case-insensitive, "f0101" works).
+ * @param name Identifier as printed in diagnostics
+ * @return Code, or ERROR_CODE_NONE if there is no such code
+ */
+ErrorCode ErrorCode_parse(const char *name);
+
+/**
+ * Print the long-form explanation of a code with an example and a fix.
+ * @param out Stream to print to
+ * @param code Error code (not ERROR_CODE_NONE)
+ */
+void ErrorCode_explain(FILE *out, ErrorCode code);
+
+/**
+ * Run `franz --explain [code]`: explain one code, or list all codes.
+ * @param name Code identifier, or NULL to list the catalog
+ * @return Exit code (1 for an unknown code)
+ */
+int ErrorCode_explainMain(const char *name);
+
+#endif
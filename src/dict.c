@@ -20,7 +20,7 @@ unsigned int Dict_hash(Generic *key, int capacity) {
       hash *= 16777619u;
     }
   } else if (key->type == TYPE_INT) {
-    int val = *((int *) key->p_val);
+    int val = *((int64_t *) key->p_val);
     hash ^= val;
     hash *= 16777619u;
   } else if (key->type == TYPE_FLOAT) {
@@ -0,0 +1,96 @@
+#include "dump.h"
+#include "../json/json.h"
+#include "../freevar/freevar.h"
+#include "../optimization/compile.h"
+
+//  Position fields shared by tokens and nodes (column 0 means unknown)
+static void appendSpan(JsonBuffer *json, int line, int column, int endLine, int endColumn,
+                       int offset, int endOffset) {
+  JsonBuffer_appendf(json, "\"line\":%d,\"column\":", line);
+  if (column > 0) {
+    JsonBuffer_appendf(json, "%d,\"endLine\":%d,\"endColumn\":%d", column, endLine, endColumn);
+  } else {
+    JsonBuffer_append(json, "null,\"endLine\":null,\"endColumn\":null");
+  }
+  JsonBuffer_appendf(json, ",\"offset\":%d,\"endOffset\":%d", offset, endOffset);
+}
+
+void Dump_tokens(FILE *out, TokenArray *tokens) {
+  fprintf(out, "[\n");
+  for (int i = 0; i < tokens->count; i++) {
+    Token *tok = &tokens->tokens[i];
+
+    JsonBuffer json;
+    JsonBuffer_init(&json);
+    JsonBuffer_append(&json, "  {\"type\":");
+    JsonBuffer_appendString(&json, getTokenTypeString(tok->type));
+    JsonBuffer_append(&json, ",\"value\":");
+    JsonBuffer_appendString(&json, tok->val);
+    JsonBuffer_append(&json, ",");
+    appendSpan(&json, tok->lineNumber, tok->column, tok->endLine, tok->endColumn,
+               tok->offset, tok->endOffset);
+    JsonBuffer_append(&json, i + 1 < tokens->count ? "},\n" : "}\n");
+
+    fputs(json.data, out);
+    JsonBuffer_free(&json);
+  }
+  fprintf(out, "]\n");
+}
+
+//  Fill freeVars of every function node (FreeVar_analyze skips analyzed nodes)
+static void analyzeFunctions(AstNode *node) {
+  if (node == NULL) return;
+  if (node->opcode == OP_FUNCTION) FreeVar_analyze(node);
+  for (int i = 0; i < node->childCount; i++) {
+    analyzeFunctions(node->children[i]);
+  }
+}
+
+static void dumpNode(FILE *out, AstNode *node, int depth, bool last) {
+  JsonBuffer json;
+  JsonBuffer_init(&json);
+  JsonBuffer_appendf(&json, "%*s{\"opcode\":", depth * 2, "");
+  JsonBuffer_appendString(&json, getOpcodeString(node->opcode));
+  JsonBuffer_append(&json, ",\"value\":");
+  JsonBuffer_appendString(&json, node->val);
+  JsonBuffer_append(&json, ",");
+  appendSpan(&json, node->lineNumber, node->column, node->endLine, node->endColumn,
+             node->offset, node->endOffset);
+  JsonBuffer_appendf(&json, ",\"isMutable\":%s,\"varOffset\":%d,\"varDepth\":%d,\"freeVars\":[",
+                     node->isMutable ? "true" : "false", node->var_offset, node->var_depth);
+  for (int i = 0; i < node->freeVarsCount; i++) {
+    if (i > 0) JsonBuffer_append(&json, ",");
+    JsonBuffer_appendString(&json, node->freeVars[i]);
+  }
+  JsonBuffer_append(&json, "],\"children\":[");
+
+  if (node->childCount == 0) {
+    JsonBuffer_append(&json, last ? "]}\n" : "]},\n");
+    fputs(json.data, out);
+    JsonBuffer_free(&json);
+    return;
+  }
+
+  JsonBuffer_append(&json, "\n");
+  fputs(json.data, out);
+  JsonBuffer_free(&json);
+
+  for (int i = 0; i < node->childCount; i++) {
+    dumpNode(out, node->children[i], depth + 1, i + 1 == node->childCount);
+  }
+  fprintf(out, "%*s]}%s\n", depth * 2, "", last ? "" : ",");
+}
+
+void Dump_ast(FILE *out, AstNode *root) {
+  analyzeFunctions(root);
+
+  // compile_optimize only runs when the variable-offset optimization is on
+  int optimizationEnabled = g_optimization_enabled;
+  g_optimization_enabled = 1;
+  CompileEnv *env = CompileEnv_new(NULL);
+  compile_optimize(root, env);
+  CompileEnv_free(env);
+  g_optimization_enabled = optimizationEnabled;
+
+  dumpNode(out, root, 0, true);
+}
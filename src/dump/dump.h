@@ -0,0 +1,41 @@
+#ifndef DUMP_H
+#define DUMP_H
+
+#include <stdio.h>
+#include "../tokens.h"
+#include "../ast.h"
+
+/**
+ * JSON dumps of the compiler's front end (`--dump-tokens=json`, `--dump-ast=json`)
+ *
+ * Tokens are written as an array with one token object per line. The AST is
+ * written as nested node objects: each node's own fields go on one line and
+ * its children follow, indented, so a parser change shows up as a small diff
+ * in snapshot files.
+ *
+ * Columns are 1-based and ends are exclusive, as in diagnostics; a column
+ * that is not known is null.
+ */
+
+/**
+ * Print the token array as JSON.
+ *
+ * @param out - Stream to print to
+ * @param tokens - Lexer output (including the start and end tokens)
+ */
+void Dump_tokens(FILE *out, TokenArray *tokens);
+
+/**
+ * Print the AST as JSON.
+ *
+ * Before printing, every function node is analyzed with FreeVar_analyze and
+ * identifiers are resolved with compile_optimize, so freeVars, varOffset and
+ * varDepth show what the closure and variable-offset passes compute
+ * (varOffset is -1 for names those passes leave to runtime lookup).
+ *
+ * @param out - Stream to print to
+ * @param root - Program returned by parseProgram
+ */
+void Dump_ast(FILE *out, AstNode *root);
+
+#endif
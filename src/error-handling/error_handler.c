@@ -1,4 +1,5 @@
 #include "error_handler.h"
+#include "../diagnostics/diagnostic.h"
 #include <stdio.h>
 #include <stdlib.h>
 #include <string.h>
@@ -66,11 +67,9 @@ void ErrorState_setError(ErrorType type, int line_number, const char *format, ..
     if (g_error_state.try_depth == 0) {
         const char *error_type_str = ErrorState_typeToString(type);
 
-        if (line_number > 0) {
-            fprintf(stderr, "%s @ Line %d: %s\n", error_type_str, line_number, error_buffer);
-        } else {
-            fprintf(stderr, "%s: %s\n", error_type_str, error_buffer);
-        }
+        // Quotes the offending line when the program source is registered
+        Diagnostic_report(stderr, error_type_str, ErrorState_typeCode(type),
+                          Diagnostic_spanOfLine(line_number), "%s", error_buffer);
 
         exit(1);
     }
@@ -132,3 +131,17 @@ const char* ErrorState_typeToString(ErrorType type) {
         default:              return "Unknown Error";
     }
 }
+
+ErrorCode ErrorState_typeCode(ErrorType type) {
+    switch (type) {
+        case ERROR_RUNTIME:   return ERROR_CODE_RUNTIME;
+        case ERROR_SYNTAX:    return ERROR_CODE_SYNTAX;
+        case ERROR_TYPE:      return ERROR_CODE_RUNTIME_TYPE;
+        case ERROR_FILE:      return ERROR_CODE_FILE;
+        case ERROR_IMPORT:    return ERROR_CODE_IMPORT;
+        case ERROR_CIRCULAR:  return ERROR_CODE_CIRCULAR_IMPORT;
+        case ERROR_MEMORY:    return ERROR_CODE_OUT_OF_MEMORY;
+        case ERROR_CUSTOM:    return ERROR_CODE_UNCAUGHT_ERROR;
+        default:              return ERROR_CODE_NONE;
+    }
+}
@@ -3,6 +3,7 @@
 
 #include <stdbool.h>
 #include "../generic.h"
+#include "../diagnostics/error_codes.h"
 
 /*
  * Franz Error Handling System V2 - Flag-based approach
@@ -98,4 +99,9 @@ bool ErrorState_inTryBlock(void);
  */
 const char* ErrorState_typeToString(ErrorType type);
 
+/**
+ * Stable error code reported for an error type (see franz --explain)
+ */
+ErrorCode ErrorState_typeCode(ErrorType type);
+
 #endif // ERROR_HANDLER_V2_H
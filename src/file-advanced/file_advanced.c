@@ -1,6 +1,7 @@
 #include "file_advanced.h"
 #include "../generic.h"
 #include "../list.h"
+#include "../diagnostics/diagnostic.h"
 #include <stdio.h>
 #include <stdlib.h>
 #include <string.h>
@@ -42,8 +43,8 @@ char *readBinary(char *path, size_t *outSize) {
     return NULL;
   }
 
-  // Allocate buffer for binary data
-  char *data = malloc(fileSize);
+  // Allocate buffer for binary data (plus a terminator: the data is used as a string)
+  char *data = malloc(fileSize + 1);
   if (data == NULL) {
     fclose(p_file);
     *outSize = 0;
@@ -53,6 +54,7 @@ char *readBinary(char *path, size_t *outSize) {
   // Read binary data
   size_t bytesRead = fread(data, 1, fileSize, p_file);
   fclose(p_file);
+  data[bytesRead] = '\0';
 
   *outSize = bytesRead;
   return data;
@@ -62,11 +64,11 @@ void writeBinary(char *path, char *data, size_t size, int lineNumber) {
   FILE *p_file = fopen(path, "wb");  // "wb" = write binary mode
 
   if (p_file == NULL) {
-    printf(
-      "Runtime Error @ Line %i: Cannot write binary file \"%s\".\n",
-      lineNumber, path
+    Diagnostic_report(
+      stdout, "Runtime Error", ERROR_CODE_FILE, Diagnostic_spanOfLine(lineNumber),
+      "Cannot write binary file \"%s\".", path
     );
-    exit(0);
+    exit(1);
   }
 
   // Write binary data
@@ -74,11 +76,11 @@ void writeBinary(char *path, char *data, size_t size, int lineNumber) {
   fclose(p_file);
 
   if (bytesWritten != size) {
-    printf(
-      "Runtime Error @ Line %i: Failed to write complete binary data to \"%s\".\n",
-      lineNumber, path
+    Diagnostic_report(
+      stdout, "Runtime Error", ERROR_CODE_FILE, Diagnostic_spanOfLine(lineNumber),
+      "Failed to write complete binary data to \"%s\".", path
     );
-    exit(0);
+    exit(1);
   }
 }
 
@@ -2,7 +2,10 @@
 #include <stdio.h>
 #include <stdbool.h>
 #include <string.h>
+#include <dirent.h>
+#include <sys/stat.h>
 #include "file.h"
+#include "diagnostics/diagnostic.h"
 
 // Normalize a path to be as simple as possible, relative to the current working dir.
 // Makes similar files have the same path.
@@ -16,7 +19,7 @@ char *normalizePath(char *path) {
         "paths in invocations to the use function must be relative, "
         "and the path cannot step out of the working directory.\n", path
       );
-      exit(0);
+      exit(1);
   }
 
   // Copy that path for strtok_r.
@@ -54,7 +57,7 @@ char *normalizePath(char *path) {
             "paths in invocations to the use function must be relative, "
             "and the path cannot step out of the working directory.\n", path
           );
-          exit(0);
+          exit(1);
         }
       }
 
@@ -191,11 +194,11 @@ void writeFile(char *path, char* contents, int lineNumber) {
 
   // check the file succesfully opened
   if (p_file == NULL) {
-    printf(
-      "Runtime Error @ Line %i: Cannot write file \"%s\".\n",
-      lineNumber, path
+    Diagnostic_report(
+      stdout, "Runtime Error", ERROR_CODE_FILE, Diagnostic_spanOfLine(lineNumber),
+      "Cannot write file \"%s\".", path
     );
-    exit(0);
+    exit(1);
   }
 
   fprintf(p_file, "%s", contents);
@@ -222,13 +225,53 @@ void appendFile(char *path, char *contents, int lineNumber) {
 
   // check the file successfully opened
   if (p_file == NULL) {
-    printf(
-      "Runtime Error @ Line %i: Cannot append to file \"%s\".\n",
-      lineNumber, path
+    Diagnostic_report(
+      stdout, "Runtime Error", ERROR_CODE_FILE, Diagnostic_spanOfLine(lineNumber),
+      "Cannot append to file \"%s\".", path
     );
-    exit(0);
+    exit(1);
   }
 
   fprintf(p_file, "%s", contents);
   fclose(p_file);
-}
\ No newline at end of file
+}
+
+static bool hasFranzExtension(const char *name) {
+  size_t length = strlen(name);
+  return length > 6 && strcmp(name + length - 6, ".franz") == 0;
+}
+
+//  Visit a file, or every .franz file in a directory tree
+int walkFranzFiles(const char *path, int (*visit)(const char *path, void *context), void *context) {
+  struct stat info;
+  if (stat(path, &info) != 0) {
+    fprintf(stderr, "Error: No such file or directory '%s'.\n", path);
+    return 1;
+  }
+  if (!S_ISDIR(info.st_mode)) return visit(path, context) != 0;
+
+  struct dirent **entries;
+  int count = scandir(path, &entries, NULL, alphasort);
+  if (count < 0) {
+    fprintf(stderr, "Error: Could not read directory '%s'.\n", path);
+    return 1;
+  }
+
+  int status = 0;
+  for (int i = 0; i < count; i++) {
+    const char *name = entries[i]->d_name;
+    if (name[0] != '.') {
+      char *child = malloc(strlen(path) + strlen(name) + 2);
+      sprintf(child, "%s%s%s", path, path[strlen(path) - 1] == '/' ? "" : "/", name);
+
+      struct stat childInfo;
+      if (stat(child, &childInfo) == 0 && (S_ISDIR(childInfo.st_mode) || hasFranzExtension(name))) {
+        if (walkFranzFiles(child, visit, context) != 0) status = 1;
+      }
+      free(child);
+    }
+    free(entries[i]);
+  }
+  free(entries);
+  return status;
+}
@@ -28,4 +28,8 @@ void FileCache_freeze();
 int fileExists(char *path);
 void appendFile(char *path, char *contents, int lineNumber);
 
+//  Call visit for path, or for every .franz file below it (sorted, hidden entries
+// skipped). Returns 1 if a path could not be read or any visit returned non-zero.
+int walkFranzFiles(const char *path, int (*visit)(const char *path, void *context), void *context);
+
 #endif
\ No newline at end of file
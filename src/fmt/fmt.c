@@ -0,0 +1,794 @@
+#include "fmt.h"
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <stdbool.h>
+#include "../tokens.h"
+#include "../lex.h"
+#include "../parse.h"
+#include "../ast.h"
+#include "../file.h"
+#include "../diagnostics/diagnostic.h"
+
+#define FMT_WIDTH 100
+#define FMT_INDENT 2
+
+//  Growable output text
+typedef struct FmtBuffer {
+  char *data;
+  int length;
+  int capacity;
+} FmtBuffer;
+
+typedef struct Formatter {
+  const char *source;
+  Comment *comments;
+  int commentCount;
+  int next;            // First comment not printed yet
+  FmtBuffer out;
+  int column;          // Width of the current output line (0 = nothing written yet)
+  int indent;          // Indentation written before the first text of the line
+  bool space;          // A space is due before the next text on this line
+  int lastLine;        // Source line of the last item printed in the current block
+} Formatter;
+
+static void FmtBuffer_append(FmtBuffer *buffer, const char *text, int length) {
+  if (buffer->length + length + 1 > buffer->capacity) {
+    int capacity = buffer->capacity > 0 ? buffer->capacity : 256;
+    while (buffer->length + length + 1 > capacity) capacity *= 2;
+    buffer->data = realloc(buffer->data, capacity);
+    buffer->capacity = capacity;
+  }
+  memcpy(buffer->data + buffer->length, text, length);
+  buffer->length += length;
+  buffer->data[buffer->length] = '\0';
+}
+
+static void FmtBuffer_appendString(FmtBuffer *buffer, const char *text) {
+  FmtBuffer_append(buffer, text, strlen(text));
+}
+
+//  Display width: UTF-8 continuation bytes take no column
+static int textWidth(const char *text) {
+  int width = 0;
+  for (const unsigned char *p = (const unsigned char *) text; *p; p++) {
+    if ((*p & 0xC0) != 0x80) width++;
+  }
+  return width;
+}
+
+// ---------------------------------------------------------------------------
+// Output
+// ---------------------------------------------------------------------------
+
+static void put(Formatter *fmt, const char *text) {
+  if (fmt->column == 0) {
+    for (int i = 0; i < fmt->indent; i++) FmtBuffer_append(&fmt->out, " ", 1);
+    fmt->column = fmt->indent;
+  } else if (fmt->space) {
+    FmtBuffer_append(&fmt->out, " ", 1);
+    fmt->column++;
+  }
+  fmt->space = false;
+  FmtBuffer_appendString(&fmt->out, text);
+  fmt->column += textWidth(text);
+}
+
+static void space(Formatter *fmt) {
+  fmt->space = true;
+}
+
+static void newline(Formatter *fmt, int indent) {
+  FmtBuffer_append(&fmt->out, "\n", 1);
+  fmt->column = 0;
+  fmt->indent = indent;
+  fmt->space = false;
+}
+
+static void blankLine(Formatter *fmt) {
+  FmtBuffer_append(&fmt->out, "\n", 1);
+}
+
+//  Column where the next text would start
+static int currentColumn(Formatter *fmt) {
+  if (fmt->column == 0) return fmt->indent;
+  return fmt->column + (fmt->space ? 1 : 0);
+}
+
+// ---------------------------------------------------------------------------
+// Comments
+// ---------------------------------------------------------------------------
+
+static bool commentBefore(Formatter *fmt, int offset) {
+  return fmt->next < fmt->commentCount && fmt->comments[fmt->next].offset < offset;
+}
+
+//  Whether a comment not printed yet lies in [start, end)
+static bool commentWithin(Formatter *fmt, int start, int end) {
+  for (int i = fmt->next; i < fmt->commentCount && fmt->comments[i].offset < end; i++) {
+    if (fmt->comments[i].offset >= start) return true;
+  }
+  return false;
+}
+
+//  Print the next comment and end its line. A comment that followed code in the
+// input stays at the end of the current line; others get a line of their own.
+static void emitComment(Formatter *fmt, int indent) {
+  Comment *comment = &fmt->comments[fmt->next++];
+
+  if (fmt->column != 0 && comment->ownLine) newline(fmt, indent);
+
+  if (fmt->column != 0) {
+    FmtBuffer_appendString(&fmt->out, "  ");
+    FmtBuffer_appendString(&fmt->out, comment->text);
+  } else {
+    fmt->space = false;
+    put(fmt, comment->text);
+  }
+  newline(fmt, indent);
+}
+
+//  Print the comments before offset inside a node; the next text starts a new
+// line at indent when there were any
+static void emitCommentsBefore(Formatter *fmt, int offset, int indent) {
+  while (commentBefore(fmt, offset)) emitComment(fmt, indent);
+}
+
+//  Print the comments before offset between statements, keeping one blank line
+// where the input had one or more
+static void emitBlockComments(Formatter *fmt, int offset, int indent, bool *first) {
+  while (commentBefore(fmt, offset)) {
+    Comment *comment = &fmt->comments[fmt->next];
+    if (fmt->column == 0 && !*first && comment->lineNumber > fmt->lastLine + 1) blankLine(fmt);
+    fmt->indent = fmt->column == 0 ? indent : fmt->indent;
+    emitComment(fmt, indent);
+    fmt->lastLine = comment->lineNumber;
+    *first = false;
+  }
+}
+
+// ---------------------------------------------------------------------------
+// Layout
+// ---------------------------------------------------------------------------
+
+static void printNode(Formatter *fmt, AstNode *node, int indent);
+
+static int paramCount(const AstNode *fn) {
+  int count = 0;
+  while (count < fn->childCount && fn->children[count]->opcode == OP_IDENTIFIER) count++;
+  return count;
+}
+
+//  Statements of a function body or program (statement groups are flattened)
+static AstNode **bodyStatements(AstNode *node, int first, int *count) {
+  AstNode **list = malloc(sizeof(AstNode *) * (node->childCount + 1));
+  int n = 0;
+
+  for (int i = first; i < node->childCount; i++) {
+    AstNode *child = node->children[i];
+    if (child->opcode == OP_STATEMENT) {
+      int nested;
+      AstNode **inner = bodyStatements(child, 0, &nested);
+      list = realloc(list, sizeof(AstNode *) * (n + nested + node->childCount + 1));
+      memcpy(list + n, inner, sizeof(AstNode *) * nested);
+      n += nested;
+      free(inner);
+    } else {
+      list[n++] = child;
+    }
+  }
+
+  *count = n;
+  return list;
+}
+
+//  Source text of a single-token node (keeps string escapes and number formats)
+static void appendSource(Formatter *fmt, const AstNode *node, FmtBuffer *out) {
+  if (node->column > 0 && node->endOffset > node->offset) {
+    FmtBuffer_append(out, fmt->source + node->offset, node->endOffset - node->offset);
+  } else if (node->val != NULL) {
+    FmtBuffer_appendString(out, node->val);
+  }
+}
+
+//  Render a node on one line; false when it has to break
+static bool renderFlat(Formatter *fmt, AstNode *node, FmtBuffer *out) {
+  if (commentWithin(fmt, node->offset, node->endOffset)) return false;
+
+  switch (node->opcode) {
+    case OP_INT:
+    case OP_FLOAT:
+    case OP_STRING:
+    case OP_IDENTIFIER:
+      appendSource(fmt, node, out);
+      return true;
+
+    case OP_QUALIFIED:
+      FmtBuffer_appendString(out, node->val);
+      return true;
+
+    case OP_LIST:
+      FmtBuffer_appendString(out, "[");
+      for (int i = 0; i < node->childCount; i++) {
+        if (i > 0) FmtBuffer_appendString(out, ", ");
+        if (!renderFlat(fmt, node->children[i], out)) return false;
+      }
+      FmtBuffer_appendString(out, "]");
+      return true;
+
+    case OP_APPLICATION:
+      FmtBuffer_appendString(out, "(");
+      for (int i = 0; i < node->childCount; i++) {
+        if (i > 0) FmtBuffer_appendString(out, " ");
+        if (!renderFlat(fmt, node->children[i], out)) return false;
+      }
+      FmtBuffer_appendString(out, ")");
+      return true;
+
+    case OP_FUNCTION: {
+      int params = paramCount(node);
+      int count;
+      AstNode **body = bodyStatements(node, params, &count);
+      bool flat = count <= 1;
+
+      if (flat) {
+        FmtBuffer_appendString(out, "{");
+        for (int i = 0; i < params; i++) {
+          if (i > 0) FmtBuffer_appendString(out, " ");
+          FmtBuffer_appendString(out, node->children[i]->val);
+        }
+        if (params > 0) FmtBuffer_appendString(out, " ->");
+        if (count == 1) {
+          if (params > 0) FmtBuffer_appendString(out, " ");
+          flat = renderFlat(fmt, body[0], out);
+        }
+        FmtBuffer_appendString(out, "}");
+      }
+      free(body);
+      return flat;
+    }
+
+    case OP_ASSIGNMENT:
+      if (node->isMutable) FmtBuffer_appendString(out, "mut ");
+      FmtBuffer_appendString(out, node->children[0]->val);
+      FmtBuffer_appendString(out, " = ");
+      return renderFlat(fmt, node->children[1], out);
+
+    case OP_RETURN:
+      FmtBuffer_appendString(out, "<- ");
+      return renderFlat(fmt, node->children[0], out);
+
+    case OP_STATEMENT:
+      if (node->childCount != 1) return false;
+      return renderFlat(fmt, node->children[0], out);
+
+    default:
+      return false;
+  }
+}
+
+//  Flat text of a node, or NULL when it has to break
+static char *flatText(Formatter *fmt, AstNode *node) {
+  FmtBuffer text = { NULL, 0, 0 };
+  if (!renderFlat(fmt, node, &text)) {
+    free(text.data);
+    return NULL;
+  }
+  if (text.data == NULL) return strdup("");
+  return text.data;
+}
+
+static bool isLeaf(const AstNode *node) {
+  return node->opcode == OP_INT || node->opcode == OP_FLOAT || node->opcode == OP_STRING ||
+         node->opcode == OP_IDENTIFIER || node->opcode == OP_QUALIFIED;
+}
+
+//  Statement list at indent; comments up to endOffset belong to it. Starts and
+// ends at the beginning of a line.
+static void printBlock(Formatter *fmt, AstNode **statements, int count, int indent, int endOffset) {
+  bool first = true;
+
+  for (int i = 0; i < count; i++) {
+    AstNode *statement = statements[i];
+
+    emitBlockComments(fmt, statement->offset, indent, &first);
+    if (!first && statement->lineNumber > fmt->lastLine + 1) blankLine(fmt);
+
+    fmt->indent = indent;
+    printNode(fmt, statement, indent);
+    fmt->lastLine = statement->endLine;
+    first = false;
+
+    // A comment after the statement on its last line stays there
+    Comment *comment = fmt->next < fmt->commentCount ? &fmt->comments[fmt->next] : NULL;
+    if (comment != NULL && !comment->ownLine && comment->lineNumber == statement->endLine &&
+        comment->offset < endOffset) {
+      emitComment(fmt, indent);
+    } else {
+      newline(fmt, indent);
+    }
+  }
+
+  emitBlockComments(fmt, endOffset, indent, &first);
+}
+
+static void printFunction(Formatter *fmt, AstNode *node, int indent) {
+  int params = paramCount(node);
+
+  put(fmt, "{");
+  for (int i = 0; i < params; i++) {
+    if (i > 0) space(fmt);
+    put(fmt, node->children[i]->val);
+  }
+  if (params > 0) {
+    space(fmt);
+    put(fmt, "->");
+  }
+
+  int count;
+  AstNode **body = bodyStatements(node, params, &count);
+
+  // `{(if ...)}`: a lone call without parameters hugs the braces
+  if (params == 0 && count == 1 && body[0]->opcode == OP_APPLICATION &&
+      !commentWithin(fmt, node->offset, node->endOffset)) {
+    printNode(fmt, body[0], indent);
+    put(fmt, "}");
+    free(body);
+    return;
+  }
+
+  // A comment after `{x ->` stays on that line
+  int bodyStart = count > 0 ? body[0]->offset : node->endOffset;
+  if (commentBefore(fmt, bodyStart) && !fmt->comments[fmt->next].ownLine) {
+    emitComment(fmt, indent + FMT_INDENT);
+  } else {
+    newline(fmt, indent + FMT_INDENT);
+  }
+
+  int lastLine = fmt->lastLine;
+  printBlock(fmt, body, count, indent + FMT_INDENT, node->endOffset);
+  fmt->lastLine = lastLine;
+  free(body);
+
+  fmt->indent = indent;
+  put(fmt, "}");
+}
+
+static void printList(Formatter *fmt, AstNode *node, int indent) {
+  put(fmt, "[");
+  for (int i = 0; i < node->childCount; i++) {
+    AstNode *element = node->children[i];
+    emitCommentsBefore(fmt, element->offset, indent + FMT_INDENT);
+    if (fmt->column != 0) newline(fmt, indent + FMT_INDENT);
+    printNode(fmt, element, indent + FMT_INDENT);
+    if (i + 1 < node->childCount) put(fmt, ",");
+  }
+  emitCommentsBefore(fmt, node->endOffset, indent + FMT_INDENT);
+  if (fmt->column != 0) newline(fmt, indent);
+  fmt->indent = indent;
+  put(fmt, "]");
+}
+
+//  Opening text of a node printed broken: what has to fit on the first line
+static int openingWidth(const AstNode *node) {
+  if (node->opcode == OP_LIST) return 1;
+
+  int width = 1;  // {
+  int params = paramCount(node);
+  for (int i = 0; i < params; i++) width += textWidth(node->children[i]->val) + 1;
+  if (params > 0) width += 2;  // ->
+  return width;
+}
+
+//  `(f a b {x ->` ... `})`: every argument but a trailing function or list on
+// the first line
+static bool printHugged(Formatter *fmt, AstNode *node, int indent) {
+  int n = node->childCount;
+  if (n < 2) return false;
+
+  AstNode *last = node->children[n - 1];
+  if (last->opcode != OP_FUNCTION && last->opcode != OP_LIST) return false;
+  if (commentWithin(fmt, node->offset, last->offset)) return false;
+
+  // Calls taking several functions (if, match, ...) print them one per line
+  for (int i = 0; i < n - 1; i++) {
+    if (node->children[i]->opcode == OP_FUNCTION) return false;
+  }
+
+  char **texts = calloc(n - 1, sizeof(char *));
+  int width = currentColumn(fmt) + 1;
+  bool ok = true;
+  for (int i = 0; i < n - 1 && ok; i++) {
+    texts[i] = flatText(fmt, node->children[i]);
+    ok = texts[i] != NULL;
+    if (ok) width += textWidth(texts[i]) + 1;
+  }
+  ok = ok && width + openingWidth(last) <= FMT_WIDTH;
+
+  if (ok) {
+    put(fmt, "(");
+    for (int i = 0; i < n - 1; i++) {
+      if (i > 0) space(fmt);
+      put(fmt, texts[i]);
+    }
+    space(fmt);
+    if (last->opcode == OP_FUNCTION) {
+      printFunction(fmt, last, indent);
+    } else {
+      printList(fmt, last, indent);
+    }
+    put(fmt, ")");
+  }
+
+  for (int i = 0; i < n - 1; i++) free(texts[i]);
+  free(texts);
+  return ok;
+}
+
+//  Whether argument i labels the function after it: a single token (match
+// cases), or any argument when the call has several such pairs (if chains)
+static bool isLabel(const AstNode *node, int i, int pairs) {
+  if (i + 1 >= node->childCount || node->children[i + 1]->opcode != OP_FUNCTION) return false;
+  if (node->children[i]->opcode == OP_FUNCTION) return false;
+  return isLeaf(node->children[i]) || pairs >= 2;
+}
+
+static void printApplication(Formatter *fmt, AstNode *node, int indent) {
+  if (node->childCount == 0) {
+    put(fmt, "()");
+    return;
+  }
+  if (printHugged(fmt, node, indent)) return;
+
+  put(fmt, "(");
+  printNode(fmt, node->children[0], indent);
+
+  int pairs = 0;
+  for (int i = 1; i + 1 < node->childCount; i++) {
+    if (node->children[i]->opcode != OP_FUNCTION && node->children[i + 1]->opcode == OP_FUNCTION) pairs++;
+  }
+
+  // The first argument stays next to the callee when it is one line, unless
+  // it is the first condition of several: `(if` / `(is n 0) {<- 0}` / ...
+  int next = 1;
+  bool packing = false;  // the current line ends with a single-token argument
+  if (node->childCount > 1 && !commentBefore(fmt, node->children[1]->offset) &&
+      !(pairs >= 2 && isLabel(node, 1, pairs))) {
+    char *text = flatText(fmt, node->children[1]);
+    if (text != NULL && (isLeaf(node->children[1]) || currentColumn(fmt) + 1 + textWidth(text) <= FMT_WIDTH)) {
+      space(fmt);
+      put(fmt, text);
+      next = 2;
+      packing = isLeaf(node->children[1]);
+    }
+    free(text);
+  }
+
+  for (int i = next; i < node->childCount; i++) {
+    AstNode *argument = node->children[i];
+    bool label = isLabel(node, i, pairs);
+
+    // Runs of single tokens fill lines: `"k1" "v1" "k2" "v2"`
+    if (packing && isLeaf(argument) && !label && !commentBefore(fmt, argument->offset)) {
+      char *text = flatText(fmt, argument);
+      bool fits = currentColumn(fmt) + 1 + textWidth(text) <= FMT_WIDTH;
+      if (fits) {
+        space(fmt);
+        put(fmt, text);
+      }
+      free(text);
+      if (fits) continue;
+    }
+
+    emitCommentsBefore(fmt, argument->offset, indent + FMT_INDENT);
+    if (fmt->column != 0) newline(fmt, indent + FMT_INDENT);
+    printNode(fmt, argument, indent + FMT_INDENT);
+    packing = isLeaf(argument) && !label;
+
+    // A label keeps the function after it on its line: `"Some" {x -> ...}`
+    if (label && !commentBefore(fmt, node->children[i + 1]->offset)) {
+      space(fmt);
+      printNode(fmt, node->children[++i], indent + FMT_INDENT);
+    }
+  }
+
+  // A comment after the last argument puts the parenthesis on its own line
+  if (commentBefore(fmt, node->endOffset)) {
+    emitCommentsBefore(fmt, node->endOffset, indent + FMT_INDENT);
+    fmt->indent = indent;
+  }
+  put(fmt, ")");
+}
+
+//  Print a value after `=` or `<-`, moving comments in between out of the way
+static void printValue(Formatter *fmt, AstNode *value, int indent) {
+  if (commentBefore(fmt, value->offset)) {
+    emitCommentsBefore(fmt, value->offset, indent + FMT_INDENT);
+    indent += FMT_INDENT;
+  }
+  space(fmt);
+  printNode(fmt, value, indent);
+}
+
+//  Print a node starting at the current position; indent is the indentation of
+// the line it starts on
+static void printNode(Formatter *fmt, AstNode *node, int indent) {
+  char *text = flatText(fmt, node);
+  if (text != NULL && (isLeaf(node) || currentColumn(fmt) + textWidth(text) <= FMT_WIDTH)) {
+    put(fmt, text);
+    free(text);
+    return;
+  }
+  free(text);
+
+  switch (node->opcode) {
+    case OP_FUNCTION:
+      printFunction(fmt, node, indent);
+      break;
+
+    case OP_APPLICATION:
+      printApplication(fmt, node, indent);
+      break;
+
+    case OP_LIST:
+      printList(fmt, node, indent);
+      break;
+
+    case OP_ASSIGNMENT:
+      if (node->isMutable) put(fmt, "mut");
+      if (node->isMutable) space(fmt);
+      put(fmt, node->children[0]->val);
+      space(fmt);
+      put(fmt, "=");
+      printValue(fmt, node->children[1], indent);
+      break;
+
+    case OP_RETURN:
+      put(fmt, "<-");
+      printValue(fmt, node->children[0], indent);
+      break;
+
+    case OP_STATEMENT: {
+      int count;
+      AstNode **statements = bodyStatements(node, 0, &count);
+      for (int i = 0; i < count; i++) {
+        if (i > 0) newline(fmt, indent);
+        printNode(fmt, statements[i], indent);
+      }
+      free(statements);
+      break;
+    }
+
+    default: {
+      // Single tokens never break
+      FmtBuffer leaf = { NULL, 0, 0 };
+      appendSource(fmt, node, &leaf);
+      if (leaf.data != NULL) put(fmt, leaf.data);
+      free(leaf.data);
+      break;
+    }
+  }
+}
+
+// ---------------------------------------------------------------------------
+// Verification
+// ---------------------------------------------------------------------------
+
+static bool sameNode(AstNode *a, AstNode *b);
+
+static bool sameStatements(AstNode *a, int firstA, AstNode *b, int firstB) {
+  int countA, countB;
+  AstNode **listA = bodyStatements(a, firstA, &countA);
+  AstNode **listB = bodyStatements(b, firstB, &countB);
+
+  bool same = countA == countB;
+  for (int i = 0; same && i < countA; i++) same = sameNode(listA[i], listB[i]);
+
+  free(listA);
+  free(listB);
+  return same;
+}
+
+//  Structural equality, ignoring how statements were grouped while parsing
+static bool sameNode(AstNode *a, AstNode *b) {
+  if (a->opcode != b->opcode) return false;
+  if ((a->val == NULL) != (b->val == NULL)) return false;
+  if (a->val != NULL && strcmp(a->val, b->val) != 0) return false;
+  if (a->opcode == OP_ASSIGNMENT && a->isMutable != b->isMutable) return false;
+
+  if (a->opcode == OP_FUNCTION || a->opcode == OP_STATEMENT) {
+    int params = a->opcode == OP_FUNCTION ? paramCount(a) : 0;
+    if (a->opcode == OP_FUNCTION && params != paramCount(b)) return false;
+    for (int i = 0; i < params; i++) {
+      if (!sameNode(a->children[i], b->children[i])) return false;
+    }
+    return sameStatements(a, params, b, params);
+  }
+
+  if (a->childCount != b->childCount) return false;
+  for (int i = 0; i < a->childCount; i++) {
+    if (!sameNode(a->children[i], b->children[i])) return false;
+  }
+  return true;
+}
+
+static bool sameComments(TokenArray *a, TokenArray *b) {
+  if (a->commentCount != b->commentCount) return false;
+  for (int i = 0; i < a->commentCount; i++) {
+    if (strcmp(a->comments[i].text, b->comments[i].text) != 0) return false;
+  }
+  return true;
+}
+
+// ---------------------------------------------------------------------------
+// Formatting
+// ---------------------------------------------------------------------------
+
+//  Lex and parse; ast is NULL for a program without statements
+static TokenArray *parseSource(char *code, int length, AstNode **ast) {
+  TokenArray *tokens = lex(code, length);
+  *ast = NULL;
+  if (Diagnostic_count() == 0 && tokens->count > 2) {
+    *ast = parseProgram(tokens);
+  }
+  return tokens;
+}
+
+static char *render(const char *code, TokenArray *tokens, AstNode *ast) {
+  Formatter fmt = { 0 };
+  fmt.source = code;
+  fmt.comments = tokens->comments;
+  fmt.commentCount = tokens->commentCount;
+
+  int count = 0;
+  AstNode **statements = ast != NULL ? bodyStatements(ast, 0, &count) : NULL;
+  printBlock(&fmt, statements, count, 0, tokens->tokens[tokens->count - 1].endOffset + 1);
+  free(statements);
+
+  if (fmt.out.data == NULL) return strdup("");
+  return fmt.out.data;
+}
+
+char *Fmt_format(const char *path, const char *code, int length) {
+  char *source = malloc(length + 1);
+  memcpy(source, code, length);
+  source[length] = '\0';
+  Diagnostic_addSource(path, source, length);
+
+  AstNode *ast;
+  TokenArray *tokens = parseSource(source, length, &ast);
+  if (Diagnostic_count() > 0) {
+    if (ast) AstNode_free(ast);
+    TokenArray_free(tokens);
+    free(source);
+    return NULL;
+  }
+
+  char *result = render(source, tokens, ast);
+
+  // The output must parse to the same program with the same comments
+  AstNode *check;
+  TokenArray *checkTokens = parseSource(result, strlen(result), &check);
+  bool same = Diagnostic_count() == 0 && (ast == NULL) == (check == NULL) &&
+              (ast == NULL || sameNode(ast, check)) && sameComments(tokens, checkTokens);
+  Diagnostic_clear();
+
+  if (!same) {
+    fprintf(stderr, "Error: franz fmt could not format %s without changing it; the file was left as is.\n", path);
+    free(result);
+    result = strdup(source);
+  }
+
+  if (check) AstNode_free(check);
+  TokenArray_free(checkTokens);
+  if (ast) AstNode_free(ast);
+  TokenArray_free(tokens);
+  free(source);
+  return result;
+}
+
+// ---------------------------------------------------------------------------
+// Command line
+// ---------------------------------------------------------------------------
+
+static char *readStream(FILE *in, int *length) {
+  FmtBuffer text = { NULL, 0, 0 };
+  char chunk[4096];
+  size_t n;
+  while ((n = fread(chunk, 1, sizeof(chunk), in)) > 0) FmtBuffer_append(&text, chunk, n);
+  *length = text.length;
+  return text.data != NULL ? text.data : strdup("");
+}
+
+//  Format one file; returns 0 if formatted (or already formatted), 1 otherwise
+static int formatFile(const char *path, bool check) {
+  char *code = readFile((char *) path, false);
+  if (code == NULL) {
+    fprintf(stderr, "Error: Could not read '%s'.\n", path);
+    return 1;
+  }
+
+  int length = strlen(code);
+  char *formatted = Fmt_format(path, code, length);
+  if (formatted == NULL) {
+    int errors = Diagnostic_count();
+    Diagnostic_flush(stdout);
+    fflush(stdout);
+    fprintf(stderr, "Error: cannot format %s: %d syntax error%s\n", path, errors, errors == 1 ? "" : "s");
+    free(code);
+    return 1;
+  }
+
+  int status = 0;
+  if (strcmp(formatted, code) != 0) {
+    if (check) {
+      printf("Would reformat %s\n", path);
+      status = 1;
+    } else {
+      FILE *out = fopen(path, "w");
+      if (out == NULL || fwrite(formatted, 1, strlen(formatted), out) != strlen(formatted)) {
+        fprintf(stderr, "Error: Could not write '%s'.\n", path);
+        status = 1;
+      }
+      if (out) fclose(out);
+    }
+  }
+
+  free(formatted);
+  free(code);
+  return status;
+}
+
+static int visitFile(const char *path, void *context) {
+  return formatFile(path, *(bool *) context);
+}
+
+int Fmt_run(int argc, char *argv[]) {
+  bool check = false;
+  int pathCount = 0;
+
+  for (int i = 0; i < argc; i++) {
+    if (strcmp(argv[i], "--check") == 0) {
+      check = true;
+    } else if (strcmp(argv[i], "-") == 0) {
+      continue;
+    } else if (argv[i][0] == '-' && argv[i][1] != '\0') {
+      fprintf(stderr, "Error: Unknown option '%s' for 'franz fmt' (expected --check).\n", argv[i]);
+      return 1;
+    } else {
+      pathCount++;
+    }
+  }
+
+  // No paths (or `-`): stdin to stdout (with --check, only the exit code)
+  if (pathCount == 0) {
+    int length;
+    char *code = readStream(stdin, &length);
+    char *formatted = Fmt_format("<stdin>", code, length);
+    int status = 0;
+
+    if (formatted == NULL) {
+      int errors = Diagnostic_count();
+      Diagnostic_flush(stdout);
+      fflush(stdout);
+      fprintf(stderr, "Error: cannot format <stdin>: %d syntax error%s\n", errors, errors == 1 ? "" : "s");
+      status = 1;
+    } else if (check) {
+      status = strcmp(formatted, code) != 0;
+      if (status) printf("Would reformat <stdin>\n");
+    } else {
+      fputs(formatted, stdout);
+    }
+
+    free(formatted);
+    free(code);
+    return status;
+  }
+
+  int status = 0;
+  for (int i = 0; i < argc; i++) {
+    if (strcmp(argv[i], "--check") == 0 || strcmp(argv[i], "-") == 0) continue;
+    if (walkFranzFiles(argv[i], visitFile, &check) != 0) status = 1;
+  }
+  return status;
+}
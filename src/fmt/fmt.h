@@ -0,0 +1,57 @@
+#ifndef FMT_H
+#define FMT_H
+
+/**
+ * Canonical source formatter for Franz (`franz fmt`)
+ *
+ * Programs are lexed and parsed with the compiler's front end and printed
+ * back from the AST, so the layout depends only on the program and its
+ * comments, never on how the input was laid out:
+ *
+ *   - one statement per line, 2-space indentation, at most one blank line
+ *     kept between statements
+ *   - a node is printed on one line when it fits in 100 columns; functions
+ *     with more than one statement always break
+ *   - a broken function puts its body on its own lines: `{x ->` ... `}`;
+ *     a function without parameters whose body is one call hugs it:
+ *     `{(if ...` ... `)}`
+ *   - a call whose last argument is a broken function or list keeps the
+ *     other arguments on the first line: `(loop 10 {i ->` ... `})`, unless
+ *     another argument is a function too (if, match, ...)
+ *   - other broken calls keep the first argument next to the callee and put
+ *     each remaining argument on its own line; a label stays in front of the
+ *     function after it: `"Some" {x -> ...}`, and runs of single tokens fill
+ *     their lines
+ *
+ * Comments are kept by the lexer as trivia (TokenArray.comments) and are
+ * printed before the statement or argument that follows them, or after the
+ * line they ended in the input.
+ *
+ * Every result is parsed again and compared with the input AST and comments,
+ * so formatting can never change what a program does.
+ */
+
+/**
+ * Format Franz source code
+ *
+ * @param path - Name shown in syntax error messages
+ * @param code - Source text
+ * @param length - Length of code in bytes
+ * @return Formatted source (caller frees), or NULL when the code has syntax errors
+ *         (collected with Diagnostic_add)
+ */
+char *Fmt_format(const char *path, const char *code, int length);
+
+/**
+ * Run `franz fmt [--check] [paths...]`
+ *
+ * Files are rewritten in place; directories are searched for .franz files.
+ * Without paths (or with `-`), stdin is formatted to stdout.
+ *
+ * @param argc - Number of arguments after `fmt`
+ * @param argv - Arguments after `fmt`
+ * @return Exit code (1 if --check found unformatted files or a file had errors)
+ */
+int Fmt_run(int argc, char *argv[]);
+
+#endif
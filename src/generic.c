@@ -8,12 +8,12 @@
 #include "scope.h"
 #include "closure/closure.h"  //  Closure support
 #include "mutable-refs/ref.h"  //  Mutable reference support
-#include "bytecode_stub.h"     //  Stub for removed bytecode closures
+#include "bytecode/bytecode.h"
 
 // print generic nicely
 void Generic_print(Generic *in) {
   if (in->type == TYPE_INT) {
-    printf("%i", *((int *) in->p_val));
+    printf("%lld", (long long) *((int64_t *) in->p_val));
   } else if (in->type == TYPE_FLOAT) {
     printf("%f", *((double *) in->p_val));
   } else if (in->type == TYPE_STRING) {
@@ -71,8 +71,8 @@ void Generic_free(Generic *target) {
     #ifdef DEBUG_GENERIC_FREE
     fprintf(stderr, "[DEBUG] Generic_free: Calling BytecodeClosure_free\n");
     #endif
-    //  Bytecode closures removed - stub does nothing
-    BytecodeClosure_free((BytecodeClosure *)target->p_val);
+    //  Releases closures of the bytecode VM; does nothing for other closures
+    BytecodeClosure_free(target->p_val);
   } else if (target->type == TYPE_NAMESPACE) {
     Scope_free((Scope *) target->p_val); // namespaces contain Scope*, free the scope
   } else if (target->type == TYPE_REF) {
@@ -124,13 +124,14 @@ Generic *Generic_copy(Generic *target) {
   } else if (res->type == TYPE_BYTECODE_CLOSURE) {
     //  Bytecode closures are shared references (shallow copy)
     res->p_val = target->p_val;
+    BytecodeClosure_retain(res->p_val);
   } else if (res->type == TYPE_NATIVEFUNCTION) {
     res->p_val = target->p_val;
   } else if (res->type == TYPE_VOID) {
     res->p_val = NULL;
   } else if (res->type == TYPE_INT) {
-    res->p_val = (int *) malloc(sizeof(int));
-    *((int *) res->p_val) = *((int *) target->p_val);
+    res->p_val = (int64_t *) malloc(sizeof(int64_t));
+    *((int64_t *) res->p_val) = *((int64_t *) target->p_val);
   } else if (res->type == TYPE_FLOAT) {
     res->p_val = (double *) malloc(sizeof(double));
     *((double *) res->p_val) = *((double *) target->p_val);
@@ -159,8 +160,8 @@ int Generic_is(Generic *a, Generic *b) {
     && (b->type == TYPE_INT || b->type == TYPE_FLOAT)
   ) {
     return (
-      (a->type == TYPE_FLOAT ? *((double *) a->p_val) : *((int *) a->p_val))
-      == (b->type == TYPE_FLOAT ? *((double *) b->p_val) : *((int *) b->p_val))
+      (a->type == TYPE_FLOAT ? *((double *) a->p_val) : *((int64_t *) a->p_val))
+      == (b->type == TYPE_FLOAT ? *((double *) b->p_val) : *((int64_t *) b->p_val))
     );
   }
 
@@ -173,7 +174,7 @@ int Generic_is(Generic *a, Generic *b) {
         if (*((double *) a->p_val) == *((double *) b->p_val)) res = 1;
         break;
       case TYPE_INT:
-        if (*((int *) a->p_val) == *((int *) b->p_val)) res = 1;
+        if (*((int64_t *) a->p_val) == *((int64_t *) b->p_val)) res = 1;
         break;
       case TYPE_STRING:
         if (strcmp(*((char **) a->p_val), *((char **) b->p_val)) == 0) res = 1;
@@ -208,8 +209,8 @@ int Generic_is(Generic *a, Generic *b) {
 }
 
 //  Convenience constructors for bytecode compiler/VM
-Generic *Generic_fromInt(int value) {
-  int *p_int = malloc(sizeof(int));
+Generic *Generic_fromInt(int64_t value) {
+  int64_t *p_int = malloc(sizeof(int64_t));
   *p_int = value;
   return Generic_new(TYPE_INT, p_int, 1);
 }
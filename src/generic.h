@@ -1,6 +1,8 @@
 #ifndef GENERIC_H
 #define GENERIC_H
 
+#include <stdint.h>
+
 // types for generic
 enum Type {
   TYPE_INT,
@@ -37,7 +39,7 @@ Generic *Generic_copy(Generic *);
 int Generic_is(Generic *, Generic *);
 
 //  Convenience constructors for bytecode compiler/VM
-Generic *Generic_fromInt(int value);
+Generic *Generic_fromInt(int64_t value);
 Generic *Generic_fromFloat(double value);
 Generic *Generic_fromString(const char *value);
 Generic *Generic_fromVoid(void);
@@ -0,0 +1,447 @@
+#include "interpret.h"
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include "../stdlib.h"
+#include "../list.h"
+#include "../string.h"
+#include "../closure/closure.h"
+#include "../number-formats/number_parse.h"
+#include "../diagnostics/diagnostic.h"
+
+//  How control leaves the statements being evaluated
+typedef enum {
+  FLOW_NORMAL,
+  FLOW_RETURN,    // `<-`: leaves the body, or ends the enclosing loop with a value
+  FLOW_BLOCK,     // `<-` void or 0 inside a loop: ends only the current block
+  FLOW_BREAK,
+  FLOW_CONTINUE
+} Flow;
+
+static Flow g_flow = FLOW_NORMAL;
+static int g_loopDepth = 0;   // loops open in the current body
+
+static Generic *evalNode(AstNode *node, Scope *p_scope, int depth);
+
+static Generic *voidValue(void) {
+  return Generic_new(TYPE_VOID, NULL, 0);
+}
+
+static Generic *intValue(int64_t value) {
+  int64_t *p_val = (int64_t *) malloc(sizeof(int64_t));
+  *p_val = value;
+  return Generic_new(TYPE_INT, p_val, 0);
+}
+
+//  Free a value nobody else owns
+static void release(Generic *value) {
+  if (value != NULL && value->refCount == 0) Generic_free(value);
+}
+
+static int truthy(Generic *value) {
+  switch (value->type) {
+    case TYPE_INT: return *((int64_t *) value->p_val) != 0;
+    case TYPE_FLOAT: return *((double *) value->p_val) != 0.0;
+    case TYPE_VOID: return 0;
+    default: return 1;
+  }
+}
+
+static void runtimeError(ErrorCode code, AstNode *node, const char *message, const char *name) {
+  Diagnostic_report(stdout, "Runtime Error", code, Diagnostic_spanOfNode(node), message, name);
+  exit(1);
+}
+
+static void requireArgs(AstNode *node, const char *name, int min, int max) {
+  int count = node->childCount - 1;
+  if (count < min || (max >= 0 && count > max)) {
+    runtimeError(ERROR_CODE_RUNTIME_ARITY, node, "Wrong number of arguments to %s.", name);
+  }
+}
+
+// ============================================================================
+// Statements and blocks
+// ============================================================================
+
+//  Children of a node from first on, in order, until control flow leaves them
+static Generic *evalSequence(AstNode *node, int first, Scope *p_scope, int depth) {
+  Generic *last = NULL;
+  for (int i = first; i < node->childCount; i++) {
+    release(last);
+    last = evalNode(node->children[i], p_scope, depth);
+    if (g_flow != FLOW_NORMAL) break;
+  }
+  return last ? last : voidValue();
+}
+
+//  Run a branch or loop body. A block literal runs inline in p_scope with its
+// parameters bound to args; anything else is evaluated, and called with args
+// if there are any. args are borrowed.
+static Generic *evalBlock(AstNode *node, Scope *p_scope, Generic *args[], int argCount, int depth) {
+  if (node->opcode != OP_FUNCTION) {
+    Generic *value = evalNode(node, p_scope, depth);
+    if (argCount == 0 || g_flow != FLOW_NORMAL) return value;
+
+    for (int i = 0; i < argCount; i++) args[i]->refCount++;
+    Generic *res = applyFunc(value, p_scope, args, argCount, node->lineNumber);
+    for (int i = 0; i < argCount; i++) args[i]->refCount--;
+    return res;
+  }
+
+  int paramCount = Interpret_paramCount(node);
+  for (int i = 0; i < paramCount && i < argCount; i++) {
+    Scope_set(p_scope, node->children[i]->val, args[i], -1);
+  }
+
+  Generic *res = evalSequence(node, paramCount, p_scope, depth + 1);
+
+  //  `<-` gives the block's value; only inside a loop may it end more than the block
+  if (g_flow == FLOW_BLOCK || (g_flow == FLOW_RETURN && g_loopDepth == 0)) g_flow = FLOW_NORMAL;
+  return res;
+}
+
+//  `<- value`. Inside a loop, returning void or a literal 0 continues the loop.
+static Generic *evalReturn(AstNode *node, Scope *p_scope, int depth) {
+  if (node->childCount == 0) {
+    g_flow = g_loopDepth > 0 ? FLOW_BLOCK : FLOW_RETURN;
+    return voidValue();
+  }
+
+  AstNode *valueNode = node->children[0];
+  Generic *value = evalNode(valueNode, p_scope, depth);
+  if (g_flow != FLOW_NORMAL) return value;
+
+  int continues = value->type == TYPE_VOID ||
+                  (valueNode->opcode == OP_INT && parseInteger(valueNode->val) == 0);
+  g_flow = g_loopDepth > 0 && continues ? FLOW_BLOCK : FLOW_RETURN;
+  return value;
+}
+
+// ============================================================================
+// Control flow forms
+// ============================================================================
+
+//  Evaluate a condition; returns -1 if control flow left it
+static int evalCondition(AstNode *node, Scope *p_scope, int depth) {
+  Generic *value = evalNode(node, p_scope, depth);
+  int res = g_flow == FLOW_NORMAL ? truthy(value) : -1;
+  release(value);
+  return res;
+}
+
+//  (if cond then [cond then ...] [else])
+static Generic *evalIf(AstNode *node, Scope *p_scope, int depth) {
+  requireArgs(node, "if", 2, -1);
+
+  int i = 1;
+  for (; i + 1 < node->childCount; i += 2) {
+    int passed = evalCondition(node->children[i], p_scope, depth);
+    if (passed < 0) return voidValue();
+    if (passed) return evalBlock(node->children[i + 1], p_scope, NULL, 0, depth);
+  }
+  if (i < node->childCount) return evalBlock(node->children[i], p_scope, NULL, 0, depth);
+  return voidValue();
+}
+
+//  (when cond action) and (unless cond action)
+static Generic *evalWhen(AstNode *node, Scope *p_scope, int depth, int expected, const char *name) {
+  requireArgs(node, name, 2, 2);
+
+  int passed = evalCondition(node->children[1], p_scope, depth);
+  if (passed < 0) return voidValue();
+  if (passed == expected) return evalBlock(node->children[2], p_scope, NULL, 0, depth);
+  return intValue(0);
+}
+
+//  (cond (test result) ... (else default))
+static Generic *evalCond(AstNode *node, Scope *p_scope, int depth) {
+  for (int i = 1; i < node->childCount; i++) {
+    AstNode *clause = node->children[i];
+    if (clause->opcode != OP_APPLICATION || clause->childCount != 2) {
+      runtimeError(ERROR_CODE_RUNTIME, clause, "%s clauses must be (test result) or (else result).", "cond");
+    }
+
+    AstNode *test = clause->children[0];
+    int passed = test->opcode == OP_IDENTIFIER && strcmp(test->val, "else") == 0
+      ? 1 : evalCondition(test, p_scope, depth);
+    if (passed < 0) return voidValue();
+    if (passed) return evalBlock(clause->children[1], p_scope, NULL, 0, depth);
+  }
+  return intValue(0);
+}
+
+//  After one iteration: returns the loop's result if the loop ends, NULL otherwise
+static Generic *endOfIteration(Generic *value) {
+  if (g_flow == FLOW_RETURN || g_flow == FLOW_BREAK) {
+    g_flow = FLOW_NORMAL;
+    return value;
+  }
+  g_flow = FLOW_NORMAL;
+  release(value);
+  return NULL;
+}
+
+//  (loop count {i -> body})
+static Generic *evalLoop(AstNode *node, Scope *p_scope, int depth) {
+  requireArgs(node, "loop", 2, 2);
+
+  Generic *count = evalNode(node->children[1], p_scope, depth);
+  if (g_flow != FLOW_NORMAL) return count;
+  if (count->type != TYPE_INT) {
+    Diagnostic_report(
+      stdout, "Runtime Error", ERROR_CODE_RUNTIME_TYPE, Diagnostic_spanOfNode(node),
+      "loop function requires integer type for argument #1, %s type supplied instead.",
+      getTypeString(count->type)
+    );
+    exit(1);
+  }
+  int64_t times = *((int64_t *) count->p_val);
+  release(count);
+
+  Generic *res = NULL;
+  g_loopDepth++;
+  for (int64_t i = 0; i < times && res == NULL; i++) {
+    Generic *index = intValue(i);
+    Generic *args[1] = {index};
+    Generic *value = evalBlock(node->children[2], p_scope, args, 1, depth);
+    res = endOfIteration(value);
+    if (res != index) release(index);
+  }
+  g_loopDepth--;
+
+  return res ? res : intValue(0);
+}
+
+//  (while cond {body})
+static Generic *evalWhile(AstNode *node, Scope *p_scope, int depth) {
+  requireArgs(node, "while", 2, 2);
+
+  Generic *res = NULL;
+  g_loopDepth++;
+  while (res == NULL) {
+    int passed = evalCondition(node->children[1], p_scope, depth);
+    if (passed <= 0) {
+      g_flow = FLOW_NORMAL;
+      break;
+    }
+    res = endOfIteration(evalBlock(node->children[2], p_scope, NULL, 0, depth));
+  }
+  g_loopDepth--;
+
+  return res ? res : intValue(0);
+}
+
+//  (break [value]) and (continue)
+static Generic *evalLoopExit(AstNode *node, Scope *p_scope, int depth, Flow flow, const char *name) {
+  if (g_loopDepth == 0) {
+    runtimeError(ERROR_CODE_OUTSIDE_LOOP, node, "'%s' used outside of a loop.", name);
+  }
+  requireArgs(node, name, 0, flow == FLOW_BREAK ? 1 : 0);
+
+  Generic *value = node->childCount > 1 ? evalNode(node->children[1], p_scope, depth) : intValue(0);
+  if (g_flow == FLOW_NORMAL) g_flow = flow;
+  return value;
+}
+
+//  (and a b ...) and (or a b ...): stop at the first operand that decides the result
+static Generic *evalLogical(AstNode *node, Scope *p_scope, int depth, int stopOn, const char *name) {
+  requireArgs(node, name, 1, -1);
+
+  for (int i = 1; i < node->childCount; i++) {
+    int value = evalCondition(node->children[i], p_scope, depth);
+    if (value < 0) return voidValue();
+    if (value == stopOn) return intValue(stopOn);
+  }
+  return intValue(!stopOn);
+}
+
+// ============================================================================
+// Expressions
+// ============================================================================
+
+static Generic *evalApplication(AstNode *node, Scope *p_scope, int depth) {
+  AstNode *head = node->children[0];
+
+  //  Forms that control evaluation of their arguments, as in the compiled backend
+  if (head->opcode == OP_IDENTIFIER) {
+    const char *name = head->val;
+    if (strcmp(name, "if") == 0) return evalIf(node, p_scope, depth);
+    if (strcmp(name, "when") == 0) return evalWhen(node, p_scope, depth, 1, "when");
+    if (strcmp(name, "unless") == 0) return evalWhen(node, p_scope, depth, 0, "unless");
+    if (strcmp(name, "cond") == 0) return evalCond(node, p_scope, depth);
+    if (strcmp(name, "loop") == 0) return evalLoop(node, p_scope, depth);
+    if (strcmp(name, "while") == 0) return evalWhile(node, p_scope, depth);
+    if (strcmp(name, "break") == 0) return evalLoopExit(node, p_scope, depth, FLOW_BREAK, "break");
+    if (strcmp(name, "continue") == 0) return evalLoopExit(node, p_scope, depth, FLOW_CONTINUE, "continue");
+    if (strcmp(name, "and") == 0) return evalLogical(node, p_scope, depth, 0, "and");
+    if (strcmp(name, "or") == 0) return evalLogical(node, p_scope, depth, 1, "or");
+  }
+
+  Generic *func = evalNode(head, p_scope, depth);
+  if (g_flow != FLOW_NORMAL) return func;
+
+  int argCount = node->childCount - 1;
+  Generic **args = (Generic **) malloc(sizeof(Generic *) * (argCount > 0 ? argCount : 1));
+  for (int i = 0; i < argCount; i++) {
+    args[i] = evalNode(node->children[i + 1], p_scope, depth);
+    if (g_flow != FLOW_NORMAL) {
+      Generic *value = args[i];
+      for (int j = 0; j < i; j++) release(args[j]);
+      release(func);
+      free(args);
+      return value;
+    }
+  }
+
+  //  applyFunc frees temporary arguments and functions
+  Generic *res = applyFunc(func, p_scope, args, argCount, node->lineNumber);
+  free(args);
+  return res;
+}
+
+static Generic *evalIdentifier(AstNode *node, Scope *p_scope) {
+  Generic *value = Scope_get(p_scope, node->val, -1);
+  if (value != NULL) return value;
+  if (strcmp(node->val, "void") == 0) return voidValue();
+
+  runtimeError(ERROR_CODE_UNDEFINED_VARIABLE, node, "Undefined variable '%s'.", node->val);
+  return NULL;
+}
+
+//  ns.member: a member of a namespace created with use_as
+static Generic *evalQualified(AstNode *node, Scope *p_scope) {
+  char *dot = strchr(node->val, '.');
+  *dot = '\0';
+  Generic *ns = Scope_get(p_scope, node->val, -1);
+  *dot = '.';
+
+  if (ns == NULL || ns->type != TYPE_NAMESPACE) {
+    runtimeError(ERROR_CODE_UNDEFINED_VARIABLE, node, "'%s' does not name a namespace.", node->val);
+  }
+  Generic *value = Scope_get((Scope *) ns->p_val, dot + 1, -1);
+  if (value == NULL) {
+    runtimeError(ERROR_CODE_UNDEFINED_VARIABLE, node, "Undefined variable '%s'.", node->val);
+  }
+  return value;
+}
+
+static Generic *evalAssignment(AstNode *node, Scope *p_scope, int depth) {
+  Generic *value = evalNode(node->children[1], p_scope, depth);
+  if (g_flow != FLOW_NORMAL) return value;
+
+  Scope_set(p_scope, node->children[0]->val, value, node->lineNumber);
+  return voidValue();
+}
+
+static Generic *evalList(AstNode *node, Scope *p_scope, int depth) {
+  Generic **items = (Generic **) malloc(sizeof(Generic *) * (node->childCount > 0 ? node->childCount : 1));
+  for (int i = 0; i < node->childCount; i++) {
+    items[i] = evalNode(node->children[i], p_scope, depth);
+    if (g_flow != FLOW_NORMAL) {
+      Generic *value = items[i];
+      for (int j = 0; j < i; j++) release(items[j]);
+      free(items);
+      return value;
+    }
+  }
+
+  //  List_new stores copies
+  List *list = List_new(items, node->childCount);
+  for (int i = 0; i < node->childCount; i++) release(items[i]);
+  free(items);
+  return Generic_new(TYPE_LIST, list, 0);
+}
+
+//  Functions capture their free variables in lexical mode and see the caller's scope otherwise
+static Generic *evalFunction(AstNode *node, Scope *p_scope) {
+  if (g_scoping_mode == SCOPING_LEXICAL) {
+    return Generic_new(TYPE_BYTECODE_CLOSURE, Closure_new(node, p_scope), 0);
+  }
+  return Generic_new(TYPE_FUNCTION, AstNode_copy(node, 0), 0);
+}
+
+static Generic *evalNode(AstNode *node, Scope *p_scope, int depth) {
+  switch (node->opcode) {
+    case OP_INT:
+      return intValue(parseInteger(node->val));
+
+    case OP_FLOAT: {
+      double *p_val = (double *) malloc(sizeof(double));
+      *p_val = parseFloat(node->val);
+      return Generic_new(TYPE_FLOAT, p_val, 0);
+    }
+
+    case OP_STRING: {
+      char **p_val = (char **) malloc(sizeof(char *));
+      *p_val = parseString(node->val);
+      return Generic_new(TYPE_STRING, p_val, 0);
+    }
+
+    case OP_IDENTIFIER:
+      return evalIdentifier(node, p_scope);
+
+    case OP_QUALIFIED:
+      return evalQualified(node, p_scope);
+
+    case OP_ASSIGNMENT:
+      return evalAssignment(node, p_scope, depth);
+
+    case OP_RETURN:
+      return evalReturn(node, p_scope, depth);
+
+    case OP_STATEMENT:
+      return evalSequence(node, 0, p_scope, depth);
+
+    case OP_APPLICATION:
+      return evalApplication(node, p_scope, depth);
+
+    case OP_FUNCTION:
+      return evalFunction(node, p_scope);
+
+    case OP_LIST:
+      return evalList(node, p_scope, depth);
+
+    case OP_SIGNATURE:
+      //  Type signatures only matter to the type checker
+      return voidValue();
+  }
+  return voidValue();
+}
+
+//  A body: its own `<-` ends it, and loops of the caller are out of reach
+static int enterBody(void) {
+  int savedLoopDepth = g_loopDepth;
+  g_loopDepth = 0;
+  return savedLoopDepth;
+}
+
+//  The body's scope may be freed next: hand out a value of our own
+static Generic *leaveBody(int savedLoopDepth, Generic *res) {
+  g_flow = FLOW_NORMAL;
+  g_loopDepth = savedLoopDepth;
+  return res->refCount == 0 ? res : Generic_copy(res);
+}
+
+Generic *eval(AstNode *node, Scope *p_scope, int depth) {
+  if (depth > 0) return evalNode(node, p_scope, depth);
+
+  int savedLoopDepth = enterBody();
+  return leaveBody(savedLoopDepth, evalNode(node, p_scope, 1));
+}
+
+int Interpret_paramCount(AstNode *p_fn) {
+  int count = 0;
+  while (count < p_fn->childCount && p_fn->children[count]->opcode == OP_IDENTIFIER) count++;
+  return count;
+}
+
+Generic *Interpret_functionBody(AstNode *p_fn, Scope *p_local) {
+  int savedLoopDepth = enterBody();
+  return leaveBody(savedLoopDepth, evalSequence(p_fn, Interpret_paramCount(p_fn), p_local, 1));
+}
+
+int Interpret_run(AstNode *program, Scope *p_global) {
+  Generic_free(eval(program, p_global, 0));
+  fflush(stdout);
+  return 0;
+}
@@ -0,0 +1,74 @@
+#ifndef INTERPRET_H
+#define INTERPRET_H
+
+#include "../ast.h"
+#include "../scope.h"
+#include "../generic.h"
+
+/**
+ * Tree-walking interpreter (franz --interpret)
+ *
+ * Evaluates the AST directly on the runtime in stdlib.c: values are
+ * Generics, variables live in Scopes and calls go through applyFunc, so a
+ * program starts at once and needs neither LLVM nor a C toolchain.
+ *
+ * Control flow follows the compiled backend:
+ * - `if`, `when`, `unless`, `cond`, `loop` and `while` run block literals
+ *   inline in the current scope, so assignments in a branch or loop body
+ *   update the enclosing variables.
+ * - `<-` in such a block gives the block's value. Inside a loop a value
+ *   other than void or a literal 0 also ends the loop, which returns it.
+ * - `and` and `or` short-circuit; `break` and `continue` leave loops.
+ * - A function without `<-` returns the value of its last expression.
+ *
+ * Values follow the stdlib's ownership rule: a result with refCount 0 is a
+ * temporary owned by the caller, anything else belongs to a scope or
+ * container.
+ */
+
+/**
+ * Evaluate a node
+ *
+ * Used for programs and modules (use, use_as, use_with). Depth 0 marks
+ * such a body: `<-` returns from it, loops outside it are out of reach of
+ * `break`, and the result is always a temporary.
+ *
+ * @param node - Node to evaluate
+ * @param p_scope - Scope to evaluate it in
+ * @param depth - 0 for a body, nesting depth of blocks inside it otherwise
+ * @return Value of the node
+ */
+Generic *eval(AstNode *node, Scope *p_scope, int depth);
+
+/**
+ * Number of parameters of a function node
+ *
+ * A function's children are its parameters (identifiers) followed by one
+ * statement per line of its body.
+ *
+ * @param p_fn - OP_FUNCTION node
+ * @return Number of leading parameter children
+ */
+int Interpret_paramCount(AstNode *p_fn);
+
+/**
+ * Evaluate the body of a called function
+ *
+ * Called by applyFunc once the parameters are bound in p_local.
+ *
+ * @param p_fn - OP_FUNCTION node
+ * @param p_local - Scope of the call
+ * @return Return value of the call (a temporary)
+ */
+Generic *Interpret_functionBody(AstNode *p_fn, Scope *p_local);
+
+/**
+ * Run a parsed program in the global scope
+ *
+ * @param program - Program root (OP_STATEMENT)
+ * @param p_global - Global scope from newGlobal
+ * @return Exit code of the program
+ */
+int Interpret_run(AstNode *program, Scope *p_global);
+
+#endif
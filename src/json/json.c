@@ -0,0 +1,399 @@
+#include "json.h"
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <stdarg.h>
+
+//  Nesting limit: deeper input is rejected instead of exhausting the stack
+#define JSON_MAX_DEPTH 256
+
+typedef struct JsonParser {
+  const char *text;
+  int length;
+  int pos;
+} JsonParser;
+
+static JsonValue *parseValue(JsonParser *p, int depth);
+
+static JsonValue *newValue(JsonType type) {
+  JsonValue *value = calloc(1, sizeof(JsonValue));
+  value->type = type;
+  return value;
+}
+
+static void skipWhitespace(JsonParser *p) {
+  while (p->pos < p->length) {
+    char c = p->text[p->pos];
+    if (c != ' ' && c != '\t' && c != '\n' && c != '\r') break;
+    p->pos++;
+  }
+}
+
+static bool matchLiteral(JsonParser *p, const char *literal) {
+  int n = strlen(literal);
+  if (p->pos + n > p->length || strncmp(p->text + p->pos, literal, n) != 0) return false;
+  p->pos += n;
+  return true;
+}
+
+static int hexValue(char c) {
+  if (c >= '0' && c <= '9') return c - '0';
+  if (c >= 'a' && c <= 'f') return c - 'a' + 10;
+  if (c >= 'A' && c <= 'F') return c - 'A' + 10;
+  return -1;
+}
+
+static bool parseHex4(JsonParser *p, unsigned *out) {
+  if (p->pos + 4 > p->length) return false;
+  unsigned code = 0;
+  for (int i = 0; i < 4; i++) {
+    int digit = hexValue(p->text[p->pos + i]);
+    if (digit < 0) return false;
+    code = code * 16 + digit;
+  }
+  p->pos += 4;
+  *out = code;
+  return true;
+}
+
+//  Encode a code point as UTF-8; returns the number of bytes written
+static int encodeUtf8(unsigned code, char *out) {
+  if (code < 0x80) {
+    out[0] = code;
+    return 1;
+  } else if (code < 0x800) {
+    out[0] = 0xC0 | (code >> 6);
+    out[1] = 0x80 | (code & 0x3F);
+    return 2;
+  } else if (code < 0x10000) {
+    out[0] = 0xE0 | (code >> 12);
+    out[1] = 0x80 | ((code >> 6) & 0x3F);
+    out[2] = 0x80 | (code & 0x3F);
+    return 3;
+  }
+  out[0] = 0xF0 | (code >> 18);
+  out[1] = 0x80 | ((code >> 12) & 0x3F);
+  out[2] = 0x80 | ((code >> 6) & 0x3F);
+  out[3] = 0x80 | (code & 0x3F);
+  return 4;
+}
+
+//  Parse a string literal (p->pos on the opening quote); returns a malloc'd copy
+static char *parseString(JsonParser *p) {
+  if (p->pos >= p->length || p->text[p->pos] != '"') return NULL;
+  p->pos++;
+
+  // Escapes only shrink the text, except \u which never grows past 6 -> 4 bytes
+  char *out = malloc(p->length - p->pos + 1);
+  int n = 0;
+
+  while (p->pos < p->length) {
+    char c = p->text[p->pos++];
+
+    if (c == '"') {
+      out[n] = '\0';
+      return out;
+    }
+
+    if ((unsigned char) c < 0x20) break;
+
+    if (c != '\\') {
+      out[n++] = c;
+      continue;
+    }
+
+    if (p->pos >= p->length) break;
+    char e = p->text[p->pos++];
+    switch (e) {
+      case '"': out[n++] = '"'; break;
+      case '\\': out[n++] = '\\'; break;
+      case '/': out[n++] = '/'; break;
+      case 'b': out[n++] = '\b'; break;
+      case 'f': out[n++] = '\f'; break;
+      case 'n': out[n++] = '\n'; break;
+      case 'r': out[n++] = '\r'; break;
+      case 't': out[n++] = '\t'; break;
+      case 'u': {
+        unsigned code;
+        if (!parseHex4(p, &code)) goto fail;
+        // Surrogate pair: "\ud83d\ude00" is a single code point
+        if (code >= 0xD800 && code <= 0xDBFF && p->pos + 6 <= p->length &&
+            p->text[p->pos] == '\\' && p->text[p->pos + 1] == 'u') {
+          p->pos += 2;
+          unsigned low;
+          if (!parseHex4(p, &low) || low < 0xDC00 || low > 0xDFFF) goto fail;
+          code = 0x10000 + ((code - 0xD800) << 10) + (low - 0xDC00);
+        }
+        n += encodeUtf8(code, out + n);
+        break;
+      }
+      default:
+        goto fail;
+    }
+  }
+
+fail:
+  free(out);
+  return NULL;
+}
+
+static JsonValue *parseNumber(JsonParser *p) {
+  const char *start = p->text + p->pos;
+  int n = 0;
+  while (p->pos + n < p->length && strchr("+-0123456789.eE", start[n]) != NULL) n++;
+  if (n == 0) return NULL;
+
+  char buf[64];
+  if (n >= (int) sizeof(buf)) return NULL;
+  memcpy(buf, start, n);
+  buf[n] = '\0';
+
+  char *end;
+  double number = strtod(buf, &end);
+  if (end != buf + n) return NULL;
+
+  p->pos += n;
+  JsonValue *value = newValue(JSON_NUMBER);
+  value->number = number;
+  return value;
+}
+
+static void appendItem(JsonValue *container, char *key, JsonValue *item) {
+  container->items = realloc(container->items, sizeof(JsonValue *) * (container->count + 1));
+  container->items[container->count] = item;
+  if (container->type == JSON_OBJECT) {
+    container->keys = realloc(container->keys, sizeof(char *) * (container->count + 1));
+    container->keys[container->count] = key;
+  }
+  container->count++;
+}
+
+static JsonValue *parseContainer(JsonParser *p, int depth) {
+  bool isObject = p->text[p->pos] == '{';
+  char close = isObject ? '}' : ']';
+  JsonValue *container = newValue(isObject ? JSON_OBJECT : JSON_ARRAY);
+  p->pos++;
+
+  skipWhitespace(p);
+  if (p->pos < p->length && p->text[p->pos] == close) {
+    p->pos++;
+    return container;
+  }
+
+  while (p->pos < p->length) {
+    char *key = NULL;
+    if (isObject) {
+      skipWhitespace(p);
+      key = parseString(p);
+      if (key == NULL) break;
+      skipWhitespace(p);
+      if (p->pos >= p->length || p->text[p->pos] != ':') {
+        free(key);
+        break;
+      }
+      p->pos++;
+    }
+
+    JsonValue *item = parseValue(p, depth + 1);
+    if (item == NULL) {
+      free(key);
+      break;
+    }
+    appendItem(container, key, item);
+
+    skipWhitespace(p);
+    if (p->pos < p->length && p->text[p->pos] == ',') {
+      p->pos++;
+      continue;
+    }
+    if (p->pos < p->length && p->text[p->pos] == close) {
+      p->pos++;
+      return container;
+    }
+    break;
+  }
+
+  Json_free(container);
+  return NULL;
+}
+
+static JsonValue *parseValue(JsonParser *p, int depth) {
+  if (depth > JSON_MAX_DEPTH) return NULL;
+
+  skipWhitespace(p);
+  if (p->pos >= p->length) return NULL;
+
+  char c = p->text[p->pos];
+  if (c == '{' || c == '[') return parseContainer(p, depth);
+
+  if (c == '"') {
+    char *string = parseString(p);
+    if (string == NULL) return NULL;
+    JsonValue *value = newValue(JSON_STRING);
+    value->string = string;
+    return value;
+  }
+
+  if (matchLiteral(p, "null")) return newValue(JSON_NULL);
+  if (matchLiteral(p, "true")) {
+    JsonValue *value = newValue(JSON_BOOL);
+    value->boolean = true;
+    return value;
+  }
+  if (matchLiteral(p, "false")) return newValue(JSON_BOOL);
+
+  return parseNumber(p);
+}
+
+JsonValue *Json_parse(const char *text, int length) {
+  JsonParser parser = { text, length, 0 };
+  JsonValue *value = parseValue(&parser, 0);
+
+  // Only whitespace may follow the value
+  skipWhitespace(&parser);
+  if (value != NULL && parser.pos != length) {
+    Json_free(value);
+    return NULL;
+  }
+  return value;
+}
+
+void Json_free(JsonValue *value) {
+  if (value == NULL) return;
+
+  for (int i = 0; i < value->count; i++) {
+    Json_free(value->items[i]);
+    if (value->keys) free(value->keys[i]);
+  }
+  free(value->items);
+  free(value->keys);
+  free(value->string);
+  free(value);
+}
+
+JsonValue *Json_get(const JsonValue *object, const char *key) {
+  if (object == NULL || object->type != JSON_OBJECT) return NULL;
+
+  for (int i = 0; i < object->count; i++) {
+    if (strcmp(object->keys[i], key) == 0) return object->items[i];
+  }
+  return NULL;
+}
+
+const char *Json_getString(const JsonValue *object, const char *key) {
+  JsonValue *value = Json_get(object, key);
+  return value != NULL && value->type == JSON_STRING ? value->string : NULL;
+}
+
+int Json_getInt(const JsonValue *object, const char *key, int fallback) {
+  JsonValue *value = Json_get(object, key);
+  return value != NULL && value->type == JSON_NUMBER ? (int) value->number : fallback;
+}
+
+void JsonBuffer_init(JsonBuffer *buffer) {
+  buffer->capacity = 256;
+  buffer->length = 0;
+  buffer->data = malloc(buffer->capacity);
+  buffer->data[0] = '\0';
+}
+
+void JsonBuffer_free(JsonBuffer *buffer) {
+  free(buffer->data);
+  buffer->data = NULL;
+  buffer->length = 0;
+  buffer->capacity = 0;
+}
+
+static void reserve(JsonBuffer *buffer, int extra) {
+  if (buffer->length + extra + 1 <= buffer->capacity) return;
+  while (buffer->length + extra + 1 > buffer->capacity) buffer->capacity *= 2;
+  buffer->data = realloc(buffer->data, buffer->capacity);
+}
+
+static void appendBytes(JsonBuffer *buffer, const char *bytes, int n) {
+  reserve(buffer, n);
+  memcpy(buffer->data + buffer->length, bytes, n);
+  buffer->length += n;
+  buffer->data[buffer->length] = '\0';
+}
+
+void JsonBuffer_append(JsonBuffer *buffer, const char *text) {
+  appendBytes(buffer, text, strlen(text));
+}
+
+void JsonBuffer_appendf(JsonBuffer *buffer, const char *format, ...) {
+  va_list args;
+  va_start(args, format);
+  int n = vsnprintf(NULL, 0, format, args);
+  va_end(args);
+
+  reserve(buffer, n);
+  va_start(args, format);
+  vsnprintf(buffer->data + buffer->length, n + 1, format, args);
+  va_end(args);
+  buffer->length += n;
+}
+
+void JsonBuffer_appendString(JsonBuffer *buffer, const char *text) {
+  if (text == NULL) {
+    JsonBuffer_append(buffer, "null");
+    return;
+  }
+
+  appendBytes(buffer, "\"", 1);
+  for (const char *c = text; *c; c++) {
+    switch (*c) {
+      case '"': appendBytes(buffer, "\\\"", 2); break;
+      case '\\': appendBytes(buffer, "\\\\", 2); break;
+      case '\n': appendBytes(buffer, "\\n", 2); break;
+      case '\r': appendBytes(buffer, "\\r", 2); break;
+      case '\t': appendBytes(buffer, "\\t", 2); break;
+      default:
+        if ((unsigned char) *c < 0x20) {
+          JsonBuffer_appendf(buffer, "\\u%04x", (unsigned char) *c);
+        } else {
+          appendBytes(buffer, c, 1);
+        }
+    }
+  }
+  appendBytes(buffer, "\"", 1);
+}
+
+void JsonBuffer_appendValue(JsonBuffer *buffer, const JsonValue *value) {
+  if (value == NULL) {
+    JsonBuffer_append(buffer, "null");
+    return;
+  }
+
+  switch (value->type) {
+    case JSON_NULL:
+      JsonBuffer_append(buffer, "null");
+      break;
+    case JSON_BOOL:
+      JsonBuffer_append(buffer, value->boolean ? "true" : "false");
+      break;
+    case JSON_NUMBER:
+      if (value->number == (long long) value->number) {
+        JsonBuffer_appendf(buffer, "%lld", (long long) value->number);
+      } else {
+        JsonBuffer_appendf(buffer, "%.17g", value->number);
+      }
+      break;
+    case JSON_STRING:
+      JsonBuffer_appendString(buffer, value->string);
+      break;
+    case JSON_ARRAY:
+    case JSON_OBJECT:
+      JsonBuffer_append(buffer, value->type == JSON_ARRAY ? "[" : "{");
+      for (int i = 0; i < value->count; i++) {
+        if (i > 0) JsonBuffer_append(buffer, ",");
+        if (value->type == JSON_OBJECT) {
+          JsonBuffer_appendString(buffer, value->keys[i]);
+          JsonBuffer_append(buffer, ":");
+        }
+        JsonBuffer_appendValue(buffer, value->items[i]);
+      }
+      JsonBuffer_append(buffer, value->type == JSON_ARRAY ? "]" : "}");
+      break;
+  }
+}
@@ -0,0 +1,126 @@
+#ifndef JSON_H
+#define JSON_H
+
+#include <stdbool.h>
+
+/*
+ * Franz JSON - minimal reader and writer for tool protocols
+ *
+ * Json_parse() builds a tree of JsonValue nodes (used to read LSP requests).
+ * JsonBuffer accumulates JSON text; callers write the structure themselves
+ * and use JsonBuffer_appendString() for every string so it is escaped.
+ *
+ *   JsonBuffer out;
+ *   JsonBuffer_init(&out);
+ *   JsonBuffer_append(&out, "{\"name\":");
+ *   JsonBuffer_appendString(&out, name);
+ *   JsonBuffer_append(&out, "}");
+ */
+
+typedef enum {
+  JSON_NULL,
+  JSON_BOOL,
+  JSON_NUMBER,
+  JSON_STRING,
+  JSON_ARRAY,
+  JSON_OBJECT
+} JsonType;
+
+//  Parsed JSON value. Arrays use items; objects use keys and items in parallel.
+typedef struct JsonValue {
+  JsonType type;
+  bool boolean;
+  double number;
+  char *string;
+  char **keys;
+  struct JsonValue **items;
+  int count;
+} JsonValue;
+
+//  Growable output buffer (data is always NUL-terminated)
+typedef struct JsonBuffer {
+  char *data;
+  int length;
+  int capacity;
+} JsonBuffer;
+
+/**
+ * Parse JSON text.
+ * @param text JSON text (need not be NUL-terminated)
+ * @param length Length of text in bytes
+ * @return Parsed value owned by the caller (Json_free), or NULL if the text is not valid JSON
+ */
+JsonValue *Json_parse(const char *text, int length);
+
+/**
+ * Free a parsed value and all of its children.
+ * @param value Value returned by Json_parse (may be NULL)
+ */
+void Json_free(JsonValue *value);
+
+/**
+ * Look up a member of an object.
+ * @param object Object value (any other value yields NULL)
+ * @param key Member name
+ * @return Member value, or NULL if missing
+ */
+JsonValue *Json_get(const JsonValue *object, const char *key);
+
+/**
+ * Look up a string member of an object.
+ * @param object Object value
+ * @param key Member name
+ * @return String owned by the value, or NULL if missing or not a string
+ */
+const char *Json_getString(const JsonValue *object, const char *key);
+
+/**
+ * Look up an integer member of an object.
+ * @param object Object value
+ * @param key Member name
+ * @param fallback Value returned when the member is missing or not a number
+ * @return Member value truncated to int
+ */
+int Json_getInt(const JsonValue *object, const char *key, int fallback);
+
+/**
+ * Start an empty buffer.
+ * @param buffer Buffer to initialize
+ */
+void JsonBuffer_init(JsonBuffer *buffer);
+
+/**
+ * Release the buffer's memory.
+ * @param buffer Buffer to free
+ */
+void JsonBuffer_free(JsonBuffer *buffer);
+
+/**
+ * Append raw JSON text (punctuation, numbers, literals).
+ * @param buffer Output buffer
+ * @param text Text copied as-is
+ */
+void JsonBuffer_append(JsonBuffer *buffer, const char *text);
+
+/**
+ * Append printf-formatted raw JSON text.
+ * @param buffer Output buffer
+ * @param format printf-style format
+ */
+void JsonBuffer_appendf(JsonBuffer *buffer, const char *format, ...);
+
+/**
+ * Append a quoted, escaped JSON string (NULL is written as null).
+ * @param buffer Output buffer
+ * @param text String to quote
+ */
+void JsonBuffer_appendString(JsonBuffer *buffer, const char *text);
+
+/**
+ * Append a parsed value re-serialized as JSON.
+ * @param buffer Output buffer
+ * @param value Value to write (NULL is written as null)
+ */
+void JsonBuffer_appendValue(JsonBuffer *buffer, const JsonValue *value);
+
+#endif
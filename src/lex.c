@@ -6,18 +6,30 @@
 #include "lex.h"
 #include "tokens.h"
 #include "string.h"
+#include "diagnostics/diagnostic.h"
+
+// span of code[start, end) on the current line
+static DiagnosticSpan lexSpan(int sourceId, int lineNumber, int lineStart, int start, int end) {
+  DiagnosticSpan span = {
+    sourceId, lineNumber, start - lineStart + 1, lineNumber, end - lineStart + 1
+  };
+  return span;
+}
 
-// handles errors while scanning chars in a string
-void handleStringError(char c, int lineNumber) {
+// handles errors while scanning chars in a string (span covers the string so far)
+// returns true if the string cannot continue past c
+bool handleStringError(char c, DiagnosticSpan span) {
   if (c == '\n') {
-    printf("Syntax Error @ Line %i: Unexpected new line before string closed.\n", lineNumber);
-    exit(0);
+    Diagnostic_add("Syntax Error", ERROR_CODE_UNTERMINATED_STRING, span, "Unexpected new line before string closed.");
+    return true;
   }
 
   if (c == '\0') {
-    printf("Syntax Error @ Line %i: Unexpected end of file before string closed.\n", lineNumber);
-    exit(0);
+    Diagnostic_add("Syntax Error", ERROR_CODE_UNTERMINATED_STRING, span, "Unexpected end of file before string closed.");
+    return true;
   }
+
+  return false;
 }
 
 //  Lex code into array-based token list
@@ -31,17 +43,39 @@ TokenArray* lex(char *code, int fileLength) {
   // line number
   int lineNumber = 1;
 
+  //  Span tracking: offset where the current line begins, source the tokens belong to
+  int lineStart = 0;
+  int sourceId = Diagnostic_findSource(code, fileLength);
+
   // for each char (including terminator, helps us not need to push number tokens if they are last)
   int i = 0;
   while (i < fileLength) {
     char c = code[i];
+    int tokenStart = i;
+    int tokenCount = tokens->count;
 
-    if (c == '\n') lineNumber++;
+    if (c == '\n') {
+      lineNumber++;
+      lineStart = i + 1;
+    }
 
     if (c == '/' && code[i + 1] == '/') {
-      // comments
+      // comments are kept as trivia for the formatter
+      int ownLine = 1;
+      for (int k = lineStart; k < i; k++) {
+        if (strchr(" \t\r\f\v", code[k]) == NULL) ownLine = 0;
+      }
       while (code[i] != '\n' && i < fileLength) i++;
+
+      int textEnd = i;
+      while (textEnd > tokenStart && strchr(" \t\r\f\v", code[textEnd - 1]) != NULL) textEnd--;
+      char *text = malloc(textEnd - tokenStart + 1);
+      memcpy(text, &code[tokenStart], textEnd - tokenStart);
+      text[textEnd - tokenStart] = '\0';
+      TokenArray_pushComment(tokens, text, lineNumber, tokenStart - lineStart + 1, tokenStart, ownLine);
+
       lineNumber++;
+      lineStart = i + 1;
     } else if (c == '(') {
       TokenArray_push(tokens, NULL, TOK_APPLYOPEN, lineNumber);
     } else if (c == ')') {
@@ -76,15 +110,23 @@ TokenArray* lex(char *code, int fileLength) {
       i++;
 
       // count to last char in string (last quote)
+      // an unterminated string is reported and ends at the line break
+      bool unterminated = false;
       while (code[i] != '"') {
 
         // error handling
-        handleStringError(code[i], lineNumber);
+        if (handleStringError(code[i], lexSpan(sourceId, lineNumber, lineStart, tokenStart, i))) {
+          unterminated = true;
+          break;
+        }
         
         // skip escape codes
         if (code[i] == '\\') {
           i++;
-          handleStringError(code[i], lineNumber);
+          if (handleStringError(code[i], lexSpan(sourceId, lineNumber, lineStart, tokenStart, i))) {
+            unterminated = true;
+            break;
+          }
         }
 
         i++;
@@ -99,6 +141,9 @@ TokenArray* lex(char *code, int fileLength) {
 
       free(val);
 
+      // leave the line break (or end of file) for the next iteration
+      if (unterminated) i--;
+
     } else if (c == '0' && (code[i + 1] == 'x' || code[i + 1] == 'X')) {
       //  Hexadecimal integer or float literal (0x1A or 0x1.5p2)
       int numStart = i;
@@ -134,8 +179,10 @@ TokenArray* lex(char *code, int fileLength) {
 
         // Exponent must have digits
         if (!isdigit((unsigned char) code[i])) {
-          printf("Syntax Error @ Line %i: Hexadecimal float requires exponent after 'p'.\n", lineNumber);
-          exit(0);
+          Diagnostic_add(
+            "Syntax Error", ERROR_CODE_INVALID_NUMBER, lexSpan(sourceId, lineNumber, lineStart, tokenStart, i + 1),
+            "Hexadecimal float requires exponent after 'p'."
+          );
         }
 
         while (isdigit((unsigned char) code[i])) {
@@ -144,8 +191,10 @@ TokenArray* lex(char *code, int fileLength) {
       }
 
       if (!hasDigits && !isHexFloat) {
-        printf("Syntax Error @ Line %i: Invalid hexadecimal literal - no digits after '0x'.\n", lineNumber);
-        exit(0);
+        Diagnostic_add(
+          "Syntax Error", ERROR_CODE_INVALID_NUMBER, lexSpan(sourceId, lineNumber, lineStart, tokenStart, i + 1),
+          "Invalid hexadecimal literal - no digits after '0x'."
+        );
       }
 
       // Extract the hex literal
@@ -173,8 +222,10 @@ TokenArray* lex(char *code, int fileLength) {
       }
 
       if (!hasDigits) {
-        printf("Syntax Error @ Line %i: Invalid binary literal - no digits after '0b'.\n", lineNumber);
-        exit(0);
+        Diagnostic_add(
+          "Syntax Error", ERROR_CODE_INVALID_NUMBER, lexSpan(sourceId, lineNumber, lineStart, tokenStart, i + 1),
+          "Invalid binary literal - no digits after '0b'."
+        );
       }
 
       char *val = malloc(i - numStart + 1);
@@ -196,8 +247,10 @@ TokenArray* lex(char *code, int fileLength) {
       }
 
       if (!hasDigits) {
-        printf("Syntax Error @ Line %i: Invalid octal literal - no digits after '0o'.\n", lineNumber);
-        exit(0);
+        Diagnostic_add(
+          "Syntax Error", ERROR_CODE_INVALID_NUMBER, lexSpan(sourceId, lineNumber, lineStart, tokenStart, i + 1),
+          "Invalid octal literal - no digits after '0o'."
+        );
       }
 
       char *val = malloc(i - numStart + 1);
@@ -230,8 +283,10 @@ TokenArray* lex(char *code, int fileLength) {
             // This is a decimal point in a float
             if (isFloat) {
               // case were we saw a point before
-              printf("Syntax Error @ Line %i: Multiple decimal points in single number.\n", lineNumber);
-              exit(0);
+              Diagnostic_add(
+                "Syntax Error", ERROR_CODE_INVALID_NUMBER, lexSpan(sourceId, lineNumber, lineStart, tokenStart, i + 1),
+                "Multiple decimal points in single number."
+              );
             } else {
               isFloat = true;
             }
@@ -257,8 +312,10 @@ TokenArray* lex(char *code, int fileLength) {
 
         // Must have at least one digit after 'e' or 'e+'/'e-'
         if (!isdigit((unsigned char) code[i])) {
-          printf("Syntax Error @ Line %i: Invalid scientific notation - expected digit after 'e'.\n", lineNumber);
-          exit(0);
+          Diagnostic_add(
+            "Syntax Error", ERROR_CODE_INVALID_NUMBER, lexSpan(sourceId, lineNumber, lineStart, tokenStart, i + 1),
+            "Invalid scientific notation - expected digit after 'e'."
+          );
         }
 
         // Parse exponent digits
@@ -322,15 +379,33 @@ TokenArray* lex(char *code, int fileLength) {
       i--;
     } else if (strchr(" \n\r\t\f\v", code[i]) == NULL) {
       // handle unexpected char
-      printf("Syntax Error @ Line %i: Unexpected char \"%c\".\n", lineNumber, c);
-      exit(0);
+      Diagnostic_add(
+        "Syntax Error", ERROR_CODE_UNEXPECTED_CHAR, lexSpan(sourceId, lineNumber, lineStart, tokenStart, i + 1),
+        "Unexpected char \"%c\".", c
+      );
+    }
+
+    //  Record where the token produced by this iteration sits in the source
+    //  (i is on its last char here)
+    if (tokens->count > tokenCount) {
+      Token *tok = &tokens->tokens[tokens->count - 1];
+      tok->offset = tokenStart;
+      tok->column = tokenStart - lineStart + 1;
+      tok->endOffset = i + 1;
+      tok->endLine = lineNumber;
+      tok->endColumn = i + 1 - lineStart + 1;
+      tok->sourceId = sourceId;
     }
 
     i++;
   }
 
-  // Add END token
+  // Add END token (zero-width, at the end of the code)
   TokenArray_push(tokens, NULL, TOK_END, lineNumber);
+  Token *endTok = &tokens->tokens[tokens->count - 1];
+  endTok->offset = endTok->endOffset = i;
+  endTok->column = endTok->endColumn = i - lineStart + 1;
+  endTok->sourceId = sourceId;
 
   return tokens;
 }
\ No newline at end of file
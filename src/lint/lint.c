@@ -0,0 +1,710 @@
+#include "lint.h"
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include "../tokens.h"
+#include "../lex.h"
+#include "../parse.h"
+#include "../ast.h"
+#include "../file.h"
+#include "../freevar/freevar.h"
+#include "../optimization/compile.h"
+#include "../diagnostics/diagnostic.h"
+#include "../lsp/lsp_stdlib.h"
+#include "../lsp/lsp_analysis.h"
+
+static const char *g_ruleNames[LINT_RULE_COUNT] = {
+  "unused-binding",
+  "unused-param",
+  "shadowed-stdlib",
+  "immutable-assign",
+  "unused-import",
+  "if-without-else",
+  "unreachable-code"
+};
+
+#define LINT_DIRECTIVE "// franz-lint: disable="
+
+typedef enum LintBindingKind {
+  BINDING_PARAM,
+  BINDING_LOCAL,      // assignment inside a function
+  BINDING_TOP_LEVEL,  // assignment in the program: may be used by importers
+} LintBindingKind;
+
+//  A name introduced by a parameter or assignment. CompileEnv maps names to the
+// index of their LintBinding (VarBinding.offset).
+typedef struct LintBinding {
+  AstNode *name;        // Identifier node (parameter or assignment target)
+  LintBindingKind kind;
+  int isMutable;
+  bool used;
+  AstNode *import;      // use_as call when the value is a module namespace
+} LintBinding;
+
+//  Function body waiting for the end of the block that contains it
+typedef struct LintDeferred {
+  AstNode *function;
+  CompileEnv *env;
+} LintDeferred;
+
+//  Module loaded for its top-level names
+typedef struct LintModule {
+  char *path;           // As written in the import
+  bool ok;              // found and parsed
+  char **exports;
+  int exportCount;
+} LintModule;
+
+//  Module imported without a callback: its names are looked up in the whole file
+typedef struct LintGlobalImport {
+  AstNode *path;        // Path string node
+  LintModule *module;
+  const char *prefix;   // `(use_as "m.franz" "ns")` namespace, NULL for use
+} LintGlobalImport;
+
+typedef struct Linter {
+  const char *path;
+  bool enabled[LINT_RULE_COUNT];
+  int warnings;
+
+  LintBinding *bindings;
+  int bindingCount;
+
+  LintDeferred *deferred;
+  int deferredCount;
+
+  LintModule *modules;
+  int moduleCount;
+
+  LintGlobalImport *imports;
+  int importCount;
+
+  char **names;         // Names that did not resolve to a binding (globals, module names)
+  int nameCount;
+} Linter;
+
+//  Statements of one block, walked in order
+typedef struct LintBlock {
+  CompileEnv *env;
+  bool inFunction;
+  bool returned;        // a <- statement was seen
+  bool reported;        // unreachable code already reported for this block
+} LintBlock;
+
+const char *Lint_ruleName(LintRule rule) {
+  return g_ruleNames[rule];
+}
+
+//  Returns false when the rule is disabled (nothing was collected)
+static bool warn(Linter *linter, LintRule rule, const AstNode *node, const char *format, const char *name) {
+  if (!linter->enabled[rule]) return false;
+
+  char message[512];
+  snprintf(message, sizeof(message), format, name);
+  Diagnostic_add("Warning", ERROR_CODE_NONE, Diagnostic_spanOfNode(node), "%s [%s]", message, g_ruleNames[rule]);
+  linter->warnings++;
+  return true;
+}
+
+static bool ignored(const char *name) {
+  return name[0] == '_';
+}
+
+static void addName(Linter *linter, const char *name) {
+  for (int i = 0; i < linter->nameCount; i++) {
+    if (strcmp(linter->names[i], name) == 0) return;
+  }
+  linter->names = realloc(linter->names, sizeof(char *) * (linter->nameCount + 1));
+  linter->names[linter->nameCount++] = strdup(name);
+}
+
+static bool hasName(Linter *linter, const char *name) {
+  for (int i = 0; i < linter->nameCount; i++) {
+    if (strcmp(linter->names[i], name) == 0) return true;
+  }
+  return false;
+}
+
+// ---------------------------------------------------------------------------
+// Modules
+// ---------------------------------------------------------------------------
+
+//  use, use_as or use_with call (NULL otherwise)
+static const char *importKind(const AstNode *node) {
+  if (node->opcode != OP_APPLICATION || node->childCount < 2) return NULL;
+  const AstNode *callee = node->children[0];
+  if (callee->opcode != OP_IDENTIFIER) return NULL;
+  if (strcmp(callee->val, "use") == 0 || strcmp(callee->val, "use_as") == 0 ||
+      strcmp(callee->val, "use_with") == 0) {
+    return callee->val;
+  }
+  return NULL;
+}
+
+//  Top-level names a module defines; false when it cannot be read or parsed
+static bool readExports(const char *fromPath, const char *modulePath, char ***exports, int *count) {
+  *exports = NULL;
+  *count = 0;
+
+  char *path = LspAnalysis_resolveImport(fromPath, modulePath);
+  char *code = path != NULL ? readFile(path, false) : NULL;
+  free(path);
+  if (code == NULL) return false;
+
+  // Modules are loaded before any warning is collected: their errors are dropped
+  TokenArray *tokens = lex(code, strlen(code));
+  AstNode *ast = Diagnostic_count() == 0 && tokens->count > 2 ? parseProgram(tokens) : NULL;
+  bool ok = Diagnostic_count() == 0;
+  Diagnostic_clear();
+
+  for (int i = 0; ok && ast != NULL && i < ast->childCount; i++) {
+    AstNode *statement = ast->children[i];
+    int n = statement->opcode == OP_STATEMENT ? statement->childCount : 1;
+    for (int j = 0; j < n; j++) {
+      AstNode *node = statement->opcode == OP_STATEMENT ? statement->children[j] : statement;
+      if (node->opcode == OP_ASSIGNMENT) {
+        *exports = realloc(*exports, sizeof(char *) * (*count + 1));
+        (*exports)[(*count)++] = strdup(node->children[0]->val);
+      }
+    }
+  }
+
+  if (ast) AstNode_free(ast);
+  TokenArray_free(tokens);
+  free(code);
+  return ok;
+}
+
+//  Load every module the program imports (any string argument of use/use_as/use_with)
+static void loadModules(Linter *linter, AstNode *node) {
+  if (importKind(node) != NULL) {
+    for (int i = 1; i < node->childCount; i++) {
+      AstNode *argument = node->children[i];
+      if (argument->opcode != OP_STRING) continue;
+
+      bool loaded = false;
+      for (int m = 0; m < linter->moduleCount && !loaded; m++) {
+        loaded = strcmp(linter->modules[m].path, argument->val) == 0;
+      }
+      if (loaded) continue;
+
+      linter->modules = realloc(linter->modules, sizeof(LintModule) * (linter->moduleCount + 1));
+      LintModule *module = &linter->modules[linter->moduleCount++];
+      module->path = strdup(argument->val);
+      module->ok = readExports(linter->path, argument->val, &module->exports, &module->exportCount);
+    }
+  }
+  for (int i = 0; i < node->childCount; i++) loadModules(linter, node->children[i]);
+}
+
+//  Names a loaded module defines; NULL when it could not be loaded
+static LintModule *findModule(Linter *linter, const char *path) {
+  for (int i = 0; i < linter->moduleCount; i++) {
+    LintModule *module = &linter->modules[i];
+    if (strcmp(module->path, path) == 0) return module->ok ? module : NULL;
+  }
+  return NULL;
+}
+
+//  Paths imported by a use/use_with call: every string argument
+static bool isModulePath(const AstNode *call, int index) {
+  return index > 0 && call->children[index]->opcode == OP_STRING;
+}
+
+//  Any identifier named name below node (FreeVar skips builtin names a module may redefine)
+static bool referencesName(const AstNode *node, const char *name) {
+  if (node->opcode == OP_IDENTIFIER && strcmp(node->val, name) == 0) return true;
+  for (int i = 0; i < node->childCount; i++) {
+    if (referencesName(node->children[i], name)) return true;
+  }
+  return false;
+}
+
+//  (use "m.franz" {...}): some name of each module must be used in the callback
+static void checkScopedImport(Linter *linter, AstNode *call, AstNode *callback) {
+  FreeVar_analyze(callback);
+
+  for (int i = 1; i < call->childCount; i++) {
+    if (!isModulePath(call, i)) continue;
+
+    LintModule *module = findModule(linter, call->children[i]->val);
+    if (module == NULL) continue;
+
+    bool used = module->exportCount == 0;  // a module without definitions is imported for its effects
+    for (int e = 0; e < module->exportCount && !used; e++) {
+      for (int f = 0; f < callback->freeVarsCount && !used; f++) {
+        used = strcmp(module->exports[e], callback->freeVars[f]) == 0;
+      }
+      if (!used && FreeVar_is_global_builtin(module->exports[e])) {
+        used = referencesName(callback, module->exports[e]);
+      }
+    }
+    if (!used) {
+      warn(linter, LINT_UNUSED_IMPORT, call->children[i], "Nothing from '%s' is used in the callback.",
+           call->children[i]->val);
+    }
+  }
+}
+
+//  (use "m.franz") or (use_as "m.franz" "ns"): checked once the whole file was walked
+static void recordGlobalImport(Linter *linter, AstNode *call, const char *prefix) {
+  for (int i = 1; i < call->childCount; i++) {
+    if (!isModulePath(call, i)) continue;
+    if (prefix != NULL && i > 1) break;  // use_as: the second string is the namespace
+
+    LintModule *module = findModule(linter, call->children[i]->val);
+    if (module == NULL) continue;
+
+    linter->imports = realloc(linter->imports, sizeof(LintGlobalImport) * (linter->importCount + 1));
+    linter->imports[linter->importCount++] = (LintGlobalImport) { call->children[i], module, prefix };
+  }
+}
+
+static void checkGlobalImports(Linter *linter) {
+  for (int i = 0; i < linter->importCount; i++) {
+    LintGlobalImport *import = &linter->imports[i];
+
+    LintModule *module = import->module;
+
+    bool used = module->exportCount == 0;
+    for (int e = 0; e < module->exportCount && !used; e++) {
+      if (import->prefix == NULL) {
+        used = hasName(linter, module->exports[e]);
+      } else {
+        char name[512];
+        snprintf(name, sizeof(name), "%s_%s", import->prefix, module->exports[e]);
+        used = hasName(linter, name);
+        snprintf(name, sizeof(name), "%s.%s", import->prefix, module->exports[e]);
+        used = used || hasName(linter, name);
+      }
+    }
+    if (!used) {
+      warn(linter, LINT_UNUSED_IMPORT, import->path, "Nothing from '%s' is used.", import->path->val);
+    }
+  }
+}
+
+// ---------------------------------------------------------------------------
+// Scopes
+// ---------------------------------------------------------------------------
+
+static LintBinding *lookup(Linter *linter, CompileEnv *env, const char *name) {
+  VarBinding *binding = CompileEnv_lookup(env, name);
+  return binding != NULL ? &linter->bindings[binding->offset] : NULL;
+}
+
+static LintBinding *bind(Linter *linter, CompileEnv *env, AstNode *name, LintBindingKind kind, int isMutable) {
+  if (LspStdlib_has(name->val)) {
+    warn(linter, LINT_SHADOWED_STDLIB, name, "'%s' shadows the stdlib function of the same name.", name->val);
+  }
+
+  linter->bindings = realloc(linter->bindings, sizeof(LintBinding) * (linter->bindingCount + 1));
+  LintBinding *binding = &linter->bindings[linter->bindingCount];
+  binding->name = name;
+  binding->kind = kind;
+  binding->isMutable = isMutable;
+  binding->used = false;
+  binding->import = NULL;
+
+  BindingMap_add(env->bindings, name->val, linter->bindingCount, 0);
+  linter->bindingCount++;
+  return binding;
+}
+
+//  Mark a name as read; names no binding defines are remembered for imports
+static void useName(Linter *linter, CompileEnv *env, const char *name) {
+  LintBinding *binding = lookup(linter, env, name);
+  if (binding != NULL) {
+    binding->used = true;
+  } else {
+    addName(linter, name);
+  }
+}
+
+//  A parameter before a used one cannot be dropped: {k v -> ...} must take the key
+static bool laterParamUsed(Linter *linter, CompileEnv *env, int index) {
+  for (int i = index + 1; i < env->bindings->count; i++) {
+    LintBinding *binding = &linter->bindings[env->bindings->bindings[i].offset];
+    if (binding->kind != BINDING_PARAM) break;
+    if (binding->used) return true;
+  }
+  return false;
+}
+
+//  Report what a scope defined and never read
+static void checkUnused(Linter *linter, CompileEnv *env) {
+  for (int i = 0; i < env->bindings->count; i++) {
+    LintBinding *binding = &linter->bindings[env->bindings->bindings[i].offset];
+    const char *name = binding->name->val;
+    if (binding->used || ignored(name)) continue;
+    if (binding->kind == BINDING_PARAM && laterParamUsed(linter, env, i)) continue;
+
+    if (binding->import != NULL) {
+      warn(linter, LINT_UNUSED_IMPORT, binding->name, "Module namespace '%s' is never used.", name);
+    } else if (binding->kind == BINDING_PARAM) {
+      warn(linter, LINT_UNUSED_PARAM, binding->name,
+           "Parameter '%s' is never used (prefix it with '_' if that is intended).", name);
+    } else if (binding->kind == BINDING_LOCAL) {
+      warn(linter, LINT_UNUSED_BINDING, binding->name, "'%s' is assigned but never used.", name);
+    }
+  }
+}
+
+// ---------------------------------------------------------------------------
+// Walk
+// ---------------------------------------------------------------------------
+
+static void lintNode(Linter *linter, AstNode *node, CompileEnv *env, bool valueUsed);
+static void lintStatements(Linter *linter, AstNode *node, int first, LintBlock *block);
+static void finishBlock(Linter *linter, LintBlock *block, int mark);
+
+static int paramCount(const AstNode *fn) {
+  int count = 0;
+  while (count < fn->childCount && fn->children[count]->opcode == OP_IDENTIFIER) count++;
+  return count;
+}
+
+static void lintAssignment(Linter *linter, AstNode *node, CompileEnv *env, bool inFunction) {
+  AstNode *name = node->children[0];
+  AstNode *value = node->children[1];
+  lintNode(linter, value, env, true);
+
+  // Reassignment in the same scope
+  VarBinding *local = BindingMap_lookup(env->bindings, name->val);
+  if (local != NULL) {
+    LintBinding *binding = &linter->bindings[local->offset];
+    if (!binding->isMutable && !node->isMutable && value->opcode != OP_FUNCTION) {
+      if (warn(linter, LINT_IMMUTABLE_ASSIGN, node,
+               "'%s' is reassigned but was not declared with 'mut'.", name->val)) {
+        Diagnostic_addRelated(Diagnostic_spanOfNode(binding->name), "'%s' was first assigned here", name->val);
+      }
+    }
+    return;
+  }
+
+  // Assigning an outer mutable variable from a closure updates it
+  LintBinding *outer = lookup(linter, env, name->val);
+  if (outer != NULL && outer->isMutable && !node->isMutable) return;
+
+  LintBinding *binding = bind(linter, env, name, inFunction ? BINDING_LOCAL : BINDING_TOP_LEVEL, node->isMutable);
+  const char *kind = importKind(value);
+  if (kind != NULL && strcmp(kind, "use_as") == 0) binding->import = value;
+}
+
+static void lintApplication(Linter *linter, AstNode *node, CompileEnv *env, bool valueUsed) {
+  AstNode *callee = node->childCount > 0 ? node->children[0] : NULL;
+  bool builtin = callee != NULL && callee->opcode == OP_IDENTIFIER && lookup(linter, env, callee->val) == NULL;
+
+  // (if c {...}) and (if c1 {...} c2 {...}) have no else branch
+  if (builtin && valueUsed && strcmp(callee->val, "if") == 0 && node->childCount >= 3 &&
+      (node->childCount - 1) % 2 == 0) {
+    warn(linter, LINT_IF_WITHOUT_ELSE, node,
+         "'%s' has no else branch but its value is used (it is void when no condition holds).", "if");
+  }
+
+  const char *kind = builtin ? importKind(node) : NULL;
+  if (kind != NULL && strcmp(kind, "use_as") == 0) {
+    // (use_as "m.franz" "ns") defines ns_name; ns = (use_as "m.franz") is a binding
+    if (node->childCount >= 3 && node->children[2]->opcode == OP_STRING) {
+      recordGlobalImport(linter, node, node->children[2]->val);
+    }
+  } else if (kind != NULL) {
+    AstNode *last = node->children[node->childCount - 1];
+    if (last->opcode == OP_FUNCTION) {
+      checkScopedImport(linter, node, last);
+    } else {
+      recordGlobalImport(linter, node, NULL);
+    }
+  }
+
+  for (int i = 0; i < node->childCount; i++) {
+    lintNode(linter, node->children[i], env, true);
+  }
+}
+
+//  Walk a function body in a scope of its own
+static void lintFunction(Linter *linter, AstNode *function, CompileEnv *parent) {
+  CompileEnv *env = CompileEnv_new(parent);
+  int params = paramCount(function);
+  for (int i = 0; i < params; i++) {
+    bind(linter, env, function->children[i], BINDING_PARAM, 0);
+  }
+
+  LintBlock block = { env, true, false, false };
+  int mark = linter->deferredCount;
+  lintStatements(linter, function, params, &block);
+  finishBlock(linter, &block, mark);
+  CompileEnv_free(env);
+}
+
+static void lintNode(Linter *linter, AstNode *node, CompileEnv *env, bool valueUsed) {
+  switch (node->opcode) {
+    case OP_IDENTIFIER:
+      useName(linter, env, node->val);
+      break;
+
+    case OP_QUALIFIED: {
+      // ns.name reads the namespace binding ns
+      addName(linter, node->val);
+      char *namespace = strdup(node->val);
+      char *dot = strchr(namespace, '.');
+      if (dot != NULL) *dot = '\0';
+      LintBinding *binding = lookup(linter, env, namespace);
+      if (binding != NULL) binding->used = true;
+      free(namespace);
+      break;
+    }
+
+    case OP_FUNCTION:
+      // Walked when the enclosing block ends, so it sees every name defined there
+      linter->deferred = realloc(linter->deferred, sizeof(LintDeferred) * (linter->deferredCount + 1));
+      linter->deferred[linter->deferredCount++] = (LintDeferred) { node, env };
+      break;
+
+    case OP_APPLICATION:
+      lintApplication(linter, node, env, valueUsed);
+      break;
+
+    case OP_ASSIGNMENT:
+      // Assignments inside expressions are rare; treat them as statements of this scope
+      lintAssignment(linter, node, env, env->parent != NULL);
+      break;
+
+    default:
+      for (int i = 0; i < node->childCount; i++) {
+        lintNode(linter, node->children[i], env, true);
+      }
+      break;
+  }
+}
+
+//  Statements of a block in order (statement groups from the parser are walked through)
+static void lintStatements(Linter *linter, AstNode *node, int first, LintBlock *block) {
+  for (int i = first; i < node->childCount; i++) {
+    AstNode *statement = node->children[i];
+    if (statement->opcode == OP_STATEMENT) {
+      lintStatements(linter, statement, 0, block);
+      continue;
+    }
+
+    if (block->returned && !block->reported) {
+      warn(linter, LINT_UNREACHABLE, statement, "Unreachable %s after '<-'.", "statement");
+      block->reported = true;
+    }
+
+    if (statement->opcode == OP_ASSIGNMENT) {
+      lintAssignment(linter, statement, block->env, block->inFunction);
+    } else {
+      lintNode(linter, statement, block->env, false);
+    }
+    if (statement->opcode == OP_RETURN) block->returned = true;
+  }
+}
+
+//  Walk the function bodies deferred since mark, then report the scope's unused names
+static void finishBlock(Linter *linter, LintBlock *block, int mark) {
+  while (linter->deferredCount > mark) {
+    LintDeferred entry = linter->deferred[--linter->deferredCount];
+    lintFunction(linter, entry.function, entry.env);
+  }
+  checkUnused(linter, block->env);
+}
+
+// ---------------------------------------------------------------------------
+// Linting
+// ---------------------------------------------------------------------------
+
+//  Set the rules of a comma-separated list ("all" means every rule); false on an unknown name
+static bool setRules(const char *list, bool enabled[LINT_RULE_COUNT], bool value, char unknown[64]) {
+  const char *start = list;
+  while (*start != '\0') {
+    while (*start == ' ') start++;
+    int length = strcspn(start, ",");
+    while (length > 0 && start[length - 1] == ' ') length--;
+
+    bool found = false;
+    for (int r = 0; r < LINT_RULE_COUNT; r++) {
+      bool all = length == 3 && strncmp(start, "all", 3) == 0;
+      if (all || ((int) strlen(g_ruleNames[r]) == length && strncmp(start, g_ruleNames[r], length) == 0)) {
+        enabled[r] = value;
+        found = true;
+      }
+    }
+    if (!found && length > 0) {
+      snprintf(unknown, 64, "%.*s", length, start);
+      return false;
+    }
+
+    start += strcspn(start, ",");
+    if (*start == ',') start++;
+  }
+  return true;
+}
+
+//  `// franz-lint: disable=<rules>` comments switch rules off for the file
+static void applyDirectives(TokenArray *tokens, bool enabled[LINT_RULE_COUNT]) {
+  for (int i = 0; i < tokens->commentCount; i++) {
+    const char *text = tokens->comments[i].text;
+    if (strncmp(text, LINT_DIRECTIVE, strlen(LINT_DIRECTIVE)) != 0) continue;
+
+    char unknown[64];
+    if (!setRules(text + strlen(LINT_DIRECTIVE), enabled, false, unknown)) {
+      fprintf(stderr, "Warning: unknown lint rule '%s' in directive on line %d\n", unknown,
+              tokens->comments[i].lineNumber);
+    }
+  }
+}
+
+static void Linter_free(Linter *linter) {
+  for (int i = 0; i < linter->moduleCount; i++) {
+    for (int e = 0; e < linter->modules[i].exportCount; e++) free(linter->modules[i].exports[e]);
+    free(linter->modules[i].exports);
+    free(linter->modules[i].path);
+  }
+  for (int i = 0; i < linter->nameCount; i++) free(linter->names[i]);
+  free(linter->modules);
+  free(linter->names);
+  free(linter->imports);
+  free(linter->deferred);
+  free(linter->bindings);
+}
+
+int Lint_source(const char *path, const char *code, int length, const bool enabled[LINT_RULE_COUNT]) {
+  char *source = malloc(length + 1);
+  memcpy(source, code, length);
+  source[length] = '\0';
+  Diagnostic_addSource(path, source, length);
+
+  TokenArray *tokens = lex(source, length);
+  AstNode *ast = Diagnostic_count() == 0 && tokens->count > 2 ? parseProgram(tokens) : NULL;
+  if (Diagnostic_count() > 0) {
+    if (ast) AstNode_free(ast);
+    TokenArray_free(tokens);
+    free(source);
+    return -1;
+  }
+
+  Linter linter = { 0 };
+  linter.path = path;
+  memcpy(linter.enabled, enabled, sizeof(linter.enabled));
+  applyDirectives(tokens, linter.enabled);
+
+  if (ast != NULL) {
+    loadModules(&linter, ast);
+
+    CompileEnv *env = CompileEnv_new(NULL);
+    LintBlock block = { env, false, false, false };
+    lintStatements(&linter, ast, 0, &block);
+    finishBlock(&linter, &block, 0);
+    checkGlobalImports(&linter);
+    CompileEnv_free(env);
+    AstNode_free(ast);
+  }
+
+  int warnings = linter.warnings;
+  Linter_free(&linter);
+  TokenArray_free(tokens);
+  free(source);
+  return warnings;
+}
+
+// ---------------------------------------------------------------------------
+// Command line
+// ---------------------------------------------------------------------------
+
+typedef struct LintRun {
+  bool enabled[LINT_RULE_COUNT];
+  int files;
+  int warnings;
+  int failed;           // files that could not be read or parsed
+} LintRun;
+
+//  Lint one buffer and print its findings
+static void lintBuffer(LintRun *run, const char *path, const char *code, int length) {
+  int warnings = Lint_source(path, code, length, run->enabled);
+  if (warnings < 0) {
+    int errors = Diagnostic_count();
+    Diagnostic_flush(stdout);
+    fflush(stdout);
+    fprintf(stderr, "Error: cannot lint %s: %d syntax error%s\n", path, errors, errors == 1 ? "" : "s");
+    run->failed++;
+  } else {
+    Diagnostic_flush(stdout);
+    run->warnings += warnings;
+  }
+  run->files++;
+}
+
+static int visitFile(const char *path, void *context) {
+  LintRun *run = context;
+  char *code = readFile((char *) path, false);
+  if (code == NULL) {
+    fprintf(stderr, "Error: Could not read '%s'.\n", path);
+    run->failed++;
+    return 1;
+  }
+  lintBuffer(run, path, code, strlen(code));
+  free(code);
+  return 0;
+}
+
+static char *readStream(FILE *in, int *length) {
+  size_t capacity = 4096, size = 0, n;
+  char *text = malloc(capacity);
+  while ((n = fread(text + size, 1, capacity - size - 1, in)) > 0) {
+    size += n;
+    if (capacity - size <= 1) text = realloc(text, capacity *= 2);
+  }
+  text[size] = '\0';
+  *length = size;
+  return text;
+}
+
+int Lint_run(int argc, char *argv[]) {
+  LintRun run = { 0 };
+  for (int r = 0; r < LINT_RULE_COUNT; r++) run.enabled[r] = true;
+  int pathCount = 0;
+
+  // Options apply in order: --disable=all --enable=unused-param keeps one rule
+  for (int i = 0; i < argc; i++) {
+    char unknown[64];
+    bool known = true;
+    if (strcmp(argv[i], "--list-rules") == 0) {
+      for (int r = 0; r < LINT_RULE_COUNT; r++) printf("%s\n", g_ruleNames[r]);
+      return 0;
+    } else if (strncmp(argv[i], "--enable=", 9) == 0) {
+      known = setRules(argv[i] + 9, run.enabled, true, unknown);
+    } else if (strncmp(argv[i], "--disable=", 10) == 0) {
+      known = setRules(argv[i] + 10, run.enabled, false, unknown);
+    } else if (argv[i][0] == '-' && argv[i][1] != '\0') {
+      fprintf(stderr, "Error: Unknown option '%s' for 'franz lint' "
+                      "(expected --enable=<rules>, --disable=<rules> or --list-rules).\n", argv[i]);
+      return 1;
+    } else if (strcmp(argv[i], "-") != 0) {
+      pathCount++;
+    }
+
+    if (!known) {
+      fprintf(stderr, "Error: Unknown lint rule '%s' (see 'franz lint --list-rules').\n", unknown);
+      return 1;
+    }
+  }
+
+  // No paths (or `-`): lint stdin
+  if (pathCount == 0) {
+    int length;
+    char *code = readStream(stdin, &length);
+    lintBuffer(&run, "<stdin>", code, length);
+    free(code);
+  }
+
+  for (int i = 0; i < argc && pathCount > 0; i++) {
+    if (argv[i][0] == '-') continue;
+    walkFranzFiles(argv[i], visitFile, &run);
+  }
+
+  fflush(stdout);
+  fprintf(stderr, "%d warning%s in %d file%s\n", run.warnings, run.warnings == 1 ? "" : "s",
+          run.files, run.files == 1 ? "" : "s");
+  return run.warnings > 0 || run.failed > 0;
+}
@@ -0,0 +1,64 @@
+#ifndef LINT_H
+#define LINT_H
+
+#include <stdbool.h>
+
+/**
+ * Static analyzer for common Franz mistakes (`franz lint`)
+ *
+ * Programs are parsed with the compiler's front end and walked once with the
+ * compile-time scopes of optimization/compile.c (CompileEnv), so names
+ * resolve the way the code generator resolves them. Function bodies are
+ * walked after the block that contains them, which lets a closure use a
+ * name defined further down (mutual recursion). `use` callbacks are checked
+ * against the module's top-level names with FreeVar_analyze.
+ *
+ * Findings are collected with Diagnostic_add under the label "Warning" and
+ * end with the rule name in brackets, e.g. "[unused-binding]".
+ */
+
+typedef enum LintRule {
+  LINT_UNUSED_BINDING,    // local assignment never read
+  LINT_UNUSED_PARAM,      // closure parameter never read
+  LINT_SHADOWED_STDLIB,   // binding named like a newGlobal function (map, filter, ...)
+  LINT_IMMUTABLE_ASSIGN,  // second assignment to a variable declared without mut
+  LINT_UNUSED_IMPORT,     // use/use_as module none of whose names are used
+  LINT_IF_WITHOUT_ELSE,   // (if c {...}) whose value is used
+  LINT_UNREACHABLE,       // statement after <- in the same block
+  LINT_RULE_COUNT
+} LintRule;
+
+/**
+ * Name of a rule as used on the command line ("unused-binding", ...)
+ *
+ * @param rule - Rule
+ * @return Static name
+ */
+const char *Lint_ruleName(LintRule rule);
+
+/**
+ * Lint Franz source code
+ *
+ * A `// franz-lint: disable=<rules>` comment turns rules off for the whole file.
+ *
+ * @param path - File name shown in messages; modules are resolved relative to it
+ * @param code - Source text
+ * @param length - Length of code in bytes
+ * @param enabled - One flag per LintRule
+ * @return Number of warnings collected with Diagnostic_add, or -1 when the code
+ *         has syntax errors (also collected)
+ */
+int Lint_source(const char *path, const char *code, int length, const bool enabled[LINT_RULE_COUNT]);
+
+/**
+ * Run `franz lint [--enable=<rules>] [--disable=<rules>] [--list-rules] [paths...]`
+ *
+ * Directories are searched for .franz files. Without paths, stdin is linted.
+ *
+ * @param argc - Number of arguments after `lint`
+ * @param argv - Arguments after `lint`
+ * @return Exit code (1 if there were warnings or syntax errors)
+ */
+int Lint_run(int argc, char *argv[]);
+
+#endif
@@ -5,6 +5,8 @@
 #include "../llvm-codegen/llvm_codegen.h"
 #include "../freevar/freevar.h"
 #include "../type-inference/type_infer.h"
+#include "../llvm-trace/llvm_trace.h"
+#include "../llvm-profile/llvm_profile.h"
 
 static int LLVMClosures_nodeIsVoid(LLVMCodeGen *gen, AstNode *node) {
   if (!node) {
@@ -779,6 +781,14 @@ LLVMValueRef LLVMClosures_compileClosure(LLVMCodeGen *gen, AstNode *node) {
     }
   }
 
+  if (gen->profile) {
+    LLVMProfile_instrumentFunction(gen, closureFunc, node);
+  }
+  if (gen->trace) {
+    LLVMTrace_instrumentClosure(gen, closureFunc, node, returnTypeTag,
+                                returnsParameter ? returnedParamIndex : -1);
+  }
+
   // Restore builder state and variable map
   LLVMVariableMap_free(gen->variables);
   gen->variables = savedVariables;
@@ -1372,6 +1382,8 @@ LLVMValueRef LLVMClosures_callClosure(LLVMCodeGen *gen, LLVMValueRef closureValu
   LLVMValueRef finalValues[] = {nativeResult, boxedResultAsI64};
   LLVMBasicBlockRef finalBlocks[] = {skipBoxingBlock, boxMergeBlock};
   LLVMAddIncoming(finalResult, finalValues, finalBlocks, 2);
+  gen->lastClosureCallResult = finalResult;
+  gen->lastClosureCallTag = finalTag;
 
   // Cleanup malloc'd arrays
   free(augmentedArgs);
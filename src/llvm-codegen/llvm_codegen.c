@@ -1,40 +1,334 @@
-#include "llvm_codegen.h"
-#include <stdio.h>
-#include <stdlib.h>
-#include <string.h>
-
-// LLVM IR Generation - Main implementation in llvm_ir_gen.c
-extern LLVMCodeGen *LLVMCodeGen_new_impl(const char *moduleName);
-extern void LLVMCodeGen_free_impl(LLVMCodeGen *gen);
-extern int LLVMCodeGen_compile_impl(LLVMCodeGen *gen, AstNode *ast, Scope *globalScope);
-
-// LLVM Code Generator - Public API
-
-LLVMCodeGen *LLVMCodeGen_new(const char *moduleName) {
-  return LLVMCodeGen_new_impl(moduleName);
-}
-
-void LLVMCodeGen_free(LLVMCodeGen *gen) {
-  LLVMCodeGen_free_impl(gen);
-}
-
-void LLVMCodeGen_dumpIR(LLVMCodeGen *gen) {
-  if (!gen || !gen->module) return;
-  LLVMDumpModule(gen->module);
-}
-
-int LLVMCodeGen_writeToFile(LLVMCodeGen *gen, const char *filename) {
-  if (!gen || !gen->module) return -1;
-
-  if (LLVMWriteBitcodeToFile(gen->module, filename)) {
-    fprintf(stderr, "Failed to write bitcode to file: %s\n", filename);
-    return -1;
-  }
-
-  return 0;
-}
-
-// Compilation entry point
-int LLVMCodeGen_compile(LLVMCodeGen *gen, AstNode *ast, Scope *globalScope) {
-  return LLVMCodeGen_compile_impl(gen, ast, globalScope);
-}
+#include "llvm_codegen.h"
+#include "../diagnostics/diagnostic.h"
+#include <llvm-c/Transforms/PassBuilder.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <stdint.h>
+
+// LLVM IR Generation - Main implementation in llvm_ir_gen.c
+extern LLVMCodeGen *LLVMCodeGen_new_impl(const char *moduleName);
+extern void LLVMCodeGen_free_impl(LLVMCodeGen *gen);
+extern int LLVMCodeGen_compile_impl(LLVMCodeGen *gen, AstNode *ast, Scope *globalScope);
+
+// LLVM Code Generator - Public API
+
+LLVMCodeGen *LLVMCodeGen_new(const char *moduleName) {
+  return LLVMCodeGen_new_impl(moduleName);
+}
+
+void LLVMCodeGen_free(LLVMCodeGen *gen) {
+  LLVMCodeGen_free_impl(gen);
+}
+
+void LLVMCodeGen_dumpIR(LLVMCodeGen *gen) {
+  if (!gen || !gen->module) return;
+  LLVMDumpModule(gen->module);
+}
+
+static int isFloatKind(LLVMTypeKind kind) {
+  return kind == LLVMHalfTypeKind || kind == LLVMBFloatTypeKind || kind == LLVMFloatTypeKind ||
+         kind == LLVMDoubleTypeKind || kind == LLVMX86_FP80TypeKind || kind == LLVMFP128TypeKind ||
+         kind == LLVMPPC_FP128TypeKind;
+}
+
+//  Whether a cast between these types can exist. Builder calls with constant operands
+// fold into constant expressions without this check, and the verifier trusts them.
+static int castIsValid(LLVMOpcode opcode, LLVMTypeRef src, LLVMTypeRef dest) {
+  LLVMTypeKind from = LLVMGetTypeKind(src);
+  LLVMTypeKind to = LLVMGetTypeKind(dest);
+  if (from == LLVMVectorTypeKind || to == LLVMVectorTypeKind) return 1;
+
+  int fromInt = from == LLVMIntegerTypeKind, toInt = to == LLVMIntegerTypeKind;
+  int fromPtr = from == LLVMPointerTypeKind, toPtr = to == LLVMPointerTypeKind;
+  switch (opcode) {
+    case LLVMTrunc: case LLVMZExt: case LLVMSExt: return fromInt && toInt;
+    case LLVMFPTrunc: case LLVMFPExt: return isFloatKind(from) && isFloatKind(to);
+    case LLVMFPToUI: case LLVMFPToSI: return isFloatKind(from) && toInt;
+    case LLVMUIToFP: case LLVMSIToFP: return fromInt && isFloatKind(to);
+    case LLVMPtrToInt: return fromPtr && toInt;
+    case LLVMIntToPtr: return fromInt && toPtr;
+    case LLVMBitCast: return fromPtr == toPtr;
+    default: return 1;
+  }
+}
+
+//  First constant expression under value that casts between incompatible types, or NULL
+static LLVMValueRef findInvalidConstantCast(LLVMValueRef value) {
+  if (!LLVMIsAConstantExpr(value)) return NULL;
+  LLVMValueRef source = LLVMGetOperand(value, 0);
+  if (!castIsValid(LLVMGetConstOpcode(value), LLVMTypeOf(source), LLVMTypeOf(value))) return value;
+
+  for (int i = 0; i < LLVMGetNumOperands(value); i++) {
+    LLVMValueRef invalid = findInvalidConstantCast(LLVMGetOperand(value, i));
+    if (invalid) return invalid;
+  }
+  return NULL;
+}
+
+//  Check gen->module with the LLVM verifier, plus the constant casts it does not check.
+// Recovered codegen errors can leave instructions that reference detached values or
+// casts such as inttoptr (double ...); both are rejected here where the backend would
+// crash or emit a broken program. Returns 0 if valid, -1 (diagnostic reported) if not.
+static int verifyModule(LLVMCodeGen *gen) {
+  char *error = NULL;
+  if (LLVMVerifyModule(gen->module, LLVMReturnStatusAction, &error)) {
+    // First line names the broken instruction; the rest repeats it as IR
+    error[strcspn(error, "\n")] = '\0';
+    Diagnostic_error(ERROR_CODE_CODEGEN_FAILED, Diagnostic_spanOfLine(0), "LLVM module verification failed: %s", error);
+    LLVMDisposeMessage(error);
+    return -1;
+  }
+  LLVMDisposeMessage(error);
+
+  for (LLVMValueRef fn = LLVMGetFirstFunction(gen->module); fn; fn = LLVMGetNextFunction(fn)) {
+    for (LLVMBasicBlockRef block = LLVMGetFirstBasicBlock(fn); block; block = LLVMGetNextBasicBlock(block)) {
+      for (LLVMValueRef inst = LLVMGetFirstInstruction(block); inst; inst = LLVMGetNextInstruction(inst)) {
+        for (int i = 0; i < LLVMGetNumOperands(inst); i++) {
+          LLVMValueRef invalid = findInvalidConstantCast(LLVMGetOperand(inst, i));
+          if (!invalid) continue;
+
+          char *from = LLVMPrintTypeToString(LLVMTypeOf(LLVMGetOperand(invalid, 0)));
+          char *to = LLVMPrintTypeToString(LLVMTypeOf(invalid));
+          Diagnostic_error(ERROR_CODE_CODEGEN_FAILED, Diagnostic_spanOfLine(0),
+                           "LLVM module verification failed: invalid cast from '%s' to '%s' in '%s'",
+                           from, to, LLVMGetValueName(fn));
+          LLVMDisposeMessage(from);
+          LLVMDisposeMessage(to);
+          return -1;
+        }
+      }
+    }
+  }
+  return 0;
+}
+
+//  Target machine for gen->targetTriple (host triple if NULL), with a generic CPU so
+// output runs on any machine of the same architecture, and position-independent code
+// for PIE executables. Stamps the triple and data layout onto gen->module so emitted
+// IR/bitcode carry them. Returns NULL (error printed) on failure. Caller disposes the machine.
+static LLVMTargetMachineRef createTargetMachine(LLVMCodeGen *gen) {
+  char *triple;
+  if (gen->targetTriple) {
+    // Cross-compilation: any target built into LLVM
+    LLVMInitializeAllTargetInfos();
+    LLVMInitializeAllTargets();
+    LLVMInitializeAllTargetMCs();
+    LLVMInitializeAllAsmPrinters();
+    triple = LLVMNormalizeTargetTriple(gen->targetTriple);
+  } else {
+    LLVMInitializeNativeTarget();
+    LLVMInitializeNativeAsmPrinter();
+    triple = LLVMGetDefaultTargetTriple();
+  }
+
+  char *error = NULL;
+  LLVMTargetRef target;
+
+  if (LLVMGetTargetFromTriple(triple, &target, &error)) {
+    fprintf(stderr, "Failed to find LLVM target for %s: %s\n", triple, error);
+    LLVMDisposeMessage(error);
+    LLVMDisposeMessage(triple);
+    return NULL;
+  }
+
+  // Backend never drops below the default level: at LLVMCodeGenLevelNone FastISel
+  // ignores `tail` markers, and TCO depends on them becoming jumps
+  LLVMCodeGenOptLevel codegenLevel = gen->optLevel >= 3 ? LLVMCodeGenLevelAggressive : LLVMCodeGenLevelDefault;
+
+  LLVMTargetMachineRef machine = LLVMCreateTargetMachine(
+      target, triple, "generic", "",
+      codegenLevel, LLVMRelocPIC, LLVMCodeModelDefault);
+
+  // Module must agree with the target machine on triple and data layout
+  LLVMSetTarget(gen->module, triple);
+  LLVMTargetDataRef dataLayout = LLVMCreateTargetDataLayout(machine);
+  char *layoutString = LLVMCopyStringRepOfTargetData(dataLayout);
+  LLVMSetDataLayout(gen->module, layoutString);
+  LLVMDisposeMessage(layoutString);
+  LLVMDisposeTargetData(dataLayout);
+
+  LLVMDisposeMessage(triple);
+  return machine;
+}
+
+//  Run the new pass manager's default<On> pipeline (mem2reg, inlining, GVN, loop
+// passes, ...) over module for gen->optLevel. -O0 leaves the module untouched.
+// machine may be NULL (JIT); target-specific cost models are then unavailable.
+static int optimizeModule(LLVMCodeGen *gen, LLVMModuleRef module, LLVMTargetMachineRef machine) {
+  if (gen->optLevel <= 0) return 0;
+
+  char pipeline[32];
+  snprintf(pipeline, sizeof(pipeline), "default<O%d>", gen->optLevel > 3 ? 3 : gen->optLevel);
+
+  LLVMPassBuilderOptionsRef passOptions = LLVMCreatePassBuilderOptions();
+  LLVMPassBuilderOptionsSetLoopVectorization(passOptions, gen->optLevel >= 2);
+  LLVMPassBuilderOptionsSetSLPVectorization(passOptions, gen->optLevel >= 2);
+  LLVMPassBuilderOptionsSetLoopUnrolling(passOptions, gen->optLevel >= 2);
+
+  int result = 0;
+  LLVMErrorRef error = LLVMRunPasses(module, pipeline, machine, passOptions);
+  if (error) {
+    char *message = LLVMGetErrorMessage(error);
+    fprintf(stderr, "Failed to optimize module (%s): %s\n", pipeline, message);
+    LLVMDisposeErrorMessage(message);
+    result = -1;
+  }
+
+  LLVMDisposePassBuilderOptions(passOptions);
+  return result;
+}
+
+//  Module ready for output: verified copy of gen->module, optimized for gen->optLevel.
+// Returns NULL (error printed) on failure. Caller owns the module.
+static LLVMModuleRef prepareModule(LLVMCodeGen *gen, LLVMTargetMachineRef machine) {
+  if (verifyModule(gen) != 0) return NULL;
+  LLVMModuleRef module = LLVMCloneModule(gen->module);
+
+  if (optimizeModule(gen, module, machine) != 0) {
+    LLVMDisposeModule(module);
+    return NULL;
+  }
+  return module;
+}
+
+//  Machine code emission through the LLVM TargetMachine API (no llc/clang needed)
+static int emitWithTargetMachine(LLVMCodeGen *gen, const char *filename, LLVMCodeGenFileType fileType) {
+  if (!gen || !gen->module) return -1;
+
+  LLVMTargetMachineRef machine = createTargetMachine(gen);
+  if (!machine) return -1;
+
+  LLVMModuleRef emitModule = prepareModule(gen, machine);
+  if (!emitModule) {
+    LLVMDisposeTargetMachine(machine);
+    return -1;
+  }
+
+  int result = 0;
+  char *error = NULL;
+  if (LLVMTargetMachineEmitToFile(machine, emitModule, (char *)filename, fileType, &error)) {
+    fprintf(stderr, "Failed to emit %s: %s\n", filename, error);
+    LLVMDisposeMessage(error);
+    result = -1;
+  }
+
+  LLVMDisposeModule(emitModule);
+  LLVMDisposeTargetMachine(machine);
+  return result;
+}
+
+int LLVMCodeGen_emitObject(LLVMCodeGen *gen, const char *filename) {
+  return emitWithTargetMachine(gen, filename, LLVMObjectFile);
+}
+
+int LLVMCodeGen_emitAssembly(LLVMCodeGen *gen, const char *filename) {
+  return emitWithTargetMachine(gen, filename, LLVMAssemblyFile);
+}
+
+//  Bitcode (.bc) for the host target
+int LLVMCodeGen_writeToFile(LLVMCodeGen *gen, const char *filename) {
+  if (!gen || !gen->module) return -1;
+
+  LLVMTargetMachineRef machine = createTargetMachine(gen);
+  if (!machine) return -1;
+
+  LLVMModuleRef outputModule = prepareModule(gen, machine);
+  LLVMDisposeTargetMachine(machine);
+  if (!outputModule) return -1;
+
+  int result = 0;
+  if (LLVMWriteBitcodeToFile(outputModule, filename)) {
+    fprintf(stderr, "Failed to write bitcode to file: %s\n", filename);
+    result = -1;
+  }
+
+  LLVMDisposeModule(outputModule);
+  return result;
+}
+
+//  Textual IR (.ll) for the host target, for inspection or external LLVM pipelines
+int LLVMCodeGen_writeIRToFile(LLVMCodeGen *gen, const char *filename) {
+  if (!gen || !gen->module) return -1;
+
+  LLVMTargetMachineRef machine = createTargetMachine(gen);
+  if (!machine) return -1;
+
+  LLVMModuleRef outputModule = prepareModule(gen, machine);
+  LLVMDisposeTargetMachine(machine);
+  if (!outputModule) return -1;
+
+  int result = 0;
+  char *error = NULL;
+  if (LLVMPrintModuleToFile(outputModule, filename, &error)) {
+    fprintf(stderr, "Failed to write LLVM IR to file %s: %s\n", filename, error);
+    LLVMDisposeMessage(error);
+    result = -1;
+  }
+
+  LLVMDisposeModule(outputModule);
+  return result;
+}
+
+//  MCJIT engine for gen->module. External calls resolve against the runtime already
+// linked into franz (stdlib.c, dict.c, ...), so no C compiler is needed; franz must
+// export its symbols (-rdynamic). The engine's module lives in gen->context, so the
+// engine must be disposed before gen is freed. Returns NULL (error printed) on failure.
+LLVMExecutionEngineRef LLVMCodeGen_createJIT(LLVMCodeGen *gen) {
+  if (!gen || !gen->module) return NULL;
+
+  LLVMLinkInMCJIT();
+  LLVMInitializeNativeTarget();
+  LLVMInitializeNativeAsmPrinter();
+
+  LLVMModuleRef jitModule = prepareModule(gen, NULL);
+  if (!jitModule) return NULL;
+
+  struct LLVMMCJITCompilerOptions options;
+  LLVMInitializeMCJITCompilerOptions(&options, sizeof(options));
+  options.OptLevel = gen->optLevel >= 3 ? 3 : 2;  // same backend floor as createTargetMachine
+
+  // Engine takes ownership of jitModule
+  LLVMExecutionEngineRef engine;
+  char *error = NULL;
+  if (LLVMCreateMCJITCompilerForModule(&engine, jitModule, &options, sizeof(options), &error)) {
+    fprintf(stderr, "Failed to create JIT: %s\n", error);
+    LLVMDisposeMessage(error);
+    LLVMDisposeModule(jitModule);
+    return NULL;
+  }
+  return engine;
+}
+
+//  JIT execution through MCJIT: runs main() in-process instead of linking an executable.
+// argc/argv are the program arguments (without a program name), bound to `arguments`.
+int LLVMCodeGen_runJIT(LLVMCodeGen *gen, int argc, char *argv[], int *exitCode) {
+  LLVMExecutionEngineRef engine = LLVMCodeGen_createJIT(gen);
+  if (!engine) return -1;
+
+  uint64_t mainAddress = LLVMGetFunctionAddress(engine, "main");
+  if (mainAddress == 0) {
+    fprintf(stderr, "Failed to JIT compile main()\n");
+    LLVMDisposeExecutionEngine(engine);
+    return -1;
+  }
+
+  // main(argc, argv) expects argv[0] to be the program name, as for an executable
+  char **programArgv = malloc(sizeof(char *) * (argc + 2));
+  programArgv[0] = "franz";
+  for (int i = 0; i < argc; i++) programArgv[i + 1] = argv[i];
+  programArgv[argc + 1] = NULL;
+
+  int (*programMain)(int, char **) = (int (*)(int, char **))(intptr_t)mainAddress;
+  *exitCode = programMain(argc + 1, programArgv);
+  free(programArgv);
+  fflush(NULL);
+
+  LLVMDisposeExecutionEngine(engine);
+  return 0;
+}
+
+// Compilation entry point
+int LLVMCodeGen_compile(LLVMCodeGen *gen, AstNode *ast, Scope *globalScope) {
+  return LLVMCodeGen_compile_impl(gen, ast, globalScope);
+}
@@ -4,8 +4,10 @@
 #include <llvm-c/Core.h>
 #include <llvm-c/ExecutionEngine.h>
 #include <llvm-c/Target.h>
+#include <llvm-c/TargetMachine.h>
 #include <llvm-c/Analysis.h>
 #include <llvm-c/BitWriter.h>
+#include <llvm-c/DebugInfo.h>
 
 #include "../ast.h"
 #include "../scope.h"
@@ -79,6 +81,11 @@ typedef struct {
   // Used by isGenericPointerNode to avoid Generic* boxing for int/float returns
   LLVMVariableMap *returnTypeTags;  // Function name → return type tag (cast to void*)
 
+  //  Most recent closure call: its result is a native int/float or a Generic* (as i64)
+  // depending on the return tag, which is only known at runtime
+  LLVMValueRef lastClosureCallResult;  // final_result of the call
+  LLVMValueRef lastClosureCallTag;     // i32 CLOSURE_RETURN_* tag of that result
+
   // Runtime function declarations ()
   LLVMValueRef printfFunc;      // printf() for output
   LLVMTypeRef printfType;       // printf function type
@@ -88,8 +95,8 @@ typedef struct {
   LLVMTypeRef fflushType;       // fflush function type
 
   // Type conversion runtime functions ( extension)
-  LLVMValueRef atoiFunc;        // atoi() for string→int
-  LLVMTypeRef atoiType;         // atoi function type
+  LLVMValueRef atollFunc;       // atoll() for string→int
+  LLVMTypeRef atollType;        // atoll function type
   LLVMValueRef atofFunc;        // atof() for string→float
   LLVMTypeRef atofType;         // atof function type
   LLVMValueRef snprintfFunc;    // snprintf() for int/float→string
@@ -159,6 +166,7 @@ typedef struct {
 
   //  Recursion support (forward declarations)
   const char *currentFunctionName;  // Name of function being compiled (NULL if not compiling function)
+  AstNode *currentFunctionNode;     // Function node assigned to currentFunctionName
 
   //  Polymorphic function support
   int isPolymorphicFunction;    // 1 if current function has UNKNOWN inferred type (polymorphic)
@@ -169,6 +177,39 @@ typedef struct {
   int enableTCO;                // 1 to enable TCO, 0 to disable (controlled by --tco flag)
   int inTailPosition;           // 1 if currently compiling code in tail position (for tail call detection)
   int currentClosureReturnTag;  // Expected closure return tag (INT/FLOAT) for return conversions
+
+  //  Program arguments: main(argc, argv) stores the `arguments` list in this global
+  LLVMValueRef argumentsGlobal; // Generic* list of strings (argv without argv[0])
+
+  //  Cross-compilation target (--target); NULL compiles for the host
+  const char *targetTriple;     // e.g. "aarch64-linux-gnu"
+
+  //  Optimization level (-O0..-O3) applied when emitting or JIT-compiling the module
+  int optLevel;                 // 0 = no IR passes (default), 1-3 = default<On> pass pipeline
+
+  //  DWARF debug info (-g): source lines and one subprogram per function
+  int debugInfo;                // 1 to emit debug metadata
+  const char *sourcePath;       // .franz file named in the compile unit (NULL = stdin)
+  LLVMDIBuilderRef diBuilder;
+  LLVMMetadataRef diFile;
+  LLVMMetadataRef diCompileUnit;
+  LLVMMetadataRef diScratchScope;  // scope of locations until LLVMDebugInfo_finalize
+
+  //  Line and branch coverage (--coverage): counters for the program's own lines
+  int coverage;                 // 1 to instrument the program with execution counters
+  struct CoverageCounters *coverageCounters;  // counters built so far (see llvm_coverage.c)
+  LLVMValueRef coverageFunction;  // function and line already counted by the enclosing node
+  int coverageLine;
+
+  //  Execution tracing (--trace): calls and returns of matching functions logged to stderr
+  int trace;                    // 1 to instrument user functions and closures
+  const char *tracePattern;     // fnmatch glob on function names (NULL = every function)
+
+  //  Profiling (--profile): call counts and wall time per function and runtime call
+  int profile;                  // 1 to time user functions, closures and franz_* runtime calls
+
+  //  `franz repl`: globals carried over from earlier entries (NULL when compiling a program)
+  struct ReplSession *replSession;
 } LLVMCodeGen;
 
 //  Setup and initialization
@@ -247,7 +288,12 @@ LLVMValueRef LLVMCodeGen_compileOr(LLVMCodeGen *gen, AstNode *node);
 
 // Output and execution
 void LLVMCodeGen_dumpIR(LLVMCodeGen *gen);
-int LLVMCodeGen_writeToFile(LLVMCodeGen *gen, const char *filename);
+int LLVMCodeGen_writeToFile(LLVMCodeGen *gen, const char *filename);     // bitcode (.bc)
+int LLVMCodeGen_writeIRToFile(LLVMCodeGen *gen, const char *filename);   // textual IR (.ll)
+int LLVMCodeGen_emitAssembly(LLVMCodeGen *gen, const char *filename);    // assembly (.s)
+int LLVMCodeGen_emitObject(LLVMCodeGen *gen, const char *filename);      // object file (.o)
+int LLVMCodeGen_runJIT(LLVMCodeGen *gen, int argc, char *argv[], int *exitCode);
+LLVMExecutionEngineRef LLVMCodeGen_createJIT(LLVMCodeGen *gen);  // caller disposes (before gen)
 int LLVMCodeGen_compile(LLVMCodeGen *gen, AstNode *ast, Scope *globalScope);
 
 //  Helper for semantic type checking (used by closures)
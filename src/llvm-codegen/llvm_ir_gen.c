@@ -23,6 +23,12 @@
 #include "../llvm-string-ops/llvm_string_ops.h"  //  String operations (get substring)
 #include "../llvm-type/llvm_type.h"  //  Type introspection (type function)
 #include "../llvm-refs/llvm_refs.h"  //  Mutable references (ref, deref, set!)
+#include "../repl/repl.h"  //  REPL sessions (globals carried between entries)
+#include "../llvm-debuginfo/llvm_debuginfo.h"  //  DWARF line info (-g)
+#include "../llvm-coverage/llvm_coverage.h"  //  Line and branch counters (--coverage)
+#include "../llvm-trace/llvm_trace.h"  //  Call and return logging (--trace)
+#include "../llvm-profile/llvm_profile.h"  //  Call counts and wall time (--profile)
+#include "../diagnostics/diagnostic.h"  //  Source snippets under compile errors
 #include <llvm-c/Core.h>
 #include <llvm-c/ExecutionEngine.h>
 #include <llvm-c/Target.h>
@@ -121,7 +127,7 @@ static int TypeInfer_toReturnTypeTag(InferredTypeKind kind) {
 // ============================================================================
 
 LLVMCodeGen *LLVMCodeGen_new_impl(const char *moduleName) {
-  LLVMCodeGen *gen = (LLVMCodeGen *)malloc(sizeof(LLVMCodeGen));
+  LLVMCodeGen *gen = (LLVMCodeGen *)calloc(1, sizeof(LLVMCodeGen));
   if (!gen) {
     fprintf(stderr, "Failed to allocate LLVMCodeGen\n");
     return NULL;
@@ -146,6 +152,14 @@ LLVMCodeGen *LLVMCodeGen_new_impl(const char *moduleName) {
   // TCO: Initialize tail call optimization flags (enabled by default - functional language standard)
   gen->enableTCO = 1;        // Enabled by default (matching OCaml/Scheme), use --no-tco to disable
   gen->inTailPosition = 0;   // Not in tail position initially
+  gen->optLevel = 0;         // -O0: no IR optimization passes
+  gen->argumentsGlobal = NULL;
+  gen->targetTriple = NULL;  // host
+  gen->replSession = NULL;
+  gen->coverage = 0;         // no --coverage counters
+  gen->trace = 0;            // no --trace logging
+  gen->tracePattern = NULL;
+  gen->profile = 0;          // no --profile timing
   gen->currentClosureReturnTag = -1;
 
   //  Initialize loop context (NULL = not in loop)
@@ -156,6 +170,7 @@ LLVMCodeGen *LLVMCodeGen_new_impl(const char *moduleName) {
 
   //  Initialize recursion support (NULL = not compiling function)
   gen->currentFunctionName = NULL;
+  gen->currentFunctionNode = NULL;
 
   // Initialize variable map
   gen->variables = LLVMVariableMap_new();
@@ -205,11 +220,10 @@ LLVMCodeGen *LLVMCodeGen_new_impl(const char *moduleName) {
   gen->fflushFunc = LLVMAddFunction(gen->module, "fflush", gen->fflushType);
 
   //  Declare C runtime functions for type conversions
-  // atoi: string -> int
-  LLVMTypeRef atoiParams[] = {gen->stringType};
-  gen->atoiType = LLVMFunctionType(LLVMInt32TypeInContext(gen->context),
-                                   atoiParams, 1, 0);
-  gen->atoiFunc = LLVMAddFunction(gen->module, "atoi", gen->atoiType);
+  // atoll: string -> int (64-bit, like every Franz integer)
+  LLVMTypeRef atollParams[] = {gen->stringType};
+  gen->atollType = LLVMFunctionType(gen->intType, atollParams, 1, 0);
+  gen->atollFunc = LLVMAddFunction(gen->module, "atoll", gen->atollType);
 
   // atof: string -> double
   LLVMTypeRef atofParams[] = {gen->stringType};
@@ -441,6 +455,8 @@ void LLVMCodeGen_free_impl(LLVMCodeGen *gen) {
   if (gen->paramTypeTags) LLVMVariableMap_free(gen->paramTypeTags);  //  Free param tag tracking
   if (gen->typeMetadata) LLVMVariableMap_free(gen->typeMetadata);    //  Free type metadata tracking
   if (gen->returnTypeTags) LLVMVariableMap_free(gen->returnTypeTags);  //  Free return type tracking
+  LLVMDebugInfo_dispose(gen);
+  LLVMCoverage_dispose(gen);
   if (gen->builder) LLVMDisposeBuilder(gen->builder);
   if (gen->module) LLVMDisposeModule(gen->module);
   if (gen->context) LLVMContextDispose(gen->context);
@@ -458,7 +474,7 @@ LLVMValueRef LLVMCodeGen_compileNode_impl(LLVMCodeGen *gen, AstNode *node);
 // Compile integer literal
 LLVMValueRef LLVMCodeGen_compileInteger_impl(LLVMCodeGen *gen, AstNode *node) {
   if (!node || !node->val) {
-    fprintf(stderr, "ERROR: Invalid integer node\n");
+    Diagnostic_error(ERROR_CODE_CODEGEN_FAILED, Diagnostic_spanOfLine(0), "Invalid integer node");
     return NULL;
   }
 
@@ -470,7 +486,7 @@ LLVMValueRef LLVMCodeGen_compileInteger_impl(LLVMCodeGen *gen, AstNode *node) {
 // Compile float literal
 LLVMValueRef LLVMCodeGen_compileFloat_impl(LLVMCodeGen *gen, AstNode *node) {
   if (!node || !node->val) {
-    fprintf(stderr, "ERROR: Invalid float node\n");
+    Diagnostic_error(ERROR_CODE_CODEGEN_FAILED, Diagnostic_spanOfLine(0), "Invalid float node");
     return NULL;
   }
 
@@ -482,7 +498,7 @@ LLVMValueRef LLVMCodeGen_compileFloat_impl(LLVMCodeGen *gen, AstNode *node) {
 // Compile string literal
 LLVMValueRef LLVMCodeGen_compileString_impl(LLVMCodeGen *gen, AstNode *node) {
   if (!node || !node->val) {
-    fprintf(stderr, "ERROR: Invalid string node\n");
+    Diagnostic_error(ERROR_CODE_CODEGEN_FAILED, Diagnostic_spanOfLine(0), "Invalid string node");
     return NULL;
   }
 
@@ -503,7 +519,7 @@ LLVMValueRef LLVMCodeGen_compileString_impl(LLVMCodeGen *gen, AstNode *node) {
 //  Handle mutable variables (load from alloca pointer)
 LLVMValueRef LLVMCodeGen_compileVariable_impl(LLVMCodeGen *gen, AstNode *node) {
   if (!node || !node->val) {
-    fprintf(stderr, "ERROR: Invalid variable node\n");
+    Diagnostic_error(ERROR_CODE_CODEGEN_FAILED, Diagnostic_spanOfLine(0), "Invalid variable node");
     return NULL;
   }
 
@@ -518,9 +534,12 @@ LLVMValueRef LLVMCodeGen_compileVariable_impl(LLVMCodeGen *gen, AstNode *node) {
     // CRITICAL FIX: Also check functions map for variable-stored closures
     // Closures with forward declarations are stored in functions map for recursion support
     value = LLVMVariableMap_get(gen->functions, node->val);
+    if (!value && gen->argumentsGlobal && strcmp(node->val, "arguments") == 0) {
+      //  Program arguments list (set up at the start of main)
+      return LLVMBuildLoad2(gen->builder, gen->stringType, gen->argumentsGlobal, "arguments");
+    }
     if (!value) {
-      fprintf(stderr, "ERROR: Undefined variable '%s' at line %d\n",
-              node->val, node->lineNumber);
+      Diagnostic_error(ERROR_CODE_UNDEFINED_VARIABLE, Diagnostic_spanOfNode(node), "Undefined variable '%s'", node->val);
       return NULL;
     }
     if (gen->debugMode) {
@@ -540,7 +559,7 @@ LLVMValueRef LLVMCodeGen_compileVariable_impl(LLVMCodeGen *gen, AstNode *node) {
   // If it's a pointer type, we need to load the value
   LLVMTypeRef valueType = LLVMTypeOf(value);
   if (!valueType) {
-    fprintf(stderr, "ERROR: Failed to get type of variable '%s'\n", node->val);
+    Diagnostic_error(ERROR_CODE_CODEGEN_FAILED, Diagnostic_spanOfNode(node), "Failed to get type of variable '%s'", node->val);
     return NULL;
   }
   
@@ -569,7 +588,7 @@ LLVMValueRef LLVMCodeGen_compileVariable_impl(LLVMCodeGen *gen, AstNode *node) {
       // In LLVM 15+ (opaque pointers), use LLVMGetAllocatedType to get the type
       LLVMTypeRef pointeeType = LLVMGetAllocatedType(value);
       if (!pointeeType) {
-        fprintf(stderr, "ERROR: Failed to get pointee type for '%s'\n", node->val);
+        Diagnostic_error(ERROR_CODE_CODEGEN_FAILED, Diagnostic_spanOfNode(node), "Failed to get pointee type for '%s'", node->val);
         return NULL;
       }
       return LLVMBuildLoad2(gen->builder, pointeeType, value, node->val);
@@ -603,7 +622,7 @@ LLVMValueRef LLVMCodeGen_compileAssignment_impl(LLVMCodeGen *gen, AstNode *node)
   #endif
 
   if (!node || node->childCount < 2) {
-    fprintf(stderr, "ERROR: Invalid assignment node\n");
+    Diagnostic_error(ERROR_CODE_CODEGEN_FAILED, Diagnostic_spanOfLine(0), "Invalid assignment node");
     return NULL;
   }
 
@@ -614,7 +633,7 @@ LLVMValueRef LLVMCodeGen_compileAssignment_impl(LLVMCodeGen *gen, AstNode *node)
   #endif
 
   if (!varNode || !varNode->val) {
-    fprintf(stderr, "ERROR: Invalid assignment variable\n");
+    Diagnostic_error(ERROR_CODE_CODEGEN_FAILED, Diagnostic_spanOfNode(node), "Invalid assignment variable");
     return NULL;
   }
 
@@ -635,8 +654,10 @@ LLVMValueRef LLVMCodeGen_compileAssignment_impl(LLVMCodeGen *gen, AstNode *node)
 
   //  If assigning a function, set currentFunctionName for recursion support
   const char *prevFunctionName = gen->currentFunctionName;
+  AstNode *prevFunctionNode = gen->currentFunctionNode;
   if (valueNode->opcode == OP_FUNCTION) {
     gen->currentFunctionName = varNode->val;
+    gen->currentFunctionNode = valueNode;
     if (gen->debugMode) {
       #if 0  // Debug output disabled
       fprintf(stderr, "[DEBUG] Setting currentFunctionName to '%s' for recursion support\n", varNode->val);
@@ -649,10 +670,11 @@ LLVMValueRef LLVMCodeGen_compileAssignment_impl(LLVMCodeGen *gen, AstNode *node)
   //  Restore previous function name after compilation
   if (valueNode->opcode == OP_FUNCTION) {
     gen->currentFunctionName = prevFunctionName;
+    gen->currentFunctionNode = prevFunctionNode;
   }
 
   if (!value) {
-    fprintf(stderr, "ERROR: Failed to compile assignment value\n");
+    Diagnostic_error(ERROR_CODE_CODEGEN_FAILED, Diagnostic_spanOfNode(valueNode), "Failed to compile assignment value");
     return NULL;
   }
 
@@ -718,10 +740,11 @@ LLVMValueRef LLVMCodeGen_compileAssignment_impl(LLVMCodeGen *gen, AstNode *node)
         LLVMBuildStore(gen->builder, value, existingVar);
       } else {
         // Existing variable is immutable - error!
-        fprintf(stderr, "ERROR: Cannot reassign immutable variable '%s' at line %d\n",
-                varNode->val, node->lineNumber);
-        fprintf(stderr, "Hint: Declare the variable with 'mut' to make it mutable: mut %s = ...\n",
-                varNode->val);
+        Diagnostic_error(ERROR_CODE_IMMUTABLE_ASSIGN, Diagnostic_spanOfNode(node), "Cannot reassign immutable variable '%s'", varNode->val);
+        if (Diagnostic_getFormat() == DIAGNOSTIC_FORMAT_HUMAN) {
+          fprintf(stderr, "Hint: Declare the variable with 'mut' to make it mutable: mut %s = ...\n",
+                  varNode->val);
+        }
         return NULL;
       }
     } else {
@@ -1083,7 +1106,7 @@ LLVMValueRef LLVMCodeGen_compileAdd_impl(LLVMCodeGen *gen, AstNode *node) {
   fprintf(stderr, "[ADD] Entry: childCount=%d\n", node->childCount);
   #endif
   if (node->childCount < 2) {
-    fprintf(stderr, "ERROR: add requires at least 2 arguments at line %d\n", node->lineNumber);
+    Diagnostic_error(ERROR_CODE_BUILTIN_ARITY, Diagnostic_spanOfNode(node), "add requires at least 2 arguments");
     return NULL;
   }
 
@@ -1096,7 +1119,7 @@ LLVMValueRef LLVMCodeGen_compileAdd_impl(LLVMCodeGen *gen, AstNode *node) {
   fprintf(stderr, "[ADD] First operand compiled: %p\n", (void*)result);
   #endif
   if (!result) {
-    fprintf(stderr, "ERROR: Failed to compile add operand\n");
+    Diagnostic_error(ERROR_CODE_CODEGEN_FAILED, Diagnostic_spanOfNode(node), "Failed to compile add operand");
     return NULL;
   }
 
@@ -1119,7 +1142,7 @@ LLVMValueRef LLVMCodeGen_compileAdd_impl(LLVMCodeGen *gen, AstNode *node) {
     fprintf(stderr, "[ADD] Operand %d compiled: %p\n", i, (void*)right);
     #endif
     if (!right) {
-      fprintf(stderr, "ERROR: Failed to compile add operand at position %d\n", i);
+      Diagnostic_error(ERROR_CODE_CODEGEN_FAILED, Diagnostic_spanOfNode(node), "Failed to compile add operand at position %d", i);
       return NULL;
     }
 
@@ -1162,7 +1185,7 @@ LLVMValueRef LLVMCodeGen_compileAdd_impl(LLVMCodeGen *gen, AstNode *node) {
 // Compile (subtract a b ...) - supports variadic arguments
 LLVMValueRef LLVMCodeGen_compileSubtract_impl(LLVMCodeGen *gen, AstNode *node) {
   if (node->childCount < 2) {
-    fprintf(stderr, "ERROR: subtract requires at least 2 arguments at line %d\n", node->lineNumber);
+    Diagnostic_error(ERROR_CODE_BUILTIN_ARITY, Diagnostic_spanOfNode(node), "subtract requires at least 2 arguments");
     return NULL;
   }
 
@@ -1203,7 +1226,7 @@ LLVMValueRef LLVMCodeGen_compileSubtract_impl(LLVMCodeGen *gen, AstNode *node) {
 // Compile (multiply a b ...) - supports variadic arguments
 LLVMValueRef LLVMCodeGen_compileMultiply_impl(LLVMCodeGen *gen, AstNode *node) {
   if (node->childCount < 2) {
-    fprintf(stderr, "ERROR: multiply requires at least 2 arguments at line %d\n", node->lineNumber);
+    Diagnostic_error(ERROR_CODE_BUILTIN_ARITY, Diagnostic_spanOfNode(node), "multiply requires at least 2 arguments");
     return NULL;
   }
 
@@ -1244,7 +1267,7 @@ LLVMValueRef LLVMCodeGen_compileMultiply_impl(LLVMCodeGen *gen, AstNode *node) {
 // Compile (divide a b ...) - supports variadic arguments with division by zero checking
 LLVMValueRef LLVMCodeGen_compileDivide_impl(LLVMCodeGen *gen, AstNode *node) {
   if (node->childCount < 2) {
-    fprintf(stderr, "ERROR: divide requires at least 2 arguments at line %d\n", node->lineNumber);
+    Diagnostic_error(ERROR_CODE_BUILTIN_ARITY, Diagnostic_spanOfNode(node), "divide requires at least 2 arguments");
     return NULL;
   }
 
@@ -1271,7 +1294,7 @@ LLVMValueRef LLVMCodeGen_compileDivide_impl(LLVMCodeGen *gen, AstNode *node) {
       // Check if divisor is a constant zero
       if (LLVMIsConstant(divisor)) {
         if (LLVMConstIntGetSExtValue(divisor) == 0) {
-          fprintf(stderr, "ERROR: Division by zero at line %d\n", node->lineNumber);
+          Diagnostic_error(ERROR_CODE_DIVISION_BY_ZERO, Diagnostic_spanOfNode(node), "Division by zero");
           return NULL;
         }
       }
@@ -1743,20 +1766,22 @@ LLVMValueRef LLVMCodeGen_compilePrint_impl(LLVMCodeGen *gen, AstNode *node) {
 LLVMValueRef LLVMCodeGen_compileInput_impl(LLVMCodeGen *gen, AstNode *node) {
   // input takes no arguments
   if (node->childCount != 0) {
-    fprintf(stderr, "ERROR: input requires 0 arguments at line %d\n", node->lineNumber);
+    Diagnostic_error(ERROR_CODE_BUILTIN_ARITY, Diagnostic_spanOfNode(node), "input requires 0 arguments");
     return NULL;
   }
 
   // Safety check: input() requires a current function context
   if (!gen->currentFunction) {
-    fprintf(stderr, "ERROR: input() cannot be used at top-level (line %d)\n", node->lineNumber);
-    fprintf(stderr, "Hint: Wrap input() call inside a function or use it in main body\n");
+    Diagnostic_error(ERROR_CODE_UNSUPPORTED, Diagnostic_spanOfNode(node), "input() cannot be used at top-level");
+    if (Diagnostic_getFormat() == DIAGNOSTIC_FORMAT_HUMAN) {
+      fprintf(stderr, "Hint: Wrap input() call inside a function or use it in main body\n");
+    }
     return NULL;
   }
 
   // Verify malloc is available
   if (!gen->mallocFunc) {
-    fprintf(stderr, "ERROR: malloc function not initialized\n");
+    Diagnostic_error(ERROR_CODE_CODEGEN_FAILED, Diagnostic_spanOfNode(node), "malloc function not initialized");
     return NULL;
   }
 
@@ -1767,20 +1792,20 @@ LLVMValueRef LLVMCodeGen_compileInput_impl(LLVMCodeGen *gen, AstNode *node) {
                                        mallocArgs, 1, "input_buffer");
   
   if (!buffer) {
-    fprintf(stderr, "ERROR: Failed to build malloc call for input buffer\n");
+    Diagnostic_error(ERROR_CODE_CODEGEN_FAILED, Diagnostic_spanOfNode(node), "Failed to build malloc call for input buffer");
     return NULL;
   }
 
   // Allocate stack variables for loop state
   LLVMValueRef lengthPtr = LLVMBuildAlloca(gen->builder, gen->intType, "input_length_ptr");
   if (!lengthPtr) {
-    fprintf(stderr, "ERROR: Failed to allocate length pointer\n");
+    Diagnostic_error(ERROR_CODE_CODEGEN_FAILED, Diagnostic_spanOfNode(node), "Failed to allocate length pointer");
     return NULL;
   }
   
   LLVMValueRef bufferPtr = LLVMBuildAlloca(gen->builder, gen->stringType, "input_buffer_ptr");
   if (!bufferPtr) {
-    fprintf(stderr, "ERROR: Failed to allocate buffer pointer\n");
+    Diagnostic_error(ERROR_CODE_CODEGEN_FAILED, Diagnostic_spanOfNode(node), "Failed to allocate buffer pointer");
     return NULL;
   }
   
@@ -1873,13 +1898,13 @@ LLVMValueRef LLVMCodeGen_compileInput_impl(LLVMCodeGen *gen, AstNode *node) {
 // Compile (integer x) - converts string/float/int to integer
 LLVMValueRef LLVMCodeGen_compileInteger_func_impl(LLVMCodeGen *gen, AstNode *node) {
   if (node->childCount != 1) {
-    fprintf(stderr, "ERROR: integer requires 1 argument at line %d\n", node->lineNumber);
+    Diagnostic_error(ERROR_CODE_BUILTIN_ARITY, Diagnostic_spanOfNode(node), "integer requires 1 argument");
     return NULL;
   }
 
   LLVMValueRef arg = LLVMCodeGen_compileNode_impl(gen, node->children[0]);
   if (!arg) {
-    fprintf(stderr, "ERROR: Failed to compile integer argument\n");
+    Diagnostic_error(ERROR_CODE_CODEGEN_FAILED, Diagnostic_spanOfNode(node->children[0]), "Failed to compile integer argument");
     return NULL;
   }
 
@@ -1895,16 +1920,14 @@ LLVMValueRef LLVMCodeGen_compileInteger_func_impl(LLVMCodeGen *gen, AstNode *nod
   else if (argType == gen->floatType) {
     return LLVMBuildFPToSI(gen->builder, arg, gen->intType, "ftoi");
   }
-  // If string, use atoi
+  // If string, use atoll
   else if (LLVMGetTypeKind(argType) == LLVMPointerTypeKind) {
-    LLVMValueRef atoiArgs[] = {arg};
-    LLVMValueRef result = LLVMBuildCall2(gen->builder, gen->atoiType, gen->atoiFunc,
-                                         atoiArgs, 1, "atoi_call");
-    // atoi returns i32, extend to i64
-    return LLVMBuildSExt(gen->builder, result, gen->intType, "int_extend");
+    LLVMValueRef atollArgs[] = {arg};
+    return LLVMBuildCall2(gen->builder, gen->atollType, gen->atollFunc,
+                          atollArgs, 1, "atoll_call");
   }
   else {
-    fprintf(stderr, "ERROR: integer() requires int, float, or string argument\n");
+    Diagnostic_error(ERROR_CODE_BUILTIN_ARGUMENT, Diagnostic_spanOfNode(node->children[0]), "integer() requires int, float, or string argument");
     return NULL;
   }
 }
@@ -1912,13 +1935,13 @@ LLVMValueRef LLVMCodeGen_compileInteger_func_impl(LLVMCodeGen *gen, AstNode *nod
 // Compile (float x) - converts string/int/float to float
 LLVMValueRef LLVMCodeGen_compileFloat_func_impl(LLVMCodeGen *gen, AstNode *node) {
   if (node->childCount != 1) {
-    fprintf(stderr, "ERROR: float requires 1 argument at line %d\n", node->lineNumber);
+    Diagnostic_error(ERROR_CODE_BUILTIN_ARITY, Diagnostic_spanOfNode(node), "float requires 1 argument");
     return NULL;
   }
 
   LLVMValueRef arg = LLVMCodeGen_compileNode_impl(gen, node->children[0]);
   if (!arg) {
-    fprintf(stderr, "ERROR: Failed to compile float argument\n");
+    Diagnostic_error(ERROR_CODE_CODEGEN_FAILED, Diagnostic_spanOfNode(node->children[0]), "Failed to compile float argument");
     return NULL;
   }
 
@@ -1939,7 +1962,7 @@ LLVMValueRef LLVMCodeGen_compileFloat_func_impl(LLVMCodeGen *gen, AstNode *node)
                           atofArgs, 1, "atof_call");
   }
   else {
-    fprintf(stderr, "ERROR: float() requires int, float, or string argument\n");
+    Diagnostic_error(ERROR_CODE_BUILTIN_ARGUMENT, Diagnostic_spanOfNode(node->children[0]), "float() requires int, float, or string argument");
     return NULL;
   }
 }
@@ -1947,13 +1970,13 @@ LLVMValueRef LLVMCodeGen_compileFloat_func_impl(LLVMCodeGen *gen, AstNode *node)
 // Compile (string x) - converts int/float/string to string
 LLVMValueRef LLVMCodeGen_compileString_func_impl(LLVMCodeGen *gen, AstNode *node) {
   if (node->childCount != 1) {
-    fprintf(stderr, "ERROR: string requires 1 argument at line %d\n", node->lineNumber);
+    Diagnostic_error(ERROR_CODE_BUILTIN_ARITY, Diagnostic_spanOfNode(node), "string requires 1 argument");
     return NULL;
   }
 
   LLVMValueRef arg = LLVMCodeGen_compileNode_impl(gen, node->children[0]);
   if (!arg) {
-    fprintf(stderr, "ERROR: Failed to compile string argument\n");
+    Diagnostic_error(ERROR_CODE_CODEGEN_FAILED, Diagnostic_spanOfNode(node->children[0]), "Failed to compile string argument");
     return NULL;
   }
 
@@ -1996,7 +2019,7 @@ LLVMValueRef LLVMCodeGen_compileString_func_impl(LLVMCodeGen *gen, AstNode *node
     return buffer;
   }
   else {
-    fprintf(stderr, "ERROR: string() requires int, float, or string argument\n");
+    Diagnostic_error(ERROR_CODE_BUILTIN_ARGUMENT, Diagnostic_spanOfNode(node->children[0]), "string() requires int, float, or string argument");
     return NULL;
   }
 }
@@ -2004,28 +2027,28 @@ LLVMValueRef LLVMCodeGen_compileString_func_impl(LLVMCodeGen *gen, AstNode *node
 //  Compile (format-int value base)
 LLVMValueRef LLVMCodeGen_compileFormatInt_impl(LLVMCodeGen *gen, AstNode *node) {
   if (node->childCount != 2) {
-    fprintf(stderr, "ERROR: format-int requires 2 arguments at line %d\n", node->lineNumber);
+    Diagnostic_error(ERROR_CODE_BUILTIN_ARITY, Diagnostic_spanOfNode(node), "format-int requires 2 arguments");
     return NULL;
   }
 
   // Compile value (first argument)
   LLVMValueRef value = LLVMCodeGen_compileNode_impl(gen, node->children[0]);
   if (!value) {
-    fprintf(stderr, "ERROR: Failed to compile format-int value\n");
+    Diagnostic_error(ERROR_CODE_CODEGEN_FAILED, Diagnostic_spanOfNode(node->children[0]), "Failed to compile format-int value");
     return NULL;
   }
 
   // Compile base (second argument)
   LLVMValueRef base = LLVMCodeGen_compileNode_impl(gen, node->children[1]);
   if (!base) {
-    fprintf(stderr, "ERROR: Failed to compile format-int base\n");
+    Diagnostic_error(ERROR_CODE_CODEGEN_FAILED, Diagnostic_spanOfNode(node->children[1]), "Failed to compile format-int base");
     return NULL;
   }
 
   // Ensure value is i64
   LLVMTypeRef valueType = LLVMTypeOf(value);
   if (valueType != gen->intType) {
-    fprintf(stderr, "ERROR: format-int value must be integer\n");
+    Diagnostic_error(ERROR_CODE_BUILTIN_ARGUMENT, Diagnostic_spanOfNode(node->children[0]), "format-int value must be integer");
     return NULL;
   }
 
@@ -2045,21 +2068,21 @@ LLVMValueRef LLVMCodeGen_compileFormatInt_impl(LLVMCodeGen *gen, AstNode *node)
 //  Compile (format-float value precision)
 LLVMValueRef LLVMCodeGen_compileFormatFloat_impl(LLVMCodeGen *gen, AstNode *node) {
   if (node->childCount != 2) {
-    fprintf(stderr, "ERROR: format-float requires 2 arguments at line %d\n", node->lineNumber);
+    Diagnostic_error(ERROR_CODE_BUILTIN_ARITY, Diagnostic_spanOfNode(node), "format-float requires 2 arguments");
     return NULL;
   }
 
   // Compile value (first argument)
   LLVMValueRef value = LLVMCodeGen_compileNode_impl(gen, node->children[0]);
   if (!value) {
-    fprintf(stderr, "ERROR: Failed to compile format-float value\n");
+    Diagnostic_error(ERROR_CODE_CODEGEN_FAILED, Diagnostic_spanOfNode(node->children[0]), "Failed to compile format-float value");
     return NULL;
   }
 
   // Compile precision (second argument)
   LLVMValueRef precision = LLVMCodeGen_compileNode_impl(gen, node->children[1]);
   if (!precision) {
-    fprintf(stderr, "ERROR: Failed to compile format-float precision\n");
+    Diagnostic_error(ERROR_CODE_CODEGEN_FAILED, Diagnostic_spanOfNode(node->children[1]), "Failed to compile format-float precision");
     return NULL;
   }
 
@@ -2068,7 +2091,7 @@ LLVMValueRef LLVMCodeGen_compileFormatFloat_impl(LLVMCodeGen *gen, AstNode *node
   if (valueType == gen->intType) {
     value = LLVMBuildSIToFP(gen->builder, value, gen->floatType, "int_to_float");
   } else if (valueType != gen->floatType) {
-    fprintf(stderr, "ERROR: format-float value must be int or float\n");
+    Diagnostic_error(ERROR_CODE_BUILTIN_ARGUMENT, Diagnostic_spanOfNode(node->children[0]), "format-float value must be int or float");
     return NULL;
   }
 
@@ -2093,7 +2116,7 @@ LLVMValueRef LLVMCodeGen_compileFormatFloat_impl(LLVMCodeGen *gen, AstNode *node
 // Implements the same behavior as StdLib_join from stdlib.c
 LLVMValueRef LLVMCodeGen_compileJoin_impl(LLVMCodeGen *gen, AstNode *node) {
   if (node->childCount < 2) {
-    fprintf(stderr, "ERROR: join requires at least 2 arguments at line %d\n", node->lineNumber);
+    Diagnostic_error(ERROR_CODE_BUILTIN_ARITY, Diagnostic_spanOfNode(node), "join requires at least 2 arguments");
     return NULL;
   }
 
@@ -2113,7 +2136,8 @@ LLVMValueRef LLVMCodeGen_compileJoin_impl(LLVMCodeGen *gen, AstNode *node) {
     // Verify it's a string type
     LLVMTypeRef argType = LLVMTypeOf(strings[i]);
     if (argType != gen->stringType) {
-      fprintf(stderr, "ERROR: join argument %d must be a string at line %d\n", i + 1, node->lineNumber);
+      Diagnostic_error(ERROR_CODE_BUILTIN_ARGUMENT, Diagnostic_spanOfNode(node->children[i]),
+                       "join argument %d must be a string", i + 1);
       free(strings);
       free(lengths);
       return NULL;
@@ -2170,21 +2194,21 @@ LLVMValueRef LLVMCodeGen_compileGet_impl(LLVMCodeGen *gen, AstNode *node) {
 /*
 LLVMValueRef LLVMCodeGen_compileGet_impl_OLD(LLVMCodeGen *gen, AstNode *node) {
   if (node->childCount < 2 || node->childCount > 3) {
-    fprintf(stderr, "ERROR: get requires 2 or 3 arguments at line %d\n", node->lineNumber);
+    Diagnostic_error(ERROR_CODE_BUILTIN_ARITY, Diagnostic_spanOfNode(node), "get requires 2 or 3 arguments");
     return NULL;
   }
 
   // Compile collection/string (first argument)
   LLVMValueRef collection = LLVMCodeGen_compileNode_impl(gen, node->children[0]);
   if (!collection) {
-    fprintf(stderr, "ERROR: Failed to compile get collection argument at line %d\n", node->lineNumber);
+    Diagnostic_error(ERROR_CODE_CODEGEN_FAILED, Diagnostic_spanOfNode(node->children[0]), "Failed to compile get collection argument");
     return NULL;
   }
 
   // Compile index/start (second argument)
   LLVMValueRef index = LLVMCodeGen_compileNode_impl(gen, node->children[1]);
   if (!index) {
-    fprintf(stderr, "ERROR: Failed to compile get index argument at line %d\n", node->lineNumber);
+    Diagnostic_error(ERROR_CODE_CODEGEN_FAILED, Diagnostic_spanOfNode(node->children[1]), "Failed to compile get index argument");
     return NULL;
   }
 
@@ -2226,7 +2250,7 @@ LLVMValueRef LLVMCodeGen_compileGet_impl_OLD(LLVMCodeGen *gen, AstNode *node) {
         // Substring: (get "hello" 0 3) → "hel"
         LLVMValueRef end = LLVMCodeGen_compileNode_impl(gen, node->children[2]);
         if (!end) {
-          fprintf(stderr, "ERROR: Failed to compile get end argument at line %d\n", node->lineNumber);
+          Diagnostic_error(ERROR_CODE_CODEGEN_FAILED, Diagnostic_spanOfNode(node->children[2]), "Failed to compile get end argument");
           return NULL;
         }
 
@@ -2319,7 +2343,7 @@ LLVMValueRef LLVMCodeGen_compileGet_impl_OLD(LLVMCodeGen *gen, AstNode *node) {
     return LLVMBuildCall2(gen->builder, LLVMGlobalGetValueType(listNthFunc),
                           listNthFunc, args, 2, "list_nth");
   } else {
-    fprintf(stderr, "ERROR: get requires list or string as first argument at line %d\n", node->lineNumber);
+    Diagnostic_error(ERROR_CODE_BUILTIN_ARGUMENT, Diagnostic_spanOfNode(node), "get requires list or string as first argument");
     return NULL;
   }
 }
@@ -2331,7 +2355,7 @@ LLVMValueRef LLVMCodeGen_compileGet_impl_OLD(LLVMCodeGen *gen, AstNode *node) {
 
 LLVMValueRef LLVMCodeGen_compileApplication_impl(LLVMCodeGen *gen, AstNode *node) {
   if (node->childCount == 0) {
-    fprintf(stderr, "ERROR: Empty application at line %d\n", node->lineNumber);
+    Diagnostic_error(ERROR_CODE_UNSUPPORTED, Diagnostic_spanOfNode(node), "Empty application");
     return NULL;
   }
 
@@ -2343,6 +2367,10 @@ LLVMValueRef LLVMCodeGen_compileApplication_impl(LLVMCodeGen *gen, AstNode *node
   argNode.children = node->children + 1;
   argNode.childCount = node->childCount - 1;
   argNode.lineNumber = node->lineNumber;
+  argNode.column = node->column;
+  argNode.endLine = node->endLine;
+  argNode.endColumn = node->endColumn;
+  argNode.sourceId = node->sourceId;
 
   // Check if it's an identifier (function name)
   if (funcNode->opcode == OP_IDENTIFIER) {
@@ -2355,14 +2383,14 @@ LLVMValueRef LLVMCodeGen_compileApplication_impl(LLVMCodeGen *gen, AstNode *node
 
       // Validate argument count (need at least module path, callback is optional)
       if (argNode.childCount < 1) {
-        fprintf(stderr, "ERROR: use() requires at least 1 argument (module path) at line %d\n", node->lineNumber);
+        Diagnostic_error(ERROR_CODE_BUILTIN_ARITY, Diagnostic_spanOfNode(node), "use() requires at least 1 argument (module path)");
         return LLVMConstInt(gen->intType, 0, 0);
       }
 
       // Extract module path from first argument (must be string literal)
       AstNode *pathNode = argNode.children[0];
       if (pathNode->opcode != OP_STRING) {
-        fprintf(stderr, "ERROR: use() first argument must be a string literal (module path) at line %d\n", node->lineNumber);
+        Diagnostic_error(ERROR_CODE_BUILTIN_ARGUMENT, Diagnostic_spanOfNode(pathNode), "use() first argument must be a string literal (module path)");
         return LLVMConstInt(gen->intType, 0, 0);
       }
 
@@ -2376,7 +2404,7 @@ LLVMValueRef LLVMCodeGen_compileApplication_impl(LLVMCodeGen *gen, AstNode *node
 
       // Load and compile the module
       if (LLVMModules_use(gen, modulePath, node->lineNumber) != 0) {
-        fprintf(stderr, "ERROR: Failed to load module '%s' at line %d\n", modulePath, node->lineNumber);
+        Diagnostic_error(ERROR_CODE_IMPORT, Diagnostic_spanOfNode(node), "Failed to load module '%s'", modulePath);
         return LLVMConstInt(gen->intType, 0, 0);
       }
 
@@ -2387,7 +2415,7 @@ LLVMValueRef LLVMCodeGen_compileApplication_impl(LLVMCodeGen *gen, AstNode *node
         // Compile the callback (should be a function literal)
         LLVMValueRef callbackValue = LLVMCodeGen_compileNode_impl(gen, callbackNode);
         if (!callbackValue) {
-          fprintf(stderr, "ERROR: Failed to compile use() callback at line %d\n", node->lineNumber);
+          Diagnostic_error(ERROR_CODE_CODEGEN_FAILED, Diagnostic_spanOfNode(callbackNode), "Failed to compile use() callback");
           return LLVMConstInt(gen->intType, 0, 0);
         }
 
@@ -2422,21 +2450,21 @@ LLVMValueRef LLVMCodeGen_compileApplication_impl(LLVMCodeGen *gen, AstNode *node
 
       // Validate argument count
       if (argNode.childCount < 2) {
-        fprintf(stderr, "ERROR: use_as() requires 2 arguments (module path, namespace name) at line %d\n", node->lineNumber);
+        Diagnostic_error(ERROR_CODE_BUILTIN_ARITY, Diagnostic_spanOfNode(node), "use_as() requires 2 arguments (module path, namespace name)");
         return LLVMConstInt(gen->intType, 0, 0);
       }
 
       // Extract module path from first argument (must be string literal)
       AstNode *pathNode = argNode.children[0];
       if (pathNode->opcode != OP_STRING) {
-        fprintf(stderr, "ERROR: use_as() first argument must be a string literal (module path) at line %d\n", node->lineNumber);
+        Diagnostic_error(ERROR_CODE_BUILTIN_ARGUMENT, Diagnostic_spanOfNode(pathNode), "use_as() first argument must be a string literal (module path)");
         return LLVMConstInt(gen->intType, 0, 0);
       }
 
       // Extract namespace name from second argument (must be string literal)
       AstNode *namespaceNode = argNode.children[1];
       if (namespaceNode->opcode != OP_STRING) {
-        fprintf(stderr, "ERROR: use_as() second argument must be a string literal (namespace name) at line %d\n", node->lineNumber);
+        Diagnostic_error(ERROR_CODE_BUILTIN_ARGUMENT, Diagnostic_spanOfNode(namespaceNode), "use_as() second argument must be a string literal (namespace name)");
         return LLVMConstInt(gen->intType, 0, 0);
       }
 
@@ -2452,8 +2480,7 @@ LLVMValueRef LLVMCodeGen_compileApplication_impl(LLVMCodeGen *gen, AstNode *node
 
       // Load and compile the module with namespace prefix
       if (LLVMModules_useAs(gen, modulePath, namespaceName, node->lineNumber) != 0) {
-        fprintf(stderr, "ERROR: Failed to load module '%s' with namespace '%s' at line %d\n",
-                modulePath, namespaceName, node->lineNumber);
+        Diagnostic_error(ERROR_CODE_IMPORT, Diagnostic_spanOfNode(node), "Failed to load module '%s' with namespace '%s'", modulePath, namespaceName);
         return LLVMConstInt(gen->intType, 0, 0);
       }
 
@@ -2467,14 +2494,14 @@ LLVMValueRef LLVMCodeGen_compileApplication_impl(LLVMCodeGen *gen, AstNode *node
 
       // Validate argument count (need at least capabilities list + 1 module path)
       if (argNode.childCount < 2) {
-        fprintf(stderr, "ERROR: use_with() requires at least 2 arguments (capabilities list, module path) at line %d\n", node->lineNumber);
+        Diagnostic_error(ERROR_CODE_BUILTIN_ARITY, Diagnostic_spanOfNode(node), "use_with() requires at least 2 arguments (capabilities list, module path)");
         return LLVMConstInt(gen->intType, 0, 0);
       }
 
       // Extract capabilities from first argument (must be a list literal)
       AstNode *capListNode = argNode.children[0];
       if (capListNode->opcode != OP_LIST) {
-        fprintf(stderr, "ERROR: use_with() first argument must be a list literal (capabilities) at line %d\n", node->lineNumber);
+        Diagnostic_error(ERROR_CODE_BUILTIN_ARGUMENT, Diagnostic_spanOfNode(capListNode), "use_with() first argument must be a list literal (capabilities)");
         return LLVMConstInt(gen->intType, 0, 0);
       }
 
@@ -2484,7 +2511,7 @@ LLVMValueRef LLVMCodeGen_compileApplication_impl(LLVMCodeGen *gen, AstNode *node
 
       for (int i = 0; i < capCount; i++) {
         if (capListNode->children[i]->opcode != OP_STRING) {
-          fprintf(stderr, "ERROR: use_with() capability list must contain only strings at line %d\n", node->lineNumber);
+          Diagnostic_error(ERROR_CODE_BUILTIN_ARGUMENT, Diagnostic_spanOfNode(capListNode->children[i]), "use_with() capability list must contain only strings");
           free(capabilities);
           return LLVMConstInt(gen->intType, 0, 0);
         }
@@ -2508,7 +2535,7 @@ LLVMValueRef LLVMCodeGen_compileApplication_impl(LLVMCodeGen *gen, AstNode *node
         AstNode *pathNode = argNode.children[i];
 
         if (pathNode->opcode != OP_STRING) {
-          fprintf(stderr, "ERROR: use_with() module paths must be string literals at line %d\n", node->lineNumber);
+          Diagnostic_error(ERROR_CODE_BUILTIN_ARGUMENT, Diagnostic_spanOfNode(pathNode), "use_with() module paths must be string literals");
           free(capabilities);
           return LLVMConstInt(gen->intType, 0, 0);
         }
@@ -2524,8 +2551,7 @@ LLVMValueRef LLVMCodeGen_compileApplication_impl(LLVMCodeGen *gen, AstNode *node
 
         // Load and compile the module with capability restrictions
         if (LLVMModules_useWith(gen, capabilities, capCount, modulePath, node->lineNumber) != 0) {
-          fprintf(stderr, "ERROR: Failed to load module '%s' with capabilities at line %d\n",
-                  modulePath, node->lineNumber);
+          Diagnostic_error(ERROR_CODE_IMPORT, Diagnostic_spanOfNode(node), "Failed to load module '%s' with capabilities", modulePath);
           free(capabilities);
           return LLVMConstInt(gen->intType, 0, 0);
         }
@@ -2977,7 +3003,7 @@ LLVMValueRef LLVMCodeGen_compileApplication_impl(LLVMCodeGen *gen, AstNode *node
     } else if (strcmp(funcName, "break") == 0) {
       //  break - early loop exit
       if (gen->loopExitBlock == NULL) {
-        fprintf(stderr, "ERROR: 'break' can only be used inside a loop at line %d\n", node->lineNumber);
+        Diagnostic_error(ERROR_CODE_OUTSIDE_LOOP, Diagnostic_spanOfNode(node), "'break' can only be used inside a loop");
         return NULL;
       }
 
@@ -2986,7 +3012,7 @@ LLVMValueRef LLVMCodeGen_compileApplication_impl(LLVMCodeGen *gen, AstNode *node
         // break with value
         breakValue = LLVMCodeGen_compileNode_impl(gen, argNode.children[0]);
         if (!breakValue) {
-          fprintf(stderr, "ERROR: Failed to compile break value at line %d\n", node->lineNumber);
+          Diagnostic_error(ERROR_CODE_CODEGEN_FAILED, Diagnostic_spanOfNode(argNode.children[0]), "Failed to compile break value");
           return NULL;
         }
       }
@@ -3020,7 +3046,7 @@ LLVMValueRef LLVMCodeGen_compileApplication_impl(LLVMCodeGen *gen, AstNode *node
     } else if (strcmp(funcName, "continue") == 0) {
       //  continue - skip to next iteration
       if (gen->loopIncrBlock == NULL) {
-        fprintf(stderr, "ERROR: 'continue' can only be used inside a loop at line %d\n", node->lineNumber);
+        Diagnostic_error(ERROR_CODE_OUTSIDE_LOOP, Diagnostic_spanOfNode(node), "'continue' can only be used inside a loop");
         return NULL;
       }
 
@@ -3033,14 +3059,15 @@ LLVMValueRef LLVMCodeGen_compileApplication_impl(LLVMCodeGen *gen, AstNode *node
       //  while - condition-based iteration
       return LLVMCodeGen_compileWhile(gen, &argNode);
     } else {
-      fprintf(stderr, "ERROR: Unknown function '%s' at line %d\n",
-              funcName, node->lineNumber);
-      fprintf(stderr, " supports: add, subtract, multiply, divide, println, print, input, rows, columns, repeat, read_file, write_file, integer, float, string, format-int, format-float, join, remainder, power, random, random_int, random_range, random_seed, floor, ceil, round, abs, min, max, sqrt, is, less_than, greater_than, not, and, or, if, when, unless, is_int, is_float, is_string, is_list, is_function, type, cond, loop, while, break, continue, and user-defined functions\n");
+      Diagnostic_error(ERROR_CODE_UNKNOWN_FUNCTION, Diagnostic_spanOfNode(funcNode), "Unknown function '%s'", funcName);
+      if (Diagnostic_getFormat() == DIAGNOSTIC_FORMAT_HUMAN) {
+        fprintf(stderr, " supports: add, subtract, multiply, divide, println, print, input, rows, columns, repeat, read_file, write_file, integer, float, string, format-int, format-float, join, remainder, power, random, random_int, random_range, random_seed, floor, ceil, round, abs, min, max, sqrt, is, less_than, greater_than, not, and, or, if, when, unless, is_int, is_float, is_string, is_list, is_function, type, cond, loop, while, break, continue, and user-defined functions\n");
+      }
       return NULL;
     }
   } else {
     // Higher-order function call (function is an expression)
-    fprintf(stderr, "ERROR: Higher-order function calls not yet supported at line %d\n", node->lineNumber);
+    Diagnostic_error(ERROR_CODE_UNSUPPORTED, Diagnostic_spanOfNode(node), "Higher-order function calls not yet supported");
     return NULL;
   }
 }
@@ -3187,7 +3214,7 @@ LLVMValueRef LLVMCodeGen_compileFunction_impl(LLVMCodeGen *gen, AstNode *node) {
   //   - children[n]: Function body (statement or return)
 
   if (node->childCount == 0) {
-    fprintf(stderr, "ERROR: Empty function definition at line %d\n", node->lineNumber);
+    Diagnostic_error(ERROR_CODE_UNSUPPORTED, Diagnostic_spanOfNode(node), "Empty function definition");
     return NULL;
   }
 
@@ -3237,7 +3264,7 @@ LLVMValueRef LLVMCodeGen_compileFunction_impl(LLVMCodeGen *gen, AstNode *node) {
 
   // Validate we have at least one body statement
   if (paramCount >= node->childCount) {
-    fprintf(stderr, "ERROR: Function has no body at line %d\n", node->lineNumber);
+    Diagnostic_error(ERROR_CODE_UNSUPPORTED, Diagnostic_spanOfNode(node), "Function has no body");
     return NULL;
   }
 
@@ -3248,7 +3275,7 @@ LLVMValueRef LLVMCodeGen_compileFunction_impl(LLVMCodeGen *gen, AstNode *node) {
   //  Infer function type signature
   InferredFunctionType *inferredType = TypeInfer_inferFunction(node);
   if (!inferredType) {
-    fprintf(stderr, "ERROR: Failed to infer function type at line %d\n", node->lineNumber);
+    Diagnostic_error(ERROR_CODE_CODEGEN_FAILED, Diagnostic_spanOfNode(node), "Failed to infer function type");
     return NULL;
   }
 
@@ -3374,7 +3401,7 @@ LLVMValueRef LLVMCodeGen_compileFunction_impl(LLVMCodeGen *gen, AstNode *node) {
   for (int i = 0; i < paramCount; i++) {
     AstNode *paramNode = node->children[i];
     if (paramNode->opcode != OP_IDENTIFIER) {
-      fprintf(stderr, "ERROR: Function parameter must be identifier at line %d\n", paramNode->lineNumber);
+      Diagnostic_error(ERROR_CODE_UNSUPPORTED, Diagnostic_spanOfNode(paramNode), "Function parameter must be identifier");
       free(paramTypes);
       TypeInfer_freeInferredType(inferredType);  //  Cleanup
       LLVMVariableMap_free(gen->variables);
@@ -3403,8 +3430,7 @@ LLVMValueRef LLVMCodeGen_compileFunction_impl(LLVMCodeGen *gen, AstNode *node) {
       LLVMValueRef stmtValue = LLVMCodeGen_compileNode_impl(gen, stmt);
 
       if (!stmtValue) {
-        fprintf(stderr, "ERROR: Failed to compile statement %d in function body at line %d\n",
-                i - paramCount + 1, stmt->lineNumber);
+        Diagnostic_error(ERROR_CODE_CODEGEN_FAILED, Diagnostic_spanOfNode(stmt), "Failed to compile statement %d in function body", i - paramCount + 1);
         free(paramTypes);
         TypeInfer_freeInferredType(inferredType);
         LLVMVariableMap_free(gen->variables);
@@ -3429,7 +3455,7 @@ LLVMValueRef LLVMCodeGen_compileFunction_impl(LLVMCodeGen *gen, AstNode *node) {
     bodyValue = LLVMCodeGen_compileNode_impl(gen, singleBodyStmt);
 
     if (!bodyValue) {
-      fprintf(stderr, "ERROR: Failed to compile function body at line %d\n", node->lineNumber);
+      Diagnostic_error(ERROR_CODE_CODEGEN_FAILED, Diagnostic_spanOfNode(singleBodyStmt), "Failed to compile function body");
       free(paramTypes);
       TypeInfer_freeInferredType(inferredType);
       LLVMVariableMap_free(gen->variables);
@@ -3585,6 +3611,14 @@ LLVMValueRef LLVMCodeGen_compileFunction_impl(LLVMCodeGen *gen, AstNode *node) {
     LLVMBuildRet(gen->builder, bodyValue);
   }
 
+  //  Profile first so that trace logging is not timed
+  if (gen->profile) {
+    LLVMProfile_instrumentFunction(gen, function, node);
+  }
+  if (gen->trace) {
+    LLVMTrace_instrumentFunction(gen, function, node);
+  }
+
   // Restore previous function and position
   gen->currentFunction = prevFunction;
   gen->isPolymorphicFunction = prevIsPolymorphic;  //  Restore polymorphic flag
@@ -3740,7 +3774,7 @@ LLVMValueRef LLVMCodeGen_compileReturn_impl(LLVMCodeGen *gen, AstNode *node) {
     // Compile return value
     LLVMValueRef retValue = LLVMCodeGen_compileNode_impl(gen, node->children[0]);
     if (!retValue) {
-      fprintf(stderr, "ERROR: Failed to compile return value at line %d\n", node->lineNumber);
+      Diagnostic_error(ERROR_CODE_CODEGEN_FAILED, Diagnostic_spanOfNode(node->children[0]), "Failed to compile return value");
       return NULL;
     }
 
@@ -3808,7 +3842,7 @@ LLVMValueRef LLVMCodeGen_compileReturn_impl(LLVMCodeGen *gen, AstNode *node) {
     if (gen->enableTCO) {
       gen->inTailPosition = prevTailPosition;
     }
-    fprintf(stderr, "ERROR: Failed to compile return value at line %d\n", node->lineNumber);
+    Diagnostic_error(ERROR_CODE_CODEGEN_FAILED, Diagnostic_spanOfNode(node), "Failed to compile return value");
     return NULL;
   }
 
@@ -3894,12 +3928,41 @@ LLVMValueRef LLVMCodeGen_compileReturn_impl(LLVMCodeGen *gen, AstNode *node) {
 // Main Node Dispatcher
 // ============================================================================
 
+static LLVMValueRef compileNodeByOpcode(LLVMCodeGen *gen, AstNode *node);
+
 LLVMValueRef LLVMCodeGen_compileNode_impl(LLVMCodeGen *gen, AstNode *node) {
   if (!node) {
-    fprintf(stderr, "ERROR: NULL node\n");
+    Diagnostic_error(ERROR_CODE_CODEGEN_FAILED, Diagnostic_spanOfLine(0), "NULL node");
     return NULL;
   }
 
+  if (!gen->debugInfo && !gen->coverage) return compileNodeByOpcode(gen, node);
+
+  //  -g: code built for this node carries its position; the parent's is restored after
+  LLVMMetadataRef parentLocation = NULL;
+  if (gen->debugInfo) {
+    parentLocation = LLVMGetCurrentDebugLocation2(gen->builder);
+    LLVMDebugInfo_setLocation(gen, node->lineNumber, node->column);
+  }
+
+  //  --coverage: count the node's line unless the parent already counts it
+  LLVMValueRef parentCoverageFunction = gen->coverageFunction;
+  int parentCoverageLine = gen->coverageLine;
+  if (gen->coverage) {
+    LLVMCoverage_countLine(gen, node);
+  }
+
+  LLVMValueRef result = compileNodeByOpcode(gen, node);
+
+  if (gen->debugInfo) {
+    LLVMSetCurrentDebugLocation2(gen->builder, parentLocation);
+  }
+  gen->coverageFunction = parentCoverageFunction;
+  gen->coverageLine = parentCoverageLine;
+  return result;
+}
+
+static LLVMValueRef compileNodeByOpcode(LLVMCodeGen *gen, AstNode *node) {
   switch (node->opcode) {
     case OP_INT:
       return LLVMCodeGen_compileInteger_impl(gen, node);
@@ -3933,9 +3996,10 @@ LLVMValueRef LLVMCodeGen_compileNode_impl(LLVMCodeGen *gen, AstNode *node) {
       return LLVMLists_compileList(gen, node);
 
     default:
-      fprintf(stderr, "ERROR: Unsupported opcode %d at line %d\n",
-              node->opcode, node->lineNumber);
-      fprintf(stderr, " supports: int, float, string, variable, assignment, application, statement, function, return, list\n");
+      Diagnostic_error(ERROR_CODE_UNSUPPORTED, Diagnostic_spanOfNode(node), "Unsupported opcode %d", node->opcode);
+      if (Diagnostic_getFormat() == DIAGNOSTIC_FORMAT_HUMAN) {
+        fprintf(stderr, " supports: int, float, string, variable, assignment, application, statement, function, return, list\n");
+      }
       return NULL;
   }
 }
@@ -4067,32 +4131,64 @@ int LLVMCodeGen_compile_impl(LLVMCodeGen *gen, AstNode *ast, Scope *globalScope)
 
   gen->currentScope = globalScope;
 
-  // Create main function
+  if (gen->debugInfo) {
+    LLVMDebugInfo_init(gen, gen->sourcePath);
+  }
+
+  if (gen->coverage) {
+    LLVMCoverage_init(gen, ast->sourceId);
+  }
+
+  // Create main function: int main(int argc, char **argv)
+  LLVMTypeRef argvType = LLVMPointerType(gen->stringType, 0);
+  LLVMTypeRef mainParams[] = { LLVMInt32TypeInContext(gen->context), argvType };
   LLVMTypeRef mainType = LLVMFunctionType(LLVMInt32TypeInContext(gen->context),
-                                          NULL, 0, 0);
+                                          mainParams, 2, 0);
   gen->currentFunction = LLVMAddFunction(gen->module, "main", mainType);
 
   // Create entry basic block
   LLVMBasicBlockRef entry = LLVMAppendBasicBlock(gen->currentFunction, "entry");
   LLVMPositionBuilderAtEnd(gen->builder, entry);
 
+  //  Bind `arguments`: franz_arguments(argc, argv) -> Generic* list, kept in a global
+  // so function bodies can read it as well as top-level code
+  LLVMTypeRef argumentsParams[] = { LLVMInt32TypeInContext(gen->context), argvType };
+  LLVMTypeRef argumentsType = LLVMFunctionType(gen->stringType, argumentsParams, 2, 0);
+  LLVMValueRef argumentsFunc = LLVMAddFunction(gen->module, "franz_arguments", argumentsType);
+  gen->argumentsGlobal = LLVMAddGlobal(gen->module, gen->stringType, "franz.arguments");
+  LLVMSetLinkage(gen->argumentsGlobal, LLVMInternalLinkage);
+  LLVMSetInitializer(gen->argumentsGlobal, LLVMConstPointerNull(gen->stringType));
+  LLVMValueRef mainArgs[] = { LLVMGetParam(gen->currentFunction, 0), LLVMGetParam(gen->currentFunction, 1) };
+  LLVMValueRef argumentsList = LLVMBuildCall2(gen->builder, argumentsType, argumentsFunc,
+                                              mainArgs, 2, "arguments");
+  LLVMBuildStore(gen->builder, argumentsList, gen->argumentsGlobal);
+  LLVMVariableMap_set(gen->genericVariables, "arguments", (LLVMValueRef)1);
+
   //  Add 'void' constant to global scope (10 represents void sentinel)
   LLVMValueRef voidValue = LLVMConstInt(gen->intType, 10, 0);
   LLVMVariableMap_set(gen->variables, "void", voidValue);
 
+  //  REPL: bind the globals defined by earlier entries
+  if (gen->replSession) {
+    Repl_bindGlobals(gen->replSession, gen);
+  }
+
   // PASS 1: Compile non-function assignments first
   // This ensures variables like `base = 100` exist before we analyze closures that use them
-  if (ast && ast->opcode == OP_STATEMENT) {
-    for (int i = 0; i < ast->childCount; i++) {
-      AstNode *child = ast->children[i];
-      // Compile non-function assignments (e.g., base = 100, x = 42)
-      if (child && child->opcode == OP_ASSIGNMENT && child->childCount >= 2) {
-        AstNode *valueNode = child->children[1];
-        // Skip function assignments - they'll be handled in PASS 2
-        if (!valueNode || valueNode->opcode != OP_FUNCTION) {
-          LLVMCodeGen_compileNode_impl(gen, child);
-        }
-      }
+  //  Assignments compiled here are skipped in PASS 3, so each runs exactly once
+  int statementCount = (ast && ast->opcode == OP_STATEMENT) ? ast->childCount : 0;
+  char *compiledEarly = calloc(statementCount > 0 ? statementCount : 1, 1);
+  for (int i = 0; i < statementCount; i++) {
+    AstNode *child = ast->children[i];
+    //  Stop at the first statement that is not an assignment: later assignments
+    // may depend on its side effects, so they run in order in PASS 3
+    if (!child || child->opcode != OP_ASSIGNMENT || child->childCount < 2) break;
+
+    // Compile non-function assignments (e.g., base = 100, x = 42)
+    AstNode *valueNode = child->children[1];
+    // Skip function assignments - they'll be handled in PASS 2
+    if (!valueNode || valueNode->opcode != OP_FUNCTION) {
+      compiledEarly[i] = LLVMCodeGen_compileNode_impl(gen, child) != NULL;
     }
   }
 
@@ -4112,27 +4208,58 @@ int LLVMCodeGen_compile_impl(LLVMCodeGen *gen, AstNode *ast, Scope *globalScope)
 
   //  PASS 3 - Compile the AST (including function bodies)
   // Functions can now reference each other because all are declared
-  LLVMCodeGen_compileNode_impl(gen, ast);
+  LLVMValueRef lastValue = NULL;
+  if (ast->opcode == OP_STATEMENT) {
+    for (int i = 0; i < statementCount; i++) {
+      if (!compiledEarly[i]) lastValue = LLVMCodeGen_compileNode_impl(gen, ast->children[i]);
+    }
+  } else {
+    lastValue = LLVMCodeGen_compileNode_impl(gen, ast);
+  }
+  free(compiledEarly);
+
+  //  REPL: keep this entry's globals for later entries and print its value
+  if (gen->replSession) {
+    Repl_captureGlobals(gen->replSession, gen, ast, lastValue);
+  }
+
+  //  --coverage: register the counters on entry to main and write them out here
+  if (gen->coverage) {
+    LLVMCoverage_finalize(gen, gen->sourcePath);
+  }
+
+  //  --profile: time runtime calls and start the call tree on entry to main
+  if (gen->profile) {
+    LLVMProfile_finalize(gen);
+  }
 
   // Return 0
   LLVMBuildRet(gen->builder, LLVMConstInt(LLVMInt32TypeInContext(gen->context), 0, 0));
 
+  //  -g: one subprogram per function, line locations on every instruction
+  if (gen->debugInfo) {
+    LLVMDebugInfo_finalize(gen);
+  }
+
   // Verify module
   #if 0  // Debug output disabled
   fprintf(stderr, "[DEBUG] About to verify LLVM module...\n");
   #endif
   char *error = NULL;
-  if (LLVMVerifyModule(gen->module, LLVMPrintMessageAction, &error)) {
-    fprintf(stderr, "ERROR: LLVM module verification failed:\n%s\n", error);
-    #if 0  // Debug output disabled
-    fprintf(stderr, "\n[DEBUG] Dumping LLVM IR for inspection:\n");
-    #endif
-    LLVMDumpModule(gen->module);
+  if (LLVMVerifyModule(gen->module, LLVMReturnStatusAction, &error)) {
+    // First line names the broken instruction; the rest repeats it as IR
+    error[strcspn(error, "\n")] = '\0';
+    Diagnostic_error(ERROR_CODE_CODEGEN_FAILED, Diagnostic_spanOfLine(0), "LLVM module verification failed: %s", error);
+    if (Diagnostic_getFormat() == DIAGNOSTIC_FORMAT_HUMAN) {
+      LLVMDumpModule(gen->module);
+    }
     LLVMDisposeMessage(error);
     return -1;
   }
 
-  printf("✅  LLVM IR generation complete\n");
+  if (!gen->replSession) {
+    printf("✅  LLVM IR generation complete\n");
+  }
   return 0;
 }
 
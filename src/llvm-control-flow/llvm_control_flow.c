@@ -1,4 +1,5 @@
 #include "llvm_control_flow.h"
+#include "../llvm-coverage/llvm_coverage.h"
 #include <stdio.h>
 #include <string.h>
 
@@ -272,8 +273,10 @@ LLVMValueRef LLVMCodeGen_compileIf(LLVMCodeGen *gen, AstNode *node) {
     if (metadata) {
       int storedOpcode = (int)(intptr_t)metadata - 1;  // Subtract 1 (was added to avoid NULL)
 
-      // If variable was assigned from OP_APPLICATION (function call), it's boxed as Generic*
-      if (storedOpcode == OP_APPLICATION) {
+      // If variable was assigned from OP_APPLICATION (function call) returning Generic*, unbox it
+      // (builtins such as and/is_int return raw i64 and are not tracked as Generic*)
+      if (storedOpcode == OP_APPLICATION &&
+          LLVMVariableMap_get(gen->genericVariables, condNode->val) != NULL) {
         // Declare franz_unbox_int if needed
         LLVMValueRef unboxIntFunc = LLVMGetNamedFunction(gen->module, "franz_unbox_int");
         if (!unboxIntFunc) {
@@ -315,6 +318,7 @@ LLVMValueRef LLVMCodeGen_compileIf(LLVMCodeGen *gen, AstNode *node) {
   // Step 5: Compile then branch
   // Support both direct values and multi-statement blocks
   LLVMPositionBuilderAtEnd(gen->builder, thenBlock);
+  if (gen->coverage) LLVMCoverage_countBranch(gen, node, 0);
   LLVMValueRef thenValue = LLVMCodeGen_compileBlockAsStatements(gen, thenNode);
   
   //  fix: Check if block has a terminator (e.g., loop early exit via return)
@@ -348,6 +352,7 @@ LLVMValueRef LLVMCodeGen_compileIf(LLVMCodeGen *gen, AstNode *node) {
   // Step 6: Compile else branch (or use default 0 if optional else)
   //  Support optional else
   LLVMPositionBuilderAtEnd(gen->builder, elseBlock);
+  if (gen->coverage) LLVMCoverage_countBranch(gen, node, 1);
   LLVMValueRef elseValue;
 
   if (elseNode == NULL) {
@@ -1,4 +1,5 @@
 #include "llvm_control_flow.h"
+#include "../llvm-coverage/llvm_coverage.h"
 #include <stdio.h>
 #include <string.h>
 
@@ -109,6 +110,7 @@ LLVMValueRef LLVMCodeGen_compileUnless(LLVMCodeGen *gen, AstNode *node) {
 
   // Step 5: Compile action block
   LLVMPositionBuilderAtEnd(gen->builder, actionBlock);
+  if (gen->coverage) LLVMCoverage_countBranch(gen, node, 0);
 
   // Check if actionNode is a block or direct expression
   LLVMValueRef actionValue;
@@ -132,6 +134,7 @@ LLVMValueRef LLVMCodeGen_compileUnless(LLVMCodeGen *gen, AstNode *node) {
 
   // Step 6: Skip block (condition was true, return 0)
   LLVMPositionBuilderAtEnd(gen->builder, skipBlock);
+  if (gen->coverage) LLVMCoverage_countBranch(gen, node, 1);
   LLVMValueRef skipValue = zero;
   LLVMBuildBr(gen->builder, mergeBlock);
 
@@ -0,0 +1,156 @@
+#include "llvm_coverage.h"
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <limits.h>
+
+#ifndef PATH_MAX
+#define PATH_MAX 4096
+#endif
+
+//  Counters built so far; record i is (line, group, branch) of counter i,
+// with group -1 for line counters
+typedef struct CoverageCounters {
+  int sourceId;
+  LLVMValueRef *globals;
+  int *records;
+  int count;
+  int capacity;
+  int groups;   // branch groups opened so far
+} CoverageCounters;
+
+void LLVMCoverage_init(LLVMCodeGen *gen, int sourceId) {
+  CoverageCounters *counters = calloc(1, sizeof(CoverageCounters));
+  counters->sourceId = sourceId;
+  gen->coverageCounters = counters;
+  gen->coverageFunction = NULL;
+  gen->coverageLine = 0;
+}
+
+//  Add a zeroed counter and build its increment at the insert point
+static void buildCounter(LLVMCodeGen *gen, int line, int group, int branch) {
+  CoverageCounters *counters = gen->coverageCounters;
+  if (counters->count == counters->capacity) {
+    counters->capacity = counters->capacity ? counters->capacity * 2 : 64;
+    counters->globals = realloc(counters->globals, sizeof(LLVMValueRef) * counters->capacity);
+    counters->records = realloc(counters->records, sizeof(int) * 3 * counters->capacity);
+  }
+
+  LLVMTypeRef i64 = LLVMInt64TypeInContext(gen->context);
+  LLVMValueRef counter = LLVMAddGlobal(gen->module, i64, "franz.coverage");
+  LLVMSetLinkage(counter, LLVMInternalLinkage);
+  LLVMSetInitializer(counter, LLVMConstInt(i64, 0, 0));
+
+  counters->globals[counters->count] = counter;
+  counters->records[counters->count * 3] = line;
+  counters->records[counters->count * 3 + 1] = group;
+  counters->records[counters->count * 3 + 2] = branch;
+  counters->count++;
+
+  LLVMValueRef hits = LLVMBuildLoad2(gen->builder, i64, counter, "cov_hits");
+  hits = LLVMBuildAdd(gen->builder, hits, LLVMConstInt(i64, 1, 0), "cov_hits_next");
+  LLVMBuildStore(gen->builder, hits, counter);
+}
+
+//  Whether code can be added at the insert point for this node
+static int canCount(LLVMCodeGen *gen, AstNode *node) {
+  CoverageCounters *counters = gen->coverageCounters;
+  if (!counters || node->lineNumber <= 0 || node->sourceId != counters->sourceId) return 0;
+  LLVMBasicBlockRef block = LLVMGetInsertBlock(gen->builder);
+  return block != NULL && LLVMGetBasicBlockTerminator(block) == NULL;
+}
+
+void LLVMCoverage_countLine(LLVMCodeGen *gen, AstNode *node) {
+  if (!canCount(gen, node)) return;
+
+  //  Nested nodes on the same line share the counter of the outermost one;
+  // a closure body is another function, so its lines are counted again
+  LLVMValueRef function = LLVMGetBasicBlockParent(LLVMGetInsertBlock(gen->builder));
+  if (function == gen->coverageFunction && node->lineNumber == gen->coverageLine) return;
+
+  gen->coverageFunction = function;
+  gen->coverageLine = node->lineNumber;
+  buildCounter(gen, node->lineNumber, -1, 0);
+}
+
+void LLVMCoverage_countBranch(LLVMCodeGen *gen, AstNode *node, int branch) {
+  if (!canCount(gen, node)) return;
+
+  CoverageCounters *counters = gen->coverageCounters;
+  if (branch == 0) counters->groups++;
+  buildCounter(gen, node->lineNumber, counters->groups - 1, branch);
+}
+
+//  Internal constant global holding an array
+static LLVMValueRef constantTable(LLVMCodeGen *gen, LLVMTypeRef elementType,
+                                  LLVMValueRef *values, int count, const char *name) {
+  LLVMValueRef array = LLVMConstArray(elementType, values, count);
+  LLVMValueRef table = LLVMAddGlobal(gen->module, LLVMTypeOf(array), name);
+  LLVMSetLinkage(table, LLVMInternalLinkage);
+  LLVMSetGlobalConstant(table, 1);
+  LLVMSetInitializer(table, array);
+  return LLVMConstBitCast(table, LLVMPointerType(elementType, 0));
+}
+
+void LLVMCoverage_finalize(LLVMCodeGen *gen, const char *sourcePath) {
+  CoverageCounters *counters = gen->coverageCounters;
+  if (!counters) return;
+
+  LLVMTypeRef i32 = LLVMInt32TypeInContext(gen->context);
+  LLVMTypeRef i64 = LLVMInt64TypeInContext(gen->context);
+  LLVMTypeRef counterPtr = LLVMPointerType(i64, 0);
+  LLVMTypeRef stringType = LLVMPointerType(LLVMInt8TypeInContext(gen->context), 0);
+
+  //  Flush on return from main(); early exits are flushed by the runtime's atexit handler
+  LLVMTypeRef flushType = LLVMFunctionType(LLVMVoidTypeInContext(gen->context), NULL, 0, 0);
+  LLVMValueRef flush = LLVMAddFunction(gen->module, "franz_coverage_flush", flushType);
+  LLVMBasicBlockRef endBlock = LLVMGetInsertBlock(gen->builder);
+  LLVMBuildCall2(gen->builder, flushType, flush, NULL, 0, "");
+
+  LLVMValueRef *records = malloc(sizeof(LLVMValueRef) * (counters->count * 3 + 1));
+  for (int i = 0; i < counters->count * 3; i++) {
+    records[i] = LLVMConstInt(i32, counters->records[i], 1);
+  }
+  LLVMValueRef counterTable = constantTable(gen, counterPtr, counters->globals, counters->count,
+                                            "franz.coverage.counters");
+  LLVMValueRef recordTable = constantTable(gen, i32, records, counters->count * 3,
+                                           "franz.coverage.records");
+  free(records);
+
+  //  The data file names the program by its absolute path so reports work from any directory
+  char resolved[PATH_MAX];
+  const char *path = sourcePath ? sourcePath : "<stdin>";
+  if (sourcePath && realpath(sourcePath, resolved) != NULL) path = resolved;
+
+  //  Register before any counted code runs: first thing in main()
+  LLVMValueRef mainFunction = LLVMGetBasicBlockParent(endBlock);
+  LLVMBasicBlockRef entry = LLVMGetEntryBasicBlock(mainFunction);
+  LLVMValueRef first = LLVMGetFirstInstruction(entry);
+  if (first) {
+    LLVMPositionBuilderBefore(gen->builder, first);
+  } else {
+    LLVMPositionBuilderAtEnd(gen->builder, entry);
+  }
+
+  LLVMTypeRef registerParams[] = { stringType, i64, LLVMPointerType(counterPtr, 0), LLVMPointerType(i32, 0) };
+  LLVMTypeRef registerType = LLVMFunctionType(LLVMVoidTypeInContext(gen->context), registerParams, 4, 0);
+  LLVMValueRef registerFunc = LLVMAddFunction(gen->module, "franz_coverage_register", registerType);
+  LLVMValueRef registerArgs[] = {
+    LLVMBuildGlobalStringPtr(gen->builder, path, "franz.coverage.source"),
+    LLVMConstInt(i64, counters->count, 0),
+    counterTable,
+    recordTable
+  };
+  LLVMBuildCall2(gen->builder, registerType, registerFunc, registerArgs, 4, "");
+
+  LLVMPositionBuilderAtEnd(gen->builder, endBlock);
+}
+
+void LLVMCoverage_dispose(LLVMCodeGen *gen) {
+  CoverageCounters *counters = gen->coverageCounters;
+  if (!counters) return;
+  free(counters->globals);
+  free(counters->records);
+  free(counters);
+  gen->coverageCounters = NULL;
+}
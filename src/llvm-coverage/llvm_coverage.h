@@ -0,0 +1,77 @@
+#ifndef LLVM_COVERAGE_H
+#define LLVM_COVERAGE_H
+
+#include "../llvm-codegen/llvm_codegen.h"
+
+/**
+ * Line and Branch Coverage for Franz (--coverage)
+ *
+ * Every source line of the program gets an execution counter: the first
+ * node compiled for a line (per function) increments it before its own
+ * code runs. The two arms of each `if` get one counter each. Counters are
+ * private i64 globals, so instrumented code runs at nearly full speed.
+ *
+ * LLVMCoverage_finalize tables the counters with their lines and makes
+ * main() hand them to the runtime (franz_coverage_register) on entry and
+ * write them out (franz_coverage_flush) on return. See
+ * src/coverage/coverage.h for the data file and `franz coverage report`.
+ *
+ * Only the program's own source is counted; code of imported modules is
+ * left uninstrumented.
+ */
+
+/**
+ * Start instrumenting a module
+ *
+ * Called by LLVMCodeGen_compile before main() is generated.
+ *
+ * @param gen - Code generator (gen->coverage set)
+ * @param sourceId - Diagnostic source of the program (nodes from other sources are not counted)
+ */
+void LLVMCoverage_init(LLVMCodeGen *gen, int sourceId);
+
+/**
+ * Count the execution of a node's line
+ *
+ * Builds a counter increment at the insert point unless the enclosing
+ * node already counted this line in the same function. Called by
+ * LLVMCodeGen_compileNode for every node; the caller saves
+ * gen->coverageFunction and gen->coverageLine and restores them after
+ * compiling the node.
+ *
+ * @param gen - Code generator
+ * @param node - Node about to be compiled
+ */
+void LLVMCoverage_countLine(LLVMCodeGen *gen, AstNode *node);
+
+/**
+ * Count the execution of one arm of a branch
+ *
+ * Called with the builder at the start of the arm. Branch 0 opens a new
+ * branch group; following branches belong to the same group.
+ *
+ * @param gen - Code generator
+ * @param node - Branching node (its line is reported)
+ * @param branch - Arm index (0 = then, 1 = else)
+ */
+void LLVMCoverage_countBranch(LLVMCodeGen *gen, AstNode *node, int branch);
+
+/**
+ * Register the counters at the start of main() and flush them at its end
+ *
+ * Called by LLVMCodeGen_compile with the builder at the end of main(),
+ * before its return is built.
+ *
+ * @param gen - Code generator
+ * @param sourcePath - Path of the .franz file, or NULL for stdin
+ */
+void LLVMCoverage_finalize(LLVMCodeGen *gen, const char *sourcePath);
+
+/**
+ * Free the counter table
+ *
+ * @param gen - Code generator
+ */
+void LLVMCoverage_dispose(LLVMCodeGen *gen);
+
+#endif
@@ -0,0 +1,123 @@
+#include "llvm_debuginfo.h"
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <limits.h>
+#include <unistd.h>
+#include <llvm-c/DebugInfo.h>
+
+#ifndef PATH_MAX
+#define PATH_MAX 4096
+#endif
+
+#define FRANZ_PRODUCER "franz"
+
+void LLVMDebugInfo_init(LLVMCodeGen *gen, const char *sourcePath) {
+  //  DWARF stores the file as directory + name; use an absolute directory so
+  // debuggers find the source from any working directory
+  char resolved[PATH_MAX];
+  const char *path = sourcePath ? sourcePath : "<stdin>";
+  if (sourcePath && realpath(sourcePath, resolved) != NULL) path = resolved;
+
+  const char *slash = strrchr(path, '/');
+  const char *fileName = slash ? slash + 1 : path;
+  char directory[PATH_MAX];
+  if (slash) {
+    snprintf(directory, sizeof(directory), "%.*s", (int)(slash - path), path);
+  } else if (getcwd(directory, sizeof(directory)) == NULL) {
+    strcpy(directory, ".");
+  }
+
+  gen->diBuilder = LLVMCreateDIBuilder(gen->module);
+  gen->diFile = LLVMDIBuilderCreateFile(gen->diBuilder, fileName, strlen(fileName),
+                                        directory, strlen(directory));
+  gen->diCompileUnit = LLVMDIBuilderCreateCompileUnit(
+      gen->diBuilder, LLVMDWARFSourceLanguageC, gen->diFile,
+      FRANZ_PRODUCER, strlen(FRANZ_PRODUCER), gen->optLevel > 0, "", 0, 0, "", 0,
+      LLVMDWARFEmissionFull, 0, 0, 0, "", 0, "", 0);
+
+  LLVMAddModuleFlag(gen->module, LLVMModuleFlagBehaviorWarning, "Debug Info Version", 18,
+                    LLVMValueAsMetadata(LLVMConstInt(LLVMInt32TypeInContext(gen->context),
+                                                     LLVMDebugMetadataVersion(), 0)));
+  LLVMAddModuleFlag(gen->module, LLVMModuleFlagBehaviorWarning, "Dwarf Version", 13,
+                    LLVMValueAsMetadata(LLVMConstInt(LLVMInt32TypeInContext(gen->context), 4, 0)));
+
+  //  Scratch scope for locations set during compilation (see LLVMDebugInfo_finalize)
+  LLVMMetadataRef type = LLVMDIBuilderCreateSubroutineType(gen->diBuilder, gen->diFile, NULL, 0, LLVMDIFlagZero);
+  gen->diScratchScope = LLVMDIBuilderCreateFunction(gen->diBuilder, gen->diFile, "", 0, "", 0,
+                                                    gen->diFile, 0, type, 1, 0, 0, LLVMDIFlagZero,
+                                                    gen->optLevel > 0);
+}
+
+void LLVMDebugInfo_setLocation(LLVMCodeGen *gen, int line, int column) {
+  if (!gen->diBuilder || line <= 0) return;
+  LLVMMetadataRef location = LLVMDIBuilderCreateDebugLocation(gen->context, line, column,
+                                                              gen->diScratchScope, NULL);
+  LLVMSetCurrentDebugLocation2(gen->builder, location);
+}
+
+//  Franz name of a compiled function (top-level `name = {...}`), or NULL
+static const char *franzFunctionName(LLVMCodeGen *gen, LLVMValueRef function) {
+  LLVMVariableMap *functions = gen->functions;
+  for (int i = 0; i < functions->count; i++) {
+    if (functions->entries[i].value == function) return functions->entries[i].name;
+  }
+  return NULL;
+}
+
+//  First source line in a function, or 0 if none of its code has a line
+static unsigned firstLine(LLVMValueRef function) {
+  unsigned line = 0;
+  for (LLVMBasicBlockRef block = LLVMGetFirstBasicBlock(function); block; block = LLVMGetNextBasicBlock(block)) {
+    for (LLVMValueRef inst = LLVMGetFirstInstruction(block); inst; inst = LLVMGetNextInstruction(inst)) {
+      unsigned instLine = LLVMGetDebugLocLine(inst);
+      if (instLine > 0 && (line == 0 || instLine < line)) line = instLine;
+    }
+  }
+  return line;
+}
+
+static void attachSubprogram(LLVMCodeGen *gen, LLVMValueRef function) {
+  size_t linkageLength;
+  const char *linkageName = LLVMGetValueName2(function, &linkageLength);
+  const char *name = franzFunctionName(gen, function);
+  if (!name) name = linkageName;
+
+  //  Closure ABI wrappers have no source of their own
+  LLVMDIFlags flags = strncmp(linkageName, "_franz_wrapper_", 15) == 0 ? LLVMDIFlagArtificial : LLVMDIFlagZero;
+  unsigned line = firstLine(function);
+
+  LLVMMetadataRef type = LLVMDIBuilderCreateSubroutineType(gen->diBuilder, gen->diFile, NULL, 0, LLVMDIFlagZero);
+  LLVMMetadataRef subprogram = LLVMDIBuilderCreateFunction(
+      gen->diBuilder, gen->diFile, name, strlen(name), linkageName, linkageLength,
+      gen->diFile, line, type, 0, 1, line, flags, gen->optLevel > 0);
+  LLVMSetSubprogram(function, subprogram);
+
+  //  Every instruction gets a location in this function's scope; code without a
+  // line of its own (prologues, wrappers) is attributed to the function's first line
+  for (LLVMBasicBlockRef block = LLVMGetFirstBasicBlock(function); block; block = LLVMGetNextBasicBlock(block)) {
+    for (LLVMValueRef inst = LLVMGetFirstInstruction(block); inst; inst = LLVMGetNextInstruction(inst)) {
+      unsigned instLine = LLVMGetDebugLocLine(inst);
+      unsigned instColumn = instLine ? LLVMGetDebugLocColumn(inst) : 0;
+      LLVMMetadataRef location = LLVMDIBuilderCreateDebugLocation(gen->context, instLine ? instLine : line,
+                                                                  instColumn, subprogram, NULL);
+      LLVMInstructionSetDebugLoc(inst, location);
+    }
+  }
+}
+
+void LLVMDebugInfo_finalize(LLVMCodeGen *gen) {
+  if (!gen->diBuilder) return;
+
+  LLVMSetCurrentDebugLocation2(gen->builder, NULL);
+  for (LLVMValueRef function = LLVMGetFirstFunction(gen->module); function;
+       function = LLVMGetNextFunction(function)) {
+    if (LLVMCountBasicBlocks(function) > 0) attachSubprogram(gen, function);
+  }
+  LLVMDIBuilderFinalize(gen->diBuilder);
+}
+
+void LLVMDebugInfo_dispose(LLVMCodeGen *gen) {
+  if (gen->diBuilder) LLVMDisposeDIBuilder(gen->diBuilder);
+  gen->diBuilder = NULL;
+}
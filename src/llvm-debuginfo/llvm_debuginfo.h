@@ -0,0 +1,57 @@
+#ifndef LLVM_DEBUGINFO_H
+#define LLVM_DEBUGINFO_H
+
+#include "../llvm-codegen/llvm_codegen.h"
+
+/**
+ * DWARF Debug Information for Franz (-g)
+ *
+ * Maps generated machine code back to .franz source lines so debuggers
+ * (gdb, lldb) show Franz functions and lines in backtraces and accept
+ * breakpoints such as `break app.franz:12`.
+ *
+ * While compiling, every AST node sets the builder's debug location to its
+ * line. Instructions are attached to a scratch scope at that point because
+ * helpers build code in several functions at once (closure wrappers,
+ * forward declarations). LLVMDebugInfo_finalize then creates one
+ * subprogram per defined function and moves each instruction's line into
+ * the subprogram of the function that contains it.
+ */
+
+/**
+ * Create the compile unit for a module
+ *
+ * Called by LLVMCodeGen_compile before main() is generated.
+ *
+ * @param gen - Code generator (gen->debugInfo set)
+ * @param sourcePath - Path of the .franz file, or NULL for stdin
+ */
+void LLVMDebugInfo_init(LLVMCodeGen *gen, const char *sourcePath);
+
+/**
+ * Set the source position of the instructions built next
+ *
+ * @param gen - Code generator
+ * @param line - Line number (lines <= 0 are ignored)
+ * @param column - Column number (0 if unknown)
+ */
+void LLVMDebugInfo_setLocation(LLVMCodeGen *gen, int line, int column);
+
+/**
+ * Create subprograms, attach line locations and finalize the metadata
+ *
+ * Called by LLVMCodeGen_compile once all code is generated, before the
+ * module is verified.
+ *
+ * @param gen - Code generator
+ */
+void LLVMDebugInfo_finalize(LLVMCodeGen *gen);
+
+/**
+ * Free the debug info builder
+ *
+ * @param gen - Code generator
+ */
+void LLVMDebugInfo_dispose(LLVMCodeGen *gen);
+
+#endif
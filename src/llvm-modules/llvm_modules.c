@@ -5,6 +5,7 @@
 #include "../file.h"
 #include "../lex.h"
 #include "../parse.h"
+#include "../diagnostics/diagnostic.h"
 #include "../llvm-codegen/llvm_codegen.h"
 
 //  LLVM Module System Implementation
@@ -222,8 +223,8 @@ int LLVMModules_use(LLVMCodeGen *gen, const char *modulePath, int lineNumber) {
   // Check for circular dependency BEFORE loading
   if (LLVMModules_pushImport(modulePath, lineNumber) != 0) {
     LLVMModules_printCircularChain(modulePath);
-    fprintf(stderr, "ERROR: Circular dependency detected when importing '%s' at line %d\n",
-            modulePath, lineNumber);
+    Diagnostic_error(ERROR_CODE_CIRCULAR_IMPORT, Diagnostic_spanOfLine(lineNumber),
+                     "Circular dependency detected when importing '%s'", modulePath);
     return -1;
   }
 
@@ -257,8 +258,8 @@ int LLVMModules_use(LLVMCodeGen *gen, const char *modulePath, int lineNumber) {
   // Read the module file
   char *code = readFile(modulePath, 1); // 1 = true for isModule
   if (!code) {
-    fprintf(stderr, "ERROR: Failed to read module file '%s' at line %d\n",
-            modulePath, lineNumber);
+    Diagnostic_error(ERROR_CODE_IMPORT, Diagnostic_spanOfLine(lineNumber),
+                     "Failed to read module file '%s'", modulePath);
     LLVMModules_popImport(modulePath);
     return -1;
   }
@@ -266,7 +267,8 @@ int LLVMModules_use(LLVMCodeGen *gen, const char *modulePath, int lineNumber) {
   // Get file length for lexer
   int fileLength = strlen(code);
 
-  // Lex the code
+  // Lex the code (registered so errors inside the module quote the module)
+  Diagnostic_addSource(modulePath, code, fileLength);
   TokenArray *tokens = lex(code, fileLength);
   if (!tokens) {
     fprintf(stderr, "ERROR: Failed to tokenize module '%s' at line %d\n",
@@ -278,6 +280,11 @@ int LLVMModules_use(LLVMCodeGen *gen, const char *modulePath, int lineNumber) {
 
   // Parse the code
   AstNode *ast = parseProgram(tokens);
+  if (Diagnostic_count() > 0) {
+    Diagnostic_flush(stdout);
+    if (ast) AstNode_free(ast);
+    ast = NULL;
+  }
   if (!ast) {
     fprintf(stderr, "ERROR: Failed to parse module '%s' at line %d\n",
             modulePath, lineNumber);
@@ -357,8 +364,8 @@ int LLVMModules_useAs(LLVMCodeGen *gen, const char *modulePath, const char *name
   // Check for circular dependency BEFORE loading
   if (LLVMModules_pushImport(modulePath, lineNumber) != 0) {
     LLVMModules_printCircularChain(modulePath);
-    fprintf(stderr, "ERROR: Circular dependency detected when importing '%s' at line %d\n",
-            modulePath, lineNumber);
+    Diagnostic_error(ERROR_CODE_CIRCULAR_IMPORT, Diagnostic_spanOfLine(lineNumber),
+                     "Circular dependency detected when importing '%s'", modulePath);
     return -1;
   }
 
@@ -396,8 +403,8 @@ int LLVMModules_useAs(LLVMCodeGen *gen, const char *modulePath, const char *name
   // Read the module file
   char *code = readFile(modulePath, 1);
   if (!code) {
-    fprintf(stderr, "ERROR: Failed to read module file '%s' at line %d\n",
-            modulePath, lineNumber);
+    Diagnostic_error(ERROR_CODE_IMPORT, Diagnostic_spanOfLine(lineNumber),
+                     "Failed to read module file '%s'", modulePath);
     LLVMModules_popImport(modulePath);
     return -1;
   }
@@ -405,7 +412,8 @@ int LLVMModules_useAs(LLVMCodeGen *gen, const char *modulePath, const char *name
   // Get file length for lexer
   int fileLength = strlen(code);
 
-  // Lex the code
+  // Lex the code (registered so errors inside the module quote the module)
+  Diagnostic_addSource(modulePath, code, fileLength);
   TokenArray *tokens = lex(code, fileLength);
   if (!tokens) {
     fprintf(stderr, "ERROR: Failed to tokenize module '%s' at line %d\n",
@@ -417,6 +425,11 @@ int LLVMModules_useAs(LLVMCodeGen *gen, const char *modulePath, const char *name
 
   // Parse the code
   AstNode *ast = parseProgram(tokens);
+  if (Diagnostic_count() > 0) {
+    Diagnostic_flush(stdout);
+    if (ast) AstNode_free(ast);
+    ast = NULL;
+  }
   if (!ast) {
     fprintf(stderr, "ERROR: Failed to parse module '%s' at line %d\n",
             modulePath, lineNumber);
@@ -581,8 +594,8 @@ int LLVMModules_useWith(LLVMCodeGen *gen, const char **capabilities, int capabil
   // Check for circular dependency BEFORE loading
   if (LLVMModules_pushImport(modulePath, lineNumber) != 0) {
     LLVMModules_printCircularChain(modulePath);
-    fprintf(stderr, "ERROR: Circular dependency detected when importing '%s' at line %d\n",
-            modulePath, lineNumber);
+    Diagnostic_error(ERROR_CODE_CIRCULAR_IMPORT, Diagnostic_spanOfLine(lineNumber),
+                     "Circular dependency detected when importing '%s'", modulePath);
     return -1;
   }
 
@@ -597,8 +610,8 @@ int LLVMModules_useWith(LLVMCodeGen *gen, const char **capabilities, int capabil
   // Read the module file
   char *code = readFile(modulePath, 1);
   if (!code) {
-    fprintf(stderr, "ERROR: Failed to read module file '%s' at line %d\n",
-            modulePath, lineNumber);
+    Diagnostic_error(ERROR_CODE_IMPORT, Diagnostic_spanOfLine(lineNumber),
+                     "Failed to read module file '%s'", modulePath);
     LLVMModules_popImport(modulePath);
     return -1;
   }
@@ -606,7 +619,8 @@ int LLVMModules_useWith(LLVMCodeGen *gen, const char **capabilities, int capabil
   // Get file length for lexer
   int fileLength = strlen(code);
 
-  // Lex the code
+  // Lex the code (registered so errors inside the module quote the module)
+  Diagnostic_addSource(modulePath, code, fileLength);
   TokenArray *tokens = lex(code, fileLength);
   if (!tokens) {
     fprintf(stderr, "ERROR: Failed to tokenize module '%s' at line %d\n",
@@ -618,6 +632,11 @@ int LLVMModules_useWith(LLVMCodeGen *gen, const char **capabilities, int capabil
 
   // Parse the code
   AstNode *ast = parseProgram(tokens);
+  if (Diagnostic_count() > 0) {
+    Diagnostic_flush(stdout);
+    if (ast) AstNode_free(ast);
+    ast = NULL;
+  }
   if (!ast) {
     fprintf(stderr, "ERROR: Failed to parse module '%s' at line %d\n",
             modulePath, lineNumber);
@@ -0,0 +1,127 @@
+#include "llvm_profile.h"
+#include "../llvm-trace/llvm_trace.h"
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+//  Runtime helpers that are not worth timing on their own
+static const char *untimedPrefixes[] = {
+  "franz_box_",
+  "franz_unbox_",
+  "franz_generic_",
+  "franz_profile_",
+  "franz_trace_",
+  "franz_coverage_",
+  NULL
+};
+
+static LLVMValueRef runtimeFunction(LLVMCodeGen *gen, const char *name, LLVMTypeRef type) {
+  LLVMValueRef function = LLVMGetNamedFunction(gen->module, name);
+  return function ? function : LLVMAddFunction(gen->module, name, type);
+}
+
+static void buildEnter(LLVMCodeGen *gen, LLVMValueRef name) {
+  LLVMTypeRef params[] = { LLVMPointerType(LLVMInt8TypeInContext(gen->context), 0) };
+  LLVMTypeRef type = LLVMFunctionType(LLVMVoidTypeInContext(gen->context), params, 1, 0);
+  LLVMBuildCall2(gen->builder, type, runtimeFunction(gen, "franz_profile_enter", type), &name, 1, "");
+}
+
+static void buildNoArgCall(LLVMCodeGen *gen, const char *function) {
+  LLVMTypeRef type = LLVMFunctionType(LLVMVoidTypeInContext(gen->context), NULL, 0, 0);
+  LLVMBuildCall2(gen->builder, type, runtimeFunction(gen, function, type), NULL, 0, "");
+}
+
+static void positionAtStart(LLVMCodeGen *gen, LLVMValueRef function) {
+  LLVMBasicBlockRef entry = LLVMGetEntryBasicBlock(function);
+  LLVMValueRef first = LLVMGetFirstInstruction(entry);
+  if (first) {
+    LLVMPositionBuilderBefore(gen->builder, first);
+  } else {
+    LLVMPositionBuilderAtEnd(gen->builder, entry);
+  }
+}
+
+void LLVMProfile_instrumentFunction(LLVMCodeGen *gen, LLVMValueRef function, AstNode *node) {
+  char buffer[64];
+  const char *name = LLVMTrace_functionName(gen, node, buffer, sizeof(buffer));
+
+  //  Inserted calls carry no debug location
+  LLVMBasicBlockRef savedBlock = LLVMGetInsertBlock(gen->builder);
+  LLVMMetadataRef savedLocation = LLVMGetCurrentDebugLocation2(gen->builder);
+  LLVMSetCurrentDebugLocation2(gen->builder, NULL);
+
+  //  Returns are collected first: the calls below must not be counted twice
+  int returnCount = 0;
+  for (LLVMBasicBlockRef block = LLVMGetFirstBasicBlock(function); block; block = LLVMGetNextBasicBlock(block)) {
+    LLVMValueRef terminator = LLVMGetBasicBlockTerminator(block);
+    if (terminator && LLVMIsAReturnInst(terminator)) returnCount++;
+  }
+  LLVMValueRef *returns = malloc(sizeof(LLVMValueRef) * (returnCount + 1));
+  returnCount = 0;
+  for (LLVMBasicBlockRef block = LLVMGetFirstBasicBlock(function); block; block = LLVMGetNextBasicBlock(block)) {
+    LLVMValueRef terminator = LLVMGetBasicBlockTerminator(block);
+    if (terminator && LLVMIsAReturnInst(terminator)) returns[returnCount++] = terminator;
+  }
+
+  positionAtStart(gen, function);
+  buildEnter(gen, LLVMBuildGlobalStringPtr(gen->builder, name, "franz.profile.name"));
+  for (int i = 0; i < returnCount; i++) {
+    LLVMPositionBuilderBefore(gen->builder, returns[i]);
+    buildNoArgCall(gen, "franz_profile_exit");
+  }
+  free(returns);
+
+  if (savedBlock) LLVMPositionBuilderAtEnd(gen->builder, savedBlock);
+  LLVMSetCurrentDebugLocation2(gen->builder, savedLocation);
+}
+
+//  Callee of a call to a franz_* runtime function that is worth timing, or NULL
+static const char *timedRuntimeCallee(LLVMValueRef instruction) {
+  if (!LLVMIsACallInst(instruction)) return NULL;
+  LLVMValueRef callee = LLVMGetCalledValue(instruction);
+  if (!callee || !LLVMIsAFunction(callee) || !LLVMIsDeclaration(callee)) return NULL;
+
+  const char *name = LLVMGetValueName(callee);
+  if (strncmp(name, "franz_", 6) != 0) return NULL;
+  for (int i = 0; untimedPrefixes[i]; i++) {
+    if (strncmp(name, untimedPrefixes[i], strlen(untimedPrefixes[i])) == 0) return NULL;
+  }
+  return name;
+}
+
+void LLVMProfile_finalize(LLVMCodeGen *gen) {
+  LLVMBasicBlockRef endBlock = LLVMGetInsertBlock(gen->builder);
+  LLVMValueRef mainFunction = LLVMGetBasicBlockParent(endBlock);
+  LLVMMetadataRef savedLocation = LLVMGetCurrentDebugLocation2(gen->builder);
+  LLVMSetCurrentDebugLocation2(gen->builder, NULL);
+
+  //  Wrap runtime calls in every function body, main() included
+  for (LLVMValueRef function = LLVMGetFirstFunction(gen->module); function;
+       function = LLVMGetNextFunction(function)) {
+    for (LLVMBasicBlockRef block = LLVMGetFirstBasicBlock(function); block; block = LLVMGetNextBasicBlock(block)) {
+      LLVMValueRef instruction = LLVMGetFirstInstruction(block);
+      while (instruction) {
+        LLVMValueRef next = LLVMGetNextInstruction(instruction);
+        const char *callee = timedRuntimeCallee(instruction);
+        if (callee) {
+          LLVMPositionBuilderBefore(gen->builder, instruction);
+          buildEnter(gen, LLVMBuildGlobalStringPtr(gen->builder, callee, "franz.profile.runtime"));
+          if (next) {
+            LLVMPositionBuilderBefore(gen->builder, next);
+          } else {
+            LLVMPositionBuilderAtEnd(gen->builder, block);
+          }
+          buildNoArgCall(gen, "franz_profile_exit");
+        }
+        instruction = next;
+      }
+    }
+  }
+
+  //  Start before any profiled code runs: first thing in main()
+  positionAtStart(gen, mainFunction);
+  buildNoArgCall(gen, "franz_profile_start");
+
+  LLVMPositionBuilderAtEnd(gen->builder, endBlock);
+  LLVMSetCurrentDebugLocation2(gen->builder, savedLocation);
+}
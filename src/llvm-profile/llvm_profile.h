@@ -0,0 +1,42 @@
+#ifndef LLVM_PROFILE_H
+#define LLVM_PROFILE_H
+
+#include "../llvm-codegen/llvm_codegen.h"
+
+/**
+ * Call Profiling for Franz (--profile)
+ *
+ * Every user function and closure calls franz_profile_enter with its name
+ * on entry and franz_profile_exit before each `ret`. Calls to franz_*
+ * runtime functions (franz_llvm_map, franz_dict_merge, ...) are wrapped
+ * the same way, under their C name, so time spent in the standard library
+ * shows up next to the Franz code that called it. Boxing, unboxing and
+ * instrumentation helpers are left out: they are too small to time and
+ * would only add overhead.
+ *
+ * The runtime (src/profile/profile.h) builds the call tree and reports it
+ * when the program exits.
+ */
+
+/**
+ * Profile a function or closure once its body is complete
+ *
+ * Called by LLVMCodeGen_compileFunction and LLVMClosures_compileClosure.
+ *
+ * @param gen - Code generator (gen->profile set)
+ * @param function - Completed function
+ * @param node - Its OP_FUNCTION node (names it, see LLVMTrace_functionName)
+ */
+void LLVMProfile_instrumentFunction(LLVMCodeGen *gen, LLVMValueRef function, AstNode *node);
+
+/**
+ * Wrap the module's runtime calls and start profiling in main()
+ *
+ * Called by LLVMCodeGen_compile with the builder at the end of main(),
+ * before its return is built.
+ *
+ * @param gen - Code generator
+ */
+void LLVMProfile_finalize(LLVMCodeGen *gen);
+
+#endif
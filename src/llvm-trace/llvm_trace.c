@@ -0,0 +1,199 @@
+#include "llvm_trace.h"
+#include "../llvm-closures/llvm_closures.h"
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <fnmatch.h>
+
+const char *LLVMTrace_functionName(LLVMCodeGen *gen, AstNode *node, char *buffer, size_t size) {
+  if (gen->currentFunctionName != NULL && gen->currentFunctionNode == node) {
+    return gen->currentFunctionName;
+  }
+  snprintf(buffer, size, "lambda@%d", node->lineNumber);
+  return buffer;
+}
+
+static int shouldTrace(LLVMCodeGen *gen, const char *name) {
+  return gen->tracePattern == NULL || fnmatch(gen->tracePattern, name, 0) == 0;
+}
+
+static LLVMValueRef runtimeFunction(LLVMCodeGen *gen, const char *name, LLVMTypeRef type) {
+  LLVMValueRef function = LLVMGetNamedFunction(gen->module, name);
+  return function ? function : LLVMAddFunction(gen->module, name, type);
+}
+
+static LLVMTypeRef enterType(LLVMCodeGen *gen) {
+  LLVMTypeRef params[] = {
+    LLVMPointerType(LLVMInt8TypeInContext(gen->context), 0),
+    LLVMInt32TypeInContext(gen->context)
+  };
+  return LLVMFunctionType(LLVMVoidTypeInContext(gen->context), params, 2, 0);
+}
+
+//  franz_trace_arg and franz_trace_exit: (i8* name, i64 value, i32 tag)
+static LLVMTypeRef valueReportType(LLVMCodeGen *gen) {
+  LLVMTypeRef params[] = {
+    LLVMPointerType(LLVMInt8TypeInContext(gen->context), 0),
+    gen->intType,
+    LLVMInt32TypeInContext(gen->context)
+  };
+  return LLVMFunctionType(LLVMVoidTypeInContext(gen->context), params, 3, 0);
+}
+
+//  Convert a value of any compiled type to an i64 payload and its closure return tag
+static LLVMValueRef payload(LLVMCodeGen *gen, LLVMValueRef value, int *tag) {
+  LLVMTypeRef type = LLVMTypeOf(value);
+  switch (LLVMGetTypeKind(type)) {
+    case LLVMIntegerTypeKind:
+      *tag = CLOSURE_RETURN_INT;
+      if (type == gen->intType) return value;
+      return LLVMBuildIntCast2(gen->builder, value, gen->intType, 1, "trace_int");
+    case LLVMFloatTypeKind:
+      value = LLVMBuildFPExt(gen->builder, value, gen->floatType, "trace_fpext");
+      // fall through
+    case LLVMDoubleTypeKind:
+      *tag = CLOSURE_RETURN_FLOAT;
+      return LLVMBuildBitCast(gen->builder, value, gen->intType, "trace_float");
+    case LLVMPointerTypeKind: {
+      LLVMTypeRef closurePtr = LLVMPointerType(LLVMClosures_getClosureType(gen->context), 0);
+      *tag = type == closurePtr ? CLOSURE_RETURN_CLOSURE : CLOSURE_RETURN_POINTER;
+      return LLVMBuildPtrToInt(gen->builder, value, gen->intType, "trace_ptr");
+    }
+    default:
+      *tag = CLOSURE_RETURN_VOID;
+      return LLVMConstInt(gen->intType, 0, 0);
+  }
+}
+
+static void buildEnter(LLVMCodeGen *gen, LLVMValueRef name, int argCount) {
+  LLVMTypeRef type = enterType(gen);
+  LLVMValueRef args[] = { name, LLVMConstInt(LLVMInt32TypeInContext(gen->context), argCount, 0) };
+  LLVMBuildCall2(gen->builder, type, runtimeFunction(gen, "franz_trace_enter", type), args, 2, "");
+}
+
+static void buildValueReport(LLVMCodeGen *gen, const char *function, LLVMValueRef name,
+                             LLVMValueRef value, LLVMValueRef tag) {
+  LLVMTypeRef type = valueReportType(gen);
+  LLVMValueRef args[] = { name, value, tag };
+  LLVMBuildCall2(gen->builder, type, runtimeFunction(gen, function, type), args, 3, "");
+}
+
+//  Builder state saved around instrumentation; inserted calls carry no debug location
+typedef struct TraceBuilderState {
+  LLVMBasicBlockRef block;
+  LLVMMetadataRef location;
+} TraceBuilderState;
+
+static TraceBuilderState enterFunction(LLVMCodeGen *gen, LLVMValueRef function) {
+  TraceBuilderState state = { LLVMGetInsertBlock(gen->builder), LLVMGetCurrentDebugLocation2(gen->builder) };
+  LLVMSetCurrentDebugLocation2(gen->builder, NULL);
+
+  LLVMBasicBlockRef entry = LLVMGetEntryBasicBlock(function);
+  LLVMValueRef first = LLVMGetFirstInstruction(entry);
+  if (first) {
+    LLVMPositionBuilderBefore(gen->builder, first);
+  } else {
+    LLVMPositionBuilderAtEnd(gen->builder, entry);
+  }
+  return state;
+}
+
+static void leaveFunction(LLVMCodeGen *gen, TraceBuilderState state) {
+  if (state.block) LLVMPositionBuilderAtEnd(gen->builder, state.block);
+  LLVMSetCurrentDebugLocation2(gen->builder, state.location);
+}
+
+//  Every `ret` of the function, collected before new code is inserted
+static LLVMValueRef *collectReturns(LLVMValueRef function, int *count) {
+  int capacity = 4;
+  LLVMValueRef *returns = malloc(sizeof(LLVMValueRef) * capacity);
+  *count = 0;
+  for (LLVMBasicBlockRef block = LLVMGetFirstBasicBlock(function); block; block = LLVMGetNextBasicBlock(block)) {
+    LLVMValueRef terminator = LLVMGetBasicBlockTerminator(block);
+    if (!terminator || !LLVMIsAReturnInst(terminator)) continue;
+    if (*count == capacity) {
+      capacity *= 2;
+      returns = realloc(returns, sizeof(LLVMValueRef) * capacity);
+    }
+    returns[(*count)++] = terminator;
+  }
+  return returns;
+}
+
+void LLVMTrace_instrumentFunction(LLVMCodeGen *gen, LLVMValueRef function, AstNode *node) {
+  char buffer[64];
+  const char *name = LLVMTrace_functionName(gen, node, buffer, sizeof(buffer));
+  if (!shouldTrace(gen, name)) return;
+
+  LLVMTypeRef i32 = LLVMInt32TypeInContext(gen->context);
+  TraceBuilderState state = enterFunction(gen, function);
+
+  LLVMValueRef nameString = LLVMBuildGlobalStringPtr(gen->builder, name, "franz.trace.name");
+  int paramCount = LLVMCountParams(function);
+  buildEnter(gen, nameString, paramCount);
+  for (int i = 0; i < paramCount; i++) {
+    int tag;
+    LLVMValueRef value = payload(gen, LLVMGetParam(function, i), &tag);
+    const char *paramName = i < node->childCount && node->children[i]->val ? node->children[i]->val : "?";
+    buildValueReport(gen, "franz_trace_arg",
+                     LLVMBuildGlobalStringPtr(gen->builder, paramName, "franz.trace.arg"),
+                     value, LLVMConstInt(i32, tag, 0));
+  }
+
+  int returnCount;
+  LLVMValueRef *returns = collectReturns(function, &returnCount);
+  for (int i = 0; i < returnCount; i++) {
+    LLVMPositionBuilderBefore(gen->builder, returns[i]);
+    int tag = CLOSURE_RETURN_VOID;
+    LLVMValueRef value = LLVMConstInt(gen->intType, 0, 0);
+    if (LLVMGetNumOperands(returns[i]) > 0) {
+      value = payload(gen, LLVMGetOperand(returns[i], 0), &tag);
+    }
+    buildValueReport(gen, "franz_trace_exit", nameString, value, LLVMConstInt(i32, tag, 0));
+  }
+  free(returns);
+
+  leaveFunction(gen, state);
+}
+
+void LLVMTrace_instrumentClosure(LLVMCodeGen *gen, LLVMValueRef function, AstNode *node,
+                                 int returnTag, int returnedParam) {
+  char buffer[64];
+  const char *name = LLVMTrace_functionName(gen, node, buffer, sizeof(buffer));
+  if (!shouldTrace(gen, name)) return;
+
+  LLVMTypeRef i32 = LLVMInt32TypeInContext(gen->context);
+  TraceBuilderState state = enterFunction(gen, function);
+
+  //  Parameters after the environment come in (value, tag) pairs
+  LLVMValueRef nameString = LLVMBuildGlobalStringPtr(gen->builder, name, "franz.trace.name");
+  int paramCount = (LLVMCountParams(function) - 1) / 2;
+  buildEnter(gen, nameString, paramCount);
+  for (int i = 0; i < paramCount; i++) {
+    const char *paramName = i < node->childCount && node->children[i]->val ? node->children[i]->val : "?";
+    buildValueReport(gen, "franz_trace_arg",
+                     LLVMBuildGlobalStringPtr(gen->builder, paramName, "franz.trace.arg"),
+                     LLVMGetParam(function, 1 + i * 2), LLVMGetParam(function, 2 + i * 2));
+  }
+
+  //  A closure returning one of its parameters has that parameter's runtime tag
+  LLVMValueRef tag = LLVMConstInt(i32, returnTag, 0);
+  if (returnTag == CLOSURE_RETURN_DYNAMIC) {
+    tag = returnedParam >= 0 && returnedParam < paramCount
+            ? LLVMGetParam(function, 2 + returnedParam * 2)
+            : LLVMConstInt(i32, CLOSURE_RETURN_POINTER, 0);
+  }
+
+  int returnCount;
+  LLVMValueRef *returns = collectReturns(function, &returnCount);
+  for (int i = 0; i < returnCount; i++) {
+    if (LLVMGetNumOperands(returns[i]) == 0) continue;
+    LLVMPositionBuilderBefore(gen->builder, returns[i]);
+    int unusedTag;
+    LLVMValueRef value = payload(gen, LLVMGetOperand(returns[i], 0), &unusedTag);
+    buildValueReport(gen, "franz_trace_exit", nameString, value, tag);
+  }
+  free(returns);
+
+  leaveFunction(gen, state);
+}
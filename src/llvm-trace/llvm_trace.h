@@ -0,0 +1,60 @@
+#ifndef LLVM_TRACE_H
+#define LLVM_TRACE_H
+
+#include "../llvm-codegen/llvm_codegen.h"
+
+/**
+ * Function-Level Execution Tracing for Franz (--trace)
+ *
+ * Every user function and closure whose name matches gen->tracePattern
+ * reports its calls to the runtime (src/trace/trace.h): on entry its name
+ * and arguments, before each return its result. The runtime prints them to
+ * stderr indented by nesting depth.
+ *
+ * Functions are instrumented after their body is complete, so the calls
+ * are inserted at the start of the entry block and in front of every
+ * `ret`, whichever path (implicit result or `<-`) built it.
+ *
+ * Functions bound by an assignment are traced under the assigned name;
+ * anonymous ones are named "lambda@LINE".
+ */
+
+/**
+ * Name of a function in traces and profiles
+ *
+ * @param gen - Code generator (currentFunctionName/Node describe the assignment being compiled)
+ * @param node - OP_FUNCTION node
+ * @param buffer - Storage for generated names
+ * @param size - Size of buffer
+ * @return The assigned name, or "lambda@LINE" in buffer
+ */
+const char *LLVMTrace_functionName(LLVMCodeGen *gen, AstNode *node, char *buffer, size_t size);
+
+/**
+ * Trace a top-level function compiled by LLVMCodeGen_compileFunction
+ *
+ * Arguments and results are reported by their LLVM type (i64, double,
+ * closure pointer or other pointer).
+ *
+ * @param gen - Code generator (gen->trace set)
+ * @param function - Completed function
+ * @param node - Its OP_FUNCTION node (parameter names and line)
+ */
+void LLVMTrace_instrumentFunction(LLVMCodeGen *gen, LLVMValueRef function, AstNode *node);
+
+/**
+ * Trace a closure compiled by LLVMClosures_compileClosure
+ *
+ * Arguments are the (i64 value, i32 tag) parameter pairs; the i8* result
+ * is reported with the closure's return tag.
+ *
+ * @param gen - Code generator (gen->trace set)
+ * @param function - Completed closure function (i8* env, i64, i32, ...)
+ * @param node - Its OP_FUNCTION node
+ * @param returnTag - Closure return tag (CLOSURE_RETURN_*)
+ * @param returnedParam - For CLOSURE_RETURN_DYNAMIC, the returned parameter whose tag applies (-1 otherwise)
+ */
+void LLVMTrace_instrumentClosure(LLVMCodeGen *gen, LLVMValueRef function, AstNode *node,
+                                 int returnTag, int returnedParam);
+
+#endif
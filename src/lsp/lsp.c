@@ -0,0 +1,320 @@
+#include "lsp.h"
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <stdbool.h>
+#include <unistd.h>
+#include "lsp_analysis.h"
+#include "../json/json.h"
+
+//  JSON-RPC error codes
+#define LSP_PARSE_ERROR (-32700)
+#define LSP_INVALID_REQUEST (-32600)
+#define LSP_METHOD_NOT_FOUND (-32601)
+#define LSP_INVALID_PARAMS (-32602)
+
+//  An open document and its latest analysis
+typedef struct LspDocument {
+  char *uri;
+  LspAnalysis *analysis;
+} LspDocument;
+
+typedef struct LspServer {
+  FILE *out;             // Protocol stream (the original stdout)
+  LspDocument *documents;
+  int documentCount;
+  bool shutdown;
+  const char *version;   // Reported in serverInfo
+} LspServer;
+
+// ---------------------------------------------------------------------------
+// Transport
+// ---------------------------------------------------------------------------
+
+//  Read one message body; returns NULL at end of input
+static char *readMessage(int *length) {
+  char header[1024];
+  int contentLength = -1;
+
+  while (fgets(header, sizeof(header), stdin) != NULL) {
+    if (strcmp(header, "\r\n") == 0 || strcmp(header, "\n") == 0) {
+      if (contentLength < 0) continue;  // tolerate stray blank lines between messages
+
+      char *body = malloc(contentLength + 1);
+      if (fread(body, 1, contentLength, stdin) != (size_t) contentLength) {
+        free(body);
+        return NULL;
+      }
+      body[contentLength] = '\0';
+      *length = contentLength;
+      return body;
+    }
+    if (strncasecmp(header, "Content-Length:", 15) == 0) {
+      contentLength = atoi(header + 15);
+    }
+  }
+  return NULL;
+}
+
+static void writeMessage(LspServer *server, JsonBuffer *body) {
+  fprintf(server->out, "Content-Length: %d\r\n\r\n", body->length);
+  fwrite(body->data, 1, body->length, server->out);
+  fflush(server->out);
+}
+
+//  Reply with a result (raw JSON text)
+static void respond(LspServer *server, const JsonValue *id, const char *result) {
+  JsonBuffer body;
+  JsonBuffer_init(&body);
+  JsonBuffer_append(&body, "{\"jsonrpc\":\"2.0\",\"id\":");
+  JsonBuffer_appendValue(&body, id);
+  JsonBuffer_append(&body, ",\"result\":");
+  JsonBuffer_append(&body, result);
+  JsonBuffer_append(&body, "}");
+  writeMessage(server, &body);
+  JsonBuffer_free(&body);
+}
+
+static void respondError(LspServer *server, const JsonValue *id, int code, const char *message) {
+  JsonBuffer body;
+  JsonBuffer_init(&body);
+  JsonBuffer_append(&body, "{\"jsonrpc\":\"2.0\",\"id\":");
+  JsonBuffer_appendValue(&body, id);
+  JsonBuffer_appendf(&body, ",\"error\":{\"code\":%d,\"message\":", code);
+  JsonBuffer_appendString(&body, message);
+  JsonBuffer_append(&body, "}}");
+  writeMessage(server, &body);
+  JsonBuffer_free(&body);
+}
+
+// ---------------------------------------------------------------------------
+// Documents
+// ---------------------------------------------------------------------------
+
+static LspDocument *findDocument(LspServer *server, const char *uri) {
+  for (int i = 0; i < server->documentCount; i++) {
+    if (strcmp(server->documents[i].uri, uri) == 0) return &server->documents[i];
+  }
+  return NULL;
+}
+
+static void publishDiagnostics(LspServer *server, const char *uri, LspAnalysis *analysis) {
+  JsonBuffer body;
+  JsonBuffer_init(&body);
+  JsonBuffer_append(&body, "{\"jsonrpc\":\"2.0\",\"method\":\"textDocument/publishDiagnostics\",\"params\":{\"uri\":");
+  JsonBuffer_appendString(&body, uri);
+  JsonBuffer_append(&body, ",\"diagnostics\":");
+  if (analysis != NULL) {
+    LspAnalysis_writeDiagnostics(analysis, &body);
+  } else {
+    JsonBuffer_append(&body, "[]");
+  }
+  JsonBuffer_append(&body, "}}");
+  writeMessage(server, &body);
+  JsonBuffer_free(&body);
+}
+
+//  (Re)analyze a document and publish its problems
+static void updateDocument(LspServer *server, const char *uri, const char *text) {
+  LspDocument *document = findDocument(server, uri);
+  if (document == NULL) {
+    server->documents = realloc(server->documents, sizeof(LspDocument) * (server->documentCount + 1));
+    document = &server->documents[server->documentCount++];
+    document->uri = strdup(uri);
+    document->analysis = NULL;
+  }
+
+  LspAnalysis_free(document->analysis);
+  document->analysis = LspAnalysis_new(uri, text);
+  publishDiagnostics(server, uri, document->analysis);
+}
+
+static void closeDocument(LspServer *server, const char *uri) {
+  LspDocument *document = findDocument(server, uri);
+  if (document == NULL) return;
+
+  LspAnalysis_free(document->analysis);
+  free(document->uri);
+  *document = server->documents[--server->documentCount];
+
+  // Clear the problems the editor is still showing
+  publishDiagnostics(server, uri, NULL);
+}
+
+// ---------------------------------------------------------------------------
+// Methods
+// ---------------------------------------------------------------------------
+
+//  Prefer utf-8 positions when the client offers them (no UTF-16 counting needed)
+static bool clientSupportsUtf8(const JsonValue *params) {
+  JsonValue *general = Json_get(Json_get(params, "capabilities"), "general");
+  JsonValue *encodings = Json_get(general, "positionEncodings");
+  if (encodings == NULL || encodings->type != JSON_ARRAY) return false;
+
+  for (int i = 0; i < encodings->count; i++) {
+    JsonValue *encoding = encodings->items[i];
+    if (encoding->type == JSON_STRING && strcmp(encoding->string, "utf-8") == 0) return true;
+  }
+  return false;
+}
+
+static void handleInitialize(LspServer *server, const JsonValue *id, const JsonValue *params) {
+  bool utf8 = clientSupportsUtf8(params);
+  LspAnalysis_setUtf8Positions(utf8);
+
+  JsonBuffer result;
+  JsonBuffer_init(&result);
+  JsonBuffer_appendf(&result,
+    "{\"capabilities\":{"
+      "\"positionEncoding\":\"%s\","
+      "\"textDocumentSync\":{\"openClose\":true,\"change\":1},"
+      "\"hoverProvider\":true,"
+      "\"definitionProvider\":true,"
+      "\"completionProvider\":{\"triggerCharacters\":[\"(\",\".\"]},"
+      "\"documentSymbolProvider\":true"
+    "},\"serverInfo\":{\"name\":\"franz-lsp\",\"version\":\"%s\"}}",
+    utf8 ? "utf-8" : "utf-16", server->version);
+  respond(server, id, result.data);
+  JsonBuffer_free(&result);
+}
+
+//  Requests on a position: hover, definition, completion
+typedef void (*LspPositionQuery)(LspAnalysis *, int, int, JsonBuffer *);
+
+static void handlePositionRequest(LspServer *server, const JsonValue *id, const JsonValue *params,
+                                  LspPositionQuery query) {
+  const char *uri = Json_getString(Json_get(params, "textDocument"), "uri");
+  JsonValue *position = Json_get(params, "position");
+  if (uri == NULL || position == NULL) {
+    respondError(server, id, LSP_INVALID_PARAMS, "Expected textDocument.uri and position");
+    return;
+  }
+
+  LspDocument *document = findDocument(server, uri);
+  if (document == NULL) {
+    respond(server, id, "null");
+    return;
+  }
+
+  JsonBuffer result;
+  JsonBuffer_init(&result);
+  query(document->analysis, Json_getInt(position, "line", 0), Json_getInt(position, "character", 0), &result);
+  respond(server, id, result.data);
+  JsonBuffer_free(&result);
+}
+
+static void handleDocumentSymbol(LspServer *server, const JsonValue *id, const JsonValue *params) {
+  const char *uri = Json_getString(Json_get(params, "textDocument"), "uri");
+  LspDocument *document = uri != NULL ? findDocument(server, uri) : NULL;
+  if (document == NULL) {
+    respond(server, id, "[]");
+    return;
+  }
+
+  JsonBuffer result;
+  JsonBuffer_init(&result);
+  LspAnalysis_writeSymbols(document->analysis, &result);
+  respond(server, id, result.data);
+  JsonBuffer_free(&result);
+}
+
+//  didChange with full sync: the last change holds the whole text
+static const char *changedText(const JsonValue *params) {
+  JsonValue *changes = Json_get(params, "contentChanges");
+  if (changes == NULL || changes->type != JSON_ARRAY || changes->count == 0) return NULL;
+  return Json_getString(changes->items[changes->count - 1], "text");
+}
+
+//  Handle one message; returns false when the server should stop
+static bool dispatch(LspServer *server, const JsonValue *message, int *exitCode) {
+  const char *method = Json_getString(message, "method");
+  const JsonValue *id = Json_get(message, "id");
+  const JsonValue *params = Json_get(message, "params");
+  const JsonValue *textDocument = Json_get(params, "textDocument");
+
+  if (method == NULL) {
+    // Responses to server requests are not expected; ignore them
+    if (id == NULL) respondError(server, NULL, LSP_INVALID_REQUEST, "Missing method");
+    return true;
+  }
+
+  if (strcmp(method, "exit") == 0) {
+    *exitCode = server->shutdown ? 0 : 1;
+    return false;
+  }
+
+  if (server->shutdown && id != NULL) {
+    respondError(server, id, LSP_INVALID_REQUEST, "Server is shutting down");
+    return true;
+  }
+
+  if (strcmp(method, "initialize") == 0) {
+    handleInitialize(server, id, params);
+  } else if (strcmp(method, "shutdown") == 0) {
+    server->shutdown = true;
+    respond(server, id, "null");
+  } else if (strcmp(method, "textDocument/didOpen") == 0) {
+    const char *uri = Json_getString(textDocument, "uri");
+    const char *text = Json_getString(textDocument, "text");
+    if (uri != NULL && text != NULL) updateDocument(server, uri, text);
+  } else if (strcmp(method, "textDocument/didChange") == 0) {
+    const char *uri = Json_getString(textDocument, "uri");
+    const char *text = changedText(params);
+    if (uri != NULL && text != NULL) updateDocument(server, uri, text);
+  } else if (strcmp(method, "textDocument/didClose") == 0) {
+    const char *uri = Json_getString(textDocument, "uri");
+    if (uri != NULL) closeDocument(server, uri);
+  } else if (strcmp(method, "textDocument/hover") == 0) {
+    handlePositionRequest(server, id, params, LspAnalysis_writeHover);
+  } else if (strcmp(method, "textDocument/definition") == 0) {
+    handlePositionRequest(server, id, params, LspAnalysis_writeDefinition);
+  } else if (strcmp(method, "textDocument/completion") == 0) {
+    handlePositionRequest(server, id, params, LspAnalysis_writeCompletion);
+  } else if (strcmp(method, "textDocument/documentSymbol") == 0) {
+    handleDocumentSymbol(server, id, params);
+  } else if (id != NULL) {
+    respondError(server, id, LSP_METHOD_NOT_FOUND, "Method not found");
+  }
+  // Other notifications (initialized, $/cancelRequest, didSave, ...) need no answer
+
+  return true;
+}
+
+int Lsp_run(const char *version) {
+  LspServer server = { NULL, NULL, 0, false, version };
+
+  // Keep the protocol on the real stdout; compiler output goes to stderr
+  int protocolFd = dup(STDOUT_FILENO);
+  if (protocolFd < 0 || (server.out = fdopen(protocolFd, "w")) == NULL) {
+    perror("franz lsp");
+    return 1;
+  }
+  fflush(stdout);
+  dup2(STDERR_FILENO, STDOUT_FILENO);
+
+  int exitCode = 1;  // stdin closed without an exit notification
+  int length;
+  char *body;
+  while ((body = readMessage(&length)) != NULL) {
+    JsonValue *message = Json_parse(body, length);
+    free(body);
+
+    if (message == NULL || message->type != JSON_OBJECT) {
+      respondError(&server, NULL, LSP_PARSE_ERROR, "Invalid JSON");
+      Json_free(message);
+      continue;
+    }
+
+    bool keepGoing = dispatch(&server, message, &exitCode);
+    Json_free(message);
+    if (!keepGoing) break;
+  }
+
+  for (int i = 0; i < server.documentCount; i++) {
+    LspAnalysis_free(server.documents[i].analysis);
+    free(server.documents[i].uri);
+  }
+  free(server.documents);
+  fclose(server.out);
+  return exitCode;
+}
@@ -0,0 +1,28 @@
+#ifndef LSP_H
+#define LSP_H
+
+/**
+ * Franz language server (`franz lsp`)
+ *
+ * Speaks the Language Server Protocol over stdin/stdout: JSON-RPC messages
+ * framed by Content-Length headers. Documents are re-analyzed on every
+ * change with the compiler's own lexer, parser, free-variable analysis and
+ * type inference (see lsp_analysis.h).
+ *
+ * Supported: diagnostics (publishDiagnostics), hover (inferred types),
+ * go-to-definition (also into modules loaded with use/use_as), completion
+ * (names in scope and the stdlib) and document symbols.
+ *
+ * Stdout is reserved for protocol messages: anything the compiler prints
+ * while analyzing is redirected to stderr.
+ */
+
+/**
+ * Serve LSP requests on stdin until the client sends `exit` or closes stdin
+ *
+ * @param version - Franz version reported to the client
+ * @return Exit code (0 after shutdown + exit, 1 otherwise)
+ */
+int Lsp_run(const char *version);
+
+#endif
@@ -0,0 +1,878 @@
+#include "lsp_analysis.h"
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <limits.h>
+#include <unistd.h>
+#include "../lex.h"
+#include "../parse.h"
+#include "../file.h"
+#include "../types.h"
+#include "../typeinfer.h"
+#include "../typecheck.h"
+#include "../freevar/freevar.h"
+#include "../diagnostics/diagnostic.h"
+#include "lsp_stdlib.h"
+
+//  Imports nested deeper than this are not followed (cycles are caught earlier)
+#define LSP_MAX_IMPORT_DEPTH 8
+
+//  Deepest AST path tracked for position lookups
+#define LSP_MAX_PATH 512
+
+static bool g_utf8Positions = false;
+
+void LspAnalysis_setUtf8Positions(bool utf8) {
+  g_utf8Positions = utf8;
+}
+
+// ---------------------------------------------------------------------------
+// URIs and paths
+// ---------------------------------------------------------------------------
+
+static int hexDigit(char c) {
+  if (c >= '0' && c <= '9') return c - '0';
+  if (c >= 'a' && c <= 'f') return c - 'a' + 10;
+  if (c >= 'A' && c <= 'F') return c - 'A' + 10;
+  return -1;
+}
+
+char *LspAnalysis_uriToPath(const char *uri) {
+  if (strncmp(uri, "file://", 7) != 0) return strdup(uri);
+
+  const char *p = uri + 7;
+  char *path = malloc(strlen(p) + 1);
+  int n = 0;
+
+  while (*p) {
+    if (p[0] == '%' && hexDigit(p[1]) >= 0 && hexDigit(p[2]) >= 0) {
+      path[n++] = (char) (hexDigit(p[1]) * 16 + hexDigit(p[2]));
+      p += 3;
+    } else {
+      path[n++] = *p++;
+    }
+  }
+  path[n] = '\0';
+  return path;
+}
+
+static char *pathToUri(const char *path) {
+  static const char *hex = "0123456789ABCDEF";
+  char *uri = malloc(strlen(path) * 3 + 8);
+  int n = sprintf(uri, "file://");
+
+  for (const unsigned char *p = (const unsigned char *) path; *p; p++) {
+    if ((*p >= 'a' && *p <= 'z') || (*p >= 'A' && *p <= 'Z') || (*p >= '0' && *p <= '9') ||
+        strchr("/-_.~", *p) != NULL) {
+      uri[n++] = *p;
+    } else {
+      uri[n++] = '%';
+      uri[n++] = hex[*p >> 4];
+      uri[n++] = hex[*p & 15];
+    }
+  }
+  uri[n] = '\0';
+  return uri;
+}
+
+//  Module paths are tried next to the importing file, then from the working
+// directory (where `franz` resolves them)
+char *LspAnalysis_resolveImport(const char *fromPath, const char *modulePath) {
+  char candidate[PATH_MAX];
+  char resolved[PATH_MAX];
+
+  if (modulePath[0] != '/') {
+    const char *slash = strrchr(fromPath, '/');
+    if (slash != NULL) {
+      snprintf(candidate, sizeof(candidate), "%.*s/%s", (int) (slash - fromPath), fromPath, modulePath);
+      if (access(candidate, R_OK) == 0 && realpath(candidate, resolved) != NULL) {
+        return strdup(resolved);
+      }
+    }
+  }
+
+  if (access(modulePath, R_OK) == 0 && realpath(modulePath, resolved) != NULL) {
+    return strdup(resolved);
+  }
+  return NULL;
+}
+
+// ---------------------------------------------------------------------------
+// Positions
+// ---------------------------------------------------------------------------
+
+//  Start of a 1-based line; returns its length without the line break, or -1
+static int findLine(const LspSource *src, int line, const char **start) {
+  const char *p = src->text;
+  const char *end = src->text + src->length;
+
+  for (int current = 1; current < line; current++) {
+    p = memchr(p, '\n', end - p);
+    if (p == NULL) return -1;
+    p++;
+  }
+
+  const char *lineEnd = memchr(p, '\n', end - p);
+  if (lineEnd == NULL) lineEnd = end;
+  if (lineEnd > p && lineEnd[-1] == '\r') lineEnd--;
+
+  *start = p;
+  return (int) (lineEnd - p);
+}
+
+//  1-based byte column -> LSP character
+static int toCharacter(const LspSource *src, int line, int column) {
+  const char *text;
+  int length = findLine(src, line, &text);
+  if (length < 0) return 0;
+
+  int bytes = column - 1;
+  if (bytes < 0) bytes = 0;
+  if (bytes > length) bytes = length;
+  if (g_utf8Positions) return bytes;
+
+  int units = 0;
+  for (int i = 0; i < bytes; i++) {
+    unsigned char c = text[i];
+    if ((c & 0xC0) != 0x80) units += c >= 0xF0 ? 2 : 1;  // 4-byte sequences are surrogate pairs
+  }
+  return units;
+}
+
+//  LSP character -> 1-based byte column
+static int toColumn(const LspSource *src, int line, int character) {
+  const char *text;
+  int length = findLine(src, line, &text);
+  if (length < 0) return 1;
+  if (g_utf8Positions) return (character < length ? character : length) + 1;
+
+  int units = 0;
+  int i = 0;
+  while (i < length && units < character) {
+    unsigned char c = text[i++];
+    units += c >= 0xF0 ? 2 : 1;
+    while (i < length && (text[i] & 0xC0) == 0x80) i++;
+  }
+  return i + 1;
+}
+
+static void writePosition(JsonBuffer *out, const LspSource *src, int line, int column) {
+  JsonBuffer_appendf(out, "{\"line\":%d,\"character\":%d}",
+                     line > 0 ? line - 1 : 0, toCharacter(src, line, column));
+}
+
+//  Range of a span; column 0 covers the whole line
+static void writeRange(JsonBuffer *out, const LspSource *src, int line, int column, int endLine, int endColumn) {
+  if (column <= 0) {
+    const char *text;
+    int length = findLine(src, line, &text);
+    column = 1;
+    endLine = line;
+    endColumn = (length > 0 ? length : 0) + 1;
+  }
+
+  JsonBuffer_append(out, "{\"start\":");
+  writePosition(out, src, line, column);
+  JsonBuffer_append(out, ",\"end\":");
+  writePosition(out, src, endLine, endColumn);
+  JsonBuffer_append(out, "}");
+}
+
+static void writeNodeRange(JsonBuffer *out, const LspSource *src, const AstNode *node) {
+  writeRange(out, src, node->lineNumber, node->column, node->endLine, node->endColumn);
+}
+
+// ---------------------------------------------------------------------------
+// Loading
+// ---------------------------------------------------------------------------
+
+static void loadSource(LspSource *src, const char *path, const char *uri, const char *text, int length) {
+  src->path = strdup(path);
+  src->uri = uri != NULL ? strdup(uri) : pathToUri(path);
+  src->text = malloc(length + 1);
+  memcpy(src->text, text, length);
+  src->text[length] = '\0';
+  src->length = length;
+  src->prefix = NULL;
+  src->tokens = lex(src->text, length);
+  src->ast = parseProgram(src->tokens);
+}
+
+static void freeSource(LspSource *src) {
+  if (src->ast) AstNode_free(src->ast);
+  if (src->tokens) TokenArray_free(src->tokens);
+  free(src->path);
+  free(src->uri);
+  free(src->text);
+  free(src->prefix);
+}
+
+static void addProblem(LspAnalysis *analysis, int severity, DiagnosticSpan span, const char *message) {
+  analysis->problems = realloc(analysis->problems, sizeof(LspProblem) * (analysis->problemCount + 1));
+  LspProblem *problem = &analysis->problems[analysis->problemCount++];
+  problem->severity = severity;
+  problem->line = span.line;
+  problem->column = span.column;
+  problem->endLine = span.endLine;
+  problem->endColumn = span.endColumn;
+  problem->message = strdup(message);
+}
+
+//  Move collected diagnostics into the problem list. Type errors are only hints:
+// the inference engine is much stricter than the compiler (monomorphic stdlib
+// signatures), so most of them are programs that compile and run fine.
+static void takeDiagnostics(LspAnalysis *analysis) {
+  for (int i = 0; i < Diagnostic_count(); i++) {
+    const Diagnostic *diagnostic = Diagnostic_get(i);
+    int severity = strcmp(diagnostic->label, "Type Error") == 0 ? 4 : 1;
+    addProblem(analysis, severity, diagnostic->span, diagnostic->message);
+  }
+  Diagnostic_clear();
+}
+
+//  use, use_as or use_with call (NULL otherwise)
+static const char *importKind(const AstNode *node) {
+  if (node->opcode != OP_APPLICATION || node->childCount < 2) return NULL;
+  const AstNode *callee = node->children[0];
+  if (callee->opcode != OP_IDENTIFIER) return NULL;
+  if (strcmp(callee->val, "use") == 0 || strcmp(callee->val, "use_as") == 0 ||
+      strcmp(callee->val, "use_with") == 0) {
+    return callee->val;
+  }
+  return NULL;
+}
+
+static char *joinPrefix(const char *outer, const char *inner) {
+  if (outer == NULL) return inner != NULL ? strdup(inner) : NULL;
+  if (inner == NULL) return strdup(outer);
+  char *prefix = malloc(strlen(outer) + strlen(inner) + 2);
+  sprintf(prefix, "%s_%s", outer, inner);
+  return prefix;
+}
+
+static bool alreadyLoaded(LspAnalysis *analysis, const char *path, const char *prefix) {
+  for (int i = 0; i < analysis->moduleCount; i++) {
+    LspSource *m = &analysis->modules[i];
+    bool samePrefix = (m->prefix == NULL && prefix == NULL) ||
+                      (m->prefix != NULL && prefix != NULL && strcmp(m->prefix, prefix) == 0);
+    if (samePrefix && strcmp(m->path, path) == 0) return true;
+  }
+  return false;
+}
+
+static void loadImports(LspAnalysis *analysis, const char *fromPath, AstNode *node, const char *prefix, int depth);
+
+//  Load one imported file (and, recursively, what it imports)
+static void loadModule(LspAnalysis *analysis, const char *fromPath, const char *modulePath, const char *prefix,
+                       int depth) {
+  char *path = LspAnalysis_resolveImport(fromPath, modulePath);
+  char *code = path != NULL ? readFile(path, false) : NULL;
+
+  if (code == NULL) {
+    analysis->importsComplete = false;
+  } else if (depth < LSP_MAX_IMPORT_DEPTH && !alreadyLoaded(analysis, path, prefix)) {
+    analysis->modules = realloc(analysis->modules, sizeof(LspSource) * (analysis->moduleCount + 1));
+    LspSource *module = &analysis->modules[analysis->moduleCount++];
+    loadSource(module, path, NULL, code, strlen(code));
+    module->prefix = prefix != NULL ? strdup(prefix) : NULL;
+
+    // Errors inside modules are reported when the module itself is open
+    if (Diagnostic_count() > 0) analysis->importsComplete = false;
+    Diagnostic_clear();
+
+    // analysis->modules is reallocated by nested imports: module is not used after this
+    loadImports(analysis, path, module->ast, prefix, depth + 1);
+  }
+
+  free(code);
+  free(path);
+}
+
+//  Load the modules imported anywhere under node. A module imported with a plain
+// `use` inside a use_as module shares its namespace.
+static void loadImports(LspAnalysis *analysis, const char *fromPath, AstNode *node, const char *prefix, int depth) {
+  if (node == NULL) return;
+
+  const char *kind = importKind(node);
+  if (kind != NULL && strcmp(kind, "use_with") == 0) {
+    // (use_with (list "io") "a.franz" "b.franz"): every string argument is a path
+    for (int i = 1; i < node->childCount; i++) {
+      if (node->children[i]->opcode == OP_STRING) {
+        loadModule(analysis, fromPath, node->children[i]->val, prefix, depth);
+      }
+    }
+  } else if (kind != NULL && node->children[1]->opcode == OP_STRING) {
+    char *modulePrefix = NULL;
+    if (strcmp(kind, "use_as") == 0 && node->childCount >= 3 && node->children[2]->opcode == OP_STRING) {
+      modulePrefix = joinPrefix(prefix, node->children[2]->val);
+    } else {
+      modulePrefix = joinPrefix(prefix, NULL);
+    }
+    loadModule(analysis, fromPath, node->children[1]->val, modulePrefix, depth);
+    free(modulePrefix);
+  }
+
+  for (int i = 0; i < node->childCount; i++) {
+    loadImports(analysis, fromPath, node->children[i], prefix, depth);
+  }
+}
+
+// ---------------------------------------------------------------------------
+// Scopes and bindings
+// ---------------------------------------------------------------------------
+
+//  A definition found for a name
+typedef struct LspBinding {
+  LspSource *source;   // NULL when nothing in the program defines the name
+  AstNode *name;       // Parameter or assignment target
+  AstNode *value;      // Assigned value (NULL for parameters)
+  AstNode *function;   // Function declaring the parameter
+  int paramIndex;
+} LspBinding;
+
+//  Parameters are the leading identifiers of a function (as in FreeVar_analyze)
+static int paramCount(const AstNode *fn) {
+  int count = 0;
+  while (count < fn->childCount && fn->children[count]->opcode == OP_IDENTIFIER) count++;
+  return count;
+}
+
+//  First assignment to name, not looking into nested functions
+static AstNode *findAssignment(AstNode *node, const char *name) {
+  if (node == NULL) return NULL;
+
+  if (node->opcode == OP_ASSIGNMENT && node->childCount >= 2 &&
+      node->children[0]->val != NULL && strcmp(node->children[0]->val, name) == 0) {
+    return node;
+  }
+  if (node->opcode == OP_FUNCTION) return NULL;
+
+  for (int i = 0; i < node->childCount; i++) {
+    AstNode *found = findAssignment(node->children[i], name);
+    if (found) return found;
+  }
+  return NULL;
+}
+
+static bool bindAssignment(LspBinding *binding, LspSource *source, AstNode *assignment) {
+  if (assignment == NULL) return false;
+  binding->source = source;
+  binding->name = assignment->children[0];
+  binding->value = assignment->children[1];
+  return true;
+}
+
+//  Name of a module member as written in the importing file: math_square -> square
+static const char *memberName(const LspSource *module, const char *name) {
+  if (module->prefix == NULL) return name;
+  size_t n = strlen(module->prefix);
+  if (strncmp(name, module->prefix, n) == 0 && (name[n] == '_' || name[n] == '.')) return name + n + 1;
+  return NULL;
+}
+
+//  Resolve a name used under path[0..depth): enclosing functions innermost first,
+// then the document's top level, then imported modules
+static LspBinding resolve(LspAnalysis *analysis, AstNode **path, int depth, const char *name) {
+  LspBinding binding = { NULL, NULL, NULL, NULL, -1 };
+
+  for (int i = depth - 1; i >= 0; i--) {
+    AstNode *fn = path[i];
+    if (fn->opcode != OP_FUNCTION) continue;
+
+    // A free variable of this function is bound further out
+    FreeVar_analyze(fn);
+    if (FreeVar_is_bound((char *) name, fn->freeVars, fn->freeVarsCount)) continue;
+
+    int params = paramCount(fn);
+    for (int p = 0; p < params; p++) {
+      if (strcmp(fn->children[p]->val, name) == 0) {
+        binding.source = &analysis->document;
+        binding.name = fn->children[p];
+        binding.function = fn;
+        binding.paramIndex = p;
+        return binding;
+      }
+    }
+    for (int b = params; b < fn->childCount; b++) {
+      if (bindAssignment(&binding, &analysis->document, findAssignment(fn->children[b], name))) return binding;
+    }
+  }
+
+  if (bindAssignment(&binding, &analysis->document, findAssignment(analysis->document.ast, name))) return binding;
+
+  for (int i = 0; i < analysis->moduleCount; i++) {
+    LspSource *module = &analysis->modules[i];
+    const char *member = memberName(module, name);
+    if (member != NULL && bindAssignment(&binding, module, findAssignment(module->ast, member))) return binding;
+  }
+
+  return binding;
+}
+
+// ---------------------------------------------------------------------------
+// Position lookup
+// ---------------------------------------------------------------------------
+
+static bool nodeContains(const AstNode *node, int line, int column) {
+  if (node->column <= 0) return false;
+  if (line < node->lineNumber || (line == node->lineNumber && column < node->column)) return false;
+  if (line > node->endLine || (line == node->endLine && column >= node->endColumn)) return false;
+  return true;
+}
+
+//  Fill path with the nodes from root down to the innermost node at the position;
+// returns the path length
+static int nodePathAt(AstNode *node, int line, int column, AstNode **path, int depth) {
+  if (node == NULL || depth >= LSP_MAX_PATH) return depth;
+  path[depth++] = node;
+
+  for (int i = 0; i < node->childCount; i++) {
+    AstNode *child = node->children[i];
+    if (nodeContains(child, line, column)) {
+      return nodePathAt(child, line, column, path, depth);
+    }
+    // Nodes built without a span may still contain the position
+    if (child->column <= 0) {
+      int found = nodePathAt(child, line, column, path, depth);
+      if (found > depth + 1) return found;
+    }
+  }
+  return depth;
+}
+
+//  Path to the identifier under the cursor, also accepting a cursor just past it
+static int namePathAt(LspAnalysis *analysis, int line, int character, AstNode **path) {
+  LspSource *doc = &analysis->document;
+  int column = toColumn(doc, line + 1, character);
+
+  for (int attempt = 0; attempt < 2; attempt++) {
+    int depth = nodePathAt(doc->ast, line + 1, column - attempt, path, 0);
+    AstNode *node = path[depth - 1];
+    if (depth > 1 && (node->opcode == OP_IDENTIFIER || node->opcode == OP_QUALIFIED)) return depth;
+  }
+  return nodePathAt(doc->ast, line + 1, column, path, 0);
+}
+
+static bool isDeclaration(AstNode *node, AstNode *parent) {
+  if (parent == NULL) return false;
+  if (parent->opcode == OP_ASSIGNMENT) return parent->children[0] == node;
+  if (parent->opcode == OP_FUNCTION) {
+    int params = paramCount(parent);
+    for (int p = 0; p < params; p++) {
+      if (parent->children[p] == node) return true;
+    }
+  }
+  return false;
+}
+
+//  Resolve the identifier at the end of path (qualified names go straight to modules)
+static LspBinding resolveAt(LspAnalysis *analysis, AstNode **path, int depth) {
+  AstNode *node = path[depth - 1];
+  AstNode *parent = depth > 1 ? path[depth - 2] : NULL;
+
+  if (node->opcode == OP_IDENTIFIER && isDeclaration(node, parent)) {
+    if (parent->opcode == OP_FUNCTION) {
+      int index = 0;
+      while (parent->children[index] != node) index++;
+      LspBinding binding = { &analysis->document, node, NULL, parent, index };
+      return binding;
+    }
+    // Reassignments (mut) point at the first assignment in scope
+    return resolve(analysis, path, depth - 2, node->val);
+  }
+
+  if (node->opcode == OP_QUALIFIED) {
+    LspBinding binding = { NULL, NULL, NULL, NULL, -1 };
+    for (int i = 0; i < analysis->moduleCount; i++) {
+      LspSource *module = &analysis->modules[i];
+      const char *member = memberName(module, node->val);
+      if (member != NULL && bindAssignment(&binding, module, findAssignment(module->ast, member))) break;
+    }
+    return binding;
+  }
+
+  return resolve(analysis, path, depth - 1, node->val);
+}
+
+// ---------------------------------------------------------------------------
+// Types
+// ---------------------------------------------------------------------------
+
+//  Inference context with the stdlib signatures and the imported modules' names
+static InferContext *newInferContext(LspAnalysis *analysis) {
+  InferContext *ctx = InferContext_new();
+  typecheck_add_stdlib(ctx->env);
+  ctx->collect = 1;
+
+  // Nested imports were loaded after their importer: infer them first
+  for (int i = analysis->moduleCount - 1; i >= 0; i--) {
+    LspSource *module = &analysis->modules[i];
+    if (module->ast == NULL) continue;
+
+    TypeEnv *scratch = TypeEnv_new(ctx->env);
+    ctx->env = scratch;
+    (void) infer(module->ast, ctx, 1);
+    ctx->env = scratch->parent;
+
+    for (TypeEnvItem *item = scratch->head; item != NULL; item = item->next) {
+      if (module->prefix == NULL) {
+        TypeEnv_set(ctx->env, item->name, Substitution_apply(ctx, item->type));
+      } else {
+        char *name = joinPrefix(module->prefix, item->name);
+        TypeEnv_set(ctx->env, name, Substitution_apply(ctx, item->type));
+        free(name);
+      }
+    }
+    TypeEnv_free(scratch);
+  }
+
+  // Problems inside modules are not the document's
+  Diagnostic_clear();
+  ctx->error_count = 0;
+  return ctx;
+}
+
+// ---------------------------------------------------------------------------
+// Undefined names
+// ---------------------------------------------------------------------------
+
+static void checkNames(LspAnalysis *analysis, AstNode *node, AstNode **path, int depth) {
+  if (node == NULL || depth >= LSP_MAX_PATH) return;
+  path[depth] = node;
+
+  if (node->opcode == OP_IDENTIFIER && !isDeclaration(node, depth > 0 ? path[depth - 1] : NULL)) {
+    LspBinding binding = resolve(analysis, path, depth, node->val);
+    if (binding.source == NULL && !LspStdlib_has(node->val)) {
+      DiagnosticSpan span = Diagnostic_spanOfNode(node);
+      char message[512];
+      snprintf(message, sizeof(message), "Undefined variable '%s'", node->val);
+      addProblem(analysis, 1, span, message);
+    }
+  }
+
+  for (int i = 0; i < node->childCount; i++) {
+    checkNames(analysis, node->children[i], path, depth + 1);
+  }
+}
+
+// ---------------------------------------------------------------------------
+// Analysis
+// ---------------------------------------------------------------------------
+
+LspAnalysis *LspAnalysis_new(const char *uri, const char *text) {
+  LspAnalysis *analysis = calloc(1, sizeof(LspAnalysis));
+  analysis->importsComplete = true;
+
+  char *path = LspAnalysis_uriToPath(uri);
+  Diagnostic_clear();
+  loadSource(&analysis->document, path, uri, text, strlen(text));
+  free(path);
+
+  bool syntaxErrors = Diagnostic_count() > 0;
+  takeDiagnostics(analysis);
+
+  AstNode *ast = analysis->document.ast;
+  if (ast == NULL) return analysis;
+
+  loadImports(analysis, analysis->document.path, ast, NULL, 0);
+
+  // Names and types are only checked in a program that parses
+  if (syntaxErrors) return analysis;
+
+  if (analysis->importsComplete) {
+    AstNode *path[LSP_MAX_PATH];
+    checkNames(analysis, ast, path, 0);
+  }
+
+  InferContext *ctx = newInferContext(analysis);
+  (void) infer(ast, ctx, 1);
+  takeDiagnostics(analysis);
+  InferContext_free(ctx);
+
+  return analysis;
+}
+
+void LspAnalysis_free(LspAnalysis *analysis) {
+  if (analysis == NULL) return;
+
+  freeSource(&analysis->document);
+  for (int i = 0; i < analysis->moduleCount; i++) {
+    freeSource(&analysis->modules[i]);
+  }
+  free(analysis->modules);
+
+  for (int i = 0; i < analysis->problemCount; i++) {
+    free(analysis->problems[i].message);
+  }
+  free(analysis->problems);
+  free(analysis);
+}
+
+// ---------------------------------------------------------------------------
+// Queries
+// ---------------------------------------------------------------------------
+
+void LspAnalysis_writeDiagnostics(LspAnalysis *analysis, JsonBuffer *out) {
+  JsonBuffer_append(out, "[");
+  for (int i = 0; i < analysis->problemCount; i++) {
+    LspProblem *problem = &analysis->problems[i];
+    if (i > 0) JsonBuffer_append(out, ",");
+    JsonBuffer_append(out, "{\"range\":");
+    writeRange(out, &analysis->document, problem->line, problem->column, problem->endLine, problem->endColumn);
+    JsonBuffer_appendf(out, ",\"severity\":%d,\"source\":\"franz\",\"message\":", problem->severity);
+    JsonBuffer_appendString(out, problem->message);
+    JsonBuffer_append(out, "}");
+  }
+  JsonBuffer_append(out, "]");
+}
+
+void LspAnalysis_writeHover(LspAnalysis *analysis, int line, int character, JsonBuffer *out) {
+  if (analysis->document.ast == NULL) {
+    JsonBuffer_append(out, "null");
+    return;
+  }
+
+  AstNode *path[LSP_MAX_PATH];
+  int depth = namePathAt(analysis, line, character, path);
+  AstNode *node = path[depth - 1];
+  AstNode *parent = depth > 1 ? path[depth - 2] : NULL;
+
+  // What to infer: the node itself, the value of an assignment, or the function of a parameter
+  AstNode *watch = node;
+  const char *name = NULL;
+  LspBinding binding = { NULL, NULL, NULL, NULL, -1 };
+
+  switch (node->opcode) {
+    case OP_IDENTIFIER:
+    case OP_QUALIFIED:
+      name = node->val;
+      binding = resolveAt(analysis, path, depth);
+      if (binding.function != NULL) {
+        watch = binding.function;
+      } else if (isDeclaration(node, parent)) {
+        watch = parent->children[1];
+      }
+      break;
+    case OP_INT:
+    case OP_FLOAT:
+    case OP_STRING:
+    case OP_LIST:
+    case OP_APPLICATION:
+    case OP_FUNCTION:
+      break;
+    default:
+      JsonBuffer_append(out, "null");
+      return;
+  }
+
+  InferContext *ctx = newInferContext(analysis);
+  ctx->watch = watch;
+  (void) infer(analysis->document.ast, ctx, 1);
+  Diagnostic_clear();
+
+  Type *type = ctx->watchType != NULL ? Substitution_apply(ctx, ctx->watchType) : NULL;
+  if (type != NULL && binding.function != NULL && watch == binding.function) {
+    type = type->kind == TYPE_FUNCTION && binding.paramIndex < type->data.func.param_count
+      ? Substitution_apply(ctx, type->data.func.param_types[binding.paramIndex])
+      : NULL;
+  }
+  char *typeName = type != NULL ? Type_to_string(type) : strdup("any");
+
+  JsonBuffer text;
+  JsonBuffer_init(&text);
+  JsonBuffer_append(&text, "```franz\n");
+  if (name != NULL) JsonBuffer_appendf(&text, "%s : ", name);
+  JsonBuffer_appendf(&text, "%s\n```", typeName);
+  if (name != NULL && binding.source == NULL && LspStdlib_has(name)) {
+    JsonBuffer_append(&text, "\n\nBuilt-in");
+  } else if (binding.source != NULL && binding.source != &analysis->document) {
+    JsonBuffer_appendf(&text, "\n\nImported from `%s`", binding.source->path);
+  }
+
+  JsonBuffer_append(out, "{\"contents\":{\"kind\":\"markdown\",\"value\":");
+  JsonBuffer_appendString(out, text.data);
+  JsonBuffer_append(out, "},\"range\":");
+  writeNodeRange(out, &analysis->document, node);
+  JsonBuffer_append(out, "}");
+
+  JsonBuffer_free(&text);
+  free(typeName);
+  InferContext_free(ctx);
+}
+
+void LspAnalysis_writeDefinition(LspAnalysis *analysis, int line, int character, JsonBuffer *out) {
+  if (analysis->document.ast == NULL) {
+    JsonBuffer_append(out, "null");
+    return;
+  }
+
+  AstNode *path[LSP_MAX_PATH];
+  int depth = namePathAt(analysis, line, character, path);
+  AstNode *node = path[depth - 1];
+
+  if (depth < 2 || (node->opcode != OP_IDENTIFIER && node->opcode != OP_QUALIFIED)) {
+    JsonBuffer_append(out, "null");
+    return;
+  }
+
+  LspBinding binding = resolveAt(analysis, path, depth);
+  if (binding.source == NULL) {
+    JsonBuffer_append(out, "null");
+    return;
+  }
+
+  JsonBuffer_append(out, "{\"uri\":");
+  JsonBuffer_appendString(out, binding.source->uri);
+  JsonBuffer_append(out, ",\"range\":");
+  writeNodeRange(out, binding.source, binding.name);
+  JsonBuffer_append(out, "}");
+}
+
+//  Completion candidates, without duplicates
+typedef struct LspNames {
+  char **names;
+  int count;
+} LspNames;
+
+static bool isIdentifierChar(char c) {
+  return (c >= 'a' && c <= 'z') || (c >= 'A' && c <= 'Z') || (c >= '0' && c <= '9') ||
+         c == '_' || c == '-' || c == '!' || c == '?';
+}
+
+//  Add a completion item if it matches the typed prefix and was not offered yet
+static void offer(JsonBuffer *out, LspNames *seen, const char *prefix, const char *name, int kind, const char *detail) {
+  if (strncmp(name, prefix, strlen(prefix)) != 0) return;
+  for (int i = 0; i < seen->count; i++) {
+    if (strcmp(seen->names[i], name) == 0) return;
+  }
+  seen->names = realloc(seen->names, sizeof(char *) * (seen->count + 1));
+  seen->names[seen->count++] = strdup(name);
+
+  if (seen->count > 1) JsonBuffer_append(out, ",");
+  JsonBuffer_append(out, "{\"label\":");
+  JsonBuffer_appendString(out, name);
+  JsonBuffer_appendf(out, ",\"kind\":%d", kind);
+  if (detail != NULL) {
+    JsonBuffer_append(out, ",\"detail\":");
+    JsonBuffer_appendString(out, detail);
+  }
+  JsonBuffer_append(out, "}");
+}
+
+//  LSP CompletionItemKind
+#define LSP_KIND_FUNCTION 3
+#define LSP_KIND_VARIABLE 6
+
+static void offerAssignments(JsonBuffer *out, LspNames *seen, const char *prefix, AstNode *node,
+                             const char *namespace, const char *detail) {
+  if (node == NULL || node->opcode == OP_FUNCTION) return;
+
+  if (node->opcode == OP_ASSIGNMENT && node->childCount >= 2 && node->children[0]->val != NULL) {
+    int kind = node->children[1]->opcode == OP_FUNCTION ? LSP_KIND_FUNCTION : LSP_KIND_VARIABLE;
+    char *name = joinPrefix(namespace, node->children[0]->val);
+    offer(out, seen, prefix, name, kind, detail);
+    free(name);
+  }
+
+  for (int i = 0; i < node->childCount; i++) {
+    offerAssignments(out, seen, prefix, node->children[i], namespace, detail);
+  }
+}
+
+void LspAnalysis_writeCompletion(LspAnalysis *analysis, int line, int character, JsonBuffer *out) {
+  LspSource *doc = &analysis->document;
+  int column = toColumn(doc, line + 1, character);
+
+  // The partial name before the cursor
+  const char *text;
+  int length = findLine(doc, line + 1, &text);
+  int end = length >= 0 && column - 1 <= length ? column - 1 : 0;
+  int start = end;
+  while (start > 0 && isIdentifierChar(text[start - 1])) start--;
+  char *prefix = strndup(length >= 0 ? text + start : "", end - start);
+
+  LspNames seen = { NULL, 0 };
+  JsonBuffer_append(out, "[");
+
+  // Names in scope: enclosing functions, the top level, imported modules
+  if (doc->ast != NULL) {
+    AstNode *path[LSP_MAX_PATH];
+    int depth = nodePathAt(doc->ast, line + 1, column > 1 ? column - 1 : column, path, 0);
+    for (int i = depth - 1; i >= 0; i--) {
+      AstNode *fn = path[i];
+      if (fn->opcode != OP_FUNCTION) continue;
+      int params = paramCount(fn);
+      for (int p = 0; p < params; p++) offer(out, &seen, prefix, fn->children[p]->val, LSP_KIND_VARIABLE, NULL);
+      for (int b = params; b < fn->childCount; b++) offerAssignments(out, &seen, prefix, fn->children[b], NULL, NULL);
+    }
+    offerAssignments(out, &seen, prefix, doc->ast, NULL, NULL);
+  }
+
+  for (int i = 0; i < analysis->moduleCount; i++) {
+    LspSource *module = &analysis->modules[i];
+    const char *base = strrchr(module->path, '/');
+    offerAssignments(out, &seen, prefix, module->ast, module->prefix, base != NULL ? base + 1 : module->path);
+  }
+
+  // Stdlib: the names newGlobal() binds, typed where typecheck.c knows the signature
+  TypeEnv *signatures = TypeEnv_new(NULL);
+  typecheck_add_stdlib(signatures);
+  for (int i = 0; i < LspStdlib_count(); i++) {
+    const char *name = LspStdlib_name(i);
+    int kind = LspStdlib_isFunction(i) ? LSP_KIND_FUNCTION : LSP_KIND_VARIABLE;
+
+    Type *signature = TypeEnv_get(signatures, (char *) name);
+    char *detail = signature != NULL ? Type_to_string(signature) : strdup("built-in");
+    offer(out, &seen, prefix, name, kind, detail);
+    free(detail);
+  }
+  TypeEnv_free(signatures);
+
+  JsonBuffer_append(out, "]");
+
+  for (int i = 0; i < seen.count; i++) free(seen.names[i]);
+  free(seen.names);
+  free(prefix);
+}
+
+//  LSP SymbolKind
+#define LSP_SYMBOL_FUNCTION 12
+#define LSP_SYMBOL_VARIABLE 13
+
+//  Assignments under node become symbols; a function's assignments are its children
+static void writeSymbolList(JsonBuffer *out, LspSource *src, AstNode *node, bool *first) {
+  if (node == NULL) return;
+
+  if (node->opcode == OP_ASSIGNMENT && node->childCount >= 2 && node->children[0]->val != NULL) {
+    AstNode *name = node->children[0];
+    AstNode *value = node->children[1];
+    if (node->column <= 0 || name->column <= 0) return;
+
+    if (!*first) JsonBuffer_append(out, ",");
+    *first = false;
+
+    JsonBuffer_append(out, "{\"name\":");
+    JsonBuffer_appendString(out, name->val);
+    JsonBuffer_appendf(out, ",\"kind\":%d,\"range\":",
+                       value->opcode == OP_FUNCTION ? LSP_SYMBOL_FUNCTION : LSP_SYMBOL_VARIABLE);
+    writeNodeRange(out, src, node);
+    JsonBuffer_append(out, ",\"selectionRange\":");
+    writeNodeRange(out, src, name);
+    JsonBuffer_append(out, ",\"children\":[");
+    bool firstChild = true;
+    writeSymbolList(out, src, value, &firstChild);
+    JsonBuffer_append(out, "]}");
+    return;
+  }
+
+  for (int i = 0; i < node->childCount; i++) {
+    writeSymbolList(out, src, node->children[i], first);
+  }
+}
+
+void LspAnalysis_writeSymbols(LspAnalysis *analysis, JsonBuffer *out) {
+  bool first = true;
+  JsonBuffer_append(out, "[");
+  writeSymbolList(out, &analysis->document, analysis->document.ast, &first);
+  JsonBuffer_append(out, "]");
+}
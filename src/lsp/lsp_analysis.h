@@ -0,0 +1,129 @@
+#ifndef LSP_ANALYSIS_H
+#define LSP_ANALYSIS_H
+
+#include <stdbool.h>
+#include "../tokens.h"
+#include "../ast.h"
+#include "../json/json.h"
+
+/*
+ * Franz LSP - document analysis
+ *
+ * One LspAnalysis holds a parsed document plus the modules it imports with
+ * use/use_as/use_with. It is rebuilt whenever the document changes; queries
+ * write their LSP result straight into a JsonBuffer.
+ *
+ * Positions: LSP lines are 0-based and characters count UTF-16 code units
+ * (or bytes when the client accepts the utf-8 position encoding). Franz
+ * tokens use 1-based lines and 1-based byte columns; the conversion happens
+ * here, against the text of the file the position belongs to.
+ */
+
+//  A parsed file: the open document or an imported module
+typedef struct LspSource {
+  char *path;
+  char *uri;
+  char *text;
+  int length;
+  TokenArray *tokens;
+  AstNode *ast;
+  char *prefix;      // use_as namespace ("math" for math_square), NULL for use
+} LspSource;
+
+//  A problem found in the document
+typedef struct LspProblem {
+  int severity;      // LSP DiagnosticSeverity: 1 error, 4 hint (type inference)
+  int line;
+  int column;        // 0 when only the line is known
+  int endLine;
+  int endColumn;
+  char *message;
+} LspProblem;
+
+typedef struct LspAnalysis {
+  LspSource document;
+  LspSource *modules;
+  int moduleCount;
+  bool importsComplete;  // every imported module was found and parsed
+  LspProblem *problems;
+  int problemCount;
+} LspAnalysis;
+
+/**
+ * Choose how LSP characters are counted.
+ * @param utf8 true for bytes (utf-8 position encoding), false for UTF-16 code units
+ */
+void LspAnalysis_setUtf8Positions(bool utf8);
+
+/**
+ * Convert a file:// URI to a filesystem path (percent-decoded).
+ * @param uri Document URI
+ * @return malloc'd path (the URI itself when it is not a file URI)
+ */
+char *LspAnalysis_uriToPath(const char *uri);
+
+/**
+ * Find an imported module: next to the importing file, then from the working directory.
+ * @param fromPath Path of the importing file
+ * @param modulePath Path as written in use/use_as/use_with
+ * @return malloc'd absolute path, or NULL when the module does not exist
+ */
+char *LspAnalysis_resolveImport(const char *fromPath, const char *modulePath);
+
+/**
+ * Parse a document, load its imports and collect syntax, name and type problems.
+ * @param uri Document URI
+ * @param text Document text (copied)
+ * @return Analysis owned by the caller (LspAnalysis_free)
+ */
+LspAnalysis *LspAnalysis_new(const char *uri, const char *text);
+
+/**
+ * Free an analysis and everything it loaded.
+ * @param analysis Analysis to free (may be NULL)
+ */
+void LspAnalysis_free(LspAnalysis *analysis);
+
+/**
+ * Write the Diagnostic[] array for textDocument/publishDiagnostics.
+ * @param analysis Analyzed document
+ * @param out Output buffer
+ */
+void LspAnalysis_writeDiagnostics(LspAnalysis *analysis, JsonBuffer *out);
+
+/**
+ * Write the textDocument/hover result (Hover or null).
+ * @param analysis Analyzed document
+ * @param line 0-based LSP line
+ * @param character LSP character offset
+ * @param out Output buffer
+ */
+void LspAnalysis_writeHover(LspAnalysis *analysis, int line, int character, JsonBuffer *out);
+
+/**
+ * Write the textDocument/definition result (Location or null).
+ * @param analysis Analyzed document
+ * @param line 0-based LSP line
+ * @param character LSP character offset
+ * @param out Output buffer
+ */
+void LspAnalysis_writeDefinition(LspAnalysis *analysis, int line, int character, JsonBuffer *out);
+
+/**
+ * Write the textDocument/completion result (CompletionItem[]).
+ * Offers names in scope at the position and the stdlib names from newGlobal().
+ * @param analysis Analyzed document
+ * @param line 0-based LSP line
+ * @param character LSP character offset
+ * @param out Output buffer
+ */
+void LspAnalysis_writeCompletion(LspAnalysis *analysis, int line, int character, JsonBuffer *out);
+
+/**
+ * Write the textDocument/documentSymbol result (DocumentSymbol[]).
+ * @param analysis Analyzed document
+ * @param out Output buffer
+ */
+void LspAnalysis_writeSymbols(LspAnalysis *analysis, JsonBuffer *out);
+
+#endif
@@ -0,0 +1,51 @@
+#include "lsp_stdlib.h"
+#include <string.h>
+#include "../stdlib.h"
+#include "../scope.h"
+#include "../generic.h"
+#include "../freevar/freevar.h"
+
+static Scope *g_stdlib = NULL;
+
+//  Names the code generator compiles inline: they have no runtime binding
+static const char *g_intrinsics[] = {
+  "break", "cond", "continue", "else", "unless", "when", "while"
+};
+#define INTRINSIC_COUNT ((int) (sizeof(g_intrinsics) / sizeof(g_intrinsics[0])))
+
+//  The compiler's global scope, built once
+static Scope *stdlibScope(void) {
+  if (g_stdlib == NULL) {
+    char *argv[] = { "franz", NULL };
+    g_stdlib = newGlobal(1, argv);
+  }
+  return g_stdlib;
+}
+
+int LspStdlib_count(void) {
+  return stdlibScope()->count + INTRINSIC_COUNT;
+}
+
+const char *LspStdlib_name(int index) {
+  Scope *scope = stdlibScope();
+  return index < scope->count ? scope->bindings[index].name : g_intrinsics[index - scope->count];
+}
+
+bool LspStdlib_isFunction(int index) {
+  Scope *scope = stdlibScope();
+  if (index >= scope->count) return true;
+
+  Generic *value = scope->bindings[index].value;
+  return value != NULL && (value->type == TYPE_NATIVEFUNCTION || value->type == TYPE_FUNCTION);
+}
+
+bool LspStdlib_has(const char *name) {
+  Scope *scope = stdlibScope();
+  for (int i = 0; i < scope->count; i++) {
+    if (strcmp(scope->bindings[i].name, name) == 0) return true;
+  }
+  for (int i = 0; i < INTRINSIC_COUNT; i++) {
+    if (strcmp(g_intrinsics[i], name) == 0) return true;
+  }
+  return FreeVar_is_global_builtin(name);
+}
@@ -0,0 +1,42 @@
+#ifndef LSP_STDLIB_H
+#define LSP_STDLIB_H
+
+#include <stdbool.h>
+
+/*
+ * Franz LSP - stdlib names
+ *
+ * The names are read from the compiler's own global scope (newGlobal), so the
+ * editor offers exactly what a program can call, plus the few forms the code
+ * generator compiles inline (cond, nth, ...). Kept apart from the analysis
+ * because generic.h and types.h cannot share a translation unit.
+ */
+
+/**
+ * Number of stdlib names (global bindings, then inline forms).
+ * @return Name count
+ */
+int LspStdlib_count(void);
+
+/**
+ * Name of a stdlib entry.
+ * @param index Entry index (0 <= index < LspStdlib_count())
+ * @return Static name
+ */
+const char *LspStdlib_name(int index);
+
+/**
+ * Whether a stdlib entry is callable.
+ * @param index Entry index
+ * @return true for functions and inline forms
+ */
+bool LspStdlib_isFunction(int index);
+
+/**
+ * Whether a name is provided by the runtime rather than the program.
+ * @param name Identifier
+ * @return true for stdlib names and compiler builtins
+ */
+bool LspStdlib_has(const char *name);
+
+#endif
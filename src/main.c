@@ -18,9 +18,35 @@
 #include "error-handling/error_handler.h"
 // Type checking (optional pre-run assertions)
 #include "assert_types.h"
+#include "repl/repl.h"
+#include "lsp/lsp.h"
+#include "fmt/fmt.h"
+#include "lint/lint.h"
+#include "diagnostics/diagnostic.h"
+#include "diagnostics/error_codes.h"
+#include "test-runner/test_runner.h"
+#include "coverage/coverage.h"
 
 #define FRANZ_VERSION ("v0.0.4")
 
+//  Derive build/emit output name: dir/app.franz -> app (+ extension, e.g. app.ll)
+static char *buildOutputName(const char *sourcePath, const char *extension) {
+  const char *base = strrchr(sourcePath, '/');
+  base = base ? base + 1 : sourcePath;
+
+  char *name = malloc(strlen(base) + strlen(extension) + 5);
+  strcpy(name, base);
+  char *ext = strrchr(name, '.');
+  if (ext != NULL && ext != name && strcmp(ext, ".franz") == 0) {
+    *ext = '\0';
+  } else if (extension[0] == '\0') {
+    // never overwrite a source file that has no .franz extension
+    strcat(name, ".out");
+  }
+  strcat(name, extension);
+  return name;
+}
+
 int main(int argc, char *argv[]) {
   // Initialize error handling system
   ErrorState_init();
@@ -41,25 +67,223 @@ int main(int argc, char *argv[]) {
     }
   }
 
-  // parse flags: -v, -d, --assert-types, --scoping, --no-tco
+  //  `franz --explain [F0101]` prints the long-form explanation of an error code
+  if (argc > 1 && strcmp(argv[1], "--explain") == 0) {
+    if (argc > 3) {
+      fprintf(stderr, "Error: '--explain' takes one error code, e.g. 'franz --explain F0202'.\n");
+      return 1;
+    }
+    return ErrorCode_explainMain(argc > 2 ? argv[2] : NULL);
+  }
+  if (argc > 1 && strncmp(argv[1], "--explain=", 10) == 0) {
+    return ErrorCode_explainMain(argv[1] + 10);
+  }
+
+  //  `franz test [--jobs=N] [--timeout=SECONDS] [paths...]` checks programs against expected output
+  if (argc > 1 && strcmp(argv[1], "test") == 0) {
+    return TestRunner_run(argc - 2, argv + 2);
+  }
+
+  //  `franz coverage report [--lcov] [-o FILE]` summarizes the data written by --coverage runs
+  if (argc > 1 && strcmp(argv[1], "coverage") == 0) {
+    return Coverage_main(argc - 2, argv + 2);
+  }
+
+  //  `franz lsp` serves editors over stdio (Language Server Protocol)
+  if (argc > 1 && strcmp(argv[1], "lsp") == 0) {
+    for (int i = 2; i < argc; i++) {
+      if (strcmp(argv[i], "--stdio") != 0) {
+        fprintf(stderr, "Error: 'franz lsp' only supports --stdio, got '%s'.\n", argv[i]);
+        return 1;
+      }
+    }
+    return Lsp_run(FRANZ_VERSION);
+  }
+
+  //  `franz fmt [--check] [paths...]` rewrites source in the canonical layout
+  if (argc > 1 && strcmp(argv[1], "fmt") == 0) {
+    return Fmt_run(argc - 2, argv + 2);
+  }
+
+  //  `franz lint [--enable=...] [--disable=...] [paths...]` reports likely mistakes
+  if (argc > 1 && strcmp(argv[1], "lint") == 0) {
+    return Lint_run(argc - 2, argv + 2);
+  }
+
+  // parse flags: -v, -d, -g, --coverage, --trace, --profile, --interpret, --bytecode, -o, -O<n>, --emit, --target, --sysroot, --jit, --assert-types, --scoping, --no-tco, --no-cache, --message-format, --dump-tokens, --dump-ast
+  RunOptions options;
+  RunOptions_init(&options);
   bool debug = false;
   bool assert_types = false;
-  bool enable_tco = true;  // TCO: Enabled by default (functional language standard), use --no-tco to disable
   int first_arg_index = 1;
 
-  for (int i = 1; i < argc; i++) {
+  //  `franz run <file>` executes in-process with the JIT (no C compiler needed)
+  if (argc > 1 && strcmp(argv[1], "run") == 0) {
+    options.jit = true;
+    first_arg_index++;
+  }
+
+  //  `franz build <file> -o <exe>` compiles ahead of time without running
+  bool buildMode = false;
+  if (argc > 1 && strcmp(argv[1], "build") == 0) {
+    buildMode = true;
+    first_arg_index++;
+  }
+
+  //  `franz repl` reads entries interactively and runs each one with the JIT
+  bool replMode = false;
+  if (argc > 1 && strcmp(argv[1], "repl") == 0) {
+    replMode = true;
+    first_arg_index++;
+  }
+
+  //  `--` ends franz's own arguments: everything after it is passed to the program
+  int argEnd = argc;
+  for (int i = first_arg_index; i < argc; i++) {
+    if (strcmp(argv[i], "--") == 0) {
+      argEnd = i;
+      break;
+    }
+  }
+
+  //  Compile-only modes (build, --emit) take no program arguments, so flags may
+  // follow the source path (`franz build app.franz -o app`): move the source path
+  // behind them so the flag loop below sees every flag
+  bool compileOnly = buildMode;
+  for (int i = first_arg_index; i < argEnd; i++) {
+    if (strncmp(argv[i], "--emit=", 7) == 0) compileOnly = true;
+  }
+
+  if (compileOnly) {
+    for (int i = first_arg_index; i < argEnd; i++) {
+      if (strcmp(argv[i], "-o") == 0) {
+        i++;
+      } else if (argv[i][0] != '-') {
+        char *sourcePath = argv[i];
+        memmove(&argv[i], &argv[i + 1], (argEnd - i - 1) * sizeof(char *));
+        argv[argEnd - 1] = sourcePath;
+        break;
+      }
+    }
+  }
+
+  for (int i = first_arg_index; i < argEnd; i++) {
     if (strcmp(argv[i], "-v") == 0 || strcmp(argv[i], "--version") == 0) {
       printf("%s\n", FRANZ_VERSION);
       return 0;
     } else if (strcmp(argv[i], "-d") == 0) {
       debug = true;
       first_arg_index++;
+    } else if (strcmp(argv[i], "-g") == 0) {
+      //  -g emits DWARF debug info so gdb/lldb can map native code to .franz lines
+      options.debugInfo = true;
+      first_arg_index++;
+    } else if (strcmp(argv[i], "--coverage") == 0) {
+      //  --coverage counts executed lines and branches into franz.coverage
+      options.coverage = true;
+      first_arg_index++;
+    } else if (strcmp(argv[i], "--trace") == 0 || strncmp(argv[i], "--trace=", 8) == 0) {
+      //  --trace[=GLOB] logs calls and returns of (matching) functions to stderr
+      if (argv[i][7] == '=' && argv[i][8] == '\0') {
+        fprintf(stderr, "Error: Option '--trace=' requires a function name pattern.\n");
+        return 1;
+      }
+      options.trace = true;
+      options.tracePattern = argv[i][7] == '=' ? argv[i] + 8 : NULL;
+      first_arg_index++;
+    } else if (strcmp(argv[i], "--profile") == 0) {
+      //  --profile reports call counts and time per function at exit
+      options.profile = true;
+      first_arg_index++;
+    } else if (strcmp(argv[i], "--interpret") == 0) {
+      //  --interpret evaluates the AST directly (no LLVM, llc or C compiler needed)
+      options.interpret = true;
+      first_arg_index++;
+    } else if (strcmp(argv[i], "--bytecode") == 0) {
+      //  --bytecode compiles to bytecode and runs it on the VM (no LLVM, llc or C compiler needed)
+      options.bytecode = true;
+      first_arg_index++;
+    } else if (strcmp(argv[i], "-o") == 0) {
+      if (i + 1 >= argEnd) {
+        fprintf(stderr, "Error: Option '-o' requires an output path.\n");
+        return 1;
+      }
+      options.outputPath = argv[++i];
+      first_arg_index += 2;
+    } else if (strncmp(argv[i], "-O", 2) == 0) {
+      //  -O0..-O3 select the LLVM optimization pipeline (-O alone means -O2)
+      const char *level = argv[i] + 2;
+      if (level[0] == '\0') {
+        options.optLevel = 2;
+      } else if (level[0] >= '0' && level[0] <= '3' && level[1] == '\0') {
+        options.optLevel = level[0] - '0';
+      } else {
+        fprintf(stderr, "Error: Invalid optimization level '%s'. Use -O0, -O1, -O2 or -O3.\n", argv[i]);
+        return 1;
+      }
+      first_arg_index++;
+    } else if (strncmp(argv[i], "--emit=", 7) == 0) {
+      //  --emit=ir|bc|asm|obj|franzc writes compiler output instead of running
+      options.emit = EmitKind_parse(argv[i] + 7);
+      if (options.emit == EMIT_NONE) {
+        fprintf(stderr, "Error: Invalid emit kind '%s'. Use 'ir', 'bc', 'asm', 'obj' or 'franzc'.\n", argv[i] + 7);
+        return 1;
+      }
+      first_arg_index++;
+    } else if (strncmp(argv[i], "--target=", 9) == 0) {
+      //  --target=<triple> cross-compiles (e.g. aarch64-linux-gnu)
+      if (argv[i][9] == '\0') {
+        fprintf(stderr, "Error: Option '--target' requires a target triple.\n");
+        return 1;
+      }
+      options.target = argv[i] + 9;
+      first_arg_index++;
+    } else if (strncmp(argv[i], "--sysroot=", 10) == 0) {
+      //  --sysroot=<dir> links against the target's headers and libraries
+      if (argv[i][10] == '\0') {
+        fprintf(stderr, "Error: Option '--sysroot' requires a directory.\n");
+        return 1;
+      }
+      options.sysroot = argv[i] + 10;
+      first_arg_index++;
+    } else if (strncmp(argv[i], "--message-format=", 17) == 0) {
+      //  --message-format=json prints diagnostics as JSON lines on stderr
+      DiagnosticFormat format;
+      if (!Diagnostic_parseFormat(argv[i] + 17, &format)) {
+        fprintf(stderr, "Error: Invalid message format '%s'. Use 'human' or 'json'.\n", argv[i] + 17);
+        return 1;
+      }
+      Diagnostic_setFormat(format);
+      // Native programs run as child processes: their runtime errors follow the same format
+      setenv("FRANZ_MESSAGE_FORMAT", argv[i] + 17, 1);
+      first_arg_index++;
+    } else if (strncmp(argv[i], "--dump-tokens=", 14) == 0 || strncmp(argv[i], "--dump-ast=", 11) == 0) {
+      //  --dump-tokens=json / --dump-ast=json print the front end's output for tools
+      bool tokens = argv[i][7] == 't';
+      const char *format = strchr(argv[i], '=') + 1;
+      if (strcmp(format, "json") != 0) {
+        fprintf(stderr, "Error: Invalid dump format '%s'. Use 'json'.\n", format);
+        return 1;
+      }
+      if (tokens) {
+        options.dumpTokens = true;
+      } else {
+        options.dumpAst = true;
+      }
+      first_arg_index++;
+    } else if (strcmp(argv[i], "--jit") == 0) {
+      options.jit = true;
+      first_arg_index++;
     } else if (strcmp(argv[i], "--assert-types") == 0) {
       assert_types = true;
       first_arg_index++;
+    } else if (strcmp(argv[i], "--no-cache") == 0) {
+      //  Always recompile, ignoring the compilation cache
+      options.cache = false;
+      first_arg_index++;
     } else if (strcmp(argv[i], "--no-tco") == 0) {
       // TCO: Disable tail call optimization (for debugging stack traces)
-      enable_tco = false;
+      options.enable_tco = false;
       first_arg_index++;
     } else if (strncmp(argv[i], "--scoping=", 10) == 0) {
       //  Parse --scoping=lexical or --scoping=dynamic
@@ -82,6 +306,89 @@ int main(int argc, char *argv[]) {
     }
   }
 
+  options.debug = debug;
+
+  if (options.outputPath != NULL && !buildMode && options.emit == EMIT_NONE) {
+    fprintf(stderr, "Error: Option '-o' is only valid with 'franz build' or '--emit'.\n");
+    return 1;
+  }
+
+  if (options.emit != EMIT_NONE && (buildMode || options.jit)) {
+    fprintf(stderr, "Error: '--emit' cannot be combined with 'franz build', 'franz run' or '--jit'.\n");
+    return 1;
+  }
+
+  if (buildMode && options.jit) {
+    fprintf(stderr, "Error: '--jit' cannot be combined with 'franz build'.\n");
+    return 1;
+  }
+
+  //  The interpreter runs programs itself: nothing is compiled or instrumented
+  if (options.interpret) {
+    if (compileOnly || replMode || options.jit) {
+      fprintf(stderr, "Error: '--interpret' cannot be combined with 'franz build', 'franz run', 'franz repl', '--emit' or '--jit'.\n");
+      return 1;
+    }
+    if (options.debugInfo || options.coverage || options.trace || options.profile) {
+      fprintf(stderr, "Error: Options '-g', '--coverage', '--trace' and '--profile' are not supported by '--interpret'.\n");
+      return 1;
+    }
+  }
+
+  //  The bytecode VM runs programs itself, like the interpreter
+  if (options.bytecode) {
+    if (options.interpret) {
+      fprintf(stderr, "Error: '--bytecode' cannot be combined with '--interpret'.\n");
+      return 1;
+    }
+    if (compileOnly || replMode || options.jit) {
+      fprintf(stderr, "Error: '--bytecode' cannot be combined with 'franz build', 'franz run', 'franz repl', '--emit' or '--jit'.\n");
+      return 1;
+    }
+    if (options.debugInfo || options.coverage || options.trace || options.profile) {
+      fprintf(stderr, "Error: Options '-g', '--coverage', '--trace' and '--profile' are not supported by '--bytecode'.\n");
+      return 1;
+    }
+  }
+
+  //  .franzc files hold bytecode: nothing native is generated or instrumented
+  if (options.emit == EMIT_FRANZC) {
+    if (options.debugInfo || options.coverage || options.trace || options.profile ||
+        options.target != NULL || options.sysroot != NULL) {
+      fprintf(stderr, "Error: Options '-g', '--coverage', '--trace', '--profile', '--target' and '--sysroot' are not supported by '--emit=franzc'.\n");
+      return 1;
+    }
+  }
+
+  //  Cross-compiled programs cannot run on this machine
+  if ((options.target != NULL || options.sysroot != NULL) && !compileOnly) {
+    fprintf(stderr, "Error: Options '--target' and '--sysroot' are only valid with 'franz build' or '--emit'.\n");
+    return 1;
+  }
+
+  if (compileOnly && argEnd > first_arg_index + 1) {
+    fprintf(stderr, "Error: Unexpected argument '%s'.\n", argv[first_arg_index + 1]);
+    return 1;
+  }
+
+  if (compileOnly && argEnd < argc) {
+    fprintf(stderr, "Error: Program arguments after '--' are only used when running a program.\n");
+    return 1;
+  }
+
+  if (replMode) {
+    if (options.coverage) {
+      fprintf(stderr, "Error: '--coverage' is not supported by 'franz repl'.\n");
+      return 1;
+    }
+    if (options.outputPath != NULL || options.emit != EMIT_NONE || options.target != NULL ||
+        options.sysroot != NULL || argEnd > first_arg_index || argEnd < argc) {
+      fprintf(stderr, "Error: 'franz repl' takes no file, output or program arguments.\n");
+      return 1;
+    }
+    return Repl_run(&options);
+  }
+
   //  Debug output for scoping mode
   if (debug) {
     printf("Scoping mode: %s\n", ScopingMode_name(g_scoping_mode));
@@ -99,10 +406,11 @@ int main(int argc, char *argv[]) {
   rewind(stdin);
 
 
-  if (argc > first_arg_index) {
+  if (argEnd > first_arg_index) {
 
     // if a path was supplied
     char *codePath = argv[first_arg_index];
+    options.sourcePath = codePath;
 
     // if code is passed through an file argument
     FILE *p_file = fopen(codePath, "r");
@@ -169,9 +477,36 @@ int main(int argc, char *argv[]) {
     }
   }
 
-  // Calculate the number of arguments to skip (ie. name of executable, file passed, and flags).
+  //  Build/emit mode: default the output name to the source file name with the
+  // extension for the output kind (none for executables)
+  char *defaultOutput = NULL;
+  if ((buildMode || options.emit != EMIT_NONE) && options.outputPath == NULL) {
+    if (pipedInput) {
+      fprintf(stderr, "Error: Piped input requires '-o <path>' with 'franz build' or '--emit'.\n");
+      free(code);
+      return 1;
+    }
+    defaultOutput = buildOutputName(argv[first_arg_index], EmitKind_extension(options.emit));
+    options.outputPath = defaultOutput;
+  }
+
+  //  Program arguments: anything after the file path, then everything after `--`
+  // (skipping the name of executable, file passed, flags and the separator itself)
   int argsToSkip = first_arg_index + (pipedInput ? 0 : 1);
-  int exitCode = run(code, fileLength, argc - argsToSkip, &argv[argsToSkip], debug, enable_tco);
+  int programArgc = 0;
+  char **programArgv = malloc(sizeof(char *) * (argc + 1));
+  for (int i = argsToSkip; i < argEnd; i++) {
+    programArgv[programArgc++] = argv[i];
+  }
+  for (int i = argEnd + 1; i < argc; i++) {
+    programArgv[programArgc++] = argv[i];
+  }
+  programArgv[programArgc] = NULL;
+
+  int exitCode = run(code, fileLength, programArgc, programArgv, &options);
+  free(programArgv);
+
+  free(defaultOutput);
 
   // free code
   free(code);
@@ -113,8 +113,71 @@ void CompileEnv_print(CompileEnv *env) {
 static int count_params(AstNode *fn_node) {
   if (fn_node == NULL || fn_node->opcode != OP_FUNCTION) return 0;
 
-  // Array-based: last child is body, all others are parameters
-  return (fn_node->childCount > 0) ? fn_node->childCount - 1 : 0;
+  // Parameters are the leading identifiers; the body statements follow them
+  int count = 0;
+  while (count < fn_node->childCount && fn_node->children[count]->opcode == OP_IDENTIFIER) {
+    count++;
+  }
+  return count;
+}
+
+// Helper: Look up a name and count how many function scopes out it is bound
+static VarBinding *resolve(CompileEnv *env, const char *name, int *depth) {
+  for (*depth = 0; env != NULL; env = env->parent, (*depth)++) {
+    VarBinding *binding = BindingMap_lookup(env->bindings, name);
+    if (binding != NULL) return binding;
+  }
+  return NULL;
+}
+
+// Helper: Store the resolved offset and depth of a name in its AST node
+static void annotate(AstNode *node, CompileEnv *env) {
+  int depth;
+  VarBinding *binding = resolve(env, node->val, &depth);
+  if (binding != NULL) {
+    node->var_offset = binding->offset;
+    node->var_depth = depth;
+  }
+}
+
+// Helper: Bind a name in the current scope unless it is already local
+static void bind_local(AstNode *name, CompileEnv *env) {
+  if (BindingMap_lookup(env->bindings, name->val) == NULL) {
+    BindingMap_add(env->bindings, name->val, env->bindings->count, 0);  // depth=0 for local
+  }
+  annotate(name, env);
+}
+
+//  Blocks passed to if, when, unless, loop and while run inline: 1 if the
+// argument at index of this application is such a block
+int compile_is_inline_block(AstNode *application, int index) {
+  if (application->opcode != OP_APPLICATION || application->childCount < 2) return 0;
+  AstNode *head = application->children[0];
+  if (head->opcode != OP_IDENTIFIER || index < 2) return 0;
+
+  if (strcmp(head->val, "if") == 0) {
+    // (if cond then [cond then ...] [else]): every second argument and a trailing else
+    return index % 2 == 0 || index == application->childCount - 1;
+  }
+  return index == 2 && (strcmp(head->val, "when") == 0 || strcmp(head->val, "unless") == 0 ||
+                        strcmp(head->val, "loop") == 0 || strcmp(head->val, "while") == 0);
+}
+
+// Helper: An inline block shares the enclosing scope; its parameters (the
+// loop index) are bound there like assignments
+static void compile_block(AstNode *block, CompileEnv *env) {
+  if (block->opcode != OP_FUNCTION) {
+    compile_optimize(block, env);
+    return;
+  }
+
+  int param_count = count_params(block);
+  for (int i = 0; i < param_count; i++) {
+    bind_local(block->children[i], env);
+  }
+  for (int i = param_count; i < block->childCount; i++) {
+    compile_optimize(block->children[i], env);
+  }
 }
 
 // Main optimization function - assigns variable offsets
@@ -124,26 +187,22 @@ void compile_optimize(AstNode *root, CompileEnv *env) {
 
   switch (root->opcode) {
     case OP_IDENTIFIER: {
-      // Lookup variable in compile-time environment
-      VarBinding *binding = CompileEnv_lookup(env, root->val);
-      if (binding != NULL) {
-        // Store offset and depth in AST node for fast runtime lookup
-        root->var_offset = binding->offset;
-        root->var_depth = binding->scope_depth;
-      }
+      // Lookup variable in compile-time environment and store its offset
+      // and depth in the AST node for fast runtime lookup
+      annotate(root, env);
       break;
     }
 
     case OP_ASSIGNMENT: {
-      // Add variable to current scope's binding map
-      const char *var_name = root->val;
-      int offset = env->bindings->count;  // Next available offset
-      BindingMap_add(env->bindings, var_name, offset, 0);  // depth=0 for local
-
-      // Compile RHS expression (children[1])
-      if (root->childCount > 1) {
-        compile_optimize(root->children[1], env);
-      }
+      // children[0] is the name, children[1] the value
+      if (root->childCount < 2 || root->children[0]->opcode != OP_IDENTIFIER) break;
+
+      // The value is compiled first: `x = (add x 1)` reads the outer x
+      compile_optimize(root->children[1], env);
+
+      // A new name gets the next offset of the current scope; reassigning
+      // a local (mut) reuses its offset
+      bind_local(root->children[0], env);
       break;
     }
 
@@ -152,22 +211,50 @@ void compile_optimize(AstNode *root, CompileEnv *env) {
       CompileEnv *func_env = CompileEnv_new(env);
 
       // Add parameters to function environment
-      // Array-based: last child is body, all others are parameters
-      int param_count = (root->childCount > 0) ? root->childCount - 1 : 0;
+      int param_count = count_params(root);
       for (int i = 0; i < param_count; i++) {
         BindingMap_add(func_env->bindings, root->children[i]->val, i, 0);
+        annotate(root->children[i], func_env);
       }
 
-      // Compile function body (last child)
-      if (root->childCount > 0) {
-        compile_optimize(root->children[root->childCount - 1], func_env);
+      // Compile the body statements that follow the parameters
+      for (int i = param_count; i < root->childCount; i++) {
+        compile_optimize(root->children[i], func_env);
       }
 
       CompileEnv_free(func_env);
       break;
     }
 
-    case OP_APPLICATION:
+    case OP_APPLICATION: {
+      // Branch and loop bodies and the results of cond clauses are compiled
+      // in the enclosing function's scope, where they run
+      AstNode *head = root->childCount > 0 ? root->children[0] : NULL;
+      int is_cond = head != NULL && head->opcode == OP_IDENTIFIER && strcmp(head->val, "cond") == 0;
+      for (int i = 0; i < root->childCount; i++) {
+        AstNode *child = root->children[i];
+        if (is_cond && i > 0 && child->opcode == OP_APPLICATION && child->childCount == 2) {
+          compile_optimize(child->children[0], env);
+          compile_block(child->children[1], env);
+        } else if (compile_is_inline_block(root, i)) {
+          compile_block(child, env);
+        } else {
+          compile_optimize(child, env);
+        }
+      }
+      break;
+    }
+
+    case OP_QUALIFIED: {
+      // ns.member: resolve the namespace part
+      char *dot = strchr(root->val, '.');
+      if (dot == NULL) break;
+      *dot = '\0';
+      annotate(root, env);
+      *dot = '.';
+      break;
+    }
+
     case OP_STATEMENT:
     case OP_RETURN:
       // Recursively compile children
@@ -183,7 +270,6 @@ void compile_optimize(AstNode *root, CompileEnv *env) {
       break;
 
     case OP_SIGNATURE:
-    case OP_QUALIFIED:
       // Type annotations - recursively compile children
       for (int i = 0; i < root->childCount; i++) {
         compile_optimize(root->children[i], env);
@@ -41,8 +41,14 @@ VarBinding *CompileEnv_lookup(CompileEnv *env, const char *name);
 void CompileEnv_print(CompileEnv *env);
 
 // Main compilation function - assigns variable offsets to AST nodes
+// Blocks passed to if, when, unless, cond, loop and while share the scope of
+// the enclosing function, so only function literals open a new scope
 void compile_optimize(AstNode *root, CompileEnv *env);
 
+// 1 if argument index of an application is a block that runs inline
+// (the branches of if, when and unless, and the body of loop and while)
+int compile_is_inline_block(AstNode *application, int index);
+
 // Global flag to enable/disable optimizations
 extern int g_optimization_enabled;
 
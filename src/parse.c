@@ -1,34 +1,143 @@
 #include <stdio.h>
 #include <stdlib.h>
 #include <string.h>
+#include <stdarg.h>
 #include "parse.h"
 #include "ast.h"
 #include "tokens.h"
+#include "diagnostics/diagnostic.h"
+
+//  Error recovery: syntax errors are collected (Diagnostic_add) and parsing goes on.
+//  A node whose tokens contain an error is dropped (parse functions return NULL),
+//  its enclosing statement list skips it and resumes at the next statement, or
+//  after the bracket that closes the broken node. Callers check Diagnostic_count()
+//  and refuse to compile a program with errors.
+
+//  Record a syntax error underlining the offending token
+static void syntaxError(Token *tok, ErrorCode code, const char *format, ...) {
+  char message[1024];
+  va_list args;
+  va_start(args, format);
+  vsnprintf(message, sizeof(message), format, args);
+  va_end(args);
+
+  Diagnostic_add("Syntax Error", code, Diagnostic_spanOfToken(tok), "%s", message);
+}
 
-//  Skip closure using array indexing
-// Returns the index of the closing token
-int skipClosure(TokenArray *arr, int start, enum TokenType open, enum TokenType close) {
-  int depth = 1;
-  int i = start + 1;
+static int isOpenBracket(enum TokenType type) {
+  return type == TOK_APPLYOPEN || type == TOK_FUNCOPEN || type == TOK_LBRACKET;
+}
 
-  while (depth != 0 && i < arr->count) {
-    Token *tok = &arr->tokens[i];
+static enum TokenType closeBracketFor(enum TokenType open) {
+  if (open == TOK_APPLYOPEN) return TOK_APPLYCLOSE;
+  if (open == TOK_FUNCOPEN) return TOK_FUNCCLOSE;
+  return TOK_RBRACKET;
+}
 
-    if (tok->type == TOK_END) {
-      printf(
-        "Syntax Error @ Line %i: Unexpected %s token.\n",
-        tok->lineNumber, getTokenTypeString(tok->type)
-      );
-      exit(0);
+//  End of an unclosed bracket opened at index open, closed off before limit.
+//  It ends with its line (a statement boundary); bracket groups starting on
+//  that line are kept whole, so the statements after it still parse.
+static int unclosedEnd(TokenArray *arr, int open, int limit) {
+  int end = open;
+
+  for (int k = open + 1; k < limit; k++) {
+    if (arr->tokens[k].lineNumber > arr->tokens[end].endLine) break;
+    end = k;
+    if (isOpenBracket(arr->tokens[k].type) && arr->tokens[k].partner > k) {
+      end = k = arr->tokens[k].partner;
     }
+  }
 
-    if (tok->type == close) depth--;
-    else if (tok->type == open) depth++;
+  return end;
+}
 
-    i++;
+//  Pair every bracket token with its close token (Token.partner) in one pass.
+//  A close token that does not match the innermost open bracket but matches an
+//  outer one closes that outer bracket; the brackets in between are unclosed
+//  (see unclosedEnd). Close tokens matching nothing are left for the parser to
+//  report as unexpected.
+static void matchBrackets(TokenArray *arr) {
+  int *stack = malloc(sizeof(int) * arr->count);
+  int depth = 0;
+
+  for (int i = 0; i < arr->count; i++) {
+    Token *tok = &arr->tokens[i];
+    tok->partner = -1;
+
+    if (isOpenBracket(tok->type)) {
+      stack[depth++] = i;
+    } else if (tok->type == TOK_ASSIGNMENT) {
+      // An assignment never belongs inside an application or list: it starts a
+      // new statement, so the applications and lists still open are unclosed
+      // (otherwise a later close token would swallow the statement)
+      int stmtStart = i > 0 ? i - 1 : i;
+      if (stmtStart > 0 && arr->tokens[stmtStart - 1].type == TOK_MUT) stmtStart--;
+      while (depth > 0 && arr->tokens[stack[depth - 1]].type != TOK_FUNCOPEN &&
+             stack[depth - 1] < stmtStart) {
+        int open = stack[--depth];
+        arr->tokens[open].partner = unclosedEnd(arr, open, stmtStart);
+      }
+    } else if (tok->type == TOK_APPLYCLOSE || tok->type == TOK_FUNCCLOSE ||
+               tok->type == TOK_RBRACKET || tok->type == TOK_END) {
+      // Innermost open bracket this token closes (end of input closes none)
+      int target = -1;
+      if (tok->type != TOK_END) {
+        target = depth - 1;
+        while (target >= 0 && closeBracketFor(arr->tokens[stack[target]].type) != tok->type) {
+          target--;
+        }
+        if (target < 0) continue;  // stray close token
+      }
+
+      // Brackets above the target are unclosed
+      while (depth - 1 > target) {
+        int open = stack[--depth];
+        arr->tokens[open].partner = unclosedEnd(arr, open, i);
+      }
+      if (target >= 0) {
+        int open = stack[--depth];
+        arr->tokens[open].partner = i;
+        tok->partner = open;
+      }
+    }
   }
 
-  return i - 1;  // Return index of closing token
+  free(stack);
+}
+
+//  Give a node the source span of tokens [start, start + length)
+static AstNode *withSpan(AstNode *node, TokenArray *arr, int start, int length) {
+  if (node == NULL) return NULL;
+
+  Token *first = &arr->tokens[start];
+  Token *last = &arr->tokens[length > 0 ? start + length - 1 : start];
+
+  node->offset = first->offset;
+  node->column = first->column;
+  node->endOffset = last->endOffset;
+  node->endLine = last->endLine;
+  node->endColumn = last->endColumn;
+  node->sourceId = first->sourceId;
+  return node;
+}
+
+//  Skip closure using array indexing
+// Returns the index of the closing token (for an unclosed bracket: its last token)
+int skipClosure(TokenArray *arr, int start, enum TokenType open, enum TokenType close) {
+  (void) open;
+  (void) close;
+  int partner = arr->tokens[start].partner;
+  return partner >= start ? partner : start;
+}
+
+//  Append a parsed child. A child that failed to parse marks the parent as failed;
+//  statement lists pass NULL and simply resume at the next statement.
+static void appendParsed(AstNode *parent, AstNode **p_lastChild, AstNode *child, int *failed) {
+  if (child == NULL) {
+    if (failed != NULL) *failed = 1;
+    return;
+  }
+  AstNode_appendChild(parent, p_lastChild, child);
 }
 
 //  Parse list literal [elem1, elem2, ...]
@@ -36,30 +145,30 @@ int skipClosure(TokenArray *arr, int start, enum TokenType open, enum TokenType
 AstNode *parseListLiteral(TokenArray *arr, int start, int length) {
   Token *head = &arr->tokens[start];
   Token *tail = &arr->tokens[start + length - 1];
+  int failed = 0;
 
   // Verify list syntax: must start with [ and end with ]
   if (head->type != TOK_LBRACKET) {
-    printf("Syntax Error @ Line %i: Expected '[' at start of list.\n", head->lineNumber);
-    exit(0);
+    syntaxError(head, ERROR_CODE_UNEXPECTED_TOKEN, "Expected '[' at start of list.");
+    return NULL;
   }
-  if (tail->type != TOK_RBRACKET) {
-    printf("Syntax Error @ Line %i: Expected ']' at end of list.\n", tail->lineNumber);
-    exit(0);
+
+  // Index of the closing ] (past the end for an unclosed list, whose elements are still checked)
+  int close = start + length - 1;
+  if (length < 2 || tail->type != TOK_RBRACKET) {
+    syntaxError(tail, ERROR_CODE_UNCLOSED_BRACKET, "Expected ']' at end of list.");
+    close = start + length;
+    failed = 1;
   }
 
   // Create list node
   AstNode *listNode = AstNode_new(NULL, OP_LIST, head->lineNumber);
 
-  // Empty list: []
-  if (length == 2) {
-    return listNode;
-  }
-
   // Parse elements between [ and ]
   int i = start + 1;  // Skip [
   int elemStart = i;
 
-  while (i < start + length - 1) {  // Stop before ]
+  while (i < close) {  // Stop before ]
     Token *tok = &arr->tokens[i];
 
     // Skip nested structures to find element boundaries
@@ -73,41 +182,42 @@ AstNode *parseListLiteral(TokenArray *arr, int start, int length) {
     }
 
     // Check if we're at comma or end of list
-    Token *nextTok = (i + 1 < start + length) ? &arr->tokens[i + 1] : NULL;
-    if (nextTok && (nextTok->type == TOK_COMMA || i + 1 == start + length - 1)) {
+    if (i + 1 == close || arr->tokens[i + 1].type == TOK_COMMA) {
       // Parse element from elemStart to i (inclusive)
       int elemLength = i - elemStart + 1;
       if (elemLength > 0) {
-        AstNode *element = parseValue(arr, elemStart, elemLength);
-        AstNode_addChild(listNode, element);
+        appendParsed(listNode, NULL, parseValue(arr, elemStart, elemLength), &failed);
       }
 
-      if (nextTok->type == TOK_COMMA) {
-        i += 2;  // Skip comma
-        elemStart = i;
-        continue;
-      } else {
+      if (i + 1 == close) {
         // End of list
         break;
       }
+
+      i += 2;  // Skip comma
+      elemStart = i;
+      continue;
     }
 
     i++;
   }
 
+  if (failed) {
+    AstNode_free(listNode);
+    return NULL;
+  }
+
   return listNode;
 }
 
 //  Parse application using array indexing
 AstNode *parseApplication(TokenArray *arr, int start, int length) {
   Token *head = &arr->tokens[start];
+  int failed = 0;
 
   if (head->type != TOK_APPLYOPEN) {
-    printf(
-      "Syntax Error @ Line %i: Unexpected %s token.\n",
-      head->lineNumber, getTokenTypeString(head->type)
-    );
-    exit(0);
+    syntaxError(head, ERROR_CODE_UNEXPECTED_TOKEN, "Unexpected %s token.", getTokenTypeString(head->type));
+    return NULL;
   }
 
   AstNode *res = AstNode_new(NULL, OP_APPLICATION, head->lineNumber);
@@ -125,7 +235,7 @@ AstNode *parseApplication(TokenArray *arr, int start, int length) {
       int closureEnd = skipClosure(arr, i, curr->type, curr->type + 1);
       int closureLength = closureEnd - closureStart + 1;
 
-      AstNode_appendChild(res, &p_lastChild, parseValue(arr, closureStart, closureLength));
+      appendParsed(res, &p_lastChild, parseValue(arr, closureStart, closureLength), &failed);
       i = closureEnd + 1;
 
     } else if (curr->type == TOK_LBRACKET) {
@@ -134,7 +244,7 @@ AstNode *parseApplication(TokenArray *arr, int start, int length) {
       int listEnd = skipClosure(arr, i, TOK_LBRACKET, TOK_RBRACKET);
       int listLength = listEnd - listStart + 1;
 
-      AstNode_appendChild(res, &p_lastChild, parseValue(arr, listStart, listLength));
+      appendParsed(res, &p_lastChild, parseValue(arr, listStart, listLength), &failed);
       i = listEnd + 1;
 
     } else if (curr->type == TOK_IDENTIFIER &&
@@ -142,22 +252,24 @@ AstNode *parseApplication(TokenArray *arr, int start, int length) {
                arr->tokens[i + 1].type == TOK_DOT &&
                arr->tokens[i + 2].type == TOK_IDENTIFIER) {
       // Case of qualified name: ns.identifier
-      AstNode_appendChild(res, &p_lastChild, parseValue(arr, i, 3));
+      appendParsed(res, &p_lastChild, parseValue(arr, i, 3), &failed);
       i += 3;
 
     } else {
       // Case of single token value
-      AstNode_appendChild(res, &p_lastChild, parseValue(arr, i, 1));
+      appendParsed(res, &p_lastChild, parseValue(arr, i, 1), &failed);
       i++;
     }
   }
 
   if (i >= end || arr->tokens[i].type != TOK_APPLYCLOSE) {
-    printf(
-      "Syntax Error @ Line %i: Application not closed.\n",
-      arr->tokens[i < arr->count ? i : arr->count - 1].lineNumber
-    );
-    exit(0);
+    syntaxError(head, ERROR_CODE_UNCLOSED_BRACKET, "Application not closed.");
+    failed = 1;
+  }
+
+  if (failed) {
+    AstNode_free(res);
+    return NULL;
   }
 
   return res;
@@ -167,22 +279,29 @@ AstNode *parseApplication(TokenArray *arr, int start, int length) {
 AstNode *parseValue(TokenArray *arr, int start, int length) {
   Token *head = &arr->tokens[start];
 
+  // Application, function, or list literal (an unclosed one may be a single token)
+  if (head->type == TOK_APPLYOPEN) {
+    return withSpan(parseApplication(arr, start, length), arr, start, length);
+  } else if (head->type == TOK_FUNCOPEN) {
+    return withSpan(parseFunction(arr, start, length), arr, start, length);
+  } else if (head->type == TOK_LBRACKET) {
+    //  List literal [elem1, elem2, ...]
+    return withSpan(parseListLiteral(arr, start, length), arr, start, length);
+  }
+
   if (length == 1) {
     // Single token: int, float, string, or identifier
     if (head->type == TOK_STRING) {
-      return AstNode_new(head->val, OP_STRING, head->lineNumber);
+      return withSpan(AstNode_new(head->val, OP_STRING, head->lineNumber), arr, start, 1);
     } else if (head->type == TOK_FLOAT) {
-      return AstNode_new(head->val, OP_FLOAT, head->lineNumber);
+      return withSpan(AstNode_new(head->val, OP_FLOAT, head->lineNumber), arr, start, 1);
     } else if (head->type == TOK_INT) {
-      return AstNode_new(head->val, OP_INT, head->lineNumber);
+      return withSpan(AstNode_new(head->val, OP_INT, head->lineNumber), arr, start, 1);
     } else if (head->type == TOK_IDENTIFIER) {
-      return AstNode_new(head->val, OP_IDENTIFIER, head->lineNumber);
+      return withSpan(AstNode_new(head->val, OP_IDENTIFIER, head->lineNumber), arr, start, 1);
     } else {
-      printf(
-        "Syntax Error @ Line %i: Unexpected %s token.\n",
-        head->lineNumber, getTokenTypeString(head->type)
-      );
-      exit(0);
+      syntaxError(head, ERROR_CODE_UNEXPECTED_TOKEN, "Unexpected %s token.", getTokenTypeString(head->type));
+      return NULL;
     }
 
   } else if (length == 3 &&
@@ -197,24 +316,11 @@ AstNode *parseValue(TokenArray *arr, int start, int length) {
     qualified_name[namespace_len] = '.';
     strcpy(qualified_name + namespace_len + 1, arr->tokens[start + 2].val);
 
-    return AstNode_new(qualified_name, OP_QUALIFIED, head->lineNumber);
+    return withSpan(AstNode_new(qualified_name, OP_QUALIFIED, head->lineNumber), arr, start, 3);
 
   } else if (length > 1) {
-    // Application, function, or list literal
-    if (head->type == TOK_APPLYOPEN) {
-      return parseApplication(arr, start, length);
-    } else if (head->type == TOK_FUNCOPEN) {
-      return parseFunction(arr, start, length);
-    } else if (head->type == TOK_LBRACKET) {
-      //  List literal [elem1, elem2, ...]
-      return parseListLiteral(arr, start, length);
-    } else {
-      printf(
-        "Syntax Error @ Line %i: Unexpected %s token.\n",
-        head->lineNumber, getTokenTypeString(head->type)
-      );
-      exit(0);
-    }
+    syntaxError(head, ERROR_CODE_UNEXPECTED_TOKEN, "Unexpected %s token.", getTokenTypeString(head->type));
+    return NULL;
   }
 
   return NULL;
@@ -235,45 +341,38 @@ AstNode *parseAssignment(TokenArray *arr, int start, int length) {
 
     // Move to the identifier after 'mut'
     if (start + offset >= start + length) {
-      printf(
-        "Syntax Error @ Line %i: Expected identifier after 'mut'.\n",
-        head->lineNumber
-      );
-      exit(0);
+      syntaxError(head, ERROR_CODE_INCOMPLETE_STATEMENT, "Expected identifier after 'mut'.");
+      return NULL;
     }
 
     head = &arr->tokens[start + offset];
   }
 
   if (length < 3 + offset) {
-    printf(
-      "Syntax Error @ Line %i: Incomplete assignment.\n",
-      head->lineNumber
-    );
-    exit(0);
+    syntaxError(head, ERROR_CODE_INCOMPLETE_STATEMENT, "Incomplete assignment.");
+    return NULL;
   }
 
   if (head->type != TOK_IDENTIFIER) {
-    printf(
-      "Syntax Error @ Line %i: Unexpected %s token.\n",
-      head->lineNumber, getTokenTypeString(head->type)
-    );
-    exit(0);
+    syntaxError(head, ERROR_CODE_UNEXPECTED_TOKEN, "Unexpected %s token.", getTokenTypeString(head->type));
+    return NULL;
   }
 
   if (arr->tokens[start + offset + 1].type != TOK_ASSIGNMENT) {
-    printf(
-      "Syntax Error @ Line %i: Unexpected %s token.\n",
-      arr->tokens[start + offset + 1].lineNumber, getTokenTypeString(arr->tokens[start + offset + 1].type)
-    );
-    exit(0);
+    Token *op = &arr->tokens[start + offset + 1];
+    syntaxError(op, ERROR_CODE_UNEXPECTED_TOKEN, "Unexpected %s token.", getTokenTypeString(op->type));
+    return NULL;
   }
 
+  AstNode *value = parseValue(arr, start + offset + 2, length - offset - 2);
+  if (value == NULL) return NULL;
+
   // Create assignment node
-  AstNode *res = AstNode_new(NULL, OP_ASSIGNMENT, head->lineNumber);
+  AstNode *res = withSpan(AstNode_new(NULL, OP_ASSIGNMENT, head->lineNumber), arr, start, length);
   res->isMutable = isMutable;  // Mark if declared with mut
-  AstNode_addChild(res, AstNode_new(head->val, OP_IDENTIFIER, head->lineNumber));
-  AstNode_addChild(res, parseValue(arr, start + offset + 2, length - offset - 2));
+  AstNode *name = AstNode_new(head->val, OP_IDENTIFIER, head->lineNumber);
+  AstNode_addChild(res, withSpan(name, arr, start + offset, 1));
+  AstNode_addChild(res, value);
 
   return res;
 }
@@ -283,24 +382,21 @@ AstNode *parseReturn(TokenArray *arr, int start, int length) {
   Token *head = &arr->tokens[start];
 
   if (length < 2) {
-    printf(
-      "Syntax Error @ Line %i: Incomplete return.\n",
-      head->lineNumber
-    );
-    exit(0);
+    syntaxError(head, ERROR_CODE_INCOMPLETE_STATEMENT, "Incomplete return.");
+    return NULL;
   }
 
   if (head->type != TOK_RETURN) {
-    printf(
-      "Syntax Error @ Line %i: Unexpected %s token.\n",
-      head->lineNumber, getTokenTypeString(head->type)
-    );
-    exit(0);
+    syntaxError(head, ERROR_CODE_UNEXPECTED_TOKEN, "Unexpected %s token.", getTokenTypeString(head->type));
+    return NULL;
   }
 
+  AstNode *value = parseValue(arr, start + 1, length - 1);
+  if (value == NULL) return NULL;
+
   // Create return node
-  AstNode *res = AstNode_new(NULL, OP_RETURN, head->lineNumber);
-  AstNode_addChild(res, parseValue(arr, start + 1, length - 1));
+  AstNode *res = withSpan(AstNode_new(NULL, OP_RETURN, head->lineNumber), arr, start, length);
+  AstNode_addChild(res, value);
 
   return res;
 }
@@ -310,11 +406,8 @@ AstNode *parseFunction(TokenArray *arr, int start, int length) {
   Token *head = &arr->tokens[start];
 
   if (head->type != TOK_FUNCOPEN) {
-    printf(
-      "Syntax Error @ Line %i: Unexpected %s token.\n",
-      head->lineNumber, getTokenTypeString(head->type)
-    );
-    exit(0);
+    syntaxError(head, ERROR_CODE_UNEXPECTED_TOKEN, "Unexpected %s token.", getTokenTypeString(head->type));
+    return NULL;
   }
 
   AstNode *res = AstNode_new(NULL, OP_FUNCTION, head->lineNumber);
@@ -391,7 +484,7 @@ AstNode *parseFunction(TokenArray *arr, int start, int length) {
       }
 
       int stmtLength = stmtEnd - stmtStart + 1;
-      AstNode_appendChild(res, &p_lastChild, parseStatement(arr, stmtStart, stmtLength));
+      appendParsed(res, &p_lastChild, parseStatement(arr, stmtStart, stmtLength), NULL);
       i = stmtEnd + 1;
     }
 
@@ -402,7 +495,8 @@ AstNode *parseFunction(TokenArray *arr, int start, int length) {
     while (i < arrowIndex) {
       Token *curr = &arr->tokens[i];
       if (curr->type == TOK_IDENTIFIER) {
-        AstNode_appendChild(res, &p_lastChild, AstNode_new(curr->val, OP_IDENTIFIER, curr->lineNumber));
+        AstNode *param = AstNode_new(curr->val, OP_IDENTIFIER, curr->lineNumber);
+        AstNode_appendChild(res, &p_lastChild, withSpan(param, arr, i, 1));
       }
       i++;
     }
@@ -454,17 +548,14 @@ AstNode *parseFunction(TokenArray *arr, int start, int length) {
       }
 
       int stmtLength = stmtEnd - stmtStart + 1;
-      AstNode_appendChild(res, &p_lastChild, parseStatement(arr, stmtStart, stmtLength));
+      appendParsed(res, &p_lastChild, parseStatement(arr, stmtStart, stmtLength), NULL);
       i = stmtEnd + 1;
     }
   }
 
+  //  An unclosed function keeps the statements parsed so far
   if (i >= arr->count || arr->tokens[i].type != TOK_FUNCCLOSE) {
-    printf(
-      "Syntax Error @ Line %i: Function not closed.\n",
-      arr->tokens[i < arr->count ? i : arr->count - 1].lineNumber
-    );
-    exit(0);
+    syntaxError(head, ERROR_CODE_UNCLOSED_BRACKET, "Function not closed.");
   }
 
   return res;
@@ -475,12 +566,12 @@ AstNode *parseStatement(TokenArray *arr, int start, int length) {
   Token *head = &arr->tokens[start];
 
   if (length < 1) {
-    printf("Syntax Error @ Line %i: Empty statement.\n", head->lineNumber);
-    exit(0);
+    syntaxError(head, ERROR_CODE_INCOMPLETE_STATEMENT, "Empty statement.");
+    return NULL;
   }
 
   // Create statement node
-  AstNode *res = AstNode_new(NULL, OP_STATEMENT, head->lineNumber);
+  AstNode *res = withSpan(AstNode_new(NULL, OP_STATEMENT, head->lineNumber), arr, start, length);
   AstNode *p_lastChild = NULL;
 
   int i = start;
@@ -504,7 +595,7 @@ AstNode *parseStatement(TokenArray *arr, int start, int length) {
       }
 
       int returnLength = returnEnd - returnStart + 1;
-      AstNode_appendChild(res, &p_lastChild, parseReturn(arr, returnStart, returnLength));
+      appendParsed(res, &p_lastChild, parseReturn(arr, returnStart, returnLength), NULL);
       i = returnEnd + 1;
 
     } else if (curr->type == TOK_MUT && i + 1 < end && arr->tokens[i + 1].type == TOK_IDENTIFIER && i + 2 < end && arr->tokens[i + 2].type == TOK_ASSIGNMENT) {
@@ -525,7 +616,7 @@ AstNode *parseStatement(TokenArray *arr, int start, int length) {
       }
 
       int assignLength = assignEnd - assignStart + 1;
-      AstNode_appendChild(res, &p_lastChild, parseAssignment(arr, assignStart, assignLength));
+      appendParsed(res, &p_lastChild, parseAssignment(arr, assignStart, assignLength), NULL);
       i = assignEnd + 1;
 
     } else if (curr->type == TOK_IDENTIFIER && i + 1 < end && arr->tokens[i + 1].type == TOK_ASSIGNMENT) {
@@ -546,7 +637,7 @@ AstNode *parseStatement(TokenArray *arr, int start, int length) {
       }
 
       int assignLength = assignEnd - assignStart + 1;
-      AstNode_appendChild(res, &p_lastChild, parseAssignment(arr, assignStart, assignLength));
+      appendParsed(res, &p_lastChild, parseAssignment(arr, assignStart, assignLength), NULL);
       i = assignEnd + 1;
 
     } else if (curr->type == TOK_APPLYOPEN || curr->type == TOK_FUNCOPEN) {
@@ -555,12 +646,12 @@ AstNode *parseStatement(TokenArray *arr, int start, int length) {
       int exprEnd = skipClosure(arr, i, curr->type, curr->type + 1);
       int exprLength = exprEnd - exprStart + 1;
 
-      AstNode_appendChild(res, &p_lastChild, parseValue(arr, exprStart, exprLength));
+      appendParsed(res, &p_lastChild, parseValue(arr, exprStart, exprLength), NULL);
       i = exprEnd + 1;
 
     } else {
       // Single token expression (identifier, int, float, string)
-      AstNode_appendChild(res, &p_lastChild, parseValue(arr, i, 1));
+      appendParsed(res, &p_lastChild, parseValue(arr, i, 1), NULL);
       i++;
     }
   }
@@ -579,16 +670,19 @@ AstNode *parseProgram(TokenArray *arr) {
   Token *end = &arr->tokens[arr->count - 1];
 
   if (head->type != TOK_START) {
-    printf("Syntax Error @ Line 1: Missing start token.\n");
-    exit(0);
+    syntaxError(head, ERROR_CODE_SYNTAX, "Missing start token.");
+    return NULL;
   }
 
   if (end->type != TOK_END) {
-    printf("Syntax Error @ Line %i: Missing end token.\n", end->lineNumber);
-    exit(0);
+    syntaxError(end, ERROR_CODE_SYNTAX, "Missing end token.");
+    return NULL;
   }
 
+  matchBrackets(arr);
+
   // Parse all tokens between START and END as a single statement
   // (Franz programs are one big statement that may contain multiple sub-statements)
+  // Syntax errors are collected; the statements that parsed are returned
   return parseStatement(arr, 1, arr->count - 2);
 }
@@ -0,0 +1,257 @@
+#include "profile.h"
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <stdint.h>
+#include <time.h>
+
+#define TOPLEVEL_NAME "<toplevel>"
+
+const char *Profile_foldedPath(void) {
+  const char *path = getenv("FRANZ_PROFILE_FILE");
+  return path && path[0] ? path : "franz.folded";
+}
+
+//  Totals of one function (all functions of the same name share an entry)
+typedef struct ProfileEntry {
+  const char *name;
+  long long calls;
+  uint64_t inclusiveNs;   // time of outermost activations (recursion is not counted twice)
+  uint64_t exclusiveNs;   // time not spent in callees
+  int active;             // activations currently on the stack
+} ProfileEntry;
+
+//  Node of the call tree: one per distinct stack
+typedef struct ProfileNode {
+  ProfileEntry *entry;
+  struct ProfileNode *parent;
+  struct ProfileNode *firstChild;
+  struct ProfileNode *nextSibling;
+  uint64_t exclusiveNs;
+} ProfileNode;
+
+typedef struct ProfileFrame {
+  ProfileNode *node;
+  uint64_t startNs;
+  uint64_t childNs;       // time spent in callees so far
+} ProfileFrame;
+
+static ProfileEntry **g_entries = NULL;
+static int g_entryCount = 0;
+static int g_entryCapacity = 0;
+
+//  Name pointer -> entry; instrumented code passes the same constant each call
+typedef struct NameSlot {
+  const char *name;
+  ProfileEntry *entry;
+} NameSlot;
+static NameSlot *g_slots = NULL;
+static size_t g_slotCapacity = 0;
+static size_t g_slotCount = 0;
+
+static ProfileNode g_root;
+static ProfileFrame *g_frames = NULL;
+static int g_depth = 0;
+static int g_frameCapacity = 0;
+static int g_started = 0;
+static int g_reported = 0;
+
+static uint64_t now(void) {
+  struct timespec ts;
+  clock_gettime(CLOCK_MONOTONIC, &ts);
+  return (uint64_t)ts.tv_sec * 1000000000ull + (uint64_t)ts.tv_nsec;
+}
+
+static ProfileEntry *entryByName(const char *name) {
+  for (int i = 0; i < g_entryCount; i++) {
+    if (strcmp(g_entries[i]->name, name) == 0) return g_entries[i];
+  }
+  if (g_entryCount == g_entryCapacity) {
+    g_entryCapacity = g_entryCapacity ? g_entryCapacity * 2 : 32;
+    g_entries = realloc(g_entries, sizeof(ProfileEntry *) * g_entryCapacity);
+  }
+  ProfileEntry *entry = calloc(1, sizeof(ProfileEntry));
+  entry->name = strdup(name);
+  g_entries[g_entryCount++] = entry;
+  return entry;
+}
+
+static size_t slotIndex(const char *name, size_t capacity) {
+  return ((uintptr_t)name >> 3) * 2654435761u % capacity;
+}
+
+static ProfileEntry *lookupEntry(const char *name) {
+  if (g_slotCapacity > 0) {
+    for (size_t i = slotIndex(name, g_slotCapacity); g_slots[i].name; i = (i + 1) % g_slotCapacity) {
+      if (g_slots[i].name == name) return g_slots[i].entry;
+    }
+  }
+
+  if ((g_slotCount + 1) * 2 > g_slotCapacity) {
+    size_t capacity = g_slotCapacity ? g_slotCapacity * 2 : 64;
+    NameSlot *slots = calloc(capacity, sizeof(NameSlot));
+    for (size_t i = 0; i < g_slotCapacity; i++) {
+      if (!g_slots[i].name) continue;
+      size_t j = slotIndex(g_slots[i].name, capacity);
+      while (slots[j].name) j = (j + 1) % capacity;
+      slots[j] = g_slots[i];
+    }
+    free(g_slots);
+    g_slots = slots;
+    g_slotCapacity = capacity;
+  }
+
+  ProfileEntry *entry = entryByName(name);
+  size_t i = slotIndex(name, g_slotCapacity);
+  while (g_slots[i].name) i = (i + 1) % g_slotCapacity;
+  g_slots[i].name = name;
+  g_slots[i].entry = entry;
+  g_slotCount++;
+  return entry;
+}
+
+static ProfileNode *childNode(ProfileNode *parent, ProfileEntry *entry) {
+  for (ProfileNode *child = parent->firstChild; child; child = child->nextSibling) {
+    if (child->entry == entry) return child;
+  }
+  ProfileNode *child = calloc(1, sizeof(ProfileNode));
+  child->entry = entry;
+  child->parent = parent;
+  child->nextSibling = parent->firstChild;
+  parent->firstChild = child;
+  return child;
+}
+
+static void pushFrame(ProfileNode *node) {
+  if (g_depth == g_frameCapacity) {
+    g_frameCapacity = g_frameCapacity ? g_frameCapacity * 2 : 64;
+    g_frames = realloc(g_frames, sizeof(ProfileFrame) * g_frameCapacity);
+  }
+  node->entry->calls++;
+  node->entry->active++;
+  g_frames[g_depth].node = node;
+  g_frames[g_depth].childNs = 0;
+  g_frames[g_depth].startNs = now();
+  g_depth++;
+}
+
+static void popFrame(uint64_t endNs) {
+  ProfileFrame *frame = &g_frames[--g_depth];
+  ProfileEntry *entry = frame->node->entry;
+  uint64_t elapsed = endNs - frame->startNs;
+  uint64_t exclusive = elapsed - frame->childNs;
+
+  entry->exclusiveNs += exclusive;
+  frame->node->exclusiveNs += exclusive;
+  if (--entry->active == 0) entry->inclusiveNs += elapsed;
+  if (g_depth > 0) g_frames[g_depth - 1].childNs += elapsed;
+}
+
+void franz_profile_start(void) {
+  if (g_started) return;
+  g_started = 1;
+  g_root.entry = entryByName(TOPLEVEL_NAME);
+  pushFrame(&g_root);
+  atexit(franz_profile_report);
+}
+
+void franz_profile_enter(const char *name) {
+  if (!g_started || g_reported) return;
+  ProfileEntry *entry = lookupEntry(name);
+  pushFrame(childNode(g_frames[g_depth - 1].node, entry));
+}
+
+void franz_profile_exit(void) {
+  //  The <toplevel> frame is closed by the report only
+  if (g_depth > 1 && !g_reported) popFrame(now());
+}
+
+// ============================================================================
+// Report
+// ============================================================================
+
+static int compareEntries(const void *a, const void *b) {
+  const ProfileEntry *x = *(ProfileEntry *const *)a, *y = *(ProfileEntry *const *)b;
+  if (x->exclusiveNs != y->exclusiveNs) return x->exclusiveNs < y->exclusiveNs ? 1 : -1;
+  return strcmp(x->name, y->name);
+}
+
+static int compareStrings(const void *a, const void *b) {
+  return strcmp(*(char *const *)a, *(char *const *)b);
+}
+
+typedef struct FoldedLines {
+  char **lines;
+  int count;
+  int capacity;
+} FoldedLines;
+
+//  One line per node with time of its own: the stack from the root, then the time
+static void collectFolded(ProfileNode *node, char *stack, size_t length, FoldedLines *out) {
+  size_t nameLength = strlen(node->entry->name);
+  char *path = malloc(length + nameLength + 2);
+  memcpy(path, stack, length);
+  if (length > 0) path[length++] = ';';
+  memcpy(path + length, node->entry->name, nameLength + 1);
+  length += nameLength;
+
+  if (node->exclusiveNs > 0) {
+    if (out->count == out->capacity) {
+      out->capacity = out->capacity ? out->capacity * 2 : 64;
+      out->lines = realloc(out->lines, sizeof(char *) * out->capacity);
+    }
+    char *line = malloc(length + 24);
+    snprintf(line, length + 24, "%s %llu", path, (unsigned long long)node->exclusiveNs);
+    out->lines[out->count++] = line;
+  }
+  for (ProfileNode *child = node->firstChild; child; child = child->nextSibling) {
+    collectFolded(child, path, length, out);
+  }
+  free(path);
+}
+
+static void writeFolded(void) {
+  FoldedLines folded = { 0 };
+  collectFolded(&g_root, "", 0, &folded);
+  qsort(folded.lines, folded.count, sizeof(char *), compareStrings);
+
+  const char *path = Profile_foldedPath();
+  FILE *file = fopen(path, "w");
+  if (file == NULL) {
+    fprintf(stderr, "Warning: Could not write profile to '%s'.\n", path);
+  } else {
+    for (int i = 0; i < folded.count; i++) fprintf(file, "%s\n", folded.lines[i]);
+    fclose(file);
+    fprintf(stderr, "Folded stacks written to '%s'.\n", path);
+  }
+
+  for (int i = 0; i < folded.count; i++) free(folded.lines[i]);
+  free(folded.lines);
+}
+
+void franz_profile_report(void) {
+  if (!g_started || g_reported) return;
+  g_reported = 1;
+  fflush(stdout);
+
+  //  Close frames left open by an exit from inside a function, then <toplevel>
+  uint64_t end = now();
+  while (g_depth > 0) popFrame(end);
+
+  ProfileEntry **sorted = malloc(sizeof(ProfileEntry *) * g_entryCount);
+  memcpy(sorted, g_entries, sizeof(ProfileEntry *) * g_entryCount);
+  qsort(sorted, g_entryCount, sizeof(ProfileEntry *), compareEntries);
+
+  double total = g_root.entry->inclusiveNs / 1e6;
+  fprintf(stderr, "\nProfile (wall time, %.3f ms total, sorted by exclusive time)\n", total);
+  fprintf(stderr, "%10s %14s %14s %7s  %s\n", "calls", "inclusive ms", "exclusive ms", "excl %", "function");
+  for (int i = 0; i < g_entryCount; i++) {
+    ProfileEntry *entry = sorted[i];
+    double share = total > 0 ? 100.0 * (entry->exclusiveNs / 1e6) / total : 0.0;
+    fprintf(stderr, "%10lld %14.3f %14.3f %6.1f%%  %s\n", entry->calls,
+            entry->inclusiveNs / 1e6, entry->exclusiveNs / 1e6, share, entry->name);
+  }
+  free(sorted);
+
+  writeFolded();
+}
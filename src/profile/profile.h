@@ -0,0 +1,56 @@
+#ifndef PROFILE_H
+#define PROFILE_H
+
+/**
+ * Profiling data for programs compiled with --profile
+ *
+ * Instrumented programs (see src/llvm-profile/llvm_profile.h) report
+ * every entry to and exit from a user function, a closure or a franz_*
+ * runtime call. The runtime keeps a call tree with wall-clock times; when
+ * the program exits it prints a table to stderr (call count, inclusive
+ * and exclusive time per function, sorted by exclusive time) and writes
+ * the call tree as folded stacks, one line per stack with its exclusive
+ * time in nanoseconds:
+ *
+ *   <toplevel>;quad;double 5120
+ *
+ * which flamegraph.pl, inferno and speedscope read. The file is
+ * FRANZ_PROFILE_FILE, or franz.folded in the working directory.
+ */
+
+/**
+ * Path of the folded-stack file (FRANZ_PROFILE_FILE or franz.folded)
+ *
+ * @return Static path
+ */
+const char *Profile_foldedPath(void);
+
+/**
+ * Start profiling
+ *
+ * Called first thing in main(). The first call opens the <toplevel> frame
+ * and installs the exit handler that writes the report.
+ */
+void franz_profile_start(void);
+
+/**
+ * Enter a function
+ *
+ * @param name - Franz name of the function, or the runtime function's C name
+ */
+void franz_profile_enter(const char *name);
+
+/**
+ * Leave the function entered last
+ */
+void franz_profile_exit(void);
+
+/**
+ * Print the table and write the folded stacks
+ *
+ * Frames still open (the program exited from inside a function) are closed
+ * first. Called at exit; only the first call reports.
+ */
+void franz_profile_report(void);
+
+#endif
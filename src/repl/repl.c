@@ -0,0 +1,605 @@
+#include "repl.h"
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <stdint.h>
+#include <stdbool.h>
+#include <setjmp.h>
+#include <signal.h>
+#include <unistd.h>
+#include "../lex.h"
+#include "../parse.h"
+#include "../diagnostics/diagnostic.h"
+#include "../events.h"
+#include "../stdlib.h"
+#include "repl_types.h"
+#include "../llvm-closures/llvm_closures.h"
+
+//  How a global's value is stored in its slot
+typedef enum {
+  REPL_INT,      // i64 (integers, closures, Generic* cast to i64)
+  REPL_FLOAT,    // double
+  REPL_POINTER   // i8* (strings, Generic*)
+} ReplValueKind;
+
+//  Top-level variable carried from the entry that defined it to later entries
+typedef struct {
+  char *name;
+  ReplValueKind kind;
+  int isMutable;               // kept in an alloca (mut variables and closures)
+  uint64_t *slot;              // value stored by the defining entry's main()
+  bool ownsSlot;               // pending only: slot allocated by this entry
+
+  // Codegen tracking for the variable, restored in later entries
+  LLVMValueRef typeMetadata;   // gen->typeMetadata (opcode + 1)
+  LLVMValueRef returnTypeTag;  // gen->returnTypeTags (tag + 1)
+  int isClosure;
+  int isGeneric;
+  int isVoid;
+} ReplGlobal;
+
+typedef struct {
+  ReplGlobal *items;
+  int count;
+  int capacity;
+} ReplGlobals;
+
+//  Compiled entry. Its machine code stays loaded for the whole session: closures
+// and strings it created may be referenced by later entries.
+typedef struct {
+  LLVMCodeGen *gen;
+  LLVMExecutionEngineRef engine;
+  TokenArray *tokens;
+  AstNode *ast;
+} ReplEntry;
+
+struct ReplSession {
+  RunOptions *options;
+  ReplGlobals globals;   // defined by earlier entries
+  ReplGlobals pending;   // defined by the entry being compiled, kept if it runs
+  ReplEntry *entries;
+  int entryCount;
+  Scope *globalScope;
+  ReplTypes *types;      // types of earlier definitions (:type, result printing)
+  bool printResult;      // the entry being compiled ends in a non-void expression
+};
+
+// ============================================================================
+// Globals
+// ============================================================================
+
+static ReplGlobal *ReplGlobals_find(ReplGlobals *globals, const char *name) {
+  for (int i = 0; i < globals->count; i++) {
+    if (strcmp(globals->items[i].name, name) == 0) return &globals->items[i];
+  }
+  return NULL;
+}
+
+static ReplGlobal *ReplGlobals_add(ReplGlobals *globals, const char *name) {
+  if (globals->count == globals->capacity) {
+    globals->capacity = globals->capacity ? globals->capacity * 2 : 16;
+    globals->items = realloc(globals->items, sizeof(ReplGlobal) * globals->capacity);
+  }
+  ReplGlobal *global = &globals->items[globals->count++];
+  memset(global, 0, sizeof(*global));
+  global->name = strdup(name);
+  return global;
+}
+
+static LLVMTypeRef kindType(LLVMCodeGen *gen, ReplValueKind kind) {
+  switch (kind) {
+    case REPL_INT: return gen->intType;
+    case REPL_FLOAT: return gen->floatType;
+    default: return gen->stringType;
+  }
+}
+
+//  Slot address as a typed pointer constant (entries run in this process)
+static LLVMValueRef slotPointer(LLVMCodeGen *gen, ReplGlobal *global) {
+  LLVMValueRef address = LLVMConstInt(gen->intType, (uint64_t)(uintptr_t)global->slot, 0);
+  return LLVMConstIntToPtr(address, LLVMPointerType(kindType(gen, global->kind), 0));
+}
+
+void Repl_bindGlobals(ReplSession *session, LLVMCodeGen *gen) {
+  for (int i = 0; i < session->globals.count; i++) {
+    ReplGlobal *global = &session->globals.items[i];
+    LLVMTypeRef type = kindType(gen, global->kind);
+    LLVMValueRef value = LLVMBuildLoad2(gen->builder, type, slotPointer(gen, global), global->name);
+
+    if (global->isMutable) {
+      LLVMValueRef alloca = LLVMBuildAlloca(gen->builder, type, global->name);
+      LLVMBuildStore(gen->builder, value, alloca);
+      LLVMVariableMap_set(gen->variables, global->name, alloca);
+    } else {
+      LLVMVariableMap_set(gen->variables, global->name, value);
+    }
+
+    if (global->isClosure) LLVMVariableMap_set(gen->closures, global->name, (LLVMValueRef)1);
+    if (global->isGeneric) LLVMVariableMap_set(gen->genericVariables, global->name, (LLVMValueRef)1);
+    if (global->isVoid) LLVMVariableMap_set(gen->voidVariables, global->name, (LLVMValueRef)1);
+    if (global->typeMetadata) LLVMVariableMap_set(gen->typeMetadata, global->name, global->typeMetadata);
+    if (global->returnTypeTag) LLVMVariableMap_set(gen->returnTypeTags, global->name, global->returnTypeTag);
+  }
+}
+
+//  Store one top-level variable into its slot at the end of main()
+static void captureGlobal(ReplSession *session, LLVMCodeGen *gen, const char *name) {
+  if (ReplGlobals_find(&session->pending, name)) return;
+
+  ReplGlobal *existing = ReplGlobals_find(&session->globals, name);
+  if (existing && !existing->isMutable) return;  // immutable: slot already holds its value
+
+  LLVMValueRef variable = LLVMVariableMap_get(gen->variables, name);
+  if (!variable) return;  // not defined (compile error already reported)
+
+  int isMutable = LLVMIsAAllocaInst(variable) != NULL;
+  LLVMTypeRef type = isMutable ? LLVMGetAllocatedType(variable) : LLVMTypeOf(variable);
+
+  ReplValueKind kind;
+  if (type == gen->intType) {
+    kind = REPL_INT;
+  } else if (type == gen->floatType) {
+    kind = REPL_FLOAT;
+  } else if (type == gen->stringType) {
+    kind = REPL_POINTER;
+  } else {
+    fprintf(stderr, "Note: '%s' cannot be kept for later entries (unsupported value type)\n", name);
+    return;
+  }
+
+  ReplGlobal *global = ReplGlobals_add(&session->pending, name);
+  global->kind = kind;
+  global->isMutable = isMutable;
+  if (existing && existing->kind == kind) {
+    global->slot = existing->slot;
+  } else {
+    global->slot = calloc(1, sizeof(uint64_t));
+    global->ownsSlot = true;
+  }
+  global->typeMetadata = LLVMVariableMap_get(gen->typeMetadata, name);
+  global->returnTypeTag = LLVMVariableMap_get(gen->returnTypeTags, name);
+  global->isClosure = LLVMVariableMap_get(gen->closures, name) != NULL;
+  global->isGeneric = LLVMVariableMap_get(gen->genericVariables, name) != NULL;
+  global->isVoid = LLVMVariableMap_get(gen->voidVariables, name) != NULL;
+
+  LLVMValueRef value = isMutable ? LLVMBuildLoad2(gen->builder, type, variable, name) : variable;
+  LLVMBuildStore(gen->builder, value, slotPointer(gen, global));
+}
+
+// ============================================================================
+// Result printing
+// ============================================================================
+
+static LLVMValueRef runtimeFunction(LLVMCodeGen *gen, const char *name, LLVMTypeRef returnType,
+                                    LLVMTypeRef *params, int paramCount, LLVMTypeRef *typeOut) {
+  *typeOut = LLVMFunctionType(returnType, params, paramCount, 0);
+  LLVMValueRef function = LLVMGetNamedFunction(gen->module, name);
+  if (!function) function = LLVMAddFunction(gen->module, name, *typeOut);
+  return function;
+}
+
+static LLVMValueRef callBox(LLVMCodeGen *gen, const char *name, LLVMTypeRef paramType, LLVMValueRef value) {
+  LLVMTypeRef type;
+  LLVMValueRef function = runtimeFunction(gen, name, gen->genericType, &paramType, 1, &type);
+  return LLVMBuildCall2(gen->builder, type, function, &value, 1, "repl_boxed");
+}
+
+//  Return type tag the codegen inferred for a call (-1 if unknown)
+static int callReturnTag(LLVMCodeGen *gen, AstNode *node) {
+  if (node->opcode != OP_APPLICATION || node->childCount == 0) return -1;
+  AstNode *callee = node->children[0];
+  if (callee->opcode != OP_IDENTIFIER || !callee->val) return -1;
+  LLVMValueRef tag = LLVMVariableMap_get(gen->returnTypeTags, callee->val);
+  return tag ? (int)((intptr_t)tag - 1) : -1;
+}
+
+//  Print a native integer. The runtime's boxed integers are 32-bit, so results
+// that are known to be i64 are printed directly.
+static void printInt(LLVMCodeGen *gen, LLVMValueRef value) {
+  LLVMValueRef format = LLVMBuildGlobalStringPtr(gen->builder, "%lld\n", "repl_int_format");
+  LLVMValueRef args[] = { format, value };
+  LLVMBuildCall2(gen->builder, gen->printfType, gen->printfFunc, args, 2, "");
+}
+
+static void printGeneric(LLVMCodeGen *gen, LLVMValueRef boxed) {
+  LLVMTypeRef printType;
+  LLVMTypeRef param = gen->genericType;
+  LLVMValueRef print = runtimeFunction(gen, "franz_print_generic", gen->voidType, &param, 1, &printType);
+  LLVMBuildCall2(gen->builder, printType, print, &boxed, 1, "");
+
+  LLVMValueRef empty = LLVMBuildGlobalStringPtr(gen->builder, "", "repl_newline");
+  LLVMBuildCall2(gen->builder, gen->putsType, gen->putsFunc, &empty, 1, "");
+}
+
+//  Print a closure call result: native for int/float return tags, Generic* otherwise
+static void printClosureCallResult(LLVMCodeGen *gen, LLVMValueRef value) {
+  LLVMValueRef function = LLVMGetBasicBlockParent(LLVMGetInsertBlock(gen->builder));
+  LLVMBasicBlockRef intBlock = LLVMAppendBasicBlockInContext(gen->context, function, "repl_print_int");
+  LLVMBasicBlockRef otherBlock = LLVMAppendBasicBlockInContext(gen->context, function, "repl_print_other");
+  LLVMBasicBlockRef doneBlock = LLVMAppendBasicBlockInContext(gen->context, function, "repl_print_done");
+
+  LLVMTypeRef tagType = LLVMInt32TypeInContext(gen->context);
+  LLVMValueRef isInt = LLVMBuildICmp(gen->builder, LLVMIntEQ, gen->lastClosureCallTag,
+                                     LLVMConstInt(tagType, CLOSURE_RETURN_INT, 0), "repl_is_int");
+  LLVMBuildCondBr(gen->builder, isInt, intBlock, otherBlock);
+
+  LLVMPositionBuilderAtEnd(gen->builder, intBlock);
+  printInt(gen, value);
+  LLVMBuildBr(gen->builder, doneBlock);
+
+  LLVMPositionBuilderAtEnd(gen->builder, otherBlock);
+  LLVMValueRef isFloat = LLVMBuildICmp(gen->builder, LLVMIntEQ, gen->lastClosureCallTag,
+                                       LLVMConstInt(tagType, CLOSURE_RETURN_FLOAT, 0), "repl_is_float");
+  LLVMValueRef number = LLVMBuildBitCast(gen->builder, value, gen->floatType, "repl_float");
+  LLVMValueRef boxedFloat = callBox(gen, "franz_box_float", gen->floatType, number);
+  LLVMValueRef generic = LLVMBuildIntToPtr(gen->builder, value, gen->genericType, "repl_generic");
+  printGeneric(gen, LLVMBuildSelect(gen->builder, isFloat, boxedFloat, generic, "repl_boxed"));
+  LLVMBuildBr(gen->builder, doneBlock);
+
+  LLVMPositionBuilderAtEnd(gen->builder, doneBlock);
+}
+
+//  Print the value of the entry's last statement
+static void printResult(LLVMCodeGen *gen, AstNode *node, LLVMValueRef value) {
+  LLVMTypeRef type = LLVMTypeOf(value);
+
+  if (type == gen->floatType) {
+    printGeneric(gen, callBox(gen, "franz_box_float", gen->floatType, value));
+  } else if (type == gen->intType) {
+    if (value == gen->lastClosureCallResult) {
+      printClosureCallResult(gen, value);
+    } else if (isGenericPointerNode(gen, node)) {
+      printGeneric(gen, LLVMBuildIntToPtr(gen->builder, value, gen->genericType, "repl_generic"));
+    } else if (node->opcode == OP_FUNCTION ||
+               (node->opcode == OP_IDENTIFIER && LLVMVariableMap_get(gen->closures, node->val))) {
+      LLVMValueRef closure = LLVMBuildIntToPtr(gen->builder, value, gen->genericType, "repl_closure");
+      printGeneric(gen, callBox(gen, "franz_box_closure", gen->genericType, closure));
+    } else if (callReturnTag(gen, node) == CLOSURE_RETURN_FLOAT) {
+      // Closure calls return float results as their bits
+      LLVMValueRef number = LLVMBuildBitCast(gen->builder, value, gen->floatType, "repl_float");
+      printGeneric(gen, callBox(gen, "franz_box_float", gen->floatType, number));
+    } else {
+      printInt(gen, value);
+    }
+  } else if (type == gen->stringType) {
+    if (!isGenericPointerNode(gen, node)) value = callBox(gen, "franz_box_string", gen->stringType, value);
+    printGeneric(gen, value);
+  }
+}
+
+void Repl_captureGlobals(ReplSession *session, LLVMCodeGen *gen, AstNode *ast, LLVMValueRef lastValue) {
+  AstNode **statements = &ast;
+  int statementCount = 1;
+  if (ast->opcode == OP_STATEMENT) {
+    statements = ast->children;
+    statementCount = ast->childCount;
+  }
+
+  for (int i = 0; i < statementCount; i++) {
+    AstNode *statement = statements[i];
+    if (statement && statement->opcode == OP_ASSIGNMENT && statement->childCount >= 2 &&
+        statement->children[0]->val) {
+      captureGlobal(session, gen, statement->children[0]->val);
+    }
+  }
+
+  // Mutable globals of earlier entries may have been reassigned
+  for (int i = 0; i < session->globals.count; i++) {
+    if (session->globals.items[i].isMutable) captureGlobal(session, gen, session->globals.items[i].name);
+  }
+
+  if (!session->printResult || !lastValue || statementCount == 0) return;
+  AstNode *last = statements[statementCount - 1];
+  if (last && last->opcode != OP_ASSIGNMENT) {
+    printResult(gen, last, lastValue);
+  }
+}
+
+static void commitPending(ReplSession *session) {
+  for (int i = 0; i < session->pending.count; i++) {
+    ReplGlobal pending = session->pending.items[i];
+    pending.ownsSlot = false;
+
+    ReplGlobal *existing = ReplGlobals_find(&session->globals, pending.name);
+    if (existing) {
+      if (existing->slot != pending.slot) free(existing->slot);
+      free(existing->name);
+      *existing = pending;
+    } else {
+      ReplGlobal *global = ReplGlobals_add(&session->globals, pending.name);
+      free(global->name);
+      *global = pending;
+    }
+  }
+  session->pending.count = 0;
+}
+
+static void discardPending(ReplSession *session) {
+  for (int i = 0; i < session->pending.count; i++) {
+    if (session->pending.items[i].ownsSlot) free(session->pending.items[i].slot);
+    free(session->pending.items[i].name);
+  }
+  session->pending.count = 0;
+}
+
+// ============================================================================
+// Evaluation
+// ============================================================================
+
+//  The lexer and parser collect syntax errors instead of exiting. Report them
+// and skip the entry so a typo does not end the session.
+static bool parsesCleanly(char *code, int length) {
+  TokenArray *tokens = lex(code, length);
+  AstNode *ast = parseProgram(tokens);
+  if (ast) AstNode_free(ast);
+  TokenArray_free(tokens);
+
+  if (Diagnostic_count() == 0) return true;
+  Diagnostic_flush(stdout);
+  fflush(stdout);
+  return false;
+}
+
+static sigjmp_buf g_crashJump;
+
+static void onCrash(int sig) {
+  siglongjmp(g_crashJump, sig);
+}
+
+//  Run an entry's main(). A crash in the entry (e.g. a segfault) is reported and
+// the session continues. Returns 0, or the signal that stopped the entry.
+static int runEntry(LLVMExecutionEngineRef engine) {
+  uint64_t mainAddress = LLVMGetFunctionAddress(engine, "main");
+  if (mainAddress == 0) {
+    fprintf(stderr, "Failed to JIT compile main()\n");
+    return -1;
+  }
+
+  // Handlers run on their own stack so stack overflows are caught too
+  static char *alternateStack = NULL;
+  if (!alternateStack) {
+    alternateStack = malloc(SIGSTKSZ * 4);
+    stack_t stack = { .ss_sp = alternateStack, .ss_size = SIGSTKSZ * 4, .ss_flags = 0 };
+    sigaltstack(&stack, NULL);
+  }
+
+  const int signals[] = { SIGSEGV, SIGBUS, SIGFPE, SIGILL };
+  const int signalCount = sizeof(signals) / sizeof(signals[0]);
+  struct sigaction action, previous[4];
+  memset(&action, 0, sizeof(action));
+  action.sa_handler = onCrash;
+  action.sa_flags = SA_ONSTACK | SA_NODEFER;
+  sigemptyset(&action.sa_mask);
+  for (int i = 0; i < signalCount; i++) sigaction(signals[i], &action, &previous[i]);
+
+  int sig = sigsetjmp(g_crashJump, 1);
+  if (sig == 0) {
+    char *argv[] = { "franz", NULL };
+    int (*entryMain)(int, char **) = (int (*)(int, char **))(intptr_t)mainAddress;
+    entryMain(1, argv);
+  }
+
+  for (int i = 0; i < signalCount; i++) sigaction(signals[i], &previous[i], NULL);
+  fflush(NULL);
+
+  if (sig != 0) fprintf(stderr, "Runtime error: %s\n", strsignal(sig));
+  return sig;
+}
+
+static void evaluate(ReplSession *session, char *code) {
+  int length = (int)strlen(code);
+  Diagnostic_setPrimarySource(Diagnostic_addSource("<repl>", code, length));
+  if (!parsesCleanly(code, length)) return;
+
+  TokenArray *tokens = lex(code, length);
+  AstNode *ast = parseProgram(tokens);
+  char *type = ReplTypes_infer(session->types, ast, 1);
+  session->printResult = type == NULL || strcmp(type, "void") != 0;
+  free(type);
+
+  LLVMCodeGen *gen = LLVMCodeGen_new("franz_repl");
+  if (!gen) {
+    fprintf(stderr, "ERROR: Failed to initialize LLVM code generator\n");
+    AstNode_free(ast);
+    TokenArray_free(tokens);
+    return;
+  }
+  gen->debugMode = session->options->debug;
+  gen->enableTCO = session->options->enable_tco;
+  gen->optLevel = session->options->optLevel;
+  gen->trace = session->options->trace;
+  gen->tracePattern = session->options->tracePattern;
+  gen->profile = session->options->profile;
+  gen->replSession = session;
+
+  LLVMExecutionEngineRef engine = NULL;
+  if (LLVMCodeGen_compile(gen, ast, session->globalScope) == 0) {
+    if (session->options->debug) LLVMCodeGen_dumpIR(gen);
+    engine = LLVMCodeGen_createJIT(gen);
+  }
+
+  if (!engine) {
+    discardPending(session);
+    LLVMCodeGen_free(gen);
+    AstNode_free(ast);
+    TokenArray_free(tokens);
+    return;
+  }
+
+  if (runEntry(engine) == 0) {
+    commitPending(session);
+    ReplTypes_record(session->types, ast);
+  } else {
+    discardPending(session);
+  }
+
+  session->entries = realloc(session->entries, sizeof(ReplEntry) * (session->entryCount + 1));
+  session->entries[session->entryCount++] = (ReplEntry){ gen, engine, tokens, ast };
+}
+
+// ============================================================================
+// Commands and input
+// ============================================================================
+
+static void printHelp(void) {
+  printf("Enter Franz statements or expressions; the value of an expression is printed.\n");
+  printf("Input continues on the next line while (), {} or [] are open.\n\n");
+  printf("  :type <expr>   Show the inferred type of an expression\n");
+  printf("  :help          Show this help\n");
+  printf("  :quit          Leave the REPL (or Ctrl-D)\n");
+}
+
+static void showType(ReplSession *session, char *code) {
+  int length = (int)strlen(code);
+  if (length == 0) {
+    fprintf(stderr, "Usage: :type <expr>\n");
+    return;
+  }
+  Diagnostic_setPrimarySource(Diagnostic_addSource("<repl>", code, length));
+  if (!parsesCleanly(code, length)) return;
+
+  TokenArray *tokens = lex(code, length);
+  AstNode *ast = parseProgram(tokens);
+  char *type = ReplTypes_infer(session->types, ast, 0);
+  if (type) {
+    printf("%s : %s\n", code, type);
+    free(type);
+  }
+  AstNode_free(ast);
+  TokenArray_free(tokens);
+}
+
+//  Handle a :command. Returns false to end the session.
+static bool runCommand(ReplSession *session, char *line) {
+  char *argument = line + strcspn(line, " \t");
+  if (*argument) *argument++ = '\0';
+  argument += strspn(argument, " \t");
+
+  if (strcmp(line, ":quit") == 0 || strcmp(line, ":q") == 0) {
+    return false;
+  } else if (strcmp(line, ":help") == 0 || strcmp(line, ":h") == 0) {
+    printHelp();
+  } else if (strcmp(line, ":type") == 0 || strcmp(line, ":t") == 0) {
+    showType(session, argument);
+  } else {
+    fprintf(stderr, "Unknown command '%s'. Type :help for a list of commands.\n", line);
+  }
+  return true;
+}
+
+//  Net count of open (), {} and [] in a line, ignoring strings and comments
+static int bracketDepth(const char *line, bool *openString) {
+  int depth = 0;
+  bool inString = false;
+  for (const char *p = line; *p; p++) {
+    if (inString) {
+      if (*p == '\\' && p[1]) p++;
+      else if (*p == '"') inString = false;
+    } else if (*p == '"') {
+      inString = true;
+    } else if (*p == '/' && p[1] == '/') {
+      break;
+    } else if (*p == '(' || *p == '{' || *p == '[') {
+      depth++;
+    } else if (*p == ')' || *p == '}' || *p == ']') {
+      depth--;
+    }
+  }
+  *openString = inString;
+  return depth;
+}
+
+//  Read one entry: a line, plus continuation lines while brackets are open.
+// Returns NULL at end of input. Caller frees the entry.
+static char *readEntry(bool interactive) {
+  char *entry = NULL;
+  size_t entryLength = 0;
+  int depth = 0;
+  char line[4096];
+
+  for (;;) {
+    if (interactive) {
+      printf(entry ? "  ...> " : "franz> ");
+      fflush(stdout);
+    }
+    if (!fgets(line, sizeof(line), stdin)) {
+      if (interactive && !entry) printf("\n");
+      return entry;
+    }
+
+    size_t lineLength = strlen(line);
+    entry = realloc(entry, entryLength + lineLength + 1);
+    memcpy(entry + entryLength, line, lineLength + 1);
+    entryLength += lineLength;
+
+    // Strings cannot span lines: let the parser report an unterminated one
+    bool openString;
+    depth += bracketDepth(line, &openString);
+    if ((depth <= 0 || openString) && (lineLength == 0 || line[lineLength - 1] == '\n' || feof(stdin))) {
+      return entry;
+    }
+  }
+}
+
+static char *trim(char *text) {
+  while (*text == ' ' || *text == '\t' || *text == '\n' || *text == '\r') text++;
+  char *end = text + strlen(text);
+  while (end > text && (end[-1] == ' ' || end[-1] == '\t' || end[-1] == '\n' || end[-1] == '\r')) end--;
+  *end = '\0';
+  return text;
+}
+
+// ============================================================================
+// Session
+// ============================================================================
+
+int Repl_run(RunOptions *options) {
+  ReplSession session;
+  memset(&session, 0, sizeof(session));
+  session.options = options;
+
+  initEvents();
+  session.globalScope = newGlobal(0, NULL);
+  session.types = ReplTypes_new();
+
+  bool interactive = isatty(STDIN_FILENO);
+  if (interactive) {
+    printf("Franz REPL. Type :help for commands, :quit or Ctrl-D to exit.\n");
+  }
+
+  char *entry;
+  while ((entry = readEntry(interactive)) != NULL) {
+    char *code = trim(entry);
+    bool keepGoing = true;
+
+    if (code[0] == ':') {
+      keepGoing = runCommand(&session, code);
+    } else if (code[0] != '\0') {
+      evaluate(&session, code);
+    }
+
+    free(entry);
+    fflush(stdout);
+    if (!keepGoing) break;
+  }
+
+  // Engines before their code generators: each engine's module lives in its gen's context
+  for (int i = session.entryCount - 1; i >= 0; i--) {
+    LLVMDisposeExecutionEngine(session.entries[i].engine);
+    LLVMCodeGen_free(session.entries[i].gen);
+    AstNode_free(session.entries[i].ast);
+    TokenArray_free(session.entries[i].tokens);
+  }
+  free(session.entries);
+
+  for (int i = 0; i < session.globals.count; i++) {
+    free(session.globals.items[i].name);
+    free(session.globals.items[i].slot);
+  }
+  free(session.globals.items);
+  free(session.pending.items);
+
+  ReplTypes_free(session.types);
+  Scope_free(session.globalScope);
+  return 0;
+}
@@ -0,0 +1,57 @@
+#ifndef REPL_H
+#define REPL_H
+
+#include "../ast.h"
+#include "../run.h"
+#include "../llvm-codegen/llvm_codegen.h"
+
+/**
+ * Interactive REPL for Franz (`franz repl`)
+ *
+ * Every entry is compiled to its own LLVM module and run with MCJIT. The
+ * machine code of earlier entries stays loaded for the whole session, so
+ * functions and closures defined in one entry can be called from the next.
+ *
+ * Top-level variables are carried between entries through per-variable
+ * slots: the entry that defines a variable stores its final value into the
+ * slot, and later entries load it before their first statement (mutable
+ * variables are copied back into an alloca so they stay assignable).
+ *
+ * The value of an entry that ends in an expression is printed with
+ * franz_print_generic. Entries continue over several lines while
+ * (), {} or [] are open.
+ */
+
+typedef struct ReplSession ReplSession;
+
+/**
+ * Run the REPL on stdin until EOF or :quit
+ *
+ * @param options - Compilation options (-d, -O<n>, --no-tco)
+ * @return Exit code
+ */
+int Repl_run(RunOptions *options);
+
+/**
+ * Bind the globals of earlier entries at the start of main()
+ *
+ * Called by LLVMCodeGen_compile before the first statement of an entry.
+ *
+ * @param session - REPL session
+ * @param gen - Code generator of the entry (builder positioned in main)
+ */
+void Repl_bindGlobals(ReplSession *session, LLVMCodeGen *gen);
+
+/**
+ * Store the globals of an entry into their slots and print its value
+ *
+ * Called by LLVMCodeGen_compile after the last statement of an entry.
+ *
+ * @param session - REPL session
+ * @param gen - Code generator of the entry (builder positioned in main)
+ * @param ast - Entry AST (OP_STATEMENT)
+ * @param lastValue - Value of the last statement, or NULL
+ */
+void Repl_captureGlobals(ReplSession *session, LLVMCodeGen *gen, AstNode *ast, LLVMValueRef lastValue);
+
+#endif
@@ -0,0 +1,71 @@
+#include "repl_types.h"
+#include <stdio.h>
+#include <stdlib.h>
+#include <fcntl.h>
+#include <unistd.h>
+#include "../types.h"
+#include "../typeinfer.h"
+#include "../typecheck.h"
+
+struct ReplTypes {
+  InferContext *ctx;
+};
+
+ReplTypes *ReplTypes_new(void) {
+  ReplTypes *types = malloc(sizeof(ReplTypes));
+  types->ctx = InferContext_new();
+  typecheck_add_stdlib(types->ctx->env);
+  return types;
+}
+
+void ReplTypes_free(ReplTypes *types) {
+  InferContext_free(types->ctx);
+  free(types);
+}
+
+//  Type inference reports errors for code the compiler accepts; keep those quiet
+// unless the user asked for a type
+static int muteStderr(void) {
+  fflush(stderr);
+  int saved = dup(STDERR_FILENO);
+  int devNull = open("/dev/null", O_WRONLY);
+  if (devNull >= 0) {
+    dup2(devNull, STDERR_FILENO);
+    close(devNull);
+  }
+  return saved;
+}
+
+static void restoreStderr(int saved) {
+  fflush(stderr);
+  if (saved >= 0) {
+    dup2(saved, STDERR_FILENO);
+    close(saved);
+  }
+}
+
+char *ReplTypes_infer(ReplTypes *types, AstNode *ast, int quiet) {
+  InferContext *ctx = types->ctx;
+  int saved = quiet ? muteStderr() : -1;
+
+  // Definitions go into a scratch environment that is dropped afterwards
+  TypeEnv *scratch = TypeEnv_new(ctx->env);
+  ctx->env = scratch;
+
+  Type *type = infer(ast, ctx, 1);
+  char *name = ctx->error_count == 0 ? Type_to_string(Substitution_apply(ctx, type)) : NULL;
+
+  ctx->env = scratch->parent;
+  ctx->error_count = 0;
+  TypeEnv_free(scratch);
+
+  if (quiet) restoreStderr(saved);
+  return name;
+}
+
+void ReplTypes_record(ReplTypes *types, AstNode *ast) {
+  int saved = muteStderr();
+  (void) infer(ast, types->ctx, 1);
+  types->ctx->error_count = 0;
+  restoreStderr(saved);
+}
@@ -0,0 +1,42 @@
+#ifndef REPL_TYPES_H
+#define REPL_TYPES_H
+
+#include "../ast.h"
+
+/**
+ * Type inference for REPL entries (`:type` and result printing)
+ *
+ * Kept apart from repl.c: the type checker's types.h and the runtime's
+ * generic.h cannot be included in the same file.
+ */
+
+typedef struct ReplTypes ReplTypes;
+
+/**
+ * Create a type environment holding the standard library signatures
+ *
+ * @return Type environment, freed with ReplTypes_free
+ */
+ReplTypes *ReplTypes_new(void);
+
+void ReplTypes_free(ReplTypes *types);
+
+/**
+ * Infer the type of an entry without keeping its definitions
+ *
+ * @param types - Types of earlier entries
+ * @param ast - Entry AST
+ * @param quiet - Do not report type errors
+ * @return Type as text (caller frees), or NULL if inference failed
+ */
+char *ReplTypes_infer(ReplTypes *types, AstNode *ast, int quiet);
+
+/**
+ * Remember the types of the definitions in an entry that ran
+ *
+ * @param types - Types of earlier entries
+ * @param ast - Entry AST
+ */
+void ReplTypes_record(ReplTypes *types, AstNode *ast);
+
+#endif
@@ -5,8 +5,11 @@
 #include <signal.h>
 #include <stdio.h>
 #include <stdlib.h>
+#include <string.h>
 #include <sys/wait.h>
 #include <unistd.h>  //  For access() function
+#include <limits.h>
+#include <errno.h>
 
 #include "tokens.h"
 #include "lex.h"
@@ -19,241 +22,419 @@
 
 //  LLVM native compilation (default and only mode)
 #include "llvm-codegen/llvm_codegen.h"
+#include "toolchain/toolchain.h"
+#include "build-cache/build_cache.h"
+#include "diagnostics/diagnostic.h"
+#include "dump/dump.h"
+#include "interpret/interpret.h"
+#include "bytecode/bytecode.h"
+
+#ifndef PATH_MAX
+#define PATH_MAX 4096
+#endif
 
 void exitHandler() {
   exit(0);
 }
 
-int run(char *code, long length, int argc, char *argv[], bool debug, bool enable_tco) {
-  if (debug) {
-    printf("\nTOKENS\n");
-  }
+void RunOptions_init(RunOptions *options) {
+  options->debug = false;
+  options->enable_tco = true;  // TCO: Enabled by default, use --no-tco to disable
+  options->outputPath = NULL;
+  options->jit = false;
+  options->emit = EMIT_NONE;
+  options->optLevel = 0;
+  options->cache = true;
+  options->target = NULL;
+  options->sysroot = NULL;
+  options->debugInfo = false;
+  options->sourcePath = NULL;
+  options->dumpTokens = false;
+  options->dumpAst = false;
+  options->coverage = false;
+  options->trace = false;
+  options->tracePattern = NULL;
+  options->profile = false;
+  options->interpret = false;
+  options->bytecode = false;
+}
 
-  /*  Lex with array-based tokens */
-  TokenArray *tokens = lex(code, length);
+//  --emit=<kind> names; returns EMIT_NONE for unknown names
+EmitKind EmitKind_parse(const char *name) {
+  if (strcmp(name, "ir") == 0 || strcmp(name, "llvm-ir") == 0) return EMIT_IR;
+  if (strcmp(name, "bc") == 0 || strcmp(name, "llvm-bc") == 0) return EMIT_BC;
+  if (strcmp(name, "asm") == 0) return EMIT_ASM;
+  if (strcmp(name, "obj") == 0) return EMIT_OBJ;
+  if (strcmp(name, "franzc") == 0) return EMIT_FRANZC;
+  return EMIT_NONE;
+}
 
-  if (debug) {
-    // print tokens
-    TokenArray_print(tokens);
-    printf("Token Count: %i\n", tokens->count);
-    printf("\nAST\n");
+//  Default file extension for each --emit kind
+const char *EmitKind_extension(EmitKind kind) {
+  switch (kind) {
+    case EMIT_IR: return ".ll";
+    case EMIT_BC: return ".bc";
+    case EMIT_ASM: return ".s";
+    case EMIT_OBJ: return ".o";
+    case EMIT_FRANZC: return ".franzc";
+    default: return "";
   }
+}
 
-  /*  Parse with array-based tokens */
-  AstNode *p_headAstNode = parseProgram(tokens);
+//  Write the compiled module in the format selected with --emit
+static int emitArtifact(LLVMCodeGen *codegen, EmitKind kind, const char *filename) {
+  switch (kind) {
+    case EMIT_IR: return LLVMCodeGen_writeIRToFile(codegen, filename);
+    case EMIT_BC: return LLVMCodeGen_writeToFile(codegen, filename);
+    case EMIT_ASM: return LLVMCodeGen_emitAssembly(codegen, filename);
+    case EMIT_OBJ: return LLVMCodeGen_emitObject(codegen, filename);
+    default: return -1;
+  }
+}
 
-  if (debug) {
-    // print AST
-    AstNode_print(p_headAstNode, 0);
-    printf("\nLLVM NATIVE COMPILATION\n");
+//  Run a native executable with the program arguments (argv without a program name)
+// and wait for it. stdin, stdout and stderr are inherited, so piped data reaches `input`.
+// Returns the program's exit code, or 128 + signal number if it was killed (as a shell would).
+static int runExecutable(const char *exeFilename, int argc, char *argv[]) {
+  char **execArgv = malloc(sizeof(char *) * (argc + 2));
+  execArgv[0] = (char *)exeFilename;
+  for (int i = 0; i < argc; i++) execArgv[i + 1] = argv[i];
+  execArgv[argc + 1] = NULL;
+
+  pid_t pid = fork();
+  if (pid < 0) {
+    perror("ERROR: Failed to start program");
+    free(execArgv);
+    return 1;
   }
 
-  /*  LLVM native compilation (Rust-level performance) */
-  initEvents();
+  if (pid == 0) {
+    execv(exeFilename, execArgv);
+    perror("ERROR: Failed to execute program");
+    _exit(127);
+  }
 
-  // cleanly handle exit events
-  signal(SIGTERM, exitHandler);
-  signal(SIGINT, exitHandler);
+  free(execArgv);
 
-  // Create global scope with stdlib
-  Scope *p_global = newGlobal(argc, argv);
+  int status = 0;
+  while (waitpid(pid, &status, 0) < 0) {
+    if (errno != EINTR) return 1;
+  }
 
-  // Initialize LLVM code generator
-  LLVMCodeGen *codegen = LLVMCodeGen_new("franz_module");
-  if (!codegen) {
-    fprintf(stderr, "ERROR: Failed to initialize LLVM code generator\n");
-    AstNode_free(p_headAstNode);
-    TokenArray_free(tokens);
-    Scope_free(p_global);
-    return 1;
+  if (WIFSIGNALED(status)) {
+    fprintf(stderr, "%s\n", strsignal(WTERMSIG(status)));
+    return 128 + WTERMSIG(status);
   }
+  return WEXITSTATUS(status);
+}
 
-  codegen->debugMode = debug;
-  codegen->enableTCO = enable_tco;  // TCO: Enabled by default, disabled with --no-tco flag
+//  Lower the compiled module to a native executable at exeFilename
+// Emits an object file into workDir and links it with the runtime library
+// (built once per toolchain in the cache directory) using the discovered C compiler driver.
+// Returns 0 on success, 1 on failure (error already reported).
+static int emitExecutable(LLVMCodeGen *codegen, const char *exeFilename, const char *workDir,
+                          RunOptions *options) {
+  bool debug = options->debug;
+  char objFilename[PATH_MAX + 16];
+  snprintf(objFilename, sizeof(objFilename), "%s/program.o", workDir);
+
+  // Linker and runtime sources are discovered, not assumed (works on Linux and macOS)
+  //  Cross builds use the target's compiler driver and archiver for linking and the runtime
+  const char *cc = Toolchain_ccForTarget(options->target, options->sysroot);
+  if (cc == NULL) {
+    return 1;
+  }
+  const char *ar = Toolchain_arForTarget(options->target);
+  const char *root = Toolchain_runtimeRoot();
 
   if (debug) {
-    printf("[DEBUG] Compiling AST to LLVM IR...\n");
-    if (enable_tco) {
-      printf("[DEBUG] Tail Call Optimization ENABLED (default)\n");
-    } else {
-      printf("[DEBUG] Tail Call Optimization DISABLED (--no-tco flag used)\n");
-    }
+    printf("[DEBUG] Emitting object file %s...\n", objFilename);
+    printf("[DEBUG] C compiler: %s, archiver: %s, runtime sources: %s\n", cc, ar, root);
     fflush(stdout);
   }
 
-  // Compile AST to LLVM IR ( stub prints status)
-  int compileResult = LLVMCodeGen_compile(codegen, p_headAstNode, p_global);
+  // Compile LLVM module to object file through the LLVM TargetMachine API
+  if (LLVMCodeGen_emitObject(codegen, objFilename) != 0) {
+    fprintf(stderr, "ERROR: Failed to compile LLVM IR to object file\n");
+    return 1;
+  }
 
-  if (compileResult != 0) {
-    fprintf(stderr, "ERROR: LLVM compilation failed\n");
-    LLVMCodeGen_free(codegen);
-    AstNode_free(p_headAstNode);
-    TokenArray_free(tokens);
-    Scope_free(p_global);
+  //  Runtime library and objects: rebuilt only when their sources change
+  char runtimeDir[PATH_MAX];
+  if (BuildCache_runtimeDir(cc, ar, root, workDir, runtimeDir, sizeof(runtimeDir), debug) != 0) {
     return 1;
   }
 
   if (debug) {
-    printf("[DEBUG] Compilation complete\n");
-    LLVMCodeGen_dumpIR(codegen);
+    printf("[DEBUG] Linking object file to executable...\n");
     fflush(stdout);
   }
 
-  //  Write LLVM IR to file and compile to native executable
-  const char *llFilename = "/tmp/franz_output.ll";
-  const char *objFilename = "/tmp/franz_output.o";
-  const char *exeFilename = "/tmp/franz_output";
+  // Link object file + runtime library to executable using the C compiler driver
+  //  Include dict.o and stdlib.o for dict runtime support
+  char linkCmd[PATH_MAX * 10];
+  snprintf(linkCmd, sizeof(linkCmd),
+           "%s '%s' '%s/number_parse.o' '%s/terminal_runtime.o' '%s/llvm_repeat.o' "
+           "'%s/dict.o' '%s/stdlib.o' '%s/libfranz_runtime.a' -lm -o '%s'",
+           cc, objFilename, runtimeDir, runtimeDir, runtimeDir,
+           runtimeDir, runtimeDir, runtimeDir, exeFilename);
 
   if (debug) {
-    printf("[DEBUG] Writing LLVM IR to %s\n", llFilename);
+    printf("[DEBUG] Linking command: %s\n", linkCmd);
     fflush(stdout);
   }
 
-  // Write LLVM IR to .ll file
-  char *error = NULL;
-  if (LLVMPrintModuleToFile(codegen->module, llFilename, &error)) {
-    fprintf(stderr, "ERROR: Failed to write LLVM IR to file: %s\n", error);
-    LLVMDisposeMessage(error);
-    LLVMCodeGen_free(codegen);
-    AstNode_free(p_headAstNode);
-    TokenArray_free(tokens);
-    Scope_free(p_global);
+  int linkResult = system(linkCmd);
+
+  if (linkResult != 0) {
+    fprintf(stderr, "ERROR: Failed to link executable\n");
     return 1;
   }
 
-  if (debug) {
-    printf("[DEBUG] Compiling LLVM IR to object file...\n");
-    fflush(stdout);
+  return 0;
+}
+
+//  Compilation cache path of the executable for this program and these options
+// Returns false if the program must be compiled without the cache
+// (JIT, interpreter, bytecode VM, build/emit output, debug or JSON dumps, --no-cache, or no cache directory).
+static bool cachedProgramPath(char *code, long length, RunOptions *options, char *out, size_t outSize) {
+  if (!options->cache || options->debug || options->jit || options->interpret || options->bytecode ||
+      options->dumpTokens || options->dumpAst ||
+      options->emit != EMIT_NONE || options->outputPath != NULL) {
+    return false;
   }
 
-  // Compile LLVM IR to object file using llc
-  char llcCmd[512];
-  snprintf(llcCmd, sizeof(llcCmd), "/opt/homebrew/opt/llvm@17/bin/llc -filetype=obj %s -o %s",
-           llFilename, objFilename);
-  int llcResult = system(llcCmd);
+  const char *cc = Toolchain_cc();
+  if (cc == NULL) return false;
 
-  if (llcResult != 0) {
-    fprintf(stderr, "ERROR: Failed to compile LLVM IR to object file\n");
-    LLVMCodeGen_free(codegen);
-    AstNode_free(p_headAstNode);
-    TokenArray_free(tokens);
-    Scope_free(p_global);
-    return 1;
+  // Everything besides the sources that changes the generated executable
+  // (debug info and coverage data name the source file, so -g and --coverage builds
+  // are keyed by its absolute path)
+  char sourcePath[PATH_MAX] = "<stdin>";
+  if (options->sourcePath && realpath(options->sourcePath, sourcePath) == NULL) {
+    snprintf(sourcePath, sizeof(sourcePath), "%s", options->sourcePath);
   }
 
-  if (debug) {
-    printf("[DEBUG] Linking object file to executable...\n");
+  char config[PATH_MAX * 4];
+  snprintf(config, sizeof(config), "O%d tco=%d scoping=%s cc=%s runtime=%s g=%s coverage=%s trace=%s profile=%d",
+           options->optLevel, options->enable_tco ? 1 : 0,
+           ScopingMode_name(g_scoping_mode), cc, Toolchain_runtimeRoot(),
+           options->debugInfo ? sourcePath : "-", options->coverage ? sourcePath : "-",
+           !options->trace ? "-" : options->tracePattern ? options->tracePattern : "*",
+           options->profile ? 1 : 0);
+
+  char key[32];
+  BuildCache_programKey(code, length, config, Toolchain_runtimeRoot(), key, sizeof(key));
+  return BuildCache_programPath(key, out, outSize) == 0;
+}
+
+//  Report the syntax errors collected by the lexer and parser
+// Returns the number of errors (0 = nothing printed)
+static int reportSyntaxErrors(void) {
+  int syntaxErrors = Diagnostic_count();
+  if (syntaxErrors > 0) {
+    Diagnostic_flush(stdout);
     fflush(stdout);
+    if (Diagnostic_getFormat() == DIAGNOSTIC_FORMAT_HUMAN) {
+      fprintf(stderr, "ERROR: aborting due to %d syntax error%s\n", syntaxErrors, syntaxErrors == 1 ? "" : "s");
+    }
+  }
+  return syntaxErrors;
+}
+
+//  Run a precompiled .franzc program on the bytecode VM (no lexing, parsing or compiling)
+static int runImage(char *code, long length, int argc, char *argv[], RunOptions *options) {
+  const char *displayPath = options->sourcePath ? options->sourcePath : "<stdin>";
+  if (options->outputPath != NULL || options->emit != EMIT_NONE || options->jit || options->interpret ||
+      options->dumpTokens || options->dumpAst || options->debugInfo || options->coverage || options->trace ||
+      options->profile) {
+    fprintf(stderr, "Error: '%s' is a precompiled .franzc program; it can only be run (franz %s).\n",
+            displayPath, displayPath);
+    return 1;
   }
 
-  //  Compile runtime dependencies for runtime linking
-  const char *numberParseC = "src/number-formats/number_parse.c";
-  const char *numberParseObj = "/tmp/number_parse.o";
+  BytecodeProto *proto = Bytecode_read(code, length, displayPath);
+  if (proto == NULL) return 1;
 
-  char compileNumberCmd[512];
-  snprintf(compileNumberCmd, sizeof(compileNumberCmd),
-           "gcc -c %s -o %s 2>/dev/null", numberParseC, numberParseObj);
-  system(compileNumberCmd);  // Compile number_parse.c to object file
+  if (options->debug) {
+    printf("\nBYTECODE\n");
+    Bytecode_disassemble(stdout, proto);
+    fflush(stdout);
+  }
 
-  //  Compile terminal_runtime.c for runtime linking
-  const char *terminalRuntimeC = "src/llvm-terminal/terminal_runtime.c";
-  const char *terminalRuntimeObj = "/tmp/terminal_runtime.o";
+  initEvents();
+  signal(SIGTERM, exitHandler);
+  signal(SIGINT, exitHandler);
 
-  char compileTerminalCmd[512];
-  snprintf(compileTerminalCmd, sizeof(compileTerminalCmd),
-           "gcc -c %s -o %s 2>/dev/null", terminalRuntimeC, terminalRuntimeObj);
-  system(compileTerminalCmd);  // Compile terminal_runtime.c to object file
+  Scope *p_global = newGlobal(argc, argv);
+  int exitCode = BytecodeVM_run(proto, p_global);
+  Scope_free(p_global);
+  Bytecode_free(proto);
+  FileCache_free();
+  return exitCode;
+}
 
-  //  Compile llvm_repeat.c for runtime linking
-  const char *repeatC = "src/llvm-terminal/llvm_repeat.c";
-  const char *repeatObj = "/tmp/llvm_repeat.o";
+int run(char *code, long length, int argc, char *argv[], RunOptions *options) {
+  bool debug = options->debug;
+  bool enable_tco = options->enable_tco;
 
-  char compileRepeatCmd[512];
-  snprintf(compileRepeatCmd, sizeof(compileRepeatCmd),
-           "gcc -c %s -o %s 2>/dev/null", repeatC, repeatObj);
-  system(compileRepeatCmd);  // Compile llvm_repeat.c to object file
+  //  .franzc files are recognized by their signature, whatever their name
+  if (Bytecode_isImage(code, length)) {
+    return runImage(code, length, argc, argv, options);
+  }
 
-  //  Compile dict.c for runtime linking (dictionary/hash map support)
-  const char *dictC = "src/dict.c";
-  const char *dictObj = "/tmp/dict.o";
+  //  Cache hit: run the executable linked for an identical program, skipping
+  // lexing, parsing and code generation entirely
+  char cachedProgram[PATH_MAX];
+  bool useCache = cachedProgramPath(code, length, options, cachedProgram, sizeof(cachedProgram));
+  if (useCache && access(cachedProgram, X_OK) == 0) {
+    BuildCache_markUsed(cachedProgram);
+    return runExecutable(cachedProgram, argc, argv);
+  }
 
   if (debug) {
-    printf("[DEBUG] Compiling dict.c...\n");
-    fflush(stdout);
+    printf("\nTOKENS\n");
   }
 
-  char compileDictCmd[512];
-  snprintf(compileDictCmd, sizeof(compileDictCmd),
-           "gcc -c %s -o %s", dictC, dictObj);
+  //  Register the program so syntax, compile and runtime errors can quote it
+  const char *displayPath = options->sourcePath ? options->sourcePath : "<stdin>";
+  Diagnostic_setPrimarySource(Diagnostic_addSource(displayPath, code, length));
+
+  /*  Lex with array-based tokens */
+  TokenArray *tokens = lex(code, length);
 
   if (debug) {
-    printf("[DEBUG] Dict compile command: %s\n", compileDictCmd);
-    fflush(stdout);
+    // print tokens
+    TokenArray_print(tokens);
+    printf("Token Count: %i\n", tokens->count);
+    printf("\nAST\n");
   }
 
-  int dictCompileResult = system(compileDictCmd);
+  //  --dump-tokens=json: the lexer's output is printed even if the program does not parse
+  if (options->dumpTokens) {
+    Dump_tokens(stdout, tokens);
+    if (!options->dumpAst) {
+      int lexErrors = reportSyntaxErrors();
+      TokenArray_free(tokens);
+      return lexErrors > 0 ? 1 : 0;
+    }
+  }
 
-  if (dictCompileResult != 0) {
-    fprintf(stderr, "[ERROR] Failed to compile dict.c (exit code: %d)\n", dictCompileResult);
-  } else if (debug) {
-    printf("[DEBUG] Successfully compiled dict.c to %s\n", dictObj);
-    // Verify symbols in dict.o
-    system("nm /tmp/dict.o | grep -c Dict 2>/dev/null | xargs -I{} printf '[DEBUG] dict.o contains {} Dict symbols\\n'");
-    fflush(stdout);
+  /*  Parse with array-based tokens */
+  AstNode *p_headAstNode = parseProgram(tokens);
+
+  //  Syntax errors are collected by the lexer and parser; report all of them
+  //  and refuse to generate code for a partial AST
+  if (reportSyntaxErrors() > 0) {
+    if (p_headAstNode) AstNode_free(p_headAstNode);
+    TokenArray_free(tokens);
+    return 1;
   }
 
-  //  Compile stdlib.c for runtime linking (dict wrapper functions)
-  const char *stdlibC = "src/stdlib.c";
-  const char *stdlibObj = "/tmp/stdlib.o";
+  //  --dump-ast=json: print the parsed program and stop before code generation
+  if (options->dumpAst) {
+    Dump_ast(stdout, p_headAstNode);
+    AstNode_free(p_headAstNode);
+    TokenArray_free(tokens);
+    return 0;
+  }
 
   if (debug) {
-    printf("[DEBUG] Compiling stdlib.c...\n");
-    fflush(stdout);
+    // print AST
+    AstNode_print(p_headAstNode, 0);
+    printf(options->interpret ? "\nINTERPRETER\n" : options->bytecode || options->emit == EMIT_FRANZC
+           ? "\nBYTECODE\n" : "\nLLVM NATIVE COMPILATION\n");
   }
 
-  char compileStdlibCmd[512];
-  snprintf(compileStdlibCmd, sizeof(compileStdlibCmd),
-           "gcc -c %s -o %s", stdlibC, stdlibObj);
+  //  --emit=franzc: compile to bytecode and write it, without LLVM or a runtime
+  if (options->emit == EMIT_FRANZC) {
+    BytecodeProto *proto = Bytecode_compile(p_headAstNode);
+    if (debug) Bytecode_disassemble(stdout, proto);
 
-  if (debug) {
-    printf("[DEBUG] Stdlib compile command: %s\n", compileStdlibCmd);
-    fflush(stdout);
+    int exitCode = 0;
+    if (Bytecode_write(proto, options->outputPath) != 0) {
+      fprintf(stderr, "ERROR: Failed to write %s\n", options->outputPath);
+      exitCode = 1;
+    }
+    Bytecode_free(proto);
+    AstNode_free(p_headAstNode);
+    TokenArray_free(tokens);
+    return exitCode;
   }
 
-  int stdlibCompileResult = system(compileStdlibCmd);
+  initEvents();
 
-  if (stdlibCompileResult != 0) {
-    fprintf(stderr, "[ERROR] Failed to compile stdlib.c (exit code: %d)\n", stdlibCompileResult);
-  } else if (debug) {
-    printf("[DEBUG] Successfully compiled stdlib.c to %s\n", stdlibObj);
-    // Verify franz_dict_* symbols in stdlib.o
-    system("nm /tmp/stdlib.o | grep -c franz_dict 2>/dev/null | xargs -I{} printf '[DEBUG] stdlib.o contains {} franz_dict symbols\\n'");
-    fflush(stdout);
+  // cleanly handle exit events
+  signal(SIGTERM, exitHandler);
+  signal(SIGINT, exitHandler);
+
+  // Create global scope with stdlib
+  Scope *p_global = newGlobal(argc, argv);
+
+  //  --interpret: evaluate the AST in the global scope, no code generation
+  if (options->interpret) {
+    int exitCode = Interpret_run(p_headAstNode, p_global);
+    AstNode_free(p_headAstNode);
+    TokenArray_free(tokens);
+    Scope_free(p_global);
+    FileCache_free();
+    return exitCode;
   }
 
-  //  Build runtime library if not exists (industry-standard static library)
-  const char *runtimeLib = "/tmp/libfranz_runtime.a";
-  if (access(runtimeLib, F_OK) != 0) {
-    // Runtime library doesn't exist - build it
-    system("make -f Makefile.runtime 2>/dev/null");
+  //  --bytecode: compile to bytecode and run it on the VM in the global scope
+  if (options->bytecode) {
+    BytecodeProto *proto = Bytecode_compile(p_headAstNode);
+    if (debug) {
+      Bytecode_disassemble(stdout, proto);
+      fflush(stdout);
+    }
+
+    int exitCode = BytecodeVM_run(proto, p_global);
+    Scope_free(p_global);
+    Bytecode_free(proto);
+    AstNode_free(p_headAstNode);
+    TokenArray_free(tokens);
+    FileCache_free();
+    return exitCode;
   }
 
-  // Link object file + runtime library to executable using clang
-  //  Include dict.o and stdlib.o for dict runtime support
-  char clangCmd[1024];
-  snprintf(clangCmd, sizeof(clangCmd), "clang %s %s %s %s %s %s %s -lm -o %s",
-           objFilename, numberParseObj, terminalRuntimeObj, repeatObj,
-           dictObj, stdlibObj, runtimeLib, exeFilename);
+  /*  LLVM native compilation (Rust-level performance) */
+  // Initialize LLVM code generator
+  LLVMCodeGen *codegen = LLVMCodeGen_new("franz_module");
+  if (!codegen) {
+    fprintf(stderr, "ERROR: Failed to initialize LLVM code generator\n");
+    AstNode_free(p_headAstNode);
+    TokenArray_free(tokens);
+    Scope_free(p_global);
+    return 1;
+  }
+
+  codegen->debugMode = debug;
+  codegen->enableTCO = enable_tco;  // TCO: Enabled by default, disabled with --no-tco flag
+  codegen->optLevel = options->optLevel;
+  codegen->targetTriple = options->target;
+  codegen->debugInfo = options->debugInfo;
+  codegen->sourcePath = options->sourcePath;
+  codegen->coverage = options->coverage;
+  codegen->trace = options->trace;
+  codegen->tracePattern = options->tracePattern;
+  codegen->profile = options->profile;
 
   if (debug) {
-    printf("[DEBUG] Linking command: %s\n", clangCmd);
+    printf("[DEBUG] Compiling AST to LLVM IR...\n");
+    if (enable_tco) {
+      printf("[DEBUG] Tail Call Optimization ENABLED (default)\n");
+    } else {
+      printf("[DEBUG] Tail Call Optimization DISABLED (--no-tco flag used)\n");
+    }
     fflush(stdout);
   }
 
-  int clangResult = system(clangCmd);
+  // Compile AST to LLVM IR ( stub prints status)
+  int compileResult = LLVMCodeGen_compile(codegen, p_headAstNode, p_global);
 
-  if (clangResult != 0) {
-    fprintf(stderr, "ERROR: Failed to link executable\n");
+  if (compileResult != 0) {
+    fprintf(stderr, "ERROR: LLVM compilation failed\n");
     LLVMCodeGen_free(codegen);
     AstNode_free(p_headAstNode);
     TokenArray_free(tokens);
@@ -262,13 +443,101 @@ int run(char *code, long length, int argc, char *argv[], bool debug, bool enable
   }
 
   if (debug) {
-    printf("[DEBUG] Executing native binary: %s\n", exeFilename);
+    printf("[DEBUG] Compilation complete\n");
+    LLVMCodeGen_dumpIR(codegen);
     fflush(stdout);
   }
 
-  // Execute the native binary
-  int execResult = system(exeFilename);
-  int exitCode = WEXITSTATUS(execResult);
+  int exitCode = 0;
+
+  if (options->emit != EMIT_NONE) {
+    //  --emit: write the requested artifact and stop (nothing is linked or run)
+    if (debug) {
+      printf("[DEBUG] Emitting %s\n", options->outputPath);
+      fflush(stdout);
+    }
+
+    if (emitArtifact(codegen, options->emit, options->outputPath) != 0) {
+      fprintf(stderr, "ERROR: Failed to write %s\n", options->outputPath);
+      exitCode = 1;
+    }
+  } else if (options->jit) {
+    //  JIT mode: run main() in-process against the runtime linked into franz
+    if (debug) {
+      printf("[DEBUG] Executing with MCJIT...\n");
+      fflush(stdout);
+    }
+
+    if (LLVMCodeGen_runJIT(codegen, argc, argv, &exitCode) != 0) {
+      fprintf(stderr, "ERROR: JIT execution failed\n");
+      LLVMCodeGen_free(codegen);
+      AstNode_free(p_headAstNode);
+      TokenArray_free(tokens);
+      Scope_free(p_global);
+      return 1;
+    }
+  } else {
+    //  Build mode writes the executable to the requested path; run mode uses a scratch
+    // binary. Intermediate files live in a private directory removed afterwards, so
+    // concurrent franz processes never see each other's output.
+    char workDir[PATH_MAX];
+    if (BuildCache_createWorkDir(workDir, sizeof(workDir)) != 0) {
+      LLVMCodeGen_free(codegen);
+      AstNode_free(p_headAstNode);
+      TokenArray_free(tokens);
+      Scope_free(p_global);
+      return 1;
+    }
+
+    char scratchExe[PATH_MAX + 16];
+    snprintf(scratchExe, sizeof(scratchExe), "%s/program", workDir);
+    const char *exeFilename = options->outputPath ? options->outputPath : scratchExe;
+
+    //  Cache miss: link next to the cache entry, then publish it with an atomic rename
+    char cacheTemp[PATH_MAX + 32];
+    if (useCache) {
+      snprintf(cacheTemp, sizeof(cacheTemp), "%s.tmp.%d", cachedProgram, (int)getpid());
+      exeFilename = cacheTemp;
+    }
+
+    if (emitExecutable(codegen, exeFilename, workDir, options) != 0) {
+      if (useCache) unlink(cacheTemp);
+      BuildCache_removeDir(workDir);
+      LLVMCodeGen_free(codegen);
+      AstNode_free(p_headAstNode);
+      TokenArray_free(tokens);
+      Scope_free(p_global);
+      return 1;
+    }
+
+    //  A compile that reported diagnostics is not published: a later cache hit
+    // would run the program without printing them
+    if (useCache && Diagnostic_reportedCount() == 0 && rename(cacheTemp, cachedProgram) == 0) {
+      exeFilename = cachedProgram;
+      BuildCache_trimPrograms(cachedProgram);
+    }
+
+    if (options->outputPath) {
+      if (debug) {
+        printf("[DEBUG] Built native executable: %s\n", exeFilename);
+        fflush(stdout);
+      }
+    } else {
+      if (debug) {
+        printf("[DEBUG] Executing native binary: %s\n", exeFilename);
+        fflush(stdout);
+      }
+
+      // Execute the native binary with the program arguments; stdin/stdout are inherited
+      exitCode = runExecutable(exeFilename, argc, argv);
+    }
+
+    if (useCache && exeFilename == cacheTemp) {
+      unlink(cacheTemp);  // could not be published; never leave partial entries behind
+    }
+
+    BuildCache_removeDir(workDir);
+  }
 
   /* free */
   if (debug) {
@@ -2,8 +2,43 @@
 #define RUN_H
 #include <stdbool.h>
 
-//  Run function now uses bytecode VM by default
+//  Compiler output selected with --emit (EMIT_NONE = run or build an executable)
+typedef enum {
+  EMIT_NONE = 0,
+  EMIT_IR,    // textual LLVM IR (.ll)
+  EMIT_BC,    // LLVM bitcode (.bc)
+  EMIT_ASM,   // native assembly (.s)
+  EMIT_OBJ,   // native object file (.o)
+  EMIT_FRANZC // precompiled bytecode (.franzc, see bytecode/bytecode.h)
+} EmitKind;
+
+// Compilation options shared by `franz <file>`, `franz run` and `franz build`
+typedef struct {
+  bool debug;               // -d: print tokens, AST and IR
+  bool enable_tco;          // Tail call optimization (disabled with --no-tco)
+  const char *outputPath;   // build/emit mode: write output here instead of running it (NULL = run)
+  bool jit;                 // Execute in-process with MCJIT instead of linking an executable
+  EmitKind emit;            // --emit: write IR/bitcode/assembly/object to outputPath and stop
+  int optLevel;             // -O0..-O3: LLVM optimization pipeline (0 = none)
+  bool cache;               // Reuse executables of unchanged programs (disabled with --no-cache)
+  const char *target;       // --target: cross-compile for this triple (NULL = host)
+  const char *sysroot;      // --sysroot: target headers and libraries used when linking
+  bool debugInfo;           // -g: emit DWARF debug info mapping code to source lines
+  const char *sourcePath;   // program file (NULL when read from stdin), named in debug info
+  bool dumpTokens;          // --dump-tokens=json: print the tokens as JSON and stop
+  bool dumpAst;             // --dump-ast=json: print the AST as JSON and stop
+  bool coverage;            // --coverage: count executed lines and branches (see coverage/coverage.h)
+  bool trace;               // --trace: log function calls and returns to stderr (see trace/trace.h)
+  const char *tracePattern; // --trace=GLOB: only functions whose name matches (NULL = all)
+  bool profile;             // --profile: time functions and runtime calls (see profile/profile.h)
+  bool interpret;           // --interpret: evaluate the AST without LLVM (see interpret/interpret.h)
+  bool bytecode;            // --bytecode: compile to bytecode and run it on the VM (see bytecode/bytecode.h)
+} RunOptions;
+
 // prototypes
-int run(char *code, long length, int argc, char *argv[], bool debug, bool enable_tco);
+void RunOptions_init(RunOptions *options);
+EmitKind EmitKind_parse(const char *name);
+const char *EmitKind_extension(EmitKind kind);
+int run(char *code, long length, int argc, char *argv[], RunOptions *options);
 
 #endif
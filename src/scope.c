@@ -129,7 +129,7 @@ void Scope_set(Scope *p_target, char *key, Generic *p_val, int lineNumber) {
         "Runtime Error @ Line %i: Cannot reassign frozen variable '%s'.\n",
         lineNumber, key
       );
-      exit(0);
+      exit(1);
     }
 
     // Decrement old value refcount
@@ -171,7 +171,7 @@ void Scope_freeze(Scope *p_target, char *var_name, int lineNumber) {
       "Runtime Error @ Line %i: Cannot freeze undefined variable '%s'.\n",
       lineNumber, var_name
     );
-    exit(0);
+    exit(1);
   } else {
     // Recursively search parent
     Scope_freeze(p_target->p_parent, var_name, lineNumber);
@@ -194,7 +194,7 @@ Generic *Scope_get(Scope *p_target, char *key, int lineNumber) {
         "Runtime Error @ Line %i: %s is not defined.\n",
         lineNumber, key
       );
-      exit(0);
+      exit(1);
     } else {
       // Search parent scope
       return Scope_get(p_target->p_parent, key, lineNumber);
@@ -8,6 +8,7 @@
 #include <sys/stat.h>
 #include <unistd.h>
 #include <stdint.h>
+#include <ctype.h>
 
 #include "stdlib.h"
 #include "generic.h"
@@ -15,7 +16,8 @@
 #include "scope.h"
 #include "list.h"
 #include "dict.h"
-#include "eval_stub.h"  //  Temporary eval stub for runtime functions
+#include "interpret/interpret.h"  //  eval() for functions, modules and callbacks
+#include "bytecode/bytecode.h"  //  Closures and modules of the bytecode VM
 #include "events.h"
 #include "file.h"
 #include "file-advanced/file_advanced.h"  //  Advanced file operations
@@ -27,6 +29,7 @@
 #include "security/security.h"
 #include "circular-deps/circular_deps.h"
 #include "error-handling/error_handler.h"
+#include "diagnostics/diagnostic.h"
 #include "mutable-refs/ref.h"  //  Mutable reference support
 #include "number-formats/number_parse.h"  // Multi-base & formatting
 
@@ -35,18 +38,18 @@
 void validateArgCount(int min, int max, int length, int lineNumber) {
   if (length > max) {
     // supplied too many args, throw error
-    printf(
-      "Runtime Error @ Line %i: Supplied more arguments than required to function.\n", 
-      lineNumber
+    Diagnostic_report(
+      stdout, "Runtime Error", ERROR_CODE_RUNTIME_ARITY, Diagnostic_spanOfLine(lineNumber),
+      "Supplied more arguments than required to function."
     );
-    exit(0);
+    exit(1);
   } else if (length < min) {
     // supplied too little args, throw error
-    printf(
-      "Runtime Error @ Line %i: Supplied less arguments than required to function.\n", 
-      lineNumber
+    Diagnostic_report(
+      stdout, "Runtime Error", ERROR_CODE_RUNTIME_ARITY, Diagnostic_spanOfLine(lineNumber),
+      "Supplied less arguments than required to function."
     );
-    exit(0);
+    exit(1);
   }
 }
 
@@ -54,11 +57,11 @@ void validateArgCount(int min, int max, int length, int lineNumber) {
 void validateMinArgCount(int min, int length, int lineNumber) {
   if (length < min) {
     // supplied too little args, throw error
-    printf(
-      "Runtime Error @ Line %i: Supplied less arguments than required to function.\n", 
-      lineNumber
+    Diagnostic_report(
+      stdout, "Runtime Error", ERROR_CODE_RUNTIME_ARITY, Diagnostic_spanOfLine(lineNumber),
+      "Supplied less arguments than required to function."
     );
-    exit(0);
+    exit(1);
   }
 }
 
@@ -75,59 +78,78 @@ void validateType(enum Type allowedTypes[], int typeCount, enum Type type, int a
   }
 
   if (!valid) {
-    // print error msg
-    printf("Runtime Error @ Line %i: %s function requires ", lineNumber, funcName);
-
-    for (int i = 0; i < typeCount - 1; i++) {
-      printf("%s type or ", getTypeString(allowedTypes[i]));
+    // build error msg
+    char allowed[512] = "";
+    for (int i = 0; i < typeCount; i++) {
+      size_t used = strlen(allowed);
+      snprintf(allowed + used, sizeof(allowed) - used, "%s%s type",
+               i > 0 ? " or " : "", getTypeString(allowedTypes[i]));
     }
 
-    printf("%s type", getTypeString(allowedTypes[typeCount - 1]));
-    printf(" for argument #%i, %s type supplied instead.\n", argNum, getTypeString(type));
+    Diagnostic_report(
+      stdout, "Runtime Error", ERROR_CODE_RUNTIME_TYPE, Diagnostic_spanOfLine(lineNumber),
+      "%s function requires %s for argument #%i, %s type supplied instead.",
+      funcName, allowed, argNum, getTypeString(type)
+    );
 
-    exit(0);
+    exit(1);
   }
 }
 
 // validate that argument is binary
-void validateBinary(int *p_val, int argNum, int lineNumber, char* funcName) {
+void validateBinary(int64_t *p_val, int argNum, int lineNumber, char* funcName) {
   if (*(p_val) != 0 && *(p_val) != 1) {
-    printf(
-      "Runtime Error @ Line %i: %s function expected 0 or 1 for argument #%i, %i supplied instead.\n", 
-      lineNumber, funcName, argNum, *p_val
+    Diagnostic_report(
+      stdout, "Runtime Error", ERROR_CODE_RUNTIME_RANGE, Diagnostic_spanOfLine(lineNumber),
+      "%s function expected 0 or 1 for argument #%i, %lld supplied instead.",
+      funcName, argNum, (long long) *p_val
     );
-    exit(0);
+    exit(1);
   }
 }
 
 // validate that argument is within a range
-void validateRange(int *p_val, int min, int max, int argNum, int lineNumber, char* funcName) {
+void validateRange(int64_t *p_val, int min, int max, int argNum, int lineNumber, char* funcName) {
   if (*p_val > max || *p_val < min) {
-    printf(
-      "Runtime Error @ Line %i: %s function expected a value in the range [%i, %i] for argument #%i, %i supplied instead.\n", 
-      lineNumber, funcName, min, max, argNum, *p_val
+    Diagnostic_report(
+      stdout, "Runtime Error", ERROR_CODE_RUNTIME_RANGE, Diagnostic_spanOfLine(lineNumber),
+      "%s function expected a value in the range [%i, %i] for argument #%i, %lld supplied instead.",
+      funcName, min, max, argNum, (long long) *p_val
     );
 
-    exit(0);
+    exit(1);
   }
 }
 
 // validate that value is at least a minimum
-void validateMin(int *p_val, int min, int argNum, int lineNumber, char* funcName) {
+void validateMin(int64_t *p_val, int min, int argNum, int lineNumber, char* funcName) {
   if (*p_val < min) {
-    printf(
-      "Runtime Error @ Line %i: %s function expected a minimum value of %i for argument #%i, %i supplied instead.\n", 
-      lineNumber, funcName, min, argNum, *p_val
+    Diagnostic_report(
+      stdout, "Runtime Error", ERROR_CODE_RUNTIME_RANGE, Diagnostic_spanOfLine(lineNumber),
+      "%s function expected a minimum value of %i for argument #%i, %lld supplied instead.",
+      funcName, min, argNum, (long long) *p_val
     );
 
-    exit(0);
+    exit(1);
+  }
+}
+
+// runs a loaded module in its scope, with the backend running the program
+static void runModule(AstNode *p_headAstNode, Scope *p_scope) {
+  if (BytecodeVM_active()) {
+    Generic_free(BytecodeVM_runModule(p_headAstNode, p_scope));
+  } else {
+    Generic_free(eval(p_headAstNode, p_scope, 0));
   }
 }
 
 // applys a func, given arguments
 // used for callbacks from the standard library
 Generic *applyFunc(Generic *func, Scope *p_scope, Generic *args[], int length, int lineNumber) {
-  if(func->type == TYPE_NATIVEFUNCTION) {
+  if (func->type == TYPE_BYTECODE_CLOSURE && BytecodeVM_active()) {
+    // closures of the bytecode VM run in the VM
+    return BytecodeVM_call(func, p_scope, args, length, lineNumber);
+  } else if(func->type == TYPE_NATIVEFUNCTION) {
     // native func case, simply obtain cb and run
     Generic *(*cb)(Scope *, Generic *[], int, int) = func->p_val;
 
@@ -205,8 +227,8 @@ Generic *applyFunc(Generic *func, Scope *p_scope, Generic *args[], int length, i
     }
 
     //  Loop over function parameters (array-based)
-    // Function children: [0..n-1]=parameters, [n]=body(statement)
-    int funcParamCount = p_fn_ast->childCount - 1;  // Exclude body
+    // Function children: [0..n-1]=parameters, then one statement per body line
+    int funcParamCount = Interpret_paramCount(p_fn_ast);
 
     // Error handling
     if (length != funcParamCount) {
@@ -216,7 +238,7 @@ Generic *applyFunc(Generic *func, Scope *p_scope, Generic *args[], int length, i
         printf("Runtime Error @ Line %i: Supplied less arguments than required to function.\n", lineNumber);
       }
       Scope_free(p_local);
-      exit(0);
+      exit(1);
     }
 
     // Populate local scope with arguments
@@ -224,11 +246,8 @@ Generic *applyFunc(Generic *func, Scope *p_scope, Generic *args[], int length, i
       Scope_set(p_local, p_fn_ast->children[i]->val, args[i], -1);  // -1 = skip immutability check
     }
 
-    // Last child is the statement body
-    AstNode *p_curr = p_fn_ast->children[p_fn_ast->childCount - 1];
-
-    // run statement with local scope
-    Generic *res = eval(p_curr, p_local, 0);
+    // run the body statements with local scope
+    Generic *res = Interpret_functionBody(p_fn_ast, p_local);
 
     // free scope
     Scope_free(p_local);
@@ -243,17 +262,42 @@ Generic *applyFunc(Generic *func, Scope *p_scope, Generic *args[], int length, i
       "Runtime Error @ Line %i: Attempted to call %s instead of function.\n", 
       lineNumber, getTypeString(func->type)
     );
-    exit(0);
+    exit(1);
   }
 }
 
+// number of arguments to pass a callback that is offered available arguments,
+// the first required of them always: map, filter, reduce and map2 pass the
+// index last, and leave it out for callbacks that do not declare it
+static int callbackArgCount(Generic *func, int required, int available) {
+  int paramCount = available;
+  if (func->type == TYPE_FUNCTION) {
+    paramCount = Interpret_paramCount((AstNode *) func->p_val);
+  } else if (func->type == TYPE_BYTECODE_CLOSURE && BytecodeVM_active()) {
+    paramCount = ((BytecodeClosure *) func->p_val)->proto->paramCount;
+  } else if (func->type == TYPE_BYTECODE_CLOSURE) {
+    paramCount = Interpret_paramCount(((Closure *) func->p_val)->p_fn);
+  }
+
+  if (paramCount < required) return required;
+  return paramCount < available ? paramCount : available;
+}
+
+// index argument at position (1-based) of a callback call, if it is passed
+static Generic *indexArg(int index, int argCount, int position) {
+  if (argCount < position) return NULL;
+
+  int64_t *p_i = (int64_t *) malloc(sizeof(int64_t));
+  *p_i = index;
+  return Generic_new(TYPE_INT, p_i, 0);
+}
+
 /* IO */
 // (print args...)
-// prints given arguments
+// prints given arguments, back to back like compiled programs
 Generic *StdLib_print(Scope *p_scope, Generic *args[], int length, int lineNumber) {
   for (int i = 0; i < length; i++) {
-    Generic_print(args[i]);
-    if (i < length - 1) printf(" ");
+    franz_print_generic(args[i]);
   }
   fflush(stdout);  // Flush output buffer to ensure immediate display
 
@@ -264,8 +308,7 @@ Generic *StdLib_print(Scope *p_scope, Generic *args[], int length, int lineNumbe
 // prints given arguments followed by a newline
 Generic *StdLib_println(Scope *p_scope, Generic *args[], int length, int lineNumber) {
   for (int i = 0; i < length; i++) {
-    Generic_print(args[i]);
-    if (i < length - 1) printf(" ");
+    franz_print_generic(args[i]);
   }
   printf("\n");
   return Generic_new(TYPE_VOID, NULL, 0);
@@ -289,12 +332,13 @@ Generic *StdLib_input(Scope *p_scope, Generic *args[], int length, int lineNumbe
   char *res = (char *) malloc(sizeof(char));
   res[0] = '\0';
 
-  // loop through every char
-  char c = getchar();
-  while (c != '\n') {
+  // loop through every char, up to the end of the line or of stdin
+  int c = getchar();
+  while (c != '\n' && c != EOF) {
     // reallocate and add char
+    char ch = (char) c;
     res = realloc(res, sizeof(char) * (strlen(res) + 1 + 1));
-    strncat(res, &c, 1);
+    strncat(res, &ch, 1);
 
     c = getchar();
   }
@@ -314,11 +358,11 @@ Generic *StdLib_input(Scope *p_scope, Generic *args[], int length, int lineNumbe
 Generic *StdLib_columns(Scope *p_scope, Generic *args[], int length, int lineNumber) {
   // get current dimensions
   struct winsize w;
-  ioctl(STDOUT_FILENO, TIOCGWINSZ, &w);
+  int known = ioctl(STDOUT_FILENO, TIOCGWINSZ, &w) != -1;
 
-  // create pointer to rows
-  int *rows = (int *) malloc(sizeof(int));
-  *rows = w.ws_col;
+  // create pointer to rows (not a terminal: 80, as in compiled programs)
+  int64_t *rows = (int64_t *) malloc(sizeof(int64_t));
+  *rows = known ? w.ws_col : 80;
 
   // return generic
   return Generic_new(TYPE_INT, rows, 0);
@@ -329,11 +373,11 @@ Generic *StdLib_columns(Scope *p_scope, Generic *args[], int length, int lineNum
 Generic *StdLib_rows(Scope *p_scope, Generic *args[], int length, int lineNumber) {
   // get current dimensions
   struct winsize w;
-  ioctl(STDOUT_FILENO, TIOCGWINSZ, &w);
+  int known = ioctl(STDOUT_FILENO, TIOCGWINSZ, &w) != -1;
 
-  // create pointer to rows
-  int *rows = (int *) malloc(sizeof(int));
-  *rows = w.ws_row;
+  // create pointer to rows (not a terminal: 24, as in compiled programs)
+  int64_t *rows = (int64_t *) malloc(sizeof(int64_t));
+  *rows = known ? w.ws_row : 24;
 
   // return generic
   return Generic_new(TYPE_INT, rows, 0);
@@ -387,7 +431,7 @@ Generic *StdLib_file_exists(Scope *p_scope, Generic *args[], int length, int lin
   int exists = fileExists(*((char **) args[0]->p_val));
 
   // malloc pointer to int
-  int *p_res = (int *) malloc(sizeof(int));
+  int64_t *p_res = (int64_t *) malloc(sizeof(int64_t));
   *p_res = exists;
 
   // return
@@ -408,6 +452,13 @@ Generic *StdLib_append_file(Scope *p_scope, Generic *args[], int length, int lin
   return Generic_new(TYPE_VOID, NULL, 0);
 }
 
+//  Heap cell holding str, as string generics expect
+static char **boxString(char *str) {
+  char **p_res = (char **) malloc(sizeof(char *));
+  *p_res = str;
+  return p_res;
+}
+
 // ============================================================================
 //  Advanced File Operations
 // ============================================================================
@@ -427,11 +478,11 @@ Generic *StdLib_read_binary(Scope *p_scope, Generic *args[], int length, int lin
     // Return empty string on error
     char *empty = malloc(1);
     empty[0] = '\0';
-    return Generic_new(TYPE_STRING, &empty, 0);
+    return Generic_new(TYPE_STRING, boxString(empty), 0);
   }
 
   // Return binary data as string
-  return Generic_new(TYPE_STRING, &data, 0);
+  return Generic_new(TYPE_STRING, boxString(data), 0);
 }
 
 // (write_binary filepath data)
@@ -474,7 +525,7 @@ Generic *StdLib_create_dir(Scope *p_scope, Generic *args[], int length, int line
 
   int result = createDir(*((char **) args[0]->p_val), lineNumber);
 
-  int *p_res = (int *) malloc(sizeof(int));
+  int64_t *p_res = (int64_t *) malloc(sizeof(int64_t));
   *p_res = result;
   return Generic_new(TYPE_INT, p_res, 0);
 }
@@ -489,7 +540,7 @@ Generic *StdLib_dir_exists(Scope *p_scope, Generic *args[], int length, int line
 
   int exists = dirExists(*((char **) args[0]->p_val));
 
-  int *p_res = (int *) malloc(sizeof(int));
+  int64_t *p_res = (int64_t *) malloc(sizeof(int64_t));
   *p_res = exists;
   return Generic_new(TYPE_INT, p_res, 0);
 }
@@ -504,7 +555,7 @@ Generic *StdLib_remove_dir(Scope *p_scope, Generic *args[], int length, int line
 
   int result = removeDir(*((char **) args[0]->p_val), lineNumber);
 
-  int *p_res = (int *) malloc(sizeof(int));
+  int64_t *p_res = (int64_t *) malloc(sizeof(int64_t));
   *p_res = result;
   return Generic_new(TYPE_INT, p_res, 0);
 }
@@ -519,8 +570,8 @@ Generic *StdLib_file_size(Scope *p_scope, Generic *args[], int length, int lineN
 
   long size = fileSize(*((char **) args[0]->p_val));
 
-  int *p_res = (int *) malloc(sizeof(int));
-  *p_res = (int)size;  // Cast long to int
+  int64_t *p_res = (int64_t *) malloc(sizeof(int64_t));
+  *p_res = size;
   return Generic_new(TYPE_INT, p_res, 0);
 }
 
@@ -534,8 +585,8 @@ Generic *StdLib_file_mtime(Scope *p_scope, Generic *args[], int length, int line
 
   long mtime = fileMtime(*((char **) args[0]->p_val));
 
-  int *p_res = (int *) malloc(sizeof(int));
-  *p_res = (int)mtime;  // Cast long to int
+  int64_t *p_res = (int64_t *) malloc(sizeof(int64_t));
+  *p_res = mtime;
   return Generic_new(TYPE_INT, p_res, 0);
 }
 
@@ -549,7 +600,7 @@ Generic *StdLib_is_directory(Scope *p_scope, Generic *args[], int length, int li
 
   int isDir = isDirectory(*((char **) args[0]->p_val));
 
-  int *p_res = (int *) malloc(sizeof(int));
+  int64_t *p_res = (int64_t *) malloc(sizeof(int64_t));
   *p_res = isDir;
   return Generic_new(TYPE_INT, p_res, 0);
 }
@@ -566,7 +617,7 @@ Generic *StdLib_event(Scope *p_scope, Generic *args[], int length, int lineNumbe
     validateType(allowedTypes, 3, args[0]->type, 1, lineNumber, "event");
   
     if (args[0]->type == TYPE_INT) {
-      decisecondsBlock = *((int *) args[0]->p_val) * 10;
+      decisecondsBlock = *((int64_t *) args[0]->p_val) * 10;
     }
     if (args[0]->type == TYPE_FLOAT) {
       decisecondsBlock = (int) ceilf(*((double *) args[0]->p_val) * 10);
@@ -594,25 +645,34 @@ Generic *StdLib_event(Scope *p_scope, Generic *args[], int length, int lineNumbe
 }
 
 // (use path1 path2 path3 ... fn)
-// creates a new scope, evaluates the code in path1, path2, and path3, and then uses said scope to evaluate fn
+// evaluates the code in path1, path2, and path3, and then evaluates fn
+//  Modules are evaluated in the global scope, so their definitions stay visible
+// (inside fn and after it) as in compiled programs; fn is optional
 Generic *StdLib_use(Scope *p_scope, Generic *args[], int length, int lineNumber) {
-  validateMinArgCount(2, length, lineNumber);
+  validateMinArgCount(1, length, lineNumber);
+
+  int hasCallback = args[length - 1]->type != TYPE_STRING;
+  int pathCount = hasCallback ? length - 1 : length;
 
   // validate that all arguments with exception of last one are strings
-  for (int i = 0; i < length - 1; i++) {
+  for (int i = 0; i < pathCount; i++) {
     enum Type allowedTypes[] = {TYPE_STRING};
     validateType(allowedTypes, 1, args[i]->type, i + 1, lineNumber, "use");
   }
 
   // validate that last argument is a function
-  enum Type allowedTypes[] = {TYPE_FUNCTION, TYPE_NATIVEFUNCTION, TYPE_BYTECODE_CLOSURE, TYPE_BYTECODE_CLOSURE};  //  Support closures
-  validateType(allowedTypes, 3, args[length - 1]->type, length, lineNumber, "use");
+  if (hasCallback) {
+    enum Type allowedTypes[] = {TYPE_FUNCTION, TYPE_NATIVEFUNCTION, TYPE_BYTECODE_CLOSURE, TYPE_BYTECODE_CLOSURE};  //  Support closures
+    validateType(allowedTypes, 3, args[length - 1]->type, length, lineNumber, "use");
+  }
 
-  // create a new scope for all paths and fn to run in
-  Scope *p_newScope = Scope_new(p_scope);
+  Scope *p_global = p_scope;
+  while (p_global->p_parent != NULL) {
+    p_global = p_global->p_parent;
+  }
 
   // for each path
-  for (int i = 0; i < length - 1; i++) {
+  for (int i = 0; i < pathCount; i++) {
     char *module_path = *((char **) args[i]->p_val);
     AstNode *p_headAstNode = NULL;
 
@@ -620,7 +680,6 @@ Generic *StdLib_use(Scope *p_scope, Generic *args[], int length, int lineNumber)
     if (CircularDeps_push(module_path, lineNumber) != 0) {
       // Circular dependency detected!
       CircularDeps_printChain(module_path);
-      Scope_free(p_newScope);
       exit(1);
     }
 
@@ -641,7 +700,7 @@ Generic *StdLib_use(Scope *p_scope, Generic *args[], int length, int lineNumber)
           "Runtime Error @ Line %i: Cannot read file \"%s\".\n",
           lineNumber, module_path
         );
-        exit(0);
+        exit(1);
       }
 
       //  Array-based lexing
@@ -649,6 +708,10 @@ Generic *StdLib_use(Scope *p_scope, Generic *args[], int length, int lineNumber)
 
       //  Array-based parsing
       p_headAstNode = parseProgram(tokens);
+      if (Diagnostic_count() > 0) {
+        Diagnostic_flush(stdout);
+        exit(1);
+      }
 
       // Cache the parsed AST for future use
       struct stat st;
@@ -664,19 +727,16 @@ Generic *StdLib_use(Scope *p_scope, Generic *args[], int length, int lineNumber)
     }
 
     // eval and free AST
-    Generic_free(eval(p_headAstNode, p_newScope, 0));
+    runModule(p_headAstNode, p_global);
     AstNode_free(p_headAstNode);
 
     // Pop from import stack after successful load
     CircularDeps_pop(module_path);
   }
 
-  // apply callback with new scope
-  Generic *res = applyFunc(args[length - 1], p_newScope, NULL, 0, lineNumber);
+  if (!hasCallback) return Generic_new(TYPE_VOID, NULL, 0);
 
-  // free new scope and return
-  Scope_free(p_newScope);
-  return res;
+  return applyFunc(args[length - 1], p_scope, NULL, 0, lineNumber);
 }
 
 // (use_as path)
@@ -716,7 +776,7 @@ Generic *StdLib_use_as(Scope *p_scope, Generic *args[], int length, int lineNumb
         "Runtime Error @ Line %i: Cannot read file \"%s\".\n",
         lineNumber, module_path
       );
-      exit(0);
+      exit(1);
     }
 
     //  Array-based lexing
@@ -724,6 +784,10 @@ Generic *StdLib_use_as(Scope *p_scope, Generic *args[], int length, int lineNumb
 
     //  Array-based parsing
     p_headAstNode = parseProgram(tokens);
+    if (Diagnostic_count() > 0) {
+      Diagnostic_flush(stdout);
+      exit(1);
+    }
 
     // Cache the parsed AST for future use
     struct stat st;
@@ -739,10 +803,14 @@ Generic *StdLib_use_as(Scope *p_scope, Generic *args[], int length, int lineNumb
   }
 
   // Create a new isolated scope for the namespace (inherits from global for stdlib)
-  Scope *p_namespaceScope = Scope_new(p_scope->p_parent);
+  Scope *global = p_scope;
+  while (global->p_parent != NULL) {
+    global = global->p_parent;
+  }
+  Scope *p_namespaceScope = Scope_new(global);
 
   // eval module code in the isolated namespace scope
-  Generic_free(eval(p_headAstNode, p_namespaceScope, 0));
+  runModule(p_headAstNode, p_namespaceScope);
   AstNode_free(p_headAstNode);
 
   // Pop from import stack after successful load
@@ -756,22 +824,28 @@ Generic *StdLib_use_as(Scope *p_scope, Generic *args[], int length, int lineNumb
 // Capability-based sandboxed imports - imported code only has access to granted capabilities
 // First arg must be a list of capability strings, last arg must be a function
 // Example: (use_with (list "print" "arithmetic") "lib.franz" { -> (main) })
+//  Without fn the definitions of the modules are copied into the calling scope, as in compiled programs
 Generic *StdLib_use_with(Scope *p_scope, Generic *args[], int length, int lineNumber) {
-  validateMinArgCount(3, length, lineNumber);
+  validateMinArgCount(2, length, lineNumber);
 
   // Validate first argument is a list (capabilities)
   enum Type capListType[] = {TYPE_LIST};
   validateType(capListType, 1, args[0]->type, 1, lineNumber, "use_with");
 
+  int hasCallback = args[length - 1]->type != TYPE_STRING;
+  int pathEnd = hasCallback ? length - 1 : length;
+
   // Validate all middle arguments are strings (file paths)
-  for (int i = 1; i < length - 1; i++) {
+  for (int i = 1; i < pathEnd; i++) {
     enum Type allowedTypes[] = {TYPE_STRING};
     validateType(allowedTypes, 1, args[i]->type, i + 1, lineNumber, "use_with");
   }
 
   // Validate last argument is a function
-  enum Type allowedTypes[] = {TYPE_FUNCTION, TYPE_NATIVEFUNCTION, TYPE_BYTECODE_CLOSURE, TYPE_BYTECODE_CLOSURE};  //  Support closures
-  validateType(allowedTypes, 3, args[length - 1]->type, length, lineNumber, "use_with");
+  if (hasCallback) {
+    enum Type allowedTypes[] = {TYPE_FUNCTION, TYPE_NATIVEFUNCTION, TYPE_BYTECODE_CLOSURE, TYPE_BYTECODE_CLOSURE};  //  Support closures
+    validateType(allowedTypes, 3, args[length - 1]->type, length, lineNumber, "use_with");
+  }
 
   // Create an isolated scope with NO parent (complete sandboxing)
   Scope *p_capScope = Security_createIsolatedScope();
@@ -779,11 +853,12 @@ Generic *StdLib_use_with(Scope *p_scope, Generic *args[], int length, int lineNu
   // Seed the capability scope with granted capabilities
   if (Security_seedCapabilities(p_capScope, args[0], lineNumber) != 0) {
     Scope_free(p_capScope);
-    exit(0);
+    exit(1);
   }
+  int seeded = p_capScope->count;
 
   // Load and evaluate each module file in the capability-restricted scope
-  for (int i = 1; i < length - 1; i++) {
+  for (int i = 1; i < pathEnd; i++) {
     char *module_path = *((char **) args[i]->p_val);
     AstNode *p_headAstNode = NULL;
 
@@ -813,7 +888,7 @@ Generic *StdLib_use_with(Scope *p_scope, Generic *args[], int length, int lineNu
           lineNumber, module_path
         );
         Scope_free(p_capScope);
-        exit(0);
+        exit(1);
       }
 
       //  Array-based lexing
@@ -821,6 +896,10 @@ Generic *StdLib_use_with(Scope *p_scope, Generic *args[], int length, int lineNu
 
       //  Array-based parsing
       p_headAstNode = parseProgram(tokens);
+      if (Diagnostic_count() > 0) {
+        Diagnostic_flush(stdout);
+        exit(1);
+      }
 
       // Cache the parsed AST for future use
       struct stat st;
@@ -836,13 +915,29 @@ Generic *StdLib_use_with(Scope *p_scope, Generic *args[], int length, int lineNu
     }
 
     // eval module in the capability-restricted scope
-    Generic_free(eval(p_headAstNode, p_capScope, 0));
+    runModule(p_headAstNode, p_capScope);
     AstNode_free(p_headAstNode);
 
     // Pop from import stack after successful load
     CircularDeps_pop(module_path);
   }
 
+  if (!hasCallback) {
+    Scope *p_global = p_scope;
+    while (p_global->p_parent != NULL) p_global = p_global->p_parent;
+
+    for (int i = seeded; i < p_capScope->count; i++) {
+      Generic *value = p_capScope->bindings[i].value;
+      // Copied functions run with the caller's globals, so VM closures must not keep the sandbox
+      if (value->type == TYPE_BYTECODE_CLOSURE && BytecodeVM_active()) {
+        ((BytecodeClosure *) value->p_val)->scope = p_global;
+      }
+      Scope_set(p_scope, p_capScope->bindings[i].name, value, lineNumber);
+    }
+    Scope_free(p_capScope);
+    return Generic_new(TYPE_VOID, NULL, 0);
+  }
+
   // Apply callback with capability-restricted scope
   Generic *res = applyFunc(args[length - 1], p_capScope, NULL, 0, lineNumber);
 
@@ -870,7 +965,7 @@ Generic *StdLib_shell(Scope *p_scope, Generic *args[], int length, int lineNumbe
       "Runtime Error @ Line %i: Failed to run shell command.\n", 
       lineNumber
     );
-    exit(0);
+    exit(1);
   }
 
   char *res = malloc(sizeof(char));
@@ -904,7 +999,7 @@ Generic *StdLib_is(Scope *p_scope, Generic *args[], int length, int lineNumber)
   validateArgCount(2, 2, length, lineNumber);
 
   // return
-  int *res = (int *) malloc(sizeof(int));
+  int64_t *res = (int64_t *) malloc(sizeof(int64_t));
   *res = Generic_is(args[0], args[1]);
   return Generic_new(TYPE_INT, res, 0);
 }
@@ -922,10 +1017,10 @@ Generic *StdLib_less_than(Scope *p_scope, Generic *args[], int length, int lineN
   // do comparision and return
   Generic *a = args[0];
   Generic *b = args[1];
-  int *p_res = (int *) malloc(sizeof(int));
+  int64_t *p_res = (int64_t *) malloc(sizeof(int64_t));
   *p_res = (
-    (a->type == TYPE_FLOAT ? *((double *) a->p_val) : *((int *) a->p_val))
-    < (b->type == TYPE_FLOAT ? *((double *) b->p_val) : *((int *) b->p_val))
+    (a->type == TYPE_FLOAT ? *((double *) a->p_val) : *((int64_t *) a->p_val))
+    < (b->type == TYPE_FLOAT ? *((double *) b->p_val) : *((int64_t *) b->p_val))
   );
 
   return Generic_new(TYPE_INT, p_res, 0); 
@@ -944,10 +1039,10 @@ Generic *StdLib_greater_than(Scope *p_scope, Generic *args[], int length, int li
   // do comparision and return
   Generic *a = args[0];
   Generic *b = args[1];
-  int *p_res = (int *) malloc(sizeof(int));
+  int64_t *p_res = (int64_t *) malloc(sizeof(int64_t));
   *p_res = (
-    (a->type == TYPE_FLOAT ? *((double *) a->p_val) : *((int *) a->p_val))
-    > (b->type == TYPE_FLOAT ? *((double *) b->p_val) : *((int *) b->p_val))
+    (a->type == TYPE_FLOAT ? *((double *) a->p_val) : *((int64_t *) a->p_val))
+    > (b->type == TYPE_FLOAT ? *((double *) b->p_val) : *((int64_t *) b->p_val))
   );
 
   return Generic_new(TYPE_INT, p_res, 0); 
@@ -955,17 +1050,15 @@ Generic *StdLib_greater_than(Scope *p_scope, Generic *args[], int length, int li
 
 /* logical operators */
 // (not a)
-// returns 1 if a = 0, 0 if a = 1
+// returns 1 if a = 0, 0 otherwise (any non-zero value is true, as in compiled programs)
 Generic *StdLib_not(Scope *p_scope, Generic *args[], int length, int lineNumber) {
   validateArgCount(1, 1, length, lineNumber);
 
   enum Type allowedTypes[] = {TYPE_INT};
   validateType(allowedTypes, 1, args[0]->type, 1, lineNumber, "not");
 
-  validateBinary(args[0]->p_val, 1, lineNumber, "not");
-
-  int *p_res = (int *) malloc(sizeof(int));
-  *p_res = 1 - *((int *) args[0]->p_val);
+  int64_t *p_res = (int64_t *) malloc(sizeof(int64_t));
+  *p_res = *((int64_t *) args[0]->p_val) == 0;
 
   return Generic_new(TYPE_INT, p_res, 0);
 }
@@ -979,16 +1072,15 @@ Generic *StdLib_and(Scope *p_scope, Generic *args[], int length, int lineNumber)
   enum Type allowedTypes[] = {TYPE_INT};
   for (int i = 0; i < length; i++) {
     validateType(allowedTypes, 1, args[i]->type, i + 1, lineNumber, "and");
-    validateBinary(args[i]->p_val, i + 1, lineNumber, "and");
   };
 
   // set up result
-  int *p_res = (int *) malloc(sizeof(int));
+  int64_t *p_res = (int64_t *) malloc(sizeof(int64_t));
   *p_res = 1;
 
   // add each arg to *p_res
   for (int i = 0; i < length; i++) {
-    *p_res = *p_res && *((int *) args[i]->p_val);
+    *p_res = *p_res && *((int64_t *) args[i]->p_val);
   }
 
   return Generic_new(TYPE_INT, p_res, 0);
@@ -1003,16 +1095,15 @@ Generic *StdLib_or(Scope *p_scope, Generic *args[], int length, int lineNumber)
   enum Type allowedTypes[] = {TYPE_INT};
   for (int i = 0; i < length; i++) {
     validateType(allowedTypes, 1, args[i]->type, i + 1, lineNumber, "or");
-    validateBinary(args[i]->p_val, i + 1, lineNumber, "or");
   };
 
   // set up result
-  int *p_res = (int *) malloc(sizeof(int));
+  int64_t *p_res = (int64_t *) malloc(sizeof(int64_t));
   *p_res = 0;
 
   // add each arg to *p_res
   for (int i = 0; i < length; i++) {
-    *p_res = *p_res || *((int *) args[i]->p_val);
+    *p_res = *p_res || *((int64_t *) args[i]->p_val);
   }
 
   return Generic_new(TYPE_INT, p_res, 0);
@@ -1036,12 +1127,12 @@ Generic *StdLib_add(Scope *p_scope, Generic *args[], int length, int lineNumber)
   
   if (resIsInt) {
     // case where we can return int
-    int *p_res = (int *) malloc(sizeof(int));
+    int64_t *p_res = (int64_t *) malloc(sizeof(int64_t));
     *p_res = 0;
 
     // add each arg to *p_res
     for (int i = 0; i < length; i++) {
-      *p_res += *((int *) args[i]->p_val);
+      *p_res += *((int64_t *) args[i]->p_val);
     }
 
     return Generic_new(TYPE_INT, p_res, 0);
@@ -1055,7 +1146,7 @@ Generic *StdLib_add(Scope *p_scope, Generic *args[], int length, int lineNumber)
     for (int i = 0; i < length; i++) {
       *p_res += args[i]->type == TYPE_FLOAT 
         ? *((double *) args[i]->p_val) 
-        : *((int *) args[i]->p_val);
+        : *((int64_t *) args[i]->p_val);
     }
 
     return Generic_new(TYPE_FLOAT, p_res, 0);
@@ -1079,12 +1170,12 @@ Generic *StdLib_subtract(Scope *p_scope, Generic *args[], int length, int lineNu
   
   if (resIsInt) {
     // case where we can return int
-    int *p_res = (int *) malloc(sizeof(int));
-    *p_res = *((int *) args[0]->p_val);
+    int64_t *p_res = (int64_t *) malloc(sizeof(int64_t));
+    *p_res = *((int64_t *) args[0]->p_val);
 
     // subtract each arg from *p_res
     for (int i = 1; i < length; i++) {
-      *p_res -= *((int *) args[i]->p_val);
+      *p_res -= *((int64_t *) args[i]->p_val);
     }
 
     return Generic_new(TYPE_INT, p_res, 0);
@@ -1094,13 +1185,13 @@ Generic *StdLib_subtract(Scope *p_scope, Generic *args[], int length, int lineNu
     double *p_res = (double *) malloc(sizeof(double));
     *p_res = args[0]->type == TYPE_FLOAT 
         ? *((double *) args[0]->p_val) 
-        : *((int *) args[0]->p_val);;
+        : *((int64_t *) args[0]->p_val);;
 
     // subtract each arg from *p_res
     for (int i = 1; i < length; i++) {
       *p_res -= args[i]->type == TYPE_FLOAT 
         ? *((double *) args[i]->p_val) 
-        : *((int *) args[i]->p_val);
+        : *((int64_t *) args[i]->p_val);
     }
 
     return Generic_new(TYPE_FLOAT, p_res, 0);
@@ -1109,37 +1200,59 @@ Generic *StdLib_subtract(Scope *p_scope, Generic *args[], int length, int lineNu
 
 // (divide arg1 arg2 arg3 ...)
 // returns arg1 / arg2 / arg3 / ...
+// integers divide to an integer (truncated), as in compiled programs
 Generic *StdLib_divide(Scope *p_scope, Generic *args[], int length, int lineNumber) {
   validateMinArgCount(2, length, lineNumber);
 
+  // flag if we can return integer, or if we must return float
+  bool resIsInt = true;
+
   // type check
   enum Type allowedTypes[] = {TYPE_INT, TYPE_FLOAT};
   for (int i = 0; i < length; i++) {
+    resIsInt = args[i]->type == TYPE_INT && resIsInt;
     validateType(allowedTypes, 3, args[i]->type, i + 1, lineNumber, "divide");
   };
-  
-  // initial value
-  double *p_res = (double *) malloc(sizeof(double));
-  *p_res = args[0]->type == TYPE_FLOAT 
-      ? *((double *) args[0]->p_val) 
-      : *((int *) args[0]->p_val);;
 
-  // divide each arg from *p_res
+  // throw error for division by 0
   for (int i = 1; i < length; i++) {
     double val = args[i]->type == TYPE_FLOAT 
       ? *((double *) args[i]->p_val) 
-      : *((int *) args[i]->p_val);
+      : *((int64_t *) args[i]->p_val);
 
     if (val == 0) {
-      // throw error for division by 0
       printf(
         "Runtime Error @ Line %i: Division by 0.\n", 
         lineNumber
       );
-      exit(0);
+      exit(1);
     };
+  }
+
+  if (resIsInt) {
+    // case where we can return int
+    int64_t *p_res = (int64_t *) malloc(sizeof(int64_t));
+    *p_res = *((int64_t *) args[0]->p_val);
 
-    *p_res /= val;
+    // divide each arg from *p_res
+    for (int i = 1; i < length; i++) {
+      *p_res /= *((int64_t *) args[i]->p_val);
+    }
+
+    return Generic_new(TYPE_INT, p_res, 0);
+  }
+
+  // initial value
+  double *p_res = (double *) malloc(sizeof(double));
+  *p_res = args[0]->type == TYPE_FLOAT 
+      ? *((double *) args[0]->p_val) 
+      : *((int64_t *) args[0]->p_val);;
+
+  // divide each arg from *p_res
+  for (int i = 1; i < length; i++) {
+    *p_res /= args[i]->type == TYPE_FLOAT 
+      ? *((double *) args[i]->p_val) 
+      : *((int64_t *) args[i]->p_val);
   }
 
   return Generic_new(TYPE_FLOAT, p_res, 0);
@@ -1162,12 +1275,12 @@ Generic *StdLib_multiply(Scope *p_scope, Generic *args[], int length, int lineNu
   
   if (resIsInt) {
     // case where we can return int
-    int *p_res = (int *) malloc(sizeof(int));
-    *p_res = *((int *) args[0]->p_val);;
+    int64_t *p_res = (int64_t *) malloc(sizeof(int64_t));
+    *p_res = *((int64_t *) args[0]->p_val);;
 
     // multiply each arg to *p_res
     for (int i = 1; i < length; i++) {
-      *p_res *= *((int *) args[i]->p_val);
+      *p_res *= *((int64_t *) args[i]->p_val);
     }
 
     return Generic_new(TYPE_INT, p_res, 0);
@@ -1177,13 +1290,13 @@ Generic *StdLib_multiply(Scope *p_scope, Generic *args[], int length, int lineNu
     double *p_res = (double *) malloc(sizeof(double));
     *p_res = args[0]->type == TYPE_FLOAT 
         ? *((double *) args[0]->p_val) 
-        : *((int *) args[0]->p_val);
+        : *((int64_t *) args[0]->p_val);
 
     // multiply each arg to *p_res
     for (int i = 1; i < length; i++) {
       *p_res *= args[i]->type == TYPE_FLOAT 
         ? *((double *) args[i]->p_val) 
-        : *((int *) args[i]->p_val);
+        : *((int64_t *) args[i]->p_val);
     }
 
     return Generic_new(TYPE_FLOAT, p_res, 0);
@@ -1199,27 +1312,27 @@ Generic *StdLib_remainder(Scope *p_scope, Generic *args[], int length, int lineN
   validateType(allowedTypes, 3, args[0]->type, 1, lineNumber, "remainder");
   validateType(allowedTypes, 3, args[1]->type, 2, lineNumber, "remainder");
 
-  if ((args[1]->type == TYPE_FLOAT ? *((double *) args[1]->p_val) : *((int *) args[1]->p_val)) == 0) {
+  if ((args[1]->type == TYPE_FLOAT ? *((double *) args[1]->p_val) : *((int64_t *) args[1]->p_val)) == 0) {
     // throw error for division by 0
     printf(
       "Runtime Error @ Line %i: Remainder of division by 0.\n", 
       lineNumber
     );
-    exit(0);
+    exit(1);
   };
 
   if (args[0]->type == TYPE_INT && args[1]->type == TYPE_INT) {
     // if we can return integer
-    int *p_res = (int *) malloc(sizeof(int));
-    *p_res = *((int *) args[0]->p_val) % *((int *) args[1]->p_val);
+    int64_t *p_res = (int64_t *) malloc(sizeof(int64_t));
+    *p_res = *((int64_t *) args[0]->p_val) % *((int64_t *) args[1]->p_val);
     return Generic_new(TYPE_INT, p_res, 0);
   } else {
     // if we must return float
     double *p_res = (double *) malloc(sizeof(double));
 
     *p_res = fmod(
-      (args[0]->type == TYPE_FLOAT ? *((double *) args[0]->p_val) : *((int *) args[0]->p_val)),
-      (args[1]->type == TYPE_FLOAT ? *((double *) args[1]->p_val) : *((int *) args[1]->p_val))
+      (args[0]->type == TYPE_FLOAT ? *((double *) args[0]->p_val) : *((int64_t *) args[0]->p_val)),
+      (args[1]->type == TYPE_FLOAT ? *((double *) args[1]->p_val) : *((int64_t *) args[1]->p_val))
     );
 
     return Generic_new(TYPE_FLOAT, p_res, 0);
@@ -1237,11 +1350,11 @@ Generic *StdLib_power(Scope *p_scope, Generic *args[], int length, int lineNumbe
 
   if (args[0]->type == TYPE_INT && args[1]->type == TYPE_INT) {
     // if we can return integer
-    int *p_res = (int *) malloc(sizeof(int));
+    int64_t *p_res = (int64_t *) malloc(sizeof(int64_t));
 
     *p_res = pow(
-      *((int *) args[0]->p_val),
-      *((int *) args[1]->p_val)
+      *((int64_t *) args[0]->p_val),
+      *((int64_t *) args[1]->p_val)
     );
 
     return Generic_new(TYPE_INT, p_res, 0);
@@ -1250,8 +1363,8 @@ Generic *StdLib_power(Scope *p_scope, Generic *args[], int length, int lineNumbe
     double *p_res = (double *) malloc(sizeof(double));
 
     *p_res = pow(
-      (args[0]->type == TYPE_FLOAT ? *((double *) args[0]->p_val) : *((int *) args[0]->p_val)),
-      (args[1]->type == TYPE_FLOAT ? *((double *) args[1]->p_val) : *((int *) args[1]->p_val))
+      (args[0]->type == TYPE_FLOAT ? *((double *) args[0]->p_val) : *((int64_t *) args[0]->p_val)),
+      (args[1]->type == TYPE_FLOAT ? *((double *) args[1]->p_val) : *((int64_t *) args[1]->p_val))
     );
 
     return Generic_new(TYPE_FLOAT, p_res, 0);
@@ -1279,12 +1392,12 @@ Generic *StdLib_floor(Scope *p_scope, Generic *args[], int length, int lineNumbe
 
   if (args[0]->type == TYPE_INT) {
     // Already an integer, return as-is
-    int *p_res = (int *) malloc(sizeof(int));
-    *p_res = *((int *) args[0]->p_val);
+    int64_t *p_res = (int64_t *) malloc(sizeof(int64_t));
+    *p_res = *((int64_t *) args[0]->p_val);
     return Generic_new(TYPE_INT, p_res, 0);
   } else {
     // Float - use floor function
-    int *p_res = (int *) malloc(sizeof(int));
+    int64_t *p_res = (int64_t *) malloc(sizeof(int64_t));
     *p_res = (int) floor(*((double *) args[0]->p_val));
     return Generic_new(TYPE_INT, p_res, 0);
   }
@@ -1300,12 +1413,12 @@ Generic *StdLib_ceil(Scope *p_scope, Generic *args[], int length, int lineNumber
 
   if (args[0]->type == TYPE_INT) {
     // Already an integer, return as-is
-    int *p_res = (int *) malloc(sizeof(int));
-    *p_res = *((int *) args[0]->p_val);
+    int64_t *p_res = (int64_t *) malloc(sizeof(int64_t));
+    *p_res = *((int64_t *) args[0]->p_val);
     return Generic_new(TYPE_INT, p_res, 0);
   } else {
     // Float - use ceil function
-    int *p_res = (int *) malloc(sizeof(int));
+    int64_t *p_res = (int64_t *) malloc(sizeof(int64_t));
     *p_res = (int) ceil(*((double *) args[0]->p_val));
     return Generic_new(TYPE_INT, p_res, 0);
   }
@@ -1321,12 +1434,12 @@ Generic *StdLib_round(Scope *p_scope, Generic *args[], int length, int lineNumbe
 
   if (args[0]->type == TYPE_INT) {
     // Already an integer, return as-is
-    int *p_res = (int *) malloc(sizeof(int));
-    *p_res = *((int *) args[0]->p_val);
+    int64_t *p_res = (int64_t *) malloc(sizeof(int64_t));
+    *p_res = *((int64_t *) args[0]->p_val);
     return Generic_new(TYPE_INT, p_res, 0);
   } else {
     // Float - use round function
-    int *p_res = (int *) malloc(sizeof(int));
+    int64_t *p_res = (int64_t *) malloc(sizeof(int64_t));
     *p_res = (int) round(*((double *) args[0]->p_val));
     return Generic_new(TYPE_INT, p_res, 0);
   }
@@ -1341,8 +1454,8 @@ Generic *StdLib_abs(Scope *p_scope, Generic *args[], int length, int lineNumber)
   validateType(allowedTypes, 2, args[0]->type, 1, lineNumber, "abs");
 
   if (args[0]->type == TYPE_INT) {
-    int *p_res = (int *) malloc(sizeof(int));
-    int val = *((int *) args[0]->p_val);
+    int64_t *p_res = (int64_t *) malloc(sizeof(int64_t));
+    int64_t val = *((int64_t *) args[0]->p_val);
     *p_res = val < 0 ? -val : val;
     return Generic_new(TYPE_INT, p_res, 0);
   } else {
@@ -1376,11 +1489,11 @@ Generic *StdLib_min(Scope *p_scope, Generic *args[], int length, int lineNumber)
   if (hasFloat) {
     // Find minimum as float
     double minVal = args[0]->type == TYPE_FLOAT ?
-      *((double *) args[0]->p_val) : (double)(*((int *) args[0]->p_val));
+      *((double *) args[0]->p_val) : (double)(*((int64_t *) args[0]->p_val));
 
     for (int i = 1; i < length; i++) {
       double val = args[i]->type == TYPE_FLOAT ?
-        *((double *) args[i]->p_val) : (double)(*((int *) args[i]->p_val));
+        *((double *) args[i]->p_val) : (double)(*((int64_t *) args[i]->p_val));
       if (val < minVal) minVal = val;
     }
 
@@ -1389,13 +1502,13 @@ Generic *StdLib_min(Scope *p_scope, Generic *args[], int length, int lineNumber)
     return Generic_new(TYPE_FLOAT, p_res, 0);
   } else {
     // All integers
-    int minVal = *((int *) args[0]->p_val);
+    int64_t minVal = *((int64_t *) args[0]->p_val);
     for (int i = 1; i < length; i++) {
-      int val = *((int *) args[i]->p_val);
+      int64_t val = *((int64_t *) args[i]->p_val);
       if (val < minVal) minVal = val;
     }
 
-    int *p_res = (int *) malloc(sizeof(int));
+    int64_t *p_res = (int64_t *) malloc(sizeof(int64_t));
     *p_res = minVal;
     return Generic_new(TYPE_INT, p_res, 0);
   }
@@ -1425,11 +1538,11 @@ Generic *StdLib_max(Scope *p_scope, Generic *args[], int length, int lineNumber)
   if (hasFloat) {
     // Find maximum as float
     double maxVal = args[0]->type == TYPE_FLOAT ?
-      *((double *) args[0]->p_val) : (double)(*((int *) args[0]->p_val));
+      *((double *) args[0]->p_val) : (double)(*((int64_t *) args[0]->p_val));
 
     for (int i = 1; i < length; i++) {
       double val = args[i]->type == TYPE_FLOAT ?
-        *((double *) args[i]->p_val) : (double)(*((int *) args[i]->p_val));
+        *((double *) args[i]->p_val) : (double)(*((int64_t *) args[i]->p_val));
       if (val > maxVal) maxVal = val;
     }
 
@@ -1438,13 +1551,13 @@ Generic *StdLib_max(Scope *p_scope, Generic *args[], int length, int lineNumber)
     return Generic_new(TYPE_FLOAT, p_res, 0);
   } else {
     // All integers
-    int maxVal = *((int *) args[0]->p_val);
+    int64_t maxVal = *((int64_t *) args[0]->p_val);
     for (int i = 1; i < length; i++) {
-      int val = *((int *) args[i]->p_val);
+      int64_t val = *((int64_t *) args[i]->p_val);
       if (val > maxVal) maxVal = val;
     }
 
-    int *p_res = (int *) malloc(sizeof(int));
+    int64_t *p_res = (int64_t *) malloc(sizeof(int64_t));
     *p_res = maxVal;
     return Generic_new(TYPE_INT, p_res, 0);
   }
@@ -1461,7 +1574,7 @@ Generic *StdLib_sqrt(Scope *p_scope, Generic *args[], int length, int lineNumber
   double *p_res = (double *) malloc(sizeof(double));
 
   if (args[0]->type == TYPE_INT) {
-    *p_res = sqrt((double)(*((int *) args[0]->p_val)));
+    *p_res = sqrt((double)(*((int64_t *) args[0]->p_val)));
   } else {
     *p_res = sqrt(*((double *) args[0]->p_val));
   }
@@ -1477,15 +1590,15 @@ Generic *StdLib_random_int(Scope *p_scope, Generic *args[], int length, int line
   enum Type allowedTypes[] = {TYPE_INT};
   validateType(allowedTypes, 1, args[0]->type, 1, lineNumber, "random_int");
 
-  int maxVal = *((int *) args[0]->p_val);
+  int maxVal = *((int64_t *) args[0]->p_val);
 
   if (maxVal <= 0) {
     printf("Runtime Error @ Line %i: random_int argument must be positive (got %d).\n",
            lineNumber, maxVal);
-    exit(0);
+    exit(1);
   }
 
-  int *p_res = (int *) malloc(sizeof(int));
+  int64_t *p_res = (int64_t *) malloc(sizeof(int64_t));
   *p_res = rand() % maxVal;
 
   return Generic_new(TYPE_INT, p_res, 0);
@@ -1502,14 +1615,14 @@ Generic *StdLib_random_range(Scope *p_scope, Generic *args[], int length, int li
 
   // Convert both args to doubles
   double minVal = args[0]->type == TYPE_FLOAT ?
-    *((double *) args[0]->p_val) : (double)(*((int *) args[0]->p_val));
+    *((double *) args[0]->p_val) : (double)(*((int64_t *) args[0]->p_val));
   double maxVal = args[1]->type == TYPE_FLOAT ?
-    *((double *) args[1]->p_val) : (double)(*((int *) args[1]->p_val));
+    *((double *) args[1]->p_val) : (double)(*((int64_t *) args[1]->p_val));
 
   if (minVal >= maxVal) {
     printf("Runtime Error @ Line %i: random_range min must be less than max (got min=%f, max=%f).\n",
            lineNumber, minVal, maxVal);
-    exit(0);
+    exit(1);
   }
 
   double *p_res = (double *) malloc(sizeof(double));
@@ -1528,7 +1641,7 @@ Generic *StdLib_random_seed(Scope *p_scope, Generic *args[], int length, int lin
   enum Type allowedTypes[] = {TYPE_INT};
   validateType(allowedTypes, 1, args[0]->type, 1, lineNumber, "random_seed");
 
-  int seed = *((int *) args[0]->p_val);
+  int seed = *((int64_t *) args[0]->p_val);
   srand((unsigned int)seed);
 
   return Generic_new(TYPE_VOID, NULL, 0);
@@ -1551,10 +1664,10 @@ Generic *StdLib_loop(Scope *p_scope, Generic *args[], int length, int lineNumber
   validateMin(args[0]->p_val, 0, 1, lineNumber, "loop");
   
   // loop
-  for (int i = 0; i < *((int *) args[0]->p_val); i++) {
+  for (int i = 0; i < *((int64_t *) args[0]->p_val); i++) {
 
     // get arg to pass to cb
-    int *p_arg = (int *) malloc(sizeof(int));
+    int64_t *p_arg = (int64_t *) malloc(sizeof(int64_t));
     *p_arg = i;
     Generic *newArgs[1] = {Generic_new(TYPE_INT, p_arg, 0)};
     
@@ -1596,7 +1709,7 @@ Generic *StdLib_until(Scope *p_scope, Generic *args[], int length, int lineNumbe
   while (true) {
 
     // get index
-    int *p_i = (int *) malloc(sizeof(int));
+    int64_t *p_i = (int64_t *) malloc(sizeof(int64_t));
     *p_i = i;
     Generic *newArgs[2] = {Generic_copy(state), Generic_new(TYPE_INT, p_i, 0)};
 
@@ -1641,7 +1754,7 @@ Generic *StdLib_if(Scope *p_scope, Generic *args[], int length, int lineNumber)
         validateBinary(args[i]->p_val, i + 1, lineNumber, "if");
 
         // find if condition is true
-        conditionPassed = *((int *) args[i]->p_val) == 1;
+        conditionPassed = *((int64_t *) args[i]->p_val) == 1;
       }
     } else {
       // callback case
@@ -1666,7 +1779,7 @@ Generic *StdLib_wait(Scope *p_scope, Generic *args[], int length, int lineNumber
   int initialTime = clock();
   while (
     (((double) clock()) - ((double) initialTime)) / ((double) CLOCKS_PER_SEC)
-    < (args[0]->type == TYPE_INT ? *((int *) args[0]->p_val) : *((double *) args[0]->p_val))
+    < (args[0]->type == TYPE_INT ? *((int64_t *) args[0]->p_val) : *((double *) args[0]->p_val))
   ) {}
 
   return Generic_new(TYPE_VOID, NULL, 0);
@@ -1800,16 +1913,16 @@ Generic *StdLib_integer(Scope *p_scope, Generic *args[], int length, int lineNum
   enum Type allowedTypes[] = {TYPE_STRING, TYPE_INT, TYPE_FLOAT};
   validateType(allowedTypes, 3, args[0]->type, 1, lineNumber, "integer");
 
-  int *p_res = malloc(sizeof(int));
+  int64_t *p_res = malloc(sizeof(int64_t));
 
   if (args[0]->type == TYPE_STRING) {
     char *str = *((char **) args[0]->p_val);
-    *p_res = atoi(str);
+    *p_res = atoll(str);
   } else if (args[0]->type == TYPE_FLOAT) {
     double f = *((double *) args[0]->p_val);
-    *p_res = (int) f;
+    *p_res = (int64_t) f;
   } else {
-    int i = *((int *) args[0]->p_val);
+    int64_t i = *((int64_t *) args[0]->p_val);
     *p_res = i;
   }
 
@@ -1831,9 +1944,9 @@ Generic *StdLib_string(Scope *p_scope, Generic *args[], int length, int lineNumb
     res = malloc(sizeof(char) * (length + 1)); // allocate memory
     snprintf(res, length + 1, "%f", *((double *) args[0]->p_val)); // populate memory
   } else if (args[0]->type == TYPE_INT) {
-    int length = snprintf(NULL, 0, "%i", *((int *) args[0]->p_val));
+    int length = snprintf(NULL, 0, "%lld", (long long) *((int64_t *) args[0]->p_val));
     res = malloc(sizeof(char) * (length + 1));
-    snprintf(res, length + 1, "%i", *((int *) args[0]->p_val));
+    snprintf(res, length + 1, "%lld", (long long) *((int64_t *) args[0]->p_val));
   } else {
     int length = snprintf(NULL, 0, "%s", *((char **) args[0]->p_val));
     res = malloc(sizeof(char) * (length + 1));
@@ -1863,7 +1976,7 @@ Generic *StdLib_float(Scope *p_scope, Generic *args[], int length, int lineNumbe
     double f = *((double *) args[0]->p_val);
     *p_res = f;
   } else {
-    int i = *((int *) args[0]->p_val);
+    int64_t i = *((int64_t *) args[0]->p_val);
     *p_res = (double) i;
   }
 
@@ -1881,14 +1994,14 @@ Generic *StdLib_format_int(Scope *p_scope, Generic *args[], int length, int line
   enum Type allowedTypes2[] = {TYPE_INT};
   validateType(allowedTypes2, 1, args[1]->type, 2, lineNumber, "format-int");
 
-  int64_t value = *((int *) args[0]->p_val);
-  int base = *((int *) args[1]->p_val);
+  int64_t value = *((int64_t *) args[0]->p_val);
+  int base = *((int64_t *) args[1]->p_val);
 
   // Validate base
   if (base != 2 && base != 8 && base != 10 && base != 16) {
     printf("Runtime Error @ Line %i: format-int base must be 2, 8, 10, or 16, got %i.\n",
            lineNumber, base);
-    exit(0);
+    exit(1);
   }
 
   // Format using number_parse module
@@ -1915,16 +2028,16 @@ Generic *StdLib_format_float(Scope *p_scope, Generic *args[], int length, int li
   if (args[0]->type == TYPE_FLOAT) {
     value = *((double *) args[0]->p_val);
   } else {
-    value = (double) *((int *) args[0]->p_val);
+    value = (double) *((int64_t *) args[0]->p_val);
   }
 
-  int precision = *((int *) args[1]->p_val);
+  int precision = *((int64_t *) args[1]->p_val);
 
   // Validate precision
   if (precision < 0 || precision > 17) {
     printf("Runtime Error @ Line %i: format-float precision must be 0-17, got %i.\n",
            lineNumber, precision);
-    exit(0);
+    exit(1);
   }
 
   // Format using number_parse module
@@ -1957,6 +2070,56 @@ Generic *StdLib_type(Scope *p_scope, Generic *args[], int length, int lineNumber
   return Generic_new(TYPE_STRING, p_res, 0);
 }
 
+// true if value is one of the given types, as a Franz integer
+static Generic *typeGuard(Generic *value, enum Type types[], int typeCount) {
+  int64_t *p_res = (int64_t *) malloc(sizeof(int64_t));
+  *p_res = 0;
+  for (int i = 0; i < typeCount; i++) {
+    if (value->type == types[i]) *p_res = 1;
+  }
+  return Generic_new(TYPE_INT, p_res, 0);
+}
+
+// (is_int value)
+// returns 1 if value is an integer, 0 otherwise
+Generic *StdLib_is_int(Scope *p_scope, Generic *args[], int length, int lineNumber) {
+  validateArgCount(1, 1, length, lineNumber);
+  enum Type types[] = {TYPE_INT};
+  return typeGuard(args[0], types, 1);
+}
+
+// (is_float value)
+// returns 1 if value is a float, 0 otherwise
+Generic *StdLib_is_float(Scope *p_scope, Generic *args[], int length, int lineNumber) {
+  validateArgCount(1, 1, length, lineNumber);
+  enum Type types[] = {TYPE_FLOAT};
+  return typeGuard(args[0], types, 1);
+}
+
+// (is_string value)
+// returns 1 if value is a string, 0 otherwise
+Generic *StdLib_is_string(Scope *p_scope, Generic *args[], int length, int lineNumber) {
+  validateArgCount(1, 1, length, lineNumber);
+  enum Type types[] = {TYPE_STRING};
+  return typeGuard(args[0], types, 1);
+}
+
+// (is_list value)
+// returns 1 if value is a list, 0 otherwise
+Generic *StdLib_is_list(Scope *p_scope, Generic *args[], int length, int lineNumber) {
+  validateArgCount(1, 1, length, lineNumber);
+  enum Type types[] = {TYPE_LIST};
+  return typeGuard(args[0], types, 1);
+}
+
+// (is_function value)
+// returns 1 if value is a function, closure or built-in function, 0 otherwise
+Generic *StdLib_is_function(Scope *p_scope, Generic *args[], int length, int lineNumber) {
+  validateArgCount(1, 1, length, lineNumber);
+  enum Type types[] = {TYPE_FUNCTION, TYPE_NATIVEFUNCTION, TYPE_BYTECODE_CLOSURE};
+  return typeGuard(args[0], types, 3);
+}
+
 /* list and string */
 // (list args...)
 // returns a new list with args as values
@@ -1973,7 +2136,7 @@ Generic *StdLib_length(Scope *p_scope, Generic *args[], int length, int lineNumb
   validateType(allowedTypes, 3, args[0]->type, 1, lineNumber, "length");
 
   // create int
-  int *p_res = (int *) malloc(sizeof(int));
+  int64_t *p_res = (int64_t *) malloc(sizeof(int64_t));
 
   // get length and return
   if (args[0]->type == TYPE_LIST) *p_res = List_length((List *) args[0]->p_val);
@@ -2040,13 +2203,13 @@ Generic *StdLib_repeat(Scope *p_scope, Generic *args[], int length, int lineNumb
   validateType(allowedTypes2, 1, args[1]->type, 2, lineNumber, "repeat");
 
   char *str = *((char **) args[0]->p_val);
-  int count = *((int *) args[1]->p_val);
+  int count = *((int64_t *) args[1]->p_val);
 
   // Edge case: count <= 0 returns empty string
   if (count <= 0) {
     char *empty = (char *) malloc(sizeof(char));
     empty[0] = '\0';
-    return Generic_new(TYPE_STRING, &empty, 0);
+    return Generic_new(TYPE_STRING, boxString(empty), 0);
   }
 
   // Calculate total size needed
@@ -2062,7 +2225,94 @@ Generic *StdLib_repeat(Scope *p_scope, Generic *args[], int length, int lineNumb
     strcat(result, str);
   }
 
-  return Generic_new(TYPE_STRING, &result, 0);
+  return Generic_new(TYPE_STRING, boxString(result), 0);
+}
+
+// (concat arg1 arg2 arg3 ...)
+// same as join: returns strings or lists joined together
+Generic *StdLib_concat(Scope *p_scope, Generic *args[], int length, int lineNumber) {
+  return StdLib_join(p_scope, args, length, lineNumber);
+}
+
+// copy of string with every character mapped by convert (toupper, tolower)
+static Generic *convertCase(Generic *args[], int length, int lineNumber, char *funcName, int (*convert)(int)) {
+  validateArgCount(1, 1, length, lineNumber);
+
+  enum Type allowedTypes[] = {TYPE_STRING};
+  validateType(allowedTypes, 1, args[0]->type, 1, lineNumber, funcName);
+
+  char *res = strdup(*((char **) args[0]->p_val));
+  for (char *c = res; *c; c++) *c = (char) convert((unsigned char) *c);
+  return Generic_new(TYPE_STRING, boxString(res), 0);
+}
+
+// (uppercase string)
+// returns string with every letter in upper case
+Generic *StdLib_uppercase(Scope *p_scope, Generic *args[], int length, int lineNumber) {
+  return convertCase(args, length, lineNumber, "uppercase", toupper);
+}
+
+// (lowercase string)
+// returns string with every letter in lower case
+Generic *StdLib_lowercase(Scope *p_scope, Generic *args[], int length, int lineNumber) {
+  return convertCase(args, length, lineNumber, "lowercase", tolower);
+}
+
+// (substring string start end)
+// returns the characters of string from index start up to, not including, end
+Generic *StdLib_substring(Scope *p_scope, Generic *args[], int length, int lineNumber) {
+  validateArgCount(3, 3, length, lineNumber);
+
+  enum Type allowedTypes1[] = {TYPE_STRING};
+  validateType(allowedTypes1, 1, args[0]->type, 1, lineNumber, "substring");
+
+  enum Type allowedTypes2[] = {TYPE_INT};
+  validateType(allowedTypes2, 1, args[1]->type, 2, lineNumber, "substring");
+  validateType(allowedTypes2, 1, args[2]->type, 3, lineNumber, "substring");
+
+  char *str = *((char **) args[0]->p_val);
+  int inputLength = strlen(str);
+  validateRange(args[1]->p_val, 0, inputLength, 2, lineNumber, "substring");
+  validateRange(args[2]->p_val, *((int64_t *) args[1]->p_val), inputLength, 3, lineNumber, "substring");
+
+  int start = *((int64_t *) args[1]->p_val);
+  int end = *((int64_t *) args[2]->p_val);
+  return Generic_new(TYPE_STRING, boxString(strndup(str + start, end - start)), 0);
+}
+
+// (split string delimiter)
+// returns list of the parts of string between occurrences of delimiter
+// (an empty delimiter splits string into single characters)
+Generic *StdLib_split(Scope *p_scope, Generic *args[], int length, int lineNumber) {
+  validateArgCount(2, 2, length, lineNumber);
+
+  enum Type allowedTypes[] = {TYPE_STRING};
+  validateType(allowedTypes, 1, args[0]->type, 1, lineNumber, "split");
+  validateType(allowedTypes, 1, args[1]->type, 2, lineNumber, "split");
+
+  char *str = *((char **) args[0]->p_val);
+  char *delimiter = *((char **) args[1]->p_val);
+  size_t delimiterLength = strlen(delimiter);
+
+  Generic **parts = NULL;
+  int count = 0;
+  char *start = str;
+  while (1) {
+    char *end = delimiterLength == 0 ? (*start && start[1] ? start + 1 : NULL) : strstr(start, delimiter);
+    size_t partLength = end != NULL ? (size_t) (end - start) : strlen(start);
+
+    parts = (Generic **) realloc(parts, sizeof(Generic *) * (count + 1));
+    parts[count++] = Generic_new(TYPE_STRING, boxString(strndup(start, partLength)), 0);
+
+    if (end == NULL) break;
+    start = end + delimiterLength;
+  }
+
+  //  List_new stores copies
+  List *res = List_new(parts, count);
+  for (int i = 0; i < count; i++) Generic_free(parts[i]);
+  free(parts);
+  return Generic_new(TYPE_LIST, res, 0);
 }
 
 // (get list index1) or (get list index1 index2)
@@ -2082,16 +2332,16 @@ Generic *StdLib_get(Scope *p_scope, Generic *args[], int length, int lineNumber)
     validateRange(args[1]->p_val, 0, inputLength - 1, 2, lineNumber, "get");
 
     // single item from list
-    if (length == 2) return List_get((List *) (args[0]->p_val), *((int *) args[1]->p_val));
+    if (length == 2) return List_get((List *) (args[0]->p_val), *((int64_t *) args[1]->p_val));
 
     // multiple items from list
     else if (length == 3) {
-      validateRange(args[2]->p_val, *((int *) args[1]->p_val) + 1, inputLength, 3, lineNumber, "get");
+      validateRange(args[2]->p_val, *((int64_t *) args[1]->p_val) + 1, inputLength, 3, lineNumber, "get");
 
       return Generic_new(TYPE_LIST, List_sublist(
         (List *) (args[0]->p_val), 
-        *((int *) args[1]->p_val), 
-        *((int *) args[2]->p_val)
+        *((int64_t *) args[1]->p_val), 
+        *((int64_t *) args[2]->p_val)
       ), 0);
     }
 
@@ -2102,7 +2352,7 @@ Generic *StdLib_get(Scope *p_scope, Generic *args[], int length, int lineNumber)
     if (length == 2) {
       // single item from string
       char *res = malloc(sizeof(char) * 2);
-      res[0] = (*((char **) args[0]->p_val))[*((int *) args[1]->p_val)];
+      res[0] = (*((char **) args[0]->p_val))[*((int64_t *) args[1]->p_val)];
       res[1] = '\0';
 
       char **p_res = (char **) malloc(sizeof(char *));
@@ -2111,11 +2361,11 @@ Generic *StdLib_get(Scope *p_scope, Generic *args[], int length, int lineNumber)
       return Generic_new(TYPE_STRING, p_res, 0);
     } else if (length == 3) {
       // mutliple items from string
-      validateRange(args[2]->p_val, *((int *) args[1]->p_val) + 1, inputLength, 3, lineNumber, "get");
+      validateRange(args[2]->p_val, *((int64_t *) args[1]->p_val) + 1, inputLength, 3, lineNumber, "get");
 
       // create substring
-      int start = *((int *) args[1]->p_val);
-      int end = *((int *) args[2]->p_val);
+      int start = *((int64_t *) args[1]->p_val);
+      int end = *((int64_t *) args[2]->p_val);
 
       char *res = malloc(sizeof(char) * (end - start + 1));
       strncpy(
@@ -2171,7 +2421,7 @@ Generic *StdLib_insert(Scope *p_scope, Generic *args[], int length, int lineNumb
       ), 0);
     } else if (length == 3) {
       return Generic_new(TYPE_LIST, List_insert(
-        (List *) (args[0]->p_val), args[1], *((int *) args[2]->p_val)
+        (List *) (args[0]->p_val), args[1], *((int64_t *) args[2]->p_val)
       ), 0); 
     }
   } else if (args[0]->type == TYPE_STRING) {
@@ -2189,11 +2439,11 @@ Generic *StdLib_insert(Scope *p_scope, Generic *args[], int length, int lineNumb
 
 
       // copy / concat
-      strncpy(res, *((char **) args[0]->p_val), *((int *) args[2]->p_val));
-      res[*((int *) args[2]->p_val)] = '\0';
+      strncpy(res, *((char **) args[0]->p_val), *((int64_t *) args[2]->p_val));
+      res[*((int64_t *) args[2]->p_val)] = '\0';
 
       strcat(res, *((char **) args[1]->p_val));
-      strcat(res, &((*((char **) args[0]->p_val))[*((int *) args[2]->p_val)]));
+      strcat(res, &((*((char **) args[0]->p_val))[*((int64_t *) args[2]->p_val)]));
 
       // pointer
       char **p_res = (char **) malloc(sizeof(char *));
@@ -2232,13 +2482,13 @@ Generic *StdLib_set(Scope *p_scope, Generic *args[], int length, int lineNumber)
   if (args[0]->type == TYPE_LIST) {
     // list case
     return Generic_new(TYPE_LIST, List_set(
-      (List *) (args[0]->p_val), args[1], *((int *) args[2]->p_val)
+      (List *) (args[0]->p_val), args[1], *((int64_t *) args[2]->p_val)
     ), 0); 
 
   } else if (args[0]->type == TYPE_STRING) {
     char *target = *((char **) args[0]->p_val);
     char *item = *((char **) args[1]->p_val);
-    int index = *((int *) args[2]->p_val);
+    int index = *((int64_t *) args[2]->p_val);
 
     // string case
     // length of result
@@ -2284,13 +2534,13 @@ Generic *StdLib_delete(Scope *p_scope, Generic *args[], int length, int lineNumb
 
     // list case
     if (length == 2) {
-      return Generic_new(TYPE_LIST, List_delete((List *) (args[0]->p_val), *((int *) args[1]->p_val)), 0);
+      return Generic_new(TYPE_LIST, List_delete((List *) (args[0]->p_val), *((int64_t *) args[1]->p_val)), 0);
     } else if (length == 3) {
-      validateRange(args[2]->p_val, *((int *) args[1]->p_val) + 1, inputLength, 3, lineNumber, "delete");
+      validateRange(args[2]->p_val, *((int64_t *) args[1]->p_val) + 1, inputLength, 3, lineNumber, "delete");
 
       // case where we must delete multiple items
       return Generic_new(TYPE_LIST, List_deleteMultiple(
-        (List *) (args[0]->p_val), *((int *) args[1]->p_val), *((int *) args[2]->p_val)
+        (List *) (args[0]->p_val), *((int64_t *) args[1]->p_val), *((int64_t *) args[2]->p_val)
       ), 0);
     }
 
@@ -2300,7 +2550,7 @@ Generic *StdLib_delete(Scope *p_scope, Generic *args[], int length, int lineNumb
     validateRange(args[1]->p_val, 0, inputLength - 1, 2, lineNumber, "delete");
 
     char *target = *((char **) args[0]->p_val);
-    int index1 = *((int *) args[1]->p_val);
+    int index1 = *((int64_t *) args[1]->p_val);
 
     if (length == 2) {
 
@@ -2323,9 +2573,9 @@ Generic *StdLib_delete(Scope *p_scope, Generic *args[], int length, int lineNumb
       return Generic_new(TYPE_STRING, p_res, 0);
     } else if (length == 3) {
       // mutliple items from string
-      validateRange(args[2]->p_val, *((int *) args[1]->p_val) + 1, inputLength, 3, lineNumber, "delete");
+      validateRange(args[2]->p_val, *((int64_t *) args[1]->p_val) + 1, inputLength, 3, lineNumber, "delete");
 
-      int index2 = *((int *) args[2]->p_val);
+      int index2 = *((int64_t *) args[2]->p_val);
 
       // length of result
       int stringSize = strlen(target) + 1 - (index2 - index1);
@@ -2351,7 +2601,7 @@ Generic *StdLib_delete(Scope *p_scope, Generic *args[], int length, int lineNumb
 }
 
 // (map list fn)
-// applys fn to every item in list, returns list with results
+// applys fn to every item (and its index, if fn takes two arguments) in list, returns list with results
 Generic *StdLib_map(Scope *p_scope, Generic *args[], int length, int lineNumber) {
   validateArgCount(2, 2, length, lineNumber);
 
@@ -2365,12 +2615,10 @@ Generic *StdLib_map(Scope *p_scope, Generic *args[], int length, int lineNumber)
   Generic *res = Generic_copy(args[0]);
   List *p_list = (List *) (res->p_val);
 
+  int argCount = callbackArgCount(args[1], 1, 2);
   for (int i = 0; i < p_list->len; i += 1) {
-    int *p_i = (int *) malloc(sizeof(int));
-    *p_i = i;
-
-    Generic *newArgs[] = {p_list->vals[i], Generic_new(TYPE_INT, p_i, 0)};
-    p_list->vals[i] = applyFunc(args[1], p_scope, newArgs, 2, lineNumber);
+    Generic *newArgs[] = {p_list->vals[i], indexArg(i, argCount, 2)};
+    p_list->vals[i] = applyFunc(args[1], p_scope, newArgs, argCount, lineNumber);
   }
 
   return res;
@@ -2402,14 +2650,11 @@ Generic *StdLib_reduce(Scope *p_scope, Generic *args[], int length, int lineNumb
   List *p_list = (List *) (args[0]->p_val);
  
   // loop on every item
+  int argCount = callbackArgCount(args[1], 2, 3);
   for (int i = 0; i < p_list->len; i += 1) {
-    // create pointer for index, to create generic for args
-    int *p_i = (int *) malloc(sizeof(int));
-    *p_i = i;
-    
     // apply function
-    Generic *newArgs[] = {p_acc, Generic_copy(p_list->vals[i]), Generic_new(TYPE_INT, p_i, 0)};
-    p_acc = applyFunc(args[1], p_scope, newArgs, 3, lineNumber);
+    Generic *newArgs[] = {p_acc, Generic_copy(p_list->vals[i]), indexArg(i, argCount, 3)};
+    p_acc = applyFunc(args[1], p_scope, newArgs, argCount, lineNumber);
   }
 
   return p_acc;
@@ -2425,11 +2670,11 @@ Generic *StdLib_range(Scope *p_scope, Generic *args[], int length, int lineNumbe
   validateMin(args[0]->p_val, 0, 1, lineNumber, "range");
 
   // make list of arguments to pass to List_new
-  int count = *((int *) args[0]->p_val);
+  int count = *((int64_t *) args[0]->p_val);
   Generic *argList[count];
   
   for (int i = 0; i < count; i++) {
-    int *p_i = (int *) malloc(sizeof(int));
+    int64_t *p_i = (int64_t *) malloc(sizeof(int64_t));
     *p_i = i;
     argList[i] = Generic_new(TYPE_INT, p_i, 0);
   }
@@ -2465,7 +2710,7 @@ Generic *StdLib_find(Scope *p_scope, Generic *args[], int length, int lineNumber
     if (p_sub == NULL) return Generic_new(TYPE_VOID, NULL, 0);
 
     // get index and return
-    int *p_index = malloc(sizeof(int)); 
+    int64_t *p_index = malloc(sizeof(int64_t)); 
     *p_index = p_sub - *((char **) args[0]->p_val);
 
     return Generic_new(TYPE_INT, p_index, 0);
@@ -2478,7 +2723,7 @@ Generic *StdLib_find(Scope *p_scope, Generic *args[], int length, int lineNumber
 
       // if found, return index
       if (Generic_is(p_list->vals[i], args[1])) {
-        int *p_index = (int *) malloc(sizeof(int));
+        int64_t *p_index = (int64_t *) malloc(sizeof(int64_t));
         *p_index = i;
         return Generic_new(TYPE_INT, p_index, 0);
       }
@@ -2533,12 +2778,12 @@ Generic *StdLib_variant_tag(Scope *p_scope, Generic *args[], int length, int lin
   List *lst = (List *) args[0]->p_val;
   if (lst->len < 1) {
     printf("Runtime Error @ Line %i: variant_tag expected non-empty variant.\n", lineNumber);
-    exit(0);
+    exit(1);
   }
   Generic *tag = List_get(lst, 0);
   if (tag->type != TYPE_STRING) {
     printf("Runtime Error @ Line %i: variant_tag expected string tag.\n", lineNumber);
-    exit(0);
+    exit(1);
   }
   return tag;
 }
@@ -2553,12 +2798,12 @@ Generic *StdLib_variant_values(Scope *p_scope, Generic *args[], int length, int
   List *lst = (List *) args[0]->p_val;
   if (lst->len < 2) {
     printf("Runtime Error @ Line %i: variant_values expected [tag, values] structure.\n", lineNumber);
-    exit(0);
+    exit(1);
   }
   Generic *vals = List_get(lst, 1);
   if (vals->type != TYPE_LIST) {
     printf("Runtime Error @ Line %i: variant_values expected second element to be list.\n", lineNumber);
-    exit(0);
+    exit(1);
   }
   return vals;
 }
@@ -2574,17 +2819,17 @@ Generic *StdLib_match(Scope *p_scope, Generic *args[], int length, int lineNumbe
   List *lst = (List *) args[0]->p_val;
   if (lst->len < 2) {
     printf("Runtime Error @ Line %i: match expected variant [tag, values].\n", lineNumber);
-    exit(0);
+    exit(1);
   }
   Generic *tag = List_get(lst, 0);
   if (tag->type != TYPE_STRING) {
     printf("Runtime Error @ Line %i: match expected string tag.\n", lineNumber);
-    exit(0);
+    exit(1);
   }
   Generic *valsGen = List_get(lst, 1);
   if (valsGen->type != TYPE_LIST) {
     printf("Runtime Error @ Line %i: match expected list of values.\n", lineNumber);
-    exit(0);
+    exit(1);
   }
   List *values = (List *) valsGen->p_val;
 
@@ -2596,12 +2841,12 @@ Generic *StdLib_match(Scope *p_scope, Generic *args[], int length, int lineNumbe
   for (int i = 1; i < pairsEnd; i += 2) {
     if (args[i]->type != TYPE_STRING) {
       printf("Runtime Error @ Line %i: match expected string tag at argument #%i.\n", lineNumber, i + 1);
-      exit(0);
+      exit(1);
     }
     //  Support closures in match
     if (args[i + 1]->type != TYPE_FUNCTION && args[i + 1]->type != TYPE_NATIVEFUNCTION && args[i + 1]->type != TYPE_BYTECODE_CLOSURE) {
       printf("Runtime Error @ Line %i: match expected function at argument #%i.\n", lineNumber, i + 2);
-      exit(0);
+      exit(1);
     }
 
     if (strcmp(*((char **) args[i]->p_val), *((char **) tag->p_val)) == 0) {
@@ -2612,11 +2857,9 @@ Generic *StdLib_match(Scope *p_scope, Generic *args[], int length, int lineNumbe
         callArgs = (Generic **) malloc(sizeof(Generic *) * argcVals);
         for (int k = 0; k < argcVals; k++) callArgs[k] = Generic_copy(values->vals[k]);
       }
+      // applyFunc frees the temporary copies
       Generic *res = applyFunc(args[i + 1], p_scope, callArgs, argcVals, lineNumber);
-      if (callArgs) {
-        for (int k = 0; k < argcVals; k++) if (callArgs[k]->refCount == 0) Generic_free(callArgs[k]);
-        free(callArgs);
-      }
+      free(callArgs);
       Generic_free(tag);
       Generic_free(valsGen);
       return res;
@@ -2629,12 +2872,11 @@ Generic *StdLib_match(Scope *p_scope, Generic *args[], int length, int lineNumbe
     //  Support closures in match default case
     if (fn->type != TYPE_FUNCTION && fn->type != TYPE_NATIVEFUNCTION && fn->type != TYPE_BYTECODE_CLOSURE) {
       printf("Runtime Error @ Line %i: match expected function for default case.\n", lineNumber);
-      exit(0);
+      exit(1);
     }
     Generic *oneArg[1];
     oneArg[0] = Generic_copy(args[0]);
     Generic *res = applyFunc(fn, p_scope, oneArg, 1, lineNumber);
-    if (oneArg[0]->refCount == 0) Generic_free(oneArg[0]);
     Generic_free(tag);
     Generic_free(valsGen);
     return res;
@@ -2695,11 +2937,61 @@ Generic *StdLib_is_empty(Scope *p_scope, Generic *args[], int length, int lineNu
   validateType(allowedTypes, 1, args[0]->type, 1, lineNumber, "empty?");
 
   List *lst = (List *) args[0]->p_val;
-  int *res = (int *) malloc(sizeof(int));
+  int64_t *res = (int64_t *) malloc(sizeof(int64_t));
   *res = (lst->len == 0) ? 1 : 0;
   return Generic_new(TYPE_INT, res, 0);
 }
 
+// (nth lst index)
+// Returns element at index of list
+Generic *StdLib_nth(Scope *p_scope, Generic *args[], int length, int lineNumber) {
+  validateArgCount(2, 2, length, lineNumber);
+  enum Type allowedTypes1[] = {TYPE_LIST};
+  validateType(allowedTypes1, 1, args[0]->type, 1, lineNumber, "nth");
+  enum Type allowedTypes2[] = {TYPE_INT};
+  validateType(allowedTypes2, 1, args[1]->type, 2, lineNumber, "nth");
+
+  List *lst = (List *) args[0]->p_val;
+  validateRange(args[1]->p_val, 0, lst->len - 1, 2, lineNumber, "nth");
+  return List_get(lst, *((int64_t *) args[1]->p_val));
+}
+
+// (map2 lst1 lst2 fn)
+// applys fn to the items of both lists at every index, returns list with results
+// (as long as the shorter list)
+Generic *StdLib_map2(Scope *p_scope, Generic *args[], int length, int lineNumber) {
+  validateArgCount(3, 3, length, lineNumber);
+
+  enum Type allowedTypes1[] = {TYPE_LIST};
+  validateType(allowedTypes1, 1, args[0]->type, 1, lineNumber, "map2");
+  validateType(allowedTypes1, 1, args[1]->type, 2, lineNumber, "map2");
+
+  enum Type allowedTypes2[] = {TYPE_FUNCTION, TYPE_NATIVEFUNCTION, TYPE_BYTECODE_CLOSURE};
+  validateType(allowedTypes2, 3, args[2]->type, 3, lineNumber, "map2");
+
+  List *list1 = (List *) args[0]->p_val;
+  List *list2 = (List *) args[1]->p_val;
+  int count = list1->len < list2->len ? list1->len : list2->len;
+  int argCount = callbackArgCount(args[2], 2, 3);
+
+  Generic **mapped = (Generic **) malloc(sizeof(Generic *) * (count > 0 ? count : 1));
+  for (int i = 0; i < count; i++) {
+    Generic *callArgs[] = {Generic_copy(list1->vals[i]), Generic_copy(list2->vals[i]), indexArg(i, argCount, 3)};
+
+    args[2]->refCount++;
+    mapped[i] = applyFunc(args[2], p_scope, callArgs, argCount, lineNumber);
+    args[2]->refCount--;
+  }
+
+  //  List_new stores copies
+  List *res = List_new(mapped, count);
+  for (int i = 0; i < count; i++) {
+    if (mapped[i]->refCount == 0) Generic_free(mapped[i]);
+  }
+  free(mapped);
+  return Generic_new(TYPE_LIST, res, 0);
+}
+
 /* Immutability Functions */
 
 // (freeze name) - Makes an existing mutable binding immutable
@@ -2727,7 +3019,7 @@ Generic *StdLib_pipe(Scope *p_scope, Generic *args[], int length, int lineNumber
   // Validate minimum argument count (at least value + one function)
   if (length < 2) {
     printf("Runtime Error @ Line %i: pipe requires at least a value and one function.\n", lineNumber);
-    exit(0);
+    exit(1);
   }
 
   // Start with the initial value (first argument)
@@ -2740,7 +3032,7 @@ Generic *StdLib_pipe(Scope *p_scope, Generic *args[], int length, int lineNumber
     if (args[i]->type != TYPE_FUNCTION && args[i]->type != TYPE_NATIVEFUNCTION && args[i]->type != TYPE_BYTECODE_CLOSURE) {
       printf("Runtime Error @ Line %i: pipe argument %i must be a function.\n",
              lineNumber, i + 1);
-      exit(0);
+      exit(1);
     }
 
     // Prepare arguments for function call
@@ -2770,13 +3062,13 @@ Generic *StdLib_partial(Scope *p_scope, Generic *args[], int length, int lineNum
   // Validate: at least function + 1 fixed argument
   if (length < 2) {
     printf("Runtime Error @ Line %i: partial requires a function and at least one argument.\n", lineNumber);
-    exit(0);
+    exit(1);
   }
 
   //  Validate first argument is a function (including closures)
   if (args[0]->type != TYPE_FUNCTION && args[0]->type != TYPE_NATIVEFUNCTION && args[0]->type != TYPE_BYTECODE_CLOSURE) {
     printf("Runtime Error @ Line %i: partial first argument must be a function.\n", lineNumber);
-    exit(0);
+    exit(1);
   }
 
   // Create a list to store: [function, arg1, arg2, ...]
@@ -2803,20 +3095,20 @@ Generic *StdLib_partial(Scope *p_scope, Generic *args[], int length, int lineNum
 Generic *StdLib_call(Scope *p_scope, Generic *args[], int length, int lineNumber) {
   if (length < 1) {
     printf("Runtime Error @ Line %i: call requires at least a partial function.\n", lineNumber);
-    exit(0);
+    exit(1);
   }
 
   // First arg should be the partial (a list)
   if (args[0]->type != TYPE_LIST) {
     printf("Runtime Error @ Line %i: call first argument must be a partial function (got list from partial).\n", lineNumber);
-    exit(0);
+    exit(1);
   }
 
   List *partial = (List *) args[0]->p_val;
 
   if (partial->len < 2) {
     printf("Runtime Error @ Line %i: Invalid partial function - must have [function, ...fixed_args].\n", lineNumber);
-    exit(0);
+    exit(1);
   }
 
   // Extract original function (first element)
@@ -2825,7 +3117,7 @@ Generic *StdLib_call(Scope *p_scope, Generic *args[], int length, int lineNumber
   //  Validate it's actually a function (including closures)
   if (original_fn->type != TYPE_FUNCTION && original_fn->type != TYPE_NATIVEFUNCTION && original_fn->type != TYPE_BYTECODE_CLOSURE) {
     printf("Runtime Error @ Line %i: First element of partial must be a function.\n", lineNumber);
-    exit(0);
+    exit(1);
   }
 
   // Count fixed args (all elements after function)
@@ -2872,7 +3164,7 @@ Generic *StdLib_call(Scope *p_scope, Generic *args[], int length, int lineNumber
 Generic *StdLib_thread_first(Scope *p_scope, Generic *args[], int length, int lineNumber) {
   if (length < 2) {
     printf("Runtime Error @ Line %i: thread-first requires at least a value and one form.\n", lineNumber);
-    exit(0);
+    exit(1);
   }
 
   // Start with the initial value
@@ -2885,14 +3177,14 @@ Generic *StdLib_thread_first(Scope *p_scope, Generic *args[], int length, int li
     // Form must be a list: [fn, arg2, arg3, ...]
     if (form->type != TYPE_LIST) {
       printf("Runtime Error @ Line %i: thread-first form must be a list [function, args...].\n", lineNumber);
-      exit(0);
+      exit(1);
     }
 
     List *form_list = (List *) form->p_val;
 
     if (form_list->len < 1) {
       printf("Runtime Error @ Line %i: thread-first form must have at least a function.\n", lineNumber);
-      exit(0);
+      exit(1);
     }
 
     Generic *fn = form_list->vals[0];
@@ -2900,7 +3192,7 @@ Generic *StdLib_thread_first(Scope *p_scope, Generic *args[], int length, int li
     //  Support closures in thread-first
     if (fn->type != TYPE_FUNCTION && fn->type != TYPE_NATIVEFUNCTION && fn->type != TYPE_BYTECODE_CLOSURE) {
       printf("Runtime Error @ Line %i: thread-first form's first element must be a function.\n", lineNumber);
-      exit(0);
+      exit(1);
     }
 
     // Build arg list: [result, arg2, arg3, ...]
@@ -2931,7 +3223,7 @@ Generic *StdLib_thread_first(Scope *p_scope, Generic *args[], int length, int li
 Generic *StdLib_thread_last(Scope *p_scope, Generic *args[], int length, int lineNumber) {
   if (length < 2) {
     printf("Runtime Error @ Line %i: thread-last requires at least a value and one form.\n", lineNumber);
-    exit(0);
+    exit(1);
   }
 
   // Start with the initial value
@@ -2944,14 +3236,14 @@ Generic *StdLib_thread_last(Scope *p_scope, Generic *args[], int length, int lin
     // Form must be a list: [fn, arg1, arg2, ...]
     if (form->type != TYPE_LIST) {
       printf("Runtime Error @ Line %i: thread-last form must be a list [function, args...].\n", lineNumber);
-      exit(0);
+      exit(1);
     }
 
     List *form_list = (List *) form->p_val;
 
     if (form_list->len < 1) {
       printf("Runtime Error @ Line %i: thread-last form must have at least a function.\n", lineNumber);
-      exit(0);
+      exit(1);
     }
 
     Generic *fn = form_list->vals[0];
@@ -2959,7 +3251,7 @@ Generic *StdLib_thread_last(Scope *p_scope, Generic *args[], int length, int lin
     //  Support closures in thread-last
     if (fn->type != TYPE_FUNCTION && fn->type != TYPE_NATIVEFUNCTION && fn->type != TYPE_BYTECODE_CLOSURE) {
       printf("Runtime Error @ Line %i: thread-last form's first element must be a function.\n", lineNumber);
-      exit(0);
+      exit(1);
     }
 
     // Build arg list: [arg1, arg2, ..., result]
@@ -2994,7 +3286,7 @@ Generic *StdLib_thread_last(Scope *p_scope, Generic *args[], int length, int lin
 Generic *StdLib_map_last(Scope *p_scope, Generic *args[], int length, int lineNumber) {
   if (length != 2) {
     printf("Runtime Error @ Line %i: map-last requires exactly 2 arguments (callback, collection).\n", lineNumber);
-    exit(0);
+    exit(1);
   }
 
   // Reverse the arguments: map expects (collection, callback)
@@ -3011,7 +3303,7 @@ Generic *StdLib_map_last(Scope *p_scope, Generic *args[], int length, int lineNu
 Generic *StdLib_reduce_last(Scope *p_scope, Generic *args[], int length, int lineNumber) {
   if (length != 3) {
     printf("Runtime Error @ Line %i: reduce-last requires exactly 3 arguments (callback, init, collection).\n", lineNumber);
-    exit(0);
+    exit(1);
   }
 
   // Reorder arguments: reduce expects (collection, callback, init)
@@ -3026,11 +3318,11 @@ Generic *StdLib_reduce_last(Scope *p_scope, Generic *args[], int length, int lin
 
 // (filter collection callback)
 // Standard filter with collection-first signature (like map, reduce)
-// Callback signature: {item index -> boolean}
+// Callback signature: {item index -> boolean} or {item -> boolean}
 Generic *StdLib_filter(Scope *p_scope, Generic *args[], int length, int lineNumber) {
   if (length != 2) {
     printf("Runtime Error @ Line %i: filter requires exactly 2 arguments (collection, callback).\n", lineNumber);
-    exit(0);
+    exit(1);
   }
 
   enum Type allowedTypes[] = {TYPE_LIST};
@@ -3042,24 +3334,22 @@ Generic *StdLib_filter(Scope *p_scope, Generic *args[], int length, int lineNumb
   Generic **filtered = (Generic **) malloc(sizeof(Generic *) * input->len);
   int count = 0;
 
+  int argCount = callbackArgCount(args[1], 1, 2);
   for (int i = 0; i < input->len; i++) {
-    int *p_i = (int *) malloc(sizeof(int));
-    *p_i = i;
-
     // Protect input value from being freed by applyFunc
     input->vals[i]->refCount++;
 
-    Generic *funcArgs[] = {input->vals[i], Generic_new(TYPE_INT, p_i, 0)};
+    Generic *funcArgs[] = {input->vals[i], indexArg(i, argCount, 2)};
 
     args[1]->refCount++;
-    Generic *result = applyFunc(args[1], p_scope, funcArgs, 2, lineNumber);
+    Generic *result = applyFunc(args[1], p_scope, funcArgs, argCount, lineNumber);
     args[1]->refCount--;
 
     // Restore refCount
     input->vals[i]->refCount--;
 
     // If result is truthy (not 0), include this element
-    if (result->type == TYPE_INT && *((int *) result->p_val) != 0) {
+    if (result->type == TYPE_INT && *((int64_t *) result->p_val) != 0) {
       filtered[count++] = Generic_copy(input->vals[i]);
     }
 
@@ -3077,7 +3367,7 @@ Generic *StdLib_filter(Scope *p_scope, Generic *args[], int length, int lineNumb
 Generic *StdLib_filter_last(Scope *p_scope, Generic *args[], int length, int lineNumber) {
   if (length != 2) {
     printf("Runtime Error @ Line %i: filter-last requires exactly 2 arguments (callback, collection).\n", lineNumber);
-    exit(0);
+    exit(1);
   }
 
   // Reverse arguments: filter expects (collection, callback)
@@ -3099,7 +3389,7 @@ Generic *StdLib_filter_last(Scope *p_scope, Generic *args[], int length, int lin
 Generic *StdLib_dict(Scope *p_scope, Generic *args[], int length, int lineNumber) {
   if (length % 2 != 0) {
     printf("Runtime Error @ Line %i: dict requires an even number of arguments (key-value pairs).\n", lineNumber);
-    exit(0);
+    exit(1);
   }
 
   // Create new dict with appropriate capacity
@@ -3124,7 +3414,7 @@ Generic *StdLib_dict(Scope *p_scope, Generic *args[], int length, int lineNumber
 Generic *StdLib_dict_get(Scope *p_scope, Generic *args[], int length, int lineNumber) {
   if (length != 2) {
     printf("Runtime Error @ Line %i: dict_get requires exactly 2 arguments (dict, key).\n", lineNumber);
-    exit(0);
+    exit(1);
   }
 
   enum Type allowedTypes[] = {TYPE_DICT};
@@ -3148,7 +3438,7 @@ Generic *StdLib_dict_get(Scope *p_scope, Generic *args[], int length, int lineNu
 Generic *StdLib_dict_set(Scope *p_scope, Generic *args[], int length, int lineNumber) {
   if (length != 3) {
     printf("Runtime Error @ Line %i: dict_set requires exactly 3 arguments (dict, key, value).\n", lineNumber);
-    exit(0);
+    exit(1);
   }
 
   enum Type allowedTypes[] = {TYPE_DICT};
@@ -3169,7 +3459,7 @@ Generic *StdLib_dict_set(Scope *p_scope, Generic *args[], int length, int lineNu
 Generic *StdLib_dict_keys(Scope *p_scope, Generic *args[], int length, int lineNumber) {
   if (length != 1) {
     printf("Runtime Error @ Line %i: dict_keys requires exactly 1 argument (dict).\n", lineNumber);
-    exit(0);
+    exit(1);
   }
 
   enum Type allowedTypes[] = {TYPE_DICT};
@@ -3198,7 +3488,7 @@ Generic *StdLib_dict_keys(Scope *p_scope, Generic *args[], int length, int lineN
 Generic *StdLib_dict_values(Scope *p_scope, Generic *args[], int length, int lineNumber) {
   if (length != 1) {
     printf("Runtime Error @ Line %i: dict_values requires exactly 1 argument (dict).\n", lineNumber);
-    exit(0);
+    exit(1);
   }
 
   enum Type allowedTypes[] = {TYPE_DICT};
@@ -3227,7 +3517,7 @@ Generic *StdLib_dict_values(Scope *p_scope, Generic *args[], int length, int lin
 Generic *StdLib_dict_has(Scope *p_scope, Generic *args[], int length, int lineNumber) {
   if (length != 2) {
     printf("Runtime Error @ Line %i: dict_has requires exactly 2 arguments (dict, key).\n", lineNumber);
-    exit(0);
+    exit(1);
   }
 
   enum Type allowedTypes[] = {TYPE_DICT};
@@ -3238,7 +3528,7 @@ Generic *StdLib_dict_has(Scope *p_scope, Generic *args[], int length, int lineNu
 
   int has = Dict_has(dict, key);
 
-  int *result = (int *) malloc(sizeof(int));
+  int64_t *result = (int64_t *) malloc(sizeof(int64_t));
   *result = has;
   return Generic_new(TYPE_INT, result, 0);
 }
@@ -3249,7 +3539,7 @@ Generic *StdLib_dict_has(Scope *p_scope, Generic *args[], int length, int lineNu
 Generic *StdLib_dict_merge(Scope *p_scope, Generic *args[], int length, int lineNumber) {
   if (length != 2) {
     printf("Runtime Error @ Line %i: dict_merge requires exactly 2 arguments (dict1, dict2).\n", lineNumber);
-    exit(0);
+    exit(1);
   }
 
   enum Type allowedTypes[] = {TYPE_DICT};
@@ -3270,7 +3560,7 @@ Generic *StdLib_dict_merge(Scope *p_scope, Generic *args[], int length, int line
 Generic *StdLib_dict_remove(Scope *p_scope, Generic *args[], int length, int lineNumber) {
   if (length != 2) {
     printf("Runtime Error @ Line %i: dict_remove requires exactly 2 arguments (dict, key).\n", lineNumber);
-    exit(0);
+    exit(1);
   }
 
   enum Type allowedTypes[] = {TYPE_DICT};
@@ -3291,7 +3581,7 @@ Generic *StdLib_dict_remove(Scope *p_scope, Generic *args[], int length, int lin
 Generic *StdLib_dict_map(Scope *p_scope, Generic *args[], int length, int lineNumber) {
   if (length != 2) {
     printf("Runtime Error @ Line %i: dict_map requires exactly 2 arguments (dict, function).\n", lineNumber);
-    exit(0);
+    exit(1);
   }
 
   enum Type allowedTypes1[] = {TYPE_DICT};
@@ -3345,7 +3635,7 @@ Generic *StdLib_dict_map(Scope *p_scope, Generic *args[], int length, int lineNu
 Generic *StdLib_dict_filter(Scope *p_scope, Generic *args[], int length, int lineNumber) {
   if (length != 2) {
     printf("Runtime Error @ Line %i: dict_filter requires exactly 2 arguments (dict, function).\n", lineNumber);
-    exit(0);
+    exit(1);
   }
 
   enum Type allowedTypes1[] = {TYPE_DICT};
@@ -3382,7 +3672,7 @@ Generic *StdLib_dict_filter(Scope *p_scope, Generic *args[], int length, int lin
 
       // If result is truthy, keep this pair
       bool should_keep = false;
-      if (result->type == TYPE_INT && *((int *) result->p_val) != 0) {
+      if (result->type == TYPE_INT && *((int64_t *) result->p_val) != 0) {
         should_keep = true;
       }
 
@@ -3557,17 +3847,28 @@ Scope *newGlobal(int argc, char *argv[]) {
   Scope_set(p_global, "format-int", Generic_new(TYPE_NATIVEFUNCTION, &StdLib_format_int, 0), -1);
   Scope_set(p_global, "format-float", Generic_new(TYPE_NATIVEFUNCTION, &StdLib_format_float, 0), -1);
   Scope_set(p_global, "type", Generic_new(TYPE_NATIVEFUNCTION, &StdLib_type, 0), -1);
+  Scope_set(p_global, "is_int", Generic_new(TYPE_NATIVEFUNCTION, &StdLib_is_int, 0), -1);
+  Scope_set(p_global, "is_float", Generic_new(TYPE_NATIVEFUNCTION, &StdLib_is_float, 0), -1);
+  Scope_set(p_global, "is_string", Generic_new(TYPE_NATIVEFUNCTION, &StdLib_is_string, 0), -1);
+  Scope_set(p_global, "is_list", Generic_new(TYPE_NATIVEFUNCTION, &StdLib_is_list, 0), -1);
+  Scope_set(p_global, "is_function", Generic_new(TYPE_NATIVEFUNCTION, &StdLib_is_function, 0), -1);
 
   /* list and string */
   Scope_set(p_global, "list", Generic_new(TYPE_NATIVEFUNCTION, &StdLib_list, 0), -1);
   Scope_set(p_global, "length", Generic_new(TYPE_NATIVEFUNCTION, &StdLib_length, 0), -1);
   Scope_set(p_global, "join", Generic_new(TYPE_NATIVEFUNCTION, &StdLib_join, 0), -1);
   Scope_set(p_global, "repeat", Generic_new(TYPE_NATIVEFUNCTION, &StdLib_repeat, 0), -1);
+  Scope_set(p_global, "concat", Generic_new(TYPE_NATIVEFUNCTION, &StdLib_concat, 0), -1);
+  Scope_set(p_global, "uppercase", Generic_new(TYPE_NATIVEFUNCTION, &StdLib_uppercase, 0), -1);
+  Scope_set(p_global, "lowercase", Generic_new(TYPE_NATIVEFUNCTION, &StdLib_lowercase, 0), -1);
+  Scope_set(p_global, "substring", Generic_new(TYPE_NATIVEFUNCTION, &StdLib_substring, 0), -1);
+  Scope_set(p_global, "split", Generic_new(TYPE_NATIVEFUNCTION, &StdLib_split, 0), -1);
   Scope_set(p_global, "get", Generic_new(TYPE_NATIVEFUNCTION, &StdLib_get, 0), -1);
   Scope_set(p_global, "insert", Generic_new(TYPE_NATIVEFUNCTION, &StdLib_insert, 0), -1);
   Scope_set(p_global, "set", Generic_new(TYPE_NATIVEFUNCTION, &StdLib_set, 0), -1);
   Scope_set(p_global, "delete", Generic_new(TYPE_NATIVEFUNCTION, &StdLib_delete, 0), -1);
   Scope_set(p_global, "map", Generic_new(TYPE_NATIVEFUNCTION, &StdLib_map, 0), -1);
+  Scope_set(p_global, "map2", Generic_new(TYPE_NATIVEFUNCTION, &StdLib_map2, 0), -1);
   Scope_set(p_global, "reduce", Generic_new(TYPE_NATIVEFUNCTION, &StdLib_reduce, 0), -1);
   Scope_set(p_global, "filter", Generic_new(TYPE_NATIVEFUNCTION, &StdLib_filter, 0), -1);
   Scope_set(p_global, "range", Generic_new(TYPE_NATIVEFUNCTION, &StdLib_range, 0), -1);
@@ -3578,6 +3879,7 @@ Scope *newGlobal(int argc, char *argv[]) {
   Scope_set(p_global, "tail", Generic_new(TYPE_NATIVEFUNCTION, &StdLib_tail, 0), -1);
   Scope_set(p_global, "cons", Generic_new(TYPE_NATIVEFUNCTION, &StdLib_cons, 0), -1);
   Scope_set(p_global, "empty?", Generic_new(TYPE_NATIVEFUNCTION, &StdLib_is_empty, 0), -1);
+  Scope_set(p_global, "nth", Generic_new(TYPE_NATIVEFUNCTION, &StdLib_nth, 0), -1);
 
   /* algebraic data types (variants) */
   Scope_set(p_global, "variant", Generic_new(TYPE_NATIVEFUNCTION, &StdLib_variant, 0), -1);
@@ -3627,8 +3929,8 @@ Scope *newGlobal(int argc, char *argv[]) {
 // Helper: Box an i64 value into Generic*
 Generic *franz_box_int(int64_t value) {
   // fprintf(stderr, "[BOX INT] Boxing value: %lld\n", (long long)value);
-  int *p_val = (int *)malloc(sizeof(int));
-  *p_val = (int)value;
+  int64_t *p_val = (int64_t *) malloc(sizeof(int64_t));
+  *p_val = value;
   Generic *result = Generic_new(TYPE_INT, p_val, 0);
   // fprintf(stderr, "[BOX INT] Created Generic* at %p, type=%d\n", result, result->type);
   return result;
@@ -3778,9 +4080,18 @@ Generic *franz_box_closure(void *closure_ptr) {
 typedef struct LLVMClosure {
   void *funcPtr;
   void *envPtr;
-  int returnTypeTag;  // 0=int, 1=float, 2=pointer
+  int returnTypeTag;  // 0=int, 1=float, 2=pointer, 5=dynamic
 } LLVMClosure;
 
+// Helper: Return type tag of a call, resolving DYNAMIC (5) to the type of the first argument
+// (polymorphic functions such as {x i -> <- (multiply x x)} return what they were given)
+static int resolveReturnTypeTag(LLVMClosure *llvm_closure, Generic *args[]) {
+  if (llvm_closure->returnTypeTag != 5) return llvm_closure->returnTypeTag;
+  if (args[0]->type == TYPE_INT) return 0;
+  if (args[0]->type == TYPE_FLOAT) return 1;
+  return 2;
+}
+
 // Helper: Call an LLVM closure from runtime
 // LLVM closures have function signature: result (*)(env, arg1, arg2, ...)
 Generic *franz_call_llvm_closure(Generic *closure_gen, Generic *args[], int argCount, int lineNumber) {
@@ -3808,7 +4119,7 @@ Generic *franz_call_llvm_closure(Generic *closure_gen, Generic *args[], int argC
 
     int64_t key_i64;
     if (args[0]->type == TYPE_INT) {
-      key_i64 = (int64_t)(*((int *)args[0]->p_val));
+      key_i64 = *((int64_t *) args[0]->p_val);
     } else if (args[0]->type == TYPE_FLOAT) {
       double fval = *((double *)args[0]->p_val);
       key_i64 = *((int64_t *)&fval);  // Bitcast double to i64
@@ -3818,7 +4129,7 @@ Generic *franz_call_llvm_closure(Generic *closure_gen, Generic *args[], int argC
 
     int64_t val_i64;
     if (args[1]->type == TYPE_INT) {
-      val_i64 = (int64_t)(*((int *)args[1]->p_val));
+      val_i64 = *((int64_t *) args[1]->p_val);
     } else if (args[1]->type == TYPE_FLOAT) {
       double fval = *((double *)args[1]->p_val);
       val_i64 = *((int64_t *)&fval);  // Bitcast double to i64
@@ -3847,12 +4158,12 @@ Generic *franz_call_llvm_closure(Generic *closure_gen, Generic *args[], int argC
       int64_t (*func)(int64_t, int64_t, int32_t, int64_t, int32_t) = (int64_t (*)(int64_t, int64_t, int32_t, int64_t, int32_t))llvm_closure->funcPtr;
       result = func(env_i64, key_i64, key_tag, val_i64, val_tag);
     } else {
-      // REGULAR FUNCTION: No environment - call directly with args as i64 + tags
-      fprintf(stderr, "[RUNTIME DEBUG] Calling REGULAR FUNCTION with key_i64=%lld, key_tag=%d, val_i64=%lld, val_tag=%d\n",
-              key_i64, key_tag, val_i64, val_tag);
+      // REGULAR FUNCTION: funcPtr is its wrapper, which takes (env, arg1, arg2) with a NULL env
+      fprintf(stderr, "[RUNTIME DEBUG] Calling REGULAR FUNCTION with key_i64=%lld, val_i64=%lld\n",
+              key_i64, val_i64);
 
-      int64_t (*func)(int64_t, int32_t, int64_t, int32_t) = (int64_t (*)(int64_t, int32_t, int64_t, int32_t))llvm_closure->funcPtr;
-      result = func(key_i64, key_tag, val_i64, val_tag);
+      int64_t (*func)(void *, int64_t, int64_t) = (int64_t (*)(void *, int64_t, int64_t))llvm_closure->funcPtr;
+      result = func(NULL, key_i64, val_i64);
     }
 
     fprintf(stderr, "[RUNTIME DEBUG] LLVM function returned: %lld (0x%llx)\n", result, (unsigned long long)result);
@@ -3863,11 +4174,12 @@ Generic *franz_call_llvm_closure(Generic *closure_gen, Generic *args[], int argC
     // - returnTypeTag=1: FLOAT - box as Generic* with TYPE_FLOAT
     // - returnTypeTag=2: POINTER - already a Generic* pointer
 
-    if (llvm_closure->returnTypeTag == 0) {
+    int returnTypeTag = resolveReturnTypeTag(llvm_closure, args);
+    if (returnTypeTag == 0) {
       // INT - box the raw integer
       fprintf(stderr, "[RESULT DEBUG] Boxing INT result: %lld\n", result);
       return franz_box_int(result);
-    } else if (llvm_closure->returnTypeTag == 1) {
+    } else if (returnTypeTag == 1) {
       // FLOAT - reinterpret i64 as double, then box
       double fval = *((double *)&result);
       fprintf(stderr, "[RESULT DEBUG] Boxing FLOAT result: %f\n", fval);
@@ -3883,7 +4195,7 @@ Generic *franz_call_llvm_closure(Generic *closure_gen, Generic *args[], int argC
     //  FIX: LLVM closures need UNBOXED primitive values, NOT Generic* pointers!
     int64_t arg_i64;
     if (args[0]->type == TYPE_INT) {
-      arg_i64 = (int64_t)(*((int *)args[0]->p_val));
+      arg_i64 = *((int64_t *) args[0]->p_val);
     } else if (args[0]->type == TYPE_FLOAT) {
       double fval = *((double *)args[0]->p_val);
       arg_i64 = *((int64_t *)&fval);  // Bitcast double to i64
@@ -3905,11 +4217,11 @@ Generic *franz_call_llvm_closure(Generic *closure_gen, Generic *args[], int argC
       int64_t (*func)(int64_t, int64_t) = (int64_t (*)(int64_t, int64_t))llvm_closure->funcPtr;
       result = func(env_i64, arg_i64);
     } else {
-      // REGULAR FUNCTION: No environment - call directly with arg as i64
+      // REGULAR FUNCTION: funcPtr is its wrapper, which takes (env, arg) with a NULL env
       fprintf(stderr, "[RUNTIME DEBUG] Calling REGULAR FUNCTION with arg_i64=%lld\n", arg_i64);
 
-      int64_t (*func)(int64_t) = (int64_t (*)(int64_t))llvm_closure->funcPtr;
-      result = func(arg_i64);
+      int64_t (*func)(void *, int64_t) = (int64_t (*)(void *, int64_t))llvm_closure->funcPtr;
+      result = func(NULL, arg_i64);
     }
 
     fprintf(stderr, "[RUNTIME DEBUG] LLVM function returned: %lld (0x%llx)\n", result, (unsigned long long)result);
@@ -3919,11 +4231,12 @@ Generic *franz_call_llvm_closure(Generic *closure_gen, Generic *args[], int argC
     // - returnTypeTag=1: FLOAT - box as Generic* with TYPE_FLOAT
     // - returnTypeTag=2: POINTER - already a Generic* pointer
 
-    if (llvm_closure->returnTypeTag == 0) {
+    int returnTypeTag = resolveReturnTypeTag(llvm_closure, args);
+    if (returnTypeTag == 0) {
       // INT - box the raw integer
       fprintf(stderr, "[RESULT DEBUG] Boxing INT result: %lld\n", result);
       return franz_box_int(result);
-    } else if (llvm_closure->returnTypeTag == 1) {
+    } else if (returnTypeTag == 1) {
       // FLOAT - reinterpret i64 as double, then box
       double fval = *((double *)&result);
       fprintf(stderr, "[RESULT DEBUG] Boxing FLOAT result: %f\n", fval);
@@ -3941,7 +4254,7 @@ Generic *franz_call_llvm_closure(Generic *closure_gen, Generic *args[], int argC
 
     int64_t arg1_i64;
     if (args[0]->type == TYPE_INT) {
-      arg1_i64 = (int64_t)(*((int *)args[0]->p_val));
+      arg1_i64 = *((int64_t *) args[0]->p_val);
     } else if (args[0]->type == TYPE_FLOAT) {
       double fval = *((double *)args[0]->p_val);
       arg1_i64 = *((int64_t *)&fval);  // Bitcast double to i64
@@ -3951,7 +4264,7 @@ Generic *franz_call_llvm_closure(Generic *closure_gen, Generic *args[], int argC
 
     int64_t arg2_i64;
     if (args[1]->type == TYPE_INT) {
-      arg2_i64 = (int64_t)(*((int *)args[1]->p_val));
+      arg2_i64 = *((int64_t *) args[1]->p_val);
     } else if (args[1]->type == TYPE_FLOAT) {
       double fval = *((double *)args[1]->p_val);
       arg2_i64 = *((int64_t *)&fval);  // Bitcast double to i64
@@ -3961,7 +4274,7 @@ Generic *franz_call_llvm_closure(Generic *closure_gen, Generic *args[], int argC
 
     int64_t arg3_i64;
     if (args[2]->type == TYPE_INT) {
-      arg3_i64 = (int64_t)(*((int *)args[2]->p_val));
+      arg3_i64 = *((int64_t *) args[2]->p_val);
     } else if (args[2]->type == TYPE_FLOAT) {
       double fval = *((double *)args[2]->p_val);
       arg3_i64 = *((int64_t *)&fval);  // Bitcast double to i64
@@ -3993,23 +4306,24 @@ Generic *franz_call_llvm_closure(Generic *closure_gen, Generic *args[], int argC
         (int64_t (*)(int64_t, int64_t, int32_t, int64_t, int32_t, int64_t, int32_t))llvm_closure->funcPtr;
       result = func(env_i64, arg1_i64, arg1_tag, arg2_i64, arg2_tag, arg3_i64, arg3_tag);
     } else {
-      // REGULAR FUNCTION: No environment
-      fprintf(stderr, "[RUNTIME DEBUG] Calling REGULAR FUNCTION with arg1=%lld (tag=%d), arg2=%lld (tag=%d), arg3=%lld (tag=%d)\n",
-              arg1_i64, arg1_tag, arg2_i64, arg2_tag, arg3_i64, arg3_tag);
+      // REGULAR FUNCTION: funcPtr is its wrapper, which takes (env, arg1, arg2, arg3) with a NULL env
+      fprintf(stderr, "[RUNTIME DEBUG] Calling REGULAR FUNCTION with arg1=%lld, arg2=%lld, arg3=%lld\n",
+              arg1_i64, arg2_i64, arg3_i64);
 
-      int64_t (*func)(int64_t, int32_t, int64_t, int32_t, int64_t, int32_t) =
-        (int64_t (*)(int64_t, int32_t, int64_t, int32_t, int64_t, int32_t))llvm_closure->funcPtr;
-      result = func(arg1_i64, arg1_tag, arg2_i64, arg2_tag, arg3_i64, arg3_tag);
+      int64_t (*func)(void *, int64_t, int64_t, int64_t) =
+        (int64_t (*)(void *, int64_t, int64_t, int64_t))llvm_closure->funcPtr;
+      result = func(NULL, arg1_i64, arg2_i64, arg3_i64);
     }
 
     fprintf(stderr, "[RUNTIME DEBUG] LLVM function returned: %lld (0x%llx)\n", result, (unsigned long long)result);
 
     // Box the result based on returnTypeTag
-    if (llvm_closure->returnTypeTag == 0) {
+    int returnTypeTag = resolveReturnTypeTag(llvm_closure, args);
+    if (returnTypeTag == 0) {
       // INT - box the raw integer
       fprintf(stderr, "[RESULT DEBUG] Boxing INT result: %lld\n", result);
       return franz_box_int(result);
-    } else if (llvm_closure->returnTypeTag == 1) {
+    } else if (returnTypeTag == 1) {
       // FLOAT - reinterpret i64 as double, then box
       double fval = *((double *)&result);
       fprintf(stderr, "[RESULT DEBUG] Boxing FLOAT result: %f\n", fval);
@@ -4032,6 +4346,24 @@ Generic *franz_list_new(Generic **elements, int length) {
   return Generic_new(TYPE_LIST, List_new(elements, length), 0);
 }
 
+//  Build the `arguments` list from main(argc, argv) of the compiled program
+// argv[0] is the executable itself and is not part of `arguments`
+Generic *franz_arguments(int argc, char **argv) {
+  int length = argc > 1 ? argc - 1 : 0;
+  Generic **elements = malloc(sizeof(Generic *) * (length > 0 ? length : 1));
+  for (int i = 0; i < length; i++) {
+    elements[i] = franz_box_string(argv[i + 1]);
+  }
+  Generic *result = franz_list_new(elements, length);
+
+  // free boxed strings (as List_new does a copy)
+  for (int i = 0; i < length; i++) {
+    Generic_free(elements[i]);
+  }
+  free(elements);
+  return result;
+}
+
 // ============================================================================
 //  Industry-Standard List Operations (Rust-like implementation)
 // ============================================================================
@@ -4039,12 +4371,12 @@ Generic *franz_list_new(Generic **elements, int length) {
 // Helper: Get first element of list (head/car)
 Generic *franz_list_head(Generic *list) {
   if (!list || list->type != TYPE_LIST) {
-    fprintf(stderr, "Runtime Error: head requires a list argument\n");
+    Diagnostic_report(stderr, "Runtime Error", ERROR_CODE_RUNTIME_TYPE, Diagnostic_spanOfLine(0), "head requires a list argument");
     exit(1);
   }
   List *l = (List *)list->p_val;
   if (l->len == 0) {
-    fprintf(stderr, "Runtime Error: head called on empty list\n");
+    Diagnostic_report(stderr, "Runtime Error", ERROR_CODE_RUNTIME_RANGE, Diagnostic_spanOfLine(0), "head called on empty list");
     exit(1);
   }
   return l->vals[0];
@@ -4053,12 +4385,12 @@ Generic *franz_list_head(Generic *list) {
 // Helper: Get rest of list (tail/cdr)
 Generic *franz_list_tail(Generic *list) {
   if (!list || list->type != TYPE_LIST) {
-    fprintf(stderr, "Runtime Error: tail requires a list argument\n");
+    Diagnostic_report(stderr, "Runtime Error", ERROR_CODE_RUNTIME_TYPE, Diagnostic_spanOfLine(0), "tail requires a list argument");
     exit(1);
   }
   List *l = (List *)list->p_val;
   if (l->len == 0) {
-    fprintf(stderr, "Runtime Error: tail called on empty list\n");
+    Diagnostic_report(stderr, "Runtime Error", ERROR_CODE_RUNTIME_RANGE, Diagnostic_spanOfLine(0), "tail called on empty list");
     exit(1);
   }
   // Create new list with elements [1..len)
@@ -4072,7 +4404,7 @@ Generic *franz_list_tail(Generic *list) {
 // Helper: Prepend element to list (cons)
 Generic *franz_list_cons(Generic *elem, Generic *list) {
   if (!list || list->type != TYPE_LIST) {
-    fprintf(stderr, "Runtime Error: cons requires a list as second argument\n");
+    Diagnostic_report(stderr, "Runtime Error", ERROR_CODE_RUNTIME_TYPE, Diagnostic_spanOfLine(0), "cons requires a list as second argument");
     exit(1);
   }
   List *l = (List *)list->p_val;
@@ -4097,7 +4429,7 @@ int64_t franz_list_is_empty(Generic *list) {
 // Helper: Get length of list
 int64_t franz_list_length(Generic *list) {
   if (!list) {
-    fprintf(stderr, "Runtime Error: length requires a list or string argument\n");
+    Diagnostic_report(stderr, "Runtime Error", ERROR_CODE_RUNTIME_TYPE, Diagnostic_spanOfLine(0), "length requires a list or string argument");
     exit(1);
   }
   if (list->type == TYPE_LIST) {
@@ -4108,20 +4440,19 @@ int64_t franz_list_length(Generic *list) {
     char **p_str = (char **)list->p_val;
     return (int64_t)strlen(*p_str);
   }
-  fprintf(stderr, "Runtime Error: length requires a list or string argument\n");
+  Diagnostic_report(stderr, "Runtime Error", ERROR_CODE_RUNTIME_TYPE, Diagnostic_spanOfLine(0), "length requires a list or string argument");
   exit(1);
 }
 
 // Helper: Get element at index (0-indexed)
 Generic *franz_list_nth(Generic *list, int64_t index) {
   if (!list || list->type != TYPE_LIST) {
-    fprintf(stderr, "Runtime Error: nth requires a list as first argument\n");
+    Diagnostic_report(stderr, "Runtime Error", ERROR_CODE_RUNTIME_TYPE, Diagnostic_spanOfLine(0), "nth requires a list as first argument");
     exit(1);
   }
   List *l = (List *)list->p_val;
   if (index < 0 || index >= l->len) {
-    fprintf(stderr, "Runtime Error: list index %lld out of bounds (length %d)\n",
-            (long long)index, l->len);
+    Diagnostic_report(stderr, "Runtime Error", ERROR_CODE_RUNTIME_RANGE, Diagnostic_spanOfLine(0), "list index %lld out of bounds (length %d)", (long long)index, l->len);
     exit(1);
   }
   return l->vals[index];
@@ -4191,9 +4522,9 @@ Generic *franz_llvm_filter(Generic *list, Generic *predicate, int lineNumber) {
     int is_truthy = 0;
     if (result) {
       if (result->type == TYPE_INT) {
-        int result_val = *((int *)result->p_val);
+        int64_t result_val = *((int64_t *) result->p_val);
         is_truthy = (result_val != 0);
-        fprintf(stderr, "[LLVM FILTER] Integer result: %d (truthy=%d)\n", result_val, is_truthy);
+        fprintf(stderr, "[LLVM FILTER] Integer result: %lld (truthy=%d)\n", (long long) result_val, is_truthy);
       } else {
         // Non-integer truthy values (strings, lists, etc. are truthy if non-null)
         is_truthy = 1;
@@ -4588,7 +4919,7 @@ Generic *franz_llvm_set_ref(Generic *ref, Generic *value, int lineNumber) {
 // - List slice: (get [1,2,3] 0 2) -> [1,2] (returns Generic* wrapping List*)
 void *franz_get(Generic *collection, int64_t start, int64_t end_or_unused, int has_end) {
   if (!collection) {
-    fprintf(stderr, "Runtime Error: get requires non-null collection\n");
+    Diagnostic_report(stderr, "Runtime Error", ERROR_CODE_RUNTIME, Diagnostic_spanOfLine(0), "get requires non-null collection");
     exit(1);
   }
 
@@ -4598,8 +4929,7 @@ void *franz_get(Generic *collection, int64_t start, int64_t end_or_unused, int h
     int len = strlen(str);
 
     if (start < 0 || start >= len) {
-      fprintf(stderr, "Runtime Error: string index %lld out of bounds (length %d)\n",
-              (long long)start, len);
+      Diagnostic_report(stderr, "Runtime Error", ERROR_CODE_RUNTIME_RANGE, Diagnostic_spanOfLine(0), "string index %lld out of bounds (length %d)", (long long)start, len);
       exit(1);
     }
 
@@ -4613,8 +4943,7 @@ void *franz_get(Generic *collection, int64_t start, int64_t end_or_unused, int h
       // Substring: return char*
       int64_t end = end_or_unused;
       if (end < start || end > len) {
-        fprintf(stderr, "Runtime Error: string end index %lld out of bounds (start=%lld, length=%d)\n",
-                (long long)end, (long long)start, len);
+        Diagnostic_report(stderr, "Runtime Error", ERROR_CODE_RUNTIME_RANGE, Diagnostic_spanOfLine(0), "string end index %lld out of bounds (start=%lld, length=%d)", (long long)end, (long long)start, len);
         exit(1);
       }
 
@@ -4629,8 +4958,7 @@ void *franz_get(Generic *collection, int64_t start, int64_t end_or_unused, int h
     List *list = (List *)collection->p_val;
 
     if (start < 0 || start >= list->len) {
-      fprintf(stderr, "Runtime Error: list index %lld out of bounds (length %d)\n",
-              (long long)start, list->len);
+      Diagnostic_report(stderr, "Runtime Error", ERROR_CODE_RUNTIME_RANGE, Diagnostic_spanOfLine(0), "list index %lld out of bounds (length %d)", (long long)start, list->len);
       exit(1);
     }
 
@@ -4641,8 +4969,7 @@ void *franz_get(Generic *collection, int64_t start, int64_t end_or_unused, int h
       // Slice: return Generic* wrapping new List*
       int64_t end = end_or_unused;
       if (end < start || end > list->len) {
-        fprintf(stderr, "Runtime Error: list end index %lld out of bounds (start=%lld, length=%d)\n",
-                (long long)end, (long long)start, list->len);
+        Diagnostic_report(stderr, "Runtime Error", ERROR_CODE_RUNTIME_RANGE, Diagnostic_spanOfLine(0), "list end index %lld out of bounds (start=%lld, length=%d)", (long long)end, (long long)start, list->len);
         exit(1);
       }
 
@@ -4656,8 +4983,7 @@ void *franz_get(Generic *collection, int64_t start, int64_t end_or_unused, int h
       return Generic_new(TYPE_LIST, slice_list, 0);
     }
   } else {
-    fprintf(stderr, "Runtime Error: get requires string or list, got %s\n",
-            getTypeString(collection->type));
+    Diagnostic_report(stderr, "Runtime Error", ERROR_CODE_RUNTIME_TYPE, Diagnostic_spanOfLine(0), "get requires string or list, got %s", getTypeString(collection->type));
     exit(1);
   }
 }
@@ -4681,7 +5007,7 @@ void franz_print_generic(Generic *value) {
 
   switch (value->type) {
     case TYPE_INT: {
-      long long val = (long long)(*((int *)value->p_val));
+      long long val = (long long)(*((int64_t *) value->p_val));
       printf("%lld", val);
       break;
     }
@@ -4746,18 +5072,17 @@ void franz_println_value(void *value_ptr, int is_generic) {
 // Returns the native int value, or 0 if not an int
 int64_t franz_unbox_int(Generic *generic) {
   if (!generic) {
-    fprintf(stderr, "Runtime Error: Cannot unbox NULL pointer\n");
+    Diagnostic_report(stderr, "Runtime Error", ERROR_CODE_RUNTIME, Diagnostic_spanOfLine(0), "Cannot unbox NULL pointer");
     exit(1);
   }
 
   if (generic->type == TYPE_INT) {
-    return (int64_t)(*((int *)generic->p_val));
+    return *((int64_t *) generic->p_val);
   } else if (generic->type == TYPE_FLOAT) {
     // Auto-convert float to int
     return (int64_t)(*((double *)generic->p_val));
   } else {
-    fprintf(stderr, "Runtime Error: Cannot unbox %s to int\n",
-            getTypeString(generic->type));
+    Diagnostic_report(stderr, "Runtime Error", ERROR_CODE_RUNTIME_TYPE, Diagnostic_spanOfLine(0), "Cannot unbox %s to int", getTypeString(generic->type));
     exit(1);
   }
 }
@@ -4766,7 +5091,7 @@ int64_t franz_unbox_int(Generic *generic) {
 // Returns the native float value, or 0.0 if not a float
 double franz_unbox_float(Generic *generic) {
   if (!generic) {
-    fprintf(stderr, "Runtime Error: Cannot unbox NULL pointer\n");
+    Diagnostic_report(stderr, "Runtime Error", ERROR_CODE_RUNTIME, Diagnostic_spanOfLine(0), "Cannot unbox NULL pointer");
     exit(1);
   }
 
@@ -4774,10 +5099,9 @@ double franz_unbox_float(Generic *generic) {
     return *((double *)generic->p_val);
   } else if (generic->type == TYPE_INT) {
     // Auto-convert int to float
-    return (double)(*((int *)generic->p_val));
+    return (double)(*((int64_t *) generic->p_val));
   } else {
-    fprintf(stderr, "Runtime Error: Cannot unbox %s to float\n",
-            getTypeString(generic->type));
+    Diagnostic_report(stderr, "Runtime Error", ERROR_CODE_RUNTIME_TYPE, Diagnostic_spanOfLine(0), "Cannot unbox %s to float", getTypeString(generic->type));
     exit(1);
   }
 }
@@ -4786,15 +5110,14 @@ double franz_unbox_float(Generic *generic) {
 // Returns the native string pointer, or NULL if not a string
 char *franz_unbox_string(Generic *generic) {
   if (!generic) {
-    fprintf(stderr, "Runtime Error: Cannot unbox NULL pointer\n");
+    Diagnostic_report(stderr, "Runtime Error", ERROR_CODE_RUNTIME, Diagnostic_spanOfLine(0), "Cannot unbox NULL pointer");
     exit(1);
   }
 
   if (generic->type == TYPE_STRING) {
     return *((char **)generic->p_val);
   } else {
-    fprintf(stderr, "Runtime Error: Cannot unbox %s to string\n",
-            getTypeString(generic->type));
+    Diagnostic_report(stderr, "Runtime Error", ERROR_CODE_RUNTIME_TYPE, Diagnostic_spanOfLine(0), "Cannot unbox %s to string", getTypeString(generic->type));
     exit(1);
   }
 }
@@ -4854,8 +5177,7 @@ void *franz_box_dict(void *dict) {
 void *franz_unbox_dict(void *generic) {
   Generic *g = (Generic *)generic;
   if (g->type != TYPE_DICT) {
-    fprintf(stderr, "Runtime Error: Cannot unbox %s to dict\n",
-            getTypeString(g->type));
+    Diagnostic_report(stderr, "Runtime Error", ERROR_CODE_RUNTIME_TYPE, Diagnostic_spanOfLine(0), "Cannot unbox %s to dict", getTypeString(g->type));
     exit(1);
   }
   return (Dict *)g->p_val;
@@ -5008,8 +5330,8 @@ void *franz_dict_filter(void *dict_generic, void *closure_generic, int lineNumbe
       // Check if result is truthy (non-zero for int, non-void)
       int keep = 0;
       if (result->type == TYPE_INT) {
-        keep = (*((int *)result->p_val) != 0);
-        fprintf(stderr, "[RUNTIME DEBUG] Result is INT, value=%d, keep=%d\n", *((int *)result->p_val), keep);
+        keep = (*((int64_t *) result->p_val) != 0);
+        fprintf(stderr, "[RUNTIME DEBUG] Result is INT, value=%lld, keep=%d\n", (long long) *((int64_t *) result->p_val), keep);
       } else if (result->type != TYPE_VOID) {
         keep = 1;  // Non-void is truthy
         fprintf(stderr, "[RUNTIME DEBUG] Result is non-void, keep=1\n");
@@ -17,6 +17,7 @@ Generic *franz_box_closure(void *closure_ptr);
 Generic *franz_box_pointer_smart(void *ptr);
 Generic *franz_box_param_tag(int64_t rawValue, int tag);
 Generic *franz_list_new(Generic **elements, int length);
+Generic *franz_arguments(int argc, char **argv);
 
 //  Unbox Generic* to get closure i64 (for nested closures)
 int64_t franz_generic_to_closure_ptr(int64_t generic_i64);
@@ -0,0 +1,545 @@
+#include "test_runner.h"
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <stdbool.h>
+#include <errno.h>
+#include <fcntl.h>
+#include <signal.h>
+#include <unistd.h>
+#include <limits.h>
+#include <sys/wait.h>
+#include "../file.h"
+#include "../toolchain/toolchain.h"
+#include "../build-cache/build_cache.h"
+
+#ifndef PATH_MAX
+#define PATH_MAX 4096
+#endif
+
+#define DEFAULT_TIMEOUT 10       // Seconds per compilation and per run
+#define MAX_DIFF_CELLS 4000000   // Larger outputs are shown without a line diff
+#define MAX_REPORT_LINES 40      // Lines of diff or stderr shown per failure
+
+//  How far a test got
+typedef enum {
+  TEST_SKIPPED,   // No expectations
+  TEST_COMPILE,   // franz build failed: output and exit code are the compiler's
+  TEST_RUN,       // The executable ran (exit code is 128 + signal if it was killed)
+  TEST_TIMEOUT    // Compiling or running took longer than the timeout
+} TestPhase;
+
+//  Which backend runs a test
+typedef enum {
+  BACKEND_LLVM,       // franz build, then the executable
+  BACKEND_INTERPRET,  // franz --interpret
+  BACKEND_BYTECODE    // franz --bytecode
+} Backend;
+
+static const char *backendNames[] = { "llvm", "interpret", "bytecode" };
+
+typedef struct TestCase {
+  char *path;
+  Backend backend;
+  char *expected;      // Expected stdout (NULL = no expectations)
+  int expectedExit;
+  TestPhase phase;
+  int exitCode;
+  char *output;        // Actual stdout, or the compiler's messages for TEST_COMPILE
+  char *errors;        // stderr of the executable
+} TestCase;
+
+typedef struct TestRun {
+  TestCase *tests;
+  int count;
+  int capacity;
+  Backend backends[3];   // Each test with expectations runs once per backend
+  int backendCount;
+} TestRun;
+
+//  Lines of a text (without their line breaks)
+typedef struct Lines {
+  const char **starts;
+  int *lengths;
+  int count;
+} Lines;
+
+// ============================================================================
+// Expectations
+// ============================================================================
+
+static void appendText(char **text, size_t *length, const char *add, size_t addLength) {
+  *text = realloc(*text, *length + addLength + 1);
+  memcpy(*text + *length, add, addLength);
+  *length += addLength;
+  (*text)[*length] = '\0';
+}
+
+//  Position of marker in [line, end), or NULL
+static const char *findInLine(const char *line, const char *end, const char *marker) {
+  return memmem(line, end - line, marker, strlen(marker));
+}
+
+//  Read the sibling .expected file, else the `// expect:` comments
+static void readExpectations(TestCase *test, const char *code) {
+  char *inlineExpected = NULL;
+  size_t inlineLength = 0;
+
+  for (const char *line = code; *line != '\0';) {
+    const char *end = strchr(line, '\n');
+    if (end == NULL) end = line + strlen(line);
+    const char *lineEnd = (end > line && end[-1] == '\r') ? end - 1 : end;
+
+    const char *mark;
+    if ((mark = findInLine(line, lineEnd, "// expect-exit:")) != NULL) {
+      test->expectedExit = atoi(mark + strlen("// expect-exit:"));
+      if (inlineExpected == NULL) appendText(&inlineExpected, &inlineLength, "", 0);
+    } else if ((mark = findInLine(line, lineEnd, "// expect:")) != NULL) {
+      const char *text = mark + strlen("// expect:");
+      if (text < lineEnd && *text == ' ') text++;
+      appendText(&inlineExpected, &inlineLength, text, lineEnd - text);
+      appendText(&inlineExpected, &inlineLength, "\n", 1);
+    }
+
+    line = *end == '\n' ? end + 1 : end;
+  }
+
+  // greet.franz -> greet.expected
+  char siblingPath[PATH_MAX];
+  size_t pathLength = strlen(test->path);
+  const char *extension = ".franz";
+  size_t stem = pathLength;
+  if (pathLength > strlen(extension) && strcmp(test->path + pathLength - strlen(extension), extension) == 0) {
+    stem -= strlen(extension);
+  }
+  snprintf(siblingPath, sizeof(siblingPath), "%.*s.expected", (int) stem, test->path);
+
+  char *sibling = readFile(siblingPath, false);
+  if (sibling != NULL) {
+    free(inlineExpected);
+    test->expected = sibling;
+  } else {
+    test->expected = inlineExpected;
+  }
+}
+
+static TestCase *addTest(TestRun *run, const char *path) {
+  if (run->count == run->capacity) {
+    run->capacity = run->capacity ? run->capacity * 2 : 64;
+    run->tests = realloc(run->tests, sizeof(TestCase) * run->capacity);
+  }
+
+  TestCase *test = &run->tests[run->count++];
+  memset(test, 0, sizeof(*test));
+  test->path = strdup(path);
+  test->backend = run->backends[0];
+  test->phase = TEST_SKIPPED;
+  return test;
+}
+
+static int collectTest(const char *path, void *context) {
+  TestRun *run = context;
+  TestCase *test = addTest(run, path);
+
+  char *code = readFile((char *) path, false);
+  if (code == NULL) {
+    fprintf(stderr, "Error: Could not read '%s'.\n", path);
+    return 1;
+  }
+  readExpectations(test, code);
+  free(code);
+
+  //  Skipped files are listed once, tests once per backend
+  if (test->expected == NULL) return 0;
+  int expectedExit = test->expectedExit;
+  for (int i = 1; i < run->backendCount; i++) {
+    char *expected = strdup(run->tests[run->count - 1].expected);
+    TestCase *copy = addTest(run, path);
+    copy->backend = run->backends[i];
+    copy->expected = expected;
+    copy->expectedExit = expectedExit;
+  }
+  return 0;
+}
+
+// ============================================================================
+// Running
+// ============================================================================
+
+//  Run argv with stdin from /dev/null and stdout/stderr written to files (the
+// same path may be given for both). Returns the exit code, 128 + signal number
+// if the process was killed, or -1 if it was still running after timeout seconds.
+static int spawn(char *const argv[], const char *outPath, const char *errPath, int timeout) {
+  pid_t pid = fork();
+  if (pid < 0) return 127;
+
+  if (pid == 0) {
+    int in = open("/dev/null", O_RDONLY);
+    int out = open(outPath, O_WRONLY | O_CREAT | O_TRUNC, 0600);
+    int err = strcmp(outPath, errPath) == 0 ? out : open(errPath, O_WRONLY | O_CREAT | O_TRUNC, 0600);
+    if (in < 0 || out < 0 || err < 0) _exit(127);
+    dup2(in, STDIN_FILENO);
+    dup2(out, STDOUT_FILENO);
+    dup2(err, STDERR_FILENO);
+
+    // The alarm survives exec: SIGALRM ends the process when the time is up
+    alarm(timeout);
+    execv(argv[0], argv);
+    perror(argv[0]);
+    _exit(127);
+  }
+
+  int status = 0;
+  while (waitpid(pid, &status, 0) < 0) {
+    if (errno != EINTR) return 127;
+  }
+  if (WIFSIGNALED(status)) {
+    return WTERMSIG(status) == SIGALRM ? -1 : 128 + WTERMSIG(status);
+  }
+  return WEXITSTATUS(status);
+}
+
+//  Compile and run one test, or run it on the interpreter or VM (in a worker process); the result is written to
+// <index>.status, stdout to <index>.out and stderr to <index>.err in workDir
+static void runTest(int index, const char *franz, const char *workDir, TestCase *test, int timeout) {
+  const char *path = test->path;
+  char exe[PATH_MAX], out[PATH_MAX], err[PATH_MAX], status[PATH_MAX];
+  snprintf(exe, sizeof(exe), "%s/%d.exe", workDir, index);
+  snprintf(out, sizeof(out), "%s/%d.out", workDir, index);
+  snprintf(err, sizeof(err), "%s/%d.err", workDir, index);
+  snprintf(status, sizeof(status), "%s/%d.status", workDir, index);
+
+  TestPhase phase = TEST_RUN;
+  int code;
+  if (test->backend != BACKEND_LLVM) {
+    // Parse errors come from the same process, so they are part of the run
+    const char *flag = test->backend == BACKEND_INTERPRET ? "--interpret" : "--bytecode";
+    char *interpretArgv[] = { (char *) franz, (char *) flag, (char *) path, NULL };
+    code = spawn(interpretArgv, out, err, timeout);
+    if (code < 0) phase = TEST_TIMEOUT;
+  } else {
+    char *buildArgv[] = { (char *) franz, "build", (char *) path, "-o", exe, NULL };
+    code = spawn(buildArgv, out, out, timeout);
+    if (code != 0) {
+      phase = code < 0 ? TEST_TIMEOUT : TEST_COMPILE;
+    } else {
+      char *runArgv[] = { exe, NULL };
+      code = spawn(runArgv, out, err, timeout);
+      if (code < 0) phase = TEST_TIMEOUT;
+    }
+  }
+
+  FILE *file = fopen(status, "w");
+  if (file == NULL) return;
+  fprintf(file, "%d %d\n", phase, code);
+  fclose(file);
+}
+
+static void readResult(TestCase *test, int index, const char *workDir) {
+  char path[PATH_MAX];
+
+  snprintf(path, sizeof(path), "%s/%d.status", workDir, index);
+  char *status = readFile(path, false);
+  int phase = TEST_TIMEOUT;
+  if (status == NULL || sscanf(status, "%d %d", &phase, &test->exitCode) != 2) {
+    // The worker itself died: report it like a crash
+    phase = TEST_RUN;
+    test->exitCode = 128 + SIGKILL;
+  }
+  test->phase = (TestPhase) phase;
+  free(status);
+
+  snprintf(path, sizeof(path), "%s/%d.out", workDir, index);
+  test->output = readFile(path, false);
+  snprintf(path, sizeof(path), "%s/%d.err", workDir, index);
+  test->errors = readFile(path, false);
+  if (test->output == NULL) test->output = strdup("");
+}
+
+// ============================================================================
+// Reporting
+// ============================================================================
+
+static Lines splitLines(const char *text) {
+  Lines lines = { NULL, NULL, 0 };
+  int capacity = 0;
+  for (const char *line = text; *line != '\0';) {
+    const char *end = strchr(line, '\n');
+    if (end == NULL) end = line + strlen(line);
+    if (lines.count == capacity) {
+      capacity = capacity ? capacity * 2 : 16;
+      lines.starts = realloc(lines.starts, sizeof(char *) * capacity);
+      lines.lengths = realloc(lines.lengths, sizeof(int) * capacity);
+    }
+    lines.starts[lines.count] = line;
+    lines.lengths[lines.count++] = end - line;
+    line = *end == '\n' ? end + 1 : end;
+  }
+  return lines;
+}
+
+static void Lines_free(Lines *lines) {
+  free(lines->starts);
+  free(lines->lengths);
+}
+
+static bool sameLine(Lines *a, int i, Lines *b, int j) {
+  return a->lengths[i] == b->lengths[j] && memcmp(a->starts[i], b->starts[j], a->lengths[i]) == 0;
+}
+
+//  Outputs are compared line by line, so a missing final newline does not matter
+static bool sameOutput(const char *expected, const char *actual) {
+  Lines a = splitLines(expected), b = splitLines(actual);
+  bool same = a.count == b.count;
+  for (int i = 0; same && i < a.count; i++) same = sameLine(&a, i, &b, i);
+  Lines_free(&a);
+  Lines_free(&b);
+  return same;
+}
+
+static bool printLimited(int *printed, char marker, const char *text, int length) {
+  if (*printed == MAX_REPORT_LINES) {
+    printf("    ...\n");
+    (*printed)++;
+  }
+  if (*printed > MAX_REPORT_LINES) return false;
+  printf("    %c%.*s\n", marker, length, text);
+  (*printed)++;
+  return true;
+}
+
+//  Print a line diff of the expected and actual stdout (-expected, +actual)
+static void printDiff(const char *expected, const char *actual) {
+  Lines a = splitLines(expected), b = splitLines(actual);
+  int printed = 0;
+
+  if ((long) (a.count + 1) * (b.count + 1) > MAX_DIFF_CELLS) {
+    printf("    (output too long to diff: %d expected lines, %d actual lines)\n", a.count, b.count);
+    Lines_free(&a);
+    Lines_free(&b);
+    return;
+  }
+
+  // Longest common subsequence of lines, filled from the end
+  int width = b.count + 1;
+  int *lcs = calloc((size_t) (a.count + 1) * width, sizeof(int));
+  for (int i = a.count - 1; i >= 0; i--) {
+    for (int j = b.count - 1; j >= 0; j--) {
+      lcs[i * width + j] = sameLine(&a, i, &b, j)
+        ? lcs[(i + 1) * width + j + 1] + 1
+        : (lcs[(i + 1) * width + j] > lcs[i * width + j + 1] ? lcs[(i + 1) * width + j] : lcs[i * width + j + 1]);
+    }
+  }
+
+  int i = 0, j = 0;
+  while (i < a.count || j < b.count) {
+    bool more;
+    if (i < a.count && j < b.count && sameLine(&a, i, &b, j)) {
+      more = printLimited(&printed, ' ', a.starts[i], a.lengths[i]);
+      i++;
+      j++;
+    } else if (i < a.count && (j == b.count || lcs[(i + 1) * width + j] >= lcs[i * width + j + 1])) {
+      more = printLimited(&printed, '-', a.starts[i], a.lengths[i]);
+      i++;
+    } else {
+      more = printLimited(&printed, '+', b.starts[j], b.lengths[j]);
+      j++;
+    }
+    if (!more) break;
+  }
+
+  free(lcs);
+  Lines_free(&a);
+  Lines_free(&b);
+}
+
+static bool passed(TestCase *test) {
+  return test->phase != TEST_TIMEOUT && test->exitCode == test->expectedExit &&
+         sameOutput(test->expected, test->output);
+}
+
+//  Path of a test, with its backend when tests run on several
+static void printName(TestRun *run, TestCase *test) {
+  printf("%s", test->path);
+  if (run->backendCount > 1) printf(" [%s]", backendNames[test->backend]);
+}
+
+static void printFailure(TestRun *run, TestCase *test, int timeout) {
+  printf("\n--- ");
+  printName(run, test);
+  printf("\n");
+
+  if (test->phase == TEST_TIMEOUT) {
+    printf("  timed out after %ds\n", timeout);
+    return;
+  }
+  if (test->phase == TEST_COMPILE) {
+    printf("  did not compile (compiler messages are compared as stdout)\n");
+  }
+  if (test->exitCode != test->expectedExit) {
+    printf("  exit code: expected %d, got %d\n", test->expectedExit, test->exitCode);
+  }
+  if (!sameOutput(test->expected, test->output)) {
+    printf("  stdout (-expected +actual):\n");
+    printDiff(test->expected, test->output);
+  }
+  if (test->errors != NULL && test->errors[0] != '\0') {
+    printf("  stderr:\n");
+    Lines lines = splitLines(test->errors);
+    int printed = 0;
+    for (int i = 0; i < lines.count; i++) {
+      if (!printLimited(&printed, ' ', lines.starts[i], lines.lengths[i])) break;
+    }
+    Lines_free(&lines);
+  }
+}
+
+// ============================================================================
+// Entry point
+// ============================================================================
+
+static bool parseBackends(const char *text, TestRun *run) {
+  for (int i = 0; i < 3; i++) {
+    if (strcmp(text, backendNames[i]) == 0) {
+      run->backends[0] = (Backend) i;
+      run->backendCount = 1;
+      return true;
+    }
+  }
+
+  if (strcmp(text, "both") == 0 || strcmp(text, "all") == 0) {
+    run->backends[0] = BACKEND_LLVM;
+    run->backends[1] = BACKEND_INTERPRET;
+    run->backends[2] = BACKEND_BYTECODE;
+    run->backendCount = strcmp(text, "both") == 0 ? 2 : 3;
+  } else {
+    return false;
+  }
+  return true;
+}
+
+static bool parsePositive(const char *text, int *out) {
+  char *end;
+  long value = strtol(text, &end, 10);
+  if (*text == '\0' || *end != '\0' || value < 1 || value > 100000) return false;
+  *out = (int) value;
+  return true;
+}
+
+int TestRunner_run(int argc, char *argv[]) {
+  long cpus = sysconf(_SC_NPROCESSORS_ONLN);
+  int jobs = cpus > 0 ? (int) cpus : 1;
+  int timeout = DEFAULT_TIMEOUT;
+  int pathCount = 0;
+  TestRun run = { NULL, 0, 0, { BACKEND_LLVM }, 1 };
+
+  for (int i = 0; i < argc; i++) {
+    if (strcmp(argv[i], "-j") == 0 || strncmp(argv[i], "--jobs=", 7) == 0) {
+      // -j N or --jobs=N (consumed options are cleared so only paths remain)
+      bool separate = argv[i][1] == 'j';
+      const char *value = separate ? (i + 1 < argc ? argv[i + 1] : "") : argv[i] + 7;
+      if (!parsePositive(value, &jobs)) {
+        fprintf(stderr, "Error: Invalid job count '%s'.\n", value);
+        return 1;
+      }
+      argv[i] = NULL;
+      if (separate) argv[++i] = NULL;
+    } else if (strncmp(argv[i], "--timeout=", 10) == 0) {
+      if (!parsePositive(argv[i] + 10, &timeout)) {
+        fprintf(stderr, "Error: Invalid timeout '%s' (expected seconds).\n", argv[i] + 10);
+        return 1;
+      }
+      argv[i] = NULL;
+    } else if (strncmp(argv[i], "--backend=", 10) == 0) {
+      if (!parseBackends(argv[i] + 10, &run)) {
+        fprintf(stderr, "Error: Invalid backend '%s' (expected llvm, interpret, bytecode, both or all).\n", argv[i] + 10);
+        return 1;
+      }
+      argv[i] = NULL;
+    } else if (argv[i][0] == '-') {
+      fprintf(stderr, "Error: Unknown option '%s' for 'franz test' "
+                      "(expected --jobs=N, --timeout=SECONDS or --backend=NAME).\n", argv[i]);
+      return 1;
+    } else {
+      pathCount++;
+    }
+  }
+
+  int unreadable = 0;
+  if (pathCount == 0) {
+    unreadable |= walkFranzFiles(".", collectTest, &run);
+  }
+  for (int i = 0; i < argc; i++) {
+    if (argv[i] != NULL) unreadable |= walkFranzFiles(argv[i], collectTest, &run);
+  }
+
+  // Every test is compiled by a `franz build` child of this executable
+  char franz[PATH_MAX];
+  char workDir[PATH_MAX];
+  if (!Toolchain_executablePath(franz, sizeof(franz))) {
+    fprintf(stderr, "Error: Could not locate the franz executable.\n");
+    return 1;
+  }
+  if (BuildCache_createWorkDir(workDir, sizeof(workDir)) != 0) return 1;
+
+  int running = 0;
+  for (int i = 0; i < run.count; i++) {
+    if (run.tests[i].expected == NULL) continue;
+
+    if (running == jobs) {
+      while (wait(NULL) < 0 && errno == EINTR) {}
+      running--;
+    }
+
+    fflush(stdout);
+    fflush(stderr);
+    pid_t pid = fork();
+    if (pid == 0) {
+      runTest(i, franz, workDir, &run.tests[i], timeout);
+      _exit(0);
+    }
+    if (pid < 0) {
+      runTest(i, franz, workDir, &run.tests[i], timeout);
+    } else {
+      running++;
+    }
+  }
+  while (running > 0) {
+    if (wait(NULL) < 0 && errno != EINTR) break;
+    running--;
+  }
+
+  int passedCount = 0, failedCount = 0, skippedCount = 0;
+  for (int i = 0; i < run.count; i++) {
+    TestCase *test = &run.tests[i];
+    if (test->expected == NULL) {
+      skippedCount++;
+      continue;
+    }
+    readResult(test, i, workDir);
+    bool ok = passed(test);
+    printf("%s ", ok ? "PASS" : "FAIL");
+    printName(&run, test);
+    printf("\n");
+    if (ok) passedCount++; else failedCount++;
+  }
+
+  for (int i = 0; i < run.count; i++) {
+    TestCase *test = &run.tests[i];
+    if (test->expected != NULL && !passed(test)) printFailure(&run, test, timeout);
+  }
+
+  printf("\n%d passed, %d failed, %d skipped (no expectations)\n", passedCount, failedCount, skippedCount);
+  fflush(stdout);
+
+  BuildCache_removeDir(workDir);
+  for (int i = 0; i < run.count; i++) {
+    free(run.tests[i].path);
+    free(run.tests[i].expected);
+    free(run.tests[i].output);
+    free(run.tests[i].errors);
+  }
+  free(run.tests);
+
+  return failedCount > 0 || unreadable ? 1 : 0;
+}
@@ -0,0 +1,42 @@
+#ifndef TEST_RUNNER_H
+#define TEST_RUNNER_H
+
+/**
+ * Test runner for Franz programs (`franz test`)
+ *
+ * Every .franz file below the given paths is a test if it states what it
+ * expects, either in a sibling file or inline:
+ *
+ *   greet.franz      the program
+ *   greet.expected   its exact stdout
+ *
+ *   (println (add 1 2))  // expect: 3
+ *   // expect-exit: 1
+ *
+ * `// expect:` comments give the expected stdout one line at a time, in
+ * order; `// expect-exit: N` sets the expected exit code (default 0) and
+ * works with .expected files too. Files that state neither are skipped.
+ *
+ * Each test is compiled once with `franz build` into a private work
+ * directory and the executable is run with stdin from /dev/null. Tests run
+ * in parallel worker processes; results are printed in path order with a
+ * line diff for every failure. A program that does not compile is judged
+ * by the compiler's messages and exit code, so compile errors can be
+ * tested too.
+ *
+ * `--backend=interpret` runs each test with `franz --interpret` instead,
+ * `--backend=bytecode` with `franz --bytecode`; `--backend=both` runs it
+ * with LLVM and the interpreter and `--backend=all` on all three, so a
+ * program that behaves differently on one backend fails there.
+ */
+
+/**
+ * Run `franz test [--jobs=N] [--timeout=SECONDS] [--backend=NAME] [paths...]`
+ *
+ * @param argc - Number of arguments after "test"
+ * @param argv - Arguments after "test"
+ * @return Exit code: 0 if every test passed, 1 otherwise
+ */
+int TestRunner_run(int argc, char *argv[]);
+
+#endif
@@ -50,6 +50,10 @@ TokenArray* TokenArray_new(void) {
     exit(1);
   }
 
+  arr->comments = NULL;
+  arr->commentCount = 0;
+  arr->commentCapacity = 0;
+
   return arr;
 }
 
@@ -78,9 +82,41 @@ void TokenArray_push(TokenArray *arr, char *val, enum TokenType type, int lineNu
   arr->tokens[arr->count].val = val;
   arr->tokens[arr->count].type = type;
   arr->tokens[arr->count].lineNumber = lineNumber;
+
+  //  Span is filled in by the lexer once the token's extent is known
+  arr->tokens[arr->count].offset = 0;
+  arr->tokens[arr->count].column = 0;
+  arr->tokens[arr->count].endOffset = 0;
+  arr->tokens[arr->count].endLine = lineNumber;
+  arr->tokens[arr->count].endColumn = 0;
+  arr->tokens[arr->count].sourceId = -1;
+  arr->tokens[arr->count].partner = -1;
   arr->count++;
 }
 
+//  Record a comment (text is owned by the array from now on)
+void TokenArray_pushComment(TokenArray *arr, char *text, int lineNumber, int column, int offset, int ownLine) {
+  if (arr->commentCount >= arr->commentCapacity) {
+    int newCapacity = arr->commentCapacity > 0 ? arr->commentCapacity * 2 : INITIAL_TOKEN_CAPACITY;
+    Comment *newComments = (Comment *)realloc(arr->comments, sizeof(Comment) * newCapacity);
+
+    if (newComments == NULL) {
+      fprintf(stderr, "Error: Failed to grow comment array\n");
+      exit(1);
+    }
+
+    arr->comments = newComments;
+    arr->commentCapacity = newCapacity;
+  }
+
+  Comment *comment = &arr->comments[arr->commentCount++];
+  comment->text = text;
+  comment->lineNumber = lineNumber;
+  comment->column = column;
+  comment->offset = offset;
+  comment->ownLine = ownLine;
+}
+
 //  Print token array
 void TokenArray_print(TokenArray *arr) {
   if (arr == NULL) {
@@ -110,6 +146,11 @@ void TokenArray_free(TokenArray *arr) {
   // Free token array
   free(arr->tokens);
 
+  for (int i = 0; i < arr->commentCount; i++) {
+    free(arr->comments[i].text);
+  }
+  free(arr->comments);
+
   // Free container
   free(arr);
 }
\ No newline at end of file
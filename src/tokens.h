@@ -30,14 +30,39 @@ typedef struct Token {
   char *val;
   enum TokenType type;
   int lineNumber;
+
+  //  Source span for diagnostics (byte offsets into the lexed code, 1-based columns, end exclusive)
+  int offset;
+  int column;            // 0 when only the line is known
+  int endOffset;
+  int endLine;
+  int endColumn;
+  int sourceId;          // Diagnostic source the token was lexed from (-1 if unregistered)
+
+  //  Bracket tokens: index of the matching close token (set by parseProgram, -1 otherwise)
+  //  An unclosed bracket's partner is the last token before the enclosing close
+  int partner;
 } Token;
 
+//  Comment kept by the lexer as trivia: not a token, the parser never sees it
+typedef struct Comment {
+  char *text;            // From "//" to the end of the line (line break excluded)
+  int lineNumber;
+  int column;            // 1-based column of the first '/'
+  int offset;            // Byte offset of the first '/'
+  int ownLine;           // 1 if only whitespace precedes it on its line
+} Comment;
+
 //  Dynamic array container for tokens (industry standard)
 // Replaces linked list with O(1) append and O(1) random access
 typedef struct TokenArray {
   Token *tokens;        // Dynamic array of tokens
   int count;            // Number of tokens currently stored
   int capacity;         // Allocated capacity (grows as needed)
+
+  Comment *comments;    // Comments in source order (used by franz fmt)
+  int commentCount;
+  int commentCapacity;
 } TokenArray;
 
 // prototypes
@@ -46,6 +71,7 @@ char* getTokenTypeString(enum TokenType);
 //  Array-based token operations
 TokenArray* TokenArray_new(void);
 void TokenArray_push(TokenArray *arr, char *val, enum TokenType type, int lineNumber);
+void TokenArray_pushComment(TokenArray *arr, char *text, int lineNumber, int column, int offset, int ownLine);
 void TokenArray_print(TokenArray *arr);
 void TokenArray_free(TokenArray *arr);
 
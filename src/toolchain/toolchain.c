@@ -0,0 +1,198 @@
+#include "toolchain.h"
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <limits.h>
+#include <unistd.h>
+#ifdef __APPLE__
+#include <mach-o/dyld.h>
+#endif
+
+#ifndef PATH_MAX
+#define PATH_MAX 4096
+#endif
+
+// Resolved once per process
+static char g_cc[PATH_MAX];
+static int g_cc_resolved = 0;
+static char g_runtime_root[PATH_MAX];
+static int g_runtime_root_resolved = 0;
+
+int Toolchain_findProgram(const char *name, char *out, size_t outSize) {
+  if (name == NULL || name[0] == '\0') return 0;
+
+  // Explicit paths are used as-is
+  if (strchr(name, '/') != NULL) {
+    if (access(name, X_OK) != 0) return 0;
+    snprintf(out, outSize, "%s", name);
+    return 1;
+  }
+
+  const char *path = getenv("PATH");
+  if (path == NULL) path = "/usr/local/bin:/usr/bin:/bin";
+
+  char *paths = strdup(path);
+  char *saveptr = NULL;
+  int found = 0;
+
+  for (char *dir = strtok_r(paths, ":", &saveptr); dir != NULL; dir = strtok_r(NULL, ":", &saveptr)) {
+    char candidate[PATH_MAX];
+    snprintf(candidate, sizeof(candidate), "%s/%s", dir[0] ? dir : ".", name);
+    if (access(candidate, X_OK) == 0) {
+      snprintf(out, outSize, "%s", candidate);
+      found = 1;
+      break;
+    }
+  }
+
+  free(paths);
+  return found;
+}
+
+const char *Toolchain_cc(void) {
+  if (g_cc_resolved) return g_cc[0] ? g_cc : NULL;
+  g_cc_resolved = 1;
+
+  // User overrides take priority (may include a wrapper such as "ccache gcc")
+  const char *overrides[] = { getenv("FRANZ_CC"), getenv("CC") };
+  for (size_t i = 0; i < sizeof(overrides) / sizeof(overrides[0]); i++) {
+    if (overrides[i] != NULL && overrides[i][0] != '\0') {
+      snprintf(g_cc, sizeof(g_cc), "%s", overrides[i]);
+      return g_cc;
+    }
+  }
+
+  const char *candidates[] = { "clang", "cc", "gcc" };
+  for (size_t i = 0; i < sizeof(candidates) / sizeof(candidates[0]); i++) {
+    if (Toolchain_findProgram(candidates[i], g_cc, sizeof(g_cc))) {
+      return g_cc;
+    }
+  }
+
+  g_cc[0] = '\0';
+  fprintf(stderr, "ERROR: No C compiler found (tried clang, cc, gcc on PATH).\n");
+  fprintf(stderr, "Hint: Install one or set FRANZ_CC to the compiler to use for linking.\n");
+  return NULL;
+}
+
+const char *Toolchain_ccForTarget(const char *triple, const char *sysroot) {
+  static char cc[PATH_MAX * 3];
+
+  if (triple == NULL && sysroot == NULL) return Toolchain_cc();
+
+  char driver[PATH_MAX + 256];
+  const char *override = getenv("FRANZ_CC");
+  if (override != NULL && override[0] != '\0') {
+    snprintf(driver, sizeof(driver), "%s", override);
+  } else if (triple == NULL) {
+    const char *host = Toolchain_cc();
+    if (host == NULL) return NULL;
+    snprintf(driver, sizeof(driver), "%s", host);
+  } else {
+    //  GNU cross toolchains are named after the triple; clang handles any target
+    char name[PATH_MAX];
+    int found = 0;
+    const char *suffixes[] = { "gcc", "cc" };
+    for (size_t i = 0; i < sizeof(suffixes) / sizeof(suffixes[0]) && !found; i++) {
+      snprintf(name, sizeof(name), "%s-%s", triple, suffixes[i]);
+      found = Toolchain_findProgram(name, driver, sizeof(driver));
+    }
+    if (!found) {
+      char clang[PATH_MAX];
+      if (!Toolchain_findProgram("clang", clang, sizeof(clang))) {
+        fprintf(stderr, "ERROR: No C compiler found for target '%s' (tried %s-gcc, %s-cc, clang on PATH).\n",
+                triple, triple, triple);
+        fprintf(stderr, "Hint: Install a cross toolchain or set FRANZ_CC to the compiler to use for linking.\n");
+        return NULL;
+      }
+      snprintf(driver, sizeof(driver), "%s --target=%s", clang, triple);
+    }
+  }
+
+  if (sysroot != NULL) {
+    snprintf(cc, sizeof(cc), "%s --sysroot=%s", driver, sysroot);
+  } else {
+    snprintf(cc, sizeof(cc), "%s", driver);
+  }
+  return cc;
+}
+
+const char *Toolchain_arForTarget(const char *triple) {
+  static char ar[PATH_MAX];
+
+  const char *override = getenv("FRANZ_AR");
+  if (override != NULL && override[0] != '\0') {
+    snprintf(ar, sizeof(ar), "%s", override);
+    return ar;
+  }
+
+  if (triple != NULL) {
+    //  The host ar usually cannot index foreign object files; llvm-ar handles any target
+    char name[PATH_MAX];
+    snprintf(name, sizeof(name), "%s-ar", triple);
+    if (Toolchain_findProgram(name, ar, sizeof(ar))) return ar;
+    if (Toolchain_findProgram("llvm-ar", ar, sizeof(ar))) return ar;
+  }
+
+  snprintf(ar, sizeof(ar), "ar");
+  return ar;
+}
+
+int Toolchain_executablePath(char *out, size_t outSize) {
+  char exe[PATH_MAX];
+  out[0] = '\0';
+
+#ifdef __APPLE__
+  uint32_t size = sizeof(exe);
+  if (_NSGetExecutablePath(exe, &size) != 0) return 0;
+#else
+  ssize_t len = readlink("/proc/self/exe", exe, sizeof(exe) - 1);
+  if (len <= 0) return 0;
+  exe[len] = '\0';
+#endif
+
+  char resolved[PATH_MAX];
+  if (realpath(exe, resolved) == NULL) return 0;
+
+  snprintf(out, outSize, "%s", resolved);
+  return 1;
+}
+
+// Directory of the running franz executable (empty string if unknown)
+static void executableDir(char *out, size_t outSize) {
+  char resolved[PATH_MAX];
+  out[0] = '\0';
+  if (!Toolchain_executablePath(resolved, sizeof(resolved))) return;
+
+  char *slash = strrchr(resolved, '/');
+  if (slash == NULL) return;
+  *slash = '\0';
+  snprintf(out, outSize, "%s", resolved);
+}
+
+static int hasRuntimeSources(const char *dir) {
+  char probe[PATH_MAX];
+  snprintf(probe, sizeof(probe), "%s/src/stdlib.c", dir);
+  return access(probe, R_OK) == 0;
+}
+
+const char *Toolchain_runtimeRoot(void) {
+  if (g_runtime_root_resolved) return g_runtime_root;
+  g_runtime_root_resolved = 1;
+
+  const char *home = getenv("FRANZ_HOME");
+  if (home != NULL && home[0] != '\0') {
+    snprintf(g_runtime_root, sizeof(g_runtime_root), "%s", home);
+    return g_runtime_root;
+  }
+
+  char dir[PATH_MAX];
+  executableDir(dir, sizeof(dir));
+  if (dir[0] != '\0' && hasRuntimeSources(dir)) {
+    snprintf(g_runtime_root, sizeof(g_runtime_root), "%s", dir);
+    return g_runtime_root;
+  }
+
+  snprintf(g_runtime_root, sizeof(g_runtime_root), ".");
+  return g_runtime_root;
+}
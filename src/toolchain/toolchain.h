@@ -0,0 +1,80 @@
+#ifndef TOOLCHAIN_H
+#define TOOLCHAIN_H
+
+#include <stddef.h>
+
+/**
+ * Toolchain Discovery for Franz
+ *
+ * Locates the external tools and files needed to turn an object file
+ * into a native executable, without assuming a particular install layout:
+ * - C compiler driver (compiles runtime sources and links executables)
+ * - Archiver (bundles the runtime library), per target when cross-compiling
+ * - Franz runtime sources (src/ directory and Makefile.runtime)
+ *
+ * LLVM itself is linked into franz, so no llc/clang install is required
+ * for code generation.
+ */
+
+/**
+ * Search PATH for an executable program
+ *
+ * @param name - Program name (e.g. "clang")
+ * @param out - Buffer receiving the full path
+ * @param outSize - Size of out
+ * @return 1 if found, 0 otherwise
+ */
+int Toolchain_findProgram(const char *name, char *out, size_t outSize);
+
+/**
+ * Get the C compiler driver used to build the runtime and link executables
+ *
+ * Resolution order: $FRANZ_CC, $CC, then clang, cc, gcc on PATH.
+ *
+ * @return Compiler command, or NULL if none was found (error already printed)
+ */
+const char *Toolchain_cc(void);
+
+/**
+ * Get the C compiler driver for a cross-compilation target
+ *
+ * Resolution order: $FRANZ_CC, <triple>-gcc, <triple>-cc on PATH, then
+ * clang --target=<triple>. A sysroot is passed as --sysroot=<dir>.
+ * Without triple and sysroot this is Toolchain_cc().
+ *
+ * @param triple - Target triple (e.g. "aarch64-linux-gnu"), or NULL for the host
+ * @param sysroot - Target root directory, or NULL
+ * @return Compiler command, or NULL if none was found (error already printed)
+ */
+const char *Toolchain_ccForTarget(const char *triple, const char *sysroot);
+
+/**
+ * Get the archiver used to build the runtime library for a target
+ *
+ * Resolution order: $FRANZ_AR, <triple>-ar, llvm-ar (cross targets only), then ar.
+ *
+ * @param triple - Target triple, or NULL for the host
+ * @return Archiver command (never NULL)
+ */
+const char *Toolchain_arForTarget(const char *triple);
+
+/**
+ * Get the absolute path of the running franz executable
+ *
+ * @param out - Buffer receiving the path
+ * @param outSize - Size of out
+ * @return 1 if found, 0 otherwise
+ */
+int Toolchain_executablePath(char *out, size_t outSize);
+
+/**
+ * Get the directory containing the Franz runtime sources
+ *
+ * Resolution order: $FRANZ_HOME, the directory of the running franz
+ * executable, then the current working directory.
+ *
+ * @return Directory path (never NULL)
+ */
+const char *Toolchain_runtimeRoot(void);
+
+#endif
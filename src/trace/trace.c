@@ -0,0 +1,101 @@
+#include "trace.h"
+#include "../stdlib.h"
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <stdint.h>
+#include <unistd.h>
+
+#ifndef CLOSURE_RETURN_INT
+#define CLOSURE_RETURN_INT 0
+#define CLOSURE_RETURN_FLOAT 1
+#define CLOSURE_RETURN_POINTER 2
+#define CLOSURE_RETURN_CLOSURE 3
+#define CLOSURE_RETURN_VOID 4
+#endif
+
+static int g_depth = 0;       // nesting level of the innermost traced call
+static int g_argsLeft = 0;    // arguments still to come on the current call line
+static int g_argsPrinted = 0;
+
+//  franz_print_generic writes to stdout; point stdout at stderr while it runs
+static void printGeneric(Generic *value) {
+  fflush(stdout);
+  int savedStdout = dup(STDOUT_FILENO);
+  dup2(STDERR_FILENO, STDOUT_FILENO);
+  franz_print_generic(value);
+  fflush(stdout);
+  dup2(savedStdout, STDOUT_FILENO);
+  close(savedStdout);
+}
+
+static void printValue(int64_t value, int32_t tag) {
+  switch (tag) {
+    case CLOSURE_RETURN_INT:
+      //  Full 64-bit value
+      fprintf(stderr, "%lld", (long long)value);
+      return;
+    case CLOSURE_RETURN_FLOAT: {
+      double number;
+      memcpy(&number, &value, sizeof(double));
+      Generic generic = { TYPE_FLOAT, &number, 0, 0 };
+      printGeneric(&generic);
+      return;
+    }
+    case CLOSURE_RETURN_CLOSURE: {
+      Generic generic = { TYPE_BYTECODE_CLOSURE, (void *)(intptr_t)value, 0, 0 };
+      printGeneric(&generic);
+      return;
+    }
+    case CLOSURE_RETURN_VOID:
+      fprintf(stderr, "void");
+      return;
+    default:
+      break;
+  }
+
+  //  Pointers are Generic* or raw strings (same test as franz_box_pointer_smart,
+  // without its diagnostics); small values are int payloads
+  uintptr_t address = (uintptr_t)value;
+  if (address < 4096) {
+    fprintf(stderr, "%lld", (long long)value);
+    return;
+  }
+  Generic *generic = (Generic *)address;
+  if (generic->type >= TYPE_INT && generic->type <= TYPE_REF) {
+    printGeneric(generic);
+  } else {
+    char *text = (char *)address;
+    Generic string = { TYPE_STRING, &text, 0, 0 };
+    printGeneric(&string);
+  }
+}
+
+static void printPrefix(const char *arrow) {
+  fprintf(stderr, "[trace %d] %*s%s ", g_depth, (g_depth - 1) * 2, "", arrow);
+}
+
+void franz_trace_enter(const char *name, int32_t argCount) {
+  g_depth++;
+  printPrefix("->");
+  fprintf(stderr, "%s(", name);
+  g_argsLeft = argCount;
+  g_argsPrinted = 0;
+  if (g_argsLeft <= 0) fprintf(stderr, ")\n");
+}
+
+void franz_trace_arg(const char *argName, int64_t value, int32_t tag) {
+  if (g_argsLeft <= 0) return;
+  fprintf(stderr, "%s%s=", g_argsPrinted > 0 ? ", " : "", argName);
+  printValue(value, tag);
+  g_argsPrinted++;
+  if (--g_argsLeft == 0) fprintf(stderr, ")\n");
+}
+
+void franz_trace_exit(const char *name, int64_t value, int32_t tag) {
+  printPrefix("<-");
+  fprintf(stderr, "%s = ", name);
+  printValue(value, tag);
+  fprintf(stderr, "\n");
+  if (g_depth > 0) g_depth--;
+}
@@ -0,0 +1,51 @@
+#ifndef TRACE_H
+#define TRACE_H
+
+#include <stdint.h>
+
+/**
+ * Execution tracing for programs compiled with --trace
+ *
+ * Instrumented functions (see src/llvm-trace/llvm_trace.h) report each call
+ * and return to stderr, indented by nesting depth:
+ *
+ *   [trace 1] -> fib(n=2)
+ *   [trace 2]   -> fib(n=1)
+ *   [trace 2]   <- fib = 1
+ *   [trace 1] <- fib = 2
+ *
+ * Values travel as an i64 payload with a closure return tag (0 int,
+ * 1 float bits, 2 pointer, 3 closure, 4 void) and are rendered with
+ * franz_print_generic.
+ */
+
+/**
+ * Report a call and open a nesting level
+ *
+ * The call line is finished by the argCount calls to franz_trace_arg that
+ * follow (at once if argCount is 0).
+ *
+ * @param name - Franz name of the function ("lambda@LINE" for anonymous ones)
+ * @param argCount - Number of parameters
+ */
+void franz_trace_enter(const char *name, int32_t argCount);
+
+/**
+ * Report one argument of the call opened by franz_trace_enter
+ *
+ * @param argName - Parameter name
+ * @param value - Payload
+ * @param tag - Closure return tag of the payload
+ */
+void franz_trace_arg(const char *argName, int64_t value, int32_t tag);
+
+/**
+ * Report a return and close the nesting level
+ *
+ * @param name - Franz name of the function
+ * @param value - Payload of the return value
+ * @param tag - Closure return tag of the payload
+ */
+void franz_trace_exit(const char *name, int64_t value, int32_t tag);
+
+#endif
@@ -1,9 +1,11 @@
 #include <stdio.h>
 #include <stdlib.h>
 #include <string.h>
+#include <stdarg.h>
 #include "typeinfer.h"
 #include "types.h"
 #include "ast.h"
+#include "diagnostics/diagnostic.h"
 
 // Type environment operations
 
@@ -165,6 +167,21 @@ int occurs_check(int var_id, Type *t) {
   return 0;
 }
 
+//  Report a type error, or collect it when the context asks for it (language server)
+static void typeError(InferContext *ctx, ErrorCode code, DiagnosticSpan span, const char *format, ...) {
+  char message[1024];
+  va_list args;
+  va_start(args, format);
+  vsnprintf(message, sizeof(message), format, args);
+  va_end(args);
+
+  if (ctx->collect) {
+    Diagnostic_add("Type Error", code, span, "%s", message);
+  } else {
+    Diagnostic_report(stderr, "Type Error", code, span, "%s", message);
+  }
+}
+
 int unify(InferContext *ctx, Type *a, Type *b, int lineNumber) {
   // Apply existing substitutions
   a = Substitution_apply(ctx, a);
@@ -176,7 +193,7 @@ int unify(InferContext *ctx, Type *a, Type *b, int lineNumber) {
   // Type variable cases
   if (a->kind == TYPE_VAR) {
     if (occurs_check(a->data.var.var_id, b)) {
-      fprintf(stderr, "Type Error @ Line %d: Infinite type\n", lineNumber);
+      typeError(ctx, ERROR_CODE_INFINITE_TYPE, Diagnostic_spanOfLine(lineNumber), "Infinite type");
       ctx->error_count++;
       return 0;
     }
@@ -186,7 +203,7 @@ int unify(InferContext *ctx, Type *a, Type *b, int lineNumber) {
 
   if (b->kind == TYPE_VAR) {
     if (occurs_check(b->data.var.var_id, a)) {
-      fprintf(stderr, "Type Error @ Line %d: Infinite type\n", lineNumber);
+      typeError(ctx, ERROR_CODE_INFINITE_TYPE, Diagnostic_spanOfLine(lineNumber), "Infinite type");
       ctx->error_count++;
       return 0;
     }
@@ -208,7 +225,7 @@ int unify(InferContext *ctx, Type *a, Type *b, int lineNumber) {
   // Function types
   if (a->kind == TYPE_FUNCTION && b->kind == TYPE_FUNCTION) {
     if (a->data.func.param_count != b->data.func.param_count) {
-      fprintf(stderr, "Type Error @ Line %d: Function arity mismatch\n", lineNumber);
+      typeError(ctx, ERROR_CODE_ARITY_MISMATCH, Diagnostic_spanOfLine(lineNumber), "Function arity mismatch");
       ctx->error_count++;
       return 0;
     }
@@ -225,7 +242,8 @@ int unify(InferContext *ctx, Type *a, Type *b, int lineNumber) {
   // Failed to unify
   char *a_str = Type_to_string(a);
   char *b_str = Type_to_string(b);
-  fprintf(stderr, "Type Error @ Line %d: Cannot unify %s with %s\n", lineNumber, a_str, b_str);
+  typeError(ctx, ERROR_CODE_TYPE_MISMATCH, Diagnostic_spanOfLine(lineNumber),
+            "Cannot unify %s with %s", a_str, b_str);
   ctx->error_count++;
   free(a_str);
   free(b_str);
@@ -234,9 +252,22 @@ int unify(InferContext *ctx, Type *a, Type *b, int lineNumber) {
 
 // Main type inference function
 
+static Type *inferNode(AstNode *node, InferContext *ctx, int lineNumber);
+
 Type *infer(AstNode *node, InferContext *ctx, int lineNumber) {
+  Type *type = inferNode(node, ctx, lineNumber);
+  if (node != NULL && node == ctx->watch) {
+    ctx->watchType = Type_copy(type);
+  }
+  return type;
+}
+
+static Type *inferNode(AstNode *node, InferContext *ctx, int lineNumber) {
   if (node == NULL) return Type_void();
 
+  // Errors below this node point at its own line (lineNumber is the caller's)
+  if (node->lineNumber > 0) lineNumber = node->lineNumber;
+
   switch (node->opcode) {
     case OP_INT:
       return Type_int();
@@ -260,7 +291,8 @@ Type *infer(AstNode *node, InferContext *ctx, int lineNumber) {
     case OP_ASSIGNMENT: {
       // Get identifier name (first child)
       if (node->childCount < 2) {
-        fprintf(stderr, "Type Error @ Line %d: Assignment requires identifier and value\n", lineNumber);
+        typeError(ctx, ERROR_CODE_MALFORMED_EXPRESSION, Diagnostic_spanOfNode(node),
+                  "Assignment requires identifier and value");
         ctx->error_count++;
         return Type_void();
       }
@@ -287,11 +319,17 @@ Type *infer(AstNode *node, InferContext *ctx, int lineNumber) {
       InferContext local_ctx = {
         .env = local_env,
         .subst = ctx->subst,
-        .var_counter = ctx->var_counter
+        .var_counter = ctx->var_counter,
+        .collect = ctx->collect,
+        .watch = ctx->watch,
+        .watchType = ctx->watchType
       };
 
-      // Array-based: last child is body, all before are parameters
-      int param_count = (node->childCount > 0) ? node->childCount - 1 : 0;
+      // Leading identifiers are parameters, the statements after them are the body
+      int param_count = 0;
+      while (param_count < node->childCount && node->children[param_count]->opcode == OP_IDENTIFIER) {
+        param_count++;
+      }
       Type **param_types = NULL;
 
       if (param_count > 0) {
@@ -304,16 +342,16 @@ Type *infer(AstNode *node, InferContext *ctx, int lineNumber) {
         }
       }
 
-      // Infer body type (last child)
+      // Infer body statements in order; the last one gives the result type
       Type *body_type = Type_void();
-      if (node->childCount > 0) {
-        AstNode *body = node->children[node->childCount - 1];
-        body_type = infer(body, &local_ctx, lineNumber);
+      for (int i = param_count; i < node->childCount; i++) {
+        body_type = infer(node->children[i], &local_ctx, lineNumber);
       }
 
       // Update context
       ctx->var_counter = local_ctx.var_counter;
       ctx->subst = local_ctx.subst;
+      ctx->watchType = local_ctx.watchType;
 
       // Free local environment (but not types)
       TypeEnv_free(local_env);
@@ -324,7 +362,7 @@ Type *infer(AstNode *node, InferContext *ctx, int lineNumber) {
 
     case OP_APPLICATION: {
       if (node->childCount == 0) {
-        fprintf(stderr, "Type Error @ Line %d: Empty application\n", lineNumber);
+        typeError(ctx, ERROR_CODE_MALFORMED_EXPRESSION, Diagnostic_spanOfNode(node), "Empty application");
         ctx->error_count++;
         return Type_any();
       }
@@ -352,10 +390,11 @@ Type *infer(AstNode *node, InferContext *ctx, int lineNumber) {
       // If not a function type, try to make it one
       if (func_type->kind != TYPE_FUNCTION && func_type->kind != TYPE_ANY) {
         // Try to provide more context when possible
+        DiagnosticSpan callee = Diagnostic_spanOfNode(node->children[0]);
         if (node->children[0]->opcode == OP_IDENTIFIER) {
-          fprintf(stderr, "Type Error @ Line %d: Calling non-function '%s'\n", lineNumber, node->children[0]->val);
+          typeError(ctx, ERROR_CODE_NOT_A_FUNCTION, callee, "Calling non-function '%s'", node->children[0]->val);
         } else {
-          fprintf(stderr, "Type Error @ Line %d: Calling non-function\n", lineNumber);
+          typeError(ctx, ERROR_CODE_NOT_A_FUNCTION, callee, "Calling non-function");
         }
         ctx->error_count++;
         return Type_any();
@@ -433,11 +472,11 @@ Type *infer(AstNode *node, InferContext *ctx, int lineNumber) {
       // Default arity check for non-variadic functions
       if (func_type->kind == TYPE_FUNCTION && arg_count != func_type->data.func.param_count) {
         if (fname) {
-          fprintf(stderr, "Type Error @ Line %d: %s expects %d arguments, got %d\n",
-                  lineNumber, fname, func_type->data.func.param_count, arg_count);
+          typeError(ctx, ERROR_CODE_ARITY_MISMATCH, Diagnostic_spanOfNode(node), "%s expects %d arguments, got %d",
+                    fname, func_type->data.func.param_count, arg_count);
         } else {
-          fprintf(stderr, "Type Error @ Line %d: Expected %d arguments, got %d\n",
-                  lineNumber, func_type->data.func.param_count, arg_count);
+          typeError(ctx, ERROR_CODE_ARITY_MISMATCH, Diagnostic_spanOfNode(node), "Expected %d arguments, got %d",
+                    func_type->data.func.param_count, arg_count);
         }
         ctx->error_count++;
         return Type_any();
@@ -453,13 +492,21 @@ Type *infer(AstNode *node, InferContext *ctx, int lineNumber) {
         if (!unify(ctx, arg_type, expected, lineNumber)) {
           char *got = Type_to_string(arg_type);
           char *exp = Type_to_string(expected);
+          DiagnosticSpan arg = Diagnostic_spanOfNode(node->children[i + 1]);
+          char what[256];
           if (fname) {
-            fprintf(stderr, "Type Error @ Line %d: %s argument %d type mismatch\n", lineNumber, fname, i + 1);
+            snprintf(what, sizeof(what), "%s argument %d type mismatch", fname, i + 1);
+          } else {
+            snprintf(what, sizeof(what), "Argument %d type mismatch", i + 1);
+          }
+          //  Structured output has no room for the extra lines
+          if (ctx->collect || Diagnostic_getFormat() == DIAGNOSTIC_FORMAT_JSON) {
+            typeError(ctx, ERROR_CODE_ARGUMENT_TYPE, arg, "%s (expected %s, got %s)", what, exp, got);
           } else {
-            fprintf(stderr, "Type Error @ Line %d: Argument %d type mismatch\n", lineNumber, i + 1);
+            typeError(ctx, ERROR_CODE_ARGUMENT_TYPE, arg, "%s", what);
+            fprintf(stderr, "  Expected: %s\n", exp);
+            fprintf(stderr, "  Got: %s\n", got);
           }
-          fprintf(stderr, "  Expected: %s\n", exp);
-          fprintf(stderr, "  Got: %s\n", got);
           ctx->error_count++;
           free(got);
           free(exp);
@@ -505,6 +552,9 @@ InferContext *InferContext_new() {
   ctx->subst = NULL;
   ctx->var_counter = 0;
   ctx->error_count = 0;
+  ctx->collect = 0;
+  ctx->watch = NULL;
+  ctx->watchType = NULL;
   return ctx;
 }
 
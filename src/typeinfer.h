@@ -29,6 +29,9 @@ typedef struct InferContext {
   Substitution *subst;
   int var_counter;  // For generating fresh type variables
   int error_count;  // Number of type errors reported
+  int collect;      // Collect errors with Diagnostic_add instead of printing them
+  AstNode *watch;   // Node whose inferred type is recorded in watchType (editor hover)
+  Type *watchType;
 } InferContext;
 
 // Environment operations